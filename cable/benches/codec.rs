@@ -0,0 +1,106 @@
+//! Benchmark the encoding, decoding, signing and verification of posts and
+//! messages.
+//!
+//! Run the benchmarks:
+//!
+//! `cargo bench -p cable`
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use desert::{FromBytes, ToBytes};
+use hex::FromHex;
+
+use cable::{constants::NO_CIRCUIT, message::Message, post::Post, ChannelOptions, Hash};
+
+const PUBLIC_KEY: &str = "aead820c67703da78dba364338d8b0d65d65c03a7e9310de87b2b36818ca1e5d";
+const SECRET_KEY: &str = "5ff6fabec79407bde6402701d55be2b43a57adf3d4d454c8ad56c1397950f4a6aead820c67703da78dba364338d8b0d65d65c03a7e9310de87b2b36818ca1e5d";
+const REQ_ID: [u8; 4] = [4, 186, 175, 251];
+const TTL: u8 = 7;
+
+/// Generate the given number of distinct hashes.
+fn hashes(n: u8) -> Vec<Hash> {
+    (0..n).map(|i| [i; 32]).collect()
+}
+
+/// Create a signed text post of the given length.
+fn text_post(text_len: usize) -> Post {
+    let public_key = <[u8; 32]>::from_hex(PUBLIC_KEY).unwrap();
+    let secret_key = <[u8; 64]>::from_hex(SECRET_KEY).unwrap();
+
+    let mut post = Post::text(
+        public_key,
+        hashes(3),
+        9876543210,
+        "bike_life".to_string(),
+        "x".repeat(text_len),
+    );
+    post.sign(&secret_key).unwrap();
+
+    post
+}
+
+fn post_benchmarks(c: &mut Criterion) {
+    let secret_key = <[u8; 64]>::from_hex(SECRET_KEY).unwrap();
+    let post = text_post(256);
+    let post_bytes = post.to_bytes().unwrap();
+
+    let mut group = c.benchmark_group("post");
+    group.bench_function("to_bytes", |b| b.iter(|| black_box(&post).to_bytes()));
+    group.bench_function("from_bytes", |b| {
+        b.iter(|| Post::from_bytes(black_box(&post_bytes)))
+    });
+    group.bench_function("sign", |b| {
+        b.iter_batched(
+            || post.clone(),
+            |mut post| post.sign(&secret_key),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("verify", |b| {
+        b.iter(|| Post::verify(black_box(&post_bytes)))
+    });
+    group.bench_function("hash", |b| b.iter(|| black_box(&post).hash()));
+    group.finish();
+}
+
+fn message_benchmarks(c: &mut Criterion) {
+    let post_bytes = text_post(256).to_bytes().unwrap();
+    let messages = [
+        (
+            "post_request",
+            Message::post_request(NO_CIRCUIT, REQ_ID, TTL, hashes(64)),
+        ),
+        (
+            "hash_response",
+            Message::hash_response(NO_CIRCUIT, REQ_ID, hashes(64)),
+        ),
+        (
+            "post_response",
+            Message::post_response(NO_CIRCUIT, REQ_ID, vec![post_bytes; 16]),
+        ),
+        (
+            "channel_time_range_request",
+            Message::channel_time_range_request(
+                NO_CIRCUIT,
+                REQ_ID,
+                TTL,
+                ChannelOptions::new("bike_life", 0, 9876543210, 50),
+            ),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("message");
+    for (name, msg) in &messages {
+        let msg_bytes = msg.to_bytes().unwrap();
+
+        group.bench_function(format!("{}/to_bytes", name), |b| {
+            b.iter(|| black_box(msg).to_bytes())
+        });
+        group.bench_function(format!("{}/from_bytes", name), |b| {
+            b.iter(|| Message::from_bytes(black_box(&msg_bytes)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, post_benchmarks, message_benchmarks);
+criterion_main!(benches);
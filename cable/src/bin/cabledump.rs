@@ -0,0 +1,108 @@
+//! Print the decoded structure of encoded cable messages or posts, with the
+//! offset and length of each field.
+//!
+//! Pass frames as hex arguments:
+//!
+//! `cargo run --bin cabledump -- 0c060000000004baaffb010014`
+//!
+//! Or pipe them to stdin, either as hex (one frame per line) or as raw bytes
+//! (any number of consecutive length-prefixed messages):
+//!
+//! `cat capture.bin | cargo run --bin cabledump`
+//!
+//! Add `--post` to decode the frames as posts rather than messages.
+
+use std::{
+    io::{self, Read},
+    process,
+};
+
+use cable::inspect::{inspect_message, inspect_post};
+
+const USAGE: &str = "usage: cabledump [--post] [HEX...]";
+
+fn main() {
+    let mut posts = false;
+    let mut frames = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--post" => posts = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            hex => frames.push(decode_hex(hex).unwrap_or_else(|err| exit(&err))),
+        }
+    }
+
+    let mut inspections = Vec::new();
+    if frames.is_empty() {
+        let mut input = Vec::new();
+        if let Err(err) = io::stdin().read_to_end(&mut input) {
+            exit(&format!("failed to read stdin: {err}"));
+        }
+
+        match std::str::from_utf8(&input).ok().filter(|text| is_hex(text)) {
+            // Each line of hex input holds one frame.
+            Some(text) => {
+                for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                    frames.push(decode_hex(line).unwrap_or_else(|err| exit(&err)));
+                }
+            }
+            // Binary input holds consecutive messages, or a single post.
+            None if posts => frames.push(input),
+            None => {
+                let mut rest = &input[..];
+                while !rest.is_empty() {
+                    let inspection = inspect_message(rest);
+                    rest = &rest[inspection.len..];
+                    let failed = !inspection.is_ok() || inspection.len == 0;
+                    inspections.push(inspection);
+                    if failed {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    for frame in &frames {
+        if posts {
+            inspections.push(inspect_post(frame));
+        } else {
+            inspections.push(inspect_message(frame));
+        }
+    }
+
+    let mut ok = true;
+    for inspection in &inspections {
+        println!("{inspection}");
+        ok &= inspection.is_ok();
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
+
+/// Return `true` if the given text only contains hex digits and whitespace.
+fn is_hex(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_hexdigit() || c.is_ascii_whitespace())
+}
+
+/// Decode the given hex string, ignoring whitespace and an optional `0x`
+/// prefix.
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+
+    hex::decode(&digits).map_err(|_| format!("invalid hex frame: {text}"))
+}
+
+/// Print the given message and usage, and exit with an error status.
+fn exit(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!("{USAGE}");
+    process::exit(2)
+}
@@ -0,0 +1,139 @@
+//! The protocol version and the message and post types understood by a peer.
+//!
+//! Peers may announce their capabilities with a capability request, an
+//! extension to the cable specification, so that messages of types which a
+//! peer does not understand (such as the experimental blob requests) are not
+//! sent to it. A peer which has not announced its capabilities may understand
+//! any message type.
+
+#[cfg(feature = "archive-posts")]
+use crate::constants::ARCHIVE_POST;
+#[cfg(feature = "reactions")]
+use crate::constants::REACTION_POST;
+#[cfg(feature = "blobs")]
+use crate::constants::{BLOB_REQUEST, BLOB_RESPONSE};
+use crate::{
+    constants::{
+        CANCEL_REQUEST, CAPABILITY_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE,
+        CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, DELETE_POST, HASH_RESPONSE, INFO_POST,
+        JOIN_POST, LEAVE_POST, NO_CIRCUIT, POST_REQUEST, POST_RESPONSE, PROTOCOL_VERSION,
+        TEXT_POST, TOPIC_POST,
+    },
+    message::{Message, MessageBody, RequestBody},
+    ReqId,
+};
+
+/// The capabilities announced by a peer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version of the cable specification implemented by the peer.
+    pub version: u64,
+    /// The message types understood by the peer.
+    pub msg_types: Vec<u64>,
+    /// The post types understood by the peer.
+    pub post_types: Vec<u64>,
+}
+
+impl Capabilities {
+    /// Return the capabilities of this build of the crate, including the
+    /// experimental message and post types of the enabled features.
+    pub fn local() -> Self {
+        let mut msg_types = vec![
+            HASH_RESPONSE,
+            POST_RESPONSE,
+            POST_REQUEST,
+            CANCEL_REQUEST,
+            CHANNEL_TIME_RANGE_REQUEST,
+            CHANNEL_STATE_REQUEST,
+            CHANNEL_LIST_REQUEST,
+            CHANNEL_LIST_RESPONSE,
+        ];
+        #[cfg(feature = "blobs")]
+        msg_types.extend([BLOB_REQUEST, BLOB_RESPONSE]);
+        msg_types.push(CAPABILITY_REQUEST);
+
+        #[allow(unused_mut)]
+        let mut post_types = vec![
+            TEXT_POST,
+            DELETE_POST,
+            INFO_POST,
+            TOPIC_POST,
+            JOIN_POST,
+            LEAVE_POST,
+        ];
+        #[cfg(feature = "reactions")]
+        post_types.push(REACTION_POST);
+        #[cfg(feature = "archive-posts")]
+        post_types.push(ARCHIVE_POST);
+
+        Capabilities {
+            version: PROTOCOL_VERSION,
+            msg_types,
+            post_types,
+        }
+    }
+
+    /// Query whether the given message type is understood.
+    pub fn supports_message(&self, msg_type: u64) -> bool {
+        self.msg_types.contains(&msg_type)
+    }
+
+    /// Query whether the given post type is understood.
+    pub fn supports_post(&self, post_type: u64) -> bool {
+        self.post_types.contains(&post_type)
+    }
+
+    /// Return the capabilities announced by the given message, if it is a
+    /// capability request.
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        match &msg.body {
+            MessageBody::Request {
+                body:
+                    RequestBody::Capabilities {
+                        version,
+                        msg_types,
+                        post_types,
+                    },
+                ..
+            } => Some(Capabilities {
+                version: *version,
+                msg_types: msg_types.clone(),
+                post_types: post_types.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Construct a capability request announcing these capabilities.
+    ///
+    /// The request has a TTL of 0, as capabilities concern a single
+    /// connection and are never forwarded.
+    pub fn to_request(&self, req_id: ReqId) -> Message {
+        Message::capability_request(
+            NO_CIRCUIT,
+            req_id,
+            0,
+            self.version,
+            self.msg_types.clone(),
+            self.post_types.clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn local_capabilities() {
+        let capabilities = Capabilities::local();
+        assert_eq!(capabilities.version, PROTOCOL_VERSION);
+        assert!(capabilities.supports_message(POST_REQUEST));
+        assert!(capabilities.supports_message(CAPABILITY_REQUEST));
+        assert!(capabilities.supports_post(TEXT_POST));
+        assert!(!capabilities.supports_message(100));
+
+        let request = capabilities.to_request([1, 2, 3, 4]);
+        assert_eq!(Capabilities::from_message(&request), Some(capabilities));
+    }
+}
@@ -0,0 +1,276 @@
+//! Round-trip checks for encoded messages and posts.
+//!
+//! A conforming implementation encodes every value in exactly one way, so
+//! decoding a frame and re-encoding the result must reproduce the frame byte
+//! for byte. These checks allow other implementations and fuzzers to confirm
+//! that arbitrary frames survive the round trip and, where they do not, to
+//! find the first divergent byte and the field to which it belongs.
+
+use std::fmt;
+
+use desert::{FromBytes, ToBytes};
+
+use crate::{
+    inspect::{inspect_message, inspect_post, Field, Inspection},
+    Message, Post,
+};
+
+/// The first point at which a re-encoded frame differs from the original.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The offset of the first differing byte.
+    pub offset: usize,
+    /// The byte of the original frame at the offset, if the frame is long
+    /// enough.
+    pub expected: Option<u8>,
+    /// The byte of the re-encoded frame at the offset, if the re-encoded frame
+    /// is long enough.
+    pub actual: Option<u8>,
+    /// The field of the original frame containing the offset, if known.
+    pub field: Option<Field>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |byte: Option<u8>| match byte {
+            Some(byte) => format!("{:02x}", byte),
+            None => "end of frame".to_string(),
+        };
+        write!(
+            f,
+            "re-encoding diverges at offset {}: expected {}, found {}",
+            self.offset,
+            byte(self.expected),
+            byte(self.actual)
+        )?;
+        if let Some(field) = &self.field {
+            write!(f, " (in field {} at offset {})", field.name, field.offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The reason a frame failed to round-trip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RoundtripError {
+    /// The frame could not be decoded.
+    Decode {
+        /// The offset of the malformed field, if known.
+        offset: Option<usize>,
+        /// A description of the problem.
+        message: String,
+    },
+    /// The frame was decoded without consuming all of the given bytes.
+    TrailingBytes {
+        /// The number of bytes consumed by the decoder.
+        consumed: usize,
+        /// The number of bytes given.
+        len: usize,
+    },
+    /// The decoded value could not be encoded.
+    Encode {
+        /// A description of the problem.
+        message: String,
+    },
+    /// The re-encoded frame differs from the original.
+    Mismatch(Divergence),
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundtripError::Decode {
+                offset: Some(offset),
+                message,
+            } => write!(f, "failed to decode at offset {}: {}", offset, message),
+            RoundtripError::Decode {
+                offset: None,
+                message,
+            } => write!(f, "failed to decode: {}", message),
+            RoundtripError::TrailingBytes { consumed, len } => write!(
+                f,
+                "decoded {} of {} bytes; {} trailing bytes",
+                consumed,
+                len,
+                len - consumed
+            ),
+            RoundtripError::Encode { message } => write!(f, "failed to re-encode: {}", message),
+            RoundtripError::Mismatch(divergence) => write!(f, "{}", divergence),
+        }
+    }
+}
+
+impl std::error::Error for RoundtripError {}
+
+/// Return the offset of the first byte at which the given buffers differ, or
+/// `None` if they are identical. A buffer which is a prefix of the other
+/// differs at the end of the shorter buffer.
+pub fn first_divergence(expected: &[u8], actual: &[u8]) -> Option<usize> {
+    match expected.iter().zip(actual).position(|(a, b)| a != b) {
+        Some(offset) => Some(offset),
+        None if expected.len() != actual.len() => Some(expected.len().min(actual.len())),
+        None => None,
+    }
+}
+
+/// Compare the given re-encoding of a frame with the original, describing the
+/// first divergence in terms of the fields of the given inspection of the
+/// original.
+fn compare(
+    expected: &[u8],
+    actual: &[u8],
+    inspect: impl FnOnce(&[u8]) -> Inspection,
+) -> Result<(), RoundtripError> {
+    let offset = match first_divergence(expected, actual) {
+        Some(offset) => offset,
+        None => return Ok(()),
+    };
+
+    // Nested fields follow their enclosing field, so the last field
+    // containing the offset is the most specific.
+    let field = inspect(expected)
+        .fields
+        .into_iter()
+        .rfind(|field| field.offset <= offset && offset < field.offset + field.len);
+
+    Err(RoundtripError::Mismatch(Divergence {
+        offset,
+        expected: expected.get(offset).copied(),
+        actual: actual.get(offset).copied(),
+        field,
+    }))
+}
+
+/// Decode the given frame with the given decoder, ensuring that the whole
+/// frame is consumed.
+fn decode<T>(
+    bytes: &[u8],
+    from_bytes: impl FnOnce(&[u8]) -> Result<(usize, T), crate::Error>,
+    inspect: impl FnOnce(&[u8]) -> Inspection,
+) -> Result<T, RoundtripError> {
+    let (consumed, value) = from_bytes(bytes).map_err(|err| RoundtripError::Decode {
+        offset: inspect(bytes).error.map(|err| err.offset),
+        message: err.to_string(),
+    })?;
+    if consumed != bytes.len() {
+        return Err(RoundtripError::TrailingBytes {
+            consumed,
+            len: bytes.len(),
+        });
+    }
+
+    Ok(value)
+}
+
+/// Check that the given encoded message, including the leading message
+/// length, decodes and re-encodes to exactly the same bytes. Returns the
+/// decoded message.
+pub fn check_roundtrip(bytes: &[u8]) -> Result<Message, RoundtripError> {
+    let msg = decode(bytes, Message::from_bytes, inspect_message)?;
+    let encoded = msg.to_bytes().map_err(|err| RoundtripError::Encode {
+        message: err.to_string(),
+    })?;
+    compare(bytes, &encoded, inspect_message)?;
+
+    Ok(msg)
+}
+
+/// Check that the given encoded post decodes and re-encodes to exactly the
+/// same bytes. Returns the decoded post.
+pub fn check_post_roundtrip(bytes: &[u8]) -> Result<Post, RoundtripError> {
+    let post = decode(bytes, Post::from_bytes, inspect_post)?;
+    let encoded = post.to_bytes().map_err(|err| RoundtripError::Encode {
+        message: err.to_string(),
+    })?;
+    compare(bytes, &encoded, inspect_post)?;
+
+    Ok(post)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use hex::FromHex;
+
+    use crate::Error;
+
+    // Field values sourced from https://github.com/cabal-club/cable.js#examples.
+
+    const CHANNEL_LIST_REQUEST_HEX_BINARY: &str = "0c060000000004baaffb010014";
+    const JOIN_POST_HEX_BINARY: &str = "25b272a71555322d40efe449a7f99af8fd364b92d350f1664481b2da340a02d064425f10fa34c1e14b6101491772d3c5f15f720a952dd56c27d5ad52f61f695130ce286de73e332612b36242339b61c9e12397f5dcc94c79055c7e1cb1dbfb08015049d089a650aa896cb25ec35258653be4df196b4a5e5b6db7ed024aaa89e1b304500764656661756c74";
+
+    #[test]
+    fn first_divergence_of_buffers() {
+        assert_eq!(first_divergence(b"cable", b"cable"), None);
+        assert_eq!(first_divergence(b"cable", b"table"), Some(0));
+        assert_eq!(first_divergence(b"cable", b"cab"), Some(3));
+        assert_eq!(first_divergence(b"cab", b"cable"), Some(3));
+    }
+
+    #[test]
+    fn vectors_roundtrip() -> Result<(), Error> {
+        let msg_bytes = <Vec<u8>>::from_hex(CHANNEL_LIST_REQUEST_HEX_BINARY)?;
+        assert!(check_roundtrip(&msg_bytes).is_ok());
+
+        let post_bytes = <Vec<u8>>::from_hex(JOIN_POST_HEX_BINARY)?;
+        assert!(check_post_roundtrip(&post_bytes).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn non_canonical_varint_diverges() -> Result<(), Error> {
+        // A channel list request with a limit of 20 encoded in two bytes
+        // (`94 00`) rather than one (`14`).
+        let msg_bytes = <Vec<u8>>::from_hex("0d060000000004baaffb01009400")?;
+
+        match check_roundtrip(&msg_bytes) {
+            Err(RoundtripError::Mismatch(divergence)) => {
+                // The shorter re-encoded message length is the first
+                // divergent byte.
+                assert_eq!(divergence.offset, 0);
+                assert_eq!(divergence.expected, Some(0x0d));
+                assert_eq!(divergence.actual, Some(0x0c));
+                assert_eq!(divergence.field.unwrap().name, "msg_len");
+            }
+            res => panic!("expected a mismatch; got {:?}", res),
+        }
+
+        // A join post with a timestamp of 80 encoded in two bytes.
+        let post_hex = JOIN_POST_HEX_BINARY.replace("b304500764", "b304d0000764");
+        let post_bytes = <Vec<u8>>::from_hex(post_hex)?;
+
+        match check_post_roundtrip(&post_bytes) {
+            Err(RoundtripError::Mismatch(divergence)) => {
+                assert_eq!(divergence.offset, 130);
+                assert_eq!(divergence.field.unwrap().name, "timestamp");
+            }
+            res => panic!("expected a mismatch; got {:?}", res),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn trailing_and_truncated_frames_fail() -> Result<(), Error> {
+        let mut msg_bytes = <Vec<u8>>::from_hex(CHANNEL_LIST_REQUEST_HEX_BINARY)?;
+        msg_bytes.push(0);
+        assert_eq!(
+            check_roundtrip(&msg_bytes).unwrap_err(),
+            RoundtripError::TrailingBytes {
+                consumed: 13,
+                len: 14
+            }
+        );
+
+        let post_bytes = <Vec<u8>>::from_hex(JOIN_POST_HEX_BINARY)?;
+        match check_post_roundtrip(&post_bytes[..100]) {
+            Err(RoundtripError::Decode { .. }) => (),
+            res => panic!("expected a decode error; got {:?}", res),
+        }
+
+        Ok(())
+    }
+}
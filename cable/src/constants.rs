@@ -6,12 +6,24 @@ pub const INFO_POST: u64 = 2;
 pub const TOPIC_POST: u64 = 3;
 pub const JOIN_POST: u64 = 4;
 pub const LEAVE_POST: u64 = 5;
+/// An emoji reaction to a post. Experimental; this post type is not assigned
+/// by the cable specification and may change.
+#[cfg(feature = "reactions")]
+pub const REACTION_POST: u64 = 64;
+/// The archival of a channel by its author. Experimental; this post type is
+/// not assigned by the cable specification and may change.
+#[cfg(feature = "archive-posts")]
+pub const ARCHIVE_POST: u64 = 65;
 
 /* RESPONSE FIELD VALUES */
 
 pub const HASH_RESPONSE: u64 = 0;
 pub const POST_RESPONSE: u64 = 1;
 pub const CHANNEL_LIST_RESPONSE: u64 = 7;
+/// A chunk of a binary blob. Experimental; this message type is not assigned
+/// by the cable specification and may change.
+#[cfg(feature = "blobs")]
+pub const BLOB_RESPONSE: u64 = 65;
 
 /* REQUEST FIELD VALUES */
 
@@ -20,7 +32,21 @@ pub const CANCEL_REQUEST: u64 = 3;
 pub const CHANNEL_TIME_RANGE_REQUEST: u64 = 4;
 pub const CHANNEL_STATE_REQUEST: u64 = 5;
 pub const CHANNEL_LIST_REQUEST: u64 = 6;
+/// Request a chunk of a binary blob by its hash. Experimental; this message
+/// type is not assigned by the cable specification and may change.
+#[cfg(feature = "blobs")]
+pub const BLOB_REQUEST: u64 = 64;
+/// Announce the protocol version and the message and post types understood
+/// by the sender. Experimental; this message type is not assigned by the
+/// cable specification and may change.
+pub const CAPABILITY_REQUEST: u64 = 66;
 
 /* MISC FIELD VALUES */
 
+/// The version of the cable specification implemented by this crate, as
+/// announced in capability requests.
+pub const PROTOCOL_VERSION: u64 = 1;
+
 pub const NO_CIRCUIT: [u8; 4] = [0, 0, 0, 0];
+/// The maximum number of hops a request may be forwarded.
+pub const MAX_TTL: u8 = 16;
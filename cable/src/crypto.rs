@@ -0,0 +1,281 @@
+//! Hashing and signing primitives used by cable.
+//!
+//! Native builds use libsodium (via `sodiumoxide`). libsodium cannot be
+//! compiled for `wasm32-unknown-unknown`, so builds for that target use
+//! pure-Rust implementations of the same primitives (BLAKE2b and Ed25519)
+//! instead. The pure-Rust backend may also be selected on any target with the
+//! `rust-crypto` feature.
+//!
+//! Both backends produce identical hashes and signatures, and accept the same
+//! libsodium-formatted keys: a 32 byte public key and a 64 byte secret key
+//! consisting of the seed followed by the public key.
+//!
+//! The hash algorithm is named by `HashAlgorithm`, so that a revision of the
+//! specification may change its parameters without changing every API which
+//! takes a `Hash`. Where the algorithm of a stored hash must be recorded, the
+//! hash is tagged in the style of a multihash: the varint code of the
+//! algorithm, the varint length of the digest and the digest itself.
+
+use desert::varint;
+
+use crate::{
+    error::{CableErrorKind, Error},
+    Hash,
+};
+
+/// An Ed25519 public key.
+pub type PublicKey = [u8; 32];
+/// An Ed25519 secret key (the seed followed by the public key).
+pub type SecretKey = [u8; 64];
+/// An Ed25519 signature.
+pub type Signature = [u8; 64];
+
+#[cfg(not(any(target_arch = "wasm32", feature = "rust-crypto")))]
+mod backend {
+    use std::convert::TryInto;
+
+    use sodiumoxide::crypto::{generichash, sign};
+
+    use super::{Hash, PublicKey, SecretKey, Signature};
+
+    pub fn hash(buf: &[u8]) -> Option<Hash> {
+        let digest = generichash::hash(buf, Some(32), None).ok()?;
+
+        digest.as_ref().try_into().ok()
+    }
+
+    pub fn generate_keypair() -> (PublicKey, SecretKey) {
+        let (pk, sk) = sign::gen_keypair();
+
+        // The key lengths are fixed, so neither conversion can fail.
+        (
+            pk.as_ref().try_into().unwrap(),
+            sk.as_ref().try_into().unwrap(),
+        )
+    }
+
+    pub fn sign(buf: &[u8], secret_key: &SecretKey) -> Option<Signature> {
+        let sk = sign::SecretKey::from_slice(secret_key)?;
+
+        Some(sign::sign_detached(buf, &sk).to_bytes())
+    }
+
+    pub fn verify(buf: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        let public_key = sign::PublicKey::from_slice(public_key);
+        let signature = sign::Signature::from_bytes(signature);
+
+        match (public_key, signature) {
+            (Some(pk), Ok(sig)) => sign::verify_detached(&sig, buf, &pk),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(any(target_arch = "wasm32", feature = "rust-crypto"))]
+mod backend {
+    use std::convert::TryInto;
+
+    use ed25519_dalek::{Signer, SigningKey, VerifyingKey};
+
+    use super::{Hash, PublicKey, SecretKey, Signature};
+
+    pub fn hash(buf: &[u8]) -> Option<Hash> {
+        let digest = blake2b_simd::Params::new().hash_length(32).hash(buf);
+
+        digest.as_bytes().try_into().ok()
+    }
+
+    pub fn generate_keypair() -> (PublicKey, SecretKey) {
+        let mut seed = [0; 32];
+        // Key generation cannot proceed without a source of randomness.
+        getrandom::getrandom(&mut seed).expect("failed to generate random seed");
+        let signing_key = SigningKey::from_bytes(&seed);
+
+        (
+            signing_key.verifying_key().to_bytes(),
+            signing_key.to_keypair_bytes(),
+        )
+    }
+
+    pub fn sign(buf: &[u8], secret_key: &SecretKey) -> Option<Signature> {
+        let signing_key = SigningKey::from_keypair_bytes(secret_key).ok()?;
+
+        Some(signing_key.sign(buf).to_bytes())
+    }
+
+    pub fn verify(buf: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        let public_key = public_key
+            .try_into()
+            .ok()
+            .and_then(|pk| VerifyingKey::from_bytes(pk).ok());
+        let signature = ed25519_dalek::Signature::from_slice(signature);
+
+        match (public_key, signature) {
+            // Strict verification rejects the same weak keys and malleable
+            // signatures as libsodium.
+            (Some(pk), Ok(sig)) => pk.verify_strict(buf, &sig).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+/// An algorithm with which posts are hashed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    /// BLAKE2b with a 32 byte digest and no key, as set by the specification.
+    #[default]
+    Blake2b256,
+}
+
+impl HashAlgorithm {
+    /// Return the multihash code of the algorithm.
+    pub fn code(&self) -> u64 {
+        match self {
+            HashAlgorithm::Blake2b256 => 0xb220,
+        }
+    }
+
+    /// Return the algorithm with the given multihash code, if it is known.
+    pub fn from_code(code: u64) -> Option<Self> {
+        match code {
+            0xb220 => Some(HashAlgorithm::Blake2b256),
+            _ => None,
+        }
+    }
+
+    /// Return the length of a digest of the algorithm, in bytes.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Blake2b256 => 32,
+        }
+    }
+
+    /// Compute the digest of the given bytes.
+    ///
+    /// Returns `None` if the digest could not be computed.
+    pub fn hash(&self, buf: &[u8]) -> Option<Hash> {
+        match self {
+            HashAlgorithm::Blake2b256 => backend::hash(buf),
+        }
+    }
+
+    /// Tag the given digest of the algorithm with the code of the algorithm
+    /// and the length of the digest.
+    pub fn tag(&self, digest: &Hash) -> Vec<u8> {
+        let (code, len) = (self.code(), digest.len() as u64);
+        let mut buf = vec![0; varint::length(code) + varint::length(len) + digest.len()];
+
+        // The buffer is sized for both varints, so neither encoding can fail.
+        let mut offset = varint::encode(code, &mut buf).unwrap();
+        offset += varint::encode(len, &mut buf[offset..]).unwrap();
+        buf[offset..].copy_from_slice(digest);
+
+        buf
+    }
+
+    /// Return the algorithm and digest of the given tagged hash.
+    ///
+    /// An error is returned if the algorithm is unknown or the digest is not
+    /// of the length of its algorithm.
+    pub fn untag(buf: &[u8]) -> Result<(Self, Hash), Error> {
+        let (mut offset, code) = varint::decode(buf)?;
+        let (s, len) = varint::decode(&buf[offset..])?;
+        offset += s;
+
+        let algorithm = match HashAlgorithm::from_code(code) {
+            Some(algorithm) => algorithm,
+            None => {
+                return CableErrorKind::HashTagIncorrect {
+                    context: format!("unknown hash algorithm code {:#x}", code),
+                }
+                .raise()
+            }
+        };
+        let digest = &buf[offset..];
+        if len != algorithm.digest_len() as u64 || digest.len() as u64 != len {
+            return CableErrorKind::HashTagIncorrect {
+                context: format!(
+                    "digest of {} bytes; expected {} bytes for {:?}",
+                    digest.len(),
+                    algorithm.digest_len(),
+                    algorithm
+                ),
+            }
+            .raise();
+        }
+
+        // The length of the digest was checked above.
+        Ok((algorithm, digest.try_into().unwrap()))
+    }
+}
+
+/// Compute the 32 byte BLAKE2b digest of the given bytes, with the default
+/// `HashAlgorithm`.
+///
+/// Returns `None` if the digest could not be computed.
+pub fn hash(buf: &[u8]) -> Option<Hash> {
+    HashAlgorithm::default().hash(buf)
+}
+
+/// Generate a new random Ed25519 keypair.
+pub fn generate_keypair() -> (PublicKey, SecretKey) {
+    backend::generate_keypair()
+}
+
+/// Create a detached signature of the given bytes with the given secret key.
+///
+/// Returns `None` if the secret key is invalid.
+pub fn sign(buf: &[u8], secret_key: &SecretKey) -> Option<Signature> {
+    backend::sign(buf, secret_key)
+}
+
+/// Verify a detached signature of the given bytes against the given public
+/// key. Malformed keys and signatures fail verification.
+pub fn verify(buf: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+    backend::verify(buf, signature, public_key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify() {
+        let (pk, sk) = generate_keypair();
+        assert_eq!(&sk[32..], &pk[..]);
+
+        let signature = sign(b"entomology", &sk).unwrap();
+        assert!(verify(b"entomology", &signature, &pk));
+        assert!(!verify(b"botany", &signature, &pk));
+        assert!(!verify(b"entomology", &signature[..63], &pk));
+
+        let (other_pk, _other_sk) = generate_keypair();
+        assert!(!verify(b"entomology", &signature, &other_pk));
+    }
+
+    #[test]
+    fn tag_and_untag_hash() {
+        let digest = hash(b"entomology").unwrap();
+        let tagged = HashAlgorithm::Blake2b256.tag(&digest);
+        assert_eq!(&tagged[..4], &[0xa0, 0xe4, 0x02, 0x20]);
+        assert_eq!(
+            HashAlgorithm::untag(&tagged).unwrap(),
+            (HashAlgorithm::Blake2b256, digest)
+        );
+
+        assert!(HashAlgorithm::untag(&tagged[..20]).is_err());
+        let mut unknown = tagged.clone();
+        unknown[0] = 0x12;
+        assert!(HashAlgorithm::untag(&unknown).is_err());
+    }
+
+    #[test]
+    fn hash_matches_blake2b_vector() {
+        // BLAKE2b-256 of the empty string.
+        assert_eq!(
+            hex::encode(hash(b"").unwrap()),
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+    }
+}
@@ -1,49 +1,248 @@
 //! Custom error type with backtrace.
+//!
+//! Every error raised by cable is a [`CableError`], which may be recovered
+//! from the boxed [`Error`] by downcasting. Each kind of error belongs to an
+//! [`ErrorCategory`] and has a numeric code, allowing applications to match
+//! on failures without inspecting the displayed message:
+//!
+//! ```
+//! use cable::error::{CableError, ErrorCategory};
+//! use cable::Message;
+//! use desert::FromBytes;
+//!
+//! let err = Message::from_bytes(&[]).unwrap_err();
+//! let err = err.downcast_ref::<CableError>().unwrap();
+//! assert_eq!(err.category(), ErrorCategory::WireFormat);
+//! assert_eq!(err.code(), 102);
+//! ```
 
 #[cfg(feature = "nightly-features")]
 use std::backtrace::Backtrace;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct CableError {
     kind: CableErrorKind,
+    /// The lower-level error which caused this error, if any.
+    source: Option<Error>,
     #[cfg(feature = "nightly-features")]
     backtrace: Backtrace,
 }
 
+impl CableError {
+    /// Return the kind of the error.
+    pub fn kind(&self) -> &CableErrorKind {
+        &self.kind
+    }
+
+    /// Return the category of the error.
+    pub fn category(&self) -> ErrorCategory {
+        self.kind.category()
+    }
+
+    /// Return the numeric code of the error.
+    pub fn code(&self) -> u16 {
+        self.kind.code()
+    }
+}
+
+/// Errors are equal if they are of the same kind, regardless of their
+/// source.
+impl PartialEq for CableError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+/// The broad category to which an error belongs.
+///
+/// The numeric code of every error lies in the range of its category.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// A message or post could not be encoded or decoded (codes 100-199).
+    WireFormat,
+    /// A value falls outside the bounds set by the specification (codes
+    /// 200-299).
+    Validation,
+    /// The store failed to read or write data (codes 300-399).
+    Store,
+    /// The connection to a peer failed (codes 400-499).
+    Transport,
+    /// A peer or the local node violated the protocol (codes 500-599).
+    Protocol,
+}
+
+/// The kinds of error raised by cable.
+///
+/// The numeric code of each kind is stable: new kinds are given new codes
+/// and the code of an existing kind is never changed or reused.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum CableErrorKind {
+    /// The buffer being written to is too small (code 100).
     DstTooSmall { provided: usize, required: usize },
+    /// The buffer being read from is too small (code 101).
+    SrcTooSmall { provided: usize, required: usize },
+    /// The message being decoded is empty (code 102).
     MessageEmpty {},
+    /// The message being encoded is of an unrecognized type (code 103).
     MessageWriteUnrecognizedType { msg_type: u64 },
+    /// A hash response ended before it was fully decoded (code 104).
     MessageHashResponseEnd {},
+    /// A post response ended before it was fully decoded (code 105).
     MessageDataResponseEnd {},
+    /// A post request ended before it was fully decoded (code 106).
     MessageHashRequestEnd {},
+    /// A cancel request ended before it was fully decoded (code 107).
     MessageCancelRequestEnd {},
+    /// A channel time range request ended before it was fully decoded
+    /// (code 108).
     MessageChannelTimeRangeRequestEnd {},
+    /// A channel state request ended before it was fully decoded (code 109).
     MessageChannelStateRequestEnd {},
+    /// A channel list request ended before it was fully decoded (code 110).
     MessageChannelListRequestEnd {},
+    /// A message violates the specification (code 500).
+    MessageSpecViolation { msg_type: u64, violation: String },
+    /// The TTL of a request is greater than the maximum (code 200).
+    MessageTtlIncorrect { ttl: u64, max: u64 },
+    /// Expected data is missing or could not be recovered (code 206).
     NoneError { context: String },
+    /// The post being encoded is of an unrecognized type (code 111).
     PostWriteUnrecognizedType { post_type: u64 },
+    /// The hash of a post could not be computed (code 112).
     PostHashingFailed {},
-    ChannelLengthIncorrect { channel: String, len: usize },
-    TextLengthIncorrect { text: String, len: usize },
-    TopicLengthIncorrect { topic: String, len: usize },
-    UsernameLengthIncorrect { name: String, len: usize },
+    /// A post could not be signed (code 113).
+    PostSigningFailed { context: String },
+    /// A tagged hash names an unknown hash algorithm or holds a digest of
+    /// the wrong length (code 114).
+    HashTagIncorrect { context: String },
+    /// A channel name is empty or too long (code 201).
+    ChannelLengthIncorrect {
+        channel: String,
+        len: usize,
+        max: usize,
+    },
+    /// The text of a post is too long (code 202).
+    TextLengthIncorrect {
+        text: String,
+        len: usize,
+        max: usize,
+    },
+    /// A channel topic is too long (code 203).
+    TopicLengthIncorrect {
+        topic: String,
+        len: usize,
+        max: usize,
+    },
+    /// A reaction is empty or too long (code 204).
+    #[cfg(feature = "reactions")]
+    ReactionLengthIncorrect {
+        reaction: String,
+        len: usize,
+        max: usize,
+    },
+    /// A username is empty or too long (code 205).
+    UsernameLengthIncorrect {
+        name: String,
+        len: usize,
+        max: usize,
+    },
+    /// A message exceeds a configured limit (code 207).
+    LimitExceeded { context: String },
+    /// The value of an `avatar` info key is neither a blob hash nor inline
+    /// bytes (code 208).
+    AvatarIncorrect { avatar: String },
+    /// The store failed to read or write data (code 300).
+    StoreFailed { context: String },
+    /// A write was attempted on a store opened read-only (code 301).
+    StoreReadOnly {},
+    /// Storing a blob would exceed the blob storage quota (code 302).
+    #[cfg(feature = "blobs")]
+    BlobQuotaExceeded { size: u64, quota: u64 },
+    /// Reading from or writing to a peer failed (code 400).
+    TransportFailed { context: String },
 }
 
 impl CableErrorKind {
     pub fn raise<T>(self) -> Result<T, Error> {
-        Err(Box::new(CableError {
+        Err(self.into_error(None))
+    }
+
+    /// Raise an error of this kind, caused by the given lower-level error.
+    ///
+    /// The cause is returned by the `source()` method of the error.
+    pub fn raise_with_source<T>(self, source: impl Into<Error>) -> Result<T, Error> {
+        Err(self.into_error(Some(source.into())))
+    }
+
+    fn into_error(self, source: Option<Error>) -> Error {
+        Box::new(CableError {
             kind: self,
+            source,
             #[cfg(feature = "nightly-features")]
             backtrace: Backtrace::capture(),
-        }))
+        })
+    }
+
+    /// Return the category of the error kind.
+    pub fn category(&self) -> ErrorCategory {
+        match self.code() {
+            100..=199 => ErrorCategory::WireFormat,
+            200..=299 => ErrorCategory::Validation,
+            300..=399 => ErrorCategory::Store,
+            400..=499 => ErrorCategory::Transport,
+            _ => ErrorCategory::Protocol,
+        }
+    }
+
+    /// Return the stable numeric code of the error kind.
+    pub fn code(&self) -> u16 {
+        match self {
+            CableErrorKind::DstTooSmall { .. } => 100,
+            CableErrorKind::SrcTooSmall { .. } => 101,
+            CableErrorKind::MessageEmpty {} => 102,
+            CableErrorKind::MessageWriteUnrecognizedType { .. } => 103,
+            CableErrorKind::MessageHashResponseEnd {} => 104,
+            CableErrorKind::MessageDataResponseEnd {} => 105,
+            CableErrorKind::MessageHashRequestEnd {} => 106,
+            CableErrorKind::MessageCancelRequestEnd {} => 107,
+            CableErrorKind::MessageChannelTimeRangeRequestEnd {} => 108,
+            CableErrorKind::MessageChannelStateRequestEnd {} => 109,
+            CableErrorKind::MessageChannelListRequestEnd {} => 110,
+            CableErrorKind::PostWriteUnrecognizedType { .. } => 111,
+            CableErrorKind::PostHashingFailed {} => 112,
+            CableErrorKind::PostSigningFailed { .. } => 113,
+            CableErrorKind::HashTagIncorrect { .. } => 114,
+            CableErrorKind::MessageTtlIncorrect { .. } => 200,
+            CableErrorKind::ChannelLengthIncorrect { .. } => 201,
+            CableErrorKind::TextLengthIncorrect { .. } => 202,
+            CableErrorKind::TopicLengthIncorrect { .. } => 203,
+            #[cfg(feature = "reactions")]
+            CableErrorKind::ReactionLengthIncorrect { .. } => 204,
+            CableErrorKind::UsernameLengthIncorrect { .. } => 205,
+            CableErrorKind::NoneError { .. } => 206,
+            CableErrorKind::LimitExceeded { .. } => 207,
+            CableErrorKind::AvatarIncorrect { .. } => 208,
+            CableErrorKind::StoreFailed { .. } => 300,
+            CableErrorKind::StoreReadOnly {} => 301,
+            #[cfg(feature = "blobs")]
+            CableErrorKind::BlobQuotaExceeded { .. } => 302,
+            CableErrorKind::TransportFailed { .. } => 400,
+            CableErrorKind::MessageSpecViolation { .. } => 500,
+        }
     }
 }
 
 impl std::error::Error for CableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+
     #[cfg(feature = "nightly-features")]
     fn backtrace<'a>(&'a self) -> Option<&'a Backtrace> {
         Some(&self.backtrace)
@@ -66,6 +265,13 @@ impl std::fmt::Display for CableError {
                     required, provided
                 ]
             }
+            CableErrorKind::SrcTooSmall { provided, required } => {
+                write![
+                    f,
+                    "source buffer too small; {} bytes required, {} provided",
+                    required, provided
+                ]
+            }
             CableErrorKind::MessageHashResponseEnd {} => {
                 write![f, "unexpected end of HashResponse"]
             }
@@ -87,43 +293,140 @@ impl std::fmt::Display for CableError {
             CableErrorKind::MessageChannelListRequestEnd {} => {
                 write![f, "unexpected end of ChannelListRequest"]
             }
+            CableErrorKind::MessageSpecViolation {
+                msg_type,
+                violation,
+            } => {
+                write![
+                    f,
+                    "msg_type={} violates the specification: {}",
+                    msg_type, violation
+                ]
+            }
+            CableErrorKind::MessageTtlIncorrect { ttl, max } => {
+                write![f, "expected ttl between 0 and {}; ttl is {}", max, ttl]
+            }
             CableErrorKind::NoneError { context } => {
                 write![f, "expected data but got none: {}", context]
             }
             CableErrorKind::PostHashingFailed {} => {
                 write![f, "failed to compute hash for post"]
             }
+            CableErrorKind::PostSigningFailed { context } => {
+                write![f, "failed to sign post: {}", context]
+            }
+            CableErrorKind::HashTagIncorrect { context } => {
+                write![f, "incorrect tagged hash: {}", context]
+            }
             CableErrorKind::PostWriteUnrecognizedType { post_type } => {
                 write![f, "cannot write unrecognized post_type={}", post_type]
             }
-            CableErrorKind::ChannelLengthIncorrect { channel, len } => {
+            CableErrorKind::ChannelLengthIncorrect { channel, len, max } => {
+                write![
+                    f,
+                    "expected channel between 1 and {} codepoints; channel `{}` is {} codepoints",
+                    max, channel, len
+                ]
+            }
+            CableErrorKind::TextLengthIncorrect { text, len, max } => {
+                write![
+                    f,
+                    "expected text of {} bytes or less; text `{}` is {} bytes",
+                    max, text, len
+                ]
+            }
+            CableErrorKind::TopicLengthIncorrect { topic, len, max } => {
                 write![
                     f,
-                    "expected channel between 1 and 64 codepoints; channel `{}` is {} codepoints",
-                    channel, len
+                    "expected topic between 0 and {} codepoints; topic `{}` is {} codepoints",
+                    max, topic, len
                 ]
             }
-            CableErrorKind::TextLengthIncorrect { text, len } => {
+            #[cfg(feature = "reactions")]
+            CableErrorKind::ReactionLengthIncorrect { reaction, len, max } => {
                 write![
                     f,
-                    "expected text of 4096 bytes or less; text `{}` is {} bytes",
-                    text, len
+                    "expected reaction between 1 and {} codepoints; reaction `{}` is {} codepoints",
+                    max, reaction, len
                 ]
             }
-            CableErrorKind::TopicLengthIncorrect { topic, len } => {
+            CableErrorKind::UsernameLengthIncorrect { name, len, max } => {
                 write![
                     f,
-                    "expected topic between 0 and 512 codepoints; topic `{}` is {} codepoints",
-                    topic, len
+                    "expected username between 1 and {} codepoints; name `{}` is {} codepoints",
+                    max, name, len
                 ]
             }
-            CableErrorKind::UsernameLengthIncorrect { name, len } => {
+            CableErrorKind::LimitExceeded { context } => {
+                write![f, "limit exceeded: {}", context]
+            }
+            CableErrorKind::AvatarIncorrect { avatar } => {
                 write![
                     f,
-                    "expected username between 1 and 32 codepoints; name `{}` is {} codepoints",
-                    name, len
+                    "expected avatar of `blob:` and a hex hash or `inline:` and hex bytes; avatar `{}` is neither",
+                    avatar
                 ]
             }
+            CableErrorKind::StoreFailed { context } => {
+                write![f, "store failed: {}", context]
+            }
+            CableErrorKind::StoreReadOnly {} => {
+                write![f, "store is read-only"]
+            }
+            #[cfg(feature = "blobs")]
+            CableErrorKind::BlobQuotaExceeded { size, quota } => {
+                write![
+                    f,
+                    "storing a blob of {} bytes would exceed the blob quota of {} bytes",
+                    size, quota
+                ]
+            }
+            CableErrorKind::TransportFailed { context } => {
+                write![f, "transport failed: {}", context]
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn codes_lie_in_category_ranges() {
+        let err = CableErrorKind::TextLengthIncorrect {
+            text: String::new(),
+            len: 0,
+            max: 4096,
+        };
+        assert_eq!(err.code(), 202);
+        assert_eq!(err.category(), ErrorCategory::Validation);
+
+        let err = CableErrorKind::MessageSpecViolation {
+            msg_type: 0,
+            violation: String::new(),
+        };
+        assert_eq!(err.code(), 500);
+        assert_eq!(err.category(), ErrorCategory::Protocol);
+    }
+
+    #[test]
+    fn chain_source() {
+        let io_err = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "closed");
+        let err = CableErrorKind::TransportFailed {
+            context: "failed to write to peer".to_string(),
+        }
+        .raise_with_source::<()>(io_err)
+        .unwrap_err();
+
+        let cable_err = err.downcast_ref::<CableError>().unwrap();
+        assert_eq!(cable_err.category(), ErrorCategory::Transport);
+        let source = cable_err.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<std::io::Error>().unwrap().kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+    }
+}
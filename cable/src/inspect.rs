@@ -0,0 +1,556 @@
+//! Field-by-field inspection of encoded messages and posts.
+//!
+//! Unlike the `FromBytes` implementations, which return either a fully
+//! decoded value or an error, the inspector records the offset, length and
+//! value of every field as it is read and stops at the first malformed field.
+//! This makes it possible to see exactly where a frame produced by another
+//! implementation (such as cable.js) diverges from the expected encoding.
+//!
+//! The `cabledump` binary prints the inspection of hex or binary frames.
+
+use std::fmt;
+
+use desert::varint;
+
+#[cfg(feature = "archive-posts")]
+use crate::constants::ARCHIVE_POST;
+#[cfg(feature = "reactions")]
+use crate::constants::REACTION_POST;
+#[cfg(feature = "blobs")]
+use crate::constants::{BLOB_REQUEST, BLOB_RESPONSE};
+use crate::constants::{
+    CANCEL_REQUEST, CAPABILITY_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE,
+    CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, DELETE_POST, HASH_RESPONSE, INFO_POST,
+    JOIN_POST, LEAVE_POST, POST_REQUEST, POST_RESPONSE, TEXT_POST, TOPIC_POST,
+};
+
+/// A single field read from an encoded frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    /// The offset of the first byte of the field from the start of the frame.
+    pub offset: usize,
+    /// The number of bytes occupied by the field.
+    pub len: usize,
+    /// The name of the field, prefixed with the name of the enclosing
+    /// structure for nested fields (for example, `posts[0].timestamp`).
+    pub name: String,
+    /// The decoded value of the field.
+    pub value: String,
+}
+
+/// The point at which an encoded frame could not be read any further.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InspectError {
+    /// The offset at which the malformed field begins.
+    pub offset: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// The fields read from an encoded message or post.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inspection {
+    /// The kind of the frame, such as `post request` or `text post`.
+    pub kind: String,
+    /// All fields read from the frame, in order.
+    pub fields: Vec<Field>,
+    /// The number of bytes consumed from the input.
+    pub len: usize,
+    /// The first problem encountered, if the frame is malformed.
+    pub error: Option<InspectError>,
+}
+
+impl Inspection {
+    /// Return `true` if the frame was read without error.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl fmt::Display for Inspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({} bytes)", self.kind, self.len)?;
+        writeln!(f, "{:>6} {:>5}  {:<24} value", "offset", "len", "field")?;
+        for field in &self.fields {
+            writeln!(
+                f,
+                "{:>6} {:>5}  {:<24} {}",
+                field.offset, field.len, field.name, field.value
+            )?;
+        }
+        if let Some(err) = &self.error {
+            writeln!(f, "error at offset {}: {}", err.offset, err.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inspect the encoded message at the start of the given buffer, including
+/// the leading message length.
+pub fn inspect_message(buf: &[u8]) -> Inspection {
+    let mut reader = Reader::new(buf);
+    let mut kind = "message".to_string();
+    let res = read_message(&mut reader, &mut kind);
+
+    reader.finish(kind, res)
+}
+
+/// Inspect the encoded post filling the given buffer.
+pub fn inspect_post(buf: &[u8]) -> Inspection {
+    let mut reader = Reader::new(buf);
+    let mut kind = "post".to_string();
+    let res = read_post(&mut reader, &mut kind).and_then(|_| reader.expect_end("post"));
+
+    reader.finish(kind, res)
+}
+
+/// Return the name of the given message type.
+pub(crate) fn message_type_name(msg_type: u64) -> &'static str {
+    match msg_type {
+        HASH_RESPONSE => "hash response",
+        POST_RESPONSE => "post response",
+        POST_REQUEST => "post request",
+        CANCEL_REQUEST => "cancel request",
+        CHANNEL_TIME_RANGE_REQUEST => "channel time range request",
+        CHANNEL_STATE_REQUEST => "channel state request",
+        CHANNEL_LIST_REQUEST => "channel list request",
+        CHANNEL_LIST_RESPONSE => "channel list response",
+        #[cfg(feature = "blobs")]
+        BLOB_REQUEST => "blob request",
+        #[cfg(feature = "blobs")]
+        BLOB_RESPONSE => "blob response",
+        CAPABILITY_REQUEST => "capability request",
+        _ => "unrecognized message",
+    }
+}
+
+/// Return the name of the given post type.
+pub(crate) fn post_type_name(post_type: u64) -> &'static str {
+    match post_type {
+        TEXT_POST => "text post",
+        DELETE_POST => "delete post",
+        INFO_POST => "info post",
+        TOPIC_POST => "topic post",
+        JOIN_POST => "join post",
+        LEAVE_POST => "leave post",
+        #[cfg(feature = "reactions")]
+        REACTION_POST => "reaction post",
+        #[cfg(feature = "archive-posts")]
+        ARCHIVE_POST => "archive post",
+        _ => "unrecognized post",
+    }
+}
+
+fn read_message(r: &mut Reader, kind: &mut String) -> Result<(), InspectError> {
+    let msg_len = r.varint("msg_len")?;
+    let body_start = r.offset;
+
+    // Limit reading to the bytes covered by the message length, so that a
+    // following message is not read as part of this one.
+    let available = r.limit - body_start;
+    let truncated = msg_len > available as u64;
+    if !truncated {
+        r.limit = body_start + msg_len as usize;
+    }
+
+    let msg_type = r.varint("msg_type")?;
+    *kind = message_type_name(msg_type).to_string();
+    r.annotate(message_type_name(msg_type));
+    r.bytes("circuit_id", 4)?;
+    r.bytes("req_id", 4)?;
+
+    match msg_type {
+        HASH_RESPONSE => r.hashes("hash_count", "hashes")?,
+        POST_RESPONSE => {
+            let mut i = 0;
+            loop {
+                let post_len = r.varint(&format!("posts[{}].len", i))?;
+                if post_len == 0 {
+                    break;
+                }
+
+                let remaining = r.limit - r.offset;
+                if post_len > remaining as u64 {
+                    return r.error(format!(
+                        "post length {} exceeds the {} remaining bytes",
+                        post_len, remaining
+                    ));
+                }
+                let end = r.offset + post_len as usize;
+
+                // Read the post as a nested structure ending at the given
+                // post length.
+                let (limit, prefix) = (r.limit, r.prefix.clone());
+                r.limit = end;
+                r.prefix = format!("{}posts[{}].", prefix, i);
+                read_post(r, &mut String::new())?;
+                r.expect_end("post")?;
+                r.limit = limit;
+                r.prefix = prefix;
+
+                i += 1;
+            }
+        }
+        POST_REQUEST => {
+            r.varint("ttl")?;
+            r.hashes("hash_count", "hashes")?;
+        }
+        CANCEL_REQUEST => {
+            r.varint("ttl")?;
+            r.bytes("cancel_id", 4)?;
+        }
+        CHANNEL_TIME_RANGE_REQUEST => {
+            r.varint("ttl")?;
+            r.string("channel")?;
+            r.varint("time_start")?;
+            r.varint("time_end")?;
+            r.varint("limit")?;
+        }
+        CHANNEL_STATE_REQUEST => {
+            r.varint("ttl")?;
+            r.string("channel")?;
+            r.varint("future")?;
+        }
+        CHANNEL_LIST_REQUEST => {
+            r.varint("ttl")?;
+            r.varint("skip")?;
+            r.varint("limit")?;
+        }
+        CHANNEL_LIST_RESPONSE => {
+            let mut i = 0;
+            while r.string(&format!("channels[{}]", i))?.is_some() {
+                i += 1;
+            }
+        }
+        #[cfg(feature = "blobs")]
+        BLOB_REQUEST => {
+            r.varint("ttl")?;
+            r.bytes("hash", 32)?;
+            r.varint("start")?;
+            r.varint("max_len")?;
+        }
+        #[cfg(feature = "blobs")]
+        BLOB_RESPONSE => {
+            r.bytes("hash", 32)?;
+            r.varint("size")?;
+            r.varint("start")?;
+            let data_len = r.varint("data_len")?;
+            let remaining = r.limit - r.offset;
+            if data_len > remaining as u64 {
+                return r.error(format!(
+                    "data length {} exceeds the {} remaining bytes",
+                    data_len, remaining
+                ));
+            }
+            r.bytes("data", data_len as usize)?;
+        }
+        CAPABILITY_REQUEST => {
+            r.varint("ttl")?;
+            r.varint("version")?;
+            for (count_name, name) in [
+                ("msg_type_count", "msg_types"),
+                ("post_type_count", "post_types"),
+            ] {
+                let count = r.varint(count_name)?;
+                for i in 0..count {
+                    r.varint(&format!("{}[{}]", name, i))?;
+                }
+            }
+        }
+        _ => {
+            let len = r.limit - r.offset;
+            r.bytes("body", len)?;
+        }
+    }
+
+    if truncated {
+        return r.error(format!(
+            "message length {} exceeds the {} available bytes",
+            msg_len, available
+        ));
+    }
+    if r.offset < r.limit {
+        return r.error(format!(
+            "{} unread bytes at the end of the message",
+            r.limit - r.offset
+        ));
+    }
+    Ok(())
+}
+
+fn read_post(r: &mut Reader, kind: &mut String) -> Result<(), InspectError> {
+    r.bytes("public_key", 32)?;
+    r.bytes("signature", 64)?;
+    r.hashes("num_links", "links")?;
+    let post_type = r.varint("post_type")?;
+    *kind = post_type_name(post_type).to_string();
+    r.annotate(post_type_name(post_type));
+    r.varint("timestamp")?;
+
+    match post_type {
+        TEXT_POST => {
+            r.string("channel")?;
+            r.string("text")?;
+        }
+        DELETE_POST => r.hashes("num_deletions", "hashes")?,
+        INFO_POST => {
+            let mut i = 0;
+            while r.string(&format!("info[{}].key", i))?.is_some() {
+                r.string(&format!("info[{}].val", i))?;
+                i += 1;
+            }
+        }
+        TOPIC_POST => {
+            r.string("channel")?;
+            r.string("topic")?;
+        }
+        JOIN_POST | LEAVE_POST => {
+            r.string("channel")?;
+        }
+        #[cfg(feature = "reactions")]
+        REACTION_POST => {
+            r.string("channel")?;
+            r.bytes("target", 32)?;
+            r.string("reaction")?;
+        }
+        #[cfg(feature = "archive-posts")]
+        ARCHIVE_POST => {
+            r.string("channel")?;
+        }
+        _ => {
+            let len = r.limit - r.offset;
+            r.bytes("body", len)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A cursor over an encoded frame which records each field read.
+struct Reader<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    /// The offset at which the current structure ends.
+    limit: usize,
+    /// The prefix added to the names of fields of nested structures.
+    prefix: String,
+    fields: Vec<Field>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Reader {
+            buf,
+            offset: 0,
+            limit: buf.len(),
+            prefix: String::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    fn finish(self, kind: String, res: Result<(), InspectError>) -> Inspection {
+        Inspection {
+            kind,
+            fields: self.fields,
+            len: self.offset,
+            error: res.err(),
+        }
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, InspectError> {
+        Err(InspectError {
+            offset: self.offset,
+            message,
+        })
+    }
+
+    fn push(&mut self, offset: usize, name: &str, value: String) {
+        self.fields.push(Field {
+            offset,
+            len: self.offset - offset,
+            name: format!("{}{}", self.prefix, name),
+            value,
+        });
+    }
+
+    /// Append a description to the value of the last field read.
+    fn annotate(&mut self, note: &str) {
+        if let Some(field) = self.fields.last_mut() {
+            field.value = format!("{} ({})", field.value, note);
+        }
+    }
+
+    fn varint(&mut self, name: &str) -> Result<u64, InspectError> {
+        match varint::decode(&self.buf[self.offset..self.limit]) {
+            Ok((len, value)) => {
+                let offset = self.offset;
+                self.offset += len;
+                self.push(offset, name, value.to_string());
+                Ok(value)
+            }
+            Err(_) => self.error(format!("expected varint for {}{}", self.prefix, name)),
+        }
+    }
+
+    fn take(&mut self, name: &str, len: usize) -> Result<&'a [u8], InspectError> {
+        let remaining = self.limit - self.offset;
+        if len > remaining {
+            return self.error(format!(
+                "expected {} bytes for {}{} but {} remain",
+                len, self.prefix, name, remaining
+            ));
+        }
+
+        let bytes = &self.buf[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn bytes(&mut self, name: &str, len: usize) -> Result<(), InspectError> {
+        let offset = self.offset;
+        let bytes = self.take(name, len)?;
+        self.push(offset, name, hex::encode(bytes));
+
+        Ok(())
+    }
+
+    /// Read a count followed by that many hashes.
+    fn hashes(&mut self, count_name: &str, name: &str) -> Result<(), InspectError> {
+        let count = self.varint(count_name)?;
+        for i in 0..count {
+            self.bytes(&format!("{}[{}]", name, i), 32)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a length-prefixed UTF-8 string, returning `None` for a length of
+    /// zero.
+    fn string(&mut self, name: &str) -> Result<Option<String>, InspectError> {
+        let len = self.varint(&format!("{}_len", name))?;
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let offset = self.offset;
+        let bytes = self.take(name, len.min(usize::MAX as u64) as usize)?;
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(string) => {
+                self.push(offset, name, format!("{:?}", string));
+                Ok(Some(string))
+            }
+            Err(_) => {
+                self.offset = offset;
+                self.error(format!("invalid UTF-8 in {}{}", self.prefix, name))
+            }
+        }
+    }
+
+    /// Ensure that all bytes of the current structure have been read.
+    fn expect_end(&self, structure: &str) -> Result<(), InspectError> {
+        if self.offset < self.limit {
+            return self.error(format!(
+                "{} unread bytes at the end of the {}",
+                self.limit - self.offset,
+                structure
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use desert::{FromBytes, ToBytes};
+
+    use crate::{constants::NO_CIRCUIT, Error, Message, Post};
+
+    // Field values sourced from https://github.com/cabal-club/cable.js#examples
+    // and `examples/types.rs`.
+    const CHANNEL_TIME_RANGE_REQUEST_HEX_BINARY: &str =
+        "15040000000004baaffb010764656661756c74006414";
+    const TEXT_POST_HEX_BINARY: &str = "aead820c67703da78dba364338d8b0d65d65c03a7e9310de87b2b36818ca1e5d1ebd8a0880f53a2caeb199ee937d056c62fde63cac91d8b30b3254441b97a1a4e4a1b45ce2372ced55752a6ce660248728b4ae40ba572ebc7cf571c43990d10901fea16c09f8aa581500fcf6ee2f6aabc59ccaa271d2a3568843930b7ff929ad8600eaadc0e5240962696b655f6c6966650ce38282e38197e38282e38197";
+
+    fn field(inspection: &Inspection, name: &str) -> Field {
+        inspection
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn inspect_channel_time_range_request() {
+        let buf = hex::decode(CHANNEL_TIME_RANGE_REQUEST_HEX_BINARY).unwrap();
+        let inspection = inspect_message(&buf);
+
+        assert!(inspection.is_ok());
+        assert_eq!(inspection.kind, "channel time range request");
+        assert_eq!(inspection.len, buf.len());
+
+        let channel = field(&inspection, "channel");
+        assert_eq!((channel.offset, channel.len), (12, 7));
+        assert_eq!(channel.value, "\"default\"");
+        assert_eq!(field(&inspection, "limit").value, "20");
+    }
+
+    #[test]
+    fn inspect_text_post() {
+        let buf = hex::decode(TEXT_POST_HEX_BINARY).unwrap();
+        let inspection = inspect_post(&buf);
+
+        assert!(inspection.is_ok(), "{}", inspection);
+        assert_eq!(inspection.kind, "text post");
+        assert_eq!(field(&inspection, "post_type").value, "0 (text post)");
+        assert_eq!(field(&inspection, "text").value, "\"もしもし\"");
+    }
+
+    #[test]
+    fn inspect_post_response_with_nested_offsets() -> Result<(), Error> {
+        let post = hex::decode(TEXT_POST_HEX_BINARY).unwrap();
+        let msg = Message::post_response(NO_CIRCUIT, [1, 2, 3, 4], vec![post.clone()]);
+        let buf = msg.to_bytes()?;
+        let inspection = inspect_message(&buf);
+
+        assert!(inspection.is_ok(), "{}", inspection);
+        let post_len = field(&inspection, "posts[0].len");
+        let public_key = field(&inspection, "posts[0].public_key");
+        assert_eq!(public_key.offset, post_len.offset + post_len.len);
+        assert_eq!(
+            field(&inspection, "posts[0].channel").value,
+            "\"bike_life\""
+        );
+        assert_eq!(
+            Post::from_bytes(&post)?.1.get_channel(),
+            Some(&"bike_life".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn report_truncated_frame() {
+        let buf = hex::decode(CHANNEL_TIME_RANGE_REQUEST_HEX_BINARY).unwrap();
+        let inspection = inspect_message(&buf[..15]);
+
+        let err = inspection.error.clone().unwrap();
+        assert_eq!(err.offset, 12);
+        assert!(err.message.contains("channel"));
+        // Fields preceding the truncation are still reported.
+        assert_eq!(field(&inspection, "ttl").value, "1");
+    }
+
+    #[test]
+    fn report_trailing_bytes_in_post() {
+        let mut buf = hex::decode(TEXT_POST_HEX_BINARY).unwrap();
+        buf.extend_from_slice(&[0, 0]);
+        let inspection = inspect_post(&buf);
+
+        assert!(inspection.error.unwrap().message.contains("2 unread bytes"));
+    }
+}
@@ -0,0 +1,256 @@
+//! Conversion of posts to and from JSON.
+//!
+//! A post is represented as a flat JSON object with its fields named as in
+//! cable.js. Binary fields (the hash, public key, signature, links and
+//! deleted hashes) are hex-encoded strings, and the key-value pairs of an info
+//! post are a list of two-element lists:
+//!
+//! ```text
+//! {"hash":"…","publicKey":"…","signature":"…","links":["…"],"postType":0,
+//!  "timestamp":80,"channel":"default","text":"h€llo world"}
+//! ```
+
+use std::convert::TryInto;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "archive-posts")]
+use crate::constants::ARCHIVE_POST;
+#[cfg(feature = "reactions")]
+use crate::constants::REACTION_POST;
+use crate::{
+    constants::{DELETE_POST, INFO_POST, JOIN_POST, LEAVE_POST, TEXT_POST, TOPIC_POST},
+    error::{CableErrorKind, Error},
+    post::{PostBody, PostHeader},
+    Channel, Post, Text, Topic, UserInfo,
+};
+
+/// The JSON representation of a post.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonPost {
+    /// The hash of the post. Ignored if absent when converting from JSON.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+    public_key: String,
+    /// The signature of the post. An absent signature denotes an unsigned
+    /// post.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    links: Vec<String>,
+    post_type: u64,
+    timestamp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel: Option<Channel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<Text>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hashes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    info: Option<Vec<(String, String)>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    topic: Option<Topic>,
+    /// The hash of the post reacted to by a reaction post.
+    #[cfg(feature = "reactions")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    #[cfg(feature = "reactions")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reaction: Option<String>,
+}
+
+/// Decode a hex-encoded fixed-length byte array.
+fn from_hex<const N: usize>(hex: &str, field: &str) -> Result<[u8; N], Error> {
+    let bytes = hex::decode(hex)?;
+
+    match bytes.try_into() {
+        Ok(bytes) => Ok(bytes),
+        Err(_) => CableErrorKind::NoneError {
+            context: format!("expected {} to be {} bytes", field, N),
+        }
+        .raise(),
+    }
+}
+
+/// Return the given field of a JSON post, or an error if it is absent.
+fn required<T>(field: Option<T>, name: &str) -> Result<T, Error> {
+    match field {
+        Some(field) => Ok(field),
+        None => CableErrorKind::NoneError {
+            context: format!("missing {} field of JSON post", name),
+        }
+        .raise(),
+    }
+}
+
+impl Post {
+    /// Convert the post to a JSON object, including its hash.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let mut json = JsonPost {
+            hash: Some(hex::encode(self.hash()?)),
+            public_key: hex::encode(self.header.public_key),
+            signature: self.is_signed().then(|| hex::encode(self.header.signature)),
+            links: self.header.links.iter().map(hex::encode).collect(),
+            post_type: self.post_type(),
+            timestamp: self.header.timestamp,
+            ..JsonPost::default()
+        };
+
+        match &self.body {
+            PostBody::Text { channel, text } => {
+                json.channel = Some(channel.to_owned());
+                json.text = Some(text.to_owned());
+            }
+            PostBody::Delete { hashes } => {
+                json.hashes = Some(hashes.iter().map(hex::encode).collect())
+            }
+            PostBody::Info { info } => {
+                json.info = Some(
+                    info.iter()
+                        .map(|UserInfo { key, val }| (key.to_owned(), val.to_owned()))
+                        .collect(),
+                )
+            }
+            PostBody::Topic { channel, topic } => {
+                json.channel = Some(channel.to_owned());
+                json.topic = Some(topic.to_owned());
+            }
+            PostBody::Join { channel } | PostBody::Leave { channel } => {
+                json.channel = Some(channel.to_owned())
+            }
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel,
+                target,
+                reaction,
+            } => {
+                json.channel = Some(channel.to_owned());
+                json.target = Some(hex::encode(target));
+                json.reaction = Some(reaction.to_owned());
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => json.channel = Some(channel.to_owned()),
+            PostBody::Unrecognized { .. } => (),
+        }
+
+        Ok(serde_json::to_string(&json)?)
+    }
+
+    /// Convert a JSON object produced by `to_json()` to a post.
+    ///
+    /// If the object includes a hash, it must match the hash of the post.
+    pub fn from_json(json: &str) -> Result<Post, Error> {
+        let json: JsonPost = serde_json::from_str(json)?;
+
+        let signature = match &json.signature {
+            Some(signature) => from_hex(signature, "signature")?,
+            None => [0; 64],
+        };
+        let links = json
+            .links
+            .iter()
+            .map(|link| from_hex(link, "link"))
+            .collect::<Result<_, _>>()?;
+        let header = PostHeader::new(
+            from_hex(&json.public_key, "public key")?,
+            signature,
+            links,
+            json.post_type,
+            json.timestamp,
+        );
+
+        let body = match json.post_type {
+            TEXT_POST => PostBody::Text {
+                channel: required(json.channel, "channel")?,
+                text: required(json.text, "text")?,
+            },
+            DELETE_POST => PostBody::Delete {
+                hashes: required(json.hashes, "hashes")?
+                    .iter()
+                    .map(|hash| from_hex(hash, "hash"))
+                    .collect::<Result<_, _>>()?,
+            },
+            INFO_POST => PostBody::Info {
+                info: required(json.info, "info")?
+                    .into_iter()
+                    .map(|(key, val)| UserInfo::new(key, val))
+                    .collect(),
+            },
+            TOPIC_POST => PostBody::Topic {
+                channel: required(json.channel, "channel")?,
+                topic: required(json.topic, "topic")?,
+            },
+            JOIN_POST => PostBody::Join {
+                channel: required(json.channel, "channel")?,
+            },
+            LEAVE_POST => PostBody::Leave {
+                channel: required(json.channel, "channel")?,
+            },
+            #[cfg(feature = "reactions")]
+            REACTION_POST => PostBody::Reaction {
+                channel: required(json.channel, "channel")?,
+                target: from_hex(&required(json.target, "target")?, "target")?,
+                reaction: required(json.reaction, "reaction")?,
+            },
+            #[cfg(feature = "archive-posts")]
+            ARCHIVE_POST => PostBody::Archive {
+                channel: required(json.channel, "channel")?,
+            },
+            post_type => PostBody::Unrecognized { post_type },
+        };
+        let post = Post::new(header, body);
+
+        if let Some(hash) = json.hash {
+            if hex::encode(post.hash()?) != hash {
+                return CableErrorKind::NoneError {
+                    context: "hash of JSON post does not match its fields".to_string(),
+                }
+                .raise();
+            }
+        }
+
+        Ok(post)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use desert::{FromBytes, ToBytes};
+    use hex::FromHex;
+
+    use super::*;
+
+    // Field values sourced from https://github.com/cabal-club/cable.js#examples.
+
+    const TEXT_POST_HEX_BINARY: &str = "25b272a71555322d40efe449a7f99af8fd364b92d350f1664481b2da340a02d06725733046b35fa3a7e8dc0099a2b3dff10d3fd8b0f6da70d094352e3f5d27a8bc3f5586cf0bf71befc22536c3c50ec7b1d64398d43c3f4cde778e579e88af05015049d089a650aa896cb25ec35258653be4df196b4a5e5b6db7ed024aaa89e1b300500764656661756c740d68e282ac6c6c6f20776f726c64";
+    const INFO_POST_HEX_BINARY: &str = "25b272a71555322d40efe449a7f99af8fd364b92d350f1664481b2da340a02d04ccb1c0063ef09a200e031ee89d874bcc99f3e6fd8fd667f5e28f4dbcf4b7de6bb1ce37d5f01cc055a7b70cef175d30feeb34531db98c91fa8b3fa4d7c5fd307015049d089a650aa896cb25ec35258653be4df196b4a5e5b6db7ed024aaa89e1b30250046e616d65066361626c657200";
+
+    #[test]
+    fn posts_roundtrip_through_json() -> Result<(), Error> {
+        for hex in [TEXT_POST_HEX_BINARY, INFO_POST_HEX_BINARY] {
+            let bytes = <Vec<u8>>::from_hex(hex)?;
+            let (_, post) = Post::from_bytes(&bytes)?;
+
+            let json = post.to_json()?;
+            assert_eq!(Post::from_json(&json)?.to_bytes()?, bytes);
+        }
+
+        let (_, post) = Post::from_bytes(&<Vec<u8>>::from_hex(TEXT_POST_HEX_BINARY)?)?;
+        let json: serde_json::Value = serde_json::from_str(&post.to_json()?)?;
+        assert_eq!(json["channel"], "default");
+        assert_eq!(json["text"], "h€llo world");
+        assert_eq!(json["timestamp"], 80);
+
+        Ok(())
+    }
+
+    #[test]
+    fn mismatched_hash_is_rejected() -> Result<(), Error> {
+        let (_, post) = Post::from_bytes(&<Vec<u8>>::from_hex(TEXT_POST_HEX_BINARY)?)?;
+        let json = post.to_json()?.replace("h€llo", "hello");
+
+        assert!(Post::from_json(&json).is_err());
+
+        Ok(())
+    }
+}
@@ -2,16 +2,26 @@
 
 use std::fmt;
 
+pub mod capabilities;
+pub mod conformance;
 pub mod constants;
+pub mod crypto;
 pub mod error;
+pub mod inspect;
+#[cfg(feature = "serde")]
+mod json;
+pub mod limits;
 pub mod message;
 pub mod post;
+#[cfg(feature = "record-vectors")]
+pub mod record;
+pub mod signer;
 pub mod validation;
 
 // Public exports for library user convenience.
 pub use crate::{error::Error, message::Message, post::Post};
 
-use crate::error::CableErrorKind;
+use crate::{error::CableErrorKind, limits::Limits};
 
 /// The name of a channel.
 pub type Channel = String;
@@ -85,19 +95,19 @@ impl UserInfo {
     /// Create an instance of `UserInfo` to set a user's display name.
     pub fn name<T: Into<String>>(username: T) -> Result<Self, Error> {
         let name = username.into();
-        // Determine the length of the given username in UTF-8 codepoints.
-        let name_len = name.chars().count();
         // The name must be between 1 and 32 codepoints.
-        if !(1..=32).contains(&name_len) {
-            return CableErrorKind::UsernameLengthIncorrect {
-                name,
-                len: name_len,
-            }
-            .raise();
-        }
+        Limits::DEFAULT.check_username(&name)?;
 
         Ok(UserInfo::new("name", name))
     }
+
+    /// Create an instance of `UserInfo` to set a user's avatar.
+    pub fn avatar(avatar: &Avatar) -> Result<Self, Error> {
+        // Inline avatars must not exceed the maximum size.
+        Limits::DEFAULT.check_avatar(avatar)?;
+
+        Ok(UserInfo::new("avatar", avatar.to_string()))
+    }
 }
 
 /// Print debug representation of user info.
@@ -113,3 +123,63 @@ impl fmt::Display for UserInfo {
         write!(f, "key: {}, val: {}", &self.key, &self.val)
     }
 }
+
+/// The avatar of a user, set with the `avatar` key of a `post/info` post.
+///
+/// The value of the key is either `blob:` followed by the hex-encoded hash of
+/// an image blob, or `inline:` followed by the hex-encoded bytes of a small
+/// image. The `avatar` key is not part of the cable specification; other
+/// implementations keep it as any other info key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Avatar {
+    /// The hash of an image blob, fetched separately from peers.
+    Blob(Hash),
+    /// The bytes of a small image.
+    Inline(Vec<u8>),
+}
+
+impl Avatar {
+    /// Parse an avatar from the value of an `avatar` info key.
+    pub fn parse(val: &str) -> Result<Self, Error> {
+        let avatar = if let Some(hash) = val.strip_prefix("blob:") {
+            <[u8; 32] as hex::FromHex>::from_hex(hash)
+                .ok()
+                .map(Avatar::Blob)
+        } else if let Some(bytes) = val.strip_prefix("inline:") {
+            hex::decode(bytes).ok().map(Avatar::Inline)
+        } else {
+            None
+        };
+
+        match avatar {
+            Some(avatar) => Ok(avatar),
+            None => CableErrorKind::AvatarIncorrect {
+                avatar: val.to_owned(),
+            }
+            .raise(),
+        }
+    }
+}
+
+/// Print the avatar as the value of an `avatar` info key.
+impl fmt::Display for Avatar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Avatar::Blob(hash) => write!(f, "blob:{}", hex::encode(hash)),
+            Avatar::Inline(bytes) => write!(f, "inline:{}", hex::encode(bytes)),
+        }
+    }
+}
+
+/// Return the given number of bytes of the buffer, starting at the given
+/// offset, or an error if the buffer is too short.
+pub(crate) fn read_bytes(buf: &[u8], offset: usize, len: usize) -> Result<&[u8], Error> {
+    match offset.checked_add(len) {
+        Some(end) if end <= buf.len() => Ok(&buf[offset..end]),
+        _ => CableErrorKind::SrcTooSmall {
+            provided: buf.len(),
+            required: offset.saturating_add(len),
+        }
+        .raise(),
+    }
+}
@@ -0,0 +1,336 @@
+//! Limits on the contents of messages and posts.
+//!
+//! The cable specification bounds the length of channel names, texts and
+//! topics and the TTL of requests. Other bounds, such as the size of a
+//! message or the number of hashes it may hold, are left to each
+//! implementation. `Limits` gathers all of them, so that a deployment can
+//! tighten or relax them in one place while encoding and decoding apply the
+//! same values.
+//!
+//! The free functions of the `validation` module apply `Limits::DEFAULT`.
+
+use crate::{
+    constants::MAX_TTL,
+    error::{CableErrorKind, Error},
+    message::{MessageBody, RequestBody, ResponseBody},
+    post::PostBody,
+    Avatar, Message, Post,
+};
+
+/// Limits on the contents of messages and posts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of hops a request may be forwarded.
+    pub max_ttl: u8,
+    /// The maximum length of a channel name, in codepoints.
+    pub max_channel_len: usize,
+    /// The maximum length of the text of a post, in bytes.
+    pub max_text_len: usize,
+    /// The maximum length of a channel topic, in codepoints.
+    pub max_topic_len: usize,
+    /// The maximum length of a username, in codepoints.
+    pub max_username_len: usize,
+    /// The maximum length of a reaction, in codepoints.
+    #[cfg(feature = "reactions")]
+    pub max_reaction_len: usize,
+    /// The maximum size of an inline avatar, in bytes.
+    pub max_avatar_len: usize,
+    /// The maximum size of an encoded message, in bytes.
+    pub max_message_size: usize,
+    /// The maximum number of hashes in a hash response or post request.
+    pub max_hashes: usize,
+    /// The maximum number of channels in a channel list response.
+    pub max_channels: usize,
+    /// The maximum number of live requests held for a single peer.
+    pub max_live_requests: usize,
+}
+
+impl Limits {
+    /// The limits set by the cable specification, along with the bounds
+    /// chosen by this implementation where the specification sets none.
+    pub const DEFAULT: Limits = Limits {
+        max_ttl: MAX_TTL,
+        max_channel_len: 64,
+        max_text_len: 4096,
+        max_topic_len: 512,
+        max_username_len: 32,
+        #[cfg(feature = "reactions")]
+        max_reaction_len: 16,
+        max_avatar_len: 1024,
+        max_message_size: 50_000,
+        max_hashes: 4096,
+        max_channels: 4096,
+        max_live_requests: 256,
+    };
+
+    /// Validate the TTL of a request.
+    pub fn check_ttl(&self, ttl: u64) -> Result<(), Error> {
+        if ttl > self.max_ttl as u64 {
+            return CableErrorKind::MessageTtlIncorrect {
+                ttl,
+                max: self.max_ttl as u64,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the length of a channel name.
+    pub fn check_channel(&self, channel: &str) -> Result<(), Error> {
+        let channel_len = channel.chars().count();
+        if !(1..=self.max_channel_len).contains(&channel_len) {
+            return CableErrorKind::ChannelLengthIncorrect {
+                channel: channel.to_owned(),
+                len: channel_len,
+                max: self.max_channel_len,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the length of the text of a post.
+    pub fn check_text(&self, text: &str) -> Result<(), Error> {
+        if text.len() > self.max_text_len {
+            return CableErrorKind::TextLengthIncorrect {
+                text: text.to_owned(),
+                len: text.len(),
+                max: self.max_text_len,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the length of a channel topic.
+    pub fn check_topic(&self, topic: &str) -> Result<(), Error> {
+        let topic_len = topic.chars().count();
+        if topic_len > self.max_topic_len {
+            return CableErrorKind::TopicLengthIncorrect {
+                topic: topic.to_owned(),
+                len: topic_len,
+                max: self.max_topic_len,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the length of a username.
+    pub fn check_username(&self, name: &str) -> Result<(), Error> {
+        let name_len = name.chars().count();
+        if !(1..=self.max_username_len).contains(&name_len) {
+            return CableErrorKind::UsernameLengthIncorrect {
+                name: name.to_owned(),
+                len: name_len,
+                max: self.max_username_len,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the length of a reaction.
+    #[cfg(feature = "reactions")]
+    pub fn check_reaction(&self, reaction: &str) -> Result<(), Error> {
+        // Reactions are counted in codepoints, allowing for emoji composed
+        // of several codepoints.
+        let reaction_len = reaction.chars().count();
+        if !(1..=self.max_reaction_len).contains(&reaction_len) {
+            return CableErrorKind::ReactionLengthIncorrect {
+                reaction: reaction.to_owned(),
+                len: reaction_len,
+                max: self.max_reaction_len,
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the size of an avatar.
+    pub fn check_avatar(&self, avatar: &Avatar) -> Result<(), Error> {
+        if let Avatar::Inline(bytes) = avatar {
+            if bytes.len() > self.max_avatar_len {
+                return CableErrorKind::LimitExceeded {
+                    context: format!(
+                        "inline avatar of {} bytes; at most {} allowed",
+                        bytes.len(),
+                        self.max_avatar_len
+                    ),
+                }
+                .raise();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the number of hashes of a hash response or post request.
+    pub fn check_hash_count(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_hashes as u64 {
+            return CableErrorKind::LimitExceeded {
+                context: format!("{} hashes; at most {} allowed", count, self.max_hashes),
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the number of channels of a channel list response.
+    pub fn check_channel_count(&self, count: u64) -> Result<(), Error> {
+        if count > self.max_channels as u64 {
+            return CableErrorKind::LimitExceeded {
+                context: format!("{} channels; at most {} allowed", count, self.max_channels),
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the size of an encoded message.
+    pub fn check_message_size(&self, size: usize) -> Result<(), Error> {
+        if size > self.max_message_size {
+            return CableErrorKind::LimitExceeded {
+                context: format!(
+                    "message of {} bytes; at most {} allowed",
+                    size, self.max_message_size
+                ),
+            }
+            .raise();
+        }
+
+        Ok(())
+    }
+
+    /// Validate the fields of a post.
+    pub fn check_post(&self, post: &Post) -> Result<(), Error> {
+        match &post.body {
+            PostBody::Text { channel, text } => {
+                self.check_channel(channel)?;
+                self.check_text(text)?;
+            }
+            PostBody::Topic { channel, topic } => {
+                self.check_channel(channel)?;
+                self.check_topic(topic)?;
+            }
+            PostBody::Join { channel } | PostBody::Leave { channel } => {
+                self.check_channel(channel)?;
+            }
+            PostBody::Info { info } => {
+                for user_info in info {
+                    match user_info.key.as_str() {
+                        "name" => self.check_username(&user_info.val)?,
+                        "avatar" => self.check_avatar(&Avatar::parse(&user_info.val)?)?,
+                        _ => {}
+                    }
+                }
+            }
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel, reaction, ..
+            } => {
+                self.check_channel(channel)?;
+                self.check_reaction(reaction)?;
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => {
+                self.check_channel(channel)?;
+            }
+            PostBody::Delete { .. } | PostBody::Unrecognized { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Validate the fields of a message.
+    pub fn check_message(&self, msg: &Message) -> Result<(), Error> {
+        match &msg.body {
+            MessageBody::Request { ttl, body } => {
+                self.check_ttl(*ttl as u64)?;
+                match body {
+                    RequestBody::Post { hashes } => self.check_hash_count(hashes.len() as u64)?,
+                    RequestBody::ChannelTimeRange { channel, .. }
+                    | RequestBody::ChannelState { channel, .. } => self.check_channel(channel)?,
+                    RequestBody::Cancel { .. } | RequestBody::ChannelList { .. } => {}
+                    #[cfg(feature = "blobs")]
+                    RequestBody::Blob { .. } => {}
+                    RequestBody::Capabilities { .. } => {}
+                }
+            }
+            MessageBody::Response { body } => match body {
+                ResponseBody::Hash { hashes } => self.check_hash_count(hashes.len() as u64)?,
+                ResponseBody::Post { .. } => {}
+                ResponseBody::ChannelList { channels } => {
+                    self.check_channel_count(channels.len() as u64)?;
+                    for channel in channels {
+                        self.check_channel(channel)?;
+                    }
+                }
+                #[cfg(feature = "blobs")]
+                ResponseBody::Blob { .. } => {}
+            },
+            MessageBody::Unrecognized { .. } => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use desert::ToBytes;
+
+    use crate::constants::NO_CIRCUIT;
+
+    use super::*;
+
+    #[test]
+    fn apply_custom_limits() -> Result<(), Error> {
+        let limits = Limits {
+            max_channel_len: 8,
+            max_hashes: 1,
+            ..Limits::DEFAULT
+        };
+
+        assert!(Limits::DEFAULT.check_channel("entomology").is_ok());
+        assert!(limits.check_channel("entomology").is_err());
+
+        let msg = Message::hash_response(NO_CIRCUIT, [0, 0, 0, 1], vec![[0; 32], [1; 32]]);
+        assert!(Limits::DEFAULT.check_message(&msg).is_ok());
+        assert!(limits.check_message(&msg).is_err());
+
+        // The limits are applied when decoding.
+        let buf = msg.to_bytes()?;
+        assert!(Message::from_bytes_with_limits(&buf, &Limits::DEFAULT, true).is_ok());
+        assert!(Message::from_bytes_with_limits(&buf, &limits, true).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "reactions")]
+    #[test]
+    fn apply_reaction_limit() {
+        let limits = Limits {
+            max_reaction_len: 1,
+            ..Limits::DEFAULT
+        };
+
+        assert!(Limits::DEFAULT.check_reaction("👍🏽").is_ok());
+        assert!(limits.check_reaction("👍🏽").is_err());
+        assert!(limits.check_reaction("").is_err());
+    }
+}
@@ -9,15 +9,18 @@
 use std::fmt;
 
 use desert::{varint, CountBytes, FromBytes, ToBytes};
-use sodiumoxide::hex;
 
+#[cfg(feature = "blobs")]
+use crate::constants::{BLOB_REQUEST, BLOB_RESPONSE};
 use crate::{
     constants::{
-        CANCEL_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE, CHANNEL_STATE_REQUEST,
-        CHANNEL_TIME_RANGE_REQUEST, HASH_RESPONSE, POST_REQUEST, POST_RESPONSE,
+        CANCEL_REQUEST, CAPABILITY_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE,
+        CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, HASH_RESPONSE, MAX_TTL, POST_REQUEST,
+        POST_RESPONSE,
     },
     error::{CableErrorKind, Error},
-    Channel, ChannelOptions, CircuitId, Hash, Payload, ReqId, Timestamp,
+    limits::Limits,
+    read_bytes, Channel, ChannelOptions, CircuitId, Hash, Payload, ReqId, Timestamp,
 };
 
 /// A complete message including header and body values.
@@ -34,13 +37,38 @@ impl Message {
     }
 
     /// Decrement the TTL of a request-type message by one.
+    ///
+    /// A TTL above the maximum of 16 is clamped before it is decremented and
+    /// a TTL of 0 is left unchanged.
     pub fn decrement_ttl(&mut self) {
-        // TODO: Ensure this works as intended.
         if let MessageBody::Request { ref mut ttl, .. } = self.body {
-            *ttl -= 1
+            *ttl = (*ttl).min(MAX_TTL).saturating_sub(1)
         }
     }
 
+    /// Read bytes from the given buffer (byte array), returning the total
+    /// number of bytes and the decoded `Message` type.
+    ///
+    /// Unlike `from_bytes()`, which clamps the TTL of a request to the
+    /// maximum of 16, a request with a greater TTL is rejected with an error.
+    pub fn from_bytes_strict(buf: &[u8]) -> Result<(usize, Self), Error> {
+        Self::decode(buf, true, None)
+    }
+
+    /// Read bytes from the given buffer (byte array), returning the total
+    /// number of bytes and the decoded `Message` type, or an error if the
+    /// message exceeds the given limits.
+    ///
+    /// A request with a TTL above the maximum is rejected if `strict` is
+    /// set; otherwise its TTL is clamped to the maximum.
+    pub fn from_bytes_with_limits(
+        buf: &[u8],
+        limits: &Limits,
+        strict: bool,
+    ) -> Result<(usize, Self), Error> {
+        Self::decode(buf, strict, Some(limits))
+    }
+
     /// Return the numeric type identifier for the message.
     pub fn message_type(&self) -> u64 {
         match &self.body {
@@ -50,11 +78,16 @@ impl Message {
                 RequestBody::ChannelTimeRange { .. } => CHANNEL_TIME_RANGE_REQUEST,
                 RequestBody::ChannelState { .. } => CHANNEL_STATE_REQUEST,
                 RequestBody::ChannelList { .. } => CHANNEL_LIST_REQUEST,
+                #[cfg(feature = "blobs")]
+                RequestBody::Blob { .. } => BLOB_REQUEST,
+                RequestBody::Capabilities { .. } => CAPABILITY_REQUEST,
             },
             MessageBody::Response { body } => match body {
                 ResponseBody::Hash { .. } => HASH_RESPONSE,
                 ResponseBody::Post { .. } => POST_RESPONSE,
                 ResponseBody::ChannelList { .. } => CHANNEL_LIST_RESPONSE,
+                #[cfg(feature = "blobs")]
+                ResponseBody::Blob { .. } => BLOB_RESPONSE,
             },
             MessageBody::Unrecognized { msg_type } => *msg_type,
         }
@@ -144,6 +177,51 @@ impl Message {
         Message::new(header, body)
     }
 
+    /// Construct a blob request `Message` with the given parameters.
+    #[cfg(feature = "blobs")]
+    pub fn blob_request(
+        circuit_id: CircuitId,
+        req_id: ReqId,
+        ttl: u8,
+        hash: Hash,
+        start: u64,
+        max_len: u64,
+    ) -> Self {
+        let header = MessageHeader::new(BLOB_REQUEST, circuit_id, req_id);
+        let body = MessageBody::Request {
+            ttl,
+            body: RequestBody::Blob {
+                hash,
+                start,
+                max_len,
+            },
+        };
+
+        Message::new(header, body)
+    }
+
+    /// Construct a capability request `Message` with the given parameters.
+    pub fn capability_request(
+        circuit_id: CircuitId,
+        req_id: ReqId,
+        ttl: u8,
+        version: u64,
+        msg_types: Vec<u64>,
+        post_types: Vec<u64>,
+    ) -> Self {
+        let header = MessageHeader::new(CAPABILITY_REQUEST, circuit_id, req_id);
+        let body = MessageBody::Request {
+            ttl,
+            body: RequestBody::Capabilities {
+                version,
+                msg_types,
+                post_types,
+            },
+        };
+
+        Message::new(header, body)
+    }
+
     /// Construct a hash response `Message` with the given parameters.
     pub fn hash_response(circuit_id: CircuitId, req_id: ReqId, hashes: Vec<Hash>) -> Self {
         let header = MessageHeader::new(HASH_RESPONSE, circuit_id, req_id);
@@ -177,6 +255,29 @@ impl Message {
 
         Message::new(header, body)
     }
+
+    /// Construct a blob response `Message` with the given parameters.
+    #[cfg(feature = "blobs")]
+    pub fn blob_response(
+        circuit_id: CircuitId,
+        req_id: ReqId,
+        hash: Hash,
+        size: u64,
+        start: u64,
+        data: Vec<u8>,
+    ) -> Self {
+        let header = MessageHeader::new(BLOB_RESPONSE, circuit_id, req_id);
+        let body = MessageBody::Response {
+            body: ResponseBody::Blob {
+                hash,
+                size,
+                start,
+                data,
+            },
+        };
+
+        Message::new(header, body)
+    }
 }
 
 /// Print a message with byte arrays formatted as hex strings.
@@ -207,6 +308,15 @@ impl fmt::Display for Message {
                 "ChannelListResponse {{ {}, {} }}",
                 &self.header, &self.body
             ),
+            #[cfg(feature = "blobs")]
+            &BLOB_REQUEST => write!(f, "BlobRequest {{ {}, {} }}", &self.header, &self.body),
+            #[cfg(feature = "blobs")]
+            &BLOB_RESPONSE => write!(f, "BlobResponse {{ {}, {} }}", &self.header, &self.body),
+            &CAPABILITY_REQUEST => write!(
+                f,
+                "CapabilityRequest {{ {}, {} }}",
+                &self.header, &self.body
+            ),
             _ => write!(f, "Unknown {{ {}, {} }}", &self.header, &self.body),
         }
     }
@@ -361,6 +471,33 @@ pub enum RequestBody {
         /// (after skipping the first `offset` entries).
         limit: u64,
     },
+    /// Request a chunk of the binary blob with the given hash.
+    ///
+    /// Experimental: this message type is not part of the cable
+    /// specification.
+    #[cfg(feature = "blobs")]
+    Blob {
+        /// BLAKE2b hash of the blob.
+        hash: Hash,
+        /// Offset of the first byte of the chunk within the blob.
+        start: u64,
+        /// Maximum number of bytes to return (`0` for as many as the
+        /// responder sends in one response).
+        max_len: u64,
+    },
+    /// Announce the protocol version and the message and post types
+    /// understood by the sender. No response is expected.
+    ///
+    /// Experimental: this message type is not part of the cable
+    /// specification.
+    Capabilities {
+        /// Version of the cable specification implemented by the sender.
+        version: u64,
+        /// Message types understood by the sender.
+        msg_types: Vec<u64>,
+        /// Post types understood by the sender.
+        post_types: Vec<u64>,
+    },
 }
 
 /// Print a message request body with byte arrays formatted as hex strings.
@@ -393,6 +530,31 @@ impl fmt::Display for RequestBody {
             RequestBody::ChannelList { skip, limit } => {
                 write!(f, "offset: {}, limit: {}", skip, limit)
             }
+            #[cfg(feature = "blobs")]
+            RequestBody::Blob {
+                hash,
+                start,
+                max_len,
+            } => {
+                write!(
+                    f,
+                    "hash: {:?}, start: {}, max_len: {}",
+                    hex::encode(hash),
+                    start,
+                    max_len
+                )
+            }
+            RequestBody::Capabilities {
+                version,
+                msg_types,
+                post_types,
+            } => {
+                write!(
+                    f,
+                    "version: {}, msg_types: {:?}, post_types: {:?}",
+                    version, msg_types, post_types
+                )
+            }
         }
     }
 }
@@ -421,6 +583,22 @@ pub enum ResponseBody {
         /// A list of channels, with each one including the length and name of a channel.
         channels: Vec<Channel>,
     },
+    /// Respond with a chunk of a binary blob in response to a Blob Request.
+    ///
+    /// Experimental: this message type is not part of the cable
+    /// specification.
+    #[cfg(feature = "blobs")]
+    Blob {
+        /// BLAKE2b hash of the blob.
+        hash: Hash,
+        /// Total size of the blob in bytes (`0` if the responder does not
+        /// hold the blob).
+        size: u64,
+        /// Offset of the first byte of the chunk within the blob.
+        start: u64,
+        /// The bytes of the chunk.
+        data: Vec<u8>,
+    },
 }
 
 /// Print a message response body with byte arrays formatted as hex strings.
@@ -438,6 +616,22 @@ impl fmt::Display for ResponseBody {
             ResponseBody::ChannelList { channels } => {
                 write!(f, "channels: {:?}", channels)
             }
+            #[cfg(feature = "blobs")]
+            ResponseBody::Blob {
+                hash,
+                size,
+                start,
+                data,
+            } => {
+                write!(
+                    f,
+                    "hash: {:?}, size: {}, start: {}, data: {:?}",
+                    hex::encode(hash),
+                    size,
+                    start,
+                    hex::encode(data)
+                )
+            }
         }
     }
 }
@@ -483,6 +677,27 @@ impl CountBytes for Message {
                 RequestBody::ChannelList { skip, limit } => {
                     varint::length(*ttl as u64) + varint::length(*skip) + varint::length(*limit)
                 }
+                #[cfg(feature = "blobs")]
+                RequestBody::Blob {
+                    hash,
+                    start,
+                    max_len,
+                } => {
+                    varint::length(*ttl as u64)
+                        + hash.len()
+                        + varint::length(*start)
+                        + varint::length(*max_len)
+                }
+                RequestBody::Capabilities {
+                    version,
+                    msg_types,
+                    post_types,
+                } => {
+                    varint::length(*ttl as u64)
+                        + varint::length(*version)
+                        + varint_list_length(msg_types)
+                        + varint_list_length(post_types)
+                }
             },
             MessageBody::Response { body } => match body {
                 ResponseBody::Hash { hashes } => {
@@ -498,6 +713,19 @@ impl CountBytes for Message {
                         sum + varint::length(channel.len() as u64) + channel.len()
                     }) + varint::length(0)
                 }
+                #[cfg(feature = "blobs")]
+                ResponseBody::Blob {
+                    hash,
+                    size,
+                    start,
+                    data,
+                } => {
+                    hash.len()
+                        + varint::length(*size)
+                        + varint::length(*start)
+                        + varint::length(data.len() as u64)
+                        + data.len()
+                }
             },
             MessageBody::Unrecognized { .. } => 0,
         };
@@ -524,6 +752,8 @@ impl ToBytes for Message {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut buf = vec![0; self.count_bytes()];
         self.write_bytes(&mut buf)?;
+        #[cfg(feature = "record-vectors")]
+        crate::record::record_message(self, &buf);
         Ok(buf)
     }
 
@@ -606,6 +836,30 @@ impl ToBytes for Message {
                     offset += varint::encode(*skip, &mut buf[offset..])?;
                     offset += varint::encode(*limit, &mut buf[offset..])?;
                 }
+                #[cfg(feature = "blobs")]
+                RequestBody::Blob {
+                    hash,
+                    start,
+                    max_len,
+                } => {
+                    offset += varint::encode(*ttl as u64, &mut buf[offset..])?;
+
+                    buf[offset..offset + hash.len()].copy_from_slice(hash);
+                    offset += hash.len();
+
+                    offset += varint::encode(*start, &mut buf[offset..])?;
+                    offset += varint::encode(*max_len, &mut buf[offset..])?;
+                }
+                RequestBody::Capabilities {
+                    version,
+                    msg_types,
+                    post_types,
+                } => {
+                    offset += varint::encode(*ttl as u64, &mut buf[offset..])?;
+                    offset += varint::encode(*version, &mut buf[offset..])?;
+                    offset += write_varint_list(msg_types, &mut buf[offset..])?;
+                    offset += write_varint_list(post_types, &mut buf[offset..])?;
+                }
             },
             MessageBody::Response { body, .. } => match body {
                 ResponseBody::Hash { hashes } => {
@@ -658,6 +912,23 @@ impl ToBytes for Message {
                     // channel_len to 0.
                     offset += varint::encode(0, &mut buf[offset..])?;
                 }
+                #[cfg(feature = "blobs")]
+                ResponseBody::Blob {
+                    hash,
+                    size,
+                    start,
+                    data,
+                } => {
+                    buf[offset..offset + hash.len()].copy_from_slice(hash);
+                    offset += hash.len();
+
+                    offset += varint::encode(*size, &mut buf[offset..])?;
+                    offset += varint::encode(*start, &mut buf[offset..])?;
+
+                    offset += varint::encode(data.len() as u64, &mut buf[offset..])?;
+                    buf[offset..offset + data.len()].copy_from_slice(data);
+                    offset += data.len();
+                }
             },
             MessageBody::Unrecognized { msg_type } => {
                 return CableErrorKind::MessageWriteUnrecognizedType {
@@ -671,13 +942,75 @@ impl ToBytes for Message {
     }
 }
 
+/// Return the number of bytes of the given list of varints, prefixed with
+/// the number of varints.
+fn varint_list_length(values: &[u64]) -> usize {
+    values
+        .iter()
+        .fold(varint::length(values.len() as u64), |sum, value| {
+            sum + varint::length(*value)
+        })
+}
+
+/// Write the given list of varints to the buffer, prefixed with the number
+/// of varints, returning the number of bytes written.
+fn write_varint_list(values: &[u64], buf: &mut [u8]) -> Result<usize, Error> {
+    let mut offset = varint::encode(values.len() as u64, buf)?;
+    for value in values {
+        offset += varint::encode(*value, &mut buf[offset..])?;
+    }
+
+    Ok(offset)
+}
+
+/// Read a list of varints prefixed with the number of varints from the
+/// buffer, returning the number of bytes read and the varints.
+fn read_varint_list(buf: &[u8]) -> Result<(usize, Vec<u64>), Error> {
+    let (mut offset, count) = varint::decode(buf)?;
+    let mut values = Vec::new();
+    for _ in 0..count {
+        let (s, value) = varint::decode(&buf[offset..])?;
+        offset += s;
+        values.push(value);
+    }
+
+    Ok((offset, values))
+}
+
+/// Convert the TTL read from a request to a `u8`, clamping it to the maximum
+/// of the given limits or, if `strict` is set, rejecting a greater TTL.
+fn read_ttl(ttl: u64, strict: bool, limits: &Limits) -> Result<u8, Error> {
+    if strict {
+        limits.check_ttl(ttl)?;
+    }
+
+    Ok(ttl.min(limits.max_ttl as u64) as u8)
+}
+
 impl FromBytes for Message {
     /// Read bytes from the given buffer (byte array), returning the total
     /// number of bytes and the decoded `Message` type.
+    ///
+    /// The TTL of a request is clamped to the maximum of 16.
     fn from_bytes(buf: &[u8]) -> Result<(usize, Self), Error> {
+        Self::decode(buf, false, None)
+    }
+}
+
+impl Message {
+    /// Decode a message from the given buffer, rejecting a request TTL above
+    /// the maximum if `strict` is set.
+    ///
+    /// If limits are given, a message exceeding them is rejected; otherwise
+    /// only the TTL is bounded, by the default maximum.
+    fn decode(buf: &[u8], strict: bool, limits: Option<&Limits>) -> Result<(usize, Self), Error> {
         if buf.is_empty() {
             return CableErrorKind::MessageEmpty {}.raise();
         }
+        if let Some(limits) = limits {
+            limits.check_message_size(buf.len())?;
+        }
+        let ttl_limits = limits.unwrap_or(&Limits::DEFAULT);
 
         let mut offset = 0;
 
@@ -694,12 +1027,12 @@ impl FromBytes for Message {
 
         // Read the circuit ID bytes from the buffer and increment the offset.
         let mut circuit_id = [0; 4];
-        circuit_id.copy_from_slice(&buf[offset..offset + 4]);
+        circuit_id.copy_from_slice(read_bytes(buf, offset, 4)?);
         offset += 4;
 
         // Read the request ID bytes from the buffer and increment the offset.
         let mut req_id = [0; 4];
-        req_id.copy_from_slice(&buf[offset..offset + 4]);
+        req_id.copy_from_slice(read_bytes(buf, offset, 4)?);
         offset += 4;
 
         // Construct the message header.
@@ -718,7 +1051,7 @@ impl FromBytes for Message {
                 let (s, num_hashes) = varint::decode(&buf[offset..])?;
                 offset += s;
 
-                let mut hashes = Vec::with_capacity(num_hashes as usize);
+                let mut hashes = Vec::with_capacity((num_hashes as usize).min(buf.len() / 32));
 
                 // Iterate over the hashes, reading the bytes from the buffer
                 // and incrementing the offset for each one.
@@ -728,7 +1061,7 @@ impl FromBytes for Message {
                     }
 
                     let mut hash = [0; 32];
-                    hash.copy_from_slice(&buf[offset..offset + 32]);
+                    hash.copy_from_slice(read_bytes(buf, offset, 32)?);
                     offset += 32;
 
                     hashes.push(hash);
@@ -758,7 +1091,7 @@ impl FromBytes for Message {
                     }
 
                     // Read the post bytes and increment the offset.
-                    let post = buf[offset..offset + post_len as usize].to_vec();
+                    let post = read_bytes(buf, offset, post_len as usize)?.to_vec();
                     offset += post_len as usize;
 
                     posts.push(post);
@@ -778,7 +1111,7 @@ impl FromBytes for Message {
                 let (s, num_hashes) = varint::decode(&buf[offset..])?;
                 offset += s;
 
-                let mut hashes = Vec::with_capacity(num_hashes as usize);
+                let mut hashes = Vec::with_capacity((num_hashes as usize).min(buf.len() / 32));
 
                 // Iterate over the hashes, reading the bytes from the buffer
                 // and incrementing the offset for each one.
@@ -788,7 +1121,7 @@ impl FromBytes for Message {
                     }
 
                     let mut hash = [0; 32];
-                    hash.copy_from_slice(&buf[offset..offset + 32]);
+                    hash.copy_from_slice(read_bytes(buf, offset, 32)?);
                     offset += 32;
 
                     hashes.push(hash);
@@ -798,7 +1131,7 @@ impl FromBytes for Message {
                 let req_body = RequestBody::Post { hashes };
 
                 MessageBody::Request {
-                    ttl: ttl as u8,
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
                     body: req_body,
                 }
             }
@@ -810,14 +1143,14 @@ impl FromBytes for Message {
                 // Read the cancel request ID bytes from the buffer and
                 // increment the offset.
                 let mut cancel_id = [0; 4];
-                cancel_id.copy_from_slice(&buf[offset..offset + 4]);
+                cancel_id.copy_from_slice(read_bytes(buf, offset, 4)?);
                 offset += 4;
 
                 // Construct a new request body.
                 let req_body = RequestBody::Cancel { cancel_id };
 
                 MessageBody::Request {
-                    ttl: ttl as u8,
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
                     body: req_body,
                 }
             }
@@ -832,7 +1165,7 @@ impl FromBytes for Message {
 
                 // Read the channel bytes and increment the offset.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 offset += channel_len as usize;
 
                 // Read the time start byte and increment the offset.
@@ -855,7 +1188,7 @@ impl FromBytes for Message {
                     limit,
                 };
                 MessageBody::Request {
-                    ttl: ttl as u8,
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
                     body: req_body,
                 }
             }
@@ -870,7 +1203,7 @@ impl FromBytes for Message {
 
                 // Read the channel bytes and increment the offset.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 offset += channel_len as usize;
 
                 // Read the future byte and increment the offset.
@@ -881,7 +1214,7 @@ impl FromBytes for Message {
                 let req_body = RequestBody::ChannelState { channel, future };
 
                 MessageBody::Request {
-                    ttl: ttl as u8,
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
                     body: req_body,
                 }
             }
@@ -902,7 +1235,7 @@ impl FromBytes for Message {
                 let req_body = RequestBody::ChannelList { skip, limit };
 
                 MessageBody::Request {
-                    ttl: ttl as u8,
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
                     body: req_body,
                 }
             }
@@ -926,7 +1259,7 @@ impl FromBytes for Message {
 
                     // Read the key bytes and increment the offset.
                     let channel =
-                        String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                        String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                     offset += channel_len as usize;
 
                     channels.push(channel);
@@ -937,10 +1270,108 @@ impl FromBytes for Message {
 
                 MessageBody::Response { body: res_body }
             }
+            #[cfg(feature = "blobs")]
+            BLOB_REQUEST => {
+                // Read the TTL byte and increment the offset.
+                let (s, ttl) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the blob hash bytes and increment the offset.
+                let mut hash = [0; 32];
+                hash.copy_from_slice(read_bytes(buf, offset, 32)?);
+                offset += 32;
+
+                // Read the start byte and increment the offset.
+                let (s, start) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the maximum length byte and increment the offset.
+                let (s, max_len) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Construct a new request body.
+                let req_body = RequestBody::Blob {
+                    hash,
+                    start,
+                    max_len,
+                };
+
+                MessageBody::Request {
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
+                    body: req_body,
+                }
+            }
+            #[cfg(feature = "blobs")]
+            BLOB_RESPONSE => {
+                // Read the blob hash bytes and increment the offset.
+                let mut hash = [0; 32];
+                hash.copy_from_slice(read_bytes(buf, offset, 32)?);
+                offset += 32;
+
+                // Read the size byte and increment the offset.
+                let (s, size) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the start byte and increment the offset.
+                let (s, start) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the data length byte and increment the offset.
+                let (s, data_len) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the data bytes and increment the offset.
+                let data = read_bytes(buf, offset, data_len as usize)?.to_vec();
+                offset += data_len as usize;
+
+                // Construct a new response body.
+                let res_body = ResponseBody::Blob {
+                    hash,
+                    size,
+                    start,
+                    data,
+                };
+
+                MessageBody::Response { body: res_body }
+            }
+            CAPABILITY_REQUEST => {
+                // Read the TTL byte and increment the offset.
+                let (s, ttl) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the version byte and increment the offset.
+                let (s, version) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the message types and increment the offset.
+                let (s, msg_types) = read_varint_list(&buf[offset..])?;
+                offset += s;
+
+                // Read the post types and increment the offset.
+                let (s, post_types) = read_varint_list(&buf[offset..])?;
+                offset += s;
+
+                // Construct a new request body.
+                let req_body = RequestBody::Capabilities {
+                    version,
+                    msg_types,
+                    post_types,
+                };
+
+                MessageBody::Request {
+                    ttl: read_ttl(ttl, strict, ttl_limits)?,
+                    body: req_body,
+                }
+            }
             msg_type => MessageBody::Unrecognized { msg_type },
         };
 
-        Ok((offset, Message { header, body }))
+        let msg = Message { header, body };
+        if let Some(limits) = limits {
+            limits.check_message(&msg)?;
+        }
+
+        Ok((offset, msg))
     }
 }
 
@@ -951,7 +1382,7 @@ mod test {
     use super::{
         Error, FromBytes, Hash, Message, MessageBody, MessageHeader, Payload, RequestBody,
         ResponseBody, ToBytes, CANCEL_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE,
-        CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, HASH_RESPONSE, POST_REQUEST,
+        CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, HASH_RESPONSE, MAX_TTL, POST_REQUEST,
         POST_RESPONSE,
     };
 
@@ -987,7 +1418,6 @@ mod test {
 
     #[test]
     fn post_request_to_bytes() -> Result<(), Error> {
-        // TODO: Use `sodiumoxide::hex::decode` instead.
         let req_id = <[u8; 4]>::from_hex(REQ_ID)?;
         let hashes: Vec<Hash> = vec![
             <[u8; 32]>::from_hex(HASH_1)?,
@@ -1574,4 +2004,137 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn bytes_to_truncated_message_is_error() -> Result<(), Error> {
+        for msg_hex in [
+            POST_REQUEST_HEX_BINARY,
+            CANCEL_REQUEST_HEX_BINARY,
+            CHANNEL_TIME_RANGE_REQUEST_HEX_BINARY,
+            CHANNEL_STATE_REQUEST_HEX_BINARY,
+            CHANNEL_LIST_REQUEST_HEX_BINARY,
+            HASH_RESPONSE_HEX_BINARY,
+            POST_RESPONSE_HEX_BINARY,
+            CHANNEL_LIST_RESPONSE_HEX_BINARY,
+        ] {
+            let msg_bytes = <Vec<u8>>::from_hex(msg_hex)?;
+
+            // Every truncation of the message must fail to decode, rather
+            // than panic.
+            for len in 0..msg_bytes.len() {
+                assert!(Message::from_bytes(&msg_bytes[..len]).is_err());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn excessive_ttl_is_clamped_or_rejected() -> Result<(), Error> {
+        // A channel list request with a TTL of 17 and another with a TTL of
+        // 300, encoded as a two-byte varint.
+        for msg_hex in ["0c060000000004baaffb110014", "0d060000000004baaffbac020014"] {
+            let msg_bytes = <Vec<u8>>::from_hex(msg_hex)?;
+
+            let (_, msg) = Message::from_bytes(&msg_bytes)?;
+            match msg.body {
+                MessageBody::Request { ttl, .. } => assert_eq!(ttl, MAX_TTL),
+                _ => panic!("Incorrect message body type: expected request"),
+            }
+
+            assert!(Message::from_bytes_strict(&msg_bytes).is_err());
+        }
+
+        // A TTL of 16 is accepted in strict mode.
+        let msg_bytes = <Vec<u8>>::from_hex("0c060000000004baaffb100014")?;
+        assert!(Message::from_bytes_strict(&msg_bytes).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn decrement_ttl_at_boundaries() {
+        let req_id = <[u8; 4]>::from_hex(REQ_ID).unwrap();
+
+        for (ttl, expected) in [(0, 0), (1, 0), (2, 1), (16, 15), (17, 15), (255, 15)] {
+            let mut msg = Message::channel_list_request(CIRCUIT_ID, req_id, ttl, 0, 0);
+            msg.decrement_ttl();
+
+            match msg.body {
+                MessageBody::Request { ttl, .. } => assert_eq!(ttl, expected),
+                _ => panic!("Incorrect message body type: expected request"),
+            }
+        }
+    }
+
+    #[cfg(feature = "blobs")]
+    #[test]
+    fn blob_messages_round_trip() -> Result<(), Error> {
+        use super::{CountBytes, BLOB_REQUEST, BLOB_RESPONSE};
+
+        let req_id = <[u8; 4]>::from_hex(REQ_ID)?;
+        let hash = <[u8; 32]>::from_hex(HASH_1)?;
+
+        let request = Message::blob_request(CIRCUIT_ID, req_id, TTL, hash, 300, 16_384);
+        let response =
+            Message::blob_response(CIRCUIT_ID, req_id, hash, 1_000, 300, vec![0xca, 0xb1, 0xe5]);
+
+        for (msg, msg_type) in [(request, BLOB_REQUEST), (response, BLOB_RESPONSE)] {
+            let msg_bytes = msg.to_bytes()?;
+            assert_eq!(msg_bytes.len(), msg.count_bytes());
+
+            let (len, decoded) = Message::from_bytes(&msg_bytes)?;
+            assert_eq!(len, msg_bytes.len());
+            assert_eq!(decoded.message_type(), msg_type);
+            assert_eq!(decoded.to_bytes()?, msg_bytes);
+
+            // Every truncation of the message must fail to decode.
+            for len in 0..msg_bytes.len() {
+                assert!(Message::from_bytes(&msg_bytes[..len]).is_err());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn capability_request_round_trip() -> Result<(), Error> {
+        use super::{CountBytes, RequestBody, CAPABILITY_REQUEST};
+
+        let req_id = <[u8; 4]>::from_hex(REQ_ID)?;
+
+        let msg =
+            Message::capability_request(CIRCUIT_ID, req_id, 0, 1, vec![0, 2, 66], vec![0, 300]);
+        let msg_bytes = msg.to_bytes()?;
+        assert_eq!(msg_bytes.len(), msg.count_bytes());
+
+        let (len, decoded) = Message::from_bytes(&msg_bytes)?;
+        assert_eq!(len, msg_bytes.len());
+        assert_eq!(decoded.message_type(), CAPABILITY_REQUEST);
+        assert_eq!(decoded.to_bytes()?, msg_bytes);
+
+        if let MessageBody::Request {
+            body:
+                RequestBody::Capabilities {
+                    version,
+                    msg_types,
+                    post_types,
+                },
+            ..
+        } = decoded.body
+        {
+            assert_eq!(version, 1);
+            assert_eq!(msg_types, vec![0, 2, 66]);
+            assert_eq!(post_types, vec![0, 300]);
+        } else {
+            panic!("expected a capability request");
+        }
+
+        // Every truncation of the message must fail to decode.
+        for len in 0..msg_bytes.len() {
+            assert!(Message::from_bytes(&msg_bytes[..len]).is_err());
+        }
+
+        Ok(())
+    }
 }
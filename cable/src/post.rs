@@ -10,18 +10,19 @@
 use std::fmt;
 
 use desert::{varint, CountBytes, FromBytes, ToBytes};
-use sodiumoxide::{
-    crypto::{
-        generichash, sign,
-        sign::{PublicKey, SecretKey, Signature},
-    },
-    hex,
-};
 
+#[cfg(feature = "archive-posts")]
+use crate::constants::ARCHIVE_POST;
+#[cfg(feature = "reactions")]
+use crate::constants::REACTION_POST;
 use crate::{
     constants::{DELETE_POST, INFO_POST, JOIN_POST, LEAVE_POST, TEXT_POST, TOPIC_POST},
+    crypto::{self, HashAlgorithm},
     error::{CableErrorKind, Error},
-    validation, Channel, Hash, Text, Topic, UserInfo,
+    limits::Limits,
+    read_bytes,
+    signer::Signer,
+    Avatar, Channel, Hash, Text, Topic, UserInfo,
 };
 
 #[derive(Clone, Debug)]
@@ -114,6 +115,27 @@ pub enum PostBody {
         /// Channel name (UTF-8).
         channel: Channel,
     },
+    /// React to a post, typically with an emoji.
+    ///
+    /// Experimental: this post type is not part of the cable specification.
+    #[cfg(feature = "reactions")]
+    Reaction {
+        /// Channel name (UTF-8).
+        channel: Channel,
+        /// Hash of the post reacted to.
+        target: Hash,
+        /// The reaction (UTF-8).
+        reaction: String,
+    },
+    /// Mark a channel as archived, so that peers may hide it. The channel is
+    /// unarchived by deleting the post.
+    ///
+    /// Experimental: this post type is not part of the cable specification.
+    #[cfg(feature = "archive-posts")]
+    Archive {
+        /// Channel name (UTF-8).
+        channel: Channel,
+    },
     /// A post type which is not recognised as part of the cable specification.
     Unrecognized { post_type: u64 },
 }
@@ -141,6 +163,24 @@ impl fmt::Display for PostBody {
             PostBody::Leave { channel } => {
                 write!(f, "channel: {:?}", channel)
             }
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel,
+                target,
+                reaction,
+            } => {
+                write!(
+                    f,
+                    "channel: {:?}, target: {:?}, reaction: {:?}",
+                    channel,
+                    hex::encode(target),
+                    reaction
+                )
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => {
+                write!(f, "channel: {:?}", channel)
+            }
             PostBody::Unrecognized { post_type: _ } => {
                 write!(f, "post_type: unrecognized")
             }
@@ -231,6 +271,40 @@ impl Post {
         Post { header, body }
     }
 
+    /// Construct an unsigned reaction `Post` with the given parameters.
+    #[cfg(feature = "reactions")]
+    pub fn reaction(
+        public_key: [u8; 32],
+        links: Vec<Hash>,
+        timestamp: u64,
+        channel: Channel,
+        target: Hash,
+        reaction: String,
+    ) -> Self {
+        let header = PostHeader::new(public_key, [0; 64], links, REACTION_POST, timestamp);
+        let body = PostBody::Reaction {
+            channel,
+            target,
+            reaction,
+        };
+
+        Post { header, body }
+    }
+
+    /// Construct an unsigned archive `Post` with the given parameters.
+    #[cfg(feature = "archive-posts")]
+    pub fn archive(
+        public_key: [u8; 32],
+        links: Vec<Hash>,
+        timestamp: u64,
+        channel: Channel,
+    ) -> Self {
+        let header = PostHeader::new(public_key, [0; 64], links, ARCHIVE_POST, timestamp);
+        let body = PostBody::Archive { channel };
+
+        Post { header, body }
+    }
+
     /// Return the channel name associated with a post.
     pub fn get_channel(&self) -> Option<&Channel> {
         match &self.body {
@@ -240,6 +314,10 @@ impl Post {
             PostBody::Topic { channel, .. } => Some(channel),
             PostBody::Join { channel, .. } => Some(channel),
             PostBody::Leave { channel, .. } => Some(channel),
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction { channel, .. } => Some(channel),
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => Some(channel),
             PostBody::Unrecognized { .. } => None,
         }
     }
@@ -260,16 +338,18 @@ impl Post {
 
     /// Return the hash of the post.
     pub fn hash(&self) -> Result<Hash, Error> {
+        self.hash_with(HashAlgorithm::default())
+    }
+
+    /// Return the hash of the post computed with the given algorithm.
+    pub fn hash_with(&self, algorithm: HashAlgorithm) -> Result<Hash, Error> {
         let buf = self.to_bytes()?;
 
         // Compute a hash for the post.
-        let digest = if let Ok(hash) = generichash::hash(&buf, Some(32), None) {
-            hash
-        } else {
-            return CableErrorKind::PostHashingFailed {}.raise();
-        };
-
-        Ok(digest.as_ref().try_into()?)
+        match algorithm.hash(&buf) {
+            Some(hash) => Ok(hash),
+            None => CableErrorKind::PostHashingFailed {}.raise(),
+        }
     }
 
     /// Check if the post has a signature.
@@ -292,26 +372,55 @@ impl Post {
             PostBody::Topic { .. } => TOPIC_POST,
             PostBody::Join { .. } => JOIN_POST,
             PostBody::Leave { .. } => LEAVE_POST,
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction { .. } => REACTION_POST,
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { .. } => ARCHIVE_POST,
             PostBody::Unrecognized { post_type } => *post_type,
         }
     }
 
+    /// Return the bytes of the encoded post which are covered by its
+    /// signature: every field following the public key and the signature.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = self.to_bytes()?;
+        buf.drain(..32 + 64);
+
+        Ok(buf)
+    }
+
     /// Sign a post using the given secret key.
     pub fn sign(&mut self, secret_key: &[u8; 64]) -> Result<(), Error> {
-        let buf = self.to_bytes()?;
+        let buf = self.signing_bytes()?;
 
-        // Decode the secret key from the byte slice.
-        let sk = if let Some(key) = SecretKey::from_slice(secret_key) {
-            key
-        } else {
-            return CableErrorKind::NoneError {
+        // Sign the post bytes and update the signature field of the post header.
+        match crypto::sign(&buf, secret_key) {
+            Some(signature) => {
+                self.header.signature = signature;
+                Ok(())
+            }
+            None => CableErrorKind::NoneError {
                 context: "failed to decode secret key from slice".to_string(),
             }
+            .raise(),
+        }
+    }
+
+    /// Sign a post using the given signer, which need not expose its secret
+    /// key.
+    ///
+    /// An error is returned if the public key of the signer is not the
+    /// public key which authored the post.
+    pub async fn sign_with<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<(), Error> {
+        if signer.public_key() != self.header.public_key {
+            return CableErrorKind::PostSigningFailed {
+                context: "the public key of the signer did not author the post".to_string(),
+            }
             .raise();
-        };
+        }
 
-        // Sign the post bytes and update the signature field of the post header.
-        self.header.signature = sign::sign_detached(&buf[32 + 64..], &sk).to_bytes();
+        let buf = self.signing_bytes()?;
+        self.header.signature = signer.sign(&buf).await?;
 
         Ok(())
     }
@@ -324,13 +433,7 @@ impl Post {
             return false;
         }
 
-        let public_key = PublicKey::from_slice(&buf[0..32]);
-        let signature = Signature::from_bytes(&buf[32..32 + 64]);
-
-        match (public_key, signature) {
-            (Some(pk), Ok(sig)) => sign::verify_detached(&sig, &buf[32 + 64..], &pk),
-            _ => false,
-        }
+        crypto::verify(&buf[32 + 64..], &buf[32..32 + 64], &buf[0..32])
     }
 }
 
@@ -344,6 +447,10 @@ impl fmt::Display for Post {
             3 => write!(f, "post/topic {{ {}, {} }}", &self.header, &self.body),
             4 => write!(f, "post/join {{ {}, {} }}", &self.header, &self.body),
             5 => write!(f, "post/leave {{ {}, {} }}", &self.header, &self.body),
+            #[cfg(feature = "reactions")]
+            &REACTION_POST => write!(f, "post/reaction {{ {}, {} }}", &self.header, &self.body),
+            #[cfg(feature = "archive-posts")]
+            &ARCHIVE_POST => write!(f, "post/archive {{ {}, {} }}", &self.header, &self.body),
             _ => write!(f, "post/unknown {{ {}, {} }}", &self.header, &self.body),
         }
     }
@@ -354,6 +461,8 @@ impl ToBytes for Post {
     fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut buf = vec![0; self.count_bytes()];
         self.write_bytes(&mut buf)?;
+        #[cfg(feature = "record-vectors")]
+        crate::record::record_post(self, &buf);
 
         Ok(buf)
     }
@@ -457,6 +566,29 @@ impl ToBytes for Post {
                 buf[offset..offset + channel.len()].copy_from_slice(channel.as_bytes());
                 offset += channel.len();
             }
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel,
+                target,
+                reaction,
+            } => {
+                offset += varint::encode(channel.len() as u64, &mut buf[offset..])?;
+                buf[offset..offset + channel.len()].copy_from_slice(channel.as_bytes());
+                offset += channel.len();
+
+                buf[offset..offset + target.len()].copy_from_slice(target);
+                offset += target.len();
+
+                offset += varint::encode(reaction.len() as u64, &mut buf[offset..])?;
+                buf[offset..offset + reaction.len()].copy_from_slice(reaction.as_bytes());
+                offset += reaction.len();
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => {
+                offset += varint::encode(channel.len() as u64, &mut buf[offset..])?;
+                buf[offset..offset + channel.len()].copy_from_slice(channel.as_bytes());
+                offset += channel.len();
+            }
             PostBody::Unrecognized { post_type } => {
                 return CableErrorKind::PostWriteUnrecognizedType {
                     post_type: *post_type,
@@ -473,18 +605,27 @@ impl FromBytes for Post {
     /// Read bytes from the given buffer (byte array), returning the total
     /// number of bytes and the decoded `Post` type.
     fn from_bytes(buf: &[u8]) -> Result<(usize, Self), Error> {
+        Self::from_bytes_with_limits(buf, &Limits::DEFAULT)
+    }
+}
+
+impl Post {
+    /// Read bytes from the given buffer (byte array), returning the total
+    /// number of bytes and the decoded `Post` type, or an error if a field
+    /// of the post exceeds the given limits.
+    pub fn from_bytes_with_limits(buf: &[u8], limits: &Limits) -> Result<(usize, Self), Error> {
         let mut offset = 0;
 
         /* POST HEADER BYTES */
 
         // Read the public key bytes from the buffer and increment the offset.
         let mut public_key = [0; 32];
-        public_key.copy_from_slice(&buf[offset..offset + 32]);
+        public_key.copy_from_slice(read_bytes(buf, offset, 32)?);
         offset += 32;
 
         // Read the signature bytes from the buffer and increment the offset.
         let mut signature = [0; 64];
-        signature.copy_from_slice(&buf[offset..offset + 64]);
+        signature.copy_from_slice(read_bytes(buf, offset, 64)?);
         offset += 64;
 
         // Read the number of links byte from the buffer and increment the offset.
@@ -493,7 +634,7 @@ impl FromBytes for Post {
         let (s, num_links) = varint::decode(&buf[offset..])?;
         offset += s;
 
-        let mut links = Vec::with_capacity(num_links as usize);
+        let mut links = Vec::with_capacity((num_links as usize).min(buf.len() / 32));
 
         // Iterate over the links (hashes), reading the bytes from the buffer
         // and incrementing the offset for each one.
@@ -503,7 +644,7 @@ impl FromBytes for Post {
             }
 
             let mut link = [0; 32];
-            link.copy_from_slice(&buf[offset..offset + 32]);
+            link.copy_from_slice(read_bytes(buf, offset, 32)?);
             offset += 32;
 
             links.push(link);
@@ -537,9 +678,9 @@ impl FromBytes for Post {
 
                 // Read the channel bytes.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 // Validate the length of the channel name.
-                validation::validate_channel(&channel)?;
+                limits.check_channel(&channel)?;
                 // Increment the offset.
                 offset += channel_len as usize;
 
@@ -548,9 +689,9 @@ impl FromBytes for Post {
                 offset += s;
 
                 // Read the text bytes and increment the offset.
-                let text = String::from_utf8(buf[offset..offset + text_len as usize].to_vec())?;
+                let text = String::from_utf8(read_bytes(buf, offset, text_len as usize)?.to_vec())?;
                 // Validate the byte length of the text.
-                validation::validate_text(&text)?;
+                limits.check_text(&text)?;
                 offset += text_len as usize;
 
                 PostBody::Text { channel, text }
@@ -560,7 +701,7 @@ impl FromBytes for Post {
                 let (s, num_hashes) = varint::decode(&buf[offset..])?;
                 offset += s;
 
-                let mut hashes = Vec::with_capacity(num_hashes as usize);
+                let mut hashes = Vec::with_capacity((num_hashes as usize).min(buf.len() / 32));
 
                 // Iterate over the hashes, reading the bytes from the buffer
                 // and incrementing the offset for each one.
@@ -570,7 +711,7 @@ impl FromBytes for Post {
                     }
 
                     let mut hash = [0; 32];
-                    hash.copy_from_slice(&buf[offset..offset + 32]);
+                    hash.copy_from_slice(read_bytes(buf, offset, 32)?);
                     offset += 32;
 
                     hashes.push(hash);
@@ -597,7 +738,8 @@ impl FromBytes for Post {
                     }
 
                     // Read the key bytes and increment the offset.
-                    let key = String::from_utf8(buf[offset..offset + key_len as usize].to_vec())?;
+                    let key =
+                        String::from_utf8(read_bytes(buf, offset, key_len as usize)?.to_vec())?;
                     offset += key_len as usize;
 
                     // Read the val length byte and increment the offset.
@@ -605,12 +747,16 @@ impl FromBytes for Post {
                     offset += s;
 
                     // Read the val bytes and increment the offset.
-                    let val = String::from_utf8(buf[offset..offset + val_len as usize].to_vec())?;
+                    let val =
+                        String::from_utf8(read_bytes(buf, offset, val_len as usize)?.to_vec())?;
                     offset += val_len as usize;
 
                     let key_val = if key == "name" {
-                        // This method also performs validation.
-                        UserInfo::name(val)?
+                        limits.check_username(&val)?;
+                        UserInfo::new(key, val)
+                    } else if key == "avatar" {
+                        limits.check_avatar(&Avatar::parse(&val)?)?;
+                        UserInfo::new(key, val)
                     } else {
                         UserInfo::new(key, val)
                     };
@@ -627,9 +773,9 @@ impl FromBytes for Post {
 
                 // Read the channel bytes.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 // Validate the length of the channel name.
-                validation::validate_channel(&channel)?;
+                limits.check_channel(&channel)?;
                 // Increment the offset.
                 offset += channel_len as usize;
 
@@ -638,9 +784,10 @@ impl FromBytes for Post {
                 offset += s;
 
                 // Read the topic bytes.
-                let topic = String::from_utf8(buf[offset..offset + topic_len as usize].to_vec())?;
+                let topic =
+                    String::from_utf8(read_bytes(buf, offset, topic_len as usize)?.to_vec())?;
                 // Validate the length of the topic.
-                validation::validate_topic(&topic)?;
+                limits.check_topic(&topic)?;
                 // Increment the offset.
                 offset += topic_len as usize;
 
@@ -653,9 +800,9 @@ impl FromBytes for Post {
 
                 // Read the channel bytes.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 // Validate the length of the channel name.
-                validation::validate_channel(&channel)?;
+                limits.check_channel(&channel)?;
                 // Increment the offset.
                 offset += channel_len as usize;
 
@@ -668,14 +815,67 @@ impl FromBytes for Post {
 
                 // Read the channel bytes.
                 let channel =
-                    String::from_utf8(buf[offset..offset + channel_len as usize].to_vec())?;
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
                 // Validate the length of the channel name.
-                validation::validate_channel(&channel)?;
+                limits.check_channel(&channel)?;
                 // Increment the offset.
                 offset += channel_len as usize;
 
                 PostBody::Leave { channel }
             }
+            #[cfg(feature = "reactions")]
+            REACTION_POST => {
+                // Read the channel length byte and increment the offset.
+                let (s, channel_len) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the channel bytes.
+                let channel =
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
+                // Validate the length of the channel name.
+                limits.check_channel(&channel)?;
+                // Increment the offset.
+                offset += channel_len as usize;
+
+                // Read the target hash bytes and increment the offset.
+                let mut target = [0; 32];
+                target.copy_from_slice(read_bytes(buf, offset, 32)?);
+                offset += 32;
+
+                // Read the reaction length byte and increment the offset.
+                let (s, reaction_len) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the reaction bytes.
+                let reaction =
+                    String::from_utf8(read_bytes(buf, offset, reaction_len as usize)?.to_vec())?;
+                // Validate the length of the reaction.
+                limits.check_reaction(&reaction)?;
+                // Increment the offset.
+                offset += reaction_len as usize;
+
+                PostBody::Reaction {
+                    channel,
+                    target,
+                    reaction,
+                }
+            }
+            #[cfg(feature = "archive-posts")]
+            ARCHIVE_POST => {
+                // Read the channel length byte and increment the offset.
+                let (s, channel_len) = varint::decode(&buf[offset..])?;
+                offset += s;
+
+                // Read the channel bytes.
+                let channel =
+                    String::from_utf8(read_bytes(buf, offset, channel_len as usize)?.to_vec())?;
+                // Validate the length of the channel name.
+                limits.check_channel(&channel)?;
+                // Increment the offset.
+                offset += channel_len as usize;
+
+                PostBody::Archive { channel }
+            }
             // Unrecognized.
             post_type => PostBody::Unrecognized { post_type },
         };
@@ -722,6 +922,20 @@ impl CountBytes for Post {
             }
             PostBody::Join { channel } => varint::length(channel.len() as u64) + channel.len(),
             PostBody::Leave { channel } => varint::length(channel.len() as u64) + channel.len(),
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel,
+                target,
+                reaction,
+            } => {
+                varint::length(channel.len() as u64)
+                    + channel.len()
+                    + target.len()
+                    + varint::length(reaction.len() as u64)
+                    + reaction.len()
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => varint::length(channel.len() as u64) + channel.len(),
             PostBody::Unrecognized { .. } => 0,
         };
 
@@ -1400,4 +1614,130 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn bytes_to_truncated_post_is_error() -> Result<(), Error> {
+        for post_hex in [
+            TEXT_POST_HEX_BINARY,
+            DELETE_POST_HEX_BINARY,
+            INFO_POST_HEX_BINARY,
+            TOPIC_POST_HEX_BINARY,
+            JOIN_POST_HEX_BINARY,
+            LEAVE_POST_HEX_BINARY,
+        ] {
+            let post_bytes = <Vec<u8>>::from_hex(post_hex)?;
+
+            // Every truncation of the post must fail to decode, rather than
+            // panic.
+            for len in 0..post_bytes.len() {
+                assert!(Post::from_bytes(&post_bytes[..len]).is_err());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "reactions")]
+    #[test]
+    fn reaction_post_round_trip() -> Result<(), Error> {
+        use super::{CountBytes, REACTION_POST};
+
+        let public_key = <[u8; 32]>::from_hex(PUBLIC_KEY)?;
+        let links = vec![<[u8; 32]>::from_hex(POST_HASH)?];
+        let target = <[u8; 32]>::from_hex(HASH_1)?;
+
+        let post = Post::reaction(
+            public_key,
+            links,
+            80,
+            "default".to_string(),
+            target,
+            "🦋".to_string(),
+        );
+        let post_bytes = post.to_bytes()?;
+        assert_eq!(post_bytes.len(), post.count_bytes());
+
+        let (_, decoded) = Post::from_bytes(&post_bytes)?;
+        assert_eq!(decoded.post_type(), REACTION_POST);
+        assert_eq!(decoded.get_channel(), Some(&"default".to_string()));
+        if let PostBody::Reaction {
+            target: decoded_target,
+            reaction,
+            ..
+        } = decoded.body
+        {
+            assert_eq!(decoded_target, target);
+            assert_eq!(reaction, "🦋");
+        } else {
+            panic!("Incorrect post type: expected reaction");
+        }
+
+        // Every truncation of the post must fail to decode.
+        for len in 0..post_bytes.len() {
+            assert!(Post::from_bytes(&post_bytes[..len]).is_err());
+        }
+
+        // An empty reaction is invalid.
+        let post = Post::reaction(public_key, vec![], 80, "default".into(), target, "".into());
+        assert!(Post::from_bytes(&post.to_bytes()?).is_err());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "archive-posts")]
+    #[test]
+    fn archive_post_round_trip() -> Result<(), Error> {
+        use super::{CountBytes, ARCHIVE_POST};
+
+        let public_key = <[u8; 32]>::from_hex(PUBLIC_KEY)?;
+        let links = vec![<[u8; 32]>::from_hex(POST_HASH)?];
+
+        let post = Post::archive(public_key, links, 80, "default".to_string());
+        let post_bytes = post.to_bytes()?;
+        assert_eq!(post_bytes.len(), post.count_bytes());
+
+        let (_, decoded) = Post::from_bytes(&post_bytes)?;
+        assert_eq!(decoded.post_type(), ARCHIVE_POST);
+        assert_eq!(decoded.get_channel(), Some(&"default".to_string()));
+        assert_eq!(decoded.to_string(), post.to_string());
+
+        // Every truncation of the post must fail to decode.
+        for len in 0..post_bytes.len() {
+            assert!(Post::from_bytes(&post_bytes[..len]).is_err());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn avatar_info_round_trip() -> Result<(), Error> {
+        use crate::{limits::Limits, Avatar};
+
+        let public_key = <[u8; 32]>::from_hex(PUBLIC_KEY)?;
+        let blob = Avatar::Blob(<[u8; 32]>::from_hex(POST_HASH)?);
+        let inline = Avatar::Inline(vec![0x89, 0x50, 0x4e, 0x47]);
+        assert_eq!(Avatar::parse(&blob.to_string())?, blob);
+        assert_eq!(Avatar::parse(&inline.to_string())?, inline);
+        assert!(Avatar::parse("cabler.png").is_err());
+        assert!(UserInfo::avatar(&Avatar::Inline(vec![0; 2048])).is_err());
+
+        let info = vec![UserInfo::name("cabler")?, UserInfo::avatar(&inline)?];
+        let post_bytes = Post::info(public_key, Vec::new(), 80, info.clone()).to_bytes()?;
+        let (_, decoded) = Post::from_bytes(&post_bytes)?;
+        assert!(
+            matches!(decoded.body, PostBody::Info { info: decoded_info } if decoded_info == info)
+        );
+
+        // Posts with invalid or oversized avatars fail to decode.
+        let limits = Limits {
+            max_avatar_len: 2,
+            ..Limits::DEFAULT
+        };
+        assert!(Post::from_bytes_with_limits(&post_bytes, &limits).is_err());
+        let info = vec![UserInfo::new("avatar", "cabler.png")];
+        let post_bytes = Post::info(public_key, Vec::new(), 80, info).to_bytes()?;
+        assert!(Post::from_bytes(&post_bytes).is_err());
+
+        Ok(())
+    }
 }
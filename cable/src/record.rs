@@ -0,0 +1,103 @@
+//! Recording of golden vectors from the posts and messages encoded in tests.
+//!
+//! With the `record-vectors` feature enabled and the `CABLE_RECORD_VECTORS`
+//! environment variable set to a directory, every post and message encoded
+//! with `to_bytes()` is written to that directory as a named hex fixture. The
+//! name of a fixture is the type of the post or message followed by the first
+//! bytes of the hash of its encoding (for example,
+//! `post_request-1f0c9a2b.hex`), and its contents are the hex-encoded bytes.
+//! Running the test suites with recording enabled produces a set of canonical
+//! vectors for other implementations to decode:
+//!
+//! ```sh
+//! CABLE_RECORD_VECTORS=/tmp/vectors cargo test --workspace --features cable/record-vectors
+//! ```
+//!
+//! Identical encodings map to the same fixture, so that recording is
+//! idempotent across runs and concurrent tests. Failures to write a fixture
+//! are ignored, so that recording never affects the behaviour under test.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{
+    crypto,
+    inspect::{message_type_name, post_type_name},
+    Message, Post,
+};
+
+/// The environment variable naming the directory to which fixtures are
+/// written.
+pub const RECORD_VECTORS_VAR: &str = "CABLE_RECORD_VECTORS";
+
+/// Return the directory to which fixtures are written, if recording.
+fn directory() -> Option<&'static Path> {
+    static DIRECTORY: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    DIRECTORY
+        .get_or_init(|| env::var_os(RECORD_VECTORS_VAR).map(PathBuf::from))
+        .as_deref()
+}
+
+/// Return the name of the fixture of the given encoding of a post or message
+/// of the given type name, without the extension.
+pub fn fixture_name(type_name: &str, bytes: &[u8]) -> String {
+    let hash = crypto::hash(bytes).unwrap_or_default();
+
+    format!(
+        "{}-{}",
+        type_name.replace(' ', "_"),
+        hex::encode(&hash[..4])
+    )
+}
+
+/// Record the given encoding of a message, if recording.
+pub(crate) fn record_message(msg: &Message, bytes: &[u8]) {
+    if let Some(dir) = directory() {
+        record_to(dir, message_type_name(msg.message_type()), bytes);
+    }
+}
+
+/// Record the given encoding of a post, if recording.
+pub(crate) fn record_post(post: &Post, bytes: &[u8]) {
+    if let Some(dir) = directory() {
+        record_to(dir, post_type_name(post.post_type()), bytes);
+    }
+}
+
+/// Write the fixture of the given encoding to the given directory, unless it
+/// has been written before.
+fn record_to(dir: &Path, type_name: &str, bytes: &[u8]) {
+    let path = dir.join(format!("{}.hex", fixture_name(type_name, bytes)));
+    if path.exists() {
+        return;
+    }
+
+    let _ = fs::create_dir_all(dir).and_then(|_| fs::write(path, hex::encode(bytes)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_fixtures() {
+        let dir = env::temp_dir().join(format!("cable-vectors-{}", std::process::id()));
+        let bytes = [0x05, 0x02, 0x00, 0x00, 0x00, 0x00];
+
+        let name = fixture_name("post request", &bytes);
+        assert!(name.starts_with("post_request-"));
+        assert_eq!(name.len(), "post_request-".len() + 8);
+
+        record_to(&dir, "post request", &bytes);
+        record_to(&dir, "post request", &bytes);
+        let fixture = fs::read_to_string(dir.join(format!("{}.hex", name))).unwrap();
+        assert_eq!(fixture, "050200000000");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Signing of posts by a secret key which may be held outside the process.
+//!
+//! A `Signer` produces signatures on behalf of a single public key without
+//! exposing its secret key, so that the key may be held by a hardware
+//! security module, the keychain of the operating system or a remote signing
+//! service. Posts are signed with a signer by `Post::sign_with()`.
+//!
+//! `KeypairSigner` is the signer of a secret key held in memory.
+
+use std::fmt;
+
+use async_trait::async_trait;
+
+use crate::{
+    crypto::{self, PublicKey, SecretKey, Signature},
+    error::{CableErrorKind, Error},
+};
+
+/// A source of Ed25519 signatures for a single public key.
+#[async_trait]
+pub trait Signer: fmt::Debug + Send + Sync {
+    /// Return the public key whose signatures are produced.
+    fn public_key(&self) -> PublicKey;
+
+    /// Create a detached signature of the given bytes.
+    async fn sign(&self, buf: &[u8]) -> Result<Signature, Error>;
+}
+
+/// A signer holding its secret key in memory.
+///
+/// The secret key is omitted from the `Debug` output of the signer.
+#[derive(Clone)]
+pub struct KeypairSigner {
+    public_key: PublicKey,
+    secret_key: SecretKey,
+}
+
+impl KeypairSigner {
+    /// Create a signer from the given keypair.
+    pub fn new(public_key: PublicKey, secret_key: SecretKey) -> Self {
+        KeypairSigner {
+            public_key,
+            secret_key,
+        }
+    }
+}
+
+impl From<(PublicKey, SecretKey)> for KeypairSigner {
+    fn from((public_key, secret_key): (PublicKey, SecretKey)) -> Self {
+        KeypairSigner::new(public_key, secret_key)
+    }
+}
+
+impl fmt::Debug for KeypairSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeypairSigner")
+            .field("public_key", &hex::encode(self.public_key))
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl Signer for KeypairSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    async fn sign(&self, buf: &[u8]) -> Result<Signature, Error> {
+        match crypto::sign(buf, &self.secret_key) {
+            Some(signature) => Ok(signature),
+            None => CableErrorKind::PostSigningFailed {
+                context: "invalid secret key".to_string(),
+            }
+            .raise(),
+        }
+    }
+}
@@ -1,58 +1,64 @@
 //! Validation functions.
 
-use crate::error::{CableErrorKind, Error};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{error::Error, limits::Limits, Channel};
+
+/// The normalization applied to channel names given by the local user.
+///
+/// The same name may be typed as different sequences of codepoints on
+/// different platforms; for example, "café" may end with a single precomposed
+/// `é` or with an `e` followed by a combining acute accent. Normalizing names
+/// before they are used ensures that both map to the same channel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChannelNormalization {
+    /// Channel names are used exactly as given, as required by the cable
+    /// specification.
+    None,
+    /// Channel names are converted to Unicode Normalization Form C.
+    #[default]
+    Nfc,
+    /// Channel names are converted to Unicode Normalization Form C and
+    /// lowercased, so that channel names differing only in case are the
+    /// same channel.
+    NfcLowercase,
+}
 
-/// Validate the length of a channel name (1 to 64 UTF-8 codepoints).
-pub fn validate_channel(channel: &String) -> Result<(), Error> {
-    // Determine the length of the given channel in UTF-8 codepoints.
-    let channel_len = channel.chars().count();
-    // The channel must be between 1 and 64 codepoints.
-    if !(1..=64).contains(&channel_len) {
-        return CableErrorKind::ChannelLengthIncorrect {
-            channel: channel.to_owned(),
-            len: channel_len,
+impl ChannelNormalization {
+    /// Return the given channel name normalized according to the policy.
+    pub fn normalize(&self, channel: &str) -> Channel {
+        match self {
+            ChannelNormalization::None => channel.to_owned(),
+            ChannelNormalization::Nfc => channel.nfc().collect(),
+            ChannelNormalization::NfcLowercase => channel.nfc().collect::<String>().to_lowercase(),
         }
-        .raise();
     }
+}
 
-    Ok(())
+/// Validate the length of a channel name (1 to 64 UTF-8 codepoints).
+pub fn validate_channel(channel: &str) -> Result<(), Error> {
+    Limits::DEFAULT.check_channel(channel)
 }
 
 /// Validate the length of a post's text (less than or equal to 4096 bytes).
-pub fn validate_text(text: &String) -> Result<(), Error> {
-    // Determine the length of the given post text in bytes.
-    let text_len = text.len();
-    // The text must not exceed 4096 bytes.
-    if text_len > 4096 {
-        return CableErrorKind::TextLengthIncorrect {
-            text: text.to_owned(),
-            len: text_len,
-        }
-        .raise();
-    }
-
-    Ok(())
+pub fn validate_text(text: &str) -> Result<(), Error> {
+    Limits::DEFAULT.check_text(text)
 }
 
-/// Validate the length of a topic name (1 to 512 UTF-8 codepoints).
-pub fn validate_topic(topic: &String) -> Result<(), Error> {
-    // Determine the length of the given channel topic in UTF-8 codepoints.
-    let topic_len = topic.chars().count();
-    // The topic must be between 0 and 512 codepoints.
-    if topic_len > 521 {
-        return CableErrorKind::TopicLengthIncorrect {
-            topic: topic.to_owned(),
-            len: topic_len,
-        }
-        .raise();
-    }
+/// Validate the length of a topic name (0 to 512 UTF-8 codepoints).
+pub fn validate_topic(topic: &str) -> Result<(), Error> {
+    Limits::DEFAULT.check_topic(topic)
+}
 
-    Ok(())
+/// Validate the length of a reaction (1 to 16 UTF-8 codepoints).
+#[cfg(feature = "reactions")]
+pub fn validate_reaction(reaction: &str) -> Result<(), Error> {
+    Limits::DEFAULT.check_reaction(reaction)
 }
 
 #[cfg(test)]
 mod test {
-    use super::{validate_channel, validate_topic};
+    use super::{validate_channel, validate_topic, ChannelNormalization};
     use crate::{Channel, Error, Topic, UserInfo};
 
     #[test]
@@ -118,6 +124,22 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn normalize_channel() {
+        // "café" with a precomposed `é` and with a combining acute accent.
+        let composed = "caf\u{e9}";
+        let decomposed = "cafe\u{301}";
+
+        assert_eq!(ChannelNormalization::None.normalize(decomposed), decomposed);
+        assert_eq!(ChannelNormalization::Nfc.normalize(decomposed), composed);
+        assert_eq!(ChannelNormalization::Nfc.normalize(composed), composed);
+        assert_eq!(ChannelNormalization::Nfc.normalize("Café"), "Café");
+        assert_eq!(
+            ChannelNormalization::NfcLowercase.normalize("CAFE\u{301}"),
+            composed
+        );
+    }
+
     #[test]
     fn validate_topic_len() -> Result<(), Error> {
         // Test valid topics.
@@ -0,0 +1,119 @@
+//! Parsing of the lines entered by the user into commands.
+
+use std::net::SocketAddr;
+
+use cable::Channel;
+
+/// The help text listing all commands.
+pub const HELP: &str = "\
+Commands:
+  /connect <addr>    connect to the peer at the given address
+  /channels          list known channels and request more from peers
+  /join <channel>    join a channel and make it the active channel
+  /leave             leave the active channel
+  /nick <name>       set your nickname
+  /topic <topic>     set the topic of the active channel
+  /help              show this help
+  /quit              exit
+Any other text is posted to the active channel.";
+
+/// An action requested by the user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Connect to the peer at the given address.
+    Connect(SocketAddr),
+    /// List known channels.
+    Channels,
+    /// Join the given channel.
+    Join(Channel),
+    /// Leave the active channel.
+    Leave,
+    /// Set the nickname of the local peer.
+    Nick(String),
+    /// Set the topic of the active channel.
+    Topic(String),
+    /// Post text to the active channel.
+    Text(String),
+    /// Show the help text.
+    Help,
+    /// Exit the client.
+    Quit,
+}
+
+impl Command {
+    /// Parse a line of user input, returning `None` for an empty line.
+    pub fn parse(line: &str) -> Result<Option<Command>, String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let Some(command) = line.strip_prefix('/') else {
+            return Ok(Some(Command::Text(line.to_owned())));
+        };
+
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (command, ""),
+        };
+
+        let command = match (name, arg) {
+            ("connect", "") => return Err("usage: /connect <addr>".to_owned()),
+            ("connect", addr) => Command::Connect(
+                addr.parse()
+                    .map_err(|err| format!("invalid address {addr:?}: {err}"))?,
+            ),
+            ("channels", _) => Command::Channels,
+            ("join", "") => return Err("usage: /join <channel>".to_owned()),
+            ("join", channel) => Command::Join(channel.trim_start_matches('#').to_owned()),
+            ("leave", _) => Command::Leave,
+            ("nick", "") => return Err("usage: /nick <name>".to_owned()),
+            ("nick", name) => Command::Nick(name.to_owned()),
+            ("topic", topic) => Command::Topic(topic.to_owned()),
+            ("help", _) => Command::Help,
+            ("quit", _) | ("exit", _) => Command::Quit,
+            (name, _) => return Err(format!("unknown command /{name}; type /help for help")),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(Command::parse("\n"), Ok(None));
+        assert_eq!(
+            Command::parse("hello /world\n"),
+            Ok(Some(Command::Text("hello /world".to_owned())))
+        );
+        assert_eq!(
+            Command::parse("/connect 127.0.0.1:8007"),
+            Ok(Some(Command::Connect("127.0.0.1:8007".parse().unwrap())))
+        );
+        assert_eq!(
+            Command::parse("/join #entomology"),
+            Ok(Some(Command::Join("entomology".to_owned())))
+        );
+        assert_eq!(
+            Command::parse("/nick glyph  "),
+            Ok(Some(Command::Nick("glyph".to_owned())))
+        );
+        assert_eq!(
+            Command::parse("/topic"),
+            Ok(Some(Command::Topic(String::new())))
+        );
+        assert_eq!(Command::parse("/quit"), Ok(Some(Command::Quit)));
+    }
+
+    #[test]
+    fn reject_invalid_commands() {
+        assert!(Command::parse("/connect").is_err());
+        assert!(Command::parse("/connect nowhere").is_err());
+        assert!(Command::parse("/join").is_err());
+        assert!(Command::parse("/frobnicate").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! A terminal chat client for cable.
+//!
+//! Start a client which listens for TCP connections on port 8007:
+//!
+//! `cargo run -p cable-cli -- -l 8007`
+//!
+//! And then start a second client which connects to the first, persisting
+//! its data to a sled database:
+//!
+//! `cargo run -p cable-cli -- --store /path/to/cable.db 127.0.0.1:8007`
+//!
+//! Type `/help` for a list of commands. Any other text is posted to the
+//! active channel.
+
+mod command;
+
+use std::collections::HashMap;
+
+use async_std::{
+    io::{self, prelude::BufReadExt},
+    net::TcpListener,
+    prelude::*,
+    task::{self, JoinHandle},
+};
+use cable::{post::PostBody, Channel, ChannelOptions, Post};
+use cable_core::{CableManager, MemoryStore, SledStore, Store, Supervisor, SupervisorOptions};
+
+use crate::command::{Command, HELP};
+
+type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The maximum number of past posts displayed when joining a channel.
+const HISTORY_LIMIT: u64 = 50;
+
+/// The maximum number of channels requested from peers by `/channels`.
+const CHANNEL_LIST_LIMIT: u64 = 100;
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+
+    let (args, argv) = argmap::parse(std::env::args());
+    let port = argv.get("l").and_then(|x| x.first()).cloned();
+    let peers = args.into_iter().skip(1).collect::<Vec<_>>();
+
+    task::block_on(async move {
+        match argv.get("store").and_then(|x| x.first()) {
+            Some(path) => run(SledStore::open(path)?, port, peers).await,
+            None => run(MemoryStore::default(), port, peers).await,
+        }
+    })
+}
+
+/// Run the client with the given store, listening on the given port and
+/// connecting to the given peer addresses.
+async fn run<S: Store + Clone>(
+    store: S,
+    port: Option<String>,
+    peers: Vec<String>,
+) -> Result<(), Error> {
+    let cable = CableManager::new(store);
+    let supervisor = Supervisor::new(cable.clone(), SupervisorOptions::default());
+
+    if let Some(port) = port {
+        let listener = TcpListener::bind(format!("0.0.0.0:{port}")).await?;
+        println!("Listening for peers on 0.0.0.0:{port}");
+
+        let cable = cable.clone();
+        task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(stream) = incoming.next().await {
+                match stream {
+                    Ok(stream) => {
+                        let cable = cable.clone();
+                        task::spawn(async move {
+                            if let Err(err) = cable.listen(stream).await {
+                                eprintln!("Connection closed: {err}");
+                            }
+                        });
+                    }
+                    Err(err) => eprintln!("Failed to accept connection: {err}"),
+                }
+            }
+        });
+    }
+
+    let mut client = Client {
+        cable,
+        supervisor,
+        active_channel: None,
+        open_channels: HashMap::new(),
+    };
+
+    for addr in peers {
+        match Command::parse(&format!("/connect {addr}")) {
+            Ok(Some(command)) => client.handle(command).await?,
+            Ok(None) => (),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    println!("Type /help for a list of commands.");
+
+    let mut lines = io::BufReader::new(io::stdin()).lines();
+    while let Some(line) = lines.next().await {
+        match Command::parse(&line?) {
+            Ok(Some(Command::Quit)) => break,
+            Ok(Some(command)) => {
+                if let Err(err) = client.handle(command).await {
+                    eprintln!("Error: {err}");
+                }
+            }
+            Ok(None) => (),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// The state of the terminal client.
+struct Client<S: Store + Clone> {
+    cable: CableManager<S>,
+    supervisor: Supervisor<S>,
+    /// The channel to which text is posted.
+    active_channel: Option<Channel>,
+    /// The tasks displaying the posts of each joined channel.
+    open_channels: HashMap<Channel, JoinHandle<()>>,
+}
+
+impl<S: Store + Clone> Client<S> {
+    /// Carry out the given command.
+    async fn handle(&mut self, command: Command) -> Result<(), Error> {
+        match command {
+            Command::Connect(addr) => {
+                if self.supervisor.add_peer(addr).await {
+                    println!("Connecting to {addr}");
+                } else {
+                    println!("Already connected to {addr}");
+                }
+            }
+            Command::Channels => {
+                self.cable
+                    .request_channel_list(0, CHANNEL_LIST_LIMIT)
+                    .await?;
+
+                let channels = self.cable.store.get_channels().await.unwrap_or_default();
+                if channels.is_empty() {
+                    println!("No known channels; responses from peers will be listed next time");
+                }
+                for channel in channels {
+                    let marker = if self.open_channels.contains_key(&channel) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{marker} #{channel}");
+                }
+            }
+            Command::Join(channel) => {
+                if !self.open_channels.contains_key(&channel) {
+                    self.cable.post_join(channel.clone()).await?;
+                    let handle = self.display_channel(channel.clone());
+                    self.open_channels.insert(channel.clone(), handle);
+                }
+                println!("Active channel: #{channel}");
+                self.active_channel = Some(channel);
+            }
+            Command::Leave => {
+                let channel = self.require_active_channel()?;
+                self.cable.post_leave(channel.clone()).await?;
+                self.cable.close_channel(&channel).await?;
+                if let Some(handle) = self.open_channels.remove(&channel) {
+                    handle.cancel().await;
+                }
+                println!("Left #{channel}");
+                self.active_channel = self.open_channels.keys().next().cloned();
+            }
+            Command::Nick(name) => {
+                self.cable.post_info_name(&name).await?;
+                println!("Nickname set to {name}");
+            }
+            Command::Topic(topic) => {
+                let channel = self.require_active_channel()?;
+                self.cable.post_topic(channel, topic).await?;
+            }
+            Command::Text(text) => {
+                let channel = self.require_active_channel()?;
+                self.cable.post_text(channel, text).await?;
+            }
+            Command::Help => println!("{HELP}"),
+            Command::Quit => (),
+        }
+
+        Ok(())
+    }
+
+    /// Return the active channel, or an error if no channel has been joined.
+    fn require_active_channel(&self) -> Result<Channel, Error> {
+        self.active_channel
+            .clone()
+            .ok_or_else(|| "no active channel; use /join <channel> first".into())
+    }
+
+    /// Open the given channel and spawn a task displaying its recent and
+    /// incoming posts.
+    fn display_channel(&self, channel: Channel) -> JoinHandle<()> {
+        let mut cable = self.cable.clone();
+        let store = cable.store.clone();
+
+        task::spawn(async move {
+            let opts = ChannelOptions::new(channel.clone(), 0, 0, HISTORY_LIMIT);
+            let mut posts = match cable.open_channel(&opts).await {
+                Ok(posts) => posts,
+                Err(err) => {
+                    eprintln!("Failed to open #{channel}: {err}");
+                    return;
+                }
+            };
+
+            while let Some(post) = posts.next().await {
+                match post {
+                    Ok(post) => {
+                        if let Some(line) = format_post(&store, &post).await {
+                            println!("{line}");
+                        }
+                    }
+                    Err(err) => eprintln!("Failed to read post in #{channel}: {err}"),
+                }
+            }
+        })
+    }
+}
+
+/// Format the given post for display, naming the author by nickname where
+/// one is known.
+///
+/// Returns `None` for posts without a channel (such as `post/info`), which
+/// are included in the posts of every channel.
+async fn format_post<S: Store>(store: &S, post: &Post) -> Option<String> {
+    let public_key = post.get_public_key();
+    let author = match store.get_peer_name_and_hash(&public_key).await {
+        Some((name, _hash)) => name,
+        None => hex::encode(&public_key[..4]),
+    };
+
+    match &post.body {
+        PostBody::Text { channel, text } => Some(format!("[#{channel}] <{author}> {text}")),
+        PostBody::Topic { channel, topic } => Some(format!(
+            "[#{channel}] * {author} set the topic to {topic:?}"
+        )),
+        _ => None,
+    }
+}
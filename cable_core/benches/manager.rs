@@ -0,0 +1,82 @@
+//! Benchmark the handling of requests received from many peers at once.
+//!
+//! Each connected peer writes a burst of post requests and waits for the
+//! responses, concurrently with the other peers. The time taken reflects the
+//! contention between peers for the state of the manager.
+//!
+//! Run the benchmarks:
+//!
+//! `cargo bench -p cable_core --bench manager`
+
+use async_std::{stream::StreamExt, task};
+use cable::{constants::NO_CIRCUIT, Message};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use desert::ToBytes;
+use futures::{future, AsyncWriteExt};
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, MemoryStore};
+
+/// The number of requests written by each peer per iteration.
+const BURST: u32 = 100;
+
+fn concurrent_peers_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrent_peers");
+    group.sample_size(20);
+
+    for peers in [1, 8, 32] {
+        let cable = CableManager::new(MemoryStore::default());
+
+        // Connect the peers, keeping the writing half and a decoder of the
+        // messages written to each.
+        let mut connections = Vec::new();
+        for _ in 0..peers {
+            let (stream, peer) = duplex();
+            let cable = cable.clone();
+            task::spawn(async move { cable.listen(stream).await });
+
+            let options = DecodeOptions {
+                include_len: true,
+                ..Default::default()
+            };
+            connections.push((peer.clone(), decode_with_options(peer, options)));
+        }
+
+        // Request IDs are never reused, as a request ID which has been seen
+        // before is ignored.
+        let mut next_req_id = 0u32;
+
+        group.bench_function(BenchmarkId::new("post_requests", peers), |b| {
+            b.iter(|| {
+                task::block_on(future::join_all(connections.iter_mut().map(
+                    |(peer, responses)| {
+                        let first_req_id = next_req_id;
+                        next_req_id += BURST;
+
+                        async move {
+                            let mut burst = Vec::new();
+                            for req_id in first_req_id..first_req_id + BURST {
+                                let request = Message::post_request(
+                                    NO_CIRCUIT,
+                                    req_id.to_be_bytes(),
+                                    0,
+                                    Vec::new(),
+                                );
+                                burst.extend(request.to_bytes().unwrap());
+                            }
+                            peer.write_all(&burst).await.unwrap();
+
+                            for _ in 0..BURST {
+                                responses.next().await.unwrap().unwrap();
+                            }
+                        }
+                    },
+                )))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, concurrent_peers_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Benchmark the retrieval of post hashes from a `MemoryStore` holding a
+//! large channel history.
+//!
+//! Run the benchmarks:
+//!
+//! `cargo bench -p cable_core`
+
+use std::convert::TryInto;
+
+use async_std::{stream::StreamExt, task};
+use cable::{ChannelOptions, Post};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+const CHANNEL: &str = "entomology";
+
+/// Create a store holding the given number of signed text posts in a single
+/// channel, with timestamps 1 to `n`.
+fn store_with_history(n: u64) -> MemoryStore {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into().unwrap();
+    let sk = sk.as_ref().try_into().unwrap();
+
+    let mut store = MemoryStore::default();
+    task::block_on(async {
+        for timestamp in 1..=n {
+            let mut post = Post::text(
+                pk,
+                vec![],
+                timestamp,
+                CHANNEL.to_string(),
+                format!("post {}", timestamp),
+            );
+            post.sign(&sk).unwrap();
+            store.insert_post(&post).await.unwrap();
+        }
+    });
+
+    store
+}
+
+/// Collect the hashes matching the given options.
+fn count_post_hashes(store: &MemoryStore, opts: &ChannelOptions) -> usize {
+    task::block_on(async { store.get_post_hashes(opts).await.count().await })
+}
+
+fn get_post_hashes_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_post_hashes");
+    group.sample_size(20);
+
+    for n in [1_000, 10_000, 100_000] {
+        let store = store_with_history(n);
+
+        // The complete history of the channel.
+        let all = ChannelOptions::new(CHANNEL, 0, 0, 0);
+        group.bench_with_input(BenchmarkId::new("all", n), &all, |b, opts| {
+            b.iter(|| count_post_hashes(&store, opts))
+        });
+
+        // The most recent 100 posts, as requested by a live channel view.
+        let recent = ChannelOptions::new(CHANNEL, n - 99, 0, 100);
+        group.bench_with_input(BenchmarkId::new("recent", n), &recent, |b, opts| {
+            b.iter(|| count_post_hashes(&store, opts))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, get_post_hashes_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+//! Run a cable peer in the browser, connected to another peer over a
+//! WebSocket.
+//!
+//! See the README for build instructions.
+
+use cable::{post::PostBody, ChannelOptions};
+use cable_core::{CableManager, MemoryStore, SharedStream};
+use futures::StreamExt;
+use wasm_bindgen::prelude::*;
+use ws_stream_wasm::WsMeta;
+
+/// The address of the WebSocket relay forwarding to a cable peer.
+const RELAY: &str = "ws://127.0.0.1:8009";
+
+/// The channel to display.
+const CHANNEL: &str = "default";
+
+/// Append a line of text to the page.
+fn print(text: &str) {
+    let document = web_sys::window().and_then(|window| window.document());
+    if let Some(document) = document {
+        if let (Ok(line), Some(body)) = (document.create_element("p"), document.body()) {
+            line.set_text_content(Some(text));
+            let _ = body.append_child(&line);
+        }
+    }
+}
+
+#[wasm_bindgen(start)]
+pub async fn start() -> Result<(), JsValue> {
+    console_error_panic_hook::set_once();
+
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    let (_meta, ws) = WsMeta::connect(RELAY, None)
+        .await
+        .map_err(|err| JsValue::from_str(&err.to_string()))?;
+    print(&format!("connected to {}", RELAY));
+
+    // The manager reads and writes from separate tasks, so the WebSocket is
+    // wrapped in a cloneable handle.
+    let stream = SharedStream::new(ws.into_io());
+    let listener = cable.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = listener.listen(stream).await {
+            print(&format!("connection closed: {}", err));
+        }
+    });
+
+    cable
+        .post_text(CHANNEL, "hello from the browser")
+        .await
+        .map_err(|err| JsValue::from_str(&err.to_string()))?;
+
+    let opts = ChannelOptions::new(CHANNEL, 0, 0, 50);
+    let mut posts = cable
+        .open_channel(&opts)
+        .await
+        .map_err(|err| JsValue::from_str(&err.to_string()))?;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            print(&text);
+        }
+    }
+
+    Ok(())
+}
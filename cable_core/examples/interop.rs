@@ -0,0 +1,207 @@
+//! An interoperability smoke test against a running cable.js peer.
+//!
+//! Start a cable.js peer listening for TCP connections, post to a channel
+//! from it and then run the smoke test against it:
+//!
+//! `cargo run --example interop -- 127.0.0.1:8008 --channel default`
+//!
+//! The smoke test connects over TCP and exchanges raw messages with the peer:
+//!
+//! 1) A channel list request, asserting the response lists the channel.
+//!
+//! 2) A channel time range request for the whole history of the channel,
+//!    asserting the hash responses end with an empty response.
+//!
+//! 3) A post request for the returned hashes, asserting every post is
+//!    returned with a valid signature, matches the hash by which it was
+//!    requested and belongs to the channel.
+//!
+//! The process exits with an error at the first failed assertion, or if the
+//! peer does not respond within the timeout (`--timeout`, in seconds).
+
+use std::{collections::HashSet, time::Duration};
+
+use async_std::{future, net::TcpStream, prelude::*, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    crypto,
+    message::{MessageBody, ResponseBody},
+    ChannelOptions, Hash, Message, Post, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// The stream of messages received from the peer.
+type Messages = std::pin::Pin<Box<dyn Stream<Item = Result<Vec<u8>, Error>> + Send>>;
+
+fn now() -> Result<u64, Error> {
+    let time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis()
+        .try_into()?;
+
+    Ok(time)
+}
+
+/// Return an error with the given message unless the condition holds.
+fn check(condition: bool, msg: impl Into<String>) -> Result<(), Error> {
+    if condition {
+        Ok(())
+    } else {
+        Err(msg.into().into())
+    }
+}
+
+/// Write the given request to the peer.
+async fn send(stream: &mut TcpStream, request: &Message) -> Result<(), Error> {
+    stream.write_all(&request.to_bytes()?).await?;
+
+    Ok(())
+}
+
+/// Read messages from the peer until a response to the request with the
+/// given ID is received, skipping the requests made by the peer.
+async fn next_response(
+    messages: &mut Messages,
+    req_id: &ReqId,
+    timeout: Duration,
+) -> Result<ResponseBody, Error> {
+    future::timeout(timeout, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            match msg.body {
+                MessageBody::Response { body } if &msg.header.req_id == req_id => {
+                    return Ok(body);
+                }
+                _ => println!("  skipping {}", msg),
+            }
+        }
+
+        Err("the peer closed the connection".into())
+    })
+    .await
+    .map_err(|_| -> Error { "timed out awaiting a response".into() })?
+}
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+
+    let (args, argv) = argmap::parse(std::env::args());
+    let addr = args
+        .get(1)
+        .ok_or("usage: interop <address> [--channel <name>] [--timeout <seconds>]")?
+        .clone();
+    let channel = argv
+        .get("channel")
+        .and_then(|x| x.first())
+        .cloned()
+        .unwrap_or_else(|| "default".to_string());
+    let timeout = Duration::from_secs(
+        argv.get("timeout")
+            .and_then(|x| x.first())
+            .map(|x| x.parse())
+            .transpose()?
+            .unwrap_or(10),
+    );
+
+    task::block_on(async move {
+        println!("Connecting to the cable.js peer on {addr}");
+        let mut stream = TcpStream::connect(&addr).await?;
+
+        let options = DecodeOptions {
+            include_len: true,
+            ..Default::default()
+        };
+        let mut messages: Messages = Box::pin(
+            decode_with_options(stream.clone(), options).map(|buf| buf.map_err(Error::from)),
+        );
+
+        // 1) List the channels of the peer.
+        println!("Requesting the channel list");
+        let req_id = [0, 0, 0, 1];
+        send(
+            &mut stream,
+            &Message::channel_list_request(NO_CIRCUIT, req_id, 0, 0, 0),
+        )
+        .await?;
+        let channels = match next_response(&mut messages, &req_id, timeout).await? {
+            ResponseBody::ChannelList { channels } => channels,
+            body => return Err(format!("expected a channel list response: {body}").into()),
+        };
+        println!("  received {} channels: {:?}", channels.len(), channels);
+        check(
+            channels.contains(&channel),
+            format!("the channel list lacks {channel:?}"),
+        )?;
+
+        // 2) Request the hashes of the whole history of the channel. A
+        // time range ending now concludes with an empty hash response.
+        println!("Requesting the history of {channel:?}");
+        let req_id = [0, 0, 0, 2];
+        let opts = ChannelOptions::new(&channel, 0, now()?, 0);
+        send(
+            &mut stream,
+            &Message::channel_time_range_request(NO_CIRCUIT, req_id, 0, opts),
+        )
+        .await?;
+        let mut hashes: Vec<Hash> = Vec::new();
+        loop {
+            match next_response(&mut messages, &req_id, timeout).await? {
+                ResponseBody::Hash { hashes: batch } if batch.is_empty() => break,
+                ResponseBody::Hash { hashes: batch } => hashes.extend(batch),
+                body => return Err(format!("expected a hash response: {body}").into()),
+            }
+        }
+        println!("  received {} hashes", hashes.len());
+        check(!hashes.is_empty(), format!("no posts in {channel:?}"))?;
+        check(
+            hashes.iter().collect::<HashSet<_>>().len() == hashes.len(),
+            "the hash responses repeat a hash",
+        )?;
+
+        // 3) Request the posts of the returned hashes.
+        println!("Requesting {} posts", hashes.len());
+        let req_id = [0, 0, 0, 3];
+        send(
+            &mut stream,
+            &Message::post_request(NO_CIRCUIT, req_id, 0, hashes.clone()),
+        )
+        .await?;
+        let mut missing: HashSet<Hash> = hashes.iter().copied().collect();
+        while !missing.is_empty() {
+            let payloads = match next_response(&mut messages, &req_id, timeout).await? {
+                ResponseBody::Post { posts } if posts.is_empty() => break,
+                ResponseBody::Post { posts } => posts,
+                body => return Err(format!("expected a post response: {body}").into()),
+            };
+            for payload in payloads {
+                let hash = crypto::hash(&payload).ok_or("failed to hash a post")?;
+                check(
+                    missing.remove(&hash),
+                    format!("unrequested post {}", hex::encode(hash)),
+                )?;
+                check(
+                    Post::verify(&payload),
+                    format!("invalid signature on post {}", hex::encode(hash)),
+                )?;
+
+                let (_, post) = Post::from_bytes(&payload)?;
+                check(
+                    post.get_channel() == Some(&channel),
+                    format!("post {} is not in {channel:?}", hex::encode(hash)),
+                )?;
+                println!("  {post}");
+            }
+        }
+        check(
+            missing.is_empty(),
+            format!("{} requested posts were not returned", missing.len()),
+        )?;
+
+        println!("All checks passed against {addr}");
+
+        Ok(())
+    })
+}
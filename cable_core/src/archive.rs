@@ -0,0 +1,114 @@
+//! A portable archive format for the contents of a store.
+//!
+//! An archive begins with a format header, followed by an optional
+//! passphrase-protected identity (see `encrypt_keypair()`) and the encoded
+//! payloads of the archived posts. The identity and each payload are prefixed
+//! with their length as a varint; an identity length of zero denotes the
+//! absence of an identity.
+//!
+//! Posts are archived in their signed wire encoding, so an archive written by
+//! one store backend can be imported into any other.
+
+use std::io::{Read, Write};
+
+use cable::{error::CableErrorKind, Channel, Error, Payload};
+use desert::varint;
+
+/// The bytes which begin every archive.
+const ARCHIVE_MAGIC: &[u8] = b"cable-archive";
+
+/// The version of the archive format.
+const ARCHIVE_VERSION: u8 = 1;
+
+/// Parameters controlling the export of a store archive.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// The channels to export. All channels are exported if `None`.
+    pub channels: Option<Vec<Channel>>,
+    /// The passphrase with which to protect the exported keypair. The keypair
+    /// is omitted from the archive if `None`.
+    pub passphrase: Option<String>,
+}
+
+/// The decoded contents of an archive.
+pub(crate) struct Archive {
+    /// The passphrase-protected identity, if one was exported.
+    pub identity: Option<Vec<u8>>,
+    /// The encoded payloads of all archived posts.
+    pub payloads: Vec<Payload>,
+}
+
+/// Write the given bytes, prefixed by their length as a varint.
+fn write_length_prefixed(writer: &mut (dyn Write + Send), bytes: &[u8]) -> Result<(), Error> {
+    let mut len = [0; 10];
+    let len_len = varint::encode(bytes.len() as u64, &mut len)?;
+    writer.write_all(&len[..len_len])?;
+    writer.write_all(bytes)?;
+
+    Ok(())
+}
+
+/// Read a length-prefixed byte string from the given buffer, returning the
+/// number of bytes consumed and the byte string.
+fn read_length_prefixed(buf: &[u8]) -> Result<(usize, &[u8]), Error> {
+    let (len_len, len) = varint::decode(buf)?;
+    let end = len_len.saturating_add(len as usize);
+    match buf.get(len_len..end) {
+        Some(bytes) => Ok((end, bytes)),
+        None => CableErrorKind::NoneError {
+            context: "unexpected end of archive".to_string(),
+        }
+        .raise(),
+    }
+}
+
+impl Archive {
+    /// Write the archive to the given writer.
+    pub fn write(&self, writer: &mut (dyn Write + Send)) -> Result<(), Error> {
+        writer.write_all(ARCHIVE_MAGIC)?;
+        writer.write_all(&[ARCHIVE_VERSION])?;
+
+        write_length_prefixed(writer, self.identity.as_deref().unwrap_or_default())?;
+        for payload in &self.payloads {
+            write_length_prefixed(writer, payload)?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Read an archive from the given reader.
+    pub fn read(reader: &mut (dyn Read + Send)) -> Result<Self, Error> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let header_len = ARCHIVE_MAGIC.len() + 1;
+        if buf.len() < header_len
+            || &buf[..ARCHIVE_MAGIC.len()] != ARCHIVE_MAGIC
+            || buf[ARCHIVE_MAGIC.len()] != ARCHIVE_VERSION
+        {
+            return CableErrorKind::NoneError {
+                context: "failed to decode archive header".to_string(),
+            }
+            .raise();
+        }
+
+        let mut offset = header_len;
+        let (len, identity) = read_length_prefixed(&buf[offset..])?;
+        offset += len;
+        let identity = if identity.is_empty() {
+            None
+        } else {
+            Some(identity.to_vec())
+        };
+
+        let mut payloads = Vec::new();
+        while offset < buf.len() {
+            let (len, payload) = read_length_prefixed(&buf[offset..])?;
+            offset += len;
+            payloads.push(payload.to_vec());
+        }
+
+        Ok(Archive { identity, payloads })
+    }
+}
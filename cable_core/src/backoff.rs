@@ -0,0 +1,69 @@
+//! Exponential backoff used when retrying failed peer connections.
+
+use std::time::Duration;
+
+/// Exponential backoff state for a single retried operation.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    /// The delay before the first retry.
+    initial: Duration,
+    /// The maximum delay between retries.
+    max: Duration,
+    /// The number of consecutive failed attempts.
+    attempts: u32,
+    /// Whether to randomise each delay to avoid synchronised retries.
+    jitter: bool,
+}
+
+impl Backoff {
+    /// Create a new `Backoff` with the given initial and maximum delays.
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Backoff {
+            initial,
+            max,
+            attempts: 0,
+            jitter: false,
+        }
+    }
+
+    /// Randomise each delay to between half and all of the exponential delay.
+    ///
+    /// This prevents many peers which lost their connections at the same
+    /// moment from retrying in lockstep.
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Return the number of consecutive failed attempts.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Record a failed attempt and return the delay to wait before the next
+    /// attempt.
+    ///
+    /// The delay doubles with each consecutive failure, up to the maximum.
+    /// If jitter is enabled, the returned delay is randomised.
+    pub fn next_delay(&mut self) -> Duration {
+        // Cap the exponent to avoid overflowing the multiplier.
+        let exponent = self.attempts.min(16);
+        self.attempts = self.attempts.saturating_add(1);
+
+        let delay = self
+            .initial
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max);
+
+        if self.jitter {
+            delay / 2 + delay.mul_f64(fastrand::f64() / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /// Reset the backoff after a successful attempt.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
@@ -0,0 +1,37 @@
+//! Serialisation of the batches of writes of persistent stores.
+//!
+//! `Store::begin_batch()` and `Store::commit_batch()` are separate calls, so
+//! the lock serialising the batches of all handles of a store must be held
+//! between them. `BatchLock` keeps the owned guard of the lock alongside the
+//! lock itself until the batch is committed.
+
+use async_std::sync::{Arc, Mutex, MutexGuard, MutexGuardArc};
+
+/// A lock held for the duration of a batch of writes, shared by all handles
+/// of a store.
+#[derive(Clone, Default)]
+pub(crate) struct BatchLock {
+    /// The lock serialising batches.
+    lock: Arc<Mutex<()>>,
+    /// The guard of the lock while a batch is open.
+    guard: Arc<Mutex<Option<MutexGuardArc<()>>>>,
+}
+
+impl BatchLock {
+    /// Acquire the lock for a new batch, waiting for the open batch of any
+    /// other handle to be committed.
+    pub(crate) async fn begin(&self) {
+        let guard = self.lock.lock_arc().await;
+        *self.guard.lock().await = Some(guard);
+    }
+
+    /// Release the lock of the open batch, if any.
+    pub(crate) async fn end(&self) {
+        self.guard.lock().await.take();
+    }
+
+    /// Acquire the lock for an operation which may not run within a batch.
+    pub(crate) async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().await
+    }
+}
@@ -0,0 +1,233 @@
+//! Encoding and decoding of bencoded values, the format of the messages
+//! exchanged by the nodes of the BitTorrent DHT.
+
+use std::collections::BTreeMap;
+
+use cable::Error;
+
+/// The maximum nesting of lists and dictionaries in a decoded value.
+const MAX_DEPTH: usize = 16;
+
+/// A bencoded value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Bytes(value.as_bytes().to_vec())
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(value: &[u8]) -> Self {
+        Value::Bytes(value.to_vec())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl Value {
+    /// Return a dictionary of the given entries.
+    pub(crate) fn dict<const N: usize>(entries: [(&str, Value); N]) -> Self {
+        Value::Dict(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.as_bytes().to_vec(), value))
+                .collect(),
+        )
+    }
+
+    /// Return the value of the given key, if this is a dictionary holding it.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    /// Return the bytes of a byte string.
+    pub(crate) fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Return the value of an integer.
+    pub(crate) fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(int) => Some(*int),
+            _ => None,
+        }
+    }
+
+    /// Return the items of a list.
+    pub(crate) fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Encode the value.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode_to(&mut buf);
+
+        buf
+    }
+
+    fn encode_to(&self, buf: &mut Vec<u8>) {
+        match self {
+            Value::Int(int) => buf.extend_from_slice(format!("i{}e", int).as_bytes()),
+            Value::Bytes(bytes) => encode_bytes(bytes, buf),
+            Value::List(items) => {
+                buf.push(b'l');
+                for item in items {
+                    item.encode_to(buf);
+                }
+                buf.push(b'e');
+            }
+            Value::Dict(entries) => {
+                // Keys are sorted, as the order of a `BTreeMap`.
+                buf.push(b'd');
+                for (key, value) in entries {
+                    encode_bytes(key, buf);
+                    value.encode_to(buf);
+                }
+                buf.push(b'e');
+            }
+        }
+    }
+
+    /// Decode a value spanning the whole of the given buffer.
+    pub(crate) fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let mut decoder = Decoder { buf, offset: 0 };
+        let value = decoder.value(0)?;
+        if decoder.offset != buf.len() {
+            return Err("trailing bytes after bencoded value".into());
+        }
+
+        Ok(value)
+    }
+}
+
+fn encode_bytes(bytes: &[u8], buf: &mut Vec<u8>) {
+    buf.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// A decoder reading values from a buffer.
+struct Decoder<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl Decoder<'_> {
+    fn peek(&self) -> Result<u8, Error> {
+        match self.buf.get(self.offset) {
+            Some(byte) => Ok(*byte),
+            None => Err("unexpected end of bencoded value".into()),
+        }
+    }
+
+    /// Read the bytes up to the given delimiter, skipping the delimiter.
+    fn read_until(&mut self, delimiter: u8) -> Result<&str, Error> {
+        let rest = &self.buf[self.offset..];
+        let len = match rest.iter().position(|byte| *byte == delimiter) {
+            Some(len) => len,
+            None => return Err("unterminated bencoded value".into()),
+        };
+        self.offset += len + 1;
+
+        Ok(std::str::from_utf8(&rest[..len])?)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err("bencoded value nested too deeply".into());
+        }
+
+        match self.peek()? {
+            b'i' => {
+                self.offset += 1;
+                Ok(Value::Int(self.read_until(b'e')?.parse()?))
+            }
+            b'0'..=b'9' => Ok(Value::Bytes(self.bytes()?)),
+            b'l' => {
+                self.offset += 1;
+                let mut items = Vec::new();
+                while self.peek()? != b'e' {
+                    items.push(self.value(depth + 1)?);
+                }
+                self.offset += 1;
+
+                Ok(Value::List(items))
+            }
+            b'd' => {
+                self.offset += 1;
+                let mut entries = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    entries.insert(key, self.value(depth + 1)?);
+                }
+                self.offset += 1;
+
+                Ok(Value::Dict(entries))
+            }
+            _ => Err("invalid bencoded value".into()),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+        let len: usize = self.read_until(b':')?.parse()?;
+        let end = match self.offset.checked_add(len) {
+            Some(end) if end <= self.buf.len() => end,
+            _ => return Err("bencoded string exceeds the buffer".into()),
+        };
+        let bytes = self.buf[self.offset..end].to_vec();
+        self.offset = end;
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_values() -> Result<(), Error> {
+        let value = Value::dict([
+            ("t", Value::from(&b"aa"[..])),
+            ("y", Value::from("q")),
+            (
+                "a",
+                Value::dict([("port", Value::from(6881)), ("id", Value::from("abc"))]),
+            ),
+            ("l", Value::List(vec![Value::from(-1), Value::from("")])),
+        ]);
+        let encoded = value.encode();
+        assert_eq!(
+            encoded,
+            b"d1:ad2:id3:abc4:porti6881ee1:lli-1e0:e1:t2:aa1:y1:qe".to_vec()
+        );
+        assert_eq!(Value::decode(&encoded)?, value);
+
+        for invalid in [&b"d1:a"[..], b"5:abc", b"i1", b"i1ei2e", b"x"] {
+            assert!(Value::decode(invalid).is_err());
+        }
+        assert!(Value::decode(&[b'l'; 64]).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! Transfer of binary blobs, such as images, referenced from posts.
+//!
+//! A blob is identified by the BLAKE2b hash of its bytes and is referenced
+//! from the text of a post by a link of the form `blob:<hex hash>`. Blobs are
+//! fetched from peers in chunks with blob requests, an extension to the
+//! cable specification: the first peer to respond with the size of the blob
+//! is asked for each following chunk in turn, and the blob is returned once
+//! all of its bytes have been received and match its hash.
+
+use std::collections::{HashMap, HashSet};
+
+use async_std::channel::{self, Receiver, Sender};
+use cable::{crypto, Hash};
+
+use crate::manager::PeerId;
+
+/// The maximum number of bytes of a blob sent in a single blob response, so
+/// that each response fits the 50 kB frames accepted by the decoder of the
+/// peer.
+pub(crate) const BLOB_CHUNK_SIZE: u64 = 32 * 1024;
+
+/// The prefix of a link to a blob in the text of a post.
+const LINK_PREFIX: &str = "blob:";
+
+/// Return the link to the blob with the given hash, to be included in the
+/// text of a post.
+pub fn blob_link(hash: &Hash) -> String {
+    format!("{}{}", LINK_PREFIX, hex::encode(hash))
+}
+
+/// Return the hashes of the blobs linked from the given text, in order of
+/// appearance.
+pub fn blob_links(text: &str) -> Vec<Hash> {
+    text.match_indices(LINK_PREFIX)
+        .filter_map(|(start, _)| {
+            let start = start + LINK_PREFIX.len();
+            let hex = text.get(start..start + 64)?;
+
+            <[u8; 32] as hex::FromHex>::from_hex(hex).ok()
+        })
+        .collect()
+}
+
+/// The progress made by a chunk received for a blob.
+#[derive(Debug, PartialEq)]
+pub(crate) enum BlobProgress {
+    /// The chunk does not advance any fetch.
+    Ignored,
+    /// The next chunk, starting at the given offset, is to be requested from
+    /// the peer which sent the chunk.
+    Next(u64),
+    /// The fetch has ended, with the blob if it was received in full and
+    /// matches its hash.
+    Complete(Option<Vec<u8>>),
+}
+
+/// The state of the fetch of a single blob.
+#[derive(Default)]
+struct BlobFetch {
+    /// The bytes received so far.
+    data: Vec<u8>,
+    /// The size of the blob, once a peer holding it has responded.
+    size: u64,
+    /// The peer from which the blob is fetched, once one holding it has
+    /// responded.
+    source: Option<PeerId>,
+    /// The peers asked for the blob which have not yet responded.
+    asked: HashSet<PeerId>,
+    /// The tasks awaiting the blob.
+    waiters: Vec<Sender<Option<Vec<u8>>>>,
+}
+
+/// The blobs being fetched from peers and the tasks awaiting them.
+#[derive(Default)]
+pub(crate) struct BlobFetches {
+    fetches: HashMap<Hash, BlobFetch>,
+}
+
+impl BlobFetches {
+    /// Return a receiver of the blob with the given hash, along with `true`
+    /// if a new fetch of the blob was started by asking the given peers.
+    pub(crate) fn wait(
+        &mut self,
+        hash: &Hash,
+        peers: &[PeerId],
+    ) -> (Receiver<Option<Vec<u8>>>, bool) {
+        let (sender, receiver) = channel::bounded(1);
+        let is_new = !self.fetches.contains_key(hash);
+        let fetch = self.fetches.entry(*hash).or_default();
+        if is_new {
+            fetch.asked = peers.iter().copied().collect();
+        }
+        fetch.waiters.push(sender);
+
+        (receiver, is_new)
+    }
+
+    /// Record a chunk of the blob with the given hash and size, starting at
+    /// the given offset, received from the given peer. Blobs larger than the
+    /// given maximum size are abandoned.
+    pub(crate) fn receive(
+        &mut self,
+        peer_id: PeerId,
+        hash: &Hash,
+        size: u64,
+        start: u64,
+        data: &[u8],
+        max_size: Option<u64>,
+    ) -> BlobProgress {
+        let Some(fetch) = self.fetches.get_mut(hash) else {
+            return BlobProgress::Ignored;
+        };
+
+        match fetch.source {
+            // A peer which does not hold the blob responds with a size of 0.
+            None if size == 0 => {
+                fetch.asked.remove(&peer_id);
+                if fetch.asked.is_empty() {
+                    return BlobProgress::Complete(None);
+                }
+
+                return BlobProgress::Ignored;
+            }
+            None if fetch.asked.contains(&peer_id) => {
+                if max_size.is_some_and(|max_size| size > max_size) {
+                    return BlobProgress::Complete(None);
+                }
+                fetch.source = Some(peer_id);
+                fetch.size = size;
+            }
+            Some(source) if source == peer_id => {}
+            _ => return BlobProgress::Ignored,
+        }
+
+        // Chunks are requested in turn, so any other chunk is out of order.
+        let expected = fetch.data.len() as u64;
+        if size != fetch.size
+            || start != expected
+            || data.is_empty()
+            || expected + data.len() as u64 > size
+        {
+            return BlobProgress::Complete(None);
+        }
+        fetch.data.extend_from_slice(data);
+
+        if (fetch.data.len() as u64) < size {
+            return BlobProgress::Next(fetch.data.len() as u64);
+        }
+        let data = std::mem::take(&mut fetch.data);
+        if crypto::hash(&data).as_ref() != Some(hash) {
+            return BlobProgress::Complete(None);
+        }
+
+        BlobProgress::Complete(Some(data))
+    }
+
+    /// End the fetch of the blob with the given hash, passing the given
+    /// result to the tasks awaiting it.
+    pub(crate) fn finish(&mut self, hash: &Hash, blob: Option<Vec<u8>>) {
+        if let Some(fetch) = self.fetches.remove(hash) {
+            for waiter in fetch.waiters {
+                let _ = waiter.try_send(blob.clone());
+            }
+        }
+    }
+
+    /// Forget the given peer, which has disconnected, returning the hashes of
+    /// the blobs whose fetches can no longer complete.
+    pub(crate) fn remove_peer(&mut self, peer_id: PeerId) -> Vec<Hash> {
+        self.fetches
+            .iter_mut()
+            .filter_map(|(hash, fetch)| {
+                fetch.asked.remove(&peer_id);
+                let failed = match fetch.source {
+                    Some(source) => source == peer_id,
+                    None => fetch.asked.is_empty(),
+                };
+
+                failed.then_some(*hash)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_blob_links() {
+        let hash = [7; 32];
+        let text = format!("a luna moth! {} and blob:cafe", blob_link(&hash));
+
+        assert_eq!(blob_links(&text), vec![hash]);
+        assert!(blob_links("no blobs here").is_empty());
+    }
+
+    #[test]
+    fn fetch_blob_in_chunks() {
+        let blob = vec![1, 2, 3, 4, 5];
+        let hash = crypto::hash(&blob).unwrap();
+        let mut fetches = BlobFetches::default();
+
+        let (receiver, is_new) = fetches.wait(&hash, &[1, 2]);
+        assert!(is_new);
+        assert!(!fetches.wait(&hash, &[1, 2]).1);
+
+        // Peer 1 does not hold the blob; peer 2 does.
+        assert_eq!(
+            fetches.receive(1, &hash, 0, 0, &[], None),
+            BlobProgress::Ignored
+        );
+        assert_eq!(
+            fetches.receive(2, &hash, 5, 0, &blob[..3], None),
+            BlobProgress::Next(3)
+        );
+        assert_eq!(
+            fetches.receive(2, &hash, 5, 3, &blob[3..], None),
+            BlobProgress::Complete(Some(blob.clone()))
+        );
+
+        fetches.finish(&hash, Some(blob.clone()));
+        assert_eq!(receiver.try_recv(), Ok(Some(blob)));
+    }
+
+    #[test]
+    fn reject_oversized_or_mismatched_blobs() {
+        let blob = vec![1, 2, 3, 4, 5];
+        let hash = crypto::hash(&blob).unwrap();
+        let mut fetches = BlobFetches::default();
+
+        fetches.wait(&hash, &[1]);
+        assert_eq!(
+            fetches.receive(1, &hash, 5, 0, &blob, Some(4)),
+            BlobProgress::Complete(None)
+        );
+        fetches.finish(&hash, None);
+
+        fetches.wait(&hash, &[1]);
+        assert_eq!(
+            fetches.receive(1, &hash, 5, 0, &[5, 4, 3, 2, 1], None),
+            BlobProgress::Complete(None)
+        );
+        fetches.finish(&hash, None);
+
+        // A fetch fails once its source disconnects, or once every peer
+        // asked for the blob has disconnected.
+        fetches.wait(&hash, &[1]);
+        assert_eq!(
+            fetches.receive(1, &hash, 5, 0, &blob[..2], None),
+            BlobProgress::Next(2)
+        );
+        let other = [0; 32];
+        fetches.wait(&other, &[1, 2]);
+        assert_eq!(fetches.remove_peer(1), vec![hash]);
+        assert_eq!(fetches.remove_peer(2), vec![other]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Accounting of the memory attributable to each connected peer.
+//!
+//! A peer can make the manager hold state on its behalf: frames queued to be
+//! written to it, requests it made which are forwarded to other peers and
+//! posts requested from it after it advertised their hashes. The size of this
+//! state is estimated for each peer, so that a peer exceeding its memory
+//! budget can have its oldest state shed or be disconnected.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use cable::ReqId;
+
+/// The estimated size of the state held for a forwarded request, in addition
+/// to the encoded request.
+pub(crate) const FORWARDED_REQUEST_OVERHEAD: usize = 64;
+
+/// The estimated size of the state held for a post requested from a peer.
+pub(crate) const REQUESTED_POST_SIZE: usize = 128;
+
+/// The forwarded requests of a peer, oldest first, with their estimated size.
+#[derive(Debug, Default)]
+struct Forwarded {
+    requests: VecDeque<(ReqId, usize)>,
+    size: usize,
+}
+
+/// The memory attributable to a peer, besides the posts requested from it,
+/// which are counted by `RequestedPosts`.
+#[derive(Debug, Default)]
+pub(crate) struct PeerMemory {
+    /// The size of the frames queued to be written to the peer.
+    queued: AtomicUsize,
+    /// The requests of the peer which have been forwarded. A request may
+    /// have concluded since; such requests are only forgotten once
+    /// `retain_forwarded()` is called.
+    forwarded: Mutex<Forwarded>,
+}
+
+impl PeerMemory {
+    fn forwarded(&self) -> std::sync::MutexGuard<'_, Forwarded> {
+        self.forwarded.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Record that a frame of the given size was queued for the peer.
+    pub(crate) fn queue(&self, size: usize) {
+        self.queued.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// Record that a queued frame of the given size was written to the peer.
+    pub(crate) fn dequeue(&self, size: usize) {
+        self.queued.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    /// Return the size of the frames queued for the peer.
+    pub(crate) fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Record that the request with the given ID and encoded size, received
+    /// from the peer, was forwarded.
+    pub(crate) fn push_forwarded(&self, req_id: ReqId, len: usize) {
+        let size = len + FORWARDED_REQUEST_OVERHEAD;
+        let mut forwarded = self.forwarded();
+        forwarded.requests.push_back((req_id, size));
+        forwarded.size += size;
+    }
+
+    /// Forget the oldest forwarded request, returning its ID.
+    pub(crate) fn pop_forwarded(&self) -> Option<ReqId> {
+        let mut forwarded = self.forwarded();
+        let (req_id, size) = forwarded.requests.pop_front()?;
+        forwarded.size -= size;
+
+        Some(req_id)
+    }
+
+    /// Return the IDs of the forwarded requests, oldest first.
+    pub(crate) fn forwarded_req_ids(&self) -> Vec<ReqId> {
+        self.forwarded()
+            .requests
+            .iter()
+            .map(|(req_id, _)| *req_id)
+            .collect()
+    }
+
+    /// Forget the forwarded requests for which the given function returns
+    /// `false`.
+    pub(crate) fn retain_forwarded(&self, mut f: impl FnMut(&ReqId) -> bool) {
+        let mut forwarded = self.forwarded();
+        let Forwarded { requests, size } = &mut *forwarded;
+        requests.retain(|(req_id, request_size)| {
+            let retain = f(req_id);
+            if !retain {
+                *size -= request_size;
+            }
+            retain
+        });
+    }
+
+    /// Return the total size of the queued frames and forwarded requests.
+    pub(crate) fn size(&self) -> usize {
+        self.queued() + self.forwarded().size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn account_forwarded_requests() {
+        let memory = PeerMemory::default();
+        memory.queue(100);
+        memory.push_forwarded([0, 0, 0, 1], 10);
+        memory.push_forwarded([0, 0, 0, 2], 20);
+        assert_eq!(memory.size(), 130 + 2 * FORWARDED_REQUEST_OVERHEAD);
+
+        memory.retain_forwarded(|req_id| *req_id != [0, 0, 0, 2]);
+        assert_eq!(memory.size(), 110 + FORWARDED_REQUEST_OVERHEAD);
+
+        assert_eq!(memory.pop_forwarded(), Some([0, 0, 0, 1]));
+        assert_eq!(memory.pop_forwarded(), None);
+        memory.dequeue(100);
+        assert_eq!(memory.size(), 0);
+    }
+}
@@ -0,0 +1,264 @@
+//! Builder-style construction of a cable manager.
+//!
+//! A `ManagerBuilder` is returned by `CableManager::builder()`. It starts
+//! from `ManagerOptions::default()` and sets one option per method, so that
+//! a manager differing from the defaults in a few options is configured
+//! without spelling out the whole `ManagerOptions` struct.
+
+use std::{sync::Arc, time::Duration};
+
+use cable::{limits::Limits, signer::Signer, validation::ChannelNormalization};
+
+#[cfg(feature = "chaos")]
+use crate::chaos::ChaosOptions;
+#[cfg(feature = "archive-posts")]
+use crate::manager::ArchivePolicy;
+use crate::{
+    clock::Clock,
+    manager::{CableManager, ManagerOptions, SlowPeerPolicy},
+    retention::RetentionPolicy,
+    self_check::SelfCheck,
+    store::{Keypair, Store},
+};
+
+/// A builder of a `CableManager`, as returned by `CableManager::builder()`.
+///
+/// Each method sets the option of the same name of `ManagerOptions`; see
+/// the documentation of the option for its meaning and default value.
+pub struct ManagerBuilder<S: Store + Clone> {
+    store: S,
+    options: ManagerOptions,
+}
+
+impl<S: Store + Clone> ManagerBuilder<S> {
+    /// Create a builder of a manager over the given store, with the default
+    /// options.
+    pub fn new(store: S) -> Self {
+        ManagerBuilder {
+            store,
+            options: ManagerOptions::default(),
+        }
+    }
+
+    /// Replace all options with the given options.
+    pub fn options(mut self, options: ManagerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the interval at which keepalive pings are sent.
+    pub fn keepalive_interval(mut self, interval: Option<Duration>) -> Self {
+        self.options.keepalive_interval = interval;
+        self
+    }
+
+    /// Set the duration after which an idle peer connection is torn down.
+    pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.options.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the retention policy applied to the store.
+    pub fn retention(mut self, retention: Option<RetentionPolicy>) -> Self {
+        self.options.retention = retention;
+        self
+    }
+
+    /// Set the tolerated clock skew of received posts.
+    pub fn max_clock_skew(mut self, skew: Option<Duration>) -> Self {
+        self.options.max_clock_skew = skew;
+        self
+    }
+
+    /// Set the source of the current time.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.options.clock = clock;
+        self
+    }
+
+    /// Set whether outgoing messages are checked against the specification.
+    pub fn self_check(mut self, self_check: SelfCheck) -> Self {
+        self.options.self_check = self_check;
+        self
+    }
+
+    /// Set whether requests received with an excessive TTL are rejected.
+    pub fn reject_excessive_ttl(mut self, reject: bool) -> Self {
+        self.options.reject_excessive_ttl = reject;
+        self
+    }
+
+    /// Set the normalization applied to channel names given by the local
+    /// user.
+    pub fn channel_normalization(mut self, normalization: ChannelNormalization) -> Self {
+        self.options.channel_normalization = normalization;
+        self
+    }
+
+    /// Set whether posts received without having been requested are stored.
+    pub fn accept_unsolicited_posts(mut self, accept: bool) -> Self {
+        self.options.accept_unsolicited_posts = accept;
+        self
+    }
+
+    /// Set the duration after which an unanswered post request is retried.
+    pub fn post_request_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.options.post_request_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of workers verifying post signatures.
+    pub fn verification_workers(mut self, workers: usize) -> Self {
+        self.options.verification_workers = workers;
+        self
+    }
+
+    /// Set the number of messages of a peer which are handled concurrently.
+    pub fn handler_concurrency(mut self, concurrency: usize) -> Self {
+        self.options.handler_concurrency = concurrency;
+        self
+    }
+
+    /// Set the number of received messages awaiting each handler of a peer.
+    pub fn inbound_queue_size(mut self, size: usize) -> Self {
+        self.options.inbound_queue_size = size;
+        self
+    }
+
+    /// Set the duration for which the IDs of handled requests are
+    /// remembered.
+    pub fn handled_request_ttl(mut self, ttl: Duration) -> Self {
+        self.options.handled_request_ttl = ttl;
+        self
+    }
+
+    /// Set the maximum number of handled request IDs remembered.
+    pub fn handled_request_capacity(mut self, capacity: usize) -> Self {
+        self.options.handled_request_capacity = capacity;
+        self
+    }
+
+    /// Set the number of malformed frames skipped before a connection is
+    /// closed.
+    pub fn malformed_frame_limit(mut self, limit: usize) -> Self {
+        self.options.malformed_frame_limit = limit;
+        self
+    }
+
+    /// Set the duration after which an incomplete write to a peer is
+    /// abandoned.
+    pub fn write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.options.write_timeout = timeout;
+        self
+    }
+
+    /// Set the action taken when sending to a slow peer.
+    pub fn slow_peer_policy(mut self, policy: SlowPeerPolicy) -> Self {
+        self.options.slow_peer_policy = policy;
+        self
+    }
+
+    /// Set the estimated memory which may be held on behalf of a peer.
+    pub fn peer_memory_budget(mut self, budget: Option<usize>) -> Self {
+        self.options.peer_memory_budget = budget;
+        self
+    }
+
+    /// Set the number of protocol violations tolerated from a peer.
+    pub fn violation_limit(mut self, limit: usize) -> Self {
+        self.options.violation_limit = limit;
+        self
+    }
+
+    /// Set whether responses to unknown requests are handled.
+    pub fn accept_unknown_responses(mut self, accept: bool) -> Self {
+        self.options.accept_unknown_responses = accept;
+        self
+    }
+
+    /// Set the limits on the contents of messages and posts.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    /// Set the maximum number of live requests held for a single peer, one
+    /// of the `limits`.
+    pub fn max_live_requests(mut self, max: usize) -> Self {
+        self.options.limits.max_live_requests = max;
+        self
+    }
+
+    /// Set the TTL of the requests made by the local peer.
+    pub fn ttl(mut self, ttl: u8) -> Self {
+        self.options.ttl = ttl;
+        self
+    }
+
+    /// Set whether the manager emits events to the subscribers of
+    /// `CableManager::events()`.
+    pub fn events(mut self, events: bool) -> Self {
+        self.options.events = events;
+        self
+    }
+
+    /// Set whether the delivery of the posts published by the local peer is
+    /// tracked.
+    pub fn track_deliveries(mut self, track: bool) -> Self {
+        self.options.track_deliveries = track;
+        self
+    }
+
+    /// Set whether the capabilities of the local peer are announced to each
+    /// peer on connection.
+    pub fn announce_capabilities(mut self, announce: bool) -> Self {
+        self.options.announce_capabilities = announce;
+        self
+    }
+
+    /// Set whether the manager is read-only.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.options.read_only = read_only;
+        self
+    }
+
+    /// Set the keypair of the local peer, used instead of the keypair of the
+    /// store.
+    pub fn keypair(mut self, keypair: Keypair) -> Self {
+        self.options.keypair = Some(keypair);
+        self
+    }
+
+    /// Set the signer of the posts published by the local peer, used
+    /// instead of any keypair.
+    pub fn signer(mut self, signer: Arc<dyn Signer>) -> Self {
+        self.options.signer = Some(signer);
+        self
+    }
+
+    /// Set the authors whose archive posts hide a channel.
+    #[cfg(feature = "archive-posts")]
+    pub fn archive_policy(mut self, policy: ArchivePolicy) -> Self {
+        self.options.archive_policy = policy;
+        self
+    }
+
+    /// Set the maximum number of bytes of blobs held by the store.
+    #[cfg(feature = "blobs")]
+    pub fn blob_quota(mut self, quota: Option<u64>) -> Self {
+        self.options.blob_quota = quota;
+        self
+    }
+
+    /// Set the faults injected by the manager.
+    #[cfg(feature = "chaos")]
+    pub fn chaos(mut self, chaos: Option<ChaosOptions>) -> Self {
+        self.options.chaos = chaos;
+        self
+    }
+
+    /// Create the manager.
+    pub fn build(self) -> CableManager<S> {
+        CableManager::with_options(self.store, self.options)
+    }
+}
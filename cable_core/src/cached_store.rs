@@ -0,0 +1,609 @@
+//! A read-through cache layer over any implementation of the `Store` trait.
+//!
+//! `CachedStore` keeps the most recently used post payloads and the results
+//! of hash lookups in bounded least-recently-used caches, and the list of
+//! channels in memory. Reads are answered from the caches where possible and
+//! fall through to the wrapped store otherwise. Every write is passed to the
+//! wrapped store and applied to the caches, so the caches never hold stale
+//! data as long as the wrapped store is only modified through the wrapper.
+
+use std::num::NonZeroUsize;
+
+use async_std::sync::{Arc, Mutex, RwLock};
+use cable::{
+    post::Post, Avatar, Channel, ChannelOptions, Error, Hash, Nickname, Payload, ReqId, Timestamp,
+    Topic,
+};
+use lru::LruCache;
+
+use crate::{
+    integrity::IntegrityReport,
+    metrics::{CompactionStats, StoreMetrics},
+    store::{Keypair, PageCursor, PostPage, PublicKey, Store},
+    stream::{EventStream, HashStream, PostStream, StoreEvent},
+};
+
+/// The default capacity of each cache, in entries.
+const DEFAULT_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+/// A store wrapper which caches frequently read data in memory.
+pub struct CachedStore<S: Store + Clone> {
+    /// The wrapped store.
+    store: S,
+    /// Recently used post payloads, indexed by post hash.
+    payloads: Arc<Mutex<LruCache<Hash, Payload>>>,
+    /// Hashes of recently looked-up posts which are held by the store or
+    /// have been deleted, and are therefore not wanted.
+    unwanted: Arc<Mutex<LruCache<Hash, ()>>>,
+    /// All channels in the store, once retrieved from the wrapped store.
+    channels: Arc<RwLock<Option<Vec<Channel>>>>,
+}
+
+impl<S: Store + Clone> CachedStore<S> {
+    /// Wrap the given store with caches of the default capacity.
+    pub fn new(store: S) -> Self {
+        // The default capacity is non-zero.
+        Self::with_capacity(store, NonZeroUsize::new(DEFAULT_CAPACITY).unwrap())
+    }
+
+    /// Wrap the given store with caches holding at most `capacity` entries
+    /// each.
+    pub fn with_capacity(store: S, capacity: NonZeroUsize) -> Self {
+        CachedStore {
+            store,
+            payloads: Arc::new(Mutex::new(LruCache::new(capacity))),
+            unwanted: Arc::new(Mutex::new(LruCache::new(capacity))),
+            channels: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Return a reference to the wrapped store.
+    ///
+    /// Modifying the wrapped store directly may leave the caches stale.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Unwrap the store, discarding the caches.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Discard all cached data.
+    pub async fn clear(&self) {
+        self.payloads.lock().await.clear();
+        self.unwanted.lock().await.clear();
+        *self.channels.write().await = None;
+    }
+
+    /// Remove the given hash from the caches.
+    async fn evict(&self, hash: &Hash) {
+        self.payloads.lock().await.pop(hash);
+        self.unwanted.lock().await.pop(hash);
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Store + Clone> Store for CachedStore<S> {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        self.store.get_keypair().await
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        self.store.set_keypair(keypair).await
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        self.store.list_identities().await
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        self.store.insert_identity(keypair).await
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        self.store.set_active_identity(public_key).await
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        if let Some(channels) = self.channels.read().await.as_ref() {
+            return if channels.is_empty() {
+                None
+            } else {
+                Some(channels.clone())
+            };
+        }
+
+        let channels = self.store.get_channels().await;
+        *self.channels.write().await = Some(channels.clone().unwrap_or_default());
+
+        channels
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        self.store.insert_channel(channel).await;
+
+        // Keep the cached list sorted, matching the ordering of the stores.
+        if let Some(channels) = self.channels.write().await.as_mut() {
+            if let Err(index) = channels.binary_search(channel) {
+                channels.insert(index, channel.to_owned());
+            }
+        }
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        self.store.get_channel_members(channel).await
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        self.store.get_archived_channels().await
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        self.store.is_channel_archived(channel).await
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        self.store.set_channel_archived(channel, archived).await
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        self.store.remove_channel(channel).await;
+
+        if let Some(channels) = self.channels.write().await.as_mut() {
+            channels.retain(|cached| cached != channel);
+        }
+    }
+
+    async fn insert_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        self.store.insert_channel_member(channel, public_key).await
+    }
+
+    async fn is_channel_member(&self, channel: &Channel, public_key: &PublicKey) -> bool {
+        self.store.is_channel_member(channel, public_key).await
+    }
+
+    async fn remove_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        self.store.remove_channel_member(channel, public_key).await
+    }
+
+    async fn get_channel_membership_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        self.store.get_channel_membership_hashes(channel).await
+    }
+
+    async fn remove_channel_membership_hash(&mut self, hash: &Hash) {
+        self.store.remove_channel_membership_hash(hash).await
+    }
+
+    async fn update_channel_membership_hashes(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        self.store
+            .update_channel_membership_hashes(channel, public_key, hash)
+            .await
+    }
+
+    async fn get_ex_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        self.store.get_ex_channel_members(channel).await
+    }
+
+    async fn insert_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        self.store
+            .insert_ex_channel_member(channel, public_key)
+            .await
+    }
+
+    async fn remove_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        self.store
+            .remove_ex_channel_member(channel, public_key)
+            .await
+    }
+
+    async fn get_channel_topic_and_hash(&self, channel: &Channel) -> Option<(Topic, Hash)> {
+        self.store.get_channel_topic_and_hash(channel).await
+    }
+
+    async fn insert_channel_topic(
+        &mut self,
+        channel: &Channel,
+        topic: &Topic,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        self.store
+            .insert_channel_topic(channel, topic, timestamp, hash)
+            .await
+    }
+
+    async fn remove_channel_topic(&mut self, hash: &Hash) {
+        self.store.remove_channel_topic(hash).await
+    }
+
+    async fn get_delete_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        self.store.get_delete_hashes(public_key).await
+    }
+
+    async fn insert_delete_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        self.store.insert_delete_hash(public_key, hash).await
+    }
+
+    async fn get_info_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        self.store.get_info_hashes(public_key).await
+    }
+
+    async fn insert_info_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        self.store.insert_info_hash(public_key, hash).await
+    }
+
+    async fn remove_info_hash(&mut self, hash: &Hash) {
+        self.store.remove_info_hash(hash).await
+    }
+
+    async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        self.store.get_latest_hashes(channel).await
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        self.store.insert_channel_head(channel, hash).await
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        self.store.remove_channel_head(channel, hash).await
+    }
+
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        self.store.insert_post_link(channel, hash, link).await
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        self.store.remove_post_link(channel, hash, link).await
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        self.store.is_post_linked(channel, hash).await
+    }
+
+    async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
+        self.store.get_peer_name_and_hash(public_key).await
+    }
+
+    async fn insert_peer_name(
+        &mut self,
+        public_key: &PublicKey,
+        name: &Nickname,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        self.store
+            .insert_peer_name(public_key, name, timestamp, hash)
+            .await
+    }
+
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        self.store.remove_peer_name(hash).await
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        self.store.get_peer_avatar_and_hash(public_key).await
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        self.store
+            .insert_peer_avatar(public_key, avatar, timestamp, hash)
+            .await
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        self.store.remove_peer_avatar(hash).await
+    }
+
+    async fn get_channel_state_hashes(&self, channel: &Channel) -> Vec<Hash> {
+        self.store.get_channel_state_hashes(channel).await
+    }
+
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
+        self.store.get_posts(opts).await
+    }
+
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        self.store.get_posts_page(channel, before, limit).await
+    }
+
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        self.store.get_mentions(channel).await
+    }
+
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        self.store.insert_mention(channel, timestamp, hash).await
+    }
+
+    async fn remove_mention(&mut self, hash: &Hash) {
+        self.store.remove_mention(hash).await
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        self.store.get_author_index(public_key).await
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        self.store
+            .insert_author_post(public_key, timestamp, hash)
+            .await
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        self.store
+            .remove_author_post(public_key, timestamp, hash)
+            .await
+    }
+
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        self.store.get_replies(parent).await
+    }
+
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        self.store.insert_reply(parent, timestamp, hash).await
+    }
+
+    async fn remove_reply(&mut self, hash: &Hash) {
+        self.store.remove_reply(hash).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        self.store.get_reactions(target).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        self.store.insert_reaction(target, timestamp, hash).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        self.store.remove_reaction(hash).await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        self.store.get_channel_archives(channel).await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        self.store
+            .insert_channel_archive(channel, public_key, hash)
+            .await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        self.store.remove_channel_archive(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        self.store.get_blob(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        self.store.insert_blob(hash, blob).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        self.store.remove_blob(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        self.store.get_blob_usage().await
+    }
+
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        self.store.get_last_read(channel).await
+    }
+
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        self.store.set_last_read(channel, cursor).await
+    }
+
+    async fn get_unread_count(&self, channel: &Channel) -> Result<usize, Error> {
+        self.store.get_unread_count(channel).await
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        self.store.get_post_hashes(opts).await
+    }
+
+    async fn remove_post(&mut self, hash: &Hash) {
+        self.store.remove_post(hash).await
+    }
+
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        self.store.insert_tombstone(hash).await;
+        self.unwanted.lock().await.put(*hash, ());
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        self.store.is_tombstone(hash).await
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        self.store.get_outbound_requests().await
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        self.store.insert_outbound_request(req_id, request).await
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        self.store.remove_outbound_request(req_id).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.store.is_read_only()
+    }
+
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        self.store.begin_batch().await
+    }
+
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        self.store.commit_batch().await
+    }
+
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        self.store.rollback_batch().await
+    }
+
+    async fn compact(&mut self) -> Result<(), Error> {
+        // Purge pending tombstones through the cache, so that the payloads
+        // of the purged posts are evicted.
+        self.purge_tombstones().await;
+
+        self.store.compact().await
+    }
+
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        self.store.compaction_stats().await
+    }
+
+    async fn verify_integrity(&mut self, repair: bool) -> Result<IntegrityReport, Error> {
+        // Check the wrapped store directly, so that cached data neither masks
+        // nor outlives corrupt entries.
+        let report = self.store.verify_integrity(repair).await?;
+        if repair {
+            self.clear().await;
+        }
+
+        Ok(report)
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        let mut metrics = self.store.metrics().await?;
+        metrics.index_sizes.insert(
+            "cached_payloads".to_string(),
+            self.payloads.lock().await.len(),
+        );
+        metrics.index_sizes.insert(
+            "cached_unwanted".to_string(),
+            self.unwanted.lock().await.len(),
+        );
+
+        Ok(metrics)
+    }
+
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    ) {
+        self.store
+            .update_posts(post, channel, timestamp, hash)
+            .await
+    }
+
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload> {
+        if let Some(payload) = self.payloads.lock().await.get(hash) {
+            return Some(payload.clone());
+        }
+
+        let payload = self.store.get_post_payload(hash).await?;
+        self.payloads.lock().await.put(*hash, payload.clone());
+
+        Some(payload)
+    }
+
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload> {
+        let mut payloads = Vec::new();
+        for hash in hashes {
+            if let Some(payload) = self.get_post_payload(hash).await {
+                payloads.push(payload);
+            }
+        }
+
+        payloads
+    }
+
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        self.store.get_post_payload_hashes().await
+    }
+
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
+        self.store.insert_post_payload(hash, payload.clone()).await;
+        self.payloads.lock().await.put(*hash, payload);
+        self.unwanted.lock().await.put(*hash, ());
+    }
+
+    async fn remove_post_payload(&mut self, hash: &Hash) {
+        self.store.remove_post_payload(hash).await;
+        self.evict(hash).await;
+    }
+
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        self.store.watch(channel).await
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        self.store.send_event(channel, event).await
+    }
+
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
+        // Only query the wrapped store for hashes which are not known to be
+        // unwanted.
+        let uncached: Vec<Hash> = {
+            let mut unwanted = self.unwanted.lock().await;
+            hashes
+                .iter()
+                .filter(|hash| unwanted.get(*hash).is_none())
+                .copied()
+                .collect()
+        };
+        if uncached.is_empty() {
+            return uncached;
+        }
+
+        let wanted = self.store.want(&uncached).await;
+
+        let mut unwanted = self.unwanted.lock().await;
+        for hash in uncached.iter().filter(|hash| !wanted.contains(hash)) {
+            unwanted.put(*hash, ());
+        }
+
+        wanted
+    }
+}
@@ -0,0 +1,77 @@
+//! Causal ordering of posts according to the links between them.
+//!
+//! Timestamps are assigned by the clock of each author, so a post may carry
+//! an earlier timestamp than a post it links to when peer clocks are skewed.
+//! Ordering by links ensures that every post follows the posts its author had
+//! seen when publishing it, with timestamps only used to order concurrent
+//! posts.
+
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap},
+};
+
+use cable::{Error, Hash, Post, Timestamp};
+
+/// Sort the given posts in causal order.
+///
+/// Each post follows the posts it links to. Posts which are not ordered by
+/// their links are ordered by timestamp, and then by hash. Links to posts
+/// which are not among the given posts are ignored. Duplicate posts are
+/// removed.
+pub fn causal_order(posts: Vec<Post>) -> Result<Vec<Post>, Error> {
+    let mut indexes: HashMap<Hash, usize> = HashMap::new();
+    let mut nodes: Vec<(Hash, Post)> = Vec::new();
+    for post in posts {
+        let hash = post.hash()?;
+        if let Entry::Vacant(entry) = indexes.entry(hash) {
+            entry.insert(nodes.len());
+            nodes.push((hash, post));
+        }
+    }
+
+    // Count the linked posts preceding each post and record the posts
+    // following each post.
+    let mut preceding = vec![0; nodes.len()];
+    let mut following: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (index, (hash, post)) in nodes.iter().enumerate() {
+        let mut links = post.header.links.clone();
+        links.sort();
+        links.dedup();
+        for link in links.iter().filter(|link| *link != hash) {
+            if let Some(&link_index) = indexes.get(link) {
+                preceding[index] += 1;
+                following[link_index].push(index);
+            }
+        }
+    }
+
+    // Repeatedly emit the earliest post whose linked posts have all been
+    // emitted.
+    let key = |index: usize| -> Reverse<(Timestamp, Hash, usize)> {
+        let (hash, post) = &nodes[index];
+        Reverse((post.get_timestamp(), *hash, index))
+    };
+    let mut ready: BinaryHeap<_> = (0..nodes.len())
+        .filter(|index| preceding[*index] == 0)
+        .map(key)
+        .collect();
+
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(Reverse((_timestamp, _hash, index))) = ready.pop() {
+        order.push(index);
+        for &next in &following[index] {
+            preceding[next] -= 1;
+            if preceding[next] == 0 {
+                ready.push(key(next));
+            }
+        }
+    }
+
+    let mut nodes: Vec<Option<Post>> = nodes.into_iter().map(|(_hash, post)| Some(post)).collect();
+
+    Ok(order
+        .into_iter()
+        .filter_map(|index| nodes[index].take())
+        .collect())
+}
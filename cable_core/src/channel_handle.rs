@@ -0,0 +1,152 @@
+//! Handles to a single channel.
+//!
+//! A `ChannelHandle` is returned by `CableManager::channel()`. It bundles
+//! the operations an application performs on one channel: publishing to it,
+//! reading its topic and members, streaming its posts and closing it. The
+//! requests for the channel are opened on the first call to `posts_live()`,
+//! and the local peer joins the channel before its first post to it.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use cable::{Channel, ChannelOptions, Error, Hash, Topic};
+
+use crate::{
+    manager::CableManager,
+    store::{PublicKey, Store},
+    stream::PostStream,
+};
+
+/// A handle to a channel, as returned by `CableManager::channel()`.
+///
+/// Clones of the handle share the requests for the channel. Dropping the
+/// handle does not close the channel; call `close()` to cancel its requests.
+#[derive(Clone)]
+pub struct ChannelHandle<S: Store + Clone> {
+    manager: CableManager<S>,
+    channel: Channel,
+    /// Whether the requests for the channel have been opened by the handle.
+    open: Arc<AtomicBool>,
+}
+
+impl<S> ChannelHandle<S>
+where
+    S: Store + Clone,
+{
+    pub(crate) fn new(manager: CableManager<S>, channel: Channel) -> Self {
+        ChannelHandle {
+            manager,
+            channel,
+            open: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// The (normalized) name of the channel.
+    pub fn name(&self) -> &Channel {
+        &self.channel
+    }
+
+    /// Query whether the local peer is a member of the channel.
+    pub async fn is_member(&self) -> Result<bool, Error> {
+        let public_key = self.manager.clone().get_public_key().await?;
+
+        Ok(self
+            .manager
+            .store
+            .is_channel_member(&self.channel, &public_key)
+            .await)
+    }
+
+    /// Publish a `post/join` post for the channel if the local peer is not
+    /// yet a member, returning the hash of the post if one was published.
+    pub async fn join(&self) -> Result<Option<Hash>, Error> {
+        if self.is_member().await? {
+            return Ok(None);
+        }
+
+        let hash = self
+            .manager
+            .clone()
+            .post_join(self.channel.as_str())
+            .await?;
+
+        Ok(Some(hash))
+    }
+
+    /// Publish a `post/leave` post for the channel if the local peer is a
+    /// member, returning the hash of the post if one was published.
+    pub async fn leave(&self) -> Result<Option<Hash>, Error> {
+        if !self.is_member().await? {
+            return Ok(None);
+        }
+
+        let hash = self
+            .manager
+            .clone()
+            .post_leave(self.channel.as_str())
+            .await?;
+
+        Ok(Some(hash))
+    }
+
+    /// Publish a text post to the channel and return the hash, joining the
+    /// channel first if the local peer is not yet a member.
+    pub async fn post<T: Into<String>>(&self, text: T) -> Result<Hash, Error> {
+        self.join().await?;
+
+        self.manager
+            .clone()
+            .post_text(self.channel.as_str(), text)
+            .await
+    }
+
+    /// Retrieve the current topic of the channel.
+    pub async fn topic(&self) -> Option<Topic> {
+        self.manager.get_topic(&self.channel).await
+    }
+
+    /// Publish a new topic for the channel and return the hash of the
+    /// `post/topic` post.
+    pub async fn set_topic<T: Into<String>>(&self, topic: T) -> Result<Hash, Error> {
+        self.manager
+            .clone()
+            .post_topic(self.channel.as_str(), topic)
+            .await
+    }
+
+    /// Retrieve the public keys of the members of the channel.
+    pub async fn members(&self) -> Vec<PublicKey> {
+        self.manager
+            .store
+            .get_channel_members(&self.channel)
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Retrieve the stored posts of the channel, continuing to return new
+    /// posts as they are received or published.
+    ///
+    /// The first call opens live requests for the full history of the
+    /// channel, which remain open until the channel is closed.
+    pub async fn posts_live(&self) -> Result<PostStream<'_>, Error> {
+        let channel_opts = ChannelOptions::new(self.channel.as_str(), 0, 0, 0);
+        if !self.open.swap(true, Ordering::SeqCst) {
+            if let Err(err) = self.manager.send_channel_requests(&channel_opts).await {
+                self.open.store(false, Ordering::SeqCst);
+                return Err(err);
+            }
+        }
+
+        Ok(self.manager.get_posts_live(&channel_opts).await)
+    }
+
+    /// Cancel the requests for the channel. The local peer remains a member
+    /// of the channel; call `leave()` to publish a `post/leave` post.
+    pub async fn close(&self) -> Result<(), Error> {
+        self.open.store(false, Ordering::SeqCst);
+
+        self.manager.close_channel(&self.channel).await
+    }
+}
@@ -0,0 +1,66 @@
+//! Fault injection in the manager, for shaking out ordering assumptions in
+//! tests.
+//!
+//! With the `chaos` feature enabled, a manager configured with
+//! `ChaosOptions` randomly delays the handling of received messages, drops
+//! outgoing messages and reorders the writes to each peer. Faults are drawn
+//! from a random number generator seeded by the options, although the
+//! scheduling of tasks means runs are not exactly reproducible.
+
+use std::{sync::Mutex, time::Duration};
+
+/// The faults injected by a manager.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChaosOptions {
+    /// The seed of the random number generator drawing the faults.
+    pub seed: u64,
+    /// The maximum delay before each received message is handled; each
+    /// delay is drawn uniformly up to it.
+    pub max_handler_delay: Duration,
+    /// The fraction of outgoing messages dropped, between 0 and 1.
+    pub drop_rate: f64,
+    /// The fraction of writes to a peer swapped with the write queued after
+    /// them, between 0 and 1.
+    pub reorder_rate: f64,
+}
+
+/// The source of the faults injected by a manager.
+#[derive(Debug)]
+pub(crate) struct Chaos {
+    options: ChaosOptions,
+    rng: Mutex<fastrand::Rng>,
+}
+
+impl Chaos {
+    pub(crate) fn new(options: ChaosOptions) -> Self {
+        Chaos {
+            rng: Mutex::new(fastrand::Rng::with_seed(options.seed)),
+            options,
+        }
+    }
+
+    /// Return the delay before the next received message is handled.
+    pub(crate) fn handler_delay(&self) -> Duration {
+        let max = self.options.max_handler_delay.as_micros() as u64;
+        if max == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_micros(self.rng.lock().unwrap().u64(..=max))
+    }
+
+    /// Return whether the next outgoing message is dropped.
+    pub(crate) fn drop_message(&self) -> bool {
+        self.chance(self.options.drop_rate)
+    }
+
+    /// Return whether the next write is swapped with the write queued after
+    /// it.
+    pub(crate) fn reorder_write(&self) -> bool {
+        self.chance(self.options.reorder_rate)
+    }
+
+    fn chance(&self, rate: f64) -> bool {
+        rate > 0.0 && self.rng.lock().unwrap().f64() < rate
+    }
+}
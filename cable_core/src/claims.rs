@@ -0,0 +1,204 @@
+//! Sanity checks of the claims made by messages received from peers.
+//!
+//! A message may decode without error and yet make claims which no honest
+//! peer would make: more channels than could plausibly exist, the same hash
+//! advertised twice in one response or more posts than were requested. Such
+//! messages are rejected before they are handled and count as violations of
+//! the protocol by the peer which sent them.
+//!
+//! Counts and lengths claiming more than the message holds, such as a hash
+//! count beyond the hashes of a response or a post length beyond the end of
+//! the frame, are rejected by the decoder and are not checked again here.
+
+use std::collections::HashSet;
+
+use cable::{
+    error::CableErrorKind,
+    limits::Limits,
+    message::{MessageBody, RequestBody, ResponseBody},
+    Error, Message,
+};
+
+/// Return a violation error for the given message.
+fn violation<T>(msg: &Message, violation: impl Into<String>) -> Result<T, Error> {
+    CableErrorKind::MessageSpecViolation {
+        msg_type: msg.message_type(),
+        violation: violation.into(),
+    }
+    .raise()
+}
+
+/// Check the claims of the given message received from a peer, returning a
+/// violation error if any is implausible.
+///
+/// A response is checked against the request it answers, if that request
+/// is known, and against the given limits.
+pub(crate) fn check_claims(
+    msg: &Message,
+    request: Option<&Message>,
+    limits: &Limits,
+) -> Result<(), Error> {
+    let request = request.map(|request| &request.body);
+
+    match &msg.body {
+        MessageBody::Request { body, .. } => match body {
+            RequestBody::ChannelTimeRange {
+                time_start,
+                time_end,
+                ..
+            } if *time_end != 0 && time_end < time_start => violation(
+                msg,
+                format!("time_end {} precedes time_start {}", time_end, time_start),
+            ),
+            RequestBody::ChannelState { future, .. } if *future > 1 => {
+                violation(msg, format!("future is {}; expected 0 or 1", future))
+            }
+            _ => Ok(()),
+        },
+        MessageBody::Response { body } => match body {
+            ResponseBody::Hash { hashes } => {
+                let mut unique = HashSet::with_capacity(hashes.len());
+                if !hashes.iter().all(|hash| unique.insert(hash)) {
+                    return violation(msg, "hash response repeats a hash");
+                }
+
+                let limit = match request {
+                    Some(MessageBody::Request {
+                        body: RequestBody::ChannelTimeRange { limit, .. },
+                        ..
+                    }) => *limit,
+                    _ => 0,
+                };
+                if limit != 0 && hashes.len() as u64 > limit {
+                    return violation(
+                        msg,
+                        format!(
+                            "hash response includes {} hashes; {} were requested",
+                            hashes.len(),
+                            limit
+                        ),
+                    );
+                }
+
+                Ok(())
+            }
+            ResponseBody::Post { posts } => {
+                if let Some(MessageBody::Request {
+                    body: RequestBody::Post { hashes },
+                    ..
+                }) = request
+                {
+                    if posts.len() > hashes.len() {
+                        return violation(
+                            msg,
+                            format!(
+                                "post response includes {} posts; {} were requested",
+                                posts.len(),
+                                hashes.len()
+                            ),
+                        );
+                    }
+                }
+
+                Ok(())
+            }
+            ResponseBody::ChannelList { channels } => {
+                let limit = match request {
+                    Some(MessageBody::Request {
+                        body: RequestBody::ChannelList { limit, .. },
+                        ..
+                    }) if *limit != 0 => (*limit).min(limits.max_channels as u64) as usize,
+                    _ => limits.max_channels,
+                };
+                if channels.len() > limit {
+                    return violation(
+                        msg,
+                        format!(
+                            "channel list response includes {} channels; at most {} expected",
+                            channels.len(),
+                            limit
+                        ),
+                    );
+                }
+
+                let mut unique = HashSet::with_capacity(channels.len());
+                for channel in channels {
+                    limits.check_channel(channel)?;
+                    if !unique.insert(channel) {
+                        return violation(msg, format!("channel {} is repeated", channel));
+                    }
+                }
+
+                Ok(())
+            }
+            #[cfg(feature = "blobs")]
+            ResponseBody::Blob {
+                hash,
+                size,
+                start,
+                data,
+            } => {
+                if start.saturating_add(data.len() as u64) > *size {
+                    return violation(
+                        msg,
+                        format!(
+                            "blob response includes bytes {} to {} of a blob of {} bytes",
+                            start,
+                            start.saturating_add(data.len() as u64),
+                            size
+                        ),
+                    );
+                }
+
+                if let Some(MessageBody::Request {
+                    body:
+                        RequestBody::Blob {
+                            hash: requested_hash,
+                            start: requested_start,
+                            ..
+                        },
+                    ..
+                }) = request
+                {
+                    if hash != requested_hash || (*size != 0 && start != requested_start) {
+                        return violation(msg, "blob response does not match the request");
+                    }
+                }
+
+                Ok(())
+            }
+        },
+        MessageBody::Unrecognized { .. } => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cable::constants::NO_CIRCUIT;
+
+    use super::*;
+
+    #[test]
+    fn reject_implausible_responses() {
+        let limits = Limits::DEFAULT;
+        let hashes = Message::hash_response(NO_CIRCUIT, [0, 0, 0, 1], vec![[1; 32], [1; 32]]);
+        assert!(check_claims(&hashes, None, &limits).is_err());
+
+        let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, vec![[1; 32]]);
+        let posts = Message::post_response(NO_CIRCUIT, [0, 0, 0, 1], vec![vec![1], vec![2]]);
+        assert!(check_claims(&posts, None, &limits).is_ok());
+        assert!(check_claims(&posts, Some(&request), &limits).is_err());
+
+        let channels = (0..=limits.max_channels)
+            .map(|i| format!("channel {}", i))
+            .collect();
+        let channels = Message::channel_list_response(NO_CIRCUIT, [0, 0, 0, 1], channels);
+        assert!(check_claims(&channels, None, &limits).is_err());
+
+        let request = Message::channel_list_request(NO_CIRCUIT, [0, 0, 0, 1], 0, 0, 1);
+        let channels = vec!["a".to_string(), "b".to_string()];
+        let channels = Message::channel_list_response(NO_CIRCUIT, [0, 0, 0, 1], channels);
+        assert!(check_claims(&channels, None, &limits).is_ok());
+        assert!(check_claims(&channels, Some(&request), &limits).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Sources of the current time.
+//!
+//! The manager reads the time from a `Clock` when timestamping new posts and
+//! applying a retention policy, and passes it to the store where needed. Tests
+//! may substitute a `MockClock` to make time-dependent behaviour
+//! deterministic.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    convert::TryInto,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cable::{Error, Timestamp};
+
+/// A source of the current time.
+pub trait Clock: Debug + Send + Sync {
+    /// Return the current time in milliseconds since the UNIX epoch.
+    fn now(&self) -> Result<Timestamp, Error>;
+}
+
+/// A clock reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> Result<Timestamp, Error> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_millis()
+            // Convert from u128 to u64.
+            .try_into()?;
+
+        Ok(timestamp)
+    }
+
+    // The system time is unavailable in the browser, so the time is read from
+    // the JavaScript `Date` instead.
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> Result<Timestamp, Error> {
+        Ok(js_sys::Date::now() as Timestamp)
+    }
+}
+
+/// A clock which only moves when it is set or advanced.
+///
+/// Clones share the same time, so a clone kept by a test controls the clock
+/// of the manager to which the original was given.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    now: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Create a new clock reading the given timestamp.
+    pub fn new(now: Timestamp) -> Self {
+        MockClock {
+            now: Arc::new(AtomicU64::new(now)),
+        }
+    }
+
+    /// Set the time of the clock.
+    pub fn set(&self, now: Timestamp) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        self.now
+            .fetch_add(duration.as_millis() as Timestamp, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Result<Timestamp, Error> {
+        Ok(self.now.load(Ordering::SeqCst))
+    }
+}
@@ -0,0 +1,85 @@
+//! Tracking of the delivery of posts published by the local peer.
+//!
+//! Peers learn the hashes of new posts from hash responses and then request
+//! the posts they want by hash. A published post is therefore considered
+//! delivered once a remote peer first requests it, which is reported so that
+//! clients can display a post as sent or delivered.
+
+use std::collections::HashMap;
+
+use async_std::channel::{self, Receiver, Sender};
+use cable::Hash;
+
+use crate::manager::PeerId;
+
+/// The delivery state of a post published by the local peer, as returned by
+/// `CableManager::delivery_status()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// The post has been published but not yet requested by any peer.
+    Sent,
+    /// The post has been requested by at least one peer.
+    Delivered {
+        /// The ID of the first peer which requested the post.
+        peer_id: PeerId,
+    },
+}
+
+/// The delivery states of the tracked posts and the tasks awaiting their
+/// delivery.
+#[derive(Default)]
+pub(crate) struct Deliveries {
+    statuses: HashMap<Hash, DeliveryStatus>,
+    waiters: HashMap<Hash, Vec<Sender<PeerId>>>,
+}
+
+impl Deliveries {
+    /// Start tracking the delivery of the post with the given hash.
+    pub(crate) fn track(&mut self, hash: Hash) {
+        self.statuses.entry(hash).or_insert(DeliveryStatus::Sent);
+    }
+
+    /// Stop tracking the delivery of the post with the given hash, ending
+    /// the wait of any task awaiting it.
+    pub(crate) fn forget(&mut self, hash: &Hash) {
+        self.statuses.remove(hash);
+        self.waiters.remove(hash);
+    }
+
+    /// Return the delivery state of the post with the given hash, if it is
+    /// tracked.
+    pub(crate) fn status(&self, hash: &Hash) -> Option<DeliveryStatus> {
+        self.statuses.get(hash).copied()
+    }
+
+    /// Return a receiver of the ID of the peer to which the post with the
+    /// given hash is first delivered, if the post is tracked.
+    pub(crate) fn wait(&mut self, hash: &Hash) -> Option<Receiver<PeerId>> {
+        let (sender, receiver) = channel::bounded(1);
+        match self.statuses.get(hash)? {
+            DeliveryStatus::Sent => self.waiters.entry(*hash).or_default().push(sender),
+            DeliveryStatus::Delivered { peer_id } => {
+                let _ = sender.try_send(*peer_id);
+            }
+        }
+
+        Some(receiver)
+    }
+
+    /// Record that the post with the given hash has been requested by the
+    /// given peer, returning `true` if this is the first delivery of a
+    /// tracked post.
+    pub(crate) fn deliver(&mut self, hash: &Hash, peer_id: PeerId) -> bool {
+        match self.statuses.get_mut(hash) {
+            Some(status @ DeliveryStatus::Sent) => {
+                *status = DeliveryStatus::Delivered { peer_id };
+                for waiter in self.waiters.remove(hash).unwrap_or_default() {
+                    let _ = waiter.try_send(peer_id);
+                }
+
+                true
+            }
+            _ => false,
+        }
+    }
+}
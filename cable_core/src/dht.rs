@@ -0,0 +1,728 @@
+//! Peer discovery on the BitTorrent mainline DHT.
+//!
+//! `DhtDiscovery` runs a node of the DHT described by BEP 5 and announces
+//! and looks up peers under the discovery key of a cabal. The DHT addresses
+//! peers by 20-byte info hashes, so the first 20 bytes of the discovery key
+//! are used; as the discovery key is a digest of the cabal key, the cabal key
+//! itself is never shared with the DHT.
+//!
+//! A node bootstraps from the addresses of known nodes (for example
+//! `router.bittorrent.com:6881` for the public DHT, or other peers of the
+//! cabal on a private network) and then keeps a table of the nodes it has
+//! heard from. Lookups query the closest known nodes for peers, moving
+//! towards the info hash until no closer nodes are returned; announcements
+//! store the local peer on the closest nodes found.
+//!
+//! Only IPv4 is supported. A peer is announced with the port of its address,
+//! while its IP is the one from which the DHT node sends, as seen by the
+//! nodes storing the announcement.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use async_std::{channel, future, net::UdpSocket, prelude::FutureExt, sync::Arc, task};
+use cable::Error;
+use futures::future::join_all;
+use log::debug;
+use sodiumoxide::{crypto::generichash, randombytes::randombytes_into};
+
+use crate::{
+    bencode::Value,
+    discovery::{Discovery, DiscoveryKey},
+};
+
+/// The identifier of a node, and the key under which peers are announced.
+type NodeId = [u8; 20];
+
+/// The node queried, and the sender awaiting its response.
+type PendingQuery = (SocketAddrV4, channel::Sender<Value>);
+
+/// The number of closest nodes queried by a lookup and announced to.
+const K: usize = 8;
+
+/// The maximum number of rounds of queries in a lookup.
+const MAX_ROUNDS: usize = 16;
+
+/// The maximum number of nodes in the routing table.
+const MAX_NODES: usize = 512;
+
+/// The maximum number of info hashes for which peers are stored.
+const MAX_INFO_HASHES: usize = 4096;
+
+/// The maximum number of peers stored, and returned, per info hash.
+const MAX_PEERS: usize = 50;
+
+/// The time after which a stored announcement expires.
+const PEER_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// The interval at which the secret deriving tokens is replaced. Tokens of
+/// the previous secret remain valid, so a token is accepted for at least this
+/// long and at most twice as long.
+const SECRET_ROTATION: Duration = Duration::from_secs(5 * 60);
+
+/// Parameters of a `DhtDiscovery` node.
+#[derive(Clone, Debug)]
+pub struct DhtOptions {
+    /// The address to which the UDP socket of the node is bound.
+    pub bind_addr: SocketAddr,
+    /// The addresses of known nodes from which to bootstrap.
+    pub bootstrap: Vec<SocketAddr>,
+    /// Time to wait for a node to respond to a query.
+    pub query_timeout: Duration,
+}
+
+impl Default for DhtOptions {
+    fn default() -> Self {
+        DhtOptions {
+            bind_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            bootstrap: Vec::new(),
+            query_timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+/// A discovery backend running a node of the BitTorrent mainline DHT.
+///
+/// All clones share the same node, which stops when the last clone is
+/// dropped.
+#[derive(Clone)]
+pub struct DhtDiscovery {
+    node: Arc<Node>,
+    _closing: Arc<channel::Sender<()>>,
+}
+
+impl DhtDiscovery {
+    /// Bind a node to the address of the given options and bootstrap it from
+    /// the given known nodes.
+    pub async fn bind(options: DhtOptions) -> Result<Self, Error> {
+        let socket = UdpSocket::bind(options.bind_addr).await?;
+
+        let mut id = [0; 20];
+        randombytes_into(&mut id);
+
+        let node = Arc::new(Node {
+            id,
+            secrets: Mutex::new(Secrets::new()),
+            socket,
+            options,
+            table: Mutex::new(Vec::new()),
+            peers: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+            last_tid: AtomicU16::new(0),
+        });
+
+        let (closing, closed) = channel::bounded(1);
+        task::spawn(receive(node.clone(), closed.clone()));
+        task::spawn(rotate_secrets(node.clone(), closed));
+
+        let dht = DhtDiscovery {
+            node,
+            _closing: Arc::new(closing),
+        };
+        dht.bootstrap().await;
+
+        Ok(dht)
+    }
+
+    /// Return the address to which the node is bound.
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.node.socket.local_addr()?)
+    }
+
+    /// Return the number of nodes in the routing table.
+    pub fn node_count(&self) -> usize {
+        self.node.table.lock().unwrap().len()
+    }
+
+    /// Look up the nodes closest to the local node, filling the routing
+    /// table.
+    ///
+    /// The bootstrap nodes are queried whenever the routing table is empty,
+    /// so calling this again is only needed to refresh the table.
+    pub async fn bootstrap(&self) {
+        let id = self.node.id;
+        self.node.lookup(id, "find_node").await;
+    }
+}
+
+#[async_trait::async_trait]
+impl Discovery for DhtDiscovery {
+    /// Announce the port of the given address on the closest nodes.
+    ///
+    /// Succeeds even when no node could be reached, as a `Dialer` announces
+    /// again on its next round.
+    async fn announce(&self, key: &DiscoveryKey, addr: SocketAddr) -> Result<(), Error> {
+        if !addr.is_ipv4() {
+            return Err("only IPv4 addresses may be announced on the DHT".into());
+        }
+
+        let info_hash = info_hash(key);
+        let lookup = self.node.lookup(info_hash, "get_peers").await;
+        let announcements = lookup
+            .responders
+            .into_iter()
+            .filter_map(|(addr, token)| Some((addr, token?)))
+            .take(K)
+            .map(|(node_addr, token)| {
+                self.node.query(
+                    node_addr,
+                    "announce_peer",
+                    [
+                        ("info_hash", Value::from(&info_hash[..])),
+                        ("port", Value::from(addr.port() as i64)),
+                        ("token", Value::Bytes(token)),
+                    ],
+                )
+            });
+        let stored = join_all(announcements).await.into_iter().flatten().count();
+        debug!("Announced {} on {} DHT nodes", addr, stored);
+
+        Ok(())
+    }
+
+    async fn lookup(&self, key: &DiscoveryKey) -> Result<Vec<SocketAddr>, Error> {
+        let info_hash = info_hash(key);
+        let mut peers = self.node.lookup(info_hash, "get_peers").await.peers;
+        peers.extend(self.node.stored_peers(&info_hash));
+
+        let mut addrs: Vec<SocketAddr> = peers.into_iter().map(SocketAddr::V4).collect();
+        addrs.sort();
+
+        Ok(addrs)
+    }
+}
+
+/// The state of a node, shared with its receiving task.
+struct Node {
+    id: NodeId,
+    /// The secrets from which the tokens handed to querying nodes are derived.
+    secrets: Mutex<Secrets>,
+    socket: UdpSocket,
+    options: DhtOptions,
+    /// The known nodes, in no particular order.
+    table: Mutex<Vec<(NodeId, SocketAddrV4)>>,
+    /// The peers announced on this node, with the time of their announcement.
+    peers: Mutex<HashMap<NodeId, HashMap<SocketAddrV4, Instant>>>,
+    /// The queries awaiting a response, by transaction id.
+    pending: Mutex<HashMap<[u8; 2], PendingQuery>>,
+    last_tid: AtomicU16,
+}
+
+/// The current and previous secrets deriving tokens, as in BEP 5.
+struct Secrets {
+    current: [u8; 32],
+    previous: [u8; 32],
+}
+
+impl Secrets {
+    fn new() -> Self {
+        let mut current = [0; 32];
+        randombytes_into(&mut current);
+
+        Secrets {
+            current,
+            previous: current,
+        }
+    }
+
+    /// Replace the current secret, keeping it valid as the previous one.
+    fn rotate(&mut self) {
+        self.previous = self.current;
+        randombytes_into(&mut self.current);
+    }
+
+    /// Return the token a node at the given IP must present to announce: a
+    /// BLAKE2b digest of the IP keyed with the current secret.
+    fn token(&self, ip: &Ipv4Addr) -> Vec<u8> {
+        token(&self.current, ip)
+    }
+
+    /// Return whether the token was handed to a node at the given IP under
+    /// the current or the previous secret.
+    fn is_valid(&self, ip: &Ipv4Addr, token: &[u8]) -> bool {
+        [self.current, self.previous]
+            .iter()
+            .any(|secret| self::token(secret, ip) == token)
+    }
+}
+
+/// The outcome of a lookup.
+#[derive(Default)]
+struct Lookup {
+    /// The closest nodes which responded, with the token they returned.
+    responders: Vec<(SocketAddrV4, Option<Vec<u8>>)>,
+    /// The peers returned by the queried nodes.
+    peers: HashSet<SocketAddrV4>,
+}
+
+/// A node queried, or to be queried, by a lookup; bootstrap nodes have no
+/// known id until they respond.
+struct Candidate {
+    id: Option<NodeId>,
+    addr: SocketAddrV4,
+}
+
+impl Node {
+    /// Send a query to the given node and wait for its response.
+    ///
+    /// Returns `None` if the node responded with an error or did not respond
+    /// in time, in which case it is removed from the routing table.
+    async fn query<const N: usize>(
+        &self,
+        addr: SocketAddrV4,
+        method: &str,
+        args: [(&str, Value); N],
+    ) -> Option<Value> {
+        let mut args = Value::dict(args);
+        if let Value::Dict(entries) = &mut args {
+            entries.insert(b"id".to_vec(), Value::from(&self.id[..]));
+        }
+
+        let tid = self.last_tid.fetch_add(1, Ordering::Relaxed).to_be_bytes();
+        let query = Value::dict([
+            ("t", Value::from(&tid[..])),
+            ("y", Value::from("q")),
+            ("q", Value::from(method)),
+            ("a", args),
+        ]);
+
+        let (sender, receiver) = channel::bounded(1);
+        self.pending.lock().unwrap().insert(tid, (addr, sender));
+        let response = match self.socket.send_to(&query.encode(), addr).await {
+            Ok(_) => future::timeout(self.options.query_timeout, receiver.recv())
+                .await
+                .ok()
+                .and_then(Result::ok),
+            Err(err) => {
+                debug!("Failed to send DHT query to {}: {}", addr, err);
+                None
+            }
+        };
+        self.pending.lock().unwrap().remove(&tid);
+
+        match response.as_ref().and_then(|response| response.get("r")) {
+            Some(response) => {
+                if let Some(id) = response.get("id").and_then(node_id) {
+                    self.insert_node(id, addr);
+                }
+                Some(response.clone())
+            }
+            None => {
+                self.table
+                    .lock()
+                    .unwrap()
+                    .retain(|(_, node_addr)| *node_addr != addr);
+                None
+            }
+        }
+    }
+
+    /// Query the nodes closest to the target with the given method, moving
+    /// towards the target until no closer nodes are returned.
+    async fn lookup(&self, target: NodeId, method: &str) -> Lookup {
+        let mut candidates: Vec<Candidate> = self
+            .closest(&target, K)
+            .into_iter()
+            .map(|(id, addr)| Candidate { id: Some(id), addr })
+            .collect();
+        if candidates.is_empty() {
+            candidates = self
+                .options
+                .bootstrap
+                .iter()
+                .filter_map(|addr| match addr {
+                    SocketAddr::V4(addr) => Some(Candidate {
+                        id: None,
+                        addr: *addr,
+                    }),
+                    SocketAddr::V6(_) => None,
+                })
+                .collect();
+        }
+
+        let key = if method == "get_peers" {
+            "info_hash"
+        } else {
+            "target"
+        };
+        let mut queried = HashSet::new();
+        let mut tokens = HashMap::new();
+        let mut lookup = Lookup::default();
+
+        for _ in 0..MAX_ROUNDS {
+            // Nodes of unknown id are queried first, then the closest nodes.
+            candidates.sort_by_key(|candidate| candidate.id.map(|id| distance(&id, &target)));
+            let round: Vec<SocketAddrV4> = candidates
+                .iter()
+                .take(K)
+                .map(|candidate| candidate.addr)
+                .filter(|addr| queried.insert(*addr))
+                .collect();
+            if round.is_empty() {
+                break;
+            }
+
+            let queries = round.iter().map(|addr| async move {
+                let response = self
+                    .query(*addr, method, [(key, Value::from(&target[..]))])
+                    .await;
+                (*addr, response)
+            });
+            for (addr, response) in join_all(queries).await {
+                let response = match response {
+                    Some(response) => response,
+                    None => {
+                        candidates.retain(|candidate| candidate.addr != addr);
+                        continue;
+                    }
+                };
+
+                let id = response.get("id").and_then(node_id);
+                if let Some(candidate) = candidates.iter_mut().find(|c| c.addr == addr) {
+                    candidate.id = id;
+                }
+                if let Some(token) = response.get("token").and_then(Value::as_bytes) {
+                    tokens.insert(addr, token.to_vec());
+                }
+                for value in response
+                    .get("values")
+                    .and_then(Value::as_list)
+                    .unwrap_or(&[])
+                {
+                    if let Some(peer) = value.as_bytes().and_then(decode_peer) {
+                        lookup.peers.insert(peer);
+                    }
+                }
+                let nodes = response
+                    .get("nodes")
+                    .and_then(Value::as_bytes)
+                    .unwrap_or(&[]);
+                for (id, addr) in decode_nodes(nodes) {
+                    if id != self.id && !candidates.iter().any(|c| c.addr == addr) {
+                        candidates.push(Candidate { id: Some(id), addr });
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by_key(|candidate| candidate.id.map(|id| distance(&id, &target)));
+        lookup.responders = candidates
+            .into_iter()
+            .filter(|candidate| queried.contains(&candidate.addr))
+            .map(|candidate| (candidate.addr, tokens.remove(&candidate.addr)))
+            .collect();
+
+        lookup
+    }
+
+    /// Handle a message received from the given address.
+    async fn handle(&self, message: Value, from: SocketAddrV4) {
+        let tid = match message.get("t").and_then(Value::as_bytes) {
+            Some(tid) => tid.to_vec(),
+            None => return,
+        };
+
+        match message.get("y").and_then(Value::as_bytes) {
+            Some([b'q']) => {
+                let reply = match self.respond(&message, from) {
+                    Ok(response) => Value::dict([
+                        ("t", Value::Bytes(tid)),
+                        ("y", Value::from("r")),
+                        ("r", response),
+                    ]),
+                    Err((code, reason)) => Value::dict([
+                        ("t", Value::Bytes(tid)),
+                        ("y", Value::from("e")),
+                        (
+                            "e",
+                            Value::List(vec![Value::from(code), Value::from(reason)]),
+                        ),
+                    ]),
+                };
+                if let Err(err) = self.socket.send_to(&reply.encode(), from).await {
+                    debug!("Failed to send DHT response to {}: {}", from, err);
+                }
+            }
+            Some([b'r']) | Some([b'e']) => {
+                let tid = match <[u8; 2]>::try_from(tid.as_slice()) {
+                    Ok(tid) => tid,
+                    Err(_) => return,
+                };
+                let mut pending = self.pending.lock().unwrap();
+                // Ignore responses from nodes other than the one queried.
+                if matches!(pending.get(&tid), Some((addr, _)) if *addr == from) {
+                    if let Some((_, sender)) = pending.remove(&tid) {
+                        let _ = sender.try_send(message);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Return the response to the given query, or an error code and reason.
+    fn respond(&self, query: &Value, from: SocketAddrV4) -> Result<Value, (i64, &'static str)> {
+        let args = query.get("a").ok_or((203, "missing arguments"))?;
+        let id = args
+            .get("id")
+            .and_then(node_id)
+            .ok_or((203, "missing node id"))?;
+        self.insert_node(id, from);
+
+        let mut response = BTreeMap::new();
+        response.insert(b"id".to_vec(), Value::from(&self.id[..]));
+
+        let method = query.get("q").and_then(Value::as_bytes).unwrap_or(&[]);
+        match method {
+            b"ping" => (),
+            b"find_node" => {
+                let target = args
+                    .get("target")
+                    .and_then(node_id)
+                    .ok_or((203, "missing target"))?;
+                response.insert(b"nodes".to_vec(), self.encode_closest(&target));
+            }
+            b"get_peers" => {
+                let info_hash = args
+                    .get("info_hash")
+                    .and_then(node_id)
+                    .ok_or((203, "missing info hash"))?;
+                let peers = self.stored_peers(&info_hash);
+                if !peers.is_empty() {
+                    let values = peers.iter().map(|peer| Value::Bytes(encode_peer(peer)));
+                    response.insert(b"values".to_vec(), Value::List(values.collect()));
+                }
+                response.insert(b"nodes".to_vec(), self.encode_closest(&info_hash));
+                response.insert(
+                    b"token".to_vec(),
+                    Value::Bytes(self.secrets.lock().unwrap().token(from.ip())),
+                );
+            }
+            b"announce_peer" => {
+                let info_hash = args
+                    .get("info_hash")
+                    .and_then(node_id)
+                    .ok_or((203, "missing info hash"))?;
+                let token = args.get("token").and_then(Value::as_bytes);
+                if !token
+                    .is_some_and(|token| self.secrets.lock().unwrap().is_valid(from.ip(), token))
+                {
+                    return Err((203, "bad token"));
+                }
+                let port = if args.get("implied_port").and_then(Value::as_int) == Some(1) {
+                    from.port()
+                } else {
+                    args.get("port")
+                        .and_then(Value::as_int)
+                        .and_then(|port| u16::try_from(port).ok())
+                        .ok_or((203, "missing port"))?
+                };
+                self.store_peer(info_hash, SocketAddrV4::new(*from.ip(), port));
+            }
+            _ => return Err((204, "method unknown")),
+        }
+
+        Ok(Value::Dict(response))
+    }
+
+    /// Add or update a node in the routing table, replacing the farthest node
+    /// from the local node when the table is full.
+    fn insert_node(&self, id: NodeId, addr: SocketAddrV4) {
+        if id == self.id {
+            return;
+        }
+
+        let mut table = self.table.lock().unwrap();
+        if let Some(entry) = table.iter_mut().find(|(node_id, _)| *node_id == id) {
+            entry.1 = addr;
+        } else if table.len() < MAX_NODES {
+            table.push((id, addr));
+        } else if let Some(farthest) = table
+            .iter_mut()
+            .max_by_key(|(node_id, _)| distance(node_id, &self.id))
+        {
+            if distance(&id, &self.id) < distance(&farthest.0, &self.id) {
+                *farthest = (id, addr);
+            }
+        }
+    }
+
+    /// Return the given number of known nodes closest to the target.
+    fn closest(&self, target: &NodeId, count: usize) -> Vec<(NodeId, SocketAddrV4)> {
+        let mut nodes = self.table.lock().unwrap().clone();
+        nodes.sort_by_key(|(id, _)| distance(id, target));
+        nodes.truncate(count);
+
+        nodes
+    }
+
+    fn encode_closest(&self, target: &NodeId) -> Value {
+        let mut nodes = Vec::new();
+        for (id, addr) in self.closest(target, K) {
+            nodes.extend_from_slice(&id);
+            nodes.extend_from_slice(&encode_peer(&addr));
+        }
+
+        Value::Bytes(nodes)
+    }
+
+    /// Return the unexpired peers announced on this node for the info hash.
+    fn stored_peers(&self, info_hash: &NodeId) -> Vec<SocketAddrV4> {
+        self.peers
+            .lock()
+            .unwrap()
+            .get(info_hash)
+            .map(|peers| {
+                peers
+                    .iter()
+                    .filter(|(_, announced)| announced.elapsed() < PEER_TTL)
+                    .map(|(peer, _)| *peer)
+                    .take(MAX_PEERS)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn store_peer(&self, info_hash: NodeId, peer: SocketAddrV4) {
+        let mut peers = self.peers.lock().unwrap();
+        if peers.len() >= MAX_INFO_HASHES && !peers.contains_key(&info_hash) {
+            return;
+        }
+
+        let peers = peers.entry(info_hash).or_default();
+        peers.retain(|_, announced| announced.elapsed() < PEER_TTL);
+        if peers.len() < MAX_PEERS || peers.contains_key(&peer) {
+            peers.insert(peer, Instant::now());
+        }
+    }
+}
+
+/// Rotate the secrets of the node until the last `DhtDiscovery` is dropped.
+async fn rotate_secrets(node: Arc<Node>, closed: channel::Receiver<()>) {
+    loop {
+        let elapsed = async {
+            task::sleep(SECRET_ROTATION).await;
+            true
+        }
+        .race(async {
+            let _ = closed.recv().await;
+            false
+        })
+        .await;
+        if !elapsed {
+            break;
+        }
+
+        node.secrets.lock().unwrap().rotate();
+    }
+}
+
+/// Receive and handle messages until the last `DhtDiscovery` is dropped.
+async fn receive(node: Arc<Node>, closed: channel::Receiver<()>) {
+    let mut buf = vec![0; 2048];
+    loop {
+        let received = async { Some(node.socket.recv_from(&mut buf).await) }
+            .race(async {
+                let _ = closed.recv().await;
+                None
+            })
+            .await;
+        let (len, from) = match received {
+            Some(Ok((len, SocketAddr::V4(from)))) => (len, from),
+            Some(Ok(_)) => continue,
+            Some(Err(err)) => {
+                debug!("Failed to receive from the DHT: {}", err);
+                continue;
+            }
+            None => break,
+        };
+
+        match Value::decode(&buf[..len]) {
+            Ok(message) => node.handle(message, from).await,
+            Err(err) => debug!("Received invalid DHT message from {}: {}", from, err),
+        }
+    }
+}
+
+fn token(secret: &[u8; 32], ip: &Ipv4Addr) -> Vec<u8> {
+    generichash::hash(&ip.octets(), Some(16), Some(secret))
+        .map(|digest| digest.as_ref().to_vec())
+        .unwrap_or_default()
+}
+
+/// Return the info hash under which peers of the discovery key are announced.
+fn info_hash(key: &DiscoveryKey) -> NodeId {
+    let mut info_hash = [0; 20];
+    info_hash.copy_from_slice(&key[..20]);
+
+    info_hash
+}
+
+fn node_id(value: &Value) -> Option<NodeId> {
+    value.as_bytes()?.try_into().ok()
+}
+
+/// Return the XOR distance between two ids, which compares as a big-endian
+/// number.
+fn distance(a: &NodeId, b: &NodeId) -> NodeId {
+    let mut distance = [0; 20];
+    for (i, byte) in distance.iter_mut().enumerate() {
+        *byte = a[i] ^ b[i];
+    }
+
+    distance
+}
+
+/// Encode a peer in the compact form of an IP and big-endian port.
+fn encode_peer(peer: &SocketAddrV4) -> Vec<u8> {
+    let mut buf = peer.ip().octets().to_vec();
+    buf.extend_from_slice(&peer.port().to_be_bytes());
+
+    buf
+}
+
+fn decode_peer(buf: &[u8]) -> Option<SocketAddrV4> {
+    if buf.len() != 6 {
+        return None;
+    }
+    let ip = Ipv4Addr::new(buf[0], buf[1], buf[2], buf[3]);
+
+    Some(SocketAddrV4::new(ip, u16::from_be_bytes([buf[4], buf[5]])))
+}
+
+/// Decode the compact form of nodes, a node id followed by a compact peer.
+fn decode_nodes(buf: &[u8]) -> impl Iterator<Item = (NodeId, SocketAddrV4)> + '_ {
+    buf.chunks_exact(26).filter_map(|chunk| {
+        let id = chunk[..20].try_into().ok()?;
+        Some((id, decode_peer(&chunk[20..])?))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn accept_tokens_of_the_previous_secret() {
+        let ip = Ipv4Addr::new(127, 0, 0, 1);
+        let mut secrets = Secrets::new();
+        let first = secrets.token(&ip);
+        assert_eq!(first.len(), 16);
+        assert!(secrets.is_valid(&ip, &first));
+        assert!(!secrets.is_valid(&Ipv4Addr::new(127, 0, 0, 2), &first));
+
+        secrets.rotate();
+        assert!(secrets.is_valid(&ip, &first));
+        assert_ne!(secrets.token(&ip), first);
+
+        secrets.rotate();
+        assert!(!secrets.is_valid(&ip, &first));
+    }
+}
@@ -0,0 +1,193 @@
+//! Automatic dialing of discovered peers.
+//!
+//! Addresses returned by a `Discovery` lookup are fed into a `Dialer`, which
+//! connects to each unique address, hands the resulting stream to the cable
+//! manager and retries failed connections with exponential backoff.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use async_std::{
+    net::TcpStream,
+    sync::{Arc, RwLock},
+    task,
+};
+use cable::Error;
+use log::debug;
+
+use crate::{
+    backoff::Backoff,
+    discovery::{Discovery, DiscoveryKey},
+    manager::CableManager,
+    store::Store,
+};
+
+#[derive(Clone, Debug)]
+/// Parameters controlling the behaviour of a `Dialer`.
+pub struct DialerOptions {
+    /// Time to wait between successive announce and lookup rounds.
+    pub lookup_interval: Duration,
+    /// Delay before retrying an address after the first failed connection.
+    pub initial_backoff: Duration,
+    /// Maximum delay between connection attempts to a single address.
+    pub max_backoff: Duration,
+}
+
+impl Default for DialerOptions {
+    fn default() -> Self {
+        DialerOptions {
+            lookup_interval: Duration::from_secs(30),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+/// The dialing state of a single discovered address.
+struct DialState {
+    /// Whether a connection to the address is established or being attempted.
+    active: bool,
+    /// Backoff state for failed connection attempts.
+    backoff: Backoff,
+    /// The earliest time at which the address may be dialed again.
+    next_attempt: Option<Instant>,
+}
+
+/// Connects to discovered peer addresses and passes the resulting streams to
+/// the cable manager.
+///
+/// Each address is dialed at most once at a time; addresses which fail to
+/// connect are retried with exponential backoff.
+#[derive(Clone)]
+pub struct Dialer<S: Store + Clone> {
+    manager: CableManager<S>,
+    options: DialerOptions,
+    addrs: Arc<RwLock<HashMap<SocketAddr, DialState>>>,
+}
+
+impl<S> Dialer<S>
+where
+    S: Store + Clone,
+{
+    /// Create a new `Dialer` for the given manager.
+    pub fn new(manager: CableManager<S>, options: DialerOptions) -> Self {
+        Dialer {
+            manager,
+            options,
+            addrs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Query whether a connection to the given address is established or
+    /// currently being attempted.
+    pub async fn is_active(&self, addr: &SocketAddr) -> bool {
+        self.addrs
+            .read()
+            .await
+            .get(addr)
+            .map(|state| state.active)
+            .unwrap_or(false)
+    }
+
+    /// Dial the given address, returning `true` if a new connection was
+    /// established.
+    ///
+    /// The address is skipped if a connection is already active or if the
+    /// backoff period following a failed attempt has not yet elapsed.
+    pub async fn dial(&self, addr: SocketAddr) -> bool {
+        {
+            let mut addrs = self.addrs.write().await;
+            let state = addrs.entry(addr).or_insert_with(|| DialState {
+                active: false,
+                backoff: Backoff::new(self.options.initial_backoff, self.options.max_backoff),
+                next_attempt: None,
+            });
+
+            if state.active {
+                return false;
+            }
+            if let Some(next_attempt) = state.next_attempt {
+                if Instant::now() < next_attempt {
+                    return false;
+                }
+            }
+
+            // Mark the address as active before connecting to prevent
+            // concurrent dials to the same address.
+            state.active = true;
+        }
+
+        match TcpStream::connect(addr).await {
+            Ok(stream) => {
+                debug!("Connected to discovered peer {}", addr);
+
+                if let Some(state) = self.addrs.write().await.get_mut(&addr) {
+                    state.backoff.reset();
+                    state.next_attempt = None;
+                }
+
+                let manager = self.manager.clone();
+                let addrs = self.addrs.clone();
+                task::spawn(async move {
+                    if let Err(err) = manager.listen(stream).await {
+                        debug!("Connection to {} closed with error: {}", addr, err);
+                    }
+
+                    // Allow the address to be dialed again.
+                    if let Some(state) = addrs.write().await.get_mut(&addr) {
+                        state.active = false;
+                    }
+                });
+
+                true
+            }
+            Err(err) => {
+                if let Some(state) = self.addrs.write().await.get_mut(&addr) {
+                    let delay = state.backoff.next_delay();
+                    debug!(
+                        "Failed to connect to {} (attempt {}): {}; retrying in {:?}",
+                        addr,
+                        state.backoff.attempts(),
+                        err,
+                        delay
+                    );
+
+                    state.active = false;
+                    state.next_attempt = Some(Instant::now() + delay);
+                }
+
+                false
+            }
+        }
+    }
+
+    /// Repeatedly announce the local address (if given) and dial all peers
+    /// found for the given discovery key.
+    ///
+    /// This method runs until the discovery backend returns an error.
+    pub async fn run<D: Discovery>(
+        &self,
+        discovery: &D,
+        key: &DiscoveryKey,
+        local_addr: Option<SocketAddr>,
+    ) -> Result<(), Error> {
+        loop {
+            if let Some(addr) = local_addr {
+                discovery.announce(key, addr).await?;
+            }
+
+            for addr in discovery.lookup(key).await? {
+                // Never dial the local peer.
+                if Some(addr) == local_addr {
+                    continue;
+                }
+                self.dial(addr).await;
+            }
+
+            task::sleep(self.options.lookup_interval).await;
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Peer discovery.
+//!
+//! The `Discovery` trait describes a backend capable of announcing the local
+//! peer and looking up remote peers under a shared topic: a discovery key
+//! derived from the cabal key. With the `dht` feature enabled, `DhtDiscovery`
+//! implements this trait on the BitTorrent mainline DHT.
+//!
+//! Addresses returned by a lookup are fed into a `Dialer` (see the `dialer`
+//! module).
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+};
+
+use async_std::sync::{Arc, RwLock};
+use cable::{crypto, Error};
+
+/// The topic under which peers of a single cabal announce themselves.
+pub type DiscoveryKey = [u8; 32];
+
+/// Derive the discovery key for the given cabal key.
+///
+/// The cabal key itself is never announced; only its BLAKE2b digest is
+/// shared with the discovery backend.
+pub fn discovery_key(cabal_key: &[u8]) -> Result<DiscoveryKey, Error> {
+    match crypto::hash(cabal_key) {
+        Some(digest) => Ok(digest),
+        None => Err("failed to compute discovery key".into()),
+    }
+}
+
+#[async_trait::async_trait]
+/// A peer discovery backend, such as a DHT.
+pub trait Discovery: Send + Sync {
+    /// Announce that the local peer is reachable at the given address for the
+    /// given discovery key.
+    async fn announce(&self, key: &DiscoveryKey, addr: SocketAddr) -> Result<(), Error>;
+
+    /// Look up the addresses of peers which have announced themselves for the
+    /// given discovery key.
+    async fn lookup(&self, key: &DiscoveryKey) -> Result<Vec<SocketAddr>, Error>;
+}
+
+#[derive(Clone, Default)]
+/// An in-memory discovery backend.
+///
+/// All clones share the same announcement table, making this backend useful
+/// for tests and for peers running within a single process.
+pub struct MemoryDiscovery {
+    announcements: Arc<RwLock<HashMap<DiscoveryKey, HashSet<SocketAddr>>>>,
+}
+
+#[async_trait::async_trait]
+impl Discovery for MemoryDiscovery {
+    async fn announce(&self, key: &DiscoveryKey, addr: SocketAddr) -> Result<(), Error> {
+        self.announcements
+            .write()
+            .await
+            .entry(*key)
+            .or_default()
+            .insert(addr);
+
+        Ok(())
+    }
+
+    async fn lookup(&self, key: &DiscoveryKey) -> Result<Vec<SocketAddr>, Error> {
+        let addrs = self
+            .announcements
+            .read()
+            .await
+            .get(key)
+            .map(|addrs| addrs.iter().copied().collect())
+            .unwrap_or_default();
+
+        Ok(addrs)
+    }
+}
@@ -0,0 +1,602 @@
+//! Runtime selection of the store backend.
+//!
+//! `Store` is dyn-compatible, so that an application may choose its backend
+//! at runtime and create a `CableManager<Box<dyn Store>>` rather than making
+//! its own types generic over the store:
+//!
+//! ```rust,ignore
+//! let store: Box<dyn Store> = match path {
+//!     Some(path) => Box::new(SledStore::open(path)?),
+//!     None => Box::new(MemoryStore::default()),
+//! };
+//! let cable = CableManager::new(store);
+//! ```
+//!
+//! Cloning a store is the one operation a trait object cannot express
+//! directly; `StoreClone` provides it for every store which is `Clone`. Each
+//! method of the boxed store is forwarded to the inner store, including the
+//! provided methods, so that the overrides of the backend are used.
+
+use std::io::{Read, Write};
+
+use cable::{
+    post::Post, Avatar, Channel, ChannelOptions, Error, Hash, Nickname, Payload, ReqId, Timestamp,
+    Topic,
+};
+
+use crate::{
+    archive::ExportOptions,
+    integrity::IntegrityReport,
+    metrics::{CompactionStats, StoreMetrics},
+    retention::RetentionPolicy,
+    store::{AuthorOptions, Keypair, PageCursor, PostPage, PublicKey, Store, UserInfoEntry},
+    stream::{EventStream, HashStream, PostStream, StoreEvent},
+    thread::Thread,
+};
+
+/// Cloning of a store into a boxed trait object, implemented for every store
+/// which is `Clone`.
+pub trait StoreClone {
+    /// Clone the store into a new box.
+    fn clone_box(&self) -> Box<dyn Store>;
+}
+
+impl<S: Store + Clone> StoreClone for S {
+    fn clone_box(&self) -> Box<dyn Store> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Store> {
+    fn clone(&self) -> Self {
+        (**self).clone_box()
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for Box<dyn Store> {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        (**self).get_keypair().await
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        (**self).set_keypair(keypair).await
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        (**self).list_identities().await
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        (**self).insert_identity(keypair).await
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        (**self).set_active_identity(public_key).await
+    }
+
+    async fn create_identity(&mut self) -> PublicKey {
+        (**self).create_identity().await
+    }
+
+    async fn get_or_create_keypair(&mut self) -> Keypair {
+        (**self).get_or_create_keypair().await
+    }
+
+    async fn export_identity(&mut self, passphrase: &str) -> Result<Vec<u8>, Error> {
+        (**self).export_identity(passphrase).await
+    }
+
+    async fn import_identity(
+        &mut self,
+        identity: &[u8],
+        passphrase: &str,
+    ) -> Result<PublicKey, Error> {
+        (**self).import_identity(identity, passphrase).await
+    }
+
+    async fn export(
+        &mut self,
+        writer: &mut (dyn Write + Send),
+        opts: &ExportOptions,
+    ) -> Result<usize, Error> {
+        (**self).export(writer, opts).await
+    }
+
+    async fn import(
+        &mut self,
+        reader: &mut (dyn Read + Send),
+        passphrase: Option<&str>,
+    ) -> Result<usize, Error> {
+        (**self).import(reader, passphrase).await
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        (**self).get_channels().await
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        (**self).insert_channel(channel).await
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        (**self).get_archived_channels().await
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        (**self).is_channel_archived(channel).await
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        (**self).set_channel_archived(channel, archived).await
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        (**self).remove_channel(channel).await
+    }
+
+    async fn drop_channel(&mut self, channel: &Channel) -> Result<usize, Error> {
+        (**self).drop_channel(channel).await
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        (**self).get_channel_members(channel).await
+    }
+
+    async fn insert_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        (**self).insert_channel_member(channel, public_key).await
+    }
+
+    async fn is_channel_member(&self, channel: &Channel, public_key: &PublicKey) -> bool {
+        (**self).is_channel_member(channel, public_key).await
+    }
+
+    async fn remove_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        (**self).remove_channel_member(channel, public_key).await
+    }
+
+    async fn get_channel_membership_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        (**self).get_channel_membership_hashes(channel).await
+    }
+
+    async fn remove_channel_membership_hash(&mut self, hash: &Hash) {
+        (**self).remove_channel_membership_hash(hash).await
+    }
+
+    async fn update_channel_membership_hashes(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        (**self)
+            .update_channel_membership_hashes(channel, public_key, hash)
+            .await
+    }
+
+    async fn get_ex_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        (**self).get_ex_channel_members(channel).await
+    }
+
+    async fn insert_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        (**self).insert_ex_channel_member(channel, public_key).await
+    }
+
+    async fn remove_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        (**self).remove_ex_channel_member(channel, public_key).await
+    }
+
+    async fn get_channel_topic_and_hash(&self, channel: &Channel) -> Option<(Topic, Hash)> {
+        (**self).get_channel_topic_and_hash(channel).await
+    }
+
+    async fn insert_channel_topic(
+        &mut self,
+        channel: &Channel,
+        topic: &Topic,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        (**self)
+            .insert_channel_topic(channel, topic, timestamp, hash)
+            .await
+    }
+
+    async fn remove_channel_topic(&mut self, hash: &Hash) {
+        (**self).remove_channel_topic(hash).await
+    }
+
+    async fn get_delete_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        (**self).get_delete_hashes(public_key).await
+    }
+
+    async fn insert_delete_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        (**self).insert_delete_hash(public_key, hash).await
+    }
+
+    async fn get_info_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        (**self).get_info_hashes(public_key).await
+    }
+
+    async fn insert_info_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        (**self).insert_info_hash(public_key, hash).await
+    }
+
+    async fn remove_info_hash(&mut self, hash: &Hash) {
+        (**self).remove_info_hash(hash).await
+    }
+
+    async fn get_user_info_history(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Vec<UserInfoEntry>, Error> {
+        (**self).get_user_info_history(public_key).await
+    }
+
+    async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        (**self).get_latest_hashes(channel).await
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        (**self).insert_channel_head(channel, hash).await
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        (**self).remove_channel_head(channel, hash).await
+    }
+
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        (**self).insert_post_link(channel, hash, link).await
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        (**self).remove_post_link(channel, hash, link).await
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        (**self).is_post_linked(channel, hash).await
+    }
+
+    async fn update_channel_heads(&mut self, channel: &Channel, hash: &Hash, links: &[Hash]) {
+        (**self).update_channel_heads(channel, hash, links).await
+    }
+
+    async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
+        (**self).get_peer_name_and_hash(public_key).await
+    }
+
+    async fn insert_peer_name(
+        &mut self,
+        public_key: &PublicKey,
+        name: &Nickname,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        (**self)
+            .insert_peer_name(public_key, name, timestamp, hash)
+            .await
+    }
+
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        (**self).remove_peer_name(hash).await
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        (**self).get_peer_avatar_and_hash(public_key).await
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        (**self)
+            .insert_peer_avatar(public_key, avatar, timestamp, hash)
+            .await
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        (**self).remove_peer_avatar(hash).await
+    }
+
+    async fn get_channel_state_hashes(&self, channel: &Channel) -> Vec<Hash> {
+        (**self).get_channel_state_hashes(channel).await
+    }
+
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
+        (**self).get_posts(opts).await
+    }
+
+    async fn get_posts_causal(&self, opts: &ChannelOptions) -> PostStream {
+        (**self).get_posts_causal(opts).await
+    }
+
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        (**self).get_posts_page(channel, before, limit).await
+    }
+
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        (**self).get_mentions(channel).await
+    }
+
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        (**self).insert_mention(channel, timestamp, hash).await
+    }
+
+    async fn remove_mention(&mut self, hash: &Hash) {
+        (**self).remove_mention(hash).await
+    }
+
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        (**self).get_replies(parent).await
+    }
+
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        (**self).insert_reply(parent, timestamp, hash).await
+    }
+
+    async fn remove_reply(&mut self, hash: &Hash) {
+        (**self).remove_reply(hash).await
+    }
+
+    async fn get_thread(&self, root_hash: &Hash) -> Thread {
+        (**self).get_thread(root_hash).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        (**self).get_reactions(target).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        (**self).insert_reaction(target, timestamp, hash).await
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        (**self).remove_reaction(hash).await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        (**self).get_channel_archives(channel).await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        (**self)
+            .insert_channel_archive(channel, public_key, hash)
+            .await
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        (**self).remove_channel_archive(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        (**self).get_blob(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        (**self).insert_blob(hash, blob).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        (**self).remove_blob(hash).await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        (**self).get_blob_usage().await
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn store_blob(&mut self, blob: Vec<u8>, quota: Option<u64>) -> Result<Hash, Error> {
+        (**self).store_blob(blob, quota).await
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        (**self).get_author_index(public_key).await
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        (**self)
+            .insert_author_post(public_key, timestamp, hash)
+            .await
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        (**self)
+            .remove_author_post(public_key, timestamp, hash)
+            .await
+    }
+
+    async fn get_posts_by_author(
+        &self,
+        public_key: &PublicKey,
+        opts: &AuthorOptions,
+    ) -> Result<PostPage, Error> {
+        (**self).get_posts_by_author(public_key, opts).await
+    }
+
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        (**self).get_last_read(channel).await
+    }
+
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        (**self).set_last_read(channel, cursor).await
+    }
+
+    async fn get_unread_count(&self, channel: &Channel) -> Result<usize, Error> {
+        (**self).get_unread_count(channel).await
+    }
+
+    async fn get_posts_live(&self, opts: &ChannelOptions) -> PostStream {
+        (**self).get_posts_live(opts).await
+    }
+
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        (**self).watch(channel).await
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        (**self).send_event(channel, event).await
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        (**self).get_post_hashes(opts).await
+    }
+
+    fn is_read_only(&self) -> bool {
+        (**self).is_read_only()
+    }
+
+    async fn insert_post(&mut self, post: &Post) -> Result<Hash, Error> {
+        (**self).insert_post(post).await
+    }
+
+    async fn insert_posts(&mut self, posts: &[Post]) -> Result<Vec<Hash>, Error> {
+        (**self).insert_posts(posts).await
+    }
+
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        (**self).begin_batch().await
+    }
+
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        (**self).commit_batch().await
+    }
+
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        (**self).rollback_batch().await
+    }
+
+    async fn remove_post(&mut self, hash: &Hash) {
+        (**self).remove_post(hash).await
+    }
+
+    async fn delete_post(&mut self, hash: &Hash) {
+        (**self).delete_post(hash).await
+    }
+
+    async fn is_channel_post(&self, channel: &Channel, hash: &Hash) -> bool {
+        (**self).is_channel_post(channel, hash).await
+    }
+
+    async fn delete_posts(&mut self, hashes: &[Hash]) {
+        (**self).delete_posts(hashes).await
+    }
+
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        (**self).insert_tombstone(hash).await
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        (**self).is_tombstone(hash).await
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        (**self).get_outbound_requests().await
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        (**self).insert_outbound_request(req_id, request).await
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        (**self).remove_outbound_request(req_id).await
+    }
+
+    async fn get_pending_tombstones(&self) -> Vec<Hash> {
+        (**self).get_pending_tombstones().await
+    }
+
+    async fn purge_tombstones(&mut self) -> usize {
+        (**self).purge_tombstones().await
+    }
+
+    async fn compact(&mut self) -> Result<(), Error> {
+        (**self).compact().await
+    }
+
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        (**self).compaction_stats().await
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        (**self).metrics().await
+    }
+
+    async fn prune(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: Timestamp,
+    ) -> Result<Vec<Hash>, Error> {
+        (**self).prune(policy, now).await
+    }
+
+    async fn verify_integrity(&mut self, repair: bool) -> Result<IntegrityReport, Error> {
+        (**self).verify_integrity(repair).await
+    }
+
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    ) {
+        (**self).update_posts(post, channel, timestamp, hash).await
+    }
+
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload> {
+        (**self).get_post_payload(hash).await
+    }
+
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload> {
+        (**self).get_post_payloads(hashes).await
+    }
+
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        (**self).get_post_payload_hashes().await
+    }
+
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
+        (**self).insert_post_payload(hash, payload).await
+    }
+
+    async fn remove_post_payload(&mut self, hash: &Hash) {
+        (**self).remove_post_payload(hash).await
+    }
+
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
+        (**self).want(hashes).await
+    }
+}
@@ -0,0 +1,214 @@
+//! Passphrase-derived encryption of stored data.
+//!
+//! A `Cipher` holds two keys derived from a user-supplied passphrase with
+//! Argon2id: one for authenticated encryption of stored values (XSalsa20 and
+//! Poly1305) and one for blinding index keys with a keyed BLAKE2b hash.
+//! Blinding allows a persistent store to look up entries by channel name or
+//! public key without writing the name or key to disk.
+//!
+//! Keypairs may also be exported as a passphrase-protected identity, allowing
+//! a cable identity to be moved between devices.
+
+use cable::{error::CableErrorKind, Error};
+use sodiumoxide::crypto::{generichash, pwhash::argon2id13, secretbox};
+
+use crate::store::Keypair;
+
+/// The length of the salt used for key derivation.
+pub const SALT_LEN: usize = argon2id13::SALTBYTES;
+
+/// The bytes which begin every exported identity.
+const IDENTITY_MAGIC: &[u8] = b"cable-identity";
+
+/// The version of the exported identity format.
+const IDENTITY_VERSION: u8 = 1;
+
+/// Authenticated encryption and key blinding for stored data.
+pub struct Cipher {
+    /// The key used to encrypt and decrypt stored values.
+    key: secretbox::Key,
+    /// The key used to blind index keys.
+    #[cfg_attr(not(feature = "sled"), allow(dead_code))]
+    blinding_key: [u8; 32],
+}
+
+impl Cipher {
+    /// Generate a new random salt for key derivation.
+    pub fn generate_salt() -> [u8; SALT_LEN] {
+        argon2id13::gen_salt().0
+    }
+
+    /// Derive the encryption and blinding keys from the given passphrase and
+    /// salt.
+    pub fn from_passphrase(passphrase: &[u8], salt: &[u8]) -> Result<Self, Error> {
+        let salt = match argon2id13::Salt::from_slice(salt) {
+            Some(salt) => salt,
+            None => {
+                return CableErrorKind::NoneError {
+                    context: "failed to decode key derivation salt".to_string(),
+                }
+                .raise()
+            }
+        };
+
+        let mut derived = [0; secretbox::KEYBYTES + 32];
+        if argon2id13::derive_key(
+            &mut derived,
+            passphrase,
+            &salt,
+            argon2id13::OPSLIMIT_INTERACTIVE,
+            argon2id13::MEMLIMIT_INTERACTIVE,
+        )
+        .is_err()
+        {
+            return CableErrorKind::NoneError {
+                context: "failed to derive key from passphrase".to_string(),
+            }
+            .raise();
+        }
+
+        let (key, blinding_key) = derived.split_at(secretbox::KEYBYTES);
+
+        Ok(Cipher {
+            // The slice lengths are fixed, so neither conversion can fail.
+            key: secretbox::Key::from_slice(key).unwrap(),
+            blinding_key: blinding_key.try_into().unwrap(),
+        })
+    }
+
+    /// Encrypt the given plaintext, returning the nonce followed by the
+    /// ciphertext.
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = secretbox::gen_nonce();
+        let mut sealed = nonce.0.to_vec();
+        sealed.extend(secretbox::seal(plaintext, &nonce, &self.key));
+
+        sealed
+    }
+
+    /// Decrypt and authenticate the given output of `seal()`.
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, Error> {
+        let plaintext = sealed.get(..secretbox::NONCEBYTES).and_then(|nonce| {
+            let nonce = secretbox::Nonce::from_slice(nonce)?;
+            secretbox::open(&sealed[secretbox::NONCEBYTES..], &nonce, &self.key).ok()
+        });
+
+        match plaintext {
+            Some(plaintext) => Ok(plaintext),
+            None => CableErrorKind::NoneError {
+                context: "failed to decrypt stored value".to_string(),
+            }
+            .raise(),
+        }
+    }
+
+    /// Return a keyed hash of the given data, suitable for use as an index
+    /// key which does not reveal the data.
+    #[cfg_attr(not(feature = "sled"), allow(dead_code))]
+    pub fn blind(&self, data: &[u8]) -> [u8; 32] {
+        // The output and key lengths are within the bounds accepted by
+        // `generichash`, so these calls cannot fail.
+        let mut state = generichash::State::new(Some(32), Some(&self.blinding_key)).unwrap();
+        state.update(data).unwrap();
+
+        state.finalize().unwrap().as_ref().try_into().unwrap()
+    }
+}
+
+/// Export the given keypair as an identity protected by the given
+/// passphrase.
+///
+/// The identity consists of a format header, the key derivation salt and the
+/// encrypted keypair.
+pub fn encrypt_keypair(keypair: &Keypair, passphrase: &str) -> Result<Vec<u8>, Error> {
+    let (pk, sk) = keypair;
+    let salt = Cipher::generate_salt();
+    let cipher = Cipher::from_passphrase(passphrase.as_bytes(), &salt)?;
+
+    let mut identity = IDENTITY_MAGIC.to_vec();
+    identity.push(IDENTITY_VERSION);
+    identity.extend_from_slice(&salt);
+    identity.extend(cipher.seal(&[&pk[..], &sk[..]].concat()));
+
+    Ok(identity)
+}
+
+/// Import a keypair from an identity exported with the given passphrase.
+///
+/// An error is returned if the identity is malformed, the passphrase is
+/// incorrect or the public key does not belong to the secret key.
+pub fn decrypt_keypair(identity: &[u8], passphrase: &str) -> Result<Keypair, Error> {
+    let header_len = IDENTITY_MAGIC.len() + 1;
+    if identity.len() < header_len + SALT_LEN
+        || &identity[..IDENTITY_MAGIC.len()] != IDENTITY_MAGIC
+        || identity[IDENTITY_MAGIC.len()] != IDENTITY_VERSION
+    {
+        return CableErrorKind::NoneError {
+            context: "failed to decode exported identity".to_string(),
+        }
+        .raise();
+    }
+
+    let (salt, sealed) = identity[header_len..].split_at(SALT_LEN);
+    let cipher = Cipher::from_passphrase(passphrase.as_bytes(), salt)?;
+    let keypair = cipher.open(sealed)?;
+
+    // An ed25519 secret key is the seed followed by the public key.
+    match (keypair.get(..32), keypair.get(32..)) {
+        (Some(pk), Some(sk)) if sk.len() == 64 && &sk[32..] == pk => {
+            Ok((pk.try_into().unwrap(), sk.try_into().unwrap()))
+        }
+        _ => CableErrorKind::NoneError {
+            context: "exported identity contains an invalid keypair".to_string(),
+        }
+        .raise(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seal_open_and_blind() -> Result<(), Error> {
+        let salt = Cipher::generate_salt();
+        let cipher = Cipher::from_passphrase(b"correct horse", &salt)?;
+
+        let sealed = cipher.seal(b"entomology");
+        assert_ne!(&sealed[secretbox::NONCEBYTES..], b"entomology");
+        assert_eq!(cipher.open(&sealed)?, b"entomology");
+
+        // Blinding is deterministic for a given passphrase and salt.
+        let same_cipher = Cipher::from_passphrase(b"correct horse", &salt)?;
+        assert_eq!(cipher.blind(b"moths"), same_cipher.blind(b"moths"));
+        assert_ne!(cipher.blind(b"moths"), cipher.blind(b"beetles"));
+
+        // A different passphrase can neither open values nor match blinded
+        // keys.
+        let wrong_cipher = Cipher::from_passphrase(b"battery staple", &salt)?;
+        assert!(wrong_cipher.open(&sealed).is_err());
+        assert_ne!(cipher.blind(b"moths"), wrong_cipher.blind(b"moths"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn export_and_import_identity() -> Result<(), Error> {
+        let (pk, sk) = sodiumoxide::crypto::sign::gen_keypair();
+        let keypair = (
+            pk.as_ref().try_into().unwrap(),
+            sk.as_ref().try_into().unwrap(),
+        );
+
+        let identity = encrypt_keypair(&keypair, "correct horse")?;
+        assert!(!identity
+            .windows(32)
+            .any(|window| window == &keypair.1[..32]));
+
+        assert_eq!(decrypt_keypair(&identity, "correct horse")?, keypair);
+        assert!(decrypt_keypair(&identity, "battery staple").is_err());
+        assert!(decrypt_keypair(&identity[..20], "correct horse").is_err());
+
+        Ok(())
+    }
+}
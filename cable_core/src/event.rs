@@ -0,0 +1,105 @@
+//! Events emitted by a cable manager to notify clients of activity which
+//! concerns the local user, and of failures which are not returned to any
+//! caller.
+
+use async_std::{
+    channel,
+    stream::Stream,
+    sync::{Arc, RwLock},
+};
+use cable::{Channel, Error, Hash, Post, Topic};
+
+use crate::manager::PeerId;
+
+/// An asynchronous stream of manager events.
+pub type CableEventStream = Box<dyn Stream<Item = CableEvent> + Unpin + Send>;
+
+/// An event emitted by a `CableManager`, as returned by
+/// `CableManager::events()`.
+#[derive(Clone, Debug)]
+// Mentions are far more common than handler failures, so the post is not
+// boxed.
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum CableEvent {
+    /// A `post/text` post received from a peer mentions the local user by
+    /// name or public key.
+    Mention {
+        /// The channel of the post.
+        channel: Channel,
+        /// The hash of the post.
+        hash: Hash,
+        /// The post.
+        post: Post,
+    },
+    /// The topic of a channel changed, either because a newer `post/topic`
+    /// post was received from a peer or because one was published locally.
+    TopicChanged {
+        /// The channel.
+        channel: Channel,
+        /// The new topic of the channel.
+        topic: Topic,
+        /// The hash of the `post/topic` post which set the topic.
+        hash: Hash,
+    },
+    /// A post published by the local peer was requested by a peer for the
+    /// first time. Only emitted if `ManagerOptions::track_deliveries` is set.
+    PostDelivered {
+        /// The hash of the post.
+        hash: Hash,
+        /// The ID of the peer which requested the post.
+        peer_id: PeerId,
+    },
+    /// Handling a message received from a peer failed. The connection to
+    /// the peer remains open.
+    HandlerFailed {
+        /// The ID of the peer from which the message was received.
+        peer_id: PeerId,
+        /// The error returned by the handler.
+        error: Arc<Error>,
+    },
+}
+
+#[derive(Clone)]
+/// The subscribers to the events of a manager.
+pub(crate) struct CableEvents {
+    senders: Arc<RwLock<Vec<channel::Sender<CableEvent>>>>,
+    /// Whether events are emitted.
+    enabled: bool,
+}
+
+impl CableEvents {
+    /// Create the subscribers to the events of a manager, which emits no
+    /// events unless `enabled` is set.
+    pub(crate) fn new(enabled: bool) -> Self {
+        CableEvents {
+            senders: Arc::new(RwLock::new(Vec::new())),
+            enabled,
+        }
+    }
+
+    /// Register a new subscriber, returning the stream of events it receives.
+    ///
+    /// The stream ends immediately if events are not emitted.
+    pub(crate) async fn subscribe(&self) -> CableEventStream {
+        let (sender, receiver) = channel::unbounded();
+        if self.enabled {
+            self.senders.write().await.push(sender);
+        }
+
+        Box::new(receiver)
+    }
+
+    /// Send the given event to each subscriber, discarding the subscribers
+    /// whose streams have been dropped.
+    pub(crate) async fn send(&self, event: CableEvent) {
+        if !self.enabled {
+            return;
+        }
+
+        self.senders
+            .write()
+            .await
+            .retain(|sender| sender.try_send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+//! An approximate-membership filter of the hashes known to a store.
+//!
+//! `Store::want()` is called for every hash response received. The persistent
+//! stores keep a bloom filter of the hashes of their post payloads and
+//! tombstones so that hashes which are certainly unknown are answered without
+//! touching the database; only hashes which may be known are looked up.
+//!
+//! Post hashes are the output of a cryptographic hash function, so the bit
+//! positions are derived directly from the hash bytes rather than by hashing
+//! them again.
+
+use std::sync::{Arc, RwLock};
+
+use cable::Hash;
+
+/// The number of filter bits allocated for each hash.
+const BITS_PER_HASH: usize = 10;
+
+/// The number of bits set for each hash, giving a false positive rate of
+/// about 1% while the filter is within capacity.
+const BITS_SET: u64 = 7;
+
+/// The minimum number of hashes a filter is sized for.
+const MIN_CAPACITY: usize = 1024;
+
+/// A bloom filter of hashes.
+#[derive(Clone, Debug)]
+struct BloomFilter {
+    /// The bits of the filter.
+    bits: Vec<u64>,
+    /// The number of hashes the filter is sized for.
+    capacity: usize,
+    /// The number of hashes inserted into the filter.
+    len: usize,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for the given number of hashes.
+    fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        let words = (capacity * BITS_PER_HASH).div_ceil(64);
+
+        BloomFilter {
+            bits: vec![0; words],
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Add the given hash to the filter.
+    fn insert(&mut self, hash: &Hash) {
+        for pos in positions(self.bits.len(), hash) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+        self.len += 1;
+    }
+
+    /// Return `false` if the given hash has certainly not been inserted.
+    fn may_contain(&self, hash: &Hash) -> bool {
+        positions(self.bits.len(), hash).all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+}
+
+/// Return the positions of the bits representing the given hash in a filter
+/// of the given number of 64-bit words.
+fn positions(words: usize, hash: &Hash) -> impl Iterator<Item = usize> {
+    let num_bits = (words * 64) as u64;
+    let h1 = u64::from_le_bytes(hash[0..8].try_into().unwrap());
+    // The step is made odd so that it is never zero.
+    let h2 = u64::from_le_bytes(hash[8..16].try_into().unwrap()) | 1;
+
+    (0..BITS_SET).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+}
+
+/// A shared filter of the hashes known to a store.
+///
+/// The filter never produces false negatives: a hash for which
+/// `may_contain()` returns `false` has never been inserted. Hashes removed
+/// from the store remain in the filter, which only costs an extra lookup.
+#[derive(Clone, Debug)]
+pub(crate) struct KnownHashes(Arc<RwLock<BloomFilter>>);
+
+impl KnownHashes {
+    /// Create a filter containing the given hashes, sized to allow the store
+    /// to grow before it must be rebuilt.
+    pub(crate) fn new(hashes: Vec<Hash>) -> Self {
+        KnownHashes(Arc::new(RwLock::new(Self::build(hashes))))
+    }
+
+    fn build(hashes: Vec<Hash>) -> BloomFilter {
+        let mut filter = BloomFilter::with_capacity(hashes.len() * 2);
+        for hash in &hashes {
+            filter.insert(hash);
+        }
+
+        filter
+    }
+
+    /// Add the given hash to the filter.
+    ///
+    /// Returns `true` if the filter is over capacity and should be rebuilt
+    /// with `rebuild()` to restore its false positive rate.
+    pub(crate) fn insert(&self, hash: &Hash) -> bool {
+        let mut filter = self.0.write().unwrap_or_else(|err| err.into_inner());
+        filter.insert(hash);
+
+        filter.len > filter.capacity
+    }
+
+    /// Replace the contents of the filter with the given hashes.
+    pub(crate) fn rebuild(&self, hashes: Vec<Hash>) {
+        let filter = Self::build(hashes);
+        *self.0.write().unwrap_or_else(|err| err.into_inner()) = filter;
+    }
+
+    /// Return `false` if the given hash is certainly unknown to the store.
+    pub(crate) fn may_contain(&self, hash: &Hash) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .may_contain(hash)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cable::crypto;
+
+    use super::*;
+
+    fn hash(n: u32) -> Hash {
+        crypto::hash(&n.to_be_bytes()).unwrap()
+    }
+
+    #[test]
+    fn inserted_hashes_are_always_found() {
+        let known = KnownHashes::new((0..500).map(hash).collect());
+        for n in 500..3000 {
+            known.insert(&hash(n));
+        }
+
+        assert!((0..3000).all(|n| known.may_contain(&hash(n))));
+    }
+
+    #[test]
+    fn false_positive_rate_is_low_within_capacity() {
+        let known = KnownHashes::new((0..1000).map(hash).collect());
+
+        let false_positives = (1000..11000)
+            .filter(|n| known.may_contain(&hash(*n)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+
+    #[test]
+    fn insert_reports_when_over_capacity() {
+        let known = KnownHashes::new(Vec::new());
+        let over_capacity = (0..MIN_CAPACITY as u32 + 1)
+            .map(|n| known.insert(&hash(n)))
+            .collect::<Vec<_>>();
+        assert!(!over_capacity[MIN_CAPACITY - 1]);
+        assert!(over_capacity[MIN_CAPACITY]);
+
+        known.rebuild((0..MIN_CAPACITY as u32 + 1).map(hash).collect());
+        assert!(!known.insert(&hash(MIN_CAPACITY as u32 + 1)));
+    }
+}
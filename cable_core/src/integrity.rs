@@ -0,0 +1,62 @@
+//! Reports of the integrity checks performed by `Store::verify_integrity()`.
+
+use std::fmt;
+
+use cable::Hash;
+
+/// The outcome of checking the integrity of a store, as returned by
+/// `Store::verify_integrity()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// The number of stored post payloads which were checked.
+    pub checked_posts: usize,
+    /// The hashes of stored payloads which could not be decoded, do not
+    /// match their hash or carry an invalid signature.
+    pub corrupt_posts: Vec<Hash>,
+    /// The hashes of indexed posts for which no payload is stored.
+    pub missing_payloads: Vec<Hash>,
+    /// The hashes of deleted (tombstoned) posts which are still stored.
+    pub deleted_posts: Vec<Hash>,
+    /// The number of index entries which could not be decoded.
+    pub unreadable_entries: usize,
+    /// Whether the problems found were repaired.
+    pub repaired: bool,
+}
+
+impl IntegrityReport {
+    /// Return `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.corrupt_posts.is_empty()
+            && self.missing_payloads.is_empty()
+            && self.deleted_posts.is_empty()
+            && self.unreadable_entries == 0
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "checked posts: {}", self.checked_posts)?;
+        for (problem, hashes) in [
+            ("corrupt post", &self.corrupt_posts),
+            ("missing payload", &self.missing_payloads),
+            ("deleted post", &self.deleted_posts),
+        ] {
+            for hash in hashes {
+                writeln!(f, "{}: {}", problem, hex::encode(hash))?;
+            }
+        }
+        if self.unreadable_entries > 0 {
+            writeln!(f, "unreadable index entries: {}", self.unreadable_entries)?;
+        }
+        if !self.is_ok() {
+            let outcome = if self.repaired {
+                "repaired"
+            } else {
+                "not repaired"
+            };
+            writeln!(f, "problems {}", outcome)?;
+        }
+
+        Ok(())
+    }
+}
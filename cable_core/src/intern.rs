@@ -0,0 +1,89 @@
+//! Interning of channel names.
+//!
+//! `Channel` is a `String`, so every index entry, request and options struct
+//! naming a channel holds its own allocation of the name. Internal data
+//! structures instead hold a `SharedChannel`: each distinct name is allocated
+//! once and copies of it only increment a reference count.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+/// A channel name shared between the data structures referring to it.
+pub(crate) type SharedChannel = Arc<str>;
+
+/// A set of interned channel names.
+///
+/// Names are retained until `prune()` is called once no copy of them is held
+/// elsewhere, so an interner of names supplied by remote peers should be
+/// pruned as the data structures holding them are cleared.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ChannelInterner {
+    names: Arc<Mutex<HashSet<SharedChannel>>>,
+}
+
+impl ChannelInterner {
+    /// Return the shared copy of the given channel name, interning it if it
+    /// is not yet known.
+    pub(crate) fn intern(&self, channel: &str) -> SharedChannel {
+        let mut names = self.names.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(name) = names.get(channel) {
+            return name.clone();
+        }
+
+        let name = SharedChannel::from(channel);
+        names.insert(name.clone());
+
+        name
+    }
+
+    /// Return the shared copy of the given channel name, if it is interned.
+    pub(crate) fn get(&self, channel: &str) -> Option<SharedChannel> {
+        self.names
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(channel)
+            .cloned()
+    }
+
+    /// Forget the interned names of which no copy is held outside of the
+    /// interner.
+    pub(crate) fn prune(&self) {
+        self.names
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .retain(|name| Arc::strong_count(name) > 1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn share_interned_names() {
+        let interner = ChannelInterner::default();
+        assert!(interner.get("entomology").is_none());
+
+        let name = interner.intern("entomology");
+        assert!(Arc::ptr_eq(&name, &interner.intern("entomology")));
+        assert!(Arc::ptr_eq(&name, &interner.get("entomology").unwrap()));
+        assert!(!Arc::ptr_eq(&name, &interner.intern("botany")));
+    }
+
+    #[test]
+    fn prune_unused_names() {
+        let interner = ChannelInterner::default();
+        let name = interner.intern("entomology");
+        interner.intern("botany");
+
+        interner.prune();
+        assert!(interner.get("botany").is_none());
+        assert!(Arc::ptr_eq(&name, &interner.get("entomology").unwrap()));
+
+        drop(name);
+        interner.prune();
+        assert!(interner.get("entomology").is_none());
+    }
+}
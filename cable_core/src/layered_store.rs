@@ -0,0 +1,1550 @@
+//! Store wrappers applying cross-cutting concerns to any store.
+//!
+//! A `LayeredStore` wraps a store together with a `StoreLayer`, which is
+//! notified of every call to a method of the store and may delay or refuse
+//! calls. Each method is forwarded to the inner store, so that the layer
+//! applies whichever backend it wraps. Four layers are provided:
+//!
+//! - `LoggingStore` logs each call at the trace level.
+//! - `MetricsStore` counts the calls to each method.
+//! - `ReadOnlyStore` refuses writes, making the store read-only.
+//! - `FlakyStore` fails or delays a fraction of the calls, to exercise the
+//!   error paths of its callers in tests.
+//!
+//! Wrappers compose, along with `CachedStore`:
+//!
+//! ```rust,ignore
+//! let store = LoggingStore::new(MetricsStore::new(SledStore::open(path)?));
+//! ```
+//!
+//! A refused write raises the error returned by the layer if the method
+//! returns a `Result`, and is otherwise skipped. Only reads through methods
+//! returning a `Result` may be refused. Refused calls to
+//! `get_or_create_keypair()` and `create_identity()` return a keypair which
+//! is not stored, unless the store already holds one.
+
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_std::task;
+
+use cable::{
+    crypto, error::CableErrorKind, post::Post, Avatar, Channel, ChannelOptions, Error, Hash,
+    Nickname, Payload, ReqId, Timestamp, Topic,
+};
+use log::trace;
+
+use crate::{
+    archive::ExportOptions,
+    integrity::IntegrityReport,
+    metrics::{CompactionStats, StoreMetrics},
+    retention::RetentionPolicy,
+    store::{AuthorOptions, Keypair, PageCursor, PostPage, PublicKey, Store, UserInfoEntry},
+    stream::{EventStream, HashStream, PostStream, StoreEvent},
+    thread::Thread,
+};
+
+/// Whether a call to a method of a store reads or writes its data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// The call only reads the data of the store.
+    Read,
+    /// The call may modify the data of the store.
+    Write,
+}
+
+/// A call to a method of a layered store, as passed to its layer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoreCall {
+    /// The name of the called method.
+    pub method: &'static str,
+    /// Whether the call reads or writes the data of the store.
+    pub access: Access,
+}
+
+impl StoreCall {
+    fn new(method: &'static str, access: Access) -> Self {
+        StoreCall { method, access }
+    }
+}
+
+/// A concern applied to every call to a method of a `LayeredStore`.
+pub trait StoreLayer: Clone + Send + Sync + Unpin + 'static {
+    /// Observe a call before it is made.
+    fn before(&self, _call: &StoreCall) {}
+
+    /// Allow or refuse a write before it is made. A refused write raises the
+    /// returned error if the method returns a `Result`, and is otherwise
+    /// skipped.
+    fn allow_write(&self, _call: &StoreCall) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Allow or refuse a read before it is made. Only reads through methods
+    /// returning a `Result` may be refused, raising the returned error.
+    fn allow_read(&self, _call: &StoreCall) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Return how long to wait before a call is made, if at all.
+    fn delay(&self, _call: &StoreCall) -> Option<Duration> {
+        None
+    }
+
+    /// Observe a call after it was made.
+    fn after(&self, _call: &StoreCall) {}
+
+    /// Query whether the layer makes the store read-only.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+}
+
+/// A store wrapped with a layer, which is applied to every call to a method
+/// of the store.
+#[derive(Clone)]
+pub struct LayeredStore<S: Store + Clone, L: StoreLayer> {
+    store: S,
+    layer: L,
+}
+
+impl<S: Store + Clone, L: StoreLayer> LayeredStore<S, L> {
+    /// Wrap the given store with the given layer.
+    pub fn with_layer(store: S, layer: L) -> Self {
+        LayeredStore { store, layer }
+    }
+
+    /// Return the layer of the store.
+    pub fn layer(&self) -> &L {
+        &self.layer
+    }
+
+    /// Return the wrapped store.
+    pub fn inner(&self) -> &S {
+        &self.store
+    }
+
+    /// Unwrap the store, discarding the layer.
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Notify the layer of a call before it is made, waiting for the delay
+    /// of the layer.
+    async fn before(&self, call: &StoreCall) {
+        self.layer.before(call);
+        if let Some(delay) = self.layer.delay(call) {
+            task::sleep(delay).await;
+        }
+    }
+}
+
+/// A layer logging each call to a method of the store at the trace level.
+#[derive(Clone, Debug, Default)]
+pub struct LoggingLayer;
+
+impl StoreLayer for LoggingLayer {
+    fn before(&self, call: &StoreCall) {
+        trace!("Store call: {} ({:?})", call.method, call.access);
+    }
+}
+
+/// A store logging each call to its methods.
+pub type LoggingStore<S> = LayeredStore<S, LoggingLayer>;
+
+impl<S: Store + Clone> LayeredStore<S, LoggingLayer> {
+    /// Wrap the given store, logging each call to its methods.
+    pub fn new(store: S) -> Self {
+        Self::with_layer(store, LoggingLayer)
+    }
+}
+
+/// A layer counting the calls to each method of the store.
+#[derive(Clone, Debug, Default)]
+pub struct MetricsLayer {
+    calls: Arc<Mutex<HashMap<&'static str, u64>>>,
+}
+
+impl MetricsLayer {
+    /// Return the number of calls made to each method of the store, by
+    /// method name.
+    pub fn calls(&self) -> HashMap<&'static str, u64> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Return the number of calls made to the given method of the store.
+    pub fn call_count(&self, method: &str) -> u64 {
+        self.calls.lock().unwrap().get(method).copied().unwrap_or(0)
+    }
+}
+
+impl StoreLayer for MetricsLayer {
+    fn before(&self, call: &StoreCall) {
+        *self.calls.lock().unwrap().entry(call.method).or_insert(0) += 1;
+    }
+}
+
+/// A store counting the calls to each of its methods.
+pub type MetricsStore<S> = LayeredStore<S, MetricsLayer>;
+
+impl<S: Store + Clone> LayeredStore<S, MetricsLayer> {
+    /// Wrap the given store, counting the calls to each of its methods.
+    pub fn new(store: S) -> Self {
+        Self::with_layer(store, MetricsLayer::default())
+    }
+}
+
+/// A layer refusing every write to the store.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOnlyLayer;
+
+impl StoreLayer for ReadOnlyLayer {
+    fn allow_write(&self, _call: &StoreCall) -> Result<(), Error> {
+        CableErrorKind::StoreReadOnly {}.raise()
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/// A store refusing every write, whichever backend it wraps.
+pub type ReadOnlyStore<S> = LayeredStore<S, ReadOnlyLayer>;
+
+impl<S: Store + Clone> LayeredStore<S, ReadOnlyLayer> {
+    /// Wrap the given store, refusing every write.
+    pub fn new(store: S) -> Self {
+        Self::with_layer(store, ReadOnlyLayer)
+    }
+}
+
+/// A layer failing or delaying a fraction of the calls to the store, drawn
+/// from a seeded random number generator.
+///
+/// Failures are injected by refusing calls with a `StoreFailed` error, so
+/// that they surface wherever the store reports errors, such as when the
+/// posts of a response are inserted. Writes and the reads through methods
+/// returning a `Result` may fail; other reads, which cannot report an error,
+/// are never failed. Delays apply to reads and writes alike, standing in for
+/// a slow or stalled backend.
+///
+/// ```rust,ignore
+/// let layer = FlakyLayer::new(seed)
+///     .fail_rate(0.1)
+///     .delay_rate(0.5, Duration::from_millis(50))
+///     .methods(&["insert_posts"]);
+/// let store = FlakyStore::with_layer(MemoryStore::default(), layer);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FlakyLayer {
+    /// The fraction of calls which fail, between 0 and 1.
+    fail_rate: f64,
+    /// The fraction of calls which are delayed, between 0 and 1.
+    delay_rate: f64,
+    /// The delay of a delayed call.
+    delay: Duration,
+    /// The methods to which faults apply, or all methods if `None`.
+    methods: Option<Vec<&'static str>>,
+    rng: Arc<Mutex<fastrand::Rng>>,
+    failures: Arc<AtomicU64>,
+    delays: Arc<AtomicU64>,
+}
+
+impl FlakyLayer {
+    /// Create a layer injecting no faults, drawing from a random number
+    /// generator with the given seed.
+    pub fn new(seed: u64) -> Self {
+        FlakyLayer {
+            fail_rate: 0.0,
+            delay_rate: 0.0,
+            delay: Duration::ZERO,
+            methods: None,
+            rng: Arc::new(Mutex::new(fastrand::Rng::with_seed(seed))),
+            failures: Arc::new(AtomicU64::new(0)),
+            delays: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Fail the given fraction of writes and of reads returning a `Result`.
+    pub fn fail_rate(mut self, rate: f64) -> Self {
+        self.fail_rate = rate;
+        self
+    }
+
+    /// Delay the given fraction of calls by the given duration.
+    pub fn delay_rate(mut self, rate: f64, delay: Duration) -> Self {
+        self.delay_rate = rate;
+        self.delay = delay;
+        self
+    }
+
+    /// Only inject faults into calls to the methods with the given names.
+    pub fn methods(mut self, methods: &[&'static str]) -> Self {
+        self.methods = Some(methods.to_vec());
+        self
+    }
+
+    /// Return the number of calls which have been failed.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Return the number of calls which have been delayed.
+    pub fn delays(&self) -> u64 {
+        self.delays.load(Ordering::Relaxed)
+    }
+
+    /// Draw whether to fail the given call, returning the injected error.
+    fn fail(&self, call: &StoreCall) -> Result<(), Error> {
+        if !self.chance(call, self.fail_rate) {
+            return Ok(());
+        }
+        self.failures.fetch_add(1, Ordering::Relaxed);
+
+        CableErrorKind::StoreFailed {
+            context: format!("injected failure of {}", call.method),
+        }
+        .raise()
+    }
+
+    /// Draw whether to inject a fault with the given rate into the given
+    /// call.
+    fn chance(&self, call: &StoreCall, rate: f64) -> bool {
+        let applies = match &self.methods {
+            Some(methods) => methods.contains(&call.method),
+            None => true,
+        };
+
+        applies && rate > 0.0 && self.rng.lock().unwrap().f64() < rate
+    }
+}
+
+impl StoreLayer for FlakyLayer {
+    fn delay(&self, call: &StoreCall) -> Option<Duration> {
+        if !self.chance(call, self.delay_rate) {
+            return None;
+        }
+        self.delays.fetch_add(1, Ordering::Relaxed);
+
+        Some(self.delay)
+    }
+
+    fn allow_read(&self, call: &StoreCall) -> Result<(), Error> {
+        self.fail(call)
+    }
+
+    fn allow_write(&self, call: &StoreCall) -> Result<(), Error> {
+        self.fail(call)
+    }
+}
+
+/// A store failing or delaying a fraction of the calls to its methods, for
+/// testing.
+pub type FlakyStore<S> = LayeredStore<S, FlakyLayer>;
+
+impl<S: Store + Clone> LayeredStore<S, FlakyLayer> {
+    /// Wrap the given store, failing the given fraction of calls drawn from
+    /// a random number generator with the given seed.
+    pub fn new(store: S, fail_rate: f64, seed: u64) -> Self {
+        Self::with_layer(store, FlakyLayer::new(seed).fail_rate(fail_rate))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Store + Clone, L: StoreLayer> Store for LayeredStore<S, L> {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        let call = StoreCall::new("get_keypair", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_keypair().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        let call = StoreCall::new("set_keypair", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.set_keypair(keypair).await;
+        self.layer.after(&call);
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        let call = StoreCall::new("list_identities", Access::Read);
+        self.before(&call).await;
+        let result = self.store.list_identities().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        let call = StoreCall::new("insert_identity", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_identity(keypair).await;
+        self.layer.after(&call);
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        let call = StoreCall::new("set_active_identity", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.set_active_identity(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn create_identity(&mut self) -> PublicKey {
+        let call = StoreCall::new("create_identity", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return crypto::generate_keypair().0;
+        }
+        let result = self.store.create_identity().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_or_create_keypair(&mut self) -> Keypair {
+        let call = StoreCall::new("get_or_create_keypair", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return self
+                .store
+                .get_keypair()
+                .await
+                .unwrap_or_else(crypto::generate_keypair);
+        }
+        let result = self.store.get_or_create_keypair().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn export_identity(&mut self, passphrase: &str) -> Result<Vec<u8>, Error> {
+        let call = StoreCall::new("export_identity", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.export_identity(passphrase).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn import_identity(
+        &mut self,
+        identity: &[u8],
+        passphrase: &str,
+    ) -> Result<PublicKey, Error> {
+        let call = StoreCall::new("import_identity", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.import_identity(identity, passphrase).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn export(
+        &mut self,
+        writer: &mut (dyn Write + Send),
+        opts: &ExportOptions,
+    ) -> Result<usize, Error> {
+        let call = StoreCall::new("export", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.export(writer, opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn import(
+        &mut self,
+        reader: &mut (dyn Read + Send),
+        passphrase: Option<&str>,
+    ) -> Result<usize, Error> {
+        let call = StoreCall::new("import", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.import(reader, passphrase).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        let call = StoreCall::new("get_channels", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channels().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        let call = StoreCall::new("insert_channel", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_channel(channel).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        let call = StoreCall::new("get_archived_channels", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_archived_channels().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        let call = StoreCall::new("is_channel_archived", Access::Read);
+        self.before(&call).await;
+        let result = self.store.is_channel_archived(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        let call = StoreCall::new("set_channel_archived", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.set_channel_archived(channel, archived).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        let call = StoreCall::new("remove_channel", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel(channel).await;
+        self.layer.after(&call);
+    }
+
+    async fn drop_channel(&mut self, channel: &Channel) -> Result<usize, Error> {
+        let call = StoreCall::new("drop_channel", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.drop_channel(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        let call = StoreCall::new("get_channel_members", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channel_members(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let call = StoreCall::new("insert_channel_member", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_channel_member(channel, public_key).await;
+        self.layer.after(&call);
+    }
+
+    async fn is_channel_member(&self, channel: &Channel, public_key: &PublicKey) -> bool {
+        let call = StoreCall::new("is_channel_member", Access::Read);
+        self.before(&call).await;
+        let result = self.store.is_channel_member(channel, public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn remove_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let call = StoreCall::new("remove_channel_member", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel_member(channel, public_key).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_channel_membership_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let call = StoreCall::new("get_channel_membership_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channel_membership_hashes(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn remove_channel_membership_hash(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_channel_membership_hash", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel_membership_hash(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn update_channel_membership_hashes(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("update_channel_membership_hashes", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .update_channel_membership_hashes(channel, public_key, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn get_ex_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        let call = StoreCall::new("get_ex_channel_members", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_ex_channel_members(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let call = StoreCall::new("insert_ex_channel_member", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_ex_channel_member(channel, public_key)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let call = StoreCall::new("remove_ex_channel_member", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .remove_ex_channel_member(channel, public_key)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn get_channel_topic_and_hash(&self, channel: &Channel) -> Option<(Topic, Hash)> {
+        let call = StoreCall::new("get_channel_topic_and_hash", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channel_topic_and_hash(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_channel_topic(
+        &mut self,
+        channel: &Channel,
+        topic: &Topic,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("insert_channel_topic", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_channel_topic(channel, topic, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_channel_topic(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_channel_topic", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel_topic(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_delete_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        let call = StoreCall::new("get_delete_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_delete_hashes(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_delete_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let call = StoreCall::new("insert_delete_hash", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_delete_hash(public_key, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_info_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        let call = StoreCall::new("get_info_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_info_hashes(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_info_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let call = StoreCall::new("insert_info_hash", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_info_hash(public_key, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_info_hash(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_info_hash", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_info_hash(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_user_info_history(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Vec<UserInfoEntry>, Error> {
+        let call = StoreCall::new("get_user_info_history", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.get_user_info_history(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let call = StoreCall::new("get_latest_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_latest_hashes(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        let call = StoreCall::new("insert_channel_head", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_channel_head(channel, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        let call = StoreCall::new("remove_channel_head", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel_head(channel, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        let call = StoreCall::new("insert_post_link", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_post_link(channel, hash, link).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        let call = StoreCall::new("remove_post_link", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_post_link(channel, hash, link).await;
+        self.layer.after(&call);
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        let call = StoreCall::new("is_post_linked", Access::Read);
+        self.before(&call).await;
+        let result = self.store.is_post_linked(channel, hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn update_channel_heads(&mut self, channel: &Channel, hash: &Hash, links: &[Hash]) {
+        let call = StoreCall::new("update_channel_heads", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.update_channel_heads(channel, hash, links).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
+        let call = StoreCall::new("get_peer_name_and_hash", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_peer_name_and_hash(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_peer_name(
+        &mut self,
+        public_key: &PublicKey,
+        name: &Nickname,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("insert_peer_name", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_peer_name(public_key, name, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_peer_name", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_peer_name(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        let call = StoreCall::new("get_peer_avatar_and_hash", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_peer_avatar_and_hash(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("insert_peer_avatar", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_peer_avatar(public_key, avatar, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_peer_avatar", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_peer_avatar(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_channel_state_hashes(&self, channel: &Channel) -> Vec<Hash> {
+        let call = StoreCall::new("get_channel_state_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channel_state_hashes(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
+        let call = StoreCall::new("get_posts", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_posts(opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_posts_causal(&self, opts: &ChannelOptions) -> PostStream {
+        let call = StoreCall::new("get_posts_causal", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_posts_causal(opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        let call = StoreCall::new("get_posts_page", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.get_posts_page(channel, before, limit).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        let call = StoreCall::new("get_mentions", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_mentions(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        let call = StoreCall::new("insert_mention", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_mention(channel, timestamp, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_mention(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_mention", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_mention(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        let call = StoreCall::new("get_replies", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_replies(parent).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let call = StoreCall::new("insert_reply", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_reply(parent, timestamp, hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_reply(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_reply", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_reply(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_thread(&self, root_hash: &Hash) -> Thread {
+        let call = StoreCall::new("get_thread", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_thread(root_hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        let call = StoreCall::new("get_reactions", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_reactions(target).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let call = StoreCall::new("insert_reaction", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_reaction(target, timestamp, hash).await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_reaction", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_reaction(hash).await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        let call = StoreCall::new("get_channel_archives", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_channel_archives(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("insert_channel_archive", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_channel_archive(channel, public_key, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_channel_archive", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_channel_archive(hash).await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        let call = StoreCall::new("get_blob", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_blob(hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        let call = StoreCall::new("insert_blob", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_blob(hash, blob).await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_blob", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_blob(hash).await;
+        self.layer.after(&call);
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        let call = StoreCall::new("get_blob_usage", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_blob_usage().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn store_blob(&mut self, blob: Vec<u8>, quota: Option<u64>) -> Result<Hash, Error> {
+        let call = StoreCall::new("store_blob", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.store_blob(blob, quota).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        let call = StoreCall::new("get_author_index", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_author_index(public_key).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("insert_author_post", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .insert_author_post(public_key, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let call = StoreCall::new("remove_author_post", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .remove_author_post(public_key, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn get_posts_by_author(
+        &self,
+        public_key: &PublicKey,
+        opts: &AuthorOptions,
+    ) -> Result<PostPage, Error> {
+        let call = StoreCall::new("get_posts_by_author", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.get_posts_by_author(public_key, opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        let call = StoreCall::new("get_last_read", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_last_read(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        let call = StoreCall::new("set_last_read", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.set_last_read(channel, cursor).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_unread_count(&self, channel: &Channel) -> Result<usize, Error> {
+        let call = StoreCall::new("get_unread_count", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.get_unread_count(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_posts_live(&self, opts: &ChannelOptions) -> PostStream {
+        let call = StoreCall::new("get_posts_live", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_posts_live(opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        let call = StoreCall::new("watch", Access::Read);
+        self.before(&call).await;
+        let result = self.store.watch(channel).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        let call = StoreCall::new("send_event", Access::Read);
+        self.before(&call).await;
+        self.store.send_event(channel, event).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        let call = StoreCall::new("get_post_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_post_hashes(opts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.layer.is_read_only() || self.store.is_read_only()
+    }
+
+    async fn insert_post(&mut self, post: &Post) -> Result<Hash, Error> {
+        let call = StoreCall::new("insert_post", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.insert_post(post).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_posts(&mut self, posts: &[Post]) -> Result<Vec<Hash>, Error> {
+        let call = StoreCall::new("insert_posts", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.insert_posts(posts).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        let call = StoreCall::new("begin_batch", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.begin_batch().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        let call = StoreCall::new("commit_batch", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.commit_batch().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        let call = StoreCall::new("rollback_batch", Access::Write);
+        self.before(&call).await;
+        // The open batch must be ended whatever the layer allows.
+        let result = self.store.rollback_batch().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn remove_post(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_post", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_post(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn delete_post(&mut self, hash: &Hash) {
+        let call = StoreCall::new("delete_post", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.delete_post(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn is_channel_post(&self, channel: &Channel, hash: &Hash) -> bool {
+        let call = StoreCall::new("is_channel_post", Access::Read);
+        self.before(&call).await;
+        let result = self.store.is_channel_post(channel, hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn delete_posts(&mut self, hashes: &[Hash]) {
+        let call = StoreCall::new("delete_posts", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.delete_posts(hashes).await;
+        self.layer.after(&call);
+    }
+
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        let call = StoreCall::new("insert_tombstone", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_tombstone(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        let call = StoreCall::new("is_tombstone", Access::Read);
+        self.before(&call).await;
+        let result = self.store.is_tombstone(hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        let call = StoreCall::new("get_outbound_requests", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_outbound_requests().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        let call = StoreCall::new("insert_outbound_request", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_outbound_request(req_id, request).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        let call = StoreCall::new("remove_outbound_request", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_outbound_request(req_id).await;
+        self.layer.after(&call);
+    }
+
+    async fn get_pending_tombstones(&self) -> Vec<Hash> {
+        let call = StoreCall::new("get_pending_tombstones", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_pending_tombstones().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn purge_tombstones(&mut self) -> usize {
+        let call = StoreCall::new("purge_tombstones", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return 0;
+        }
+        let result = self.store.purge_tombstones().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn compact(&mut self) -> Result<(), Error> {
+        let call = StoreCall::new("compact", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.compact().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        let call = StoreCall::new("compaction_stats", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.compaction_stats().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        let call = StoreCall::new("metrics", Access::Read);
+        self.before(&call).await;
+        self.layer.allow_read(&call)?;
+        let result = self.store.metrics().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn prune(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: Timestamp,
+    ) -> Result<Vec<Hash>, Error> {
+        let call = StoreCall::new("prune", Access::Write);
+        self.before(&call).await;
+        self.layer.allow_write(&call)?;
+        let result = self.store.prune(policy, now).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn verify_integrity(&mut self, repair: bool) -> Result<IntegrityReport, Error> {
+        // Verification only writes to the store when repairing it.
+        let access = if repair { Access::Write } else { Access::Read };
+        let call = StoreCall::new("verify_integrity", access);
+        self.before(&call).await;
+        if repair {
+            self.layer.allow_write(&call)?;
+        }
+        let result = self.store.verify_integrity(repair).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    ) {
+        let call = StoreCall::new("update_posts", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store
+            .update_posts(post, channel, timestamp, hash)
+            .await;
+        self.layer.after(&call);
+    }
+
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload> {
+        let call = StoreCall::new("get_post_payload", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_post_payload(hash).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload> {
+        let call = StoreCall::new("get_post_payloads", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_post_payloads(hashes).await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        let call = StoreCall::new("get_post_payload_hashes", Access::Read);
+        self.before(&call).await;
+        let result = self.store.get_post_payload_hashes().await;
+        self.layer.after(&call);
+
+        result
+    }
+
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
+        let call = StoreCall::new("insert_post_payload", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.insert_post_payload(hash, payload).await;
+        self.layer.after(&call);
+    }
+
+    async fn remove_post_payload(&mut self, hash: &Hash) {
+        let call = StoreCall::new("remove_post_payload", Access::Write);
+        self.before(&call).await;
+        if self.layer.allow_write(&call).is_err() {
+            return;
+        }
+        self.store.remove_post_payload(hash).await;
+        self.layer.after(&call);
+    }
+
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
+        let call = StoreCall::new("want", Access::Read);
+        self.before(&call).await;
+        let result = self.store.want(hashes).await;
+        self.layer.after(&call);
+
+        result
+    }
+}
@@ -1,9 +1,116 @@
 #![cfg_attr(feature = "nightly-features", feature(async_closure, drain_filter))]
 #![doc=include_str!("../README.md")]
 
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod backoff;
+#[cfg(any(feature = "sled", feature = "sqlite"))]
+mod batch;
+#[cfg(all(feature = "dht", not(target_arch = "wasm32")))]
+mod bencode;
+#[cfg(feature = "blobs")]
+mod blob;
+mod budget;
+mod builder;
+mod cached_store;
+mod causal;
+mod channel_handle;
+#[cfg(feature = "chaos")]
+mod chaos;
+mod claims;
+mod clock;
+mod delivery;
+#[cfg(all(feature = "dht", not(target_arch = "wasm32")))]
+mod dht;
+#[cfg(not(target_arch = "wasm32"))]
+mod dialer;
+mod discovery;
+mod dyn_store;
+#[cfg(not(target_arch = "wasm32"))]
+mod encryption;
+mod event;
+#[cfg(any(feature = "sled", feature = "sqlite"))]
+mod filter;
+mod integrity;
+mod intern;
+mod layered_store;
 mod manager;
+mod mention;
+mod metrics;
+#[cfg(any(feature = "sled", feature = "sqlite"))]
+mod migration;
+mod peer_info;
+#[cfg(feature = "private-channels")]
+mod private;
+mod recent;
+mod relay;
+mod requested;
+mod retention;
+mod self_check;
+mod sharded;
+mod shared_stream;
+pub mod simulation;
+#[cfg(feature = "sled")]
+mod sled_store;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
 mod store;
 mod stream;
+mod subscription;
+#[cfg(not(target_arch = "wasm32"))]
+mod supervisor;
+mod sync;
+pub mod testing;
+mod thread;
+mod verify;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
 
-pub use manager::CableManager;
-pub use store::{MemoryStore, Store};
+pub use archive::ExportOptions;
+#[cfg(feature = "blobs")]
+pub use blob::{blob_link, blob_links};
+pub use builder::ManagerBuilder;
+pub use cached_store::CachedStore;
+pub use causal::causal_order;
+pub use channel_handle::ChannelHandle;
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosOptions;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use delivery::DeliveryStatus;
+#[cfg(all(feature = "dht", not(target_arch = "wasm32")))]
+pub use dht::{DhtDiscovery, DhtOptions};
+#[cfg(not(target_arch = "wasm32"))]
+pub use dialer::{Dialer, DialerOptions};
+pub use discovery::{discovery_key, Discovery, DiscoveryKey, MemoryDiscovery};
+pub use dyn_store::StoreClone;
+#[cfg(not(target_arch = "wasm32"))]
+pub use encryption::{decrypt_keypair, encrypt_keypair};
+pub use event::CableEvent;
+pub use integrity::IntegrityReport;
+pub use layered_store::{
+    Access, FlakyLayer, FlakyStore, LayeredStore, LoggingLayer, LoggingStore, MetricsLayer,
+    MetricsStore, ReadOnlyLayer, ReadOnlyStore, StoreCall, StoreLayer,
+};
+#[cfg(feature = "archive-posts")]
+pub use manager::ArchivePolicy;
+pub use manager::{CableManager, ManagerOptions, SlowPeerPolicy};
+pub use metrics::{CompactionStats, StoreMetrics};
+pub use peer_info::PeerInfo;
+#[cfg(feature = "private-channels")]
+pub use private::private_channel;
+pub use retention::RetentionPolicy;
+pub use self_check::{check_message, SelfCheck, SelfChecker};
+pub use shared_stream::SharedStream;
+#[cfg(feature = "sled")]
+pub use sled_store::SledStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite_store::SqliteStore;
+pub use store::{AuthorOptions, Keypair, MemoryStore, PageCursor, PostPage, Store, UserInfoEntry};
+pub use stream::{EventStream, StoreEvent};
+pub use subscription::{SubscribeOptions, Subscription};
+#[cfg(not(target_arch = "wasm32"))]
+pub use supervisor::{Supervisor, SupervisorOptions};
+pub use sync::SyncStatus;
+pub use thread::{parse_reply, reply_text, Thread};
+#[cfg(target_arch = "wasm32")]
+pub use wasm::{decrypt_keypair, encrypt_keypair};
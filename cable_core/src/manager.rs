@@ -5,41 +5,85 @@
 use std::{
     collections::{HashMap, HashSet},
     convert::TryInto,
+    io::{self, ErrorKind},
+    mem,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
 };
 
 use async_std::{
-    channel,
+    channel::{self, TrySendError},
+    future,
     prelude::*,
     sync::{Arc, RwLock},
     task,
 };
 use cable::{
+    capabilities::Capabilities,
     constants::NO_CIRCUIT,
+    error::{CableError, CableErrorKind},
+    limits::Limits,
     message::{Message, MessageBody, MessageHeader, RequestBody, ResponseBody},
-    validation, Channel, ChannelOptions, Error, Hash, Post, ReqId, Timestamp, UserInfo,
+    post::PostBody,
+    signer::{KeypairSigner, Signer},
+    validation::ChannelNormalization,
+    Avatar, Channel, ChannelOptions, CircuitId, Error, Hash, Post, ReqId, Timestamp, Topic,
+    UserInfo,
 };
 use desert::{FromBytes, ToBytes};
-use futures::io::{AsyncRead, AsyncWrite};
+use futures::{
+    io::{AsyncRead, AsyncWrite},
+    stream,
+};
 use length_prefixed_stream::{decode_with_options, DecodeOptions};
-use log::debug;
-
-use crate::{store::Store, stream::PostStream};
+use log::{debug, warn};
+
+#[cfg(feature = "blobs")]
+use crate::blob::{BlobFetches, BlobProgress, BLOB_CHUNK_SIZE};
+#[cfg(feature = "chaos")]
+use crate::chaos::{Chaos, ChaosOptions};
+#[cfg(feature = "private-channels")]
+use crate::private::{private_channel, SharedKey};
+use crate::{
+    budget::{PeerMemory, REQUESTED_POST_SIZE},
+    builder::ManagerBuilder,
+    channel_handle::ChannelHandle,
+    claims,
+    clock::{Clock, SystemClock},
+    delivery::{Deliveries, DeliveryStatus},
+    event::{CableEvent, CableEventStream, CableEvents},
+    intern::{ChannelInterner, SharedChannel},
+    mention,
+    peer_info::{PeerInfo, PeerTraffic},
+    recent::RecentRequests,
+    relay::RelayFilter,
+    requested::{PostContext, RequestedPosts},
+    retention::RetentionPolicy,
+    self_check::{self, SelfCheck, SelfChecker},
+    sharded::{Sharded, SHARDS},
+    store::{Keypair, PageCursor, PublicKey, Store},
+    stream::{ChannelPostStream, HashStream, PostStream, StoreEvent},
+    subscription::{SubscribeOptions, Subscription},
+    sync::SyncStatus,
+    thread::{self, Thread},
+    verify,
+};
+#[cfg(feature = "blobs")]
+use cable::constants::BLOB_REQUEST;
 
-// Define the TTL (how many times a request will be
-// forwarded.
-//
-// NOTE: We may want to set this dynamically in the
-// future, either based on user choice or connectivity
-// status.
-const TTL: u8 = 1;
+/// The maximum number of hashes sent in a single hash response.
+///
+/// Larger result sets are sent in several responses, so that no response is
+/// held in memory in full and each fits the 50 kB frames accepted by the
+/// decoder of the peer.
+const HASH_RESPONSE_BATCH: usize = 1024;
 
 /// A locally-defined peer ID used to track requests.
 pub type PeerId = usize;
 
-/// A `HashMap` of peer requests with a key of peer ID and a value of a `Vec`
-/// of request ID and `LiveRequest`.
-pub type PeerRequestMap = HashMap<PeerId, Vec<LiveRequest>>;
-
 /// Inbound requests for which the keep-alive option has been selected.
 ///
 /// This helps us to respond to live requests with new hashes as they become
@@ -48,7 +92,7 @@ pub type PeerRequestMap = HashMap<PeerId, Vec<LiveRequest>>;
 #[derive(Debug, PartialEq)]
 pub enum LiveRequest {
     /// A channel state request with specified ID and channel.
-    ChannelState(ReqId, Channel),
+    ChannelState(ReqId, SharedChannel),
     /// A channel time range request with specified ID and channel options.
     ChannelTimeRange(ReqId, ChannelOptions),
 }
@@ -67,109 +111,679 @@ impl LiveRequest {
 enum RequestOrigin {
     /// Local request.
     Local,
-    /// Remote request (from a peer).
-    Remote,
+    /// Remote request from the given peer, to which responses are relayed.
+    Remote(PeerId),
+}
+
+/// Query whether the given request is kept alive by the peers answering it,
+/// rather than being concluded once the known hashes have been returned.
+fn keeps_alive(request: &Message) -> bool {
+    matches!(
+        request.body,
+        MessageBody::Request {
+            body: RequestBody::ChannelTimeRange { time_end: 0, .. }
+                | RequestBody::ChannelState { future: 1, .. },
+            ..
+        }
+    )
+}
+
+/// Return whether the given response concludes the given forwarded request
+/// on the side of the peer which sent it: an empty hash or channel list
+/// response, or the post response to a post request. Requests which are kept
+/// alive are never concluded.
+fn concludes_forwarded(request: &Message, body: &ResponseBody) -> bool {
+    if keeps_alive(request) {
+        return false;
+    }
+
+    match body {
+        ResponseBody::Hash { hashes } => hashes.is_empty(),
+        ResponseBody::Post { .. } => matches!(
+            request.body,
+            MessageBody::Request {
+                body: RequestBody::Post { .. },
+                ..
+            }
+        ),
+        ResponseBody::ChannelList { channels } => channels.is_empty(),
+        #[cfg(feature = "blobs")]
+        ResponseBody::Blob { .. } => false,
+    }
+}
+
+/// Return the channel of the given channel time range or channel state
+/// request.
+fn request_channel(request: &Message) -> Option<&Channel> {
+    match &request.body {
+        MessageBody::Request {
+            body:
+                RequestBody::ChannelTimeRange { channel, .. }
+                | RequestBody::ChannelState { channel, .. },
+            ..
+        } => Some(channel),
+        _ => None,
+    }
+}
+
+/// Return the request ID by which the given message is ordered among the
+/// messages received from a peer: that of the request it cancels, if any,
+/// otherwise its own.
+fn ordering_key(msg: &Message) -> ReqId {
+    match &msg.body {
+        MessageBody::Request {
+            body: RequestBody::Cancel { cancel_id },
+            ..
+        } => *cancel_id,
+        _ => msg.header.req_id,
+    }
+}
+
+/// Query whether the given error is the rejection of an excessive TTL.
+fn is_ttl_error(err: &Error) -> bool {
+    matches!(
+        err.downcast_ref::<CableError>().map(CableError::kind),
+        Some(CableErrorKind::MessageTtlIncorrect { .. })
+    )
+}
+
+/// Encode the given request with its request ID zeroed, allowing requests
+/// made under different request IDs to be compared.
+fn request_key(request: &Message) -> Result<Vec<u8>, Error> {
+    let mut request = request.clone();
+    request.header.req_id = [0; 4];
+
+    request.to_bytes()
+}
+
+/// A message encoded once and shared by every peer to which it is sent.
+#[derive(Clone)]
+struct Frame {
+    msg: Arc<Message>,
+    bytes: Arc<[u8]>,
+}
+
+impl Frame {
+    fn encode(msg: &Message) -> Result<Self, Error> {
+        Ok(Frame {
+            bytes: msg.to_bytes()?.into(),
+            msg: Arc::new(msg.clone()),
+        })
+    }
+}
+
+/// The number of frames awaiting the writer task of each peer.
+const OUTBOUND_QUEUE_SIZE: usize = 100;
+
+/// The state held for a connected peer.
+struct PeerState {
+    /// The frames to be written to the stream of the peer by its writer task.
+    frames: channel::Sender<Frame>,
+    /// Closed to disconnect the peer. Nothing is ever sent on it.
+    disconnect: channel::Sender<()>,
+    /// Closed to disconnect the peer once the frames queued for it have been
+    /// written. Nothing is ever sent on it.
+    closing: channel::Sender<()>,
+    /// Fails once the connection to the peer has been torn down, as its
+    /// sender is dropped. Nothing is ever sent on it.
+    closed: channel::Receiver<()>,
+    /// The number of consecutive frames dropped because the queue of frames
+    /// was full.
+    dropped_frames: AtomicUsize,
+    /// The memory attributable to the peer, shared with its writer task.
+    memory: Arc<PeerMemory>,
+    /// The number of violations of the protocol by the peer.
+    violations: AtomicUsize,
+    /// Live inbound requests of the peer, to which the local peer is
+    /// listening and responding.
+    ///
+    /// These are channel time range requests with an end time of 0 and
+    /// channel state requests with `future` set to 1, indicating that the
+    /// peer wishes to receive new post hashes as they become known.
+    live_requests: RwLock<Vec<LiveRequest>>,
+    /// The time at which the peer connected, unless the clock failed.
+    connected_at: Option<Timestamp>,
+    /// The traffic exchanged with the peer, shared with its writer task.
+    traffic: Arc<PeerTraffic>,
+    /// The capabilities announced by the peer, if any.
+    capabilities: RwLock<Option<Capabilities>>,
+    /// Whether the capabilities of the local peer have been announced to the
+    /// peer.
+    announced: AtomicBool,
+}
+
+impl PeerState {
+    fn new(
+        frames: channel::Sender<Frame>,
+        disconnect: channel::Sender<()>,
+        closing: channel::Sender<()>,
+        closed: channel::Receiver<()>,
+        connected_at: Option<Timestamp>,
+    ) -> Self {
+        PeerState {
+            frames,
+            disconnect,
+            closing,
+            closed,
+            dropped_frames: AtomicUsize::new(0),
+            memory: Arc::new(PeerMemory::default()),
+            violations: AtomicUsize::new(0),
+            live_requests: RwLock::new(Vec::new()),
+            connected_at,
+            traffic: Arc::new(PeerTraffic::default()),
+            capabilities: RwLock::new(None),
+            announced: AtomicBool::new(false),
+        }
+    }
+
+    /// Query whether the peer may understand messages of the given type: it
+    /// has either announced that it does or not announced its capabilities.
+    async fn supports_message(&self, msg_type: u64) -> bool {
+        match &*self.capabilities.read().await {
+            Some(capabilities) => capabilities.supports_message(msg_type),
+            None => true,
+        }
+    }
+}
+
+/// The action taken when a frame is sent to a peer whose queue of frames
+/// awaiting its writer task is full, as happens when the peer reads more
+/// slowly than messages are sent to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SlowPeerPolicy {
+    /// Wait for room in the queue, however long the peer takes to read.
+    /// A stalled peer blocks every task sending to it, including broadcasts
+    /// to all peers.
+    Wait,
+    /// Drop the frame, disconnecting the peer once more than `max_dropped`
+    /// consecutive frames have been dropped.
+    Drop { max_dropped: usize },
+}
+
+impl Default for SlowPeerPolicy {
+    fn default() -> Self {
+        SlowPeerPolicy::Drop { max_dropped: 100 }
+    }
+}
+
+/// The authors whose `post/archive` posts hide a channel from
+/// `list_channels()` and from the channel list responses sent to peers.
+#[cfg(feature = "archive-posts")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ArchivePolicy {
+    /// Archive posts are stored and synced, but hide no channel.
+    Ignore,
+    /// An archive post by any author hides its channel.
+    #[default]
+    AnyAuthor,
+    /// Only the archive posts of the given channel operators hide a channel.
+    Operators(Vec<PublicKey>),
+}
+
+#[cfg(feature = "archive-posts")]
+impl ArchivePolicy {
+    /// Query whether a channel with archive posts by the given authors is
+    /// hidden under this policy.
+    fn hides(&self, authors: &[PublicKey]) -> bool {
+        match self {
+            ArchivePolicy::Ignore => false,
+            ArchivePolicy::AnyAuthor => !authors.is_empty(),
+            ArchivePolicy::Operators(operators) => {
+                authors.iter().any(|author| operators.contains(author))
+            }
+        }
+    }
+}
+
+/// Write the given bytes to the stream of a peer, failing with a timeout
+/// error if the write does not complete within the given duration.
+async fn write_with_timeout<T: AsyncWrite + Unpin>(
+    stream: &mut T,
+    bytes: &[u8],
+    timeout: Option<Duration>,
+) -> io::Result<()> {
+    match timeout {
+        Some(timeout) => future::timeout(timeout, stream.write_all(bytes))
+            .await
+            .unwrap_or_else(|_| {
+                Err(io::Error::new(
+                    ErrorKind::TimedOut,
+                    "peer connection write timeout",
+                ))
+            }),
+        None => stream.write_all(bytes).await,
+    }
 }
 
 impl RequestOrigin {
     fn is_local(&self) -> bool {
         match self {
             RequestOrigin::Local => true,
-            RequestOrigin::Remote => false,
+            RequestOrigin::Remote(_) => false,
         }
     }
 }
 
-/// Generate a timestamp for the current time.
-fn now() -> Result<u64, Error> {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_millis()
-        // Convert from u128 to u64.
-        .try_into()?;
+/// Parameters controlling the behaviour of a `CableManager`.
+#[derive(Clone, Debug)]
+pub struct ManagerOptions {
+    /// Interval at which keepalive pings are sent to each connected peer.
+    ///
+    /// A ping is an empty post request with a TTL of 0, to which the peer
+    /// replies with an empty post response. Set to `None` to disable pings.
+    pub keepalive_interval: Option<Duration>,
+    /// Duration without any received message after which a peer connection
+    /// is considered dead and is torn down. Set to `None` to wait forever.
+    pub idle_timeout: Option<Duration>,
+    /// Retention policy applied to the store. Set to `None` to retain all
+    /// posts.
+    ///
+    /// The policy is applied at its interval while any peer is connected,
+    /// and received posts older than the maximum age are not stored.
+    pub retention: Option<RetentionPolicy>,
+    /// Tolerated difference between the clocks of the local peer and the
+    /// authors of received posts. Posts timestamped further in the future
+    /// than this are not stored, but may be requested again if their hashes
+    /// are advertised once their time has come. Set to `None` to store posts
+    /// regardless of their timestamp.
+    pub max_clock_skew: Option<Duration>,
+    /// The source of the current time, used to timestamp new posts and to
+    /// apply the retention policy.
+    pub clock: Arc<dyn Clock>,
+    /// Whether every outgoing message is checked against the constraints of
+    /// the cable specification before it is written, and the action taken
+    /// on a violation.
+    pub self_check: SelfCheck,
+    /// Whether requests received with a TTL above the maximum of 16 are
+    /// rejected, closing the connection with an error. By default such a TTL
+    /// is clamped to 16.
+    pub reject_excessive_ttl: bool,
+    /// The normalization applied to the channel names given to the methods
+    /// of the manager which open, close or post to a channel. Set to
+    /// `ChannelNormalization::None` to use channel names exactly as given.
+    ///
+    /// Posts and requests received from peers are never normalized.
+    pub channel_normalization: ChannelNormalization,
+    /// Whether posts received from peers without having been requested are
+    /// stored, allowing peers to gossip posts proactively.
+    ///
+    /// Unsolicited posts are verified and skipped if already stored or
+    /// deleted. By default only requested posts are stored.
+    pub accept_unsolicited_posts: bool,
+    /// Duration after which a requested post which has not been received is
+    /// requested again, from another peer which advertised it if possible.
+    /// Set to `None` to only request posts again when the peer from which
+    /// they were requested disconnects.
+    pub post_request_timeout: Option<Duration>,
+    /// The maximum number of workers which verify the signatures of the
+    /// posts of a post response in parallel. By default, the available
+    /// parallelism of the machine.
+    pub verification_workers: usize,
+    /// The number of messages received from a single peer which are handled
+    /// concurrently.
+    ///
+    /// The messages concerning a single request (the request, its responses
+    /// and its cancellation) are always handled in the order in which they
+    /// were received.
+    pub handler_concurrency: usize,
+    /// The number of received messages awaiting each handler of a peer.
+    /// Once the queue is full, no more messages are read from the peer until
+    /// a queued message has been handled.
+    pub inbound_queue_size: usize,
+    /// Duration for which the ID of a handled request is remembered, so that
+    /// the request is neither answered nor forwarded again if it reaches the
+    /// local peer by another path. A peer may reuse a request ID once this
+    /// duration has elapsed.
+    pub handled_request_ttl: Duration,
+    /// The maximum number of handled request IDs remembered. Beyond it, the
+    /// least recently handled request IDs are forgotten early.
+    pub handled_request_capacity: usize,
+    /// The number of malformed frames received from a peer which are
+    /// skipped before the connection is closed with an error. Set to 0 to
+    /// close the connection on the first malformed frame.
+    ///
+    /// A frame is malformed if its message cannot be decoded. Decoding
+    /// resumes at the length prefix of the next frame.
+    pub malformed_frame_limit: usize,
+    /// Duration after which a write to a peer which has not completed is
+    /// abandoned and the connection is closed with an error. Set to `None`
+    /// to wait forever.
+    pub write_timeout: Option<Duration>,
+    /// The action taken when sending to a peer which is not reading the
+    /// messages already sent to it quickly enough.
+    pub slow_peer_policy: SlowPeerPolicy,
+    /// The estimated memory, in bytes, which may be held on behalf of a
+    /// single peer: messages queued to be written to it, requests it made
+    /// which are forwarded to other peers and posts requested from it. Set
+    /// to `None` for no limit.
+    ///
+    /// Once the budget is exceeded, the oldest forwarded requests of the
+    /// peer are cancelled and then the oldest posts requested from it are
+    /// forgotten. The peer is disconnected if the budget is still exceeded.
+    pub peer_memory_budget: Option<usize>,
+    /// The number of violations of the protocol by a peer which are
+    /// tolerated before it is disconnected.
+    ///
+    /// A message making implausible claims, such as a response repeating a
+    /// hash or listing more channels than were requested, is a violation.
+    /// Such a message is not handled.
+    pub violation_limit: usize,
+    /// Whether responses to requests which the local peer has neither made
+    /// nor forwarded are handled.
+    ///
+    /// By default such responses are dropped and count as violations of the
+    /// protocol. Post responses are accepted regardless if
+    /// `accept_unsolicited_posts` is set.
+    pub accept_unknown_responses: bool,
+    /// Limits on the contents of messages and posts, applied to those
+    /// received from peers and to those published locally.
+    ///
+    /// Messages received from peers which exceed the limits are skipped as
+    /// malformed; see `malformed_frame_limit`.
+    pub limits: Limits,
+    /// The TTL of the requests made by the local peer: the number of times
+    /// each may be forwarded beyond the peers to which it is sent. Values
+    /// above the `max_ttl` of `limits` are clamped to it.
+    pub ttl: u8,
+    /// Whether the manager emits events to the subscribers of `events()`.
+    /// If unset, the streams returned by `events()` end immediately.
+    pub events: bool,
+    /// Whether the delivery of the posts published by the local peer is
+    /// tracked. A published post is delivered once a peer first requests
+    /// it, which is reported by `delivery_status()` and `delivered()` and
+    /// emitted as a `CableEvent::PostDelivered`.
+    pub track_deliveries: bool,
+    /// Whether a capability request announcing the protocol version and the
+    /// message and post types understood by the local peer is sent to each
+    /// peer on connection.
+    ///
+    /// Capability requests are an extension to the cable specification.
+    /// Messages of types which a peer has announced it does not understand
+    /// are not sent to it. Peers announcing their capabilities are answered
+    /// with those of the local peer regardless of this option.
+    pub announce_capabilities: bool,
+    /// Whether the manager serves the existing history of the store to peers
+    /// without writing to the store.
+    ///
+    /// A read-only manager refuses to publish posts and discards the posts
+    /// and channels received from peers, and does not persist its requests
+    /// or last-read positions. Managers over a store opened read-only are
+    /// read-only regardless. Useful for public archive mirrors and for
+    /// inspecting a data directory.
+    pub read_only: bool,
+    /// The keypair of the local peer, supplied by the host application. Set
+    /// to `None` to use the keypair of the store, which is generated on
+    /// first use.
+    ///
+    /// A supplied keypair is used to sign the posts published by the local
+    /// peer and is never written to the store, so that identities may be
+    /// managed by the host application or shared across devices.
+    pub keypair: Option<Keypair>,
+    /// The signer of the posts published by the local peer, for secret keys
+    /// held outside the process, such as in a hardware security module or
+    /// the keychain of the operating system. Takes precedence over
+    /// `keypair` and the keypair of the store.
+    ///
+    /// The public key of the signer identifies the local peer. The manager
+    /// never holds the secret key of a signer, so `get_secret_key()` and
+    /// private channels are unavailable.
+    pub signer: Option<Arc<dyn Signer>>,
+    /// The maximum number of bytes of blobs held by the store. Attaching or
+    /// fetching a blob which would exceed it fails. Set to `None` for no
+    /// limit.
+    #[cfg(feature = "blobs")]
+    pub blob_quota: Option<u64>,
+    /// The authors whose archive posts hide a channel from `list_channels()`
+    /// and from the channel list responses sent to peers, so that channels
+    /// archived by their operators are hidden across the network.
+    #[cfg(feature = "archive-posts")]
+    pub archive_policy: ArchivePolicy,
+    /// The faults injected by the manager, for testing: delayed handling of
+    /// received messages, dropped outgoing messages and reordered writes.
+    #[cfg(feature = "chaos")]
+    pub chaos: Option<ChaosOptions>,
+}
 
-    Ok(timestamp)
+impl Default for ManagerOptions {
+    fn default() -> Self {
+        ManagerOptions {
+            keepalive_interval: Some(Duration::from_secs(30)),
+            idle_timeout: Some(Duration::from_secs(90)),
+            retention: None,
+            max_clock_skew: Some(Duration::from_secs(10 * 60)),
+            clock: Arc::new(SystemClock),
+            self_check: SelfCheck::Off,
+            reject_excessive_ttl: false,
+            channel_normalization: ChannelNormalization::default(),
+            accept_unsolicited_posts: false,
+            post_request_timeout: Some(Duration::from_secs(30)),
+            verification_workers: std::thread::available_parallelism()
+                .map(|workers| workers.get())
+                .unwrap_or(1),
+            handler_concurrency: 4,
+            inbound_queue_size: 100,
+            handled_request_ttl: Duration::from_secs(300),
+            handled_request_capacity: 65_536,
+            malformed_frame_limit: 16,
+            write_timeout: Some(Duration::from_secs(30)),
+            slow_peer_policy: SlowPeerPolicy::default(),
+            peer_memory_budget: Some(8 * 1024 * 1024),
+            violation_limit: 16,
+            accept_unknown_responses: false,
+            limits: Limits::DEFAULT,
+            ttl: 1,
+            events: true,
+            track_deliveries: false,
+            announce_capabilities: false,
+            read_only: false,
+            keypair: None,
+            signer: None,
+            #[cfg(feature = "blobs")]
+            blob_quota: Some(64 * 1024 * 1024),
+            #[cfg(feature = "archive-posts")]
+            archive_policy: ArchivePolicy::default(),
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
 }
 
 /// The manager for a single cable instance.
 #[derive(Clone)]
-pub struct CableManager<S: Store> {
+pub struct CableManager<S: Store + Clone> {
     /// Hashes of posts which remote peers have marked for deletion, or which
     /// have been authored and deleted by the local peer.
     deleted_posts: Arc<RwLock<HashSet<Hash>>>,
+    /// The hashes returned by peers in response to the local requests for
+    /// each channel, used to report the progress of synchronisation.
+    channel_hashes: Arc<RwLock<HashMap<SharedChannel, HashSet<Hash>>>>,
+    /// The channel names held by the indexes of the manager.
+    channel_names: ChannelInterner,
+    /// The subscribers to the events of the manager.
+    events: CableEvents,
+    /// The delivery states of the posts published by the local peer, if
+    /// deliveries are tracked.
+    deliveries: Arc<RwLock<Deliveries>>,
+    /// Channels watched for new posts on behalf of live requests.
+    watched_channels: Arc<RwLock<HashSet<SharedChannel>>>,
     /// Requests of remote origin which have been forwarded to other peers.
     forwarded_requests: Arc<RwLock<HashMap<ReqId, HashSet<PeerId>>>>,
+    /// The responses relayed to the origin of forwarded requests.
+    relay_filter: Arc<Mutex<RelayFilter>>,
     /// Request IDs of requests which have been handled.
-    handled_requests: Arc<RwLock<HashSet<ReqId>>>,
+    handled_requests: Sharded<RecentRequests>,
+    /// Request IDs of requests which have been written to peers, so that
+    /// responses arriving after a request has been cancelled or concluded
+    /// are recognised.
+    issued_requests: Sharded<RecentRequests>,
     /// The most recently assigned peer ID.
-    last_peer_id: Arc<RwLock<PeerId>>,
+    last_peer_id: Arc<AtomicUsize>,
     /// The most recently assigned request ID.
-    last_req_id: Arc<RwLock<u32>>,
-    /// Live inbound requests to which the local peer is listening and
-    /// responding.
-    ///
-    /// These are peer-generated channel time range requests with an end time
-    /// of 0, indicating that the peer wishes to receive new post hashes as they
-    /// become known.
-    live_requests: Arc<RwLock<PeerRequestMap>>,
+    last_req_id: Arc<AtomicU32>,
+    /// Manager configuration.
+    options: ManagerOptions,
+    /// Whether the task applying the retention policy is running.
+    retention_running: Arc<AtomicBool>,
     /// Active outbound requests (includes requests of local and remote origin).
-    outbound_requests: Arc<RwLock<HashMap<ReqId, (RequestOrigin, Message)>>>,
+    outbound_requests: Sharded<HashMap<ReqId, (RequestOrigin, Message)>>,
     /// Peers with whom communication is underway.
-    peers: Arc<RwLock<HashMap<PeerId, channel::Sender<Message>>>>,
-    /// Hashes of posts which have been requested from remote peers by the
-    /// local peer.
-    requested_posts: Arc<RwLock<HashSet<Hash>>>,
+    ///
+    /// The map is only locked for writing when a peer connects or
+    /// disconnects; the state of each peer has locks of its own.
+    peers: Arc<RwLock<HashMap<PeerId, Arc<PeerState>>>>,
+    /// The keys of the private channels of the local peer, by channel name.
+    #[cfg(feature = "private-channels")]
+    private_channels: Arc<RwLock<HashMap<Channel, SharedKey>>>,
+    /// Posts which have been requested from remote peers by the local peer.
+    requested_posts: Arc<RwLock<RequestedPosts>>,
+    /// Blobs which are being fetched from remote peers.
+    #[cfg(feature = "blobs")]
+    blob_fetches: Arc<RwLock<BlobFetches>>,
+    /// Whether the task requesting timed-out posts again is running.
+    retry_running: Arc<AtomicBool>,
+    /// Whether the outbound requests persisted by a previous session have
+    /// been restored.
+    requests_restored: Arc<AtomicBool>,
+    /// The source of the injected faults, if any.
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<Chaos>>,
     /// A cable store.
     pub store: S,
 }
 
 impl<S> CableManager<S>
 where
-    S: Store,
+    S: Store + Clone,
 {
     pub fn new(store: S) -> Self {
+        Self::with_options(store, ManagerOptions::default())
+    }
+
+    /// Return a builder of a manager over the given store, setting the
+    /// options of the manager one at a time.
+    ///
+    /// ```rust,ignore
+    /// let cable = CableManager::builder(store)
+    ///     .ttl(4)
+    ///     .max_live_requests(64)
+    ///     .events(true)
+    ///     .build();
+    /// ```
+    pub fn builder(store: S) -> ManagerBuilder<S> {
+        ManagerBuilder::new(store)
+    }
+
+    /// Create a new manager with the given configuration.
+    pub fn with_options(store: S, options: ManagerOptions) -> Self {
         Self {
             deleted_posts: Arc::new(RwLock::new(HashSet::new())),
+            channel_hashes: Arc::new(RwLock::new(HashMap::new())),
+            channel_names: ChannelInterner::default(),
+            events: CableEvents::new(options.events),
+            deliveries: Arc::new(RwLock::new(Deliveries::default())),
+            watched_channels: Arc::new(RwLock::new(HashSet::new())),
             forwarded_requests: Arc::new(RwLock::new(HashMap::new())),
-            handled_requests: Arc::new(RwLock::new(HashSet::new())),
-            last_peer_id: Arc::new(RwLock::new(0)),
+            handled_requests: Sharded::new(|| {
+                RecentRequests::new(
+                    options.handled_request_capacity.div_ceil(SHARDS),
+                    options.handled_request_ttl,
+                )
+            }),
+            issued_requests: Sharded::new(|| {
+                RecentRequests::new(
+                    options.handled_request_capacity.div_ceil(SHARDS),
+                    options.handled_request_ttl,
+                )
+            }),
+            relay_filter: Arc::new(Mutex::new(RelayFilter::default())),
+            last_peer_id: Arc::new(AtomicUsize::new(0)),
             // Generate a random u32 on startup to reduce chance of collisions.
-            last_req_id: Arc::new(RwLock::new(fastrand::u32(..))),
-            live_requests: Arc::new(RwLock::new(HashMap::new())),
-            outbound_requests: Arc::new(RwLock::new(HashMap::new())),
+            last_req_id: Arc::new(AtomicU32::new(fastrand::u32(..))),
+            #[cfg(feature = "chaos")]
+            chaos: options
+                .chaos
+                .clone()
+                .map(|chaos| Arc::new(Chaos::new(chaos))),
+            options,
+            retention_running: Arc::new(AtomicBool::new(false)),
+            outbound_requests: Sharded::default(),
             peers: Arc::new(RwLock::new(HashMap::new())),
-            requested_posts: Arc::new(RwLock::new(HashSet::new())),
+            #[cfg(feature = "private-channels")]
+            private_channels: Arc::new(RwLock::new(HashMap::new())),
+            requested_posts: Arc::new(RwLock::new(RequestedPosts::default())),
+            #[cfg(feature = "blobs")]
+            blob_fetches: Arc::new(RwLock::new(BlobFetches::default())),
+            retry_running: Arc::new(AtomicBool::new(false)),
+            requests_restored: Arc::new(AtomicBool::new(false)),
             store,
         }
     }
 
+    /// Retrieve the keypair of the local peer: the keypair supplied in the
+    /// options if any, otherwise the keypair of the store.
+    ///
+    /// An error is returned if the posts of the local peer are signed by a
+    /// signer supplied in the options, whose secret key is not available.
+    async fn keypair(&mut self) -> Result<Keypair, Error> {
+        if self.options.signer.is_some() {
+            return CableErrorKind::NoneError {
+                context: "the secret key is held by an external signer".to_string(),
+            }
+            .raise();
+        }
+
+        match self.options.keypair {
+            Some(keypair) => Ok(keypair),
+            None => Ok(self.store.get_or_create_keypair().await),
+        }
+    }
+
+    /// Return the public key of the signer or keypair supplied in the
+    /// options, if any.
+    fn supplied_public_key(&self) -> Option<[u8; 32]> {
+        match (&self.options.signer, self.options.keypair) {
+            (Some(signer), _) => Some(signer.public_key()),
+            (None, Some((public_key, _secret_key))) => Some(public_key),
+            (None, None) => None,
+        }
+    }
+
+    /// Retrieve the signer of the posts published by the local peer.
+    async fn signer(&mut self) -> Result<Arc<dyn Signer>, Error> {
+        match &self.options.signer {
+            Some(signer) => Ok(signer.clone()),
+            None => Ok(Arc::new(KeypairSigner::from(self.keypair().await?))),
+        }
+    }
+
     /// Retrieve the public key of the local peer.
     pub async fn get_public_key(&mut self) -> Result<[u8; 32], Error> {
-        let (pk, _sk) = self.store.get_or_create_keypair().await;
+        if let Some(public_key) = self.supplied_public_key() {
+            return Ok(public_key);
+        }
+        let (pk, _sk) = self.keypair().await?;
 
         Ok(pk)
     }
 
     /// Retrieve the secret key of the local peer.
+    ///
+    /// An error is returned if the posts of the local peer are signed by a
+    /// signer supplied in the options.
     pub async fn get_secret_key(&mut self) -> Result<[u8; 64], Error> {
-        let (_pk, sk) = self.store.get_or_create_keypair().await;
+        let (_pk, sk) = self.keypair().await?;
 
         Ok(sk)
     }
 
     /// Generate a new request ID.
     pub async fn new_req_id(&self) -> Result<(u32, ReqId), Error> {
-        let mut last_req_id = self.last_req_id.write().await;
-
-        // Reset request ID to 0 if the maximum u32 has been reached.
-        // Otherwise, increment the last request ID by one.
-        *last_req_id = if *last_req_id == u32::MAX {
-            0
-        } else {
-            *last_req_id + 1
-        };
-
-        let req_id = *last_req_id;
+        // Increment the last request ID by one, wrapping to 0 once the
+        // maximum u32 has been reached.
+        let req_id = self
+            .last_req_id
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_add(1);
         debug!("Generated a new request ID: {}", req_id);
 
         Ok((req_id, req_id.to_bytes()?.try_into().unwrap()))
@@ -177,170 +791,1413 @@ where
 
     /// Generate a new peer ID.
     async fn new_peer_id(&self) -> Result<usize, Error> {
-        let mut last_peer_id = self.last_peer_id.write().await;
-
         // Increment the last peer ID.
-        *last_peer_id += 1;
-        let peer_id = *last_peer_id;
+        let peer_id = self.last_peer_id.fetch_add(1, Ordering::Relaxed) + 1;
         debug!("Generated a new peer ID: {}", peer_id);
 
         Ok(peer_id)
     }
 
-    /// Create a channel time range request and a channel state request matching
-    /// the given channel parameters and broadcast them to all peers, listening
-    /// for responses.
-    pub async fn open_channel(
-        &mut self,
-        channel_opts: &ChannelOptions,
-    ) -> Result<PostStream<'_>, Error> {
-        debug!("Opening {}", channel_opts);
+    /// Retrieve the stored posts matching the given channel parameters,
+    /// continuing to return new posts as they are received or published.
+    ///
+    /// The text of the posts of a private channel is decrypted, and text
+    /// posts which cannot be decrypted are omitted.
+    #[cfg_attr(not(feature = "private-channels"), allow(unused_mut))]
+    pub(crate) async fn get_posts_live(&self, channel_opts: &ChannelOptions) -> PostStream<'_> {
+        let mut posts = self.store.get_posts_live(channel_opts).await;
+
+        #[cfg(feature = "private-channels")]
+        if let Some(key) = self
+            .private_channels
+            .read()
+            .await
+            .get(&channel_opts.channel)
+            .cloned()
+        {
+            posts = Box::new(posts.filter_map(move |post| match post {
+                Ok(post) => key.decrypt_post(post).map(Ok),
+                Err(err) => Some(Err(err)),
+            }));
+        }
+
+        posts
+    }
+
+    /// Mark the private channel shared with the user with the given public
+    /// key, returning the name of the channel.
+    ///
+    /// The text of the posts published to the channel is encrypted, and
+    /// decrypted when the posts of the channel are retrieved with
+    /// `open_channel()`. The channel must be marked in each session in which
+    /// it is used.
+    #[cfg(feature = "private-channels")]
+    pub async fn add_private_channel(&mut self, public_key: &[u8; 32]) -> Result<Channel, Error> {
+        let keypair = self.keypair().await?;
+        let channel = private_channel(&keypair.0, public_key);
+        let key = SharedKey::derive(&keypair, public_key)?;
+        self.private_channels
+            .write()
+            .await
+            .insert(channel.clone(), key);
+
+        Ok(channel)
+    }
+
+    /// Create a channel time range request and a channel state request matching
+    /// the given channel parameters and broadcast them to all peers, listening
+    /// for responses.
+    pub async fn open_channel(
+        &mut self,
+        channel_opts: &ChannelOptions,
+    ) -> Result<PostStream<'_>, Error> {
+        let channel_opts = &ChannelOptions {
+            channel: self.normalize_channel(&channel_opts.channel),
+            ..channel_opts.to_owned()
+        };
+        self.send_channel_requests(channel_opts).await?;
+
+        Ok(self.get_posts_live(channel_opts).await)
+    }
+
+    /// Open each of the channels matching the given channel parameters, as
+    /// by `open_channel()`, returning a single stream of the posts of all of
+    /// them.
+    ///
+    /// Each post is paired with the name of its channel. Posts without a
+    /// channel, such as `post/info` posts, are returned once for each
+    /// channel, as they are by the stream of each channel.
+    pub async fn open_channels(
+        &mut self,
+        channel_opts: &[ChannelOptions],
+    ) -> Result<ChannelPostStream<'_>, Error> {
+        let mut streams = Vec::new();
+        for channel_opts in channel_opts {
+            let channel_opts = ChannelOptions {
+                channel: self.normalize_channel(&channel_opts.channel),
+                ..channel_opts.to_owned()
+            };
+            self.send_channel_requests(&channel_opts).await?;
+
+            let channel = channel_opts.channel.clone();
+            let posts = self.get_posts_live(&channel_opts).await;
+            streams.push(posts.map(move |post| post.map(|post| (channel.clone(), post))));
+        }
+
+        Ok(Box::new(stream::select_all(streams)))
+    }
+
+    /// Return a handle to the given channel, through which it may be posted
+    /// to, its topic and members read and its posts streamed.
+    ///
+    /// No requests are made until the posts of the channel are streamed with
+    /// `ChannelHandle::posts_live()`.
+    ///
+    /// ```rust,ignore
+    /// let channel = cable.channel("default");
+    /// channel.post("hello").await?;
+    /// let mut posts = channel.posts_live().await?;
+    /// ```
+    pub fn channel<T: Into<String>>(&self, channel: T) -> ChannelHandle<S> {
+        let channel = self.normalize_channel(&channel.into());
+
+        ChannelHandle::new(self.clone(), channel)
+    }
+
+    /// Subscribe to the given channel, publishing a `post/join` post if the
+    /// local peer is not yet a member.
+    ///
+    /// The returned subscription holds live requests for the channel open
+    /// until it is unsubscribed, and provides a stream of its posts.
+    pub async fn subscribe<T: Into<String>>(
+        &mut self,
+        channel: T,
+    ) -> Result<Subscription<S>, Error> {
+        self.subscribe_with_options(channel, SubscribeOptions::default())
+            .await
+    }
+
+    /// Subscribe to the given channel with the given options.
+    pub async fn subscribe_with_options<T: Into<String>>(
+        &mut self,
+        channel: T,
+        options: SubscribeOptions,
+    ) -> Result<Subscription<S>, Error> {
+        let channel = self.normalize_channel(&channel.into());
+        self.options.limits.check_channel(&channel)?;
+
+        if options.join {
+            let public_key = self.get_public_key().await?;
+            if !self.store.is_channel_member(&channel, &public_key).await {
+                self.post_join(channel.as_str()).await?;
+            }
+        }
+
+        let channel_opts = ChannelOptions::new(channel, options.time_start, 0, options.limit);
+        self.send_channel_requests(&channel_opts).await?;
+
+        Ok(Subscription::new(self.clone(), channel_opts, options))
+    }
+
+    /// Unsubscribe from the given channel, cancelling its requests, and
+    /// publish a `post/leave` post if `leave` is `true` and the local peer is
+    /// a member of the channel.
+    pub async fn unsubscribe(&mut self, channel: &str, leave: bool) -> Result<(), Error> {
+        let channel = self.normalize_channel(channel);
+        self.close_channel(&channel).await?;
+
+        if leave {
+            let public_key = self.get_public_key().await?;
+            if self.store.is_channel_member(&channel, &public_key).await {
+                self.post_leave(channel).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create a channel time range request and a channel state request
+    /// matching the given (normalized) channel parameters and broadcast them
+    /// to all peers.
+    pub(crate) async fn send_channel_requests(
+        &self,
+        channel_opts: &ChannelOptions,
+    ) -> Result<(), Error> {
+        debug!("Opening {}", channel_opts);
+
+        let channel = channel_opts.channel.to_owned();
+        let future = 1;
+
+        // Create and broadcast a channel time range request.
+        let (_req_id, req_id_bytes) = self.new_req_id().await?;
+        let request = Message::channel_time_range_request(
+            NO_CIRCUIT,
+            req_id_bytes,
+            self.request_ttl(),
+            channel_opts.to_owned(),
+        );
+        self.insert_local_request(req_id_bytes, &request).await?;
+        self.broadcast(&request).await?;
+
+        // Create and broadcast a channel state request.
+        let (_req_id, req_id_bytes) = self.new_req_id().await?;
+        let request = Message::channel_state_request(
+            NO_CIRCUIT,
+            req_id_bytes,
+            self.request_ttl(),
+            channel,
+            future,
+        );
+        self.insert_local_request(req_id_bytes, &request).await?;
+        self.broadcast(&request).await?;
+
+        Ok(())
+    }
+
+    /// Create a channel list request with the given parameters and broadcast
+    /// it to all peers.
+    ///
+    /// The names of the channels returned by peers are added to the store and
+    /// may be retrieved with `Store::get_channels()`.
+    pub async fn request_channel_list(&self, skip: u64, limit: u64) -> Result<(), Error> {
+        debug!("Requesting channel list");
+
+        let (_req_id, req_id_bytes) = self.new_req_id().await?;
+        let request = Message::channel_list_request(
+            NO_CIRCUIT,
+            req_id_bytes,
+            self.request_ttl(),
+            skip,
+            limit,
+        );
+        self.insert_local_request(req_id_bytes, &request).await?;
+        self.broadcast(&request).await?;
+
+        Ok(())
+    }
+
+    /// Create a cancel request for all active outbound channel time range
+    /// and channel state requests originating locally and matching the given
+    /// channel name. Broadcast the cancel request(s) to all peers.
+    pub async fn close_channel(&self, channel: &str) -> Result<(), Error> {
+        let close_channel = &self.normalize_channel(channel);
+        debug!("Closing channel {}", close_channel);
+
+        // Vector to hold the request IDs of all outbound channel time range
+        // and channel state requests with channel names matching the given
+        // channel.
+        let mut channel_req_ids = Vec::new();
+
+        for shard in self.outbound_requests.shards() {
+            for (req_id, (request_origin, msg)) in shard.read().await.iter() {
+                if let MessageBody::Request {
+                    body:
+                        RequestBody::ChannelTimeRange { channel, .. }
+                        | RequestBody::ChannelState { channel, .. },
+                    ..
+                } = &msg.body
+                {
+                    // Ignore remotely-generated requests and non-matching
+                    // channel names.
+                    if request_origin.is_local() && channel == close_channel {
+                        channel_req_ids.push(*req_id);
+                    }
+                }
+            }
+        }
+
+        for channel_req_id in channel_req_ids {
+            let (_req_id, req_id_bytes) = self.new_req_id().await?;
+            let request = Message::cancel_request(
+                NO_CIRCUIT,
+                req_id_bytes,
+                self.request_ttl(),
+                channel_req_id,
+            );
+            self.broadcast(&request).await?;
+            self.outbound_requests
+                .shard(&channel_req_id)
+                .write()
+                .await
+                .remove(&channel_req_id);
+            if !self.is_read_only() {
+                self.store
+                    .clone()
+                    .remove_outbound_request(&channel_req_id)
+                    .await;
+            }
+        }
+
+        self.channel_hashes
+            .write()
+            .await
+            .remove(close_channel.as_str());
+
+        Ok(())
+    }
+
+    /// Archive the given channel, hiding it from `list_channels()` and
+    /// closing it so that its posts are no longer synced live.
+    ///
+    /// The stored history of an archived channel is retained and the channel
+    /// may still be opened explicitly. Persisted requests for the channel are
+    /// not restored by `restore_requests()`.
+    pub async fn archive_channel(&mut self, channel: &str) -> Result<(), Error> {
+        let channel = self.normalize_channel(channel);
+        debug!("Archiving channel {}", channel);
+
+        self.store.set_channel_archived(&channel, true).await;
+        self.close_channel(&channel).await
+    }
+
+    /// Unarchive the given channel, listing it in `list_channels()` again.
+    ///
+    /// The channel is not reopened; open it with `open_channel()` to resume
+    /// syncing.
+    pub async fn unarchive_channel(&mut self, channel: &str) {
+        let channel = self.normalize_channel(channel);
+        debug!("Unarchiving channel {}", channel);
+
+        self.store.set_channel_archived(&channel, false).await;
+    }
+
+    /// Close the given channel and delete all of its posts from the store,
+    /// returning the number of deleted posts.
+    ///
+    /// Unlike `Store::delete_posts()`, no tombstones are recorded, so the
+    /// channel may be opened and synced again later.
+    pub async fn drop_channel(&mut self, channel: &str) -> Result<usize, Error> {
+        let channel = self.normalize_channel(channel);
+        debug!("Dropping channel {}", channel);
+        if self.is_read_only() {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        self.close_channel(&channel).await?;
+        self.store.drop_channel(&channel).await
+    }
+
+    /// Publish a new archive post for the given channel and return the hash.
+    ///
+    /// Peers whose archive policy admits the local peer hide the channel
+    /// from their channel lists and from the channel list responses they
+    /// send. The stored history of the channel is retained.
+    #[cfg(feature = "archive-posts")]
+    pub async fn post_archive<T: Into<String>>(&mut self, channel: T) -> Result<Hash, Error> {
+        let channel = self.normalize_channel(&channel.into());
+        let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
+
+        // Ensure the channel name is of a valid length.
+        self.options.limits.check_channel(&channel)?;
+
+        // Construct a new archive post.
+        let post = Post::archive(public_key, links, timestamp, channel);
+
+        self.post(post).await
+    }
+
+    /// Unarchive the given channel by deleting the archive posts of the
+    /// local peer for it, returning the hash of the delete post, or `None`
+    /// if the local peer has not archived the channel.
+    #[cfg(feature = "archive-posts")]
+    pub async fn post_unarchive<T: Into<String>>(
+        &mut self,
+        channel: T,
+    ) -> Result<Option<Hash>, Error> {
+        let channel = self.normalize_channel(&channel.into());
+        let public_key = self.get_public_key().await?;
+
+        let hashes: Vec<Hash> = self
+            .store
+            .get_channel_archives(&channel)
+            .await
+            .into_iter()
+            .filter(|(author, _hash)| *author == public_key)
+            .map(|(_author, hash)| hash)
+            .collect();
+        if hashes.is_empty() {
+            return Ok(None);
+        }
+
+        self.post_delete(hashes).await.map(Some)
+    }
+
+    /// Query whether the given channel is hidden by archive posts admitted by
+    /// the archive policy of the manager.
+    #[cfg(feature = "archive-posts")]
+    pub async fn is_channel_archived_by_post(&self, channel: &Channel) -> bool {
+        let authors: Vec<PublicKey> = self
+            .store
+            .get_channel_archives(channel)
+            .await
+            .into_iter()
+            .map(|(author, _hash)| author)
+            .collect();
+
+        self.options.archive_policy.hides(&authors)
+    }
+
+    /// Remove the channels hidden by archive posts from the given channels.
+    #[cfg(feature = "archive-posts")]
+    async fn without_archived_by_post(&self, channels: Vec<Channel>) -> Vec<Channel> {
+        if self.options.archive_policy == ArchivePolicy::Ignore {
+            return channels;
+        }
+
+        let mut unarchived = Vec::with_capacity(channels.len());
+        for channel in channels {
+            if !self.is_channel_archived_by_post(&channel).await {
+                unarchived.push(channel);
+            }
+        }
+
+        unarchived
+    }
+
+    /// List all known channels, sorted by name, excluding those archived by
+    /// the local user.
+    ///
+    /// Archived channels are listed by `Store::get_archived_channels()`. With
+    /// the `archive-posts` feature, channels hidden by archive posts under
+    /// the archive policy are excluded too.
+    pub async fn list_channels(&self) -> Vec<Channel> {
+        let archived = self.store.get_archived_channels().await;
+
+        let channels = self
+            .store
+            .get_channels()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|channel| archived.binary_search(channel).is_err());
+
+        #[cfg(feature = "archive-posts")]
+        let channels = self
+            .without_archived_by_post(channels.collect())
+            .await
+            .into_iter();
+
+        channels.collect()
+    }
+
+    /// Report the progress of the synchronisation of the given channel with
+    /// remote peers.
+    ///
+    /// The hashes counted are those returned by peers since the channel was
+    /// opened by the local peer; they are forgotten when it is closed.
+    pub async fn sync_status(&self, channel: &str) -> Result<SyncStatus, Error> {
+        let channel = self.normalize_channel(channel);
+
+        let mut open_requests = 0;
+        for shard in self.outbound_requests.shards() {
+            open_requests += shard
+                .read()
+                .await
+                .values()
+                .filter(|(request_origin, request)| {
+                    request_origin.is_local() && request_channel(request) == Some(&channel)
+                })
+                .count();
+        }
+
+        let known: Vec<Hash> = self
+            .channel_hashes
+            .read()
+            .await
+            .get(channel.as_str())
+            .map(|hashes| hashes.iter().copied().collect())
+            .unwrap_or_default();
+        let missing = self.store.want(&known).await;
+        let requested_posts = self.requested_posts.read().await;
+        let pending_posts = missing
+            .iter()
+            .filter(|hash| requested_posts.contains(hash))
+            .count();
+        drop(requested_posts);
+
+        let mut oldest_timestamp = None;
+        let mut posts = self
+            .store
+            .get_posts(&ChannelOptions::new(channel.as_str(), 0, 0, 0))
+            .await;
+        while let Some(post) = posts.next().await {
+            let post = post?;
+            if post.get_channel() == Some(&channel) {
+                let timestamp = post.get_timestamp();
+                oldest_timestamp = Some(oldest_timestamp.unwrap_or(timestamp).min(timestamp));
+            }
+        }
+
+        Ok(SyncStatus {
+            open_requests,
+            pending_posts,
+            known_hashes: known.len(),
+            fetched_hashes: known.len() - missing.len(),
+            oldest_timestamp,
+        })
+    }
+
+    /// Mark the posts of the given channel as read up to and including the
+    /// post with the given hash.
+    ///
+    /// The last-read position of a channel only moves forward; marking an
+    /// older post as read has no effect.
+    pub async fn mark_read(&mut self, channel: &str, hash: &Hash) -> Result<(), Error> {
+        let channel = self.normalize_channel(channel);
+
+        let post = match self.store.get_post_payload(hash).await {
+            Some(payload) => Post::from_bytes(&payload)?.1,
+            None => {
+                return CableErrorKind::NoneError {
+                    context: format!("unknown post {}", hex::encode(hash)),
+                }
+                .raise()
+            }
+        };
+        let cursor = PageCursor {
+            timestamp: post.get_timestamp(),
+            hash: *hash,
+        };
+
+        self.advance_last_read(&channel, cursor).await;
+
+        Ok(())
+    }
+
+    /// Mark all stored posts of the given channel as read.
+    pub async fn mark_channel_read(&mut self, channel: &str) -> Result<(), Error> {
+        let channel = self.normalize_channel(channel);
+
+        let page = self.store.get_posts_page(&channel, None, 1).await?;
+        if let Some(post) = page.posts.first() {
+            let cursor = PageCursor {
+                timestamp: post.get_timestamp(),
+                hash: post.hash()?,
+            };
+            self.advance_last_read(&channel, cursor).await;
+        }
+
+        Ok(())
+    }
+
+    /// Count the unread `post/text` posts of the given channel, excluding
+    /// those authored by the local peer.
+    pub async fn unread_count(&self, channel: &str) -> Result<usize, Error> {
+        let channel = self.normalize_channel(channel);
+
+        self.store.get_unread_count(&channel).await
+    }
+
+    /// Retrieve the current topic of the given channel, as set by the latest
+    /// stored `post/topic` post, or `None` if no topic has been set.
+    ///
+    /// Subscribe to `CableEvent::TopicChanged` with `events()` to be notified
+    /// of changes to the topic.
+    pub async fn get_topic(&self, channel: &str) -> Option<Topic> {
+        let channel = self.normalize_channel(channel);
+
+        self.store
+            .get_channel_topic_and_hash(&channel)
+            .await
+            .map(|(topic, _hash)| topic)
+    }
+
+    /// Retrieve the current avatar of the given peer, as set by the latest
+    /// stored avatar-setting `post/info` post, or `None` if no avatar has
+    /// been set.
+    ///
+    /// An `Avatar::Blob` is fetched from peers with `fetch_blob()` when the
+    /// `blobs` feature is enabled.
+    pub async fn get_peer_avatar(&self, public_key: &PublicKey) -> Option<Avatar> {
+        self.store
+            .get_peer_avatar_and_hash(public_key)
+            .await
+            .map(|(avatar, _hash)| avatar)
+    }
+
+    /// Set the last-read position of the given channel, unless it is already
+    /// further along.
+    async fn advance_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        if self.is_read_only() {
+            return;
+        }
+
+        let last_read = self.store.get_last_read(channel).await;
+        if last_read.is_none_or(|last_read| cursor > last_read) {
+            self.store.set_last_read(channel, cursor).await;
+        }
+    }
+
+    /// Subscribe to the events of the manager, such as mentions of the local
+    /// user in received posts and failures to handle received messages.
+    ///
+    /// Any number of subscribers may be active at once. A subscriber is
+    /// removed once its stream is dropped.
+    pub async fn events(&self) -> CableEventStream {
+        self.events.subscribe().await
+    }
+
+    /// Record the given received post in the mention index and emit a
+    /// `CableEvent::Mention` if it is a `post/text` post which mentions the
+    /// current name or public key of any local identity.
+    async fn index_mentions(&self, post: &Post, hash: &Hash) -> Result<(), Error> {
+        let (channel, text) = match &post.body {
+            PostBody::Text { channel, text } => (channel, text),
+            _ => return Ok(()),
+        };
+
+        let mut public_keys = self.store.list_identities().await;
+        if let Some(public_key) = self.supplied_public_key() {
+            if !public_keys.contains(&public_key) {
+                public_keys.push(public_key);
+            }
+        }
+        if public_keys.contains(&post.get_public_key()) {
+            return Ok(());
+        }
+        let mut names = Vec::new();
+        for public_key in &public_keys {
+            if let Some((name, _hash)) = self.store.get_peer_name_and_hash(public_key).await {
+                names.push(name);
+            }
+        }
+
+        if mention::is_mention(text, &names, &public_keys) {
+            debug!("Received a mention in {}: {}", channel, hex::encode(hash));
+            self.store
+                .clone()
+                .insert_mention(channel, post.get_timestamp(), hash)
+                .await;
+            self.events
+                .send(CableEvent::Mention {
+                    channel: channel.to_owned(),
+                    hash: *hash,
+                    post: post.clone(),
+                })
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the hash of the current topic of the channel of each of the
+    /// given posts which is a `post/topic` post.
+    async fn topic_hashes(&self, posts: &[Post]) -> HashMap<Channel, Option<Hash>> {
+        let mut topic_hashes = HashMap::new();
+        for post in posts {
+            if let PostBody::Topic { channel, .. } = &post.body {
+                if !topic_hashes.contains_key(channel) {
+                    let topic = self.store.get_channel_topic_and_hash(channel).await;
+                    topic_hashes.insert(channel.to_owned(), topic.map(|(_topic, hash)| hash));
+                }
+            }
+        }
+
+        topic_hashes
+    }
+
+    /// Emit a `CableEvent::TopicChanged` for each of the given channels whose
+    /// current topic is no longer the topic with the given hash.
+    async fn notify_topic_changes(&self, topic_hashes: HashMap<Channel, Option<Hash>>) {
+        for (channel, previous_hash) in topic_hashes {
+            let Some((topic, hash)) = self.store.get_channel_topic_and_hash(&channel).await else {
+                continue;
+            };
+            if previous_hash != Some(hash) {
+                debug!("Topic of {} changed: {}", channel, hex::encode(hash));
+                self.events
+                    .send(CableEvent::TopicChanged {
+                        channel,
+                        topic,
+                        hash,
+                    })
+                    .await;
+            }
+        }
+    }
+
+    /// Record the delivery of the tracked posts among the given requested
+    /// hashes to the given peer, emitting a `CableEvent::PostDelivered` for
+    /// each post delivered for the first time.
+    async fn deliver_posts(&self, peer_id: PeerId, hashes: &[Hash]) {
+        let delivered: Vec<Hash> = {
+            let mut deliveries = self.deliveries.write().await;
+            hashes
+                .iter()
+                .filter(|hash| deliveries.deliver(hash, peer_id))
+                .copied()
+                .collect()
+        };
+        for hash in delivered {
+            debug!("Post delivered to peer {}: {}", peer_id, hex::encode(hash));
+            self.events
+                .send(CableEvent::PostDelivered { hash, peer_id })
+                .await;
+        }
+    }
+
+    /// Add a request of local origin to the outbound requests, persisting it
+    /// in the store so that it may be reissued after a restart.
+    async fn insert_local_request(&self, req_id: ReqId, request: &Message) -> Result<(), Error> {
+        self.outbound_requests
+            .shard(&req_id)
+            .write()
+            .await
+            .insert(req_id, (RequestOrigin::Local, request.clone()));
+        if !self.is_read_only() {
+            self.store
+                .clone()
+                .insert_outbound_request(&req_id, &request.to_bytes()?)
+                .await;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the outbound requests of local origin persisted in the store
+    /// by a previous session, reissuing them to all peers under fresh request
+    /// IDs. Returns the number of restored requests.
+    ///
+    /// This happens automatically when the first peer connection is
+    /// established; subsequent calls restore nothing. Persisted requests
+    /// matching a request made in the current session are discarded.
+    pub async fn restore_requests(&self) -> Result<usize, Error> {
+        if self.requests_restored.swap(true, Ordering::SeqCst) {
+            return Ok(0);
+        }
+
+        let mut store = self.store.clone();
+        let mut active = HashSet::new();
+        for shard in self.outbound_requests.shards() {
+            for (request_origin, request) in shard.read().await.values() {
+                if request_origin.is_local() {
+                    active.insert(request_key(request)?);
+                }
+            }
+        }
+
+        let mut restored = 0;
+        for (req_id, request_bytes) in store.get_outbound_requests().await {
+            // Requests made in the current session are already active.
+            if self
+                .outbound_requests
+                .shard(&req_id)
+                .read()
+                .await
+                .contains_key(&req_id)
+            {
+                continue;
+            }
+            if !self.is_read_only() {
+                store.remove_outbound_request(&req_id).await;
+            }
+
+            let mut request = match Message::from_bytes(&request_bytes) {
+                Ok((_, request)) => request,
+                Err(err) => {
+                    debug!("Discarding undecodable persisted request: {}", err);
+                    continue;
+                }
+            };
+            if !active.insert(request_key(&request)?) {
+                continue;
+            }
+            if let Some(channel) = request_channel(&request) {
+                if store.is_channel_archived(channel).await {
+                    debug!(
+                        "Discarding persisted request for archived channel {}",
+                        channel
+                    );
+                    continue;
+                }
+            }
+
+            let (_req_id, req_id_bytes) = self.new_req_id().await?;
+            request.header.req_id = req_id_bytes;
+            debug!("Restoring persisted request: {}", request);
+
+            self.insert_local_request(req_id_bytes, &request).await?;
+            self.broadcast(&request).await?;
+            restored += 1;
+        }
+
+        Ok(restored)
+    }
+
+    /// Listen for incoming peer messages and respond with locally-generated
+    /// messages.
+    ///
+    /// Decode each received message and pass it off to the handler.
+    pub async fn listen<T>(&self, stream: T) -> Result<(), Error>
+    where
+        T: AsyncRead + AsyncWrite + Clone + Unpin + Send + Sync + 'static,
+    {
+        debug!("Listening for incoming peer messages...");
+
+        // Generate a new peer ID.
+        let peer_id = self.new_peer_id().await?;
+
+        // Create a bounded message channel.
+        let (send, recv) = channel::bounded(OUTBOUND_QUEUE_SIZE);
+
+        // Create the channel which is closed to disconnect the peer, the
+        // channel which is closed to disconnect the peer once its queued
+        // frames are written and the channel which fails once the connection
+        // has been torn down.
+        let (disconnect, disconnected) = channel::bounded::<()>(1);
+        let (closing, close_requested) = channel::bounded::<()>(1);
+        let (_teardown, closed) = channel::bounded::<()>(1);
+
+        // Restore the requests persisted by a previous session before the
+        // first peer is sent the outbound requests.
+        self.restore_requests().await?;
+
+        // Insert the peer ID and channel sender into the list of peers.
+        let peer = PeerState::new(
+            send,
+            disconnect.clone(),
+            closing.clone(),
+            closed,
+            self.options.clock.now().ok(),
+        );
+        let memory = peer.memory.clone();
+        let traffic = peer.traffic.clone();
+        self.peers.write().await.insert(peer_id, Arc::new(peer));
+
+        // Announce the capabilities of the local peer before any request is
+        // sent to it.
+        if self.options.announce_capabilities {
+            self.announce_capabilities(peer_id).await?;
+        }
+
+        // Process and send outbound requests to the connected peer.
+        self.process_and_send_outbound_requests(stream.clone(), peer_id)
+            .await?;
+
+        // Track the requests of the peer in order to check the responses
+        // written to it.
+        let checker = match self.options.self_check {
+            SelfCheck::Off => None,
+            _ => Some(Arc::new(Mutex::new(SelfChecker::default()))),
+        };
+
+        let write_to_stream_res = {
+            let mut stream_c = stream.clone();
+            let disconnected_c = disconnected.clone();
+            let this = self.clone();
+            let checker = checker.clone();
+            let traffic = traffic.clone();
+
+            task::spawn(async move {
+                // The frame swapped behind the frame queued after it, if
+                // writes are reordered.
+                #[cfg(feature = "chaos")]
+                let mut held: Option<Frame> = None;
+
+                // Listen for incoming locally-generated messages.
+                loop {
+                    #[cfg(feature = "chaos")]
+                    let next = match held.take() {
+                        Some(frame) => Ok(frame),
+                        None => recv.recv().await,
+                    };
+                    #[cfg(not(feature = "chaos"))]
+                    let next = recv.recv().await;
+                    let Ok(frame) = next else {
+                        break;
+                    };
+
+                    #[cfg(feature = "chaos")]
+                    let frame = match &this.chaos {
+                        Some(chaos) if chaos.reorder_write() => match recv.try_recv() {
+                            Ok(later) => {
+                                held = Some(frame);
+                                later
+                            }
+                            Err(_) => frame,
+                        },
+                        _ => frame,
+                    };
+
+                    let Frame { msg, bytes } = frame;
+                    memory.dequeue(bytes.len());
+
+                    // Discard the queued messages of a disconnected peer.
+                    if disconnect.is_closed() {
+                        break;
+                    }
+
+                    if let Err(err) = this.self_check(&msg, &bytes, checker.as_deref()) {
+                        futures::AsyncWriteExt::close(&mut stream_c).await?;
+                        return Err(err);
+                    }
+
+                    // Write the message to the stream, disconnecting the peer
+                    // if the write fails or times out. A write in progress is
+                    // abandoned if the peer is disconnected.
+                    let write = async {
+                        Some(
+                            write_with_timeout(&mut stream_c, &bytes, this.options.write_timeout)
+                                .await,
+                        )
+                    };
+                    let abandon = async {
+                        let _ = disconnected_c.recv().await;
+                        None
+                    };
+                    match write.race(abandon).await {
+                        Some(Ok(())) => traffic.sent(bytes.len()),
+                        Some(Err(err)) => {
+                            disconnect.close();
+                            return CableErrorKind::TransportFailed {
+                                context: format!("failed to write to peer {}", peer_id),
+                            }
+                            .raise_with_source(err);
+                        }
+                        None => break,
+                    }
+
+                    debug!("Wrote a message to the TCP stream: {}", msg,);
+                }
+
+                // Close the stream once the queued messages of a peer which
+                // is disconnected cleanly have been written.
+                if closing.is_closed() && !disconnect.is_closed() {
+                    let _ = futures::AsyncWriteExt::close(&mut stream_c).await;
+                }
+
+                // Type inference fails without binding concretely to `Result`.
+                Result::<(), Error>::Ok(())
+            })
+        };
+
+        // Periodically ping the peer so that an idle but healthy connection
+        // is not mistaken for a dead one.
+        if let Some(interval) = self.options.keepalive_interval {
+            let this = self.clone();
+            task::spawn(async move {
+                loop {
+                    task::sleep(interval).await;
+
+                    // Stop pinging once the peer has been removed.
+                    if !this.peers.read().await.contains_key(&peer_id) {
+                        break;
+                    }
+
+                    let (_req_id, req_id_bytes) = this.new_req_id().await?;
+                    let ping = Message::post_request(NO_CIRCUIT, req_id_bytes, 0, Vec::new());
+                    this.send(peer_id, &ping).await?;
+                }
+
+                Result::<(), Error>::Ok(())
+            });
+        }
+
+        // Periodically apply the retention policy while any peer is
+        // connected. Only one such task runs at a time.
+        if let Some(policy) = &self.options.retention {
+            if !self.retention_running.swap(true, Ordering::SeqCst) {
+                let mut this = self.clone();
+                let interval = policy.interval;
+                task::spawn(async move {
+                    while !this.peers.read().await.is_empty() {
+                        if let Err(err) = this.prune().await {
+                            debug!("Failed to apply retention policy: {}", err);
+                        }
+                        task::sleep(interval).await;
+                    }
+                    this.retention_running.store(false, Ordering::SeqCst);
+                });
+            }
+        }
+
+        // Periodically request timed-out posts again while any peer is
+        // connected. Only one such task runs at a time.
+        if let Some(timeout) = self.options.post_request_timeout {
+            if !self.retry_running.swap(true, Ordering::SeqCst) {
+                let this = self.clone();
+                task::spawn(async move {
+                    while !this.peers.read().await.is_empty() {
+                        task::sleep(timeout).await;
+                        if let Err(err) = this.retry_requested_posts().await {
+                            debug!("Failed to request posts again: {}", err);
+                        }
+                    }
+                    this.retry_running.store(false, Ordering::SeqCst);
+                });
+            }
+        }
+
+        // Define the stream decoder parameters.
+        let options = DecodeOptions {
+            include_len: true,
+            max_size: self.options.limits.max_message_size,
+        };
+
+        let mut length_prefixed_stream = decode_with_options(stream, options);
+
+        // Spawn the handlers of the messages received from the peer, each
+        // fed by a bounded queue. The messages concerning a single request
+        // are queued for the same handler, preserving their order.
+        let handlers: Vec<channel::Sender<Message>> = (0..self.options.handler_concurrency.max(1))
+            .map(|_| {
+                let (send, recv) =
+                    channel::bounded::<Message>(self.options.inbound_queue_size.max(1));
+                let mut this = self.clone();
+                task::spawn(async move {
+                    while let Ok(msg) = recv.recv().await {
+                        #[cfg(feature = "chaos")]
+                        if let Some(chaos) = &this.chaos {
+                            task::sleep(chaos.handler_delay()).await;
+                        }
+
+                        // Handle the received message.
+                        if let Err(err) = this.handle(peer_id, &msg).await {
+                            warn!("Failed to handle message from peer {}: {}", peer_id, err);
+                            this.events
+                                .send(CableEvent::HandlerFailed {
+                                    peer_id,
+                                    error: Arc::new(err),
+                                })
+                                .await;
+                        }
+                    }
+                });
+
+                send
+            })
+            .collect();
+
+        // Continue reading from the peer stream until the stream is closed
+        // (either intentionally or because of an error).
+        let read_from_stream_res = async {
+            // The number of malformed frames received from the peer.
+            let mut malformed_frames = 0;
+
+            loop {
+                // Wait for the next message, giving up on the peer if nothing
+                // is received before the idle timeout elapses or if the peer
+                // is disconnected.
+                let next = async {
+                    match self.options.idle_timeout {
+                        Some(idle_timeout) => {
+                            future::timeout(idle_timeout, length_prefixed_stream.next())
+                                .await
+                                .map(Some)
+                        }
+                        None => Ok(Some(length_prefixed_stream.next().await)),
+                    }
+                };
+                let disconnect = async {
+                    let _ = disconnected.recv().await;
+                    Ok(None)
+                };
+                // A clean disconnection ends the stream of received messages.
+                let close = async {
+                    let _ = close_requested.recv().await;
+                    Ok(Some(None))
+                };
+                let read_buf = match next.race(disconnect).race(close).await {
+                    Ok(Some(read_buf)) => read_buf,
+                    Ok(None) => {
+                        debug!("Peer {} was disconnected", peer_id);
+
+                        return CableErrorKind::TransportFailed {
+                            context: format!("peer {} was disconnected", peer_id),
+                        }
+                        .raise();
+                    }
+                    Err(_) => {
+                        debug!("Peer {} timed out; closing connection", peer_id);
+
+                        return CableErrorKind::TransportFailed {
+                            context: format!("peer {} timed out", peer_id),
+                        }
+                        .raise_with_source(io::Error::new(
+                            ErrorKind::TimedOut,
+                            "peer connection idle timeout",
+                        ));
+                    }
+                };
+
+                let buf = match read_buf {
+                    Some(Ok(buf)) => buf,
+                    Some(Err(err)) => {
+                        return CableErrorKind::TransportFailed {
+                            context: format!("failed to read from peer {}", peer_id),
+                        }
+                        .raise_with_source(err);
+                    }
+                    None => break,
+                };
+                traffic.received(buf.len());
+
+                // Deserialize the received message.
+                let decoded = Message::from_bytes_with_limits(
+                    &buf,
+                    &self.options.limits,
+                    self.options.reject_excessive_ttl,
+                );
+                let msg = match decoded {
+                    Ok((_, msg)) => msg,
+                    // An excessive TTL is rejected outright, rather than
+                    // being skipped as malformed.
+                    Err(err) if is_ttl_error(&err) => return Err(err),
+                    Err(err) => {
+                        malformed_frames += 1;
+                        if malformed_frames > self.options.malformed_frame_limit {
+                            return Err(err);
+                        }
+
+                        debug!(
+                            "Skipping malformed frame {} from peer {}: {}",
+                            malformed_frames, peer_id, err
+                        );
+                        continue;
+                    }
+                };
+
+                if let Some(checker) = &checker {
+                    checker
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .inbound(&msg);
+                }
+
+                debug!("Received a message from the TCP stream: {}", msg,);
+
+                // Queue the message for its handler, waiting for room in the
+                // queue before reading any further.
+                let key = u32::from_be_bytes(ordering_key(&msg)) as usize;
+                handlers[key % handlers.len()].send(msg).await?;
+            }
+
+            Result::<(), Error>::Ok(())
+        }
+        .await;
+
+        // Allow the handlers to conclude once the queued messages have been
+        // handled.
+        drop(handlers);
+
+        // Discard all state held for the peer.
+        //
+        // Removing the peer drops the only sender for the peer's message
+        // channel, allowing the writer task to conclude once any queued
+        // messages are written.
+        if let Err(err) = self.remove_peer(peer_id).await {
+            debug!("Failed to tear down peer {}: {}", peer_id, err);
+        }
+
+        write_to_stream_res.await?;
+
+        read_from_stream_res
+    }
+
+    /// Discard all state held for the given peer once its connection has
+    /// closed.
+    ///
+    /// The live requests of the peer are dropped and the requests it made are
+    /// cancelled on the peers to which they were forwarded, as no peer remains
+    /// to relay their responses to. The peer is forgotten as a recipient of
+    /// forwarded requests and as a source of requested posts, and any posts
+    /// awaited from it are requested from other peers.
+    async fn remove_peer(&self, peer_id: PeerId) -> Result<(), Error> {
+        // The live requests of the peer are dropped with its state.
+        self.peers.write().await.remove(&peer_id);
 
-        let channel = channel_opts.channel.to_owned();
-        let future = 1;
+        let mut peer_req_ids: Vec<ReqId> = Vec::new();
+        for shard in self.outbound_requests.shards() {
+            shard.write().await.retain(|req_id, (request_origin, _)| {
+                let is_peer_request =
+                    matches!(request_origin, RequestOrigin::Remote(origin) if *origin == peer_id);
+                if is_peer_request {
+                    peer_req_ids.push(*req_id);
+                }
 
-        // Create and broadcast a channel time range request.
-        let (_req_id, req_id_bytes) = self.new_req_id().await?;
-        let request = Message::channel_time_range_request(
-            NO_CIRCUIT,
-            req_id_bytes,
-            TTL,
-            channel_opts.to_owned(),
-        );
-        self.outbound_requests
-            .write()
-            .await
-            .insert(req_id_bytes, (RequestOrigin::Local, request.clone()));
-        self.broadcast(&request).await?;
+                !is_peer_request
+            });
+        }
 
-        // Create and broadcast a channel state request.
-        let (_req_id, req_id_bytes) = self.new_req_id().await?;
-        let request =
-            Message::channel_state_request(NO_CIRCUIT, req_id_bytes, TTL, channel, future);
-        self.outbound_requests
+        self.cancel_forwarded_requests(&peer_req_ids).await?;
+        self.forwarded_requests
             .write()
             .await
-            .insert(req_id_bytes, (RequestOrigin::Local, request.clone()));
-        self.broadcast(&request).await?;
-
-        Ok(self.store.get_posts_live(channel_opts).await)
-    }
+            .retain(|_req_id, forwarded_to| {
+                forwarded_to.remove(&peer_id);
+                !forwarded_to.is_empty()
+            });
 
-    /// Create a cancel request for all active outbound channel time range
-    /// requests originating locally and matching the given channel name.
-    /// Broadcast the cancel request(s) to all peers.
-    pub async fn close_channel(&self, channel: &String) -> Result<(), Error> {
-        debug!("Closing channel {}", channel);
-        let close_channel = channel;
+        self.requested_posts.write().await.remove_peer(peer_id);
 
-        let mut outbound_requests = self.outbound_requests.write().await;
+        // Blobs which can no longer be fetched are reported as not found.
+        #[cfg(feature = "blobs")]
+        {
+            let mut blob_fetches = self.blob_fetches.write().await;
+            for hash in blob_fetches.remove_peer(peer_id) {
+                blob_fetches.finish(&hash, None);
+            }
+        }
 
-        // Vector to hold the request IDs of all outbound channel time range
-        // requests with channel names matching the given channel.
-        let mut channel_req_ids = Vec::new();
+        self.retry_requested_posts().await
+    }
 
-        for (req_id, (request_origin, msg)) in outbound_requests.iter() {
-            if let MessageBody::Request {
-                body: RequestBody::ChannelTimeRange { channel, .. },
-                ..
-            } = &msg.body
-            {
-                // Ignore remotely-generated requests and non-matching channel
-                // names.
-                if request_origin.is_local() && channel == close_channel {
-                    channel_req_ids.push(*req_id);
+    /// Stop relaying the responses to the given forwarded requests, which
+    /// have been removed from the outbound requests, and cancel them on the
+    /// peers to which they were forwarded.
+    async fn cancel_forwarded_requests(&self, req_ids: &[ReqId]) -> Result<(), Error> {
+        let mut forwarded_requests = self.forwarded_requests.write().await;
+        for req_id in req_ids {
+            self.relay_filter
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .forget(req_id);
+
+            if let Some(forwarded_to) = forwarded_requests.remove(req_id) {
+                let (_, cancel_req_id) = self.new_req_id().await?;
+                let request =
+                    Message::cancel_request(NO_CIRCUIT, cancel_req_id, self.request_ttl(), *req_id);
+                for forwarded_peer_id in forwarded_to {
+                    self.send(forwarded_peer_id, &request).await?;
                 }
             }
         }
 
-        for channel_req_id in channel_req_ids {
-            let (_req_id, req_id_bytes) = self.new_req_id().await?;
-            let request = Message::cancel_request(NO_CIRCUIT, req_id_bytes, TTL, channel_req_id);
-            self.broadcast(&request).await?;
-            outbound_requests.remove(&channel_req_id);
-        }
-
         Ok(())
     }
 
-    /// Listen for incoming peer messages and respond with locally-generated
-    /// messages.
+    /// Apply the retention policy of the manager to the store, returning the
+    /// hashes of the pruned posts.
     ///
-    /// Decode each received message and pass it off to the handler.
-    pub async fn listen<T>(&self, stream: T) -> Result<(), Error>
-    where
-        T: AsyncRead + AsyncWrite + Clone + Unpin + Send + Sync + 'static,
-    {
-        debug!("Listening for incoming peer messages...");
+    /// Pruned posts are tombstoned by the store, so they are no longer
+    /// advertised to peers or requested from them.
+    pub async fn prune(&mut self) -> Result<Vec<Hash>, Error> {
+        let policy = match &self.options.retention {
+            Some(policy) if !self.is_read_only() => policy.clone(),
+            _ => return Ok(Vec::new()),
+        };
 
-        // Generate a new peer ID.
-        let peer_id = self.new_peer_id().await?;
+        let pruned = self.store.prune(&policy, self.options.clock.now()?).await?;
 
-        // Create a bounded message channel.
-        let (send, recv) = channel::bounded(100);
+        let mut requested_posts = self.requested_posts.write().await;
+        for hash in &pruned {
+            requested_posts.remove(hash);
+        }
 
-        // Insert the peer ID and channel sender into the list of peers.
-        self.peers.write().await.insert(peer_id, send);
+        Ok(pruned)
+    }
 
-        // Process and send outbound requests to the connected peer.
-        self.process_and_send_outbound_requests(stream.clone(), peer_id)
-            .await?;
+    /// Write the history of the given channel to the given writer as JSON
+    /// lines, returning the number of exported posts.
+    ///
+    /// Each line is a single post as produced by `Post::to_json()`, including
+    /// the posts which define the channel state. Posts are written in order
+    /// of their timestamps.
+    #[cfg(feature = "serde")]
+    pub async fn export_channel_json(
+        &self,
+        channel: &Channel,
+        writer: &mut (dyn std::io::Write + Send),
+    ) -> Result<usize, Error> {
+        let mut hashes = HashSet::new();
+        let mut posts = Vec::new();
+
+        let mut stream = self
+            .store
+            .get_posts(&ChannelOptions::new(channel.clone(), 0, 0, 0))
+            .await;
+        while let Some(post) = stream.next().await {
+            let post = post?;
+            if hashes.insert(post.hash()?) {
+                posts.push(post);
+            }
+        }
 
-        let write_to_stream_res = {
-            let mut stream_c = stream.clone();
+        // The channel state includes posts which are not returned by
+        // `get_posts()`, such as `post/join` and `post/topic` posts.
+        for hash in self.store.get_channel_state_hashes(channel).await {
+            if hashes.insert(hash) {
+                if let Some(payload) = self.store.get_post_payload(&hash).await {
+                    let (_, post) = Post::from_bytes(&payload)?;
+                    posts.push(post);
+                }
+            }
+        }
 
-            task::spawn(async move {
-                // Listen for incoming locally-generated messages.
-                while let Ok(msg) = recv.recv().await {
-                    let msg_bytes = &msg.to_bytes()?;
+        posts.sort_by_key(|post| post.header.timestamp);
+        for post in &posts {
+            writeln!(writer, "{}", post.to_json()?)?;
+        }
 
-                    // Write the message to the stream.
-                    stream_c.write_all(msg_bytes).await?;
+        Ok(posts.len())
+    }
 
-                    debug!("Wrote a message to the TCP stream: {}", msg,);
-                }
+    /// Request posts again whose request has timed out or was made to a
+    /// peer which has disconnected, from another connected peer which
+    /// advertised them.
+    async fn retry_requested_posts(&self) -> Result<(), Error> {
+        let connected = self.peers.read().await.keys().copied().collect();
+        let to_request = self.requested_posts.write().await.retry(
+            &connected,
+            self.options.clock.now()?,
+            self.options.post_request_timeout,
+        );
 
-                // Type inference fails without binding concretely to `Result`.
-                Result::<(), Error>::Ok(())
-            })
-        };
+        for (peer_id, hashes) in to_request {
+            debug!(
+                "Requesting {} posts again from peer {}",
+                hashes.len(),
+                peer_id
+            );
 
-        // Define the stream decoder parameters.
-        let options = DecodeOptions {
-            include_len: true,
-            ..Default::default()
-        };
+            let (_, req_id) = self.new_req_id().await?;
+            let request = Message::post_request(NO_CIRCUIT, req_id, self.request_ttl(), hashes);
+            self.send(peer_id, &request).await?;
+        }
 
-        let mut length_prefixed_stream = decode_with_options(stream, options);
+        Ok(())
+    }
 
-        // Iterate over the stream.
-        while let Some(read_buf) = length_prefixed_stream.next().await {
-            let buf = read_buf?;
+    /// Return a snapshot of the state of the connection to the given peer,
+    /// or `None` if the peer is not connected.
+    ///
+    /// Useful for diagnostics dashboards and for debugging a sync which has
+    /// stalled: the snapshot holds the age of the connection, the traffic
+    /// exchanged, the requests sent to the peer which remain active and the
+    /// live requests the peer holds against the local peer.
+    pub async fn peer_info(&self, peer_id: PeerId) -> Option<PeerInfo> {
+        let peer = self.peers.read().await.get(&peer_id).cloned()?;
+
+        let connection_age = match (peer.connected_at, self.options.clock.now()) {
+            (Some(connected_at), Ok(now)) => {
+                Some(Duration::from_millis(now.saturating_sub(connected_at)))
+            }
+            _ => None,
+        };
+        let (messages_sent, bytes_sent, messages_received, bytes_received) = peer.traffic.totals();
+
+        // Every active request is sent to each peer, other than those
+        // forwarded on behalf of the peer itself.
+        let mut outstanding_requests = Vec::new();
+        for shard in self.outbound_requests.shards() {
+            for (req_id, (origin, msg)) in shard.read().await.iter() {
+                if !matches!(origin, RequestOrigin::Remote(id) if *id == peer_id) {
+                    outstanding_requests.push((*req_id, msg.clone()));
+                }
+            }
+        }
 
-            // Deserialize the received message.
-            let (_, msg) = Message::from_bytes(&buf)?;
+        let live_requests = peer
+            .live_requests
+            .read()
+            .await
+            .iter()
+            .map(|live_request| match live_request {
+                LiveRequest::ChannelState(req_id, channel) => (*req_id, channel.to_string()),
+                LiveRequest::ChannelTimeRange(req_id, channel_opts) => {
+                    (*req_id, channel_opts.channel.clone())
+                }
+            })
+            .collect();
+        let capabilities = peer.capabilities.read().await.clone();
+
+        Some(PeerInfo {
+            peer_id,
+            connected_at: peer.connected_at,
+            connection_age,
+            messages_sent,
+            bytes_sent,
+            messages_received,
+            bytes_received,
+            outstanding_requests,
+            requested_posts: self.requested_posts.read().await.requested_from(peer_id),
+            live_requests,
+            capabilities,
+        })
+    }
 
-            debug!("Received a message from the TCP stream: {}", msg,);
+    /// Return a snapshot of the state of the connection to each connected
+    /// peer, ordered by peer ID.
+    pub async fn list_connections(&self) -> Vec<PeerInfo> {
+        let mut peer_ids = self.get_peer_ids().await;
+        peer_ids.sort_unstable();
 
-            let mut this = self.clone();
-            task::spawn(async move {
-                // Handle the received message.
-                if let Err(err) = this.handle(peer_id, &msg).await {
-                    // TODO: Consider a better way to report.
-                    eprintln!("{err}");
-                }
-            });
+        let mut connections = Vec::new();
+        for peer_id in peer_ids {
+            if let Some(info) = self.peer_info(peer_id).await {
+                connections.push(info);
+            }
         }
 
-        // Continue reading and writing to the peer stream until the stream is
-        // closed (either intentionally or because of an error).
-        write_to_stream_res.await?;
+        connections
+    }
 
-        // Remove the peer from the list of active peers.
-        self.peers.write().await.remove(&peer_id);
+    /// Disconnect the given peer cleanly, returning `false` if the peer is
+    /// not connected.
+    ///
+    /// No further messages are read from the peer. The messages already
+    /// queued for the peer are written before its stream is closed, and the
+    /// state held for the peer is discarded as when it disconnects: its live
+    /// and forwarded requests are dropped and the posts awaited from it are
+    /// requested from other peers. Returns once the connection has been torn
+    /// down.
+    pub async fn disconnect(&self, peer_id: PeerId) -> bool {
+        let closed = match self.peers.read().await.get(&peer_id) {
+            Some(peer) => {
+                peer.closing.close();
+                peer.closed.clone()
+            }
+            None => return false,
+        };
 
-        Ok(())
+        // The state of the peer must not be held while waiting, as the
+        // writer task concludes once every sender of its frames is dropped.
+        debug!("Disconnecting peer {}", peer_id);
+        let _ = closed.recv().await;
+
+        true
     }
+
     pub async fn get_peer_ids(&self) -> Vec<usize> {
         self.peers
             .read()
@@ -354,42 +2211,91 @@ where
         self.store.get_latest_hashes(channel).await
     }
 
+    /// Retrieve the stored post with the given hash, returning `None` if the
+    /// post is unknown or has been deleted.
+    ///
+    /// Use this to resolve a link or quoted reply locally before requesting
+    /// the post from peers with a post request.
+    pub async fn get_post(&self, hash: &Hash) -> Result<Option<Post>, Error> {
+        match self.store.get_post_payload(hash).await {
+            Some(payload) => Ok(Some(Post::from_bytes(&payload)?.1)),
+            None => Ok(None),
+        }
+    }
+
+    /// Query if the post with the given hash is stored.
+    pub async fn has_post(&self, hash: &Hash) -> bool {
+        self.store.get_post_payload(hash).await.is_some()
+    }
+
+    /// Return the tree of replies to the post with the given hash, as
+    /// published with `post_reply()` and stored locally.
+    pub async fn get_thread(&self, root_hash: &Hash) -> Thread {
+        self.store.get_thread(root_hash).await
+    }
+
+    /// Return the delivery state of the post with the given hash, published
+    /// by the local peer while `ManagerOptions::track_deliveries` was set.
+    /// Returns `None` for any other post.
+    pub async fn delivery_status(&self, hash: &Hash) -> Option<DeliveryStatus> {
+        self.deliveries.read().await.status(hash)
+    }
+
+    /// Wait for the post with the given hash, published by the local peer
+    /// while `ManagerOptions::track_deliveries` was set, to be requested by
+    /// a peer, returning the ID of the first peer to request it.
+    ///
+    /// Returns `None` immediately for any other post, and once the post is
+    /// deleted if it has not been delivered.
+    pub async fn delivered(&self, hash: &Hash) -> Option<PeerId> {
+        let receiver = self.deliveries.write().await.wait(hash)?;
+
+        receiver.recv().await.ok()
+    }
+
     /// Query if the request defined by the given peer ID and request ID is an
     /// active live request.
     async fn is_live_request(&mut self, peer_id: &PeerId, req_id: &ReqId) -> bool {
-        let live_requests = self.live_requests.read().await;
-        // Check if there are active live requests for the given peer ID.
-        if let Some(peer_requests) = live_requests.get(peer_id) {
-            // Iterate over the peer requests and report on the presence of the
-            // given request ID.
-            peer_requests
-                .iter()
-                .any(|live_request| live_request.req_id() == req_id)
-        } else {
-            false
-        }
+        // Check if the given peer is connected.
+        let peer = match self.peers.read().await.get(peer_id) {
+            Some(peer) => peer.clone(),
+            None => return false,
+        };
+
+        // Iterate over the peer requests and report on the presence of the
+        // given request ID.
+        let is_live = peer
+            .live_requests
+            .read()
+            .await
+            .iter()
+            .any(|live_request| live_request.req_id() == req_id);
+
+        is_live
     }
 
     /// Remove the live request defined by the given peer ID and request ID.
     async fn remove_live_request(&mut self, peer_id: &PeerId, req_id: &ReqId) -> Result<(), Error> {
-        // Remove the request from the map of live requests.
-        let mut live_requests = self.live_requests.write().await;
-        if let Some(peer_requests) = live_requests.get_mut(peer_id) {
+        // Remove the request from the live requests of the peer.
+        let peer = self.peers.read().await.get(peer_id).cloned();
+        if let Some(peer) = peer {
             // Iterate over the peer requests and retain only the
             // requests for which the ID does not match the given
             // request ID.
-            peer_requests.retain(|live_request| live_request.req_id() != req_id);
+            peer.live_requests
+                .write()
+                .await
+                .retain(|live_request| live_request.req_id() != req_id);
         }
 
         Ok(())
     }
 
-    /// Process all outbound requests, sending each one to the connected
-    /// peer if it meets certain requirements.
+    /// Send all active outbound requests to a newly connected peer.
     ///
-    /// This method takes into account the TTL of the request. It also ensures
-    /// that cancel requests are forwarded to peers to whom the referenced
-    /// request was previously sent.
+    /// These are the requests of local origin and the requests received from
+    /// other peers with a TTL above 0, which are forwarded with their TTL
+    /// decremented.
     pub async fn process_and_send_outbound_requests<T>(
         &self,
         mut stream: T,
@@ -398,68 +2304,105 @@ where
     where
         T: AsyncRead + AsyncWrite + Clone + Unpin + Send + Sync + 'static,
     {
-        'requests: for (req_id, (request_origin, msg)) in self.outbound_requests.read().await.iter()
-        {
-            if let MessageBody::Request { ttl, body } = &msg.body {
-                // If the outbound request is a cancel request originating
-                // remotely, check if we previously sent the referenced
-                // request to the connected peer. If so, forward the cancel
-                // request. If not, move on to the next request without sending
-                // this one.
-                if let RequestBody::Cancel { cancel_id } = body {
-                    debug!("Processing cancel request...");
-                    if let RequestOrigin::Remote = request_origin {
-                        let mut forwarded_requests = self.forwarded_requests.write().await;
-                        if let Some(peers) = forwarded_requests.get_mut(cancel_id) {
-                            if peers.contains(&peer_id) {
-                                stream.write_all(&msg.to_bytes()?).await?;
-
-                                // Remove the connected peer from the set of
-                                // forwarded requests for the given cancel ID.
-                                peers.remove(&peer_id);
-
-                                // If the peer set for given cancel ID is
-                                // empty, remove the ID from the map of
-                                // forwarded requests.
-                                if peers.is_empty() {
-                                    forwarded_requests.remove(cancel_id);
-                                }
-                            } else {
-                                // Terminate the current iteration of the loop
-                                // and process the next request.
-                                continue 'requests;
-                            }
-                        }
-                    }
-                }
-                if *ttl == 0 {
-                    // The TTL for this request has been exhausted.
-                    self.outbound_requests.write().await.remove(req_id);
-                } else {
-                    // Send the message to the connected peer.
-                    stream.write_all(&msg.to_bytes()?).await?;
-
-                    // If the request originated remotely, add it to the list
-                    // of forwarded requests. This facilitates forwarding
-                    // cancel requests to these peers in the future, if
-                    // required.
-                    if let RequestOrigin::Remote = request_origin {
-                        let mut forwarded_requests = self.forwarded_requests.write().await;
-                        if let Some(peers) = forwarded_requests.get_mut(req_id) {
-                            peers.insert(peer_id);
-                        } else {
-                            let mut peer_set = HashSet::new();
-                            peer_set.insert(peer_id);
-                            forwarded_requests.insert(*req_id, peer_set);
-                        }
-                    }
+        // Copy the requests, so that no shard is locked while writing to the
+        // stream.
+        let mut outbound_requests = Vec::new();
+        for shard in self.outbound_requests.shards() {
+            for (req_id, (request_origin, msg)) in shard.read().await.iter() {
+                outbound_requests.push((*req_id, request_origin.is_local(), msg.clone()));
+            }
+        }
+
+        for (req_id, is_local, msg) in outbound_requests {
+            // Send the message to the connected peer.
+            //
+            // Remote requests are only added to the outbound requests if
+            // they were received with a TTL above 0. The forwarded copy of
+            // a request received with a TTL of 1 has a TTL of 0, so the
+            // connected peer handles it without forwarding it again.
+            let msg_bytes = msg.to_bytes()?;
+            self.self_check(&msg, &msg_bytes, None)?;
+            self.issued_request(req_id).await?;
+            if let Err(err) =
+                write_with_timeout(&mut stream, &msg_bytes, self.options.write_timeout).await
+            {
+                return CableErrorKind::TransportFailed {
+                    context: format!("failed to write to peer {}", peer_id),
                 }
+                .raise_with_source(err);
+            }
+            if let Some(peer) = self.peers.read().await.get(&peer_id) {
+                peer.traffic.sent(msg_bytes.len());
+            }
+
+            // If the request originated remotely, add the connected peer to
+            // the set of peers to which it has been forwarded. This
+            // facilitates forwarding cancel requests to these peers in the
+            // future, if required.
+            if !is_local {
+                self.forwarded_requests
+                    .write()
+                    .await
+                    .entry(req_id)
+                    .or_default()
+                    .insert(peer_id);
             }
         }
 
         Ok(())
     }
 
+    /// Check an outgoing message against the constraints of the cable
+    /// specification, according to the self-check option of the manager.
+    ///
+    /// Returns an error only if the message violates the specification and
+    /// the manager is configured to fail on violations.
+    fn self_check(
+        &self,
+        msg: &Message,
+        msg_bytes: &[u8],
+        checker: Option<&Mutex<SelfChecker>>,
+    ) -> Result<(), Error> {
+        if self.options.self_check == SelfCheck::Off {
+            return Ok(());
+        }
+
+        let result = match checker {
+            Some(checker) => checker
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .check(msg, msg_bytes),
+            None => self_check::check_message(msg, msg_bytes),
+        };
+
+        match (result, self.options.self_check) {
+            (Err(err), SelfCheck::Fail) => Err(err),
+            (Err(err), _) => {
+                warn!("Outgoing message {}: {}", msg, err);
+                Ok(())
+            }
+            (Ok(()), _) => Ok(()),
+        }
+    }
+
+    /// Return the TTL of the requests made by the local peer.
+    fn request_ttl(&self) -> u8 {
+        self.options.ttl.min(self.options.limits.max_ttl)
+    }
+
+    /// Query whether the manager is read-only, either because it was
+    /// configured so with `ManagerOptions::read_only` or because its store
+    /// was opened read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.options.read_only || self.store.is_read_only()
+    }
+
+    /// Normalize a channel name given by the local user according to the
+    /// configured policy.
+    fn normalize_channel(&self, channel: &str) -> Channel {
+        self.options.channel_normalization.normalize(channel)
+    }
+
     /// Post header value generator.
     async fn post_header_values(
         &mut self,
@@ -471,7 +2414,7 @@ where
         } else {
             Vec::new()
         };
-        let timestamp = now()?;
+        let timestamp = self.options.clock.now()?;
 
         Ok((public_key, links, timestamp))
     }
@@ -484,12 +2427,57 @@ where
     ) -> Result<Hash, Error> {
         debug!("Posting text post...");
 
-        let channel = channel.into();
+        let channel = self.normalize_channel(&channel.into());
         let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
         let text = text.into();
 
-        // Ensure the text does not exceed 4096 bytes.
-        validation::validate_text(&text)?;
+        // Encrypt the text of posts to a private channel.
+        #[cfg(feature = "private-channels")]
+        let text = match self.private_channels.read().await.get(&channel) {
+            Some(key) => key.encrypt(&text),
+            None => text,
+        };
+
+        // Ensure the text does not exceed the maximum length.
+        self.options.limits.check_text(&text)?;
+
+        // Construct a new text post.
+        let post = Post::text(public_key, links, timestamp, channel, text);
+
+        self.post(post).await
+    }
+
+    /// Publish a new text post replying to the post with the given parent
+    /// hash and return the hash.
+    ///
+    /// The text is prefixed with a reply marker naming the parent and the
+    /// parent is included in the links of the post. The text of replies to
+    /// a private channel is encrypted along with the marker, so that they
+    /// are not indexed as replies.
+    pub async fn post_reply<T: Into<String>, U: Into<String>>(
+        &mut self,
+        channel: T,
+        parent: &Hash,
+        text: U,
+    ) -> Result<Hash, Error> {
+        debug!("Posting reply to {}...", hex::encode(parent));
+
+        let channel = self.normalize_channel(&channel.into());
+        let (public_key, mut links, timestamp) = self.post_header_values(&channel).await?;
+        if !links.contains(parent) {
+            links.push(*parent);
+        }
+        let text = thread::reply_text(parent, &text.into());
+
+        // Encrypt the text of posts to a private channel.
+        #[cfg(feature = "private-channels")]
+        let text = match self.private_channels.read().await.get(&channel) {
+            Some(key) => key.encrypt(&text),
+            None => text,
+        };
+
+        // Ensure the text does not exceed the maximum length.
+        self.options.limits.check_text(&text)?;
 
         // Construct a new text post.
         let post = Post::text(public_key, links, timestamp, channel, text);
@@ -502,7 +2490,7 @@ where
     pub async fn post_delete(&mut self, hashes: Vec<Hash>) -> Result<Hash, Error> {
         let public_key = self.get_public_key().await?;
         let links = Vec::new();
-        let timestamp = now()?;
+        let timestamp = self.options.clock.now()?;
 
         // Add the hashes to the store of deleted posts.
         //
@@ -511,6 +2499,13 @@ where
         let mut deleted_posts = self.deleted_posts.write().await;
         deleted_posts.extend(&hashes);
 
+        // Stop tracking the delivery of the deleted posts.
+        let mut deliveries = self.deliveries.write().await;
+        for hash in &hashes {
+            deliveries.forget(hash);
+        }
+        drop(deliveries);
+
         // Drop the mutable borrow of `self` to allow the later
         // call to `self.post()` (immutable borrow).
         drop(deleted_posts);
@@ -525,7 +2520,7 @@ where
     pub async fn post_info_name(&mut self, username: &str) -> Result<Hash, Error> {
         let public_key = self.get_public_key().await?;
         let links = Vec::new();
-        let timestamp = now()?;
+        let timestamp = self.options.clock.now()?;
 
         // Validation is performed as part of this method.
         let name_info = UserInfo::name(username)?;
@@ -536,18 +2531,34 @@ where
         self.post(post).await
     }
 
+    /// Publish a new info post with the given avatar and return the hash.
+    pub async fn post_info_avatar(&mut self, avatar: &Avatar) -> Result<Hash, Error> {
+        let public_key = self.get_public_key().await?;
+        let links = Vec::new();
+        let timestamp = self.options.clock.now()?;
+
+        // Ensure an inline avatar does not exceed the maximum size.
+        self.options.limits.check_avatar(avatar)?;
+        let avatar_info = UserInfo::new("avatar", avatar.to_string());
+
+        // Construct a new info post.
+        let post = Post::info(public_key, links, timestamp, vec![avatar_info]);
+
+        self.post(post).await
+    }
+
     /// Publish a new topic post for the given channel and return the hash.
     pub async fn post_topic<T: Into<String>, U: Into<String>>(
         &mut self,
         channel: T,
         topic: U,
     ) -> Result<Hash, Error> {
-        let channel = channel.into();
+        let channel = self.normalize_channel(&channel.into());
         let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
         let topic = topic.into();
 
-        // Ensure the topic is between 0 and 512 UTF-8 codepoints.
-        validation::validate_topic(&topic)?;
+        // Ensure the topic does not exceed the maximum length.
+        self.options.limits.check_topic(&topic)?;
 
         // Construct a new topic post.
         let post = Post::topic(public_key, links, timestamp, channel, topic);
@@ -555,13 +2566,35 @@ where
         self.post(post).await
     }
 
+    /// Publish a new reaction post to the post with the given hash in the
+    /// given channel, returning the hash of the new post.
+    #[cfg(feature = "reactions")]
+    pub async fn post_reaction<T: Into<String>, U: Into<String>>(
+        &mut self,
+        channel: T,
+        target: &Hash,
+        reaction: U,
+    ) -> Result<Hash, Error> {
+        let channel = self.normalize_channel(&channel.into());
+        let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
+        let reaction = reaction.into();
+
+        // Ensure the reaction is between 1 and 16 UTF-8 codepoints.
+        self.options.limits.check_reaction(&reaction)?;
+
+        // Construct a new reaction post.
+        let post = Post::reaction(public_key, links, timestamp, channel, *target, reaction);
+
+        self.post(post).await
+    }
+
     /// Publish a new join post for the given channel and return the hash.
     pub async fn post_join<T: Into<String>>(&mut self, channel: T) -> Result<Hash, Error> {
-        let channel = channel.into();
+        let channel = self.normalize_channel(&channel.into());
         let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
 
-        // Ensure the channel name is between 1 and 64 UTF-8 codepoints.
-        validation::validate_channel(&channel)?;
+        // Ensure the channel name is of a valid length.
+        self.options.limits.check_channel(&channel)?;
 
         // Construct a new join post.
         let post = Post::join(public_key, links, timestamp, channel);
@@ -571,11 +2604,11 @@ where
 
     /// Publish a new leave post for the given channel and return the hash.
     pub async fn post_leave<T: Into<String>>(&mut self, channel: T) -> Result<Hash, Error> {
-        let channel = channel.into();
+        let channel = self.normalize_channel(&channel.into());
         let (public_key, links, timestamp) = self.post_header_values(&channel).await?;
 
-        // Ensure the channel name is between 1 and 64 UTF-8 codepoints.
-        validation::validate_channel(&channel)?;
+        // Ensure the channel name is of a valid length.
+        self.options.limits.check_channel(&channel)?;
 
         // Construct a new leave post.
         let post = Post::leave(public_key, links, timestamp, channel);
@@ -583,37 +2616,209 @@ where
         self.post(post).await
     }
 
-    /// Publish a post and return the hash.
-    pub async fn post(&mut self, mut post: Post) -> Result<Hash, Error> {
-        // Sign the post if required.
-        if !post.is_signed() {
-            post.sign(&self.get_secret_key().await?)?;
+    /// Store the given bytes as a blob and return its hash, which may be
+    /// linked from the text of a post with `blob_link()` so that peers can
+    /// fetch the blob.
+    ///
+    /// An error is returned if storing the blob would exceed the blob quota.
+    #[cfg(feature = "blobs")]
+    pub async fn attach_file(&mut self, data: Vec<u8>) -> Result<Hash, Error> {
+        if self.is_read_only() {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        self.store.store_blob(data, self.options.blob_quota).await
+    }
+
+    /// Return the blob with the given hash, fetching it from the connected
+    /// peers if it is not held by the local store.
+    ///
+    /// The blob is requested from every connected peer which has not
+    /// announced that it does not understand blob requests, and fetched in
+    /// chunks from the first which holds it. A fetched blob is stored unless the
+    /// manager is read-only or the blob would exceed the blob quota. An
+    /// error is returned if no connected peer holds the blob.
+    #[cfg(feature = "blobs")]
+    pub async fn fetch_blob(&self, hash: &Hash) -> Result<Vec<u8>, Error> {
+        if let Some(blob) = self.store.get_blob(hash).await {
+            return Ok(blob);
+        }
+
+        // Blobs are only requested from the peers which may understand blob
+        // requests, so that the fetch does not await peers which will never
+        // respond.
+        let mut peer_ids = Vec::new();
+        for (peer_id, peer) in self.peers.read().await.iter() {
+            if peer.supports_message(BLOB_REQUEST).await {
+                peer_ids.push(*peer_id);
+            }
+        }
+        let (receiver, is_new) = self.blob_fetches.write().await.wait(hash, &peer_ids);
+        if is_new {
+            if peer_ids.is_empty() {
+                self.blob_fetches.write().await.finish(hash, None);
+            }
+            for peer_id in peer_ids {
+                let (_, req_id) = self.new_req_id().await?;
+                let request = Message::blob_request(NO_CIRCUIT, req_id, 0, *hash, 0, 0);
+                self.send(peer_id, &request).await?;
+            }
+        }
+
+        match receiver.recv().await {
+            Ok(Some(blob)) => Ok(blob),
+            _ => CableErrorKind::NoneError {
+                context: format!("blob {} not found", hex::encode(hash)),
+            }
+            .raise(),
+        }
+    }
+
+    /// Publish a post and return the hash.
+    ///
+    /// Peers holding live requests for the channel of the post are sent the
+    /// post hashes by the watcher of the channel.
+    pub async fn post(&mut self, post: Post) -> Result<Hash, Error> {
+        let (hash, _post) = self.publish(post).await?;
+
+        Ok(hash)
+    }
+
+    /// Publish a post and return the hash along with the signed post, as
+    /// inserted into the local store.
+    ///
+    /// The post is signed with the key of the local peer if it is not yet
+    /// signed.
+    pub async fn publish(&mut self, mut post: Post) -> Result<(Hash, Post), Error> {
+        if self.is_read_only() {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        // Ensure the post is within the limits applied to received posts.
+        self.options.limits.check_post(&post)?;
+
+        // Sign the post if required.
+        if !post.is_signed() {
+            post.sign_with(&*self.signer().await?).await?;
+        }
+
+        // Insert the post into the local store.
+        let topic_hashes = self.topic_hashes(std::slice::from_ref(&post)).await;
+        self.store.begin_batch().await?;
+        let hash = match self.store.insert_post(&post).await {
+            Ok(hash) => hash,
+            Err(err) => {
+                let _ = self.store.rollback_batch().await;
+                return Err(err);
+            }
+        };
+        self.store.commit_batch().await?;
+        self.notify_topic_changes(topic_hashes).await;
+
+        if self.options.track_deliveries {
+            self.deliveries.write().await.track(hash);
+        }
+
+        Ok((hash, post))
+    }
+
+    /// Add the given live request of the given peer, watching the channel of
+    /// the request for new posts if it is not yet being watched.
+    ///
+    /// A peer holding the maximum number of live requests has further
+    /// requests answered with the known hashes only, without keeping them
+    /// alive.
+    async fn add_live_request(&self, peer_id: PeerId, live_request: LiveRequest) {
+        let channel = match &live_request {
+            LiveRequest::ChannelState(_req_id, channel) => channel.clone(),
+            LiveRequest::ChannelTimeRange(_req_id, channel_opts) => {
+                self.channel_names.intern(&channel_opts.channel)
+            }
+        };
+
+        let peer = self.peers.read().await.get(&peer_id).cloned();
+        match peer {
+            Some(peer) => {
+                let mut live_requests = peer.live_requests.write().await;
+                if live_requests.len() >= self.options.limits.max_live_requests {
+                    debug!("Peer {} holds the maximum number of live requests", peer_id);
+                    return;
+                }
+                live_requests.push(live_request);
+            }
+            // The peer has disconnected.
+            None => return,
         }
 
-        // Insert the post into the local store.
-        let hash = self.store.insert_post(&post).await?;
+        if !self.watched_channels.write().await.insert(channel.clone()) {
+            return;
+        }
 
-        // Send post hashes to all peers for whom we hold inbound requests.
-        if let Some(channel) = post.get_channel() {
-            self.send_post_hashes(channel).await?;
-        } else {
-            // Info and delete post types do not have a channel.
-            //
-            // Retrieve all channels of which the author of the post is a
-            // member and send post hashes for those channels. This ensures
-            // that any "live" channel state requests for these channels
-            // receive the latest `post/info` and `post/delete` hashes.
-            let public_key = post.get_public_key();
-            if let Some(channels) = self.store.get_channels().await {
-                for channel in channels {
-                    if self.store.is_channel_member(&channel, &public_key).await {
-                        self.send_post_hashes(&channel).await?;
+        // Subscribe before spawning the task, so that no post inserted in
+        // the meantime is missed.
+        let mut events = self.store.watch(&channel.to_string()).await;
+        let mut this = self.clone();
+        task::spawn(async move {
+            while let Some(event) = events.next().await {
+                // Stop watching once no live requests for the channel remain.
+                // The check is made while holding the lock on the watched
+                // channels, so that a concurrently added live request either
+                // is seen here or starts a new watcher.
+                {
+                    let mut watched_channels = this.watched_channels.write().await;
+                    if !this.has_live_requests(&channel).await {
+                        watched_channels.remove(&channel);
+                        drop(channel);
+                        this.channel_names.prune();
+                        break;
+                    }
+                }
+
+                if let StoreEvent::PostInserted { .. } = event {
+                    if let Err(err) = this.send_post_hashes(&channel).await {
+                        debug!("Failed to send post hashes: {}", err);
                     }
                 }
             }
+        });
+    }
+
+    /// Query whether any peer holds a live request for the given channel.
+    async fn has_live_requests(&self, channel: &str) -> bool {
+        for (_peer_id, peer) in self.connected_peers().await {
+            let has_live_request =
+                peer.live_requests
+                    .read()
+                    .await
+                    .iter()
+                    .any(|live_request| match live_request {
+                        LiveRequest::ChannelState(_req_id, req_channel) => {
+                            &**req_channel == channel
+                        }
+                        LiveRequest::ChannelTimeRange(_req_id, channel_opts) => {
+                            channel_opts.channel == channel
+                        }
+                    });
+            if has_live_request {
+                return true;
+            }
         }
 
-        Ok(hash)
+        false
+    }
+
+    /// Return the state of each connected peer.
+    ///
+    /// The map of peers is only locked while the list is made, so that the
+    /// state of each peer may be locked in turn without blocking peers from
+    /// connecting or disconnecting.
+    async fn connected_peers(&self) -> Vec<(PeerId, Arc<PeerState>)> {
+        self.peers
+            .read()
+            .await
+            .iter()
+            .map(|(peer_id, peer)| (*peer_id, peer.clone()))
+            .collect()
     }
 
     /// Send post hashes matching peer request parameters for all live
@@ -624,15 +2829,12 @@ where
     ///
     /// Hashes of `post/delete` and `post/info` posts are sent as part of
     /// ChannelState responses.
-    async fn send_post_hashes(&mut self, channel: &Channel) -> Result<(), Error> {
-        // Iterate over all live peer requests.
-        for (peer_id, live_requests) in self.live_requests.read().await.iter() {
+    async fn send_post_hashes(&mut self, channel: &str) -> Result<(), Error> {
+        // Iterate over the live requests of each peer.
+        for (peer_id, peer) in self.connected_peers().await {
+            let live_requests = peer.live_requests.read().await;
             // Iterate over peer requests.
-            for live_request in live_requests {
-                // Create an empty vector to store post hashes to be sent
-                // in response.
-                let mut hashes = Vec::new();
-
+            for live_request in live_requests.iter() {
                 match live_request {
                     LiveRequest::ChannelState(req_id, req_channel) => {
                         debug!(
@@ -643,64 +2845,18 @@ where
                         // Only send hashes if the channel of the post which invoked
                         // the call to `send_post_hashes()` matches the channel of
                         // the peer request.
-                        if req_channel == channel {
+                        if &**req_channel == channel {
                             // Return all channel state post hashes for this channel.
-                            if let Some(mut channel_membership_hashes) =
-                                self.store.get_channel_membership_hashes(channel).await
-                            {
-                                hashes.append(&mut channel_membership_hashes)
-                            }
-
-                            // Return the channel topic post hash for this channel.
-                            let channel_topic_hash =
-                                self.store.get_channel_topic_and_hash(channel).await;
-                            if let Some((_topic, hash)) = channel_topic_hash {
-                                hashes.push(hash)
-                            }
-
-                            // Retrieve public keys of all channel members.
-                            if let Some(channel_members) =
-                                self.store.get_channel_members(channel).await
-                            {
-                                for public_key in channel_members {
-                                    // Return all delete post hashes for members of
-                                    // this channel.
-                                    if let Some(peer_delete_hashes) =
-                                        self.store.get_delete_hashes(&public_key).await
-                                    {
-                                        hashes.extend(peer_delete_hashes)
-                                    }
-
-                                    // Send the most-recent name-setting info
-                                    // post hash for each peer.
-                                    if let Some((_peer_name, peer_name_hash)) =
-                                        self.store.get_peer_name_and_hash(&public_key).await
-                                    {
-                                        hashes.push(peer_name_hash)
-                                    }
-                                }
-                            }
-
-                            // Retrieve public keys of all ex-channel members.
-                            if let Some(ex_channel_members) =
-                                self.store.get_ex_channel_members(channel).await
-                            {
-                                for public_key in ex_channel_members {
-                                    // Send the most-recent name-setting info
-                                    // post hash for each peer.
-                                    if let Some((_peer_name, peer_name_hash)) =
-                                        self.store.get_peer_name_and_hash(&public_key).await
-                                    {
-                                        hashes.push(peer_name_hash)
-                                    }
-                                }
-                            }
+                            let hashes = self
+                                .store
+                                .get_channel_state_hashes(&channel.to_string())
+                                .await;
 
                             // Construct a new hash response message.
                             let response = Message::hash_response(NO_CIRCUIT, *req_id, hashes);
 
                             // Send the response to the peer.
-                            self.send(*peer_id, &response).await?;
+                            self.send(peer_id, &response).await?;
                         }
                     }
                     LiveRequest::ChannelTimeRange(req_id, channel_opts) => {
@@ -712,32 +2868,18 @@ where
                         // Only send hashes if the channel of the post which invoked
                         // the call to `send_post_hashes()` matches the channel of
                         // the peer request.
-                        if &channel_opts.channel == channel {
-                            let limit = channel_opts.limit.min(4096);
-
-                            // Get all post hashes matching the request parameters.
-                            let mut stream = self.store.get_post_hashes(channel_opts).await;
-                            while let Some(result) = stream.next().await {
-                                hashes.push(result?);
-                                // Break once the request limit has been reached.
-                                if limit != 0 && hashes.len() as u64 >= limit {
-                                    break;
-                                }
-                            }
-                            // Drop the mutable borrow of `self` to allow the later
-                            // call to `self.send()` (immutable borrow).
-                            drop(stream);
-
-                            // Construct a new hash response message.
-                            let response =
-                                Message::hash_response(NO_CIRCUIT, *req_id, hashes.clone());
-
-                            // Only send a response if there are post hashes matching
-                            // the given request parameters.
-                            if !hashes.is_empty() {
-                                // Send the response to the peer.
-                                self.send(*peer_id, &response).await?;
-                            }
+                        if channel_opts.channel == channel {
+                            // Stream all post hashes matching the request
+                            // parameters to the peer.
+                            let hashes = self.store.get_post_hashes(channel_opts).await;
+                            self.send_hash_stream(
+                                peer_id,
+                                NO_CIRCUIT,
+                                *req_id,
+                                hashes,
+                                channel_opts.limit,
+                            )
+                            .await?;
                         }
                     }
                 }
@@ -747,32 +2889,353 @@ where
         Ok(())
     }
 
+    /// Send the hashes yielded by the given stream to the given peer in hash
+    /// responses of at most `HASH_RESPONSE_BATCH` hashes each (or the maximum
+    /// number of hashes of a message, if lower), stopping once
+    /// the given limit has been reached (0 for no limit). Returns the number
+    /// of hashes sent.
+    ///
+    /// No response is sent if the stream yields no hashes, so a request which
+    /// is not kept alive must then be concluded by the caller.
+    async fn send_hash_stream(
+        &self,
+        peer_id: PeerId,
+        circuit_id: CircuitId,
+        req_id: ReqId,
+        mut hashes: HashStream<'_>,
+        limit: u64,
+    ) -> Result<u64, Error> {
+        let batch_size = HASH_RESPONSE_BATCH.min(self.options.limits.max_hashes);
+        let mut sent = 0;
+        let mut batch = Vec::new();
+        while let Some(hash) = hashes.next().await {
+            batch.push(hash?);
+
+            let limit_reached = limit != 0 && sent + batch.len() as u64 >= limit;
+            if batch.len() >= batch_size || limit_reached {
+                sent += batch.len() as u64;
+                let response = Message::hash_response(circuit_id, req_id, mem::take(&mut batch));
+                self.send(peer_id, &response).await?;
+            }
+            if limit_reached {
+                break;
+            }
+        }
+
+        if !batch.is_empty() {
+            sent += batch.len() as u64;
+            let response = Message::hash_response(circuit_id, req_id, batch);
+            self.send(peer_id, &response).await?;
+        }
+
+        Ok(sent)
+    }
+
     /// Broadcast a message to all peers.
+    ///
+    /// The message is encoded once, however many peers are connected.
     pub async fn broadcast(&self, message: &Message) -> Result<(), Error> {
-        for ch in self.peers.read().await.values() {
-            ch.send(message.clone()).await?;
+        let peers = self.peers.read().await;
+        if peers.is_empty() {
+            return Ok(());
+        }
+
+        let frame = Frame::encode(message)?;
+        for (peer_id, peer) in peers.iter() {
+            self.send_frame(*peer_id, peer, frame.clone()).await?;
         }
         Ok(())
     }
 
     /// Send a message to a single peer identified by the given peer ID.
     pub async fn send(&self, peer_id: usize, msg: &Message) -> Result<(), Error> {
-        if let Some(ch) = self.peers.read().await.get(&peer_id) {
-            ch.send(msg.clone()).await?;
+        if let Some(peer) = self.peers.read().await.get(&peer_id) {
+            self.send_frame(peer_id, peer, Frame::encode(msg)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Announce the capabilities of the local peer to the given peer, unless
+    /// they have been announced to it before.
+    async fn announce_capabilities(&self, peer_id: PeerId) -> Result<(), Error> {
+        if let Some(peer) = self.peers.read().await.get(&peer_id) {
+            if !peer.announced.swap(true, Ordering::Relaxed) {
+                let (_req_id, req_id_bytes) = self.new_req_id().await?;
+                let msg = Capabilities::local().to_request(req_id_bytes);
+                self.send_frame(peer_id, peer, Frame::encode(&msg)?).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue the given frame to be written to the given peer, applying the
+    /// slow peer policy if the queue is full.
+    async fn send_frame(
+        &self,
+        peer_id: PeerId,
+        peer: &PeerState,
+        frame: Frame,
+    ) -> Result<(), Error> {
+        // Messages of types which the peer has announced it does not
+        // understand are not sent to it.
+        let msg_type = frame.msg.message_type();
+        if !peer.supports_message(msg_type).await {
+            debug!(
+                "Not sending message of unsupported type {} to peer {}",
+                msg_type, peer_id
+            );
+            return Ok(());
+        }
+
+        #[cfg(feature = "chaos")]
+        if self
+            .chaos
+            .as_ref()
+            .is_some_and(|chaos| chaos.drop_message())
+        {
+            debug!("Dropping message to peer {}: {}", peer_id, frame.msg);
+            return Ok(());
+        }
+
+        if let MessageBody::Request { .. } = frame.msg.body {
+            self.issued_request(frame.msg.header.req_id).await?;
+        }
+
+        // The frame is accounted for before it is queued, as the writer task
+        // may take it from the queue at once.
+        let len = frame.bytes.len();
+        peer.memory.queue(len);
+
+        let queued = match self.options.slow_peer_policy {
+            SlowPeerPolicy::Wait => peer.frames.send(frame).await.map_err(Error::from),
+            SlowPeerPolicy::Drop { max_dropped } => match peer.frames.try_send(frame) {
+                Ok(()) => {
+                    peer.dropped_frames.store(0, Ordering::Relaxed);
+                    Ok(())
+                }
+                Err(TrySendError::Full(frame)) => {
+                    peer.memory.dequeue(len);
+                    let dropped = peer.dropped_frames.fetch_add(1, Ordering::Relaxed) + 1;
+                    warn!("Dropped message to slow peer {}: {}", peer_id, frame.msg);
+
+                    if dropped > max_dropped {
+                        debug!("Disconnecting slow peer {}", peer_id);
+                        peer.disconnect.close();
+                    }
+                    return Ok(());
+                }
+                Err(err) => Err(err.into()),
+            },
+        };
+        if queued.is_err() {
+            peer.memory.dequeue(len);
+            return queued;
+        }
+
+        // Disconnect the peer if its queued frames alone exceed its memory
+        // budget.
+        if let Some(budget) = self.options.peer_memory_budget {
+            if peer.memory.queued() > budget {
+                warn!("Disconnecting peer {} exceeding its memory budget", peer_id);
+                peer.disconnect.close();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record that the request with the given ID has been written to a peer.
+    async fn issued_request(&self, req_id: ReqId) -> Result<(), Error> {
+        let now = self.options.clock.now()?;
+        self.issued_requests
+            .shard(&req_id)
+            .write()
+            .await
+            .insert(req_id, now);
+
+        Ok(())
+    }
+
+    /// Query whether the request with the given ID is an outbound request or
+    /// has recently been written to a peer, such that a response to it is
+    /// expected.
+    async fn is_known_request(&self, req_id: &ReqId, now: Timestamp) -> bool {
+        self.outbound_requests
+            .shard(req_id)
+            .read()
+            .await
+            .contains_key(req_id)
+            || self
+                .issued_requests
+                .shard(req_id)
+                .read()
+                .await
+                .contains(req_id, now)
+    }
+
+    /// Record a violation of the protocol by the given peer, disconnecting
+    /// it once it has exceeded the violation limit.
+    async fn penalize(&self, peer_id: PeerId) {
+        if let Some(peer) = self.peers.read().await.get(&peer_id) {
+            let violations = peer.violations.fetch_add(1, Ordering::Relaxed) + 1;
+            debug!(
+                "Peer {} violated the protocol ({} violations)",
+                peer_id, violations
+            );
+
+            if violations > self.options.violation_limit {
+                warn!("Disconnecting peer {} for violating the protocol", peer_id);
+                peer.disconnect.close();
+            }
+        }
+    }
+
+    /// Enforce the memory budget of the given peer.
+    ///
+    /// While the budget is exceeded, the oldest forwarded requests of the
+    /// peer are cancelled and then the oldest posts requested from it are
+    /// forgotten. If the budget is still exceeded, the peer is disconnected.
+    async fn enforce_memory_budget(&self, peer_id: PeerId) -> Result<(), Error> {
+        let budget = match self.options.peer_memory_budget {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+        let peer = match self.peers.read().await.get(&peer_id) {
+            Some(peer) => peer.clone(),
+            None => return Ok(()),
+        };
+
+        let mut requested = self.requested_posts.read().await.requested_from(peer_id);
+        let size = |requested: usize| peer.memory.size() + requested * REQUESTED_POST_SIZE;
+        if size(requested) <= budget {
+            return Ok(());
+        }
+
+        // Forget the forwarded requests which have since concluded.
+        let mut concluded = HashSet::new();
+        for req_id in peer.memory.forwarded_req_ids() {
+            let is_active = matches!(
+                self.outbound_requests.shard(&req_id).read().await.get(&req_id),
+                Some((RequestOrigin::Remote(origin), _)) if *origin == peer_id
+            );
+            if !is_active {
+                concluded.insert(req_id);
+            }
+        }
+        peer.memory
+            .retain_forwarded(|req_id| !concluded.contains(req_id));
+
+        // Cancel the oldest forwarded requests.
+        let mut shed = Vec::new();
+        while size(requested) > budget {
+            match peer.memory.pop_forwarded() {
+                Some(req_id) => {
+                    self.outbound_requests
+                        .shard(&req_id)
+                        .write()
+                        .await
+                        .remove(&req_id);
+                    shed.push(req_id);
+                }
+                None => break,
+            }
+        }
+        if !shed.is_empty() {
+            debug!(
+                "Cancelling {} forwarded requests of peer {} exceeding its memory budget",
+                shed.len(),
+                peer_id
+            );
+            self.cancel_forwarded_requests(&shed).await?;
+        }
+
+        // Forget the oldest posts requested from the peer.
+        let excess = size(requested).saturating_sub(budget);
+        if excess > 0 {
+            let count = excess.div_ceil(REQUESTED_POST_SIZE);
+            requested -= self.requested_posts.write().await.shed(peer_id, count);
+        }
+
+        if size(requested) > budget {
+            warn!("Disconnecting peer {} exceeding its memory budget", peer_id);
+            peer.disconnect.close();
         }
+
         Ok(())
     }
 
-    /// Decrement the TTL of a request message and write it to the outbound
-    /// requests store.
-    async fn decrement_ttl_and_write_to_outbound(&self, req_id: ReqId, msg: &Message) {
+    /// Forward a request received from the given peer to every other
+    /// connected peer, decrementing its TTL.
+    ///
+    /// The request is also written to the outbound requests store, so that it
+    /// is sent to peers which connect while it remains active. The peers to
+    /// which the request is sent are recorded, allowing a later cancel request
+    /// to be forwarded to them.
+    async fn forward_request(
+        &self,
+        peer_id: PeerId,
+        req_id: ReqId,
+        msg: &Message,
+    ) -> Result<(), Error> {
         let mut request = msg.clone();
         request.decrement_ttl();
 
         self.outbound_requests
+            .shard(&req_id)
+            .write()
+            .await
+            .insert(req_id, (RequestOrigin::Remote(peer_id), request.clone()));
+
+        let frame = Frame::encode(&request)?;
+        let mut recipients = Vec::new();
+        for (id, peer) in self.peers.read().await.iter() {
+            if *id == peer_id {
+                peer.memory.push_forwarded(req_id, frame.bytes.len());
+            } else {
+                self.send_frame(*id, peer, frame.clone()).await?;
+                recipients.push(*id);
+            }
+        }
+
+        if !recipients.is_empty() {
+            self.forwarded_requests
+                .write()
+                .await
+                .entry(req_id)
+                .or_default()
+                .extend(recipients);
+        }
+
+        self.enforce_memory_budget(peer_id).await
+    }
+
+    /// Record that the given peer has concluded the given request forwarded
+    /// on behalf of the given origin. Once every peer to which it was
+    /// forwarded has concluded it, the request is removed from the outbound
+    /// requests and the state held to relay its responses is discarded.
+    async fn conclude_forwarded_request(&self, origin: PeerId, peer_id: PeerId, req_id: ReqId) {
+        let mut forwarded_requests = self.forwarded_requests.write().await;
+        if let Some(forwarded_to) = forwarded_requests.get_mut(&req_id) {
+            forwarded_to.remove(&peer_id);
+            if !forwarded_to.is_empty() {
+                return;
+            }
+            forwarded_requests.remove(&req_id);
+        }
+        drop(forwarded_requests);
+
+        self.outbound_requests
+            .shard(&req_id)
             .write()
             .await
-            .insert(req_id, (RequestOrigin::Remote, request));
+            .remove(&req_id);
+        self.relay_filter
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .forget(&req_id);
+        if let Some(peer) = self.peers.read().await.get(&origin) {
+            peer.memory.retain_forwarded(|id| *id != req_id);
+        }
     }
 
     /// Handle a request or response message.
@@ -782,12 +3245,34 @@ where
             circuit_id,
             req_id,
         } = msg.header;
+        let now = self.options.clock.now()?;
 
         // Ignore this message if the request ID has previously been handled
         // and it is not an active live request or outbound request.
-        if self.handled_requests.read().await.contains(&req_id)
+        //
+        // A post request may be answered by several post responses, so post
+        // responses are always handled; only the posts which have been
+        // requested are stored.
+        let is_post_response = matches!(
+            msg.body,
+            MessageBody::Response {
+                body: ResponseBody::Post { .. }
+            }
+        );
+        if !is_post_response
+            && self
+                .handled_requests
+                .shard(&req_id)
+                .read()
+                .await
+                .contains(&req_id, now)
             && !self.is_live_request(&peer_id, &req_id).await
-            && !self.outbound_requests.read().await.contains_key(&req_id)
+            && !self
+                .outbound_requests
+                .shard(&req_id)
+                .read()
+                .await
+                .contains_key(&req_id)
         {
             debug!(
                 "Dropping message from handler; request ID has been seen before: {}",
@@ -797,39 +3282,110 @@ where
             return Ok(());
         }
 
-        // TODO: Forward requests.
+        // Ignore a request which has been handled before or which was made by
+        // the local peer. Such a request has reached the local peer by more
+        // than one path and must be neither answered nor forwarded again.
+        if let MessageBody::Request { .. } = msg.body {
+            if self
+                .handled_requests
+                .shard(&req_id)
+                .read()
+                .await
+                .contains(&req_id, now)
+                || matches!(
+                    self.outbound_requests
+                        .shard(&req_id)
+                        .read()
+                        .await
+                        .get(&req_id),
+                    Some((RequestOrigin::Local, _))
+                )
+            {
+                debug!(
+                    "Dropping request which has been seen before: {}",
+                    msg.header
+                );
+
+                return Ok(());
+            }
+        }
+
+        // Reject a message making implausible claims, penalizing the peer.
+        let checked = {
+            let outbound_requests = self.outbound_requests.shard(&req_id).read().await;
+            claims::check_claims(
+                msg,
+                outbound_requests.get(&req_id).map(|(_, request)| request),
+                &self.options.limits,
+            )
+        };
+        if let Err(err) = checked {
+            self.penalize(peer_id).await;
+            return Err(err);
+        }
+
+        // Drop a response to a request which the local peer has neither made
+        // nor forwarded, penalizing the peer.
+        if let MessageBody::Response { body } = &msg.body {
+            let is_accepted = self.options.accept_unknown_responses
+                || (self.options.accept_unsolicited_posts
+                    && matches!(body, ResponseBody::Post { .. }));
+            if !is_accepted && !self.is_known_request(&req_id, now).await {
+                debug!("Dropping response to an unknown request: {}", msg.header);
+                self.penalize(peer_id).await;
+
+                return Ok(());
+            }
+        }
+
         match &msg.body {
             MessageBody::Request { ttl, body } => match body {
                 RequestBody::Post { hashes } => {
                     debug!("Handling post request...");
 
-                    // If the request TTL is > 0, decrement it and add the
-                    // message to `outbound_requests` so that it will be
-                    // forwarded to other connected peers.
-                    //
-                    // TODO: Set the TTL to 16 if it is > 16.
+                    // If the request TTL is > 0, decrement it and forward
+                    // the request to other connected peers.
                     if *ttl > 0 {
-                        self.decrement_ttl_and_write_to_outbound(req_id, msg).await;
+                        self.forward_request(peer_id, req_id, msg).await?;
                     }
 
                     let posts = self.store.get_post_payloads(hashes).await;
                     let response = Message::post_response(circuit_id, req_id, posts);
 
-                    self.send(peer_id, &response).await?
+                    self.send(peer_id, &response).await?;
+
+                    // Report the first request of each tracked post.
+                    if self.options.track_deliveries {
+                        self.deliver_posts(peer_id, hashes).await;
+                    }
                 }
                 RequestBody::Cancel { cancel_id } => {
                     debug!("Handling cancel request...");
 
-                    // TTL is ignored for cancel requests so we decrement and
-                    // write the message without first checking the value.
-                    self.decrement_ttl_and_write_to_outbound(req_id, msg).await;
-
                     // Remove the request from the map of live requests.
                     self.remove_live_request(&peer_id, cancel_id).await?;
 
                     // Remove the request from the list of outbound requests.
                     // The associated message will no longer be sent to peers.
-                    self.outbound_requests.write().await.remove(cancel_id);
+                    self.outbound_requests
+                        .shard(cancel_id)
+                        .write()
+                        .await
+                        .remove(cancel_id);
+
+                    // Forward the cancel request to the peers to which the
+                    // cancelled request was forwarded. The TTL is ignored for
+                    // cancel requests.
+                    let forwarded_to = self.forwarded_requests.write().await.remove(cancel_id);
+                    if let Some(peers) = forwarded_to {
+                        let mut request = msg.clone();
+                        request.decrement_ttl();
+                        for forwarded_peer_id in peers {
+                            if forwarded_peer_id != peer_id {
+                                self.send(forwarded_peer_id, &request).await?;
+                            }
+                        }
+                    }
                 }
                 RequestBody::ChannelTimeRange {
                     channel,
@@ -840,140 +3396,79 @@ where
                     debug!("Handling channel time range request...");
 
                     if *ttl > 0 {
-                        self.decrement_ttl_and_write_to_outbound(req_id, msg).await;
+                        self.forward_request(peer_id, req_id, msg).await?;
                     }
 
                     let channel_opts = ChannelOptions::new(channel, *time_start, *time_end, *limit);
 
-                    let n_limit = (*limit).min(4096);
-
-                    let mut hashes = Vec::new();
-                    // Create a stream of post hashes matching the given criteria.
-                    let mut stream = self.store.get_post_hashes(&channel_opts).await;
-                    // Iterate over the hashes in the stream.
-                    while let Some(result) = stream.next().await {
-                        hashes.push(result?);
-                        // Break out of the loop once the requested limit is
-                        // met.
-                        //
-                        // A limit of 0 means there is no limit on the number
-                        // of hashes that may be returned.
-                        if n_limit != 0 && hashes.len() as u64 >= n_limit {
-                            break;
-                        }
-                    }
-                    // Drop the mutable borrow of `self` to allow the later
-                    // call to `self.send()` (immutable borrow).
-                    drop(stream);
-
-                    let response = Message::hash_response(circuit_id, req_id, hashes.clone());
-
                     // Add the peer and request ID to the request tracker if
                     // the end time has been set to 0 (i.e. keep this request
                     // alive and send new messages as they become available).
                     if *time_end == 0 {
-                        let live_request = LiveRequest::ChannelTimeRange(req_id, channel_opts);
-
-                        let mut live_requests = self.live_requests.write().await;
-                        if let Some(peer_requests) = live_requests.get_mut(&peer_id) {
-                            // TODO: Only push if `peer_requests` does not
-                            // already contain this request.
-                            peer_requests.push(live_request);
-                        } else {
-                            live_requests.insert(peer_id, vec![live_request]);
-                        }
+                        // TODO: Only add the request if the peer does not
+                        // already hold it.
+                        let live_request =
+                            LiveRequest::ChannelTimeRange(req_id, channel_opts.clone());
+                        self.add_live_request(peer_id, live_request).await;
+                    }
 
-                        // Only send a response if there are post hashes matching
-                        // the given request parameters.
-                        if !hashes.is_empty() {
-                            self.send(peer_id, &response).await?
-                        }
-                    } else {
-                        // Send a hash response, even if there are no known
-                        // hashes matching the request parameters.
-                        self.send(peer_id, &response).await?
+                    // Stream the post hashes matching the given criteria to
+                    // the peer.
+                    let hashes = self.store.get_post_hashes(&channel_opts).await;
+                    self.send_hash_stream(peer_id, circuit_id, req_id, hashes, *limit)
+                        .await?;
+
+                    // Conclude a request which is not kept alive with an empty
+                    // hash response.
+                    if *time_end != 0 {
+                        let closing_response =
+                            Message::hash_response(circuit_id, req_id, Vec::new());
+                        self.send(peer_id, &closing_response).await?
                     }
                 }
                 RequestBody::ChannelState { channel, future } => {
                     debug!("Handling channel state request...");
 
                     if *ttl > 0 {
-                        self.decrement_ttl_and_write_to_outbound(req_id, msg).await;
-                    }
-
-                    let mut hashes = Vec::new();
-
-                    // Get the hash of the latest join or leave post for all
-                    // channel members and ex-members.
-                    if let Some(mut channel_membership_hashes) =
-                        self.store.get_channel_membership_hashes(channel).await
-                    {
-                        hashes.append(&mut channel_membership_hashes)
-                    }
-
-                    // If a topic has been set for the channel, return the hash
-                    // of the post.
-                    if let Some((_topic, topic_hash)) =
-                        self.store.get_channel_topic_and_hash(channel).await
-                    {
-                        hashes.push(topic_hash)
+                        self.forward_request(peer_id, req_id, msg).await?;
                     }
 
-                    let response = Message::hash_response(circuit_id, req_id, hashes.clone());
+                    // Get the hashes of all posts comprising the current
+                    // channel state.
+                    let hashes = self.store.get_channel_state_hashes(channel).await;
+                    let hashes: HashStream = Box::new(stream::iter(hashes.into_iter().map(Ok)));
 
                     // Send only the latest known hashes; do not keep the
                     // request alive after responding.
                     if *future == 0 {
-                        if !hashes.is_empty() {
-                            // Send the known hashes.
-                            self.send(peer_id, &response).await?;
-
-                            // Compost and send an empty hash response to
-                            // terminate the request.
-                            let closing_response =
-                                Message::hash_response(circuit_id, req_id, Vec::new());
-                            self.send(peer_id, &closing_response).await?;
-                        } else {
-                            // Send the empty hash response to terminate the
-                            // request.
-                            self.send(peer_id, &response).await?;
-                        }
+                        // Send the known hashes.
+                        self.send_hash_stream(peer_id, circuit_id, req_id, hashes, 0)
+                            .await?;
+
+                        // Compose and send an empty hash response to
+                        // terminate the request.
+                        let closing_response =
+                            Message::hash_response(circuit_id, req_id, Vec::new());
+                        self.send(peer_id, &closing_response).await?;
                     } else if *future == 1 {
                         // Add the peer and request ID to the request tracker if
                         // the future field has been set to 1 (i.e. keep this request
                         // alive and send new messages as they become available).
-                        let live_request = LiveRequest::ChannelState(req_id, channel.to_string());
-
-                        let mut live_requests = self.live_requests.write().await;
-                        if let Some(peer_requests) = live_requests.get_mut(&peer_id) {
-                            peer_requests.push(live_request);
-                        } else {
-                            live_requests.insert(peer_id, vec![live_request]);
-                        }
+                        let live_request =
+                            LiveRequest::ChannelState(req_id, self.channel_names.intern(channel));
+                        self.add_live_request(peer_id, live_request).await;
 
                         // Only send a response if there are post hashes matching
                         // the given request parameters.
-                        if !hashes.is_empty() {
-                            self.send(peer_id, &response).await?
-                        }
+                        self.send_hash_stream(peer_id, circuit_id, req_id, hashes, 0)
+                            .await?;
                     }
-
-                    /*
-                    TODO: We will require channel state indexes before this
-                    handler can be completed.
-
-                    Channel state includes (spec section 5.4.4):
-
-                    The latest post/info post of all members and ex-members.
-                    The latest of all users' post/join or post/leave posts to the channel.
-                    The latest post/topic post made to the channel.
-                    */
                 }
                 RequestBody::ChannelList { skip, limit } => {
                     debug!("Handling channel list request...");
 
                     if *ttl > 0 {
-                        self.decrement_ttl_and_write_to_outbound(req_id, msg).await;
+                        self.forward_request(peer_id, req_id, msg).await?;
                     }
 
                     let skip = *skip as usize;
@@ -982,6 +3477,11 @@ where
                     // Retrieve all known channels, returning an empty vector
                     // if none are known.
                     let channels = if let Some(mut all_channels) = self.store.get_channels().await {
+                        // Channels hidden by archive posts are not listed.
+                        #[cfg(feature = "archive-posts")]
+                        {
+                            all_channels = self.without_archived_by_post(all_channels).await;
+                        }
                         let channels_len = all_channels.len();
 
                         // Define the channel query limit based on the request
@@ -991,9 +3491,16 @@ where
                         } else {
                             limit
                         };
-
-                        // Drain the channels matching the given range.
-                        all_channels.drain(skip..limit).collect()
+                        // A request skipping past the limit or the known
+                        // channels matches none of them.
+                        let skip = skip.min(limit);
+
+                        // Drain the channels matching the given range, up to
+                        // the most a peer accepts in one response.
+                        all_channels
+                            .drain(skip..limit)
+                            .take(self.options.limits.max_channels)
+                            .collect()
                     } else {
                         Vec::new()
                     };
@@ -1003,96 +3510,351 @@ where
 
                     self.send(peer_id, &response).await?
                 }
-            },
-            MessageBody::Response { body } => match body {
-                // TODO: A responder MUST send a Hash Response message with
-                // hash_count = 0 to indicate that they do not intend to return
-                // any further hashes for the given req_id and they have
-                // concluded the request on their side.
-                ResponseBody::Hash { hashes } => {
-                    debug!("Handling hash response...");
-
-                    let wanted_hashes = self.store.want(hashes).await;
-                    if !wanted_hashes.is_empty() {
-                        let (_, new_req_id) = self.new_req_id().await?;
-
-                        // If a hash appears in our list of wanted hashed,
-                        // send a request for the associated post.
-                        let request = Message::post_request(
-                            circuit_id,
-                            new_req_id,
-                            TTL,
-                            wanted_hashes.to_owned(),
-                        );
+                #[cfg(feature = "blobs")]
+                RequestBody::Blob {
+                    hash,
+                    start,
+                    max_len,
+                } => {
+                    debug!("Handling blob request...");
+
+                    // Blob requests are answered from the local store only
+                    // and are never forwarded. A size of 0 signals that the
+                    // blob is not held.
+                    let response = match self.store.get_blob(hash).await {
+                        Some(blob) => {
+                            let size = blob.len() as u64;
+                            let len = match *max_len {
+                                0 => BLOB_CHUNK_SIZE,
+                                max_len => max_len.min(BLOB_CHUNK_SIZE),
+                            };
+                            let start = (*start).min(size);
+                            let end = start.saturating_add(len).min(size);
+                            let data = blob[start as usize..end as usize].to_vec();
+
+                            Message::blob_response(circuit_id, req_id, *hash, size, start, data)
+                        }
+                        None => Message::blob_response(circuit_id, req_id, *hash, 0, 0, Vec::new()),
+                    };
 
-                        self.send(peer_id, &request).await?;
+                    self.send(peer_id, &response).await?
+                }
+                RequestBody::Capabilities { .. } => {
+                    debug!("Handling capability request...");
+
+                    // Capability requests concern a single connection and are
+                    // neither forwarded nor answered with a response. The
+                    // local peer announces its own capabilities in return,
+                    // unless it has already done so.
+                    if let Some(peer) = self.peers.read().await.get(&peer_id) {
+                        *peer.capabilities.write().await = Capabilities::from_message(msg);
+                    }
+                    self.announce_capabilities(peer_id).await?;
+                }
+            },
+            MessageBody::Response { body } => {
+                // Relay the response to the peer from which the request was
+                // received, if the request was forwarded by the local peer.
+                let origin = match self
+                    .outbound_requests
+                    .shard(&req_id)
+                    .read()
+                    .await
+                    .get(&req_id)
+                {
+                    Some((RequestOrigin::Remote(origin), _)) if *origin != peer_id => Some(*origin),
+                    _ => None,
+                };
+                if let Some(origin) = origin {
+                    // Relay only the hashes, posts and channels which have not
+                    // been relayed before, as the request may have reached
+                    // several peers by different paths.
+                    let forwarded_to = self
+                        .forwarded_requests
+                        .read()
+                        .await
+                        .get(&req_id)
+                        .cloned()
+                        .unwrap_or_default();
+                    let relayed = self
+                        .relay_filter
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .filter(peer_id, &forwarded_to, msg);
+                    if let Some(relayed) = relayed {
+                        debug!("Relaying response to peer {}: {}", origin, msg.header);
+                        self.send(origin, &relayed).await?;
+                    }
+                }
 
-                        // Update the list of requested posts.
-                        let mut requested_posts = self.requested_posts.write().await;
-                        for hash in &wanted_hashes {
-                            requested_posts.insert(*hash);
+                // A response may conclude a request on the side of the peer.
+                // A local request which is not kept alive need not be
+                // reissued after a restart once it has been concluded by an
+                // empty response. A forwarded request which is not kept alive
+                // is forgotten once every peer to which it was forwarded has
+                // concluded it, so that it is no longer sent to peers which
+                // connect.
+                let is_empty = match body {
+                    ResponseBody::Hash { hashes } => hashes.is_empty(),
+                    ResponseBody::Post { .. } => false,
+                    ResponseBody::ChannelList { channels } => channels.is_empty(),
+                    #[cfg(feature = "blobs")]
+                    ResponseBody::Blob { .. } => false,
+                };
+                let concluded = match self
+                    .outbound_requests
+                    .shard(&req_id)
+                    .read()
+                    .await
+                    .get(&req_id)
+                {
+                    Some((RequestOrigin::Local, request)) => is_empty && !keeps_alive(request),
+                    Some((RequestOrigin::Remote(origin), request)) => {
+                        *origin != peer_id && concludes_forwarded(request, body)
+                    }
+                    None => false,
+                };
+                if concluded {
+                    match origin {
+                        Some(origin) => {
+                            self.conclude_forwarded_request(origin, peer_id, req_id)
+                                .await
                         }
+                        None if !self.is_read_only() => {
+                            self.store.remove_outbound_request(&req_id).await
+                        }
+                        None => (),
                     }
-
-                    // TODO: If hash_count == 0, remove the request.
-                    // This may be more relevant when responding to a channel
-                    // time range request (ie. sending a hash response).
                 }
-                ResponseBody::Post { posts } => {
-                    debug!("Handling post response...");
-
-                    // Iterate over the encoded posts.
-                    for post_bytes in posts {
-                        // Verify the post signature.
-                        if !Post::verify(post_bytes) {
-                            // Skip to the next post, bypassing the rest of the
-                            // code in this `for` loop.
-                            continue;
-                        }
 
-                        // Deserialize the post.
-                        let (s, post) = Post::from_bytes(post_bytes)?;
+                match body {
+                    ResponseBody::Hash { hashes } => {
+                        debug!("Handling hash response...");
+
+                        // Record the hashes returned for a local channel
+                        // request to report the progress of the sync, and the
+                        // context in which the hashes were advertised.
+                        let mut context = None;
+                        if let Some((origin, request)) = self
+                            .outbound_requests
+                            .shard(&req_id)
+                            .read()
+                            .await
+                            .get(&req_id)
+                        {
+                            context = PostContext::from_request(request).map(Arc::new);
+                            let channel = match origin {
+                                RequestOrigin::Local => request_channel(request),
+                                _ => None,
+                            };
+                            if let Some(channel) = channel {
+                                let mut channel_hashes = self.channel_hashes.write().await;
+                                match channel_hashes.get_mut(channel.as_str()) {
+                                    Some(channel_hashes) => channel_hashes.extend(hashes),
+                                    None => {
+                                        let channel = self.channel_names.intern(channel);
+                                        channel_hashes
+                                            .insert(channel, hashes.iter().copied().collect());
+                                    }
+                                }
+                            }
+                        }
 
-                        // Ensure the number of processed bytes matches the
-                        // received amount.
-                        if s != post_bytes.len() {
-                            continue;
+                        let wanted_hashes = self.store.want(hashes).await;
+
+                        // Request the wanted posts which have not already
+                        // been requested from another peer, recording the
+                        // peer as a source of all of them.
+                        let to_request = self.requested_posts.write().await.advertised(
+                            peer_id,
+                            &wanted_hashes,
+                            context.as_ref(),
+                            self.options.clock.now()?,
+                            self.options.post_request_timeout,
+                        );
+                        self.enforce_memory_budget(peer_id).await?;
+                        let to_request: Vec<Hash> = {
+                            let requested_posts = self.requested_posts.read().await;
+                            to_request
+                                .into_iter()
+                                .filter(|hash| requested_posts.contains(hash))
+                                .collect()
+                        };
+                        if !to_request.is_empty() {
+                            let (_, new_req_id) = self.new_req_id().await?;
+
+                            // If a hash appears in our list of wanted hashed,
+                            // send a request for the associated post.
+                            let request = Message::post_request(
+                                circuit_id,
+                                new_req_id,
+                                self.request_ttl(),
+                                to_request,
+                            );
+
+                            self.send(peer_id, &request).await?;
                         }
+                    }
+                    ResponseBody::Post { posts } => {
+                        debug!("Handling post response...");
+
+                        // Verify and deserialize the encoded posts, skipping
+                        // those with an invalid signature.
+                        let verified = verify::verify_posts(
+                            posts,
+                            self.options.verification_workers,
+                            self.options.limits,
+                        )
+                        .await?;
+
+                        // The posts to be stored, inserted in a single batch.
+                        let mut accepted = Vec::new();
+                        let mut accepted_hashes = HashSet::new();
+                        // Whether a post fell outside of its requested context.
+                        let mut misplaced = false;
+
+                        for post in verified.into_iter().flatten() {
+                            let post_hash = post.hash()?;
+                            if accepted_hashes.contains(&post_hash) {
+                                continue;
+                            }
+
+                            let deleted_posts = self.deleted_posts.read().await;
+                            // Check if a delete post has previously been
+                            // encountered which references this post hash.
+                            if deleted_posts.contains(&post_hash) {
+                                // Skip processing this post so that we do not add
+                                // it to the local store.
+                                continue;
+                            }
+
+                            let mut requested_posts = self.requested_posts.write().await;
+                            // Reject a requested post which falls outside of
+                            // the channel or time range for which its hash was
+                            // advertised.
+                            if !requested_posts.matches(&post_hash, &post) {
+                                debug!(
+                                    "Rejecting post outside of the requested context: {}",
+                                    hex::encode(post_hash)
+                                );
+                                requested_posts.remove(&post_hash);
+                                misplaced = true;
+                                continue;
+                            }
+                            // Check if this post was previously requested,
+                            // removing it from the list of requested posts.
+                            if !requested_posts.remove(&post_hash) {
+                                // Skip this post if it was not requested, unless
+                                // unsolicited posts are accepted and the post is
+                                // not already stored.
+                                if !self.options.accept_unsolicited_posts
+                                    || self.store.want(&[post_hash]).await.is_empty()
+                                {
+                                    continue;
+                                }
+                            }
+                            drop(requested_posts);
+
+                            // Skip posts timestamped implausibly far in the
+                            // future, which would otherwise be sorted after
+                            // every other post of their channel.
+                            let now = self.options.clock.now()?;
+                            if let Some(skew) = self.options.max_clock_skew {
+                                let latest = now.saturating_add(skew.as_millis() as Timestamp);
+                                if post.get_timestamp() > latest {
+                                    debug!(
+                                        "Skipping post timestamped in the future: {}",
+                                        hex::encode(post_hash)
+                                    );
+                                    continue;
+                                }
+                            }
 
-                        let post_hash = post.hash()?;
+                            // Skip text posts which the retention policy would
+                            // immediately prune.
+                            let cutoff = match &self.options.retention {
+                                Some(policy) => policy.cutoff(now),
+                                None => None,
+                            };
+                            if matches!(post.body, PostBody::Text { .. })
+                                && cutoff.is_some_and(|cutoff| post.get_timestamp() < cutoff)
+                            {
+                                continue;
+                            }
 
-                        let deleted_posts = self.deleted_posts.read().await;
-                        // Check if a delete post has previously been
-                        // encountered which references this post hash.
-                        if deleted_posts.contains(&post_hash) {
-                            // Skip processing this post so that we do not add
-                            // it to the local store.
-                            continue;
+                            accepted_hashes.insert(post_hash);
+                            accepted.push(post);
                         }
 
-                        let mut requested_posts = self.requested_posts.write().await;
-                        // Check if this post was previously requested.
-                        if !requested_posts.contains(&post_hash) {
-                            // Skip this post if it was not requested.
-                            continue;
+                        if self.is_read_only() {
+                            debug!("Discarding received posts; the manager is read-only");
+                        } else {
+                            let topic_hashes = self.topic_hashes(&accepted).await;
+                            let hashes = self.store.insert_posts(&accepted).await?;
+                            for (post, post_hash) in accepted.iter().zip(&hashes) {
+                                self.index_mentions(post, post_hash).await?;
+                            }
+                            self.notify_topic_changes(topic_hashes).await;
                         }
-                        // Remove the post hash from the list of requested
-                        // posts.
-                        requested_posts.remove(&post_hash);
 
-                        self.store.insert_post(&post).await?;
+                        // Penalize the peer once for the misplaced posts of
+                        // the response.
+                        if misplaced {
+                            self.penalize(peer_id).await;
+                        }
                     }
-                }
-                ResponseBody::ChannelList { channels } => {
-                    debug!("Handling channel list response...");
+                    ResponseBody::ChannelList { channels } => {
+                        debug!("Handling channel list response...");
 
-                    // TODO: Do we need to take action to conclude the request
-                    // which resulted in this response?
-                    for channel in channels {
-                        self.store.insert_channel(channel).await;
+                        if !self.is_read_only() {
+                            for channel in channels {
+                                self.store.insert_channel(channel).await;
+                            }
+                        }
+                    }
+                    #[cfg(feature = "blobs")]
+                    ResponseBody::Blob {
+                        hash,
+                        size,
+                        start,
+                        data,
+                    } => {
+                        debug!("Handling blob response...");
+
+                        let progress = self.blob_fetches.write().await.receive(
+                            peer_id,
+                            hash,
+                            *size,
+                            *start,
+                            data,
+                            self.options.blob_quota,
+                        );
+                        match progress {
+                            BlobProgress::Ignored => (),
+                            BlobProgress::Next(start) => {
+                                let (_, req_id) = self.new_req_id().await?;
+                                let request =
+                                    Message::blob_request(NO_CIRCUIT, req_id, 0, *hash, start, 0);
+                                self.send(peer_id, &request).await?;
+                            }
+                            BlobProgress::Complete(blob) => {
+                                // A fetched blob is returned even if it cannot
+                                // be stored.
+                                if let Some(blob) = &blob {
+                                    if !self.is_read_only() {
+                                        let quota = self.options.blob_quota;
+                                        let stored = self.store.store_blob(blob.clone(), quota);
+                                        if let Err(err) = stored.await {
+                                            warn!("Failed to store fetched blob: {}", err);
+                                        }
+                                    }
+                                }
+                                self.blob_fetches.write().await.finish(hash, blob);
+                            }
+                        }
                     }
                 }
-            },
+            }
             // Ignore unrecognized message type.
             MessageBody::Unrecognized { .. } => {
                 debug!("Received unrecognized message; skipping message handling...");
@@ -1100,7 +3862,11 @@ where
         }
 
         // Mark this request as "handled" (to prevent request loops).
-        self.handled_requests.write().await.insert(req_id);
+        self.handled_requests
+            .shard(&req_id)
+            .write()
+            .await
+            .insert(req_id, now);
 
         Ok(())
     }
@@ -0,0 +1,67 @@
+//! Detection of mentions of the local user in the text of posts.
+//!
+//! A post mentions the local user if its text contains one of the user's
+//! names prefixed by `@` (compared case-insensitively and followed by a
+//! character which may not be part of a name), or the hex-encoded public key
+//! of one of the user's identities.
+
+use cable::Nickname;
+
+use crate::store::PublicKey;
+
+/// Query whether the given character may be part of a mentioned name.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Query whether the given lowercase text mentions the given name with an
+/// `@` prefix.
+fn mentions_name(text: &str, name: &str) -> bool {
+    if name.is_empty() {
+        return false;
+    }
+
+    let mention = format!("@{}", name.to_lowercase());
+
+    text.match_indices(&mention).any(|(start, _)| {
+        let preceded = text[..start].chars().next_back().is_some_and(is_name_char);
+        let followed = text[start + mention.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_name_char);
+
+        !preceded && !followed
+    })
+}
+
+/// Query whether the given text mentions any of the given names or public
+/// keys.
+pub(crate) fn is_mention(text: &str, names: &[Nickname], public_keys: &[PublicKey]) -> bool {
+    let text = text.to_lowercase();
+
+    names.iter().any(|name| mentions_name(&text, name))
+        || public_keys
+            .iter()
+            .any(|public_key| text.contains(&hex::encode(public_key)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detect_mentions() {
+        let names = vec!["Glyph".to_string()];
+        let public_key = [7; 32];
+
+        assert!(is_mention("hi @glyph!", &names, &[]));
+        assert!(is_mention("@GLYPH, look at this moth", &names, &[]));
+        assert!(!is_mention("hi glyph", &names, &[]));
+        assert!(!is_mention("hi @glyphs", &names, &[]));
+        assert!(!is_mention("mail me at me@glyph", &names, &[]));
+
+        let text = format!("ping {}", hex::encode(public_key).to_uppercase());
+        assert!(is_mention(&text, &[], &[public_key]));
+        assert!(!is_mention(&text, &[], &[[8; 32]]));
+    }
+}
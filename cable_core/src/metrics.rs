@@ -0,0 +1,72 @@
+//! Metrics describing the size and contents of a store, and the space which
+//! compaction may reclaim.
+
+use std::{collections::BTreeMap, fmt};
+
+use cable::Channel;
+
+/// A snapshot of the size and contents of a store, as returned by
+/// `Store::metrics()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoreMetrics {
+    /// The total number of posts held by the store, including posts without
+    /// a channel and channel state posts.
+    pub total_posts: usize,
+    /// The number of channel posts (`post/text` and `post/topic`) held for
+    /// each channel.
+    pub posts_per_channel: BTreeMap<Channel, usize>,
+    /// The number of tombstones of deleted posts, if known.
+    pub tombstones: Option<usize>,
+    /// The number of entries in each index of the store, keyed by index name.
+    pub index_sizes: BTreeMap<String, usize>,
+    /// The disk space used by the store in bytes, for persistent stores.
+    pub disk_usage: Option<u64>,
+}
+
+/// A snapshot of the storage space which `Store::compact()` may reclaim, as
+/// returned by `Store::compaction_stats()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// The number of tombstoned posts whose payloads remain stored, such as
+    /// those of a deletion which was interrupted. Compaction purges them.
+    pub pending_tombstones: usize,
+    /// The number of bytes which compaction would return to the file
+    /// system, for persistent stores whose backend reports it.
+    pub reclaimable_bytes: Option<u64>,
+    /// The disk space used by the store in bytes, for persistent stores.
+    pub disk_usage: Option<u64>,
+}
+
+impl fmt::Display for CompactionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "pending tombstones: {}", self.pending_tombstones)?;
+        if let Some(reclaimable_bytes) = self.reclaimable_bytes {
+            writeln!(f, "reclaimable: {} bytes", reclaimable_bytes)?;
+        }
+        if let Some(disk_usage) = self.disk_usage {
+            writeln!(f, "disk usage: {} bytes", disk_usage)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for StoreMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "total posts: {}", self.total_posts)?;
+        for (channel, posts) in &self.posts_per_channel {
+            writeln!(f, "  #{}: {} posts", channel, posts)?;
+        }
+        if let Some(tombstones) = self.tombstones {
+            writeln!(f, "tombstones: {}", tombstones)?;
+        }
+        for (index, size) in &self.index_sizes {
+            writeln!(f, "index {}: {} entries", index, size)?;
+        }
+        if let Some(disk_usage) = self.disk_usage {
+            writeln!(f, "disk usage: {} bytes", disk_usage)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Schema versioning for persistent stores.
+//!
+//! Each persistent store records the version of the schema with which its
+//! data was written. When a store is opened, the migrations between the
+//! recorded version and the latest version are applied in order, upgrading
+//! existing data in place.
+//!
+//! The migration at index `n` of a list of migrations upgrades a store from
+//! version `n` to version `n + 1`; the latest version is therefore the length
+//! of the list. A migration may be interrupted before the new version is
+//! recorded, in which case it is applied again when the store is next opened,
+//! so migrations must be idempotent.
+
+use cable::{error::CableErrorKind, Error};
+use log::info;
+
+/// A migration which upgrades the data of a store by a single version.
+pub(crate) type Migration<T> = fn(&T) -> Result<(), Error>;
+
+/// Apply the migrations required to upgrade a store from the given schema
+/// version to the latest version, recording the new version after each
+/// migration with `set_version`.
+///
+/// An error is returned if the store was written with a schema version newer
+/// than the latest known version.
+pub(crate) fn migrate<T>(
+    store: &T,
+    version: u32,
+    migrations: &[Migration<T>],
+    set_version: fn(&T, u32) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let latest = migrations.len() as u32;
+    if version > latest {
+        return CableErrorKind::StoreFailed {
+            context: format!(
+                "store schema version {} is newer than the latest supported version {}",
+                version, latest
+            ),
+        }
+        .raise();
+    }
+
+    for (from, migration) in migrations.iter().enumerate().skip(version as usize) {
+        let to = from as u32 + 1;
+        info!("Migrating store schema from version {} to {}", from, to);
+        migration(store)?;
+        set_version(store, to)?;
+    }
+
+    Ok(())
+}
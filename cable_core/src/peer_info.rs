@@ -0,0 +1,100 @@
+//! Inspection of the state of the connection to a remote peer.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use cable::{capabilities::Capabilities, Channel, Message, ReqId, Timestamp};
+
+use crate::manager::PeerId;
+
+/// A snapshot of the state of the connection to a peer, as returned by
+/// `CableManager::peer_info()`.
+#[derive(Clone, Debug)]
+pub struct PeerInfo {
+    /// The ID of the peer.
+    pub peer_id: PeerId,
+    /// The time at which the peer connected, according to the clock of the
+    /// manager, or `None` if the clock failed.
+    pub connected_at: Option<Timestamp>,
+    /// The time elapsed since the peer connected, or `None` if the clock
+    /// failed.
+    pub connection_age: Option<Duration>,
+    /// The number of messages written to the peer.
+    pub messages_sent: u64,
+    /// The number of bytes written to the peer.
+    pub bytes_sent: u64,
+    /// The number of messages received from the peer, including malformed
+    /// frames.
+    pub messages_received: u64,
+    /// The number of bytes received from the peer.
+    pub bytes_received: u64,
+    /// The active requests which have been sent to the peer, both those of
+    /// the local peer and those forwarded on behalf of other peers.
+    pub outstanding_requests: Vec<(ReqId, Message)>,
+    /// The number of posts requested from the peer which have not yet been
+    /// received.
+    pub requested_posts: usize,
+    /// The live requests held by the peer against the local peer, with the
+    /// channel of each.
+    pub live_requests: Vec<(ReqId, Channel)>,
+    /// The capabilities announced by the peer, or `None` if it has not
+    /// announced them.
+    pub capabilities: Option<Capabilities>,
+}
+
+impl fmt::Display for PeerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer {}", self.peer_id)?;
+        if let Some(connection_age) = self.connection_age {
+            write!(f, " connected for {}s", connection_age.as_secs())?;
+        }
+        write!(
+            f,
+            ": {} messages ({} bytes) sent, {} messages ({} bytes) received, {} outstanding requests, {} requested posts, {} live requests",
+            self.messages_sent,
+            self.bytes_sent,
+            self.messages_received,
+            self.bytes_received,
+            self.outstanding_requests.len(),
+            self.requested_posts,
+            self.live_requests.len()
+        )
+    }
+}
+
+/// The traffic exchanged with a peer, shared between the reader and writer
+/// of its connection.
+#[derive(Debug, Default)]
+pub(crate) struct PeerTraffic {
+    messages_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    messages_received: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl PeerTraffic {
+    /// Record a message of the given length written to the peer.
+    pub(crate) fn sent(&self, len: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Record a message of the given length received from the peer.
+    pub(crate) fn received(&self, len: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Return the messages and bytes sent and received, in that order.
+    pub(crate) fn totals(&self) -> (u64, u64, u64, u64) {
+        (
+            self.messages_sent.load(Ordering::Relaxed),
+            self.bytes_sent.load(Ordering::Relaxed),
+            self.messages_received.load(Ordering::Relaxed),
+            self.bytes_received.load(Ordering::Relaxed),
+        )
+    }
+}
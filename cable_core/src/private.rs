@@ -0,0 +1,146 @@
+//! Private pairwise channels.
+//!
+//! This is an extension to the cable specification: peers which do not
+//! implement it store and relay the posts of a private channel like those of
+//! any other channel, without being able to read them.
+//!
+//! The name of the private channel shared by two users is derived from their
+//! public keys. The text of each `post/text` post in the channel is encrypted
+//! with a key derived from the Ed25519 keypair of one user and the public key
+//! of the other (X25519 key agreement), so that both users derive the same
+//! key. The encrypted text is hex-encoded, following a marker, so that the
+//! post remains valid UTF-8.
+
+use cable::{crypto, error::CableErrorKind, post::PostBody, Channel, Error, Post};
+use sodiumoxide::crypto::{box_, sign::ed25519};
+
+use crate::store::Keypair;
+
+/// The prefix of the name of every private channel.
+const CHANNEL_PREFIX: &str = "~";
+
+/// The marker which begins the encrypted text of a post.
+const TEXT_PREFIX: &str = "cable-private:1:";
+
+/// Return the name of the private channel shared by the users with the given
+/// public keys.
+///
+/// The name does not depend on the order of the keys.
+pub fn private_channel(public_key: &[u8; 32], other_public_key: &[u8; 32]) -> Channel {
+    let (first, second) = if public_key <= other_public_key {
+        (public_key, other_public_key)
+    } else {
+        (other_public_key, public_key)
+    };
+    let hash = crypto::hash(&[&first[..], &second[..]].concat()).unwrap_or_default();
+
+    format!("{}{}", CHANNEL_PREFIX, hex::encode(&hash[..16]))
+}
+
+/// The key shared by the two users of a private channel.
+#[derive(Clone)]
+pub(crate) struct SharedKey {
+    key: box_::PrecomputedKey,
+}
+
+impl SharedKey {
+    /// Derive the key shared by the owner of the given keypair and the user
+    /// with the given public key.
+    pub(crate) fn derive(keypair: &Keypair, other_public_key: &[u8; 32]) -> Result<Self, Error> {
+        let (_pk, sk) = keypair;
+        let secret_key =
+            ed25519::SecretKey::from_slice(sk).and_then(|sk| ed25519::to_curve25519_sk(&sk).ok());
+        let public_key = ed25519::PublicKey::from_slice(other_public_key)
+            .and_then(|pk| ed25519::to_curve25519_pk(&pk).ok());
+
+        match (public_key, secret_key) {
+            (Some(pk), Some(sk)) => Ok(SharedKey {
+                key: box_::precompute(&pk, &sk),
+            }),
+            _ => CableErrorKind::NoneError {
+                context: "failed to derive private channel key".to_string(),
+            }
+            .raise(),
+        }
+    }
+
+    /// Encrypt the given text, returning the marker followed by the
+    /// hex-encoded nonce and ciphertext.
+    pub(crate) fn encrypt(&self, text: &str) -> String {
+        let nonce = box_::gen_nonce();
+        let mut sealed = nonce.0.to_vec();
+        sealed.extend(box_::seal_precomputed(text.as_bytes(), &nonce, &self.key));
+
+        format!("{}{}", TEXT_PREFIX, hex::encode(sealed))
+    }
+
+    /// Decrypt and authenticate the given output of `encrypt()`.
+    pub(crate) fn decrypt(&self, text: &str) -> Result<String, Error> {
+        let plaintext = text
+            .strip_prefix(TEXT_PREFIX)
+            .and_then(|sealed| hex::decode(sealed).ok())
+            .and_then(|sealed| {
+                let nonce = box_::Nonce::from_slice(sealed.get(..box_::NONCEBYTES)?)?;
+                box_::open_precomputed(&sealed[box_::NONCEBYTES..], &nonce, &self.key).ok()
+            })
+            .and_then(|plaintext| String::from_utf8(plaintext).ok());
+
+        match plaintext {
+            Some(plaintext) => Ok(plaintext),
+            None => CableErrorKind::NoneError {
+                context: "failed to decrypt private post".to_string(),
+            }
+            .raise(),
+        }
+    }
+
+    /// Decrypt the text of the given post, returning `None` if it is a text
+    /// post which could not be decrypted. Posts of other types are returned
+    /// unchanged.
+    pub(crate) fn decrypt_post(&self, mut post: Post) -> Option<Post> {
+        if let PostBody::Text { text, .. } = &mut post.body {
+            *text = self.decrypt(text).ok()?;
+        }
+
+        Some(post)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keypair() -> Keypair {
+        crypto::generate_keypair()
+    }
+
+    #[test]
+    fn derive_shared_channel_and_key() -> Result<(), Error> {
+        let alice = keypair();
+        let bob = keypair();
+        let eve = keypair();
+
+        assert_eq!(
+            private_channel(&alice.0, &bob.0),
+            private_channel(&bob.0, &alice.0)
+        );
+        assert_ne!(
+            private_channel(&alice.0, &bob.0),
+            private_channel(&alice.0, &eve.0)
+        );
+
+        let alice_key = SharedKey::derive(&alice, &bob.0)?;
+        let bob_key = SharedKey::derive(&bob, &alice.0)?;
+        let encrypted = alice_key.encrypt("meet by the moth trap");
+        assert!(encrypted.starts_with(TEXT_PREFIX));
+        assert!(!encrypted.contains("moth"));
+        assert_eq!(bob_key.decrypt(&encrypted)?, "meet by the moth trap");
+
+        // A third user derives a different key and cannot decrypt the text.
+        let eve_key = SharedKey::derive(&eve, &alice.0)?;
+        assert!(eve_key.decrypt(&encrypted).is_err());
+        assert!(bob_key.decrypt("meet by the moth trap").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+//! Tracking of recently seen request IDs.
+//!
+//! A request reaching the local peer again, by another path, must be neither
+//! answered nor forwarded again, and a response must answer a request which
+//! the local peer has written to a peer. Request IDs are only remembered for
+//! a limited time and up to a limited number, however, so that the memory used
+//! stays flat and a request ID may be reused once its request has concluded.
+
+use std::{num::NonZeroUsize, time::Duration};
+
+use cable::{ReqId, Timestamp};
+use lru::LruCache;
+
+/// Recently seen request IDs, with the time at which each was last seen.
+///
+/// A request ID is forgotten once it has been retained for longer than the
+/// time to live or, if the capacity is reached, once it is the least recently
+/// seen.
+#[derive(Debug)]
+pub(crate) struct RecentRequests {
+    seen: LruCache<ReqId, Timestamp>,
+    ttl: Duration,
+}
+
+impl RecentRequests {
+    pub(crate) fn new(capacity: usize, ttl: Duration) -> Self {
+        RecentRequests {
+            seen: LruCache::new(NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)),
+            ttl,
+        }
+    }
+
+    /// Query whether the given time of sight has expired at the given time.
+    fn is_expired(&self, seen_at: Timestamp, now: Timestamp) -> bool {
+        now >= seen_at.saturating_add(self.ttl.as_millis() as Timestamp)
+    }
+
+    /// Record that the request with the given ID was seen at the given time,
+    /// forgetting the request IDs which have expired.
+    pub(crate) fn insert(&mut self, req_id: ReqId, now: Timestamp) {
+        while let Some((_, seen_at)) = self.seen.peek_lru() {
+            if !self.is_expired(*seen_at, now) {
+                break;
+            }
+            self.seen.pop_lru();
+        }
+
+        self.seen.put(req_id, now);
+    }
+
+    /// Query whether the request with the given ID was seen and has not
+    /// expired at the given time.
+    pub(crate) fn contains(&self, req_id: &ReqId, now: Timestamp) -> bool {
+        self.seen
+            .peek(req_id)
+            .is_some_and(|seen_at| !self.is_expired(*seen_at, now))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn forget_expired_and_least_recent() {
+        let mut recent = RecentRequests::new(3, Duration::from_secs(10));
+
+        recent.insert([0, 0, 0, 1], 1_000);
+        assert!(recent.contains(&[0, 0, 0, 1], 10_999));
+        assert!(!recent.contains(&[0, 0, 0, 1], 11_000));
+        assert!(!recent.contains(&[0, 0, 0, 2], 1_000));
+
+        // Expired request IDs are dropped when another is inserted.
+        recent.insert([0, 0, 0, 2], 11_000);
+        assert_eq!(recent.seen.len(), 1);
+
+        // The least recently seen request ID is dropped at capacity.
+        for i in 3..=5 {
+            recent.insert([0, 0, 0, i], 11_000);
+        }
+        assert_eq!(recent.seen.len(), 3);
+        assert!(!recent.contains(&[0, 0, 0, 2], 11_000));
+        assert!(recent.contains(&[0, 0, 0, 5], 11_000));
+    }
+}
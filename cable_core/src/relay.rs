@@ -0,0 +1,235 @@
+//! Deduplication of the responses relayed to the origin of forwarded
+//! requests.
+//!
+//! A forwarded request may reach the same peers by several paths, so the
+//! same hashes, posts or channels may be returned more than once. Only the
+//! items which have not previously been relayed for a request are passed on
+//! to its origin, and the empty response concluding the request is relayed
+//! once every peer to which the request was forwarded has concluded it.
+
+use std::{collections::HashSet, num::NonZeroUsize};
+
+use cable::{
+    crypto,
+    message::{MessageBody, ResponseBody},
+    Hash, Message, ReqId,
+};
+use lru::LruCache;
+
+use crate::manager::PeerId;
+
+/// The maximum number of forwarded requests for which relayed items are
+/// tracked. The least recently used request is forgotten first.
+const MAX_REQUESTS: usize = 256;
+
+/// The maximum number of relayed items tracked for a single request. Items
+/// beyond this number are relayed without being tracked.
+const MAX_ITEMS_PER_REQUEST: usize = 4096;
+
+/// The relayed items of a single forwarded request.
+#[derive(Debug, Default)]
+struct RelayState {
+    /// The hashes of the relayed hashes, posts and channels.
+    seen: HashSet<Hash>,
+    /// The peers which have concluded the request.
+    concluded: HashSet<PeerId>,
+    /// Whether the conclusion of the request has been relayed.
+    conclusion_relayed: bool,
+}
+
+/// A filter of the responses relayed to the origin of forwarded requests.
+#[derive(Debug)]
+pub(crate) struct RelayFilter {
+    requests: LruCache<ReqId, RelayState>,
+}
+
+impl Default for RelayFilter {
+    fn default() -> Self {
+        RelayFilter {
+            requests: LruCache::new(NonZeroUsize::new(MAX_REQUESTS).unwrap()),
+        }
+    }
+}
+
+/// Return the key identifying the given post or channel of a response.
+fn key(item: &[u8]) -> Hash {
+    crypto::hash(item).unwrap_or_default()
+}
+
+/// Return the items for which the corresponding flag is set.
+fn retain<T: Clone>(items: &[T], flags: &[bool]) -> Vec<T> {
+    items
+        .iter()
+        .zip(flags)
+        .filter(|(_, flag)| **flag)
+        .map(|(item, _)| item.clone())
+        .collect()
+}
+
+impl RelayFilter {
+    /// Stop tracking the relayed items of the given request.
+    pub(crate) fn forget(&mut self, req_id: &ReqId) {
+        self.requests.pop(req_id);
+    }
+
+    /// Filter a response received from the given peer to a forwarded request,
+    /// given the peers to which the request was forwarded. Returns the
+    /// response to relay to the origin of the request, if any.
+    pub(crate) fn filter(
+        &mut self,
+        peer_id: PeerId,
+        forwarded_to: &HashSet<PeerId>,
+        msg: &Message,
+    ) -> Option<Message> {
+        let body = match &msg.body {
+            MessageBody::Response { body } => body,
+            _ => return None,
+        };
+        let state = self
+            .requests
+            .get_or_insert_mut(msg.header.req_id, RelayState::default);
+
+        let keys: Vec<Hash> = match body {
+            ResponseBody::Hash { hashes } => hashes.to_vec(),
+            ResponseBody::Post { posts } => posts.iter().map(|post| key(post)).collect(),
+            ResponseBody::ChannelList { channels } => channels
+                .iter()
+                .map(|channel| key(channel.as_bytes()))
+                .collect(),
+            // Blob requests are never forwarded, so a blob response is
+            // passed on as is.
+            #[cfg(feature = "blobs")]
+            ResponseBody::Blob { .. } => return Some(msg.clone()),
+        };
+
+        // An empty response concludes the request on the side of the peer.
+        if keys.is_empty() {
+            state.concluded.insert(peer_id);
+            if state.conclusion_relayed || !forwarded_to.is_subset(&state.concluded) {
+                return None;
+            }
+            state.conclusion_relayed = true;
+
+            return Some(msg.clone());
+        }
+
+        // Nothing may be relayed after the conclusion of the request.
+        if state.conclusion_relayed {
+            return None;
+        }
+
+        let unseen: Vec<bool> = keys
+            .into_iter()
+            .map(|key| {
+                if state.seen.len() >= MAX_ITEMS_PER_REQUEST {
+                    !state.seen.contains(&key)
+                } else {
+                    state.seen.insert(key)
+                }
+            })
+            .collect();
+        if !unseen.contains(&true) {
+            return None;
+        }
+
+        let body = match body {
+            ResponseBody::Hash { hashes } => ResponseBody::Hash {
+                hashes: retain(hashes, &unseen),
+            },
+            ResponseBody::Post { posts } => ResponseBody::Post {
+                posts: retain(posts, &unseen),
+            },
+            ResponseBody::ChannelList { channels } => ResponseBody::ChannelList {
+                channels: retain(channels, &unseen),
+            },
+            #[cfg(feature = "blobs")]
+            ResponseBody::Blob { .. } => body.clone(),
+        };
+
+        Some(Message::new(
+            msg.header.clone(),
+            MessageBody::Response { body },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cable::constants::NO_CIRCUIT;
+
+    use super::*;
+
+    const REQ_ID: ReqId = [0, 0, 0, 1];
+
+    fn hashes(msg: Option<Message>) -> Option<Vec<Hash>> {
+        match msg?.body {
+            MessageBody::Response {
+                body: ResponseBody::Hash { hashes },
+            } => Some(hashes),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn relay_only_unseen_hashes() {
+        let mut filter = RelayFilter::default();
+        let forwarded_to = HashSet::from([1, 2]);
+
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, vec![[1; 32], [2; 32]]);
+        assert_eq!(
+            hashes(filter.filter(1, &forwarded_to, &response)),
+            Some(vec![[1; 32], [2; 32]])
+        );
+
+        // The same hashes arrive by another path, along with a new one.
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, vec![[2; 32], [3; 32]]);
+        assert_eq!(
+            hashes(filter.filter(2, &forwarded_to, &response)),
+            Some(vec![[3; 32]])
+        );
+        assert!(filter.filter(2, &forwarded_to, &response).is_none());
+    }
+
+    #[test]
+    fn relay_conclusion_once_all_peers_conclude() {
+        let mut filter = RelayFilter::default();
+        let forwarded_to = HashSet::from([1, 2]);
+        let conclusion = Message::hash_response(NO_CIRCUIT, REQ_ID, Vec::new());
+
+        assert!(filter.filter(1, &forwarded_to, &conclusion).is_none());
+        assert_eq!(
+            hashes(filter.filter(2, &forwarded_to, &conclusion)),
+            Some(Vec::new())
+        );
+        assert!(filter.filter(2, &forwarded_to, &conclusion).is_none());
+
+        // No hashes are relayed after the conclusion.
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, vec![[1; 32]]);
+        assert!(filter.filter(1, &forwarded_to, &response).is_none());
+    }
+
+    #[test]
+    fn relay_only_unseen_posts_and_channels() {
+        let mut filter = RelayFilter::default();
+        let forwarded_to = HashSet::from([1, 2]);
+
+        let response = Message::post_response(NO_CIRCUIT, REQ_ID, vec![vec![1, 2, 3]]);
+        assert!(filter.filter(1, &forwarded_to, &response).is_some());
+        assert!(filter.filter(2, &forwarded_to, &response).is_none());
+
+        let channels = vec!["entomology".to_string(), "botany".to_string()];
+        let response = Message::channel_list_response(NO_CIRCUIT, [0, 0, 0, 2], channels);
+        assert!(filter.filter(1, &forwarded_to, &response).is_some());
+        let channels = vec!["botany".to_string(), "mycology".to_string()];
+        let response = Message::channel_list_response(NO_CIRCUIT, [0, 0, 0, 2], channels);
+        match filter
+            .filter(2, &forwarded_to, &response)
+            .map(|msg| msg.body)
+        {
+            Some(MessageBody::Response {
+                body: ResponseBody::ChannelList { channels },
+            }) => assert_eq!(channels, vec!["mycology".to_string()]),
+            other => panic!("expected a channel list response; got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Tracking of the posts requested from remote peers.
+//!
+//! Each wanted post is requested from a single peer which advertised its
+//! hash. The other peers advertising the hash are remembered so that, if the
+//! request times out or the peer disconnects before answering, the post can
+//! be requested again from one of them.
+//!
+//! The channel and time range of the request answered by each advertisement
+//! are remembered too, so that a post whose hash was advertised for one
+//! channel cannot be passed off as belonging to it while being posted to
+//! another.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use cable::{
+    message::{MessageBody, RequestBody},
+    Channel, Hash, Message, Post, Timestamp,
+};
+
+use crate::manager::PeerId;
+
+/// The channel and time range of a request answered by a hash response.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PostContext {
+    channel: Channel,
+    time_start: Timestamp,
+    /// The end of the time range (exclusive), or 0 if it is open.
+    time_end: Timestamp,
+}
+
+impl PostContext {
+    /// Return the context of the posts advertised in response to the given
+    /// channel time range or channel state request.
+    pub(crate) fn from_request(request: &Message) -> Option<Self> {
+        match &request.body {
+            MessageBody::Request {
+                body:
+                    RequestBody::ChannelTimeRange {
+                        channel,
+                        time_start,
+                        time_end,
+                        ..
+                    },
+                ..
+            } => Some(PostContext {
+                channel: channel.clone(),
+                time_start: *time_start,
+                time_end: *time_end,
+            }),
+            MessageBody::Request {
+                body: RequestBody::ChannelState { channel, .. },
+                ..
+            } => Some(PostContext {
+                channel: channel.clone(),
+                time_start: 0,
+                time_end: 0,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Query whether the given post falls within the context. Posts without
+    /// a channel, such as delete posts, only need to match the time range.
+    fn matches(&self, post: &Post) -> bool {
+        let timestamp = post.get_timestamp();
+
+        post.get_channel()
+            .is_none_or(|channel| *channel == self.channel)
+            && timestamp >= self.time_start
+            && (self.time_end == 0 || timestamp < self.time_end)
+    }
+}
+
+/// A post which has been requested, or is waiting to be requested again.
+#[derive(Debug, Default)]
+struct PendingPost {
+    /// The peer from which the post was last requested and the time of the
+    /// request, or `None` if the post awaits a connected peer advertising it.
+    request: Option<(PeerId, Timestamp)>,
+    /// The peers which have advertised the hash of the post.
+    advertisers: HashSet<PeerId>,
+    /// The contexts in which the hash of the post was advertised.
+    contexts: Vec<Arc<PostContext>>,
+    /// Whether the hash was advertised outside of any known context, in which
+    /// case the post is not constrained to one.
+    unconstrained: bool,
+}
+
+/// The posts requested from remote peers, by hash.
+#[derive(Debug, Default)]
+pub(crate) struct RequestedPosts {
+    posts: HashMap<Hash, PendingPost>,
+    /// The number of posts last requested from each peer.
+    requested_from: HashMap<PeerId, usize>,
+}
+
+/// Replace the request of the given post, updating the number of posts
+/// requested from each peer.
+fn set_request(
+    requested_from: &mut HashMap<PeerId, usize>,
+    post: &mut PendingPost,
+    request: Option<(PeerId, Timestamp)>,
+) {
+    if let Some((peer_id, _)) = post.request {
+        if let Some(count) = requested_from.get_mut(&peer_id) {
+            *count -= 1;
+            if *count == 0 {
+                requested_from.remove(&peer_id);
+            }
+        }
+    }
+    if let Some((peer_id, _)) = request {
+        *requested_from.entry(peer_id).or_default() += 1;
+    }
+    post.request = request;
+}
+
+impl RequestedPosts {
+    /// Stop tracking the post with the given hash, returning `true` if it had
+    /// been requested.
+    pub(crate) fn remove(&mut self, hash: &Hash) -> bool {
+        match self.posts.remove(hash) {
+            Some(mut post) => {
+                set_request(&mut self.requested_from, &mut post, None);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the number of posts last requested from the given peer.
+    pub(crate) fn requested_from(&self, peer_id: PeerId) -> usize {
+        self.requested_from.get(&peer_id).copied().unwrap_or(0)
+    }
+
+    /// Stop tracking up to the given number of the posts requested from the
+    /// given peer, oldest request first, returning the number of posts
+    /// forgotten.
+    ///
+    /// A forgotten post is requested again if its hash is advertised again.
+    pub(crate) fn shed(&mut self, peer_id: PeerId, count: usize) -> usize {
+        let mut requested: Vec<(Timestamp, Hash)> = self
+            .posts
+            .iter()
+            .filter_map(|(hash, post)| match post.request {
+                Some((id, requested_at)) if id == peer_id => Some((requested_at, *hash)),
+                _ => None,
+            })
+            .collect();
+        requested.sort_unstable();
+        requested.truncate(count);
+
+        for (_, hash) in &requested {
+            self.remove(hash);
+        }
+
+        requested.len()
+    }
+
+    /// Query whether the post with the given hash is awaited.
+    pub(crate) fn contains(&self, hash: &Hash) -> bool {
+        self.posts.contains_key(hash)
+    }
+
+    /// Query whether the given post, with the given hash, falls within one of
+    /// the contexts in which its hash was advertised. A post which is not
+    /// awaited matches trivially.
+    pub(crate) fn matches(&self, hash: &Hash, post: &Post) -> bool {
+        match self.posts.get(hash) {
+            Some(pending) => {
+                pending.unconstrained
+                    || pending.contexts.iter().any(|context| context.matches(post))
+            }
+            None => true,
+        }
+    }
+
+    /// Forget the given peer as an advertiser of every post.
+    ///
+    /// Posts requested from the peer remain assigned to it until `retry()`
+    /// selects another peer.
+    pub(crate) fn remove_peer(&mut self, peer_id: PeerId) {
+        for post in self.posts.values_mut() {
+            post.advertisers.remove(&peer_id);
+        }
+    }
+
+    /// Record that the given wanted hashes were advertised by the given peer
+    /// in the given context, returning the hashes to request from the peer.
+    ///
+    /// A hash is requested unless a request for it to another peer is
+    /// outstanding and has not timed out.
+    pub(crate) fn advertised(
+        &mut self,
+        peer_id: PeerId,
+        hashes: &[Hash],
+        context: Option<&Arc<PostContext>>,
+        now: Timestamp,
+        timeout: Option<Duration>,
+    ) -> Vec<Hash> {
+        let mut to_request = Vec::new();
+        for hash in hashes {
+            let post = self.posts.entry(*hash).or_default();
+            post.advertisers.insert(peer_id);
+            match context {
+                Some(context) => {
+                    if !post.contexts.contains(context) {
+                        post.contexts.push(context.clone());
+                    }
+                }
+                None => post.unconstrained = true,
+            }
+
+            let outstanding = match post.request {
+                Some((_, requested_at)) => match timeout {
+                    Some(timeout) => now < requested_at + timeout.as_millis() as Timestamp,
+                    None => true,
+                },
+                None => false,
+            };
+            if !outstanding {
+                set_request(&mut self.requested_from, post, Some((peer_id, now)));
+                to_request.push(*hash);
+            }
+        }
+
+        to_request
+    }
+
+    /// Select a peer from which to request each post again, returning the
+    /// hashes to request grouped by peer.
+    ///
+    /// Posts are requested again if their request has timed out or was made
+    /// to a peer which is no longer connected. Another connected peer which
+    /// advertised the hash is preferred; if there is none, the post waits for
+    /// its hash to be advertised again.
+    pub(crate) fn retry(
+        &mut self,
+        connected: &HashSet<PeerId>,
+        now: Timestamp,
+        timeout: Option<Duration>,
+    ) -> HashMap<PeerId, Vec<Hash>> {
+        let mut to_request: HashMap<PeerId, Vec<Hash>> = HashMap::new();
+        for (hash, post) in self.posts.iter_mut() {
+            let previous = match post.request {
+                Some((peer_id, requested_at)) => {
+                    let timed_out = timeout.is_some_and(|timeout| {
+                        now >= requested_at + timeout.as_millis() as Timestamp
+                    });
+                    if connected.contains(&peer_id) && !timed_out {
+                        continue;
+                    }
+                    Some(peer_id)
+                }
+                None => None,
+            };
+
+            // Forget the advertisers which have disconnected.
+            post.advertisers
+                .retain(|peer_id| connected.contains(peer_id));
+
+            let alternate = post
+                .advertisers
+                .iter()
+                .find(|peer_id| Some(**peer_id) != previous)
+                .or_else(|| post.advertisers.iter().next())
+                .copied();
+            set_request(
+                &mut self.requested_from,
+                post,
+                alternate.map(|peer_id| (peer_id, now)),
+            );
+            if let Some(peer_id) = alternate {
+                to_request.entry(peer_id).or_default().push(*hash);
+            }
+        }
+
+        to_request
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cable::{constants::NO_CIRCUIT, ChannelOptions};
+
+    use super::*;
+
+    const TIMEOUT: Option<Duration> = Some(Duration::from_secs(10));
+
+    #[test]
+    fn request_each_hash_once() {
+        let mut requested = RequestedPosts::default();
+
+        assert_eq!(
+            requested.advertised(1, &[[1; 32]], None, 0, TIMEOUT),
+            vec![[1; 32]]
+        );
+        assert!(requested
+            .advertised(2, &[[1; 32]], None, 1_000, TIMEOUT)
+            .is_empty());
+
+        // Once the request times out, the hash is requested again.
+        assert_eq!(
+            requested.advertised(2, &[[1; 32]], None, 10_000, TIMEOUT),
+            vec![[1; 32]]
+        );
+
+        assert!(requested.remove(&[1; 32]));
+        assert!(!requested.remove(&[1; 32]));
+    }
+
+    #[test]
+    fn match_posts_to_context() {
+        let request = Message::channel_time_range_request(
+            NO_CIRCUIT,
+            [0, 0, 0, 1],
+            0,
+            ChannelOptions::new("entomology", 100, 200, 0),
+        );
+        let context = PostContext::from_request(&request).map(Arc::new);
+
+        let post = Post::text(
+            [0; 32],
+            vec![],
+            150,
+            "entomology".to_string(),
+            "moth".to_string(),
+        );
+        let early = Post::text(
+            [0; 32],
+            vec![],
+            50,
+            "entomology".to_string(),
+            "moth".to_string(),
+        );
+        let other = Post::text(
+            [0; 32],
+            vec![],
+            150,
+            "botany".to_string(),
+            "fern".to_string(),
+        );
+        let delete = Post::delete([0; 32], vec![], 150, vec![[2; 32]]);
+
+        let mut requested = RequestedPosts::default();
+        requested.advertised(1, &[[1; 32]], context.as_ref(), 0, TIMEOUT);
+        assert!(requested.matches(&[1; 32], &post));
+        assert!(requested.matches(&[1; 32], &delete));
+        assert!(!requested.matches(&[1; 32], &early));
+        assert!(!requested.matches(&[1; 32], &other));
+
+        // An advertisement outside of any context lifts the constraint.
+        requested.advertised(2, &[[1; 32]], None, 0, TIMEOUT);
+        assert!(requested.matches(&[1; 32], &other));
+    }
+
+    #[test]
+    fn shed_oldest_requests_of_peer() {
+        let mut requested = RequestedPosts::default();
+        requested.advertised(1, &[[1; 32], [2; 32]], None, 0, TIMEOUT);
+        requested.advertised(1, &[[3; 32]], None, 1_000, TIMEOUT);
+        requested.advertised(2, &[[4; 32]], None, 0, TIMEOUT);
+        assert_eq!(requested.requested_from(1), 3);
+
+        assert_eq!(requested.shed(1, 2), 2);
+        assert_eq!(requested.requested_from(1), 1);
+        assert!(requested.contains(&[3; 32]));
+        assert!(requested.contains(&[4; 32]));
+
+        // Posts requested again from another peer are counted against it.
+        let connected = HashSet::from([2]);
+        requested.advertised(2, &[[3; 32]], None, 1_000, TIMEOUT);
+        requested.retry(&connected, 2_000, TIMEOUT);
+        assert_eq!(requested.requested_from(1), 0);
+        assert_eq!(requested.requested_from(2), 2);
+    }
+
+    #[test]
+    fn retry_with_alternate_peers() {
+        let mut requested = RequestedPosts::default();
+        requested.advertised(1, &[[1; 32]], None, 0, TIMEOUT);
+        requested.advertised(2, &[[1; 32]], None, 0, TIMEOUT);
+
+        // Nothing is retried while the peer is connected and the request has
+        // not timed out.
+        let connected = HashSet::from([1, 2]);
+        assert!(requested.retry(&connected, 1_000, TIMEOUT).is_empty());
+
+        // The request is retried with the other advertiser once the first
+        // peer disconnects.
+        let connected = HashSet::from([2]);
+        assert_eq!(
+            requested.retry(&connected, 2_000, TIMEOUT),
+            HashMap::from([(2, vec![[1; 32]])])
+        );
+
+        // With no advertiser connected, the post waits to be advertised
+        // again.
+        let connected = HashSet::from([3]);
+        assert!(requested.retry(&connected, 3_000, TIMEOUT).is_empty());
+        assert_eq!(
+            requested.advertised(3, &[[1; 32]], None, 4_000, TIMEOUT),
+            vec![[1; 32]]
+        );
+    }
+}
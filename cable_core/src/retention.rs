@@ -0,0 +1,43 @@
+//! Retention policies limiting the posts kept by a store.
+
+use std::time::Duration;
+
+use cable::Timestamp;
+
+/// Parameters defining which posts a store retains.
+///
+/// Posts which fall outside the policy are pruned: they are deleted from the
+/// store and tombstoned, so that they are neither advertised to peers nor
+/// requested from them again. The posts defining the current state of each
+/// channel (membership, topic and names) and posts without a channel are
+/// always retained.
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    /// The maximum age of a retained post. Set to `None` to retain posts of
+    /// any age.
+    pub max_age: Option<Duration>,
+    /// The maximum number of posts retained for each channel; the newest
+    /// posts are retained. Set to `None` to retain any number of posts.
+    pub max_posts_per_channel: Option<usize>,
+    /// Interval at which the policy is applied while peers are connected.
+    pub interval: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_age: None,
+            max_posts_per_channel: None,
+            interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Return the timestamp before which posts are pruned at the given time,
+    /// if the policy defines a maximum age.
+    pub fn cutoff(&self, now: Timestamp) -> Option<Timestamp> {
+        self.max_age
+            .map(|max_age| now.saturating_sub(max_age.as_millis() as Timestamp))
+    }
+}
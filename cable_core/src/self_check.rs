@@ -0,0 +1,254 @@
+//! Validation of outgoing messages against the constraints of the cable
+//! specification.
+//!
+//! When enabled with `ManagerOptions::self_check`, every message is checked
+//! before it is written to a peer. This is intended for use while developing
+//! new handlers; a compliant manager never produces a violation.
+
+use std::collections::{HashMap, HashSet};
+
+use cable::{
+    constants::MAX_TTL,
+    error::CableErrorKind,
+    message::{MessageBody, RequestBody, ResponseBody},
+    validation, Error, Message, Post, ReqId,
+};
+use desert::{varint, FromBytes, ToBytes};
+
+/// The action taken when an outgoing message violates the specification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelfCheck {
+    /// Outgoing messages are not checked.
+    #[default]
+    Off,
+    /// Violations are logged and the message is written regardless.
+    Log,
+    /// The message is not written and the connection is closed with an
+    /// error describing the violation.
+    Fail,
+}
+
+/// Return a violation error for the given message.
+fn violation<T>(msg: &Message, violation: impl Into<String>) -> Result<T, Error> {
+    CableErrorKind::MessageSpecViolation {
+        msg_type: msg.message_type(),
+        violation: violation.into(),
+    }
+    .raise()
+}
+
+/// Check a single outgoing message and its encoding, without regard to the
+/// other messages of the connection.
+pub fn check_message(msg: &Message, encoded: &[u8]) -> Result<(), Error> {
+    // The length prefix must match the length of the rest of the message.
+    let (prefix_len, msg_len) = varint::decode(encoded)?;
+    if prefix_len + msg_len as usize != encoded.len() {
+        return violation(
+            msg,
+            format!(
+                "msg_len is {} but {} bytes follow it",
+                msg_len,
+                encoded.len() - prefix_len
+            ),
+        );
+    }
+
+    // The encoding must decode to a message which re-encodes identically.
+    let (len, decoded) = Message::from_bytes(encoded)?;
+    if len != encoded.len() || decoded.to_bytes()? != encoded {
+        return violation(msg, "encoding does not round-trip");
+    }
+
+    match &msg.body {
+        MessageBody::Request { ttl, body } => {
+            if *ttl > MAX_TTL {
+                return violation(msg, format!("ttl {} exceeds {}", ttl, MAX_TTL));
+            }
+            match body {
+                RequestBody::ChannelTimeRange {
+                    channel,
+                    time_start,
+                    time_end,
+                    ..
+                } => {
+                    validation::validate_channel(channel)?;
+                    if *time_end != 0 && time_end < time_start {
+                        return violation(
+                            msg,
+                            format!("time_end {} precedes time_start {}", time_end, time_start),
+                        );
+                    }
+                }
+                RequestBody::ChannelState { channel, future } => {
+                    validation::validate_channel(channel)?;
+                    if *future > 1 {
+                        return violation(msg, format!("future is {}; expected 0 or 1", future));
+                    }
+                }
+                _ => (),
+            }
+        }
+        MessageBody::Response { body } => match body {
+            ResponseBody::Post { posts } => {
+                for post in posts {
+                    // An empty post would be read as the end of the response.
+                    if post.is_empty() {
+                        return violation(msg, "post response includes an empty post");
+                    }
+                    Post::from_bytes(post)?;
+                }
+            }
+            ResponseBody::ChannelList { channels } => {
+                for channel in channels {
+                    validation::validate_channel(channel)?;
+                }
+            }
+            ResponseBody::Hash { .. } => (),
+            #[cfg(feature = "blobs")]
+            ResponseBody::Blob { .. } => (),
+        },
+        MessageBody::Unrecognized { msg_type } => {
+            return violation(msg, format!("unrecognized msg_type {}", msg_type));
+        }
+    }
+
+    Ok(())
+}
+
+/// The state of a connection required to check its outgoing messages against
+/// the requests received from the peer.
+#[derive(Debug, Default)]
+pub struct SelfChecker {
+    /// The limits of the requests received from the peer, by request ID.
+    limits: HashMap<ReqId, u64>,
+    /// The IDs of the requests concluded with an empty hash response.
+    concluded: HashSet<ReqId>,
+}
+
+impl SelfChecker {
+    /// Record a message received from the peer.
+    pub fn inbound(&mut self, msg: &Message) {
+        if let MessageBody::Request { body, .. } = &msg.body {
+            let req_id = msg.header.req_id;
+            match body {
+                RequestBody::ChannelTimeRange { limit, .. }
+                | RequestBody::ChannelList { limit, .. } => {
+                    self.limits.insert(req_id, *limit);
+                }
+                RequestBody::Cancel { cancel_id } => {
+                    self.limits.remove(cancel_id);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Check a message about to be written to the peer and its encoding.
+    pub fn check(&mut self, msg: &Message, encoded: &[u8]) -> Result<(), Error> {
+        check_message(msg, encoded)?;
+
+        let req_id = msg.header.req_id;
+        let (count, noun) = match &msg.body {
+            MessageBody::Response {
+                body: ResponseBody::Hash { hashes },
+            } => {
+                if self.concluded.contains(&req_id) {
+                    return violation(
+                        msg,
+                        format!(
+                            "hash response follows the conclusion of request {}",
+                            hex::encode(req_id)
+                        ),
+                    );
+                }
+                // An empty hash response concludes the request.
+                if hashes.is_empty() {
+                    self.limits.remove(&req_id);
+                    self.concluded.insert(req_id);
+                }
+                (hashes.len(), "hashes")
+            }
+            MessageBody::Response {
+                body: ResponseBody::ChannelList { channels },
+            } => (channels.len(), "channels"),
+            _ => return Ok(()),
+        };
+
+        // A limit of 0 means that the number of results is unlimited.
+        match self.limits.get(&req_id) {
+            Some(&limit) if limit != 0 && count as u64 > limit => violation(
+                msg,
+                format!("{} {} exceed the request limit of {}", count, noun, limit),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cable::{constants::NO_CIRCUIT, ChannelOptions};
+
+    use super::*;
+
+    const REQ_ID: ReqId = [4, 186, 175, 251];
+
+    fn check(checker: &mut SelfChecker, msg: &Message) -> Result<(), Error> {
+        checker.check(msg, &msg.to_bytes()?)
+    }
+
+    #[test]
+    fn stateless_violations() -> Result<(), Error> {
+        let mut checker = SelfChecker::default();
+
+        let request = Message::channel_list_request(NO_CIRCUIT, REQ_ID, MAX_TTL, 0, 0);
+        assert!(check(&mut checker, &request).is_ok());
+
+        let request = Message::channel_list_request(NO_CIRCUIT, REQ_ID, MAX_TTL + 1, 0, 0);
+        assert!(check(&mut checker, &request).is_err());
+
+        let request =
+            Message::channel_state_request(NO_CIRCUIT, REQ_ID, 1, "default".to_string(), 2);
+        assert!(check(&mut checker, &request).is_err());
+
+        let opts = ChannelOptions::new("default", 100, 50, 0);
+        let request = Message::channel_time_range_request(NO_CIRCUIT, REQ_ID, 1, opts);
+        assert!(check(&mut checker, &request).is_err());
+
+        let response = Message::post_response(NO_CIRCUIT, REQ_ID, vec![Vec::new()]);
+        assert!(check(&mut checker, &response).is_err());
+
+        // An encoding with an incorrect length prefix.
+        let request = Message::channel_list_request(NO_CIRCUIT, REQ_ID, 1, 0, 0);
+        let mut encoded = request.to_bytes()?;
+        encoded[0] += 1;
+        assert!(checker.check(&request, &encoded).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn responses_respect_requests() -> Result<(), Error> {
+        let mut checker = SelfChecker::default();
+        let hashes = vec![[0; 32], [1; 32], [2; 32]];
+
+        let opts = ChannelOptions::new("default", 0, 100, 2);
+        checker.inbound(&Message::channel_time_range_request(
+            NO_CIRCUIT, REQ_ID, 1, opts,
+        ));
+
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, hashes[..2].to_vec());
+        assert!(check(&mut checker, &response).is_ok());
+
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, hashes.clone());
+        assert!(check(&mut checker, &response).is_err());
+
+        // No hash response may follow the conclusion of the request.
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, Vec::new());
+        assert!(check(&mut checker, &response).is_ok());
+        let response = Message::hash_response(NO_CIRCUIT, REQ_ID, hashes[..1].to_vec());
+        assert!(check(&mut checker, &response).is_err());
+
+        Ok(())
+    }
+}
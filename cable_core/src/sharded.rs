@@ -0,0 +1,87 @@
+//! Collections divided into independently locked shards.
+//!
+//! The request maps of the manager are touched by nearly every message, from
+//! every peer. Dividing them into shards, each behind its own lock, allows
+//! the messages of different requests to be handled without contending for
+//! a single lock. Each key is assigned to a shard by its hash, so that all
+//! operations on a key lock the same shard.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hash},
+};
+
+use async_std::sync::{Arc, RwLock};
+
+/// The number of shards of each collection.
+pub(crate) const SHARDS: usize = 16;
+
+/// A collection divided into shards by the hash of its keys.
+pub(crate) struct Sharded<T> {
+    shards: Arc<[RwLock<T>]>,
+    hasher: RandomState,
+}
+
+impl<T> Clone for Sharded<T> {
+    fn clone(&self) -> Self {
+        Sharded {
+            shards: self.shards.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<T: Default> Default for Sharded<T> {
+    fn default() -> Self {
+        Sharded::new(T::default)
+    }
+}
+
+impl<T> Sharded<T> {
+    /// Create a collection with shards returned by the given function.
+    pub(crate) fn new(shard: impl Fn() -> T) -> Self {
+        Sharded {
+            shards: (0..SHARDS).map(|_| RwLock::new(shard())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Return the shard holding the given key.
+    pub(crate) fn shard<K: Hash>(&self, key: &K) -> &RwLock<T> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+
+        &self.shards[index]
+    }
+
+    /// Return all shards, to be locked in turn when visiting every entry.
+    ///
+    /// The collection is not locked as a whole, so entries may be inserted
+    /// or removed in shards which have already been visited.
+    pub(crate) fn shards(&self) -> impl Iterator<Item = &RwLock<T>> {
+        self.shards.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn visit_every_shard() {
+        let set: Sharded<HashSet<u32>> = Sharded::default();
+        for i in 0..100 {
+            set.shard(&i).write().await.insert(i);
+        }
+
+        // Each key is held by the shard to which it is assigned.
+        assert!(set.shard(&42).read().await.contains(&42));
+
+        let mut len = 0;
+        for shard in set.shards() {
+            len += shard.read().await.len();
+        }
+        assert_eq!(len, 100);
+    }
+}
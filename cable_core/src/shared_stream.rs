@@ -0,0 +1,106 @@
+//! A cloneable handle to a duplex stream.
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+/// A cloneable handle to a duplex stream, allowing any stream to be passed to
+/// `CableManager::listen()`.
+///
+/// The manager reads from and writes to the stream from separate tasks, so it
+/// requires a stream which can be cloned (such as a `TcpStream`). Streams
+/// which cannot be cloned, such as a browser WebSocket, may be wrapped in a
+/// `SharedStream` instead. All clones refer to the same underlying stream.
+#[derive(Debug)]
+pub struct SharedStream<T> {
+    inner: Arc<Mutex<T>>,
+}
+
+impl<T> SharedStream<T> {
+    /// Wrap the given stream.
+    pub fn new(stream: T) -> Self {
+        SharedStream {
+            inner: Arc::new(Mutex::new(stream)),
+        }
+    }
+
+    /// Poll the wrapped stream with the given function.
+    fn poll_with<R>(
+        &self,
+        f: impl FnOnce(Pin<&mut T>) -> Poll<io::Result<R>>,
+    ) -> Poll<io::Result<R>>
+    where
+        T: Unpin,
+    {
+        match self.inner.lock() {
+            Ok(mut stream) => f(Pin::new(&mut *stream)),
+            Err(_) => Poll::Ready(Err(io::Error::other("shared stream lock poisoned"))),
+        }
+    }
+}
+
+impl<T> Clone for SharedStream<T> {
+    fn clone(&self) -> Self {
+        SharedStream {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for SharedStream<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(|stream| stream.poll_read(cx, buf))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for SharedStream<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_with(|stream| stream.poll_write(cx, buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_with(|stream| stream.poll_flush(cx))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_with(|stream| stream.poll_close(cx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{io::Cursor, AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn clones_share_the_stream() -> io::Result<()> {
+        let mut writer = SharedStream::new(Cursor::new(Vec::new()));
+        let reader = writer.clone();
+
+        writer.write_all(b"moths").await?;
+        let cursor = reader.inner.lock().unwrap().get_ref().clone();
+        assert_eq!(cursor, b"moths");
+
+        let mut reader = SharedStream::new(Cursor::new(b"beetles".to_vec()));
+        let mut buf = Vec::new();
+        reader.clone().read_to_end(&mut buf).await?;
+        assert_eq!(buf, b"beetles");
+        assert_eq!(reader.read(&mut [0; 4]).await?, 0);
+
+        Ok(())
+    }
+}
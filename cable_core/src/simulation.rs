@@ -0,0 +1,607 @@
+//! A simulated network of cable peers with virtual time, latency, jitter,
+//! packet loss and partitions.
+//!
+//! Unlike a `testing::Network`, whose in-memory streams deliver bytes as soon
+//! as they are written, a `Simulation` carries each frame written by a peer
+//! to its destination itself. A frame is delayed by the latency of its link
+//! and a random jitter, measured on a virtual clock shared by the managers of
+//! the peers, and may be lost at random. Frames between peers on either side
+//! of a partition are held back until the partition is healed, as a reliable
+//! transport would retransmit them. Random draws come from generators seeded
+//! by the caller, so that a run is reproducible: the fate of each frame
+//! depends only on the seed and on the frames sent before it in the same
+//! direction.
+//!
+//! Virtual time only moves while the simulation is run. Before each delivery,
+//! the simulation waits for the peers to settle: to read every delivered
+//! frame and to stop writing new ones. Timers of the manager which sleep for
+//! a real duration, such as keepalive pings, are unaffected by virtual time
+//! and are disabled by `manager_options()`.
+//!
+//! ```rust,ignore
+//! use cable_core::simulation::{LinkConditions, Simulation};
+//! use cable_core::testing::Topology;
+//!
+//! let mut sim = Simulation::new(3, Topology::Line, 42).with_conditions(LinkConditions {
+//!     latency: Duration::from_millis(50),
+//!     ..Default::default()
+//! });
+//! sim.peer(0).post_text("default", "hello").await?;
+//!
+//! sim.partition(&[2]);
+//! sim.run_for(Duration::from_secs(1)).await;
+//! sim.heal();
+//!
+//! assert!(sim.run_until(Duration::from_secs(10), || async { ... }).await);
+//! ```
+
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
+
+use async_std::task::{self, JoinHandle};
+use cable::Timestamp;
+use desert::varint;
+
+use crate::{
+    clock::{Clock, MockClock},
+    manager::{CableManager, ManagerOptions},
+    store::{MemoryStore, Store},
+    testing::{lock, MemoryStream, SharedPipe, Topology},
+};
+
+/// The virtual time at which a simulation created with `Simulation::new()`
+/// starts, in milliseconds since the Unix epoch.
+pub const SIMULATION_EPOCH: Timestamp = 1_700_000_000_000;
+
+/// The virtual time, in milliseconds, which passes at each tick of a
+/// running simulation while no frame is due.
+const IDLE_TICK: Timestamp = 10;
+
+/// The real duration between checks of whether the peers have settled.
+const SETTLE_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The number of consecutive checks without activity after which the peers
+/// are considered settled.
+const SETTLE_ROUNDS: usize = 3;
+
+/// The maximum number of checks made while waiting for the peers to settle,
+/// so that a peer which never stops writing cannot stall the simulation.
+const SETTLE_LIMIT: usize = 1000;
+
+/// Return the manager options suited to a peer of a simulation driven by the
+/// given clock: the manager reads the virtual time from the clock, and
+/// keepalive pings and idle timeouts are disabled.
+pub fn manager_options(clock: &MockClock) -> ManagerOptions {
+    ManagerOptions {
+        clock: Arc::new(clock.clone()),
+        keepalive_interval: None,
+        idle_timeout: None,
+        ..Default::default()
+    }
+}
+
+/// The conditions of a simulated link, applied to the frames sent in either
+/// direction.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkConditions {
+    /// The time taken by a frame to cross the link.
+    pub latency: Duration,
+    /// The maximum additional delay of a frame, drawn uniformly for each
+    /// frame. Frames sent in one direction are still delivered in order.
+    pub jitter: Duration,
+    /// The probability, from 0 to 1, that a frame is lost.
+    pub loss: f64,
+}
+
+/// The number of frames delivered and lost by a simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    /// The number of frames delivered to their destination.
+    pub delivered: usize,
+    /// The number of frames lost at random.
+    pub lost: usize,
+}
+
+/// One direction of a simulated link: the frames written by one peer to its
+/// outbox are delivered to the inbox of the other.
+struct Direction {
+    from: usize,
+    to: usize,
+    outbox: SharedPipe,
+    inbox: SharedPipe,
+    /// The delivery time of the last frame scheduled in this direction, so
+    /// that jitter does not reorder frames.
+    last_delivery: Timestamp,
+    /// The generator of the random draws for the frames sent in this
+    /// direction, so that the draws for each frame do not depend on the
+    /// order in which the frames of different directions are collected.
+    rng: fastrand::Rng,
+}
+
+/// A simulated connection between two peers.
+struct SimLink {
+    directions: [Direction; 2],
+    conditions: LinkConditions,
+    listeners: [JoinHandle<()>; 2],
+}
+
+impl SimLink {
+    /// Close all pipes of the link, ending both connections.
+    fn close(&self) {
+        for direction in &self.directions {
+            for pipe in [&direction.outbox, &direction.inbox] {
+                let mut pipe = lock(pipe);
+                pipe.closed = true;
+                pipe.wake();
+            }
+        }
+    }
+
+    /// Return `true` if either peer has closed its end of the link.
+    fn is_closed(&self) -> bool {
+        self.directions
+            .iter()
+            .any(|direction| lock(&direction.outbox).closed || lock(&direction.inbox).closed)
+    }
+}
+
+/// A frame on its way between two peers: the link, the index of the
+/// direction and the bytes of the frame, including its length prefix.
+type Frame = ((usize, usize), usize, Vec<u8>);
+
+/// A set of cable peers connected over simulated links.
+pub struct Simulation<S: Store + Clone> {
+    peers: Vec<CableManager<S>>,
+    links: BTreeMap<(usize, usize), SimLink>,
+    /// The conditions of links connected from now on.
+    conditions: LinkConditions,
+    /// The peers cut off from the rest of the network, if partitioned.
+    partition: Option<Vec<usize>>,
+    clock: MockClock,
+    start: Timestamp,
+    seed: u64,
+    /// The frames in flight, ordered by delivery time and then by the order
+    /// in which they were scheduled.
+    in_flight: BTreeMap<(Timestamp, u64), Frame>,
+    /// The frames held back by the partition, in the order in which they
+    /// reached it.
+    held: Vec<Frame>,
+    seq: u64,
+    stats: SimulationStats,
+}
+
+impl Simulation<MemoryStore> {
+    /// Create a simulation of the given number of peers, each with an empty
+    /// `MemoryStore`, connected according to the given topology over
+    /// perfect links. Random draws are seeded with the given seed.
+    pub fn new(peers: usize, topology: Topology, seed: u64) -> Self {
+        let clock = MockClock::new(SIMULATION_EPOCH);
+        let managers = (0..peers)
+            .map(|_| CableManager::with_options(MemoryStore::default(), manager_options(&clock)))
+            .collect();
+
+        Simulation::with_managers(managers, clock, topology, seed)
+    }
+}
+
+impl<S: Store + Clone> Simulation<S> {
+    /// Create a simulation of the given managers, connected according to the
+    /// given topology over perfect links.
+    ///
+    /// The managers should read the time from the given clock, which the
+    /// simulation advances; see `manager_options()`.
+    pub fn with_managers(
+        managers: Vec<CableManager<S>>,
+        clock: MockClock,
+        topology: Topology,
+        seed: u64,
+    ) -> Self {
+        let start = clock.now().unwrap_or_default();
+        let mut simulation = Simulation {
+            peers: managers,
+            links: BTreeMap::new(),
+            conditions: LinkConditions::default(),
+            partition: None,
+            clock,
+            start,
+            seed,
+            in_flight: BTreeMap::new(),
+            held: Vec::new(),
+            seq: 0,
+            stats: SimulationStats::default(),
+        };
+        for (a, b) in topology.edges(simulation.peers.len()) {
+            simulation.connect(a, b);
+        }
+
+        simulation
+    }
+
+    /// Apply the given conditions to every link, including those connected
+    /// later.
+    pub fn with_conditions(mut self, conditions: LinkConditions) -> Self {
+        for link in self.links.values_mut() {
+            link.conditions = conditions.clone();
+        }
+        self.conditions = conditions;
+
+        self
+    }
+
+    /// Apply the given conditions to the link between the given peers.
+    /// Returns `false` if the peers are not connected.
+    pub fn set_conditions(&mut self, a: usize, b: usize, conditions: LinkConditions) -> bool {
+        match self.links.get_mut(&(a.min(b), a.max(b))) {
+            Some(link) => {
+                link.conditions = conditions;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the manager of the given peer.
+    ///
+    /// Panics if there is no such peer.
+    pub fn peer(&self, index: usize) -> CableManager<S> {
+        self.peers[index].clone()
+    }
+
+    /// Return the managers of all peers, in order.
+    pub fn peers(&self) -> &[CableManager<S>] {
+        &self.peers
+    }
+
+    /// Return the number of peers in the simulation.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Return `true` if the simulation has no peers.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Return the virtual clock of the simulation.
+    pub fn clock(&self) -> &MockClock {
+        &self.clock
+    }
+
+    /// Return the current virtual time.
+    pub fn now(&self) -> Timestamp {
+        self.clock.now().unwrap_or(self.start)
+    }
+
+    /// Return the virtual time elapsed since the simulation was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.now().saturating_sub(self.start))
+    }
+
+    /// Return the number of frames delivered and lost so far.
+    pub fn stats(&self) -> SimulationStats {
+        self.stats
+    }
+
+    /// Return `true` if the given peers are connected.
+    pub fn is_connected(&self, a: usize, b: usize) -> bool {
+        self.links.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    /// Connect the given peers, unless they are already connected.
+    ///
+    /// Panics if either peer does not exist or if a peer is connected to
+    /// itself.
+    pub fn connect(&mut self, a: usize, b: usize) {
+        assert!(a != b, "a peer cannot be connected to itself");
+        let key = (a.min(b), a.max(b));
+        if self.links.contains_key(&key) {
+            return;
+        }
+
+        let (outbox_a, inbox_a) = (SharedPipe::default(), SharedPipe::default());
+        let (outbox_b, inbox_b) = (SharedPipe::default(), SharedPipe::default());
+        let streams = [
+            (a, MemoryStream::new(inbox_a.clone(), outbox_a.clone())),
+            (b, MemoryStream::new(inbox_b.clone(), outbox_b.clone())),
+        ];
+        let listeners = streams.map(|(peer, stream)| {
+            let manager = self.peers[peer].clone();
+            task::spawn(async move {
+                if let Err(err) = manager.listen(stream).await {
+                    log::debug!("Connection of simulated peer {} closed: {}", peer, err);
+                }
+            })
+        });
+
+        let now = self.now();
+        self.links.insert(
+            key,
+            SimLink {
+                directions: [
+                    Direction {
+                        from: a,
+                        to: b,
+                        outbox: outbox_a,
+                        inbox: inbox_b,
+                        last_delivery: now,
+                        rng: self.direction_rng(a, b),
+                    },
+                    Direction {
+                        from: b,
+                        to: a,
+                        outbox: outbox_b,
+                        inbox: inbox_a,
+                        last_delivery: now,
+                        rng: self.direction_rng(b, a),
+                    },
+                ],
+                conditions: self.conditions.clone(),
+                listeners,
+            },
+        );
+    }
+
+    /// Return the generator of the random draws for the frames sent from one
+    /// peer to another, seeded with the seed of the simulation.
+    fn direction_rng(&self, from: usize, to: usize) -> fastrand::Rng {
+        fastrand::Rng::with_seed(self.seed ^ ((from as u64) << 32 | to as u64))
+    }
+
+    /// Sever the connection between the given peers, discarding the frames
+    /// in flight between them and waiting until both peers have stopped
+    /// listening. Returns `false` if the peers were not connected.
+    pub async fn disconnect(&mut self, a: usize, b: usize) -> bool {
+        let key = (a.min(b), a.max(b));
+        match self.links.remove(&key) {
+            Some(link) => {
+                self.in_flight
+                    .retain(|_, (link_key, _, _)| *link_key != key);
+                self.held.retain(|(link_key, _, _)| *link_key != key);
+                link.close();
+                for listener in link.listeners {
+                    listener.await;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sever all connections of the simulation.
+    pub async fn shutdown(&mut self) {
+        let keys: Vec<_> = self.links.keys().cloned().collect();
+        for (a, b) in keys {
+            self.disconnect(a, b).await;
+        }
+    }
+
+    /// Cut the given peers off from the rest of the network, replacing any
+    /// previous partition. Frames between a peer in the group and a peer
+    /// outside it are held back until the partition is healed.
+    pub fn partition(&mut self, group: &[usize]) {
+        self.partition = Some(group.to_vec());
+    }
+
+    /// Heal the partition, delivering the frames held back by it in the
+    /// order in which they were sent.
+    pub fn heal(&mut self) {
+        self.partition = None;
+        let now = self.now();
+        for frame in std::mem::take(&mut self.held) {
+            self.in_flight.insert((now, self.seq), frame);
+            self.seq += 1;
+        }
+    }
+
+    /// Return `true` if frames between the given peers are held back by the
+    /// partition.
+    fn is_partitioned(&self, from: usize, to: usize) -> bool {
+        match &self.partition {
+            Some(group) => group.contains(&from) != group.contains(&to),
+            None => false,
+        }
+    }
+
+    /// Deliver the next frame in flight, advancing the virtual time to its
+    /// delivery. Returns `false` if no frame is left in flight.
+    pub async fn step(&mut self) -> bool {
+        self.settle().await;
+        self.collect();
+        self.deliver_next(Timestamp::MAX)
+    }
+
+    /// Run the simulation for the given duration of virtual time,
+    /// delivering every frame due in that time. While no frame is due,
+    /// virtual time passes in ticks of 10 milliseconds.
+    pub async fn run_for(&mut self, duration: Duration) {
+        let deadline = self.now().saturating_add(duration.as_millis() as Timestamp);
+        while self.now() < deadline {
+            self.settle().await;
+            self.collect();
+            if !self.deliver_next(deadline) {
+                self.tick(deadline);
+            }
+        }
+    }
+
+    /// Run the simulation until the given check returns `true`, returning
+    /// the final result.
+    ///
+    /// The check is evaluated before each delivery and each tick of virtual
+    /// time. The run ends with the check
+    /// failing once the given duration of virtual time has elapsed.
+    pub async fn run_until<F, Fut>(&mut self, timeout: Duration, mut check: F) -> bool
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let deadline = self.now().saturating_add(timeout.as_millis() as Timestamp);
+        loop {
+            self.settle().await;
+            self.collect();
+            if check().await {
+                return true;
+            }
+            if !self.deliver_next(deadline) {
+                if self.now() >= deadline {
+                    return false;
+                }
+                self.tick(deadline);
+            }
+        }
+    }
+
+    /// Let a tick of virtual time pass, up to the given deadline, giving the
+    /// peers which are slow to write their frames the chance to.
+    fn tick(&self, deadline: Timestamp) {
+        self.clock
+            .set(self.now().saturating_add(IDLE_TICK).min(deadline));
+    }
+
+    /// Wait until the peers have read every delivered frame and stopped
+    /// writing new ones.
+    async fn settle(&self) {
+        let mut last_written = None;
+        let mut quiet_rounds = 0;
+        for _ in 0..SETTLE_LIMIT {
+            let (unread, written) = self.activity();
+            if unread == 0 && last_written == Some(written) {
+                quiet_rounds += 1;
+                if quiet_rounds >= SETTLE_ROUNDS {
+                    return;
+                }
+            } else {
+                quiet_rounds = 0;
+            }
+            last_written = Some(written);
+            task::sleep(SETTLE_INTERVAL).await;
+        }
+    }
+
+    /// Return the number of bytes delivered to the peers and not yet read,
+    /// and the number of bytes written by the peers and not yet collected.
+    fn activity(&self) -> (usize, usize) {
+        let mut unread = 0;
+        let mut written = 0;
+        for direction in self.links.values().flat_map(|link| &link.directions) {
+            let inbox = lock(&direction.inbox);
+            if !inbox.closed {
+                unread += inbox.buf.len();
+            }
+            written += lock(&direction.outbox).buf.len();
+        }
+
+        (unread, written)
+    }
+
+    /// Collect the complete frames written by the peers, dropping those
+    /// which are lost and scheduling the delivery of the others.
+    fn collect(&mut self) {
+        let now = self.now();
+        let Simulation {
+            links,
+            in_flight,
+            seq,
+            stats,
+            ..
+        } = self;
+
+        for (key, link) in links.iter_mut() {
+            // A peer which stopped listening closed its end of the link, so
+            // the connection is ended for the other peer too.
+            if link.is_closed() {
+                link.close();
+                continue;
+            }
+
+            let conditions = &link.conditions;
+            for (index, direction) in link.directions.iter_mut().enumerate() {
+                for frame in take_frames(&direction.outbox) {
+                    if conditions.loss > 0.0 && direction.rng.f64() < conditions.loss {
+                        stats.lost += 1;
+                        continue;
+                    }
+
+                    let jitter = conditions.jitter.as_millis() as Timestamp;
+                    let delay = conditions.latency.as_millis() as Timestamp
+                        + if jitter > 0 {
+                            direction.rng.u64(0..=jitter)
+                        } else {
+                            0
+                        };
+                    let at = now.saturating_add(delay).max(direction.last_delivery);
+                    direction.last_delivery = at;
+
+                    in_flight.insert((at, *seq), (*key, index, frame));
+                    *seq += 1;
+                }
+            }
+        }
+    }
+
+    /// Deliver the earliest frame in flight if it is due by the given
+    /// deadline, advancing the virtual time to its delivery. Returns `false`
+    /// if no such frame is left.
+    fn deliver_next(&mut self, deadline: Timestamp) -> bool {
+        let Some(entry) = self.in_flight.first_entry() else {
+            return false;
+        };
+        if entry.key().0 > deadline {
+            return false;
+        }
+        let ((at, _seq), frame) = entry.remove_entry();
+        self.clock.set(at.max(self.now()));
+
+        let (key, index, bytes) = &frame;
+        let Some(link) = self.links.get(key) else {
+            return true;
+        };
+        let direction = &link.directions[*index];
+        if self.is_partitioned(direction.from, direction.to) {
+            self.held.push(frame);
+            return true;
+        }
+
+        let mut inbox = lock(&direction.inbox);
+        if !inbox.closed {
+            inbox.buf.extend(bytes);
+            inbox.wake();
+            self.stats.delivered += 1;
+        }
+
+        true
+    }
+}
+
+/// Remove and return the complete length-prefixed frames at the front of the
+/// given pipe, leaving any partially written frame in place.
+fn take_frames(pipe: &SharedPipe) -> Vec<Vec<u8>> {
+    let mut pipe = lock(pipe);
+    let mut frames = Vec::new();
+    loop {
+        let buf = pipe.buf.make_contiguous();
+        let len = match varint::decode(buf) {
+            Ok((prefix_len, msg_len)) => prefix_len + msg_len as usize,
+            Err(_) => break,
+        };
+        if buf.len() < len {
+            break;
+        }
+        frames.push(pipe.buf.drain(..len).collect());
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn take_complete_frames() {
+        let pipe = SharedPipe::default();
+        lock(&pipe).buf.extend([2, 0xca, 0xfe, 1, 0xab, 3, 0x01]);
+
+        assert_eq!(take_frames(&pipe), vec![vec![2, 0xca, 0xfe], vec![1, 0xab]]);
+        assert_eq!(lock(&pipe).buf, [3, 0x01]);
+    }
+}
@@ -0,0 +1,1679 @@
+//! A persistent implementation of the `Store` trait built on the sled
+//! embedded database.
+//!
+//! Each index of the store is kept in a separate sled tree. Keys which begin
+//! with a channel name are prefixed with the length of the name so that a
+//! prefix scan for one channel never matches another. Timestamps are encoded
+//! as big-endian bytes so that the lexicographic ordering of keys matches the
+//! chronological ordering of posts.
+//!
+//! A store opened with a passphrase encrypts every stored value, including
+//! the keypairs, and replaces channel names and public keys in keys with
+//! blinded hashes. Timestamps and post hashes remain in plaintext so that
+//! range queries and lookups by hash continue to work.
+//!
+//! The posts of each channel are kept in a tree of their own, named after
+//! the (possibly blinded) channel key, so that the posts of busy channels do
+//! not fragment those of quiet ones and a channel is dropped by dropping its
+//! tree.
+//!
+//! Sled transactions cannot span the asynchronous methods of the `Store`
+//! trait, so a batch of writes is instead marked in the metadata tree while
+//! it is open. Post payloads are stored before their indexes, so a store
+//! reopened with the mark still set rebuilds its indexes from the stored
+//! payloads. A batch which is rolled back erases the posts whose payloads it
+//! stored.
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use async_std::{stream, task};
+use cable::{
+    crypto, error::CableErrorKind, post::Post, Avatar, Channel, ChannelOptions, Error, Hash,
+    Nickname, Payload, ReqId, Timestamp, Topic,
+};
+use desert::{FromBytes, ToBytes};
+use log::error;
+use sled::{Db, IVec, Tree};
+
+use crate::{
+    batch::BatchLock,
+    encryption::Cipher,
+    filter::KnownHashes,
+    metrics::{CompactionStats, StoreMetrics},
+    migration::{migrate, Migration},
+    store::{unknown_identity, Keypair, PageCursor, PostPage, PublicKey, Store},
+    stream::{EventStream, HashStream, LiveStreams, PostStream, StoreEvent},
+};
+
+/// The key under which the keypair of the active identity is stored in the
+/// metadata tree.
+const KEYPAIR_KEY: &[u8] = b"keypair";
+
+/// The key under which the key derivation salt of an encrypted store is
+/// stored in the metadata tree.
+const SALT_KEY: &[u8] = b"salt";
+
+/// The key under which a known value, encrypted with the store key, is
+/// stored in the metadata tree of an encrypted store.
+const KEY_CHECK_KEY: &[u8] = b"key_check";
+
+/// The plaintext of the key check value.
+const KEY_CHECK: &[u8] = b"cable";
+
+/// The key under which the schema version is stored in the metadata tree.
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// The key which is set in the metadata tree while a batch of writes is
+/// open.
+const BATCH_KEY: &[u8] = b"batch";
+
+/// The schema migrations of the store, applied in order. Stores written
+/// before the schema version was recorded are at version 0.
+const MIGRATIONS: &[Migration<SledStore>] = &[
+    SledStore::store_index_values,
+    SledStore::store_active_identity,
+    SledStore::index_channel_heads,
+    SledStore::index_author_posts,
+    SledStore::partition_posts,
+];
+
+/// The prefix of the names of the trees holding the posts of each channel.
+const POSTS_TREE_PREFIX: &[u8] = b"posts/";
+
+/// Log the error of a failed database operation, returning the value of a
+/// successful operation.
+fn log_err<T>(res: sled::Result<T>) -> Option<T> {
+    res.map_err(|err| error!("Sled store operation failed: {}", err))
+        .ok()
+}
+
+/// Concatenate the given key prefix and suffix.
+fn join_key(prefix: &[u8], suffix: &[u8]) -> Vec<u8> {
+    let mut key = prefix.to_vec();
+    key.extend_from_slice(suffix);
+    key
+}
+
+/// Return the final `N` bytes of the given key.
+fn key_suffix<const N: usize>(key: &[u8]) -> [u8; N] {
+    key[key.len() - N..].try_into().unwrap()
+}
+
+/// Split a stored value into a leading hash and the UTF-8 string which
+/// follows it.
+fn decode_hash_and_string(value: &[u8]) -> Option<(Hash, String)> {
+    if value.len() < 32 {
+        return None;
+    }
+    let hash = value[..32].try_into().ok()?;
+    let string = String::from_utf8(value[32..].to_vec()).ok()?;
+
+    Some((hash, string))
+}
+
+/// Encode the given hash and string as a stored value.
+fn encode_hash_and_string(hash: &Hash, string: &str) -> Vec<u8> {
+    join_key(hash, string.as_bytes())
+}
+
+/// Split a stored keypair value into a keypair.
+fn decode_keypair(value: &[u8]) -> Option<Keypair> {
+    Some((
+        value.get(..32)?.try_into().ok()?,
+        value.get(32..)?.try_into().ok()?,
+    ))
+}
+
+/// Return the name of the tree holding the posts with the given key prefix.
+fn posts_tree_name(prefix: &[u8]) -> Vec<u8> {
+    join_key(POSTS_TREE_PREFIX, hex::encode(prefix).as_bytes())
+}
+
+/// Open the trees of the given database holding the posts of each channel,
+/// keyed by the key prefix of their posts.
+fn open_posts_trees(db: &Db) -> Result<HashMap<Vec<u8>, Tree>, Error> {
+    let mut trees = HashMap::new();
+    for name in db.tree_names() {
+        if let Some(prefix) = name.strip_prefix(POSTS_TREE_PREFIX) {
+            if let Ok(prefix) = hex::decode(prefix) {
+                trees.insert(prefix, db.open_tree(&name)?);
+            }
+        }
+    }
+
+    Ok(trees)
+}
+
+/// Return an error with the given context.
+fn store_error<T>(context: &str) -> Result<T, Error> {
+    CableErrorKind::StoreFailed {
+        context: context.to_string(),
+    }
+    .raise()
+}
+
+#[derive(Clone)]
+/// A persistent store containing a keypair and post data, backed by sled.
+pub struct SledStore {
+    /// The underlying database.
+    db: Db,
+    /// Store metadata, including the keypair of the active identity.
+    meta: Tree,
+    /// The keypairs of all identities, keyed by public key.
+    identities: Tree,
+    /// The names of all channels in the store, keyed by channel.
+    channels: Tree,
+    /// The names of all channels archived by the local user, keyed by
+    /// channel.
+    archived_channels: Tree,
+    /// The public keys of all members, keyed by channel and public key.
+    channel_members: Tree,
+    /// The public keys of all ex-members, keyed by channel and public key.
+    ex_channel_members: Tree,
+    /// The hash of the latest `post/join` or `post/leave` post for each known
+    /// peer, keyed by channel and public key.
+    channel_membership: Tree,
+    /// The hash and topic of each `post/topic` post, keyed by channel and
+    /// timestamp.
+    channel_topics: Tree,
+    /// The hashes of all known `post/delete` posts, keyed by public key and
+    /// hash.
+    delete_hashes: Tree,
+    /// The hashes of all known `post/info` posts, keyed by public key and
+    /// hash.
+    info_hashes: Tree,
+    /// The hash and nickname of each `post/info` name, keyed by public key
+    /// and timestamp.
+    peer_names: Tree,
+    /// The hash and avatar of each `post/info` avatar, keyed by public key
+    /// and timestamp.
+    peer_avatars: Tree,
+    /// The hashes of the current heads of each channel, as keys (channel
+    /// and hash) with empty values.
+    channel_heads: Tree,
+    /// The links between the posts of each channel, as keys (channel,
+    /// linked hash and linking hash) with empty values.
+    post_links: Tree,
+    /// The trees holding the encoded posts of each channel, keyed by the key
+    /// prefix of the channel (see `post_channel_key()`). The posts of each
+    /// tree are keyed by channel, timestamp and hash.
+    posts_trees: Arc<RwLock<HashMap<Vec<u8>, Tree>>>,
+    /// The key of each post in the tree of its channel, keyed by hash.
+    post_keys: Tree,
+    /// Binary payloads for all posts in the store, keyed by the post hash.
+    post_payloads: Tree,
+    /// The hashes of all deleted posts, as keys with empty values.
+    tombstones: Tree,
+    /// Encoded outbound requests of local origin, keyed by request ID.
+    outbound_requests: Tree,
+    /// The timestamp and hash of the last read post of each channel, keyed
+    /// by channel.
+    last_read: Tree,
+    /// The timestamp and channel of each post mentioning the local user,
+    /// keyed by hash.
+    mentions: Tree,
+    /// The timestamp and parent hash of each reply, keyed by hash.
+    replies: Tree,
+    /// The timestamp and target hash of each reaction post, keyed by hash.
+    #[cfg(feature = "reactions")]
+    reactions: Tree,
+    /// Binary blobs, keyed by hash.
+    #[cfg(feature = "blobs")]
+    blobs: Tree,
+    /// The author and channel of each archive post, keyed by hash.
+    #[cfg(feature = "archive-posts")]
+    channel_archives: Tree,
+    /// The timestamp and hash of each post, as keys (public key of the
+    /// author, timestamp and hash) with empty values.
+    author_posts: Tree,
+    /// A filter of the hashes of all post payloads and tombstones, used to
+    /// answer `want()` for unknown hashes without reading the database.
+    known_hashes: KnownHashes,
+    /// Held for the duration of a batch of writes.
+    batch: BatchLock,
+    /// The hashes of the post payloads stored while a batch is open, erased
+    /// if the batch is rolled back.
+    batch_payloads: Arc<Mutex<Option<Vec<Hash>>>>,
+    /// All active live streams, indexed by channel.
+    live_streams: LiveStreams,
+    /// The cipher used to encrypt stored data, if the store is encrypted.
+    cipher: Option<Arc<Cipher>>,
+    /// Whether the store refuses to insert posts.
+    read_only: bool,
+}
+
+impl SledStore {
+    /// Open the store located at the given path, creating it if it does not
+    /// yet exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_db(sled::open(path)?)
+    }
+
+    /// Open the encrypted store located at the given path, creating it if it
+    /// does not yet exist.
+    ///
+    /// The encryption key is derived from the given passphrase. An error is
+    /// returned if the passphrase is incorrect or if the existing store is
+    /// not encrypted.
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self, Error> {
+        Self::from_db_encrypted(sled::open(path)?, passphrase)
+    }
+
+    /// Open the existing store located at the given path read-only.
+    ///
+    /// The store serves its posts but refuses to insert new ones, and is
+    /// neither migrated nor recovered from an interrupted batch. An error is
+    /// returned if the store does not exist, is encrypted or is not at the
+    /// latest schema version. Sled has no read-only mode of its own, so the
+    /// database is still locked against other processes while it is open.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = sled::open(path)?;
+        if db.open_tree("meta")?.contains_key(SALT_KEY)? {
+            return store_error("encrypted sled store cannot be opened read-only");
+        }
+
+        Self::init(db, None, true)
+    }
+
+    /// Open a temporary store which is removed from disk when dropped.
+    pub fn temporary() -> Result<Self, Error> {
+        Self::from_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    /// Create a store from an opened sled database.
+    ///
+    /// A new keypair is generated and persisted if the database does not
+    /// yet contain one. An error is returned if the database is encrypted.
+    pub fn from_db(db: Db) -> Result<Self, Error> {
+        if db.open_tree("meta")?.contains_key(SALT_KEY)? {
+            return store_error("sled store is encrypted and requires a passphrase");
+        }
+
+        Self::init(db, None, false)
+    }
+
+    /// Create an encrypted store from an opened sled database, deriving the
+    /// encryption key from the given passphrase.
+    ///
+    /// A new database is initialised with a random salt for key derivation.
+    pub fn from_db_encrypted(db: Db, passphrase: &str) -> Result<Self, Error> {
+        let meta = db.open_tree("meta")?;
+
+        let cipher = match meta.get(SALT_KEY)? {
+            Some(salt) => {
+                let cipher = Cipher::from_passphrase(passphrase.as_bytes(), &salt)?;
+                let key_check = meta.get(KEY_CHECK_KEY)?;
+                match key_check.map(|value| cipher.open(&value)) {
+                    Some(Ok(value)) if value == KEY_CHECK => cipher,
+                    _ => return store_error("incorrect passphrase for encrypted sled store"),
+                }
+            }
+            None => {
+                if meta.contains_key(KEYPAIR_KEY)? {
+                    return store_error("sled store is not encrypted");
+                }
+
+                let salt = Cipher::generate_salt();
+                let cipher = Cipher::from_passphrase(passphrase.as_bytes(), &salt)?;
+                meta.insert(KEY_CHECK_KEY, cipher.seal(KEY_CHECK))?;
+                meta.insert(SALT_KEY, &salt)?;
+                cipher
+            }
+        };
+
+        Self::init(db, Some(Arc::new(cipher)), false)
+    }
+
+    /// Open the trees of the given database, generating a keypair if the
+    /// database does not yet contain one and the store is not read-only.
+    fn init(db: Db, cipher: Option<Arc<Cipher>>, read_only: bool) -> Result<Self, Error> {
+        let store = SledStore {
+            meta: db.open_tree("meta")?,
+            identities: db.open_tree("identities")?,
+            channels: db.open_tree("channels")?,
+            archived_channels: db.open_tree("archived_channels")?,
+            channel_members: db.open_tree("channel_members")?,
+            ex_channel_members: db.open_tree("ex_channel_members")?,
+            channel_membership: db.open_tree("channel_membership")?,
+            channel_topics: db.open_tree("channel_topics")?,
+            delete_hashes: db.open_tree("delete_hashes")?,
+            info_hashes: db.open_tree("info_hashes")?,
+            peer_names: db.open_tree("peer_names")?,
+            peer_avatars: db.open_tree("peer_avatars")?,
+            channel_heads: db.open_tree("channel_heads")?,
+            post_links: db.open_tree("post_links")?,
+            posts_trees: Arc::new(RwLock::new(open_posts_trees(&db)?)),
+            post_keys: db.open_tree("post_keys")?,
+            post_payloads: db.open_tree("post_payloads")?,
+            tombstones: db.open_tree("tombstones")?,
+            outbound_requests: db.open_tree("outbound_requests")?,
+            last_read: db.open_tree("last_read")?,
+            mentions: db.open_tree("mentions")?,
+            replies: db.open_tree("replies")?,
+            #[cfg(feature = "reactions")]
+            reactions: db.open_tree("reactions")?,
+            #[cfg(feature = "archive-posts")]
+            channel_archives: db.open_tree("channel_archives")?,
+            #[cfg(feature = "blobs")]
+            blobs: db.open_tree("blobs")?,
+            author_posts: db.open_tree("author_posts")?,
+            known_hashes: KnownHashes::new(Vec::new()),
+            batch: BatchLock::default(),
+            batch_payloads: Arc::new(Mutex::new(None)),
+            live_streams: LiveStreams::default(),
+            cipher,
+            read_only,
+            db,
+        };
+
+        if read_only {
+            if store.meta.get(KEYPAIR_KEY)?.is_none() {
+                return store_error("no sled store to open read-only");
+            }
+            if store.schema_version()? != MIGRATIONS.len() as u32 {
+                return store_error("sled store must be migrated before it is opened read-only");
+            }
+        } else if store.meta.get(KEYPAIR_KEY)?.is_none() {
+            // A new store is created at the latest schema version.
+            store.set_schema_version(MIGRATIONS.len() as u32)?;
+
+            let keypair = crypto::generate_keypair();
+            store.insert_keypair(&keypair)?;
+            store
+                .meta
+                .insert(KEYPAIR_KEY, store.seal(&join_key(&keypair.0, &keypair.1)))?;
+        } else {
+            migrate(
+                &store,
+                store.schema_version()?,
+                MIGRATIONS,
+                Self::set_schema_version,
+            )?;
+        }
+
+        store.known_hashes.rebuild(store.known_hash_list()?);
+
+        if !read_only && store.meta.contains_key(BATCH_KEY)? {
+            task::block_on(store.clone().reindex_posts())?;
+        }
+
+        Ok(store)
+    }
+
+    /// Rebuild the indexes of the store from the stored post payloads,
+    /// completing the indexing of the posts of an interrupted batch.
+    ///
+    /// Indexing a post is idempotent, so the posts which were already
+    /// indexed are left unchanged.
+    async fn reindex_posts(mut self) -> Result<(), Error> {
+        for hash in self.get_post_payload_hashes().await {
+            let Some(payload) = self.get_post_payload(&hash).await else {
+                continue;
+            };
+            if let Ok((_s, post)) = Post::from_bytes(&payload) {
+                self.insert_post(&post).await?;
+            }
+        }
+        self.meta.remove(BATCH_KEY)?;
+
+        Ok(())
+    }
+
+    /// List the hashes of all post payloads and tombstones in the store.
+    fn known_hash_list(&self) -> sled::Result<Vec<Hash>> {
+        let mut hashes = Vec::new();
+        for key in self
+            .post_payloads
+            .iter()
+            .keys()
+            .chain(self.tombstones.iter().keys())
+        {
+            if let Ok(hash) = key?.as_ref().try_into() {
+                hashes.push(hash);
+            }
+        }
+
+        Ok(hashes)
+    }
+
+    /// Add the given hash to the filter of known hashes, rebuilding the
+    /// filter from the database once it has outgrown its capacity.
+    fn remember_hash(&self, hash: &Hash) {
+        if self.known_hashes.insert(hash) {
+            if let Some(hashes) = log_err(self.known_hash_list()) {
+                self.known_hashes.rebuild(hashes);
+            }
+        }
+    }
+
+    /// Retrieve the schema version of the store.
+    pub fn schema_version(&self) -> Result<u32, Error> {
+        match self.meta.get(SCHEMA_VERSION_KEY)? {
+            Some(version) => match version.as_ref().try_into() {
+                Ok(version) => Ok(u32::from_be_bytes(version)),
+                Err(_) => store_error("failed to decode sled store schema version"),
+            },
+            None => Ok(0),
+        }
+    }
+
+    /// Record the given schema version of the store.
+    fn set_schema_version(&self, version: u32) -> Result<(), Error> {
+        self.meta
+            .insert(SCHEMA_VERSION_KEY, &version.to_be_bytes())?;
+
+        Ok(())
+    }
+
+    /// Migrate the store from schema version 0 to 1.
+    ///
+    /// Version 0 keyed the `channels` tree by the raw channel name and
+    /// stored empty values in the channel member trees. Channel names and
+    /// member public keys are now stored as values, allowing the keys to be
+    /// blinded in encrypted stores.
+    fn store_index_values(&self) -> Result<(), Error> {
+        for entry in self.channels.iter() {
+            let (key, value) = entry?;
+            if value.is_empty() {
+                let channel = String::from_utf8(key.to_vec())?;
+                self.channels
+                    .insert(self.channel_key(&channel), self.seal(channel.as_bytes()))?;
+                self.channels.remove(key)?;
+            }
+        }
+
+        for tree in [&self.channel_members, &self.ex_channel_members] {
+            for entry in tree.iter() {
+                let (key, value) = entry?;
+                if value.is_empty() {
+                    let public_key: PublicKey = key_suffix(&key);
+                    tree.insert(key, self.seal(&public_key))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Migrate the store from schema version 1 to 2.
+    ///
+    /// Version 1 held a single keypair in the metadata tree. That keypair
+    /// is now also recorded in the `identities` tree, alongside any other
+    /// identities of the store.
+    fn store_active_identity(&self) -> Result<(), Error> {
+        let keypair = self
+            .meta
+            .get(KEYPAIR_KEY)?
+            .and_then(|value| self.open_value(&value))
+            .and_then(|value| decode_keypair(&value));
+
+        match keypair {
+            Some(keypair) => self.insert_keypair(&keypair),
+            None => store_error("failed to decode sled store keypair"),
+        }
+    }
+
+    /// Migrate the store from schema version 2 to 3.
+    ///
+    /// Version 2 derived the latest hashes of a channel from the timestamps
+    /// of its posts. The heads of each channel are now tracked according to
+    /// the links of its posts, so the heads are indexed from the stored
+    /// posts.
+    fn index_channel_heads(&self) -> Result<(), Error> {
+        for entry in self.post_payloads.iter() {
+            let (key, value) = entry?;
+            let Some(payload) = self.open_value(&value) else {
+                continue;
+            };
+            let Ok((_s, post)) = Post::from_bytes(&payload) else {
+                continue;
+            };
+            if let Some(channel) = post.get_channel() {
+                let hash: Hash = key_suffix(&key);
+                for link in &post.header.links {
+                    self.post_links
+                        .insert(self.post_link_key(channel, link, &hash), &[])?;
+                    self.channel_heads
+                        .remove(join_key(&self.channel_key(channel), link))?;
+                }
+                let linked = self
+                    .post_links
+                    .scan_prefix(join_key(&self.channel_key(channel), &hash))
+                    .next()
+                    .is_some();
+                if !linked {
+                    self.channel_heads
+                        .insert(join_key(&self.channel_key(channel), &hash), &[])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Migrate the store from schema version 3 to 4.
+    ///
+    /// Version 3 did not index posts by author, so the author index is
+    /// populated from the stored posts.
+    fn index_author_posts(&self) -> Result<(), Error> {
+        for entry in self.post_payloads.iter() {
+            let (key, value) = entry?;
+            let Some(payload) = self.open_value(&value) else {
+                continue;
+            };
+            let Ok((_s, post)) = Post::from_bytes(&payload) else {
+                continue;
+            };
+            let hash: Hash = key_suffix(&key);
+            self.author_posts.insert(
+                self.author_post_key(&post.get_public_key(), post.get_timestamp(), &hash),
+                &[],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Migrate the store from schema version 4 to 5.
+    ///
+    /// Version 4 kept the posts of all channels in a single `posts` tree.
+    /// The posts of each channel are now kept in a tree of their own, so the
+    /// entries of the single tree are moved to the tree of their channel.
+    fn partition_posts(&self) -> Result<(), Error> {
+        let posts = self.db.open_tree("posts")?;
+        for entry in posts.iter() {
+            let (key, value) = entry?;
+            if key.len() < 40 {
+                continue;
+            }
+            self.create_posts_tree(&key[..key.len() - 40])?
+                .insert(key, value)?;
+        }
+        self.db.drop_tree("posts")?;
+
+        Ok(())
+    }
+
+    /// Return the tree holding the posts with the given key prefix, as
+    /// returned by `post_channel_key()`, if any posts with the prefix have
+    /// been stored.
+    ///
+    /// Trees are only created by `create_posts_tree()`, so that queries for
+    /// unknown channels do not create empty trees.
+    fn posts_tree(&self, prefix: &[u8]) -> Option<Tree> {
+        self.posts_trees.read().unwrap().get(prefix).cloned()
+    }
+
+    /// Return the tree holding the posts with the given key prefix, creating
+    /// it if it does not yet exist.
+    fn create_posts_tree(&self, prefix: &[u8]) -> Result<Tree, Error> {
+        if let Some(tree) = self.posts_tree(prefix) {
+            return Ok(tree);
+        }
+
+        let tree = self.db.open_tree(posts_tree_name(prefix))?;
+        self.posts_trees
+            .write()
+            .unwrap()
+            .insert(prefix.to_vec(), tree.clone());
+
+        Ok(tree)
+    }
+
+    /// Return the tree holding the posts of the given optional channel, if
+    /// any have been stored.
+    fn channel_posts_tree(&self, channel: Option<&Channel>) -> Option<Tree> {
+        self.posts_tree(&self.post_channel_key(channel))
+    }
+
+    /// Add the given keypair to the `identities` tree.
+    fn insert_keypair(&self, keypair: &Keypair) -> Result<(), Error> {
+        let (pk, sk) = keypair;
+        self.identities
+            .insert(self.public_key_key(pk), self.seal(&join_key(pk, sk)))?;
+
+        Ok(())
+    }
+
+    /// Write all pending changes to disk.
+    pub async fn flush(&self) -> Result<(), Error> {
+        self.db.flush_async().await?;
+
+        Ok(())
+    }
+
+    /// Encrypt the given value if the store is encrypted.
+    fn seal(&self, value: &[u8]) -> Vec<u8> {
+        match &self.cipher {
+            Some(cipher) => cipher.seal(value),
+            None => value.to_vec(),
+        }
+    }
+
+    /// Decrypt the given stored value if the store is encrypted, logging
+    /// the error of a value which fails to decrypt.
+    fn open_value(&self, value: &[u8]) -> Option<Vec<u8>> {
+        match &self.cipher {
+            Some(cipher) => cipher
+                .open(value)
+                .map_err(|err| error!("Sled store decryption failed: {}", err))
+                .ok(),
+            None => Some(value.to_vec()),
+        }
+    }
+
+    /// Encode the given channel name as a key prefix.
+    ///
+    /// The name is prefixed with its length, or blinded if the store is
+    /// encrypted.
+    fn channel_key(&self, channel: &str) -> Vec<u8> {
+        match &self.cipher {
+            Some(cipher) => cipher.blind(channel.as_bytes()).to_vec(),
+            None => {
+                let mut key = (channel.len() as u32).to_be_bytes().to_vec();
+                key.extend_from_slice(channel.as_bytes());
+                key
+            }
+        }
+    }
+
+    /// Encode the given channel, linked post hash and linking post hash as a
+    /// key of the `post_links` tree.
+    fn post_link_key(&self, channel: &Channel, link: &Hash, hash: &Hash) -> Vec<u8> {
+        join_key(&join_key(&self.channel_key(channel), link), hash)
+    }
+
+    /// Encode the given public key as a key prefix, blinding it if the store
+    /// is encrypted.
+    fn public_key_key(&self, public_key: &PublicKey) -> Vec<u8> {
+        match &self.cipher {
+            Some(cipher) => cipher.blind(public_key).to_vec(),
+            None => public_key.to_vec(),
+        }
+    }
+
+    /// Encode the given public key, timestamp and hash as a key of the
+    /// `author_posts` tree.
+    fn author_post_key(
+        &self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) -> Vec<u8> {
+        join_key(
+            &join_key(&self.public_key_key(public_key), &timestamp.to_be_bytes()),
+            hash,
+        )
+    }
+
+    /// Encode the given channel and public key as a key.
+    fn channel_public_key_key(&self, channel: &Channel, public_key: &PublicKey) -> Vec<u8> {
+        join_key(&self.channel_key(channel), &self.public_key_key(public_key))
+    }
+
+    /// Encode the given optional channel as the prefix of a key in the posts
+    /// tree. Posts without a channel share a single prefix.
+    fn post_channel_key(&self, channel: Option<&Channel>) -> Vec<u8> {
+        match channel {
+            Some(channel) => join_key(&[1], &self.channel_key(channel)),
+            None => vec![0],
+        }
+    }
+
+    /// Collect the public keys stored under the given channel in a tree
+    /// keyed by channel and public key.
+    fn channel_public_keys(&self, tree: &Tree, channel: &Channel) -> Option<Vec<PublicKey>> {
+        let public_keys: Vec<PublicKey> = tree
+            .scan_prefix(self.channel_key(channel))
+            .values()
+            .filter_map(log_err)
+            .filter_map(|value| self.open_value(&value)?.try_into().ok())
+            .collect();
+
+        if public_keys.is_empty() {
+            None
+        } else {
+            Some(public_keys)
+        }
+    }
+
+    /// Collect the hashes stored under the given public key in a tree keyed
+    /// by public key and hash.
+    fn public_key_hashes(&self, tree: &Tree, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        let hashes: Vec<Hash> = tree
+            .scan_prefix(self.public_key_key(public_key))
+            .keys()
+            .filter_map(|key| log_err(key).map(|key| key_suffix(&key)))
+            .collect();
+
+        if hashes.is_empty() {
+            None
+        } else {
+            Some(hashes)
+        }
+    }
+
+    /// Remove every entry of the given tree whose value begins with the
+    /// given hash.
+    fn remove_by_value_hash(&self, tree: &Tree, hash: &Hash) {
+        for (key, value) in tree.iter().filter_map(log_err) {
+            if self
+                .open_value(&value)
+                .is_some_and(|value| value.starts_with(hash))
+            {
+                log_err(tree.remove(key));
+            }
+        }
+    }
+
+    /// Return the range of keys in the posts tree matching the given channel
+    /// options.
+    fn post_range(&self, opts: &ChannelOptions) -> (Vec<u8>, Vec<u8>) {
+        let prefix = self.post_channel_key(Some(&opts.channel));
+        let start = join_key(&prefix, &opts.time_start.to_be_bytes());
+        let end = match opts.time_end {
+            // An end time of 0 denotes no upper bound; the end of the range
+            // is the first key beyond the channel prefix.
+            0 => join_key(&prefix, &[0xff; 41]),
+            end => join_key(&prefix, &end.to_be_bytes()),
+        };
+
+        (start, end)
+    }
+
+    /// Decode the stored post values of the given range of the posts tree.
+    fn decode_posts(
+        &self,
+        values: impl Iterator<Item = sled::Result<IVec>>,
+    ) -> Vec<Result<Post, Error>> {
+        values.map(|value| self.decode_post(&value?)).collect()
+    }
+
+    /// Decode a stored post value.
+    fn decode_post(&self, value: &[u8]) -> Result<Post, Error> {
+        let (_s, post) = match &self.cipher {
+            Some(cipher) => Post::from_bytes(&cipher.open(value)?)?,
+            None => Post::from_bytes(value)?,
+        };
+
+        Ok(post)
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for SledStore {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        log_err(self.meta.get(KEYPAIR_KEY))
+            .flatten()
+            .and_then(|keypair| self.open_value(&keypair))
+            .and_then(|keypair| decode_keypair(&keypair))
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        let (pk, sk) = keypair;
+        if let Err(err) = self.insert_keypair(&keypair) {
+            error!("Sled store operation failed: {}", err);
+        }
+        log_err(
+            self.meta
+                .insert(KEYPAIR_KEY, self.seal(&join_key(&pk, &sk))),
+        );
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        let mut public_keys: Vec<PublicKey> = self
+            .identities
+            .iter()
+            .values()
+            .filter_map(log_err)
+            .filter_map(|value| self.open_value(&value))
+            .filter_map(|value| decode_keypair(&value))
+            .map(|(pk, _sk)| pk)
+            .collect();
+        public_keys.sort();
+
+        public_keys
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        if let Err(err) = self.insert_keypair(&keypair) {
+            error!("Sled store operation failed: {}", err);
+        }
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        match self.identities.get(self.public_key_key(public_key))? {
+            Some(value) => {
+                self.meta.insert(KEYPAIR_KEY, value)?;
+                Ok(())
+            }
+            None => unknown_identity(public_key),
+        }
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        let mut channels: Vec<Channel> = self
+            .channels
+            .iter()
+            .values()
+            .filter_map(log_err)
+            .filter_map(|value| String::from_utf8(self.open_value(&value)?).ok())
+            .collect();
+        channels.sort();
+
+        if channels.is_empty() {
+            None
+        } else {
+            Some(channels)
+        }
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        let key = self.channel_key(channel);
+        log_err(self.channels.insert(key, self.seal(channel.as_bytes())));
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        let mut channels: Vec<Channel> = self
+            .archived_channels
+            .iter()
+            .values()
+            .filter_map(log_err)
+            .filter_map(|value| String::from_utf8(self.open_value(&value)?).ok())
+            .collect();
+        channels.sort();
+
+        channels
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        log_err(
+            self.archived_channels
+                .contains_key(self.channel_key(channel)),
+        )
+        .unwrap_or(false)
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        let key = self.channel_key(channel);
+        if archived {
+            log_err(
+                self.archived_channels
+                    .insert(key, self.seal(channel.as_bytes())),
+            );
+        } else {
+            log_err(self.archived_channels.remove(key));
+        }
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        let key = self.channel_key(channel);
+        log_err(self.channels.remove(&key));
+        log_err(self.archived_channels.remove(&key));
+        log_err(self.last_read.remove(channel.as_bytes()));
+
+        // Drop the tree holding the posts of the channel, if any remain.
+        let prefix = self.post_channel_key(Some(channel));
+        if self.posts_trees.write().unwrap().remove(&prefix).is_some() {
+            log_err(self.db.drop_tree(posts_tree_name(&prefix)));
+        }
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        self.channel_public_keys(&self.channel_members, channel)
+    }
+
+    async fn insert_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.channel_members.insert(key, self.seal(public_key)));
+    }
+
+    async fn is_channel_member(&self, channel: &Channel, public_key: &PublicKey) -> bool {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.channel_members.contains_key(key)).unwrap_or(false)
+    }
+
+    async fn remove_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.channel_members.remove(key));
+    }
+
+    async fn get_channel_membership_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let hashes: Vec<Hash> = self
+            .channel_membership
+            .scan_prefix(self.channel_key(channel))
+            .values()
+            .filter_map(log_err)
+            .filter_map(|value| self.open_value(&value)?.try_into().ok())
+            .collect();
+
+        if hashes.is_empty() {
+            None
+        } else {
+            Some(hashes)
+        }
+    }
+
+    async fn remove_channel_membership_hash(&mut self, hash: &Hash) {
+        self.remove_by_value_hash(&self.channel_membership, hash);
+    }
+
+    async fn update_channel_membership_hashes(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.channel_membership.insert(key, self.seal(hash)));
+    }
+
+    async fn get_ex_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        self.channel_public_keys(&self.ex_channel_members, channel)
+    }
+
+    async fn insert_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.ex_channel_members.insert(key, self.seal(public_key)));
+    }
+
+    async fn remove_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let key = self.channel_public_key_key(channel, public_key);
+        log_err(self.ex_channel_members.remove(key));
+    }
+
+    async fn get_channel_topic_and_hash(&self, channel: &Channel) -> Option<(Topic, Hash)> {
+        // The last entry for the channel has the largest timestamp.
+        log_err(
+            self.channel_topics
+                .scan_prefix(self.channel_key(channel))
+                .last()?,
+        )
+        .and_then(|(_key, value)| decode_hash_and_string(&self.open_value(&value)?))
+        .map(|(hash, topic)| (topic, hash))
+    }
+
+    async fn insert_channel_topic(
+        &mut self,
+        channel: &Channel,
+        topic: &Topic,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let key = join_key(&self.channel_key(channel), &timestamp.to_be_bytes());
+        let value = self.seal(&encode_hash_and_string(hash, topic));
+        log_err(self.channel_topics.insert(key, value));
+    }
+
+    async fn remove_channel_topic(&mut self, hash: &Hash) {
+        self.remove_by_value_hash(&self.channel_topics, hash);
+    }
+
+    async fn get_delete_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        self.public_key_hashes(&self.delete_hashes, public_key)
+    }
+
+    async fn insert_delete_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let key = join_key(&self.public_key_key(public_key), hash);
+        log_err(self.delete_hashes.insert(key, &[]));
+    }
+
+    async fn get_info_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        self.public_key_hashes(&self.info_hashes, public_key)
+    }
+
+    async fn insert_info_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let key = join_key(&self.public_key_key(public_key), hash);
+        log_err(self.info_hashes.insert(key, &[]));
+    }
+
+    async fn remove_info_hash(&mut self, hash: &Hash) {
+        for key in self.info_hashes.iter().keys().filter_map(log_err) {
+            if key.ends_with(hash) {
+                log_err(self.info_hashes.remove(key));
+            }
+        }
+    }
+
+    async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let heads: Vec<Hash> = self
+            .channel_heads
+            .scan_prefix(self.channel_key(channel))
+            .keys()
+            .filter_map(log_err)
+            .map(|key| key_suffix(&key))
+            .collect();
+
+        if heads.is_empty() {
+            None
+        } else {
+            Some(heads)
+        }
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        log_err(
+            self.channel_heads
+                .insert(join_key(&self.channel_key(channel), hash), &[]),
+        );
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        log_err(
+            self.channel_heads
+                .remove(join_key(&self.channel_key(channel), hash)),
+        );
+    }
+
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        log_err(
+            self.post_links
+                .insert(self.post_link_key(channel, link, hash), &[]),
+        );
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        log_err(
+            self.post_links
+                .remove(self.post_link_key(channel, link, hash)),
+        );
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        self.post_links
+            .scan_prefix(join_key(&self.channel_key(channel), hash))
+            .next()
+            .is_some()
+    }
+
+    async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
+        // The last entry for the public key has the largest timestamp.
+        log_err(
+            self.peer_names
+                .scan_prefix(self.public_key_key(public_key))
+                .last()?,
+        )
+        .and_then(|(_key, value)| decode_hash_and_string(&self.open_value(&value)?))
+        .map(|(hash, name)| (name, hash))
+    }
+
+    async fn insert_peer_name(
+        &mut self,
+        public_key: &PublicKey,
+        name: &Nickname,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let key = join_key(&self.public_key_key(public_key), &timestamp.to_be_bytes());
+        let value = self.seal(&encode_hash_and_string(hash, name));
+        log_err(self.peer_names.insert(key, value));
+    }
+
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        self.remove_by_value_hash(&self.peer_names, hash);
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        // The last entry for the public key has the largest timestamp.
+        log_err(
+            self.peer_avatars
+                .scan_prefix(self.public_key_key(public_key))
+                .last()?,
+        )
+        .and_then(|(_key, value)| decode_hash_and_string(&self.open_value(&value)?))
+        .and_then(|(hash, avatar)| Some((Avatar::parse(&avatar).ok()?, hash)))
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let key = join_key(&self.public_key_key(public_key), &timestamp.to_be_bytes());
+        let value = self.seal(&encode_hash_and_string(hash, &avatar.to_string()));
+        log_err(self.peer_avatars.insert(key, value));
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        self.remove_by_value_hash(&self.peer_avatars, hash);
+    }
+
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
+        let (start, end) = self.post_range(opts);
+
+        // Retrieve all posts matching the given channel options.
+        let mut posts = match self.channel_posts_tree(Some(&opts.channel)) {
+            Some(tree) => self.decode_posts(tree.range(start..end).values()),
+            None => Vec::new(),
+        };
+
+        // Retrieve all posts which do not have a channel field.
+        // For example, `post/info` posts.
+        let non_channel_posts = match self.channel_posts_tree(None) {
+            Some(tree) => self.decode_posts(tree.iter().values()),
+            None => Vec::new(),
+        };
+
+        // Add the non-channel posts to the channel posts.
+        posts.extend(non_channel_posts);
+
+        // Return a post stream.
+        Box::new(stream::from_iter(posts))
+    }
+
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        let prefix = self.post_channel_key(Some(channel));
+        // Keys end with the timestamp and hash, so the keys preceding the
+        // cursor are exactly the posts before it.
+        let end = match before {
+            Some(before) => join_key(
+                &join_key(&prefix, &before.timestamp.to_be_bytes()),
+                &before.hash,
+            ),
+            None => join_key(&prefix, &[0xff; 41]),
+        };
+
+        // Retrieve one post beyond the limit to determine whether another
+        // page exists.
+        let take = if limit == 0 { usize::MAX } else { limit + 1 };
+        let mut posts = Vec::new();
+        let Some(tree) = self.posts_tree(&prefix) else {
+            return Ok(PostPage::from_posts(posts, limit));
+        };
+        for entry in tree.range(prefix.clone()..end).rev().take(take) {
+            let (key, value) = entry?;
+            let timestamp = key[prefix.len()..prefix.len() + 8].try_into()?;
+            let cursor = PageCursor {
+                timestamp: Timestamp::from_be_bytes(timestamp),
+                hash: key_suffix(&key),
+            };
+            posts.push((cursor, self.decode_post(&value)?));
+        }
+
+        Ok(PostPage::from_posts(posts, limit))
+    }
+
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        self.live_streams.watch(channel).await
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        self.live_streams.send(channel, event).await;
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        let (start, end) = self.post_range(opts);
+
+        let hashes = match self.channel_posts_tree(Some(&opts.channel)) {
+            Some(tree) => tree
+                .range(start..end)
+                .keys()
+                .map(|key| Ok(key_suffix(&key?)))
+                .collect::<Vec<Result<Hash, Error>>>(),
+            None => Vec::new(),
+        };
+
+        // Return a hash stream.
+        Box::new(stream::from_iter(hashes))
+    }
+
+    async fn remove_post(&mut self, hash: &Hash) {
+        if let Some(key) = log_err(self.post_keys.remove(hash)).flatten() {
+            if key.len() < 40 {
+                return;
+            }
+            if let Some(tree) = self.posts_tree(&key[..key.len() - 40]) {
+                log_err(tree.remove(key));
+            }
+        }
+    }
+
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    ) {
+        let post_bytes = match post.to_bytes() {
+            Ok(post_bytes) => post_bytes,
+            Err(err) => {
+                error!("Failed to encode post for sled store: {}", err);
+                return;
+            }
+        };
+
+        let prefix = self.post_channel_key(channel.as_ref());
+        let key = join_key(&join_key(&prefix, &timestamp.to_be_bytes()), &hash);
+
+        let tree = match self.create_posts_tree(&prefix) {
+            Ok(tree) => tree,
+            Err(err) => {
+                error!("Failed to open sled posts tree: {}", err);
+                return;
+            }
+        };
+        log_err(tree.insert(&key, self.seal(&post_bytes)));
+        log_err(self.post_keys.insert(hash, key));
+    }
+
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload> {
+        log_err(self.post_payloads.get(hash))
+            .flatten()
+            .and_then(|payload| self.open_value(&payload))
+    }
+
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload> {
+        hashes
+            .iter()
+            .filter_map(|hash| log_err(self.post_payloads.get(hash)).flatten())
+            .filter_map(|payload| self.open_value(&payload))
+            .collect()
+    }
+
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        self.post_payloads
+            .iter()
+            .keys()
+            .filter_map(log_err)
+            .filter_map(|key| key.as_ref().try_into().ok())
+            .collect()
+    }
+
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
+        if let Some(hashes) = self.batch_payloads.lock().unwrap().as_mut() {
+            // Only erase payloads which were stored by the batch.
+            if !log_err(self.post_payloads.contains_key(hash)).unwrap_or(true) {
+                hashes.push(*hash);
+            }
+        }
+        log_err(self.post_payloads.insert(hash, self.seal(&payload)));
+        self.remember_hash(hash);
+    }
+
+    async fn remove_post_payload(&mut self, hash: &Hash) {
+        log_err(self.post_payloads.remove(hash));
+    }
+
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        log_err(self.tombstones.insert(hash, &[]));
+        self.remember_hash(hash);
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        log_err(self.tombstones.contains_key(hash)).unwrap_or(false)
+    }
+
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        let mut mentions: Vec<(Timestamp, Hash)> = self
+            .mentions
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(key, value)| {
+                let value = self.open_value(&value)?;
+                if value.len() < 8 || &value[8..] != channel.as_bytes() {
+                    return None;
+                }
+                let timestamp = Timestamp::from_be_bytes(value[..8].try_into().ok()?);
+
+                Some((timestamp, key.as_ref().try_into().ok()?))
+            })
+            .collect();
+        mentions.sort();
+
+        mentions
+            .into_iter()
+            .map(|(_timestamp, hash)| hash)
+            .collect()
+    }
+
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        let value = join_key(&timestamp.to_be_bytes(), channel.as_bytes());
+        log_err(self.mentions.insert(hash, self.seal(&value)));
+    }
+
+    async fn remove_mention(&mut self, hash: &Hash) {
+        log_err(self.mentions.remove(hash));
+    }
+
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        let mut replies: Vec<(Timestamp, Hash)> = self
+            .replies
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(key, value)| {
+                let value = self.open_value(&value)?;
+                if value.len() != 40 || &value[8..] != parent {
+                    return None;
+                }
+                let timestamp = Timestamp::from_be_bytes(value[..8].try_into().ok()?);
+
+                Some((timestamp, key.as_ref().try_into().ok()?))
+            })
+            .collect();
+        replies.sort();
+
+        replies.into_iter().map(|(_timestamp, hash)| hash).collect()
+    }
+
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let value = join_key(&timestamp.to_be_bytes(), parent);
+        log_err(self.replies.insert(hash, self.seal(&value)));
+    }
+
+    async fn remove_reply(&mut self, hash: &Hash) {
+        log_err(self.replies.remove(hash));
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        let mut reactions: Vec<(Timestamp, Hash)> = self
+            .reactions
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(key, value)| {
+                let value = self.open_value(&value)?;
+                if value.len() != 40 || &value[8..] != target {
+                    return None;
+                }
+                let timestamp = Timestamp::from_be_bytes(value[..8].try_into().ok()?);
+
+                Some((timestamp, key.as_ref().try_into().ok()?))
+            })
+            .collect();
+        reactions.sort();
+
+        reactions
+            .into_iter()
+            .map(|(_timestamp, hash)| hash)
+            .collect()
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let value = join_key(&timestamp.to_be_bytes(), target);
+        log_err(self.reactions.insert(hash, self.seal(&value)));
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        log_err(self.reactions.remove(hash));
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        self.channel_archives
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(key, value)| {
+                let value = self.open_value(&value)?;
+                if value.len() < 32 || &value[32..] != channel.as_bytes() {
+                    return None;
+                }
+
+                Some((value[..32].try_into().ok()?, key.as_ref().try_into().ok()?))
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let value = join_key(public_key, channel.as_bytes());
+        log_err(self.channel_archives.insert(hash, self.seal(&value)));
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        log_err(self.channel_archives.remove(hash));
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        log_err(self.blobs.get(hash))
+            .flatten()
+            .and_then(|blob| self.open_value(&blob))
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        log_err(self.blobs.insert(hash, self.seal(&blob)));
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        log_err(self.blobs.remove(hash));
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        self.blobs
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(_key, blob)| self.open_value(&blob))
+            .map(|blob| blob.len() as u64)
+            .sum()
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        self.author_posts
+            .scan_prefix(self.public_key_key(public_key))
+            .keys()
+            .filter_map(log_err)
+            .filter_map(|key| {
+                let timestamp = key.get(key.len().checked_sub(40)?..key.len() - 32)?;
+
+                Some(PageCursor {
+                    timestamp: Timestamp::from_be_bytes(timestamp.try_into().ok()?),
+                    hash: key_suffix(&key),
+                })
+            })
+            .collect()
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let key = self.author_post_key(public_key, timestamp, hash);
+        log_err(self.author_posts.insert(key, &[]));
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let key = self.author_post_key(public_key, timestamp, hash);
+        log_err(self.author_posts.remove(key));
+    }
+
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        let value = log_err(self.last_read.get(channel.as_bytes())).flatten()?;
+        let value = self.open_value(&value)?;
+        if value.len() != 40 {
+            return None;
+        }
+
+        Some(PageCursor {
+            timestamp: Timestamp::from_be_bytes(value[..8].try_into().ok()?),
+            hash: value[8..].try_into().ok()?,
+        })
+    }
+
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        let value = join_key(&cursor.timestamp.to_be_bytes(), &cursor.hash);
+        log_err(self.last_read.insert(channel.as_bytes(), self.seal(&value)));
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        self.outbound_requests
+            .iter()
+            .filter_map(log_err)
+            .filter_map(|(key, value)| {
+                let req_id = key.as_ref().try_into().ok()?;
+                Some((req_id, self.open_value(&value)?))
+            })
+            .collect()
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        log_err(self.outbound_requests.insert(req_id, self.seal(request)));
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        log_err(self.outbound_requests.remove(req_id));
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        if self.read_only {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        self.batch.begin().await;
+        if let Err(err) = self.meta.insert(BATCH_KEY, &[]) {
+            self.batch.end().await;
+            return Err(err.into());
+        }
+        *self.batch_payloads.lock().unwrap() = Some(Vec::new());
+
+        Ok(())
+    }
+
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        self.batch_payloads.lock().unwrap().take();
+        let res = self.meta.remove(BATCH_KEY);
+        self.batch.end().await;
+        res?;
+
+        Ok(())
+    }
+
+    /// Erase the posts whose payloads were stored by the batch, as sled
+    /// cannot undo the writes themselves.
+    ///
+    /// Posts deleted by the `post/delete` posts of the batch remain deleted.
+    /// The batch stays marked until the posts are erased, so a store closed
+    /// before then rebuilds its indexes when it is reopened.
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        let hashes = self.batch_payloads.lock().unwrap().take();
+        for hash in hashes.unwrap_or_default().iter().rev() {
+            self.delete_post(hash).await;
+        }
+        let res = self.meta.remove(BATCH_KEY);
+        self.batch.end().await;
+        res?;
+
+        Ok(())
+    }
+
+    /// Write all pending changes to disk.
+    ///
+    /// Sled reclaims the space of removed entries by rewriting fragmented
+    /// segments in the background; flushing ensures that the removals are
+    /// persisted so that those segments become eligible for reuse.
+    async fn compact(&mut self) -> Result<(), Error> {
+        // Tombstones are not purged while a batch is open.
+        let batch = self.batch.clone();
+        let _batch = batch.lock().await;
+        self.purge_tombstones().await;
+
+        // Sled reclaims the space of removed entries as it rewrites its log;
+        // flushing persists the removals.
+        self.flush().await
+    }
+
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        // Sled does not report the space held by removed entries.
+        Ok(CompactionStats {
+            pending_tombstones: self.get_pending_tombstones().await.len(),
+            reclaimable_bytes: None,
+            disk_usage: Some(self.db.size_on_disk()?),
+        })
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        // Channel names may be blinded in tree names, so the posts of each
+        // known channel are counted by looking up the tree of the channel.
+        let mut posts_per_channel = std::collections::BTreeMap::new();
+        for channel in self.get_channels().await.unwrap_or_default() {
+            let posts = self
+                .channel_posts_tree(Some(&channel))
+                .map_or(0, |tree| tree.len());
+            posts_per_channel.insert(channel, posts);
+        }
+
+        let index_sizes = [
+            ("identities", &self.identities),
+            ("channels", &self.channels),
+            ("channel_members", &self.channel_members),
+            ("ex_channel_members", &self.ex_channel_members),
+            ("channel_membership", &self.channel_membership),
+            ("channel_topics", &self.channel_topics),
+            ("delete_hashes", &self.delete_hashes),
+            ("info_hashes", &self.info_hashes),
+            ("peer_names", &self.peer_names),
+            ("peer_avatars", &self.peer_avatars),
+            ("channel_heads", &self.channel_heads),
+            ("post_links", &self.post_links),
+            ("author_posts", &self.author_posts),
+        ]
+        .into_iter()
+        .map(|(index, tree)| (index.to_string(), tree.len()))
+        .chain([(
+            "posts".to_string(),
+            self.posts_trees
+                .read()
+                .unwrap()
+                .values()
+                .map(Tree::len)
+                .sum(),
+        )])
+        .collect();
+
+        Ok(StoreMetrics {
+            total_posts: self.post_payloads.len(),
+            posts_per_channel,
+            tombstones: Some(self.tombstones.len()),
+            index_sizes,
+            disk_usage: Some(self.db.size_on_disk()?),
+        })
+    }
+
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
+        // Return the "wanted" hashes, excluding those of deleted posts. Only
+        // hashes which may be known are looked up in the database.
+        hashes
+            .iter()
+            .filter(|hash| {
+                !self.known_hashes.may_contain(hash)
+                    || (!log_err(self.post_payloads.contains_key(hash)).unwrap_or(false)
+                        && !log_err(self.tombstones.contains_key(hash)).unwrap_or(false))
+            })
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,1762 @@
+//! A persistent implementation of the `Store` trait built on SQLite.
+//!
+//! Posts are stored in a single table, indexed by channel and timestamp (for
+//! channel time range queries) and by author and timestamp. Queries are
+//! answered from the database without loading the full history into memory.
+//!
+//! Timestamps are stored as SQLite integers (signed 64-bit). Timestamps
+//! beyond `i64::MAX` milliseconds are not supported.
+
+use std::{convert::TryInto, path::Path};
+
+use async_std::{
+    stream,
+    sync::{Arc, Mutex},
+};
+use cable::{
+    crypto, error::CableErrorKind, post::Post, Avatar, Channel, ChannelOptions, Error, Hash,
+    Nickname, Payload, ReqId, Timestamp, Topic,
+};
+use desert::{FromBytes, ToBytes};
+use log::error;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+
+use crate::{
+    batch::BatchLock,
+    filter::KnownHashes,
+    metrics::{CompactionStats, StoreMetrics},
+    migration::{migrate, Migration},
+    store::{unknown_identity, Keypair, PageCursor, PostPage, PublicKey, Store},
+    stream::{EventStream, HashStream, LiveStreams, PostStream, StoreEvent},
+};
+
+/// The schema migrations of the database, applied in order. The schema
+/// version is recorded as the SQLite `user_version`.
+const MIGRATIONS: &[Migration<Connection>] = &[
+    create_schema,
+    create_identities,
+    create_channel_heads,
+    create_outbound_requests,
+    create_last_read,
+    create_mentions,
+    create_reactions,
+    create_author_posts,
+    create_archived_channels,
+    create_blobs,
+    create_channel_archives,
+    create_peer_avatars,
+    create_replies,
+];
+
+/// Create the initial database schema.
+fn create_schema(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(SCHEMA)?;
+
+    Ok(())
+}
+
+/// Create the table holding the keypairs of all identities, recording the
+/// keypair of the active identity in it.
+fn create_identities(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS identities (
+            public_key BLOB PRIMARY KEY,
+            secret_key BLOB NOT NULL
+        );
+        INSERT OR IGNORE INTO identities (public_key, secret_key)
+            SELECT public_key, secret_key FROM keypair;",
+    )?;
+
+    Ok(())
+}
+
+/// Create the tables tracking the heads of each channel according to the
+/// links of its posts, indexing the heads of the stored posts.
+fn create_channel_heads(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS channel_heads (
+            channel TEXT NOT NULL,
+            hash BLOB NOT NULL,
+            PRIMARY KEY (channel, hash)
+        );
+        CREATE TABLE IF NOT EXISTS post_links (
+            channel TEXT NOT NULL,
+            link BLOB NOT NULL,
+            hash BLOB NOT NULL,
+            PRIMARY KEY (channel, link, hash)
+        );",
+    )?;
+
+    let mut stmt = conn.prepare("SELECT hash, payload FROM post_payloads")?;
+    let payloads = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (hash, payload) in payloads {
+        let Ok((_s, post)) = Post::from_bytes(&payload) else {
+            continue;
+        };
+        if let Some(channel) = post.get_channel() {
+            for link in &post.header.links {
+                conn.execute(
+                    "INSERT OR IGNORE INTO post_links (channel, link, hash) VALUES (?1, ?2, ?3)",
+                    params![channel, &link[..], &hash],
+                )?;
+                conn.execute(
+                    "DELETE FROM channel_heads WHERE channel = ?1 AND hash = ?2",
+                    params![channel, &link[..]],
+                )?;
+            }
+            conn.execute(
+                "INSERT INTO channel_heads (channel, hash)
+                 SELECT ?1, ?2 WHERE NOT EXISTS
+                    (SELECT 1 FROM post_links WHERE channel = ?1 AND link = ?2)",
+                params![channel, &hash],
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Create the table holding the outbound requests of local origin.
+fn create_outbound_requests(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS outbound_requests (
+            req_id BLOB PRIMARY KEY,
+            request BLOB NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding the position of the last read post of each
+/// channel.
+fn create_last_read(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS last_read (
+            channel TEXT PRIMARY KEY,
+            timestamp INTEGER NOT NULL,
+            hash BLOB NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table indexing the posts which mention the local user.
+fn create_mentions(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS mentions (
+            hash BLOB PRIMARY KEY,
+            channel TEXT NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS mentions_channel_timestamp ON mentions (channel, timestamp);",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table indexing reaction posts by the post they react to. The
+/// table is created whether or not the `reactions` feature is enabled, so
+/// that the schema version does not depend on the enabled features.
+fn create_reactions(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS reactions (
+            hash BLOB PRIMARY KEY,
+            target BLOB NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS reactions_target_timestamp ON reactions (target, timestamp);",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table indexing posts by author, indexing the stored posts.
+fn create_author_posts(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS author_posts (
+            hash BLOB PRIMARY KEY,
+            public_key BLOB NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS author_posts_public_key_timestamp
+            ON author_posts (public_key, timestamp);",
+    )?;
+
+    let mut stmt = conn.prepare("SELECT hash, payload FROM post_payloads")?;
+    let payloads = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (hash, payload) in payloads {
+        let Ok((_s, post)) = Post::from_bytes(&payload) else {
+            continue;
+        };
+        conn.execute(
+            "INSERT OR IGNORE INTO author_posts (hash, public_key, timestamp) VALUES (?1, ?2, ?3)",
+            params![
+                &hash,
+                &post.get_public_key()[..],
+                post.get_timestamp() as i64
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Create the table holding the channels archived by the local user.
+fn create_archived_channels(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS archived_channels (
+            channel TEXT PRIMARY KEY
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding binary blobs. The table is created whether or
+/// not the `blobs` feature is enabled, so that the schema version does not
+/// depend on the enabled features.
+fn create_blobs(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS blobs (
+            hash BLOB PRIMARY KEY,
+            blob BLOB NOT NULL
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table indexing archive posts by the channel they archive. The
+/// table is created whether or not the `archive-posts` feature is enabled, so
+/// that the schema version does not depend on the enabled features.
+fn create_channel_archives(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS channel_archives (
+            hash BLOB PRIMARY KEY,
+            channel TEXT NOT NULL,
+            public_key BLOB NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS channel_archives_channel ON channel_archives (channel);",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table holding the avatar of each `post/info` post setting one.
+fn create_peer_avatars(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS peer_avatars (
+            public_key BLOB NOT NULL,
+            timestamp INTEGER NOT NULL,
+            avatar TEXT NOT NULL,
+            hash BLOB NOT NULL,
+            PRIMARY KEY (public_key, timestamp)
+        );
+        CREATE INDEX IF NOT EXISTS peer_avatars_hash ON peer_avatars (hash);",
+    )?;
+
+    Ok(())
+}
+
+/// Create the table indexing replies by the post they reply to.
+fn create_replies(conn: &Connection) -> Result<(), Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS replies (
+            hash BLOB PRIMARY KEY,
+            parent BLOB NOT NULL,
+            timestamp INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS replies_parent_timestamp ON replies (parent, timestamp);",
+    )?;
+
+    Ok(())
+}
+
+/// Record the given schema version of the database.
+fn set_schema_version(conn: &Connection, version: u32) -> Result<(), Error> {
+    conn.pragma_update(None, "user_version", version)?;
+
+    Ok(())
+}
+
+/// The initial database schema.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS keypair (
+        id INTEGER PRIMARY KEY CHECK (id = 0),
+        public_key BLOB NOT NULL,
+        secret_key BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS channels (
+        channel TEXT PRIMARY KEY
+    );
+    CREATE TABLE IF NOT EXISTS channel_members (
+        channel TEXT NOT NULL,
+        public_key BLOB NOT NULL,
+        PRIMARY KEY (channel, public_key)
+    );
+    CREATE TABLE IF NOT EXISTS ex_channel_members (
+        channel TEXT NOT NULL,
+        public_key BLOB NOT NULL,
+        PRIMARY KEY (channel, public_key)
+    );
+    CREATE TABLE IF NOT EXISTS channel_membership (
+        channel TEXT NOT NULL,
+        public_key BLOB NOT NULL,
+        hash BLOB NOT NULL,
+        PRIMARY KEY (channel, public_key)
+    );
+    CREATE INDEX IF NOT EXISTS channel_membership_hash ON channel_membership (hash);
+    CREATE TABLE IF NOT EXISTS channel_topics (
+        channel TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        topic TEXT NOT NULL,
+        hash BLOB NOT NULL,
+        PRIMARY KEY (channel, timestamp)
+    );
+    CREATE INDEX IF NOT EXISTS channel_topics_hash ON channel_topics (hash);
+    CREATE TABLE IF NOT EXISTS delete_hashes (
+        public_key BLOB NOT NULL,
+        hash BLOB NOT NULL,
+        PRIMARY KEY (public_key, hash)
+    );
+    CREATE TABLE IF NOT EXISTS info_hashes (
+        public_key BLOB NOT NULL,
+        hash BLOB NOT NULL,
+        PRIMARY KEY (public_key, hash)
+    );
+    CREATE INDEX IF NOT EXISTS info_hashes_hash ON info_hashes (hash);
+    CREATE TABLE IF NOT EXISTS peer_names (
+        public_key BLOB NOT NULL,
+        timestamp INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        hash BLOB NOT NULL,
+        PRIMARY KEY (public_key, timestamp)
+    );
+    CREATE INDEX IF NOT EXISTS peer_names_hash ON peer_names (hash);
+    CREATE TABLE IF NOT EXISTS posts (
+        hash BLOB PRIMARY KEY,
+        channel TEXT,
+        timestamp INTEGER NOT NULL,
+        public_key BLOB NOT NULL,
+        post BLOB NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS posts_channel_timestamp ON posts (channel, timestamp);
+    CREATE INDEX IF NOT EXISTS posts_public_key_timestamp ON posts (public_key, timestamp);
+    CREATE TABLE IF NOT EXISTS post_payloads (
+        hash BLOB PRIMARY KEY,
+        payload BLOB NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tombstones (
+        hash BLOB PRIMARY KEY
+    );
+";
+
+/// Log the error of a failed database operation, returning the value of a
+/// successful operation.
+fn log_err<T>(res: rusqlite::Result<T>) -> Option<T> {
+    res.map_err(|err| error!("SQLite store operation failed: {}", err))
+        .ok()
+}
+
+/// Convert the given blob into a fixed-length array, logging a mismatched
+/// length.
+fn to_array<const N: usize>(blob: Vec<u8>) -> Option<[u8; N]> {
+    let len = blob.len();
+    blob.try_into()
+        .map_err(|_| error!("SQLite store value has unexpected length: {}", len))
+        .ok()
+}
+
+/// Return `None` if the given `Vec` is empty.
+fn non_empty<T>(values: Vec<T>) -> Option<Vec<T>> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values)
+    }
+}
+
+#[derive(Clone)]
+/// A persistent store containing a keypair and post data, backed by SQLite.
+pub struct SqliteStore {
+    /// The database connection.
+    conn: Arc<Mutex<Connection>>,
+    /// Held for the duration of a batch, which runs in a single transaction,
+    /// or of any other statement which may not run within it.
+    batch: BatchLock,
+    /// A filter of the hashes of all post payloads and tombstones, used to
+    /// answer `want()` for unknown hashes without querying the database.
+    known_hashes: KnownHashes,
+    /// All active live streams, indexed by channel.
+    live_streams: LiveStreams,
+    /// Whether the database connection is read-only.
+    read_only: bool,
+}
+
+impl SqliteStore {
+    /// Open the store located at the given path, creating it if it does not
+    /// yet exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Open the encrypted store located at the given path, creating it if it
+    /// does not yet exist.
+    ///
+    /// The database is encrypted by SQLCipher with a key derived from the
+    /// given passphrase. An error is returned if the passphrase is incorrect
+    /// or if the existing store is not encrypted.
+    #[cfg(feature = "sqlcipher")]
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self, Error> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "key", passphrase)?;
+
+        // The key is not applied until the database is first read, at which
+        // point an incorrect key results in an error.
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_row| Ok(()))?;
+
+        Self::from_connection(conn)
+    }
+
+    /// Open the existing store located at the given path read-only.
+    ///
+    /// The database is opened without write access, so the store serves its
+    /// posts but refuses to insert new ones. An error is returned if the
+    /// store does not exist or is not at the latest schema version, since it
+    /// cannot be migrated.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        if Self::read_schema_version(&conn)? != MIGRATIONS.len() as u32 {
+            return CableErrorKind::StoreFailed {
+                context: "sqlite store must be migrated before it is opened read-only".to_string(),
+            }
+            .raise();
+        }
+
+        let known_hashes = KnownHashes::new(Self::known_hash_list(&conn));
+
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+            batch: BatchLock::default(),
+            known_hashes,
+            live_streams: LiveStreams::default(),
+            read_only: true,
+        })
+    }
+
+    /// Open a store which is held in memory and discarded when dropped.
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Create a store from an opened SQLite connection, creating or migrating
+    /// the schema if required.
+    ///
+    /// A new keypair is generated and persisted if the database does not
+    /// yet contain one.
+    pub fn from_connection(conn: Connection) -> Result<Self, Error> {
+        let version = Self::read_schema_version(&conn)?;
+        migrate(&conn, version, MIGRATIONS, set_schema_version)?;
+
+        let (pk, sk) = crypto::generate_keypair();
+        conn.execute(
+            "INSERT OR IGNORE INTO keypair (id, public_key, secret_key) VALUES (0, ?1, ?2)",
+            params![&pk[..], &sk[..]],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO identities (public_key, secret_key)
+                SELECT public_key, secret_key FROM keypair",
+            [],
+        )?;
+
+        let known_hashes = KnownHashes::new(Self::known_hash_list(&conn));
+
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+            batch: BatchLock::default(),
+            known_hashes,
+            live_streams: LiveStreams::default(),
+            read_only: false,
+        })
+    }
+
+    /// Retrieve the schema version of the store.
+    pub async fn schema_version(&self) -> Result<u32, Error> {
+        let conn = self.conn.lock().await;
+
+        Self::read_schema_version(&conn)
+    }
+
+    /// Read the schema version recorded in the given database.
+    fn read_schema_version(conn: &Connection) -> Result<u32, Error> {
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Run the given query, collecting a single blob column of each row into
+    /// fixed-length arrays.
+    fn query_arrays<const N: usize>(
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Vec<[u8; N]> {
+        let res = conn.prepare_cached(sql).and_then(|mut stmt| {
+            stmt.query_map(params, |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<Vec<u8>>>>()
+        });
+
+        log_err(res)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(to_array)
+            .collect()
+    }
+
+    /// List the hashes of all post payloads and tombstones in the database.
+    fn known_hash_list(conn: &Connection) -> Vec<Hash> {
+        Self::query_arrays(
+            conn,
+            "SELECT hash FROM post_payloads UNION ALL SELECT hash FROM tombstones",
+            [],
+        )
+    }
+
+    /// Add the given hash to the filter of known hashes, rebuilding the
+    /// filter from the database once it has outgrown its capacity.
+    fn remember_hash(&self, conn: &Connection, hash: &Hash) {
+        if self.known_hashes.insert(hash) {
+            self.known_hashes.rebuild(Self::known_hash_list(conn));
+        }
+    }
+
+    /// Run the given statement, logging any error.
+    fn execute(conn: &Connection, sql: &str, params: impl rusqlite::Params) {
+        log_err(
+            conn.prepare_cached(sql)
+                .and_then(|mut stmt| stmt.execute(params)),
+        );
+    }
+
+    /// Run the given query, returning the string and hash of the first row.
+    fn query_string_and_hash(
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Option<(String, Hash)> {
+        let res = conn.prepare_cached(sql).and_then(|mut stmt| {
+            stmt.query_row(params, |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })
+            .optional()
+        });
+
+        let (string, hash) = log_err(res).flatten()?;
+
+        Some((string, to_array(hash)?))
+    }
+
+    /// Run the given posts query, decoding the post column of each row.
+    fn query_posts(
+        conn: &Connection,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Vec<Result<Post, Error>> {
+        let res = conn.prepare_cached(sql).and_then(|mut stmt| {
+            stmt.query_map(params, |row| row.get::<_, Vec<u8>>(0))?
+                .collect::<rusqlite::Result<Vec<Vec<u8>>>>()
+        });
+
+        match res {
+            Ok(rows) => rows
+                .iter()
+                .map(|post_bytes| {
+                    let (_s, post) = Post::from_bytes(post_bytes)?;
+                    Ok(post)
+                })
+                .collect(),
+            Err(err) => vec![Err(err.into())],
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for SqliteStore {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .query_row(
+                "SELECT public_key, secret_key FROM keypair WHERE id = 0",
+                [],
+                |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .optional();
+
+        let (pk, sk) = log_err(res).flatten()?;
+
+        Some((to_array(pk)?, to_array(sk)?))
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        let conn = self.conn.lock().await;
+
+        let (pk, sk) = keypair;
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO identities (public_key, secret_key) VALUES (?1, ?2)",
+            params![&pk[..], &sk[..]],
+        );
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO keypair (id, public_key, secret_key) VALUES (0, ?1, ?2)",
+            params![&pk[..], &sk[..]],
+        );
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        let conn = self.conn.lock().await;
+
+        Self::query_arrays(
+            &conn,
+            "SELECT public_key FROM identities ORDER BY public_key",
+            [],
+        )
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        let conn = self.conn.lock().await;
+
+        let (pk, sk) = keypair;
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO identities (public_key, secret_key) VALUES (?1, ?2)",
+            params![&pk[..], &sk[..]],
+        );
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        let conn = self.conn.lock().await;
+
+        let updated = conn.execute(
+            "INSERT OR REPLACE INTO keypair (id, public_key, secret_key)
+                SELECT 0, public_key, secret_key FROM identities WHERE public_key = ?1",
+            params![&public_key[..]],
+        )?;
+
+        if updated == 0 {
+            return unknown_identity(public_key);
+        }
+
+        Ok(())
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT channel FROM channels ORDER BY channel")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<Channel>>>()
+            });
+
+        non_empty(log_err(res)?)
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO channels (channel) VALUES (?1)",
+            params![channel],
+        );
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT channel FROM archived_channels ORDER BY channel")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<Channel>>>()
+            });
+
+        log_err(res).unwrap_or_default()
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT 1 FROM archived_channels WHERE channel = ?1")
+            .and_then(|mut stmt| stmt.exists(params![channel]));
+
+        log_err(res).unwrap_or(false)
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        let conn = self.conn.lock().await;
+
+        let sql = if archived {
+            "INSERT OR IGNORE INTO archived_channels (channel) VALUES (?1)"
+        } else {
+            "DELETE FROM archived_channels WHERE channel = ?1"
+        };
+        Self::execute(&conn, sql, params![channel]);
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        let conn = self.conn.lock().await;
+
+        for sql in [
+            "DELETE FROM channels WHERE channel = ?1",
+            "DELETE FROM archived_channels WHERE channel = ?1",
+            "DELETE FROM last_read WHERE channel = ?1",
+        ] {
+            Self::execute(&conn, sql, params![channel]);
+        }
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT public_key FROM channel_members WHERE channel = ?1",
+            params![channel],
+        ))
+    }
+
+    async fn insert_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO channel_members (channel, public_key) VALUES (?1, ?2)",
+            params![channel, &public_key[..]],
+        );
+    }
+
+    async fn is_channel_member(&self, channel: &Channel, public_key: &PublicKey) -> bool {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT 1 FROM channel_members WHERE channel = ?1 AND public_key = ?2")
+            .and_then(|mut stmt| stmt.exists(params![channel, &public_key[..]]));
+
+        log_err(res).unwrap_or(false)
+    }
+
+    async fn remove_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM channel_members WHERE channel = ?1 AND public_key = ?2",
+            params![channel, &public_key[..]],
+        );
+    }
+
+    async fn get_channel_membership_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT hash FROM channel_membership WHERE channel = ?1",
+            params![channel],
+        ))
+    }
+
+    async fn remove_channel_membership_hash(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM channel_membership WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn update_channel_membership_hashes(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO channel_membership (channel, public_key, hash)
+             VALUES (?1, ?2, ?3)",
+            params![channel, &public_key[..], &hash[..]],
+        );
+    }
+
+    async fn get_ex_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT public_key FROM ex_channel_members WHERE channel = ?1",
+            params![channel],
+        ))
+    }
+
+    async fn insert_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO ex_channel_members (channel, public_key) VALUES (?1, ?2)",
+            params![channel, &public_key[..]],
+        );
+    }
+
+    async fn remove_ex_channel_member(&mut self, channel: &Channel, public_key: &PublicKey) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM ex_channel_members WHERE channel = ?1 AND public_key = ?2",
+            params![channel, &public_key[..]],
+        );
+    }
+
+    async fn get_channel_topic_and_hash(&self, channel: &Channel) -> Option<(Topic, Hash)> {
+        let conn = self.conn.lock().await;
+
+        Self::query_string_and_hash(
+            &conn,
+            "SELECT topic, hash FROM channel_topics WHERE channel = ?1
+             ORDER BY timestamp DESC LIMIT 1",
+            params![channel],
+        )
+    }
+
+    async fn insert_channel_topic(
+        &mut self,
+        channel: &Channel,
+        topic: &Topic,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO channel_topics (channel, timestamp, topic, hash)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![channel, *timestamp as i64, topic, &hash[..]],
+        );
+    }
+
+    async fn remove_channel_topic(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM channel_topics WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_delete_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT hash FROM delete_hashes WHERE public_key = ?1",
+            params![&public_key[..]],
+        ))
+    }
+
+    async fn insert_delete_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO delete_hashes (public_key, hash) VALUES (?1, ?2)",
+            params![&public_key[..], &hash[..]],
+        );
+    }
+
+    async fn get_info_hashes(&self, public_key: &PublicKey) -> Option<Vec<Hash>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT hash FROM info_hashes WHERE public_key = ?1",
+            params![&public_key[..]],
+        ))
+    }
+
+    async fn insert_info_hash(&mut self, public_key: &PublicKey, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO info_hashes (public_key, hash) VALUES (?1, ?2)",
+            params![&public_key[..], &hash[..]],
+        );
+    }
+
+    async fn remove_info_hash(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM info_hashes WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
+        let conn = self.conn.lock().await;
+
+        non_empty(Self::query_arrays(
+            &conn,
+            "SELECT hash FROM channel_heads WHERE channel = ?1 ORDER BY hash",
+            params![channel],
+        ))
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO channel_heads (channel, hash) VALUES (?1, ?2)",
+            params![channel, &hash[..]],
+        );
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM channel_heads WHERE channel = ?1 AND hash = ?2",
+            params![channel, &hash[..]],
+        );
+    }
+
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO post_links (channel, link, hash) VALUES (?1, ?2, ?3)",
+            params![channel, &link[..], &hash[..]],
+        );
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM post_links WHERE channel = ?1 AND link = ?2 AND hash = ?3",
+            params![channel, &link[..], &hash[..]],
+        );
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT 1 FROM post_links WHERE channel = ?1 AND link = ?2")
+            .and_then(|mut stmt| stmt.exists(params![channel, &hash[..]]));
+
+        log_err(res).unwrap_or(false)
+    }
+
+    async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
+        let conn = self.conn.lock().await;
+
+        Self::query_string_and_hash(
+            &conn,
+            "SELECT name, hash FROM peer_names WHERE public_key = ?1
+             ORDER BY timestamp DESC LIMIT 1",
+            params![&public_key[..]],
+        )
+    }
+
+    async fn insert_peer_name(
+        &mut self,
+        public_key: &PublicKey,
+        name: &Nickname,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO peer_names (public_key, timestamp, name, hash)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![&public_key[..], *timestamp as i64, name, &hash[..]],
+        );
+    }
+
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM peer_names WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        let conn = self.conn.lock().await;
+
+        let (avatar, hash) = Self::query_string_and_hash(
+            &conn,
+            "SELECT avatar, hash FROM peer_avatars WHERE public_key = ?1
+             ORDER BY timestamp DESC LIMIT 1",
+            params![&public_key[..]],
+        )?;
+
+        Some((Avatar::parse(&avatar).ok()?, hash))
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO peer_avatars (public_key, timestamp, avatar, hash)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                &public_key[..],
+                *timestamp as i64,
+                avatar.to_string(),
+                &hash[..]
+            ],
+        );
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM peer_avatars WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
+        let conn = self.conn.lock().await;
+
+        // Retrieve all posts matching the given channel options. An end time
+        // of 0 denotes no upper bound.
+        let mut posts = Self::query_posts(
+            &conn,
+            "SELECT post FROM posts WHERE channel = ?1 AND timestamp >= ?2
+             AND (?3 = 0 OR timestamp < ?3) ORDER BY timestamp, rowid",
+            params![opts.channel, opts.time_start as i64, opts.time_end as i64],
+        );
+
+        // Retrieve all posts which do not have a channel field.
+        // For example, `post/info` posts.
+        let non_channel_posts = Self::query_posts(
+            &conn,
+            "SELECT post FROM posts WHERE channel IS NULL ORDER BY timestamp, rowid",
+            [],
+        );
+
+        // Add the non-channel posts to the channel posts.
+        posts.extend(non_channel_posts);
+
+        // Return a post stream.
+        Box::new(stream::from_iter(posts))
+    }
+
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        let conn = self.conn.lock().await;
+
+        // Retrieve one post beyond the limit to determine whether another
+        // page exists. A negative limit denotes no limit.
+        let query_limit = if limit == 0 { -1 } else { limit as i64 + 1 };
+        let mut stmt = conn.prepare_cached(
+            "SELECT timestamp, hash, post FROM posts WHERE channel = ?1
+             AND (?2 IS NULL OR timestamp < ?2 OR (timestamp = ?2 AND hash < ?3))
+             ORDER BY timestamp DESC, hash DESC LIMIT ?4",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    channel,
+                    before.map(|before| before.timestamp as i64),
+                    before.map(|before| before.hash),
+                    query_limit
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                    ))
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut posts = Vec::new();
+        for (timestamp, hash, post_bytes) in rows {
+            let cursor = PageCursor {
+                timestamp: timestamp as Timestamp,
+                hash: hash.as_slice().try_into()?,
+            };
+            let (_s, post) = Post::from_bytes(&post_bytes)?;
+            posts.push((cursor, post));
+        }
+
+        Ok(PostPage::from_posts(posts, limit))
+    }
+
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        self.live_streams.watch(channel).await
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        self.live_streams.send(channel, event).await;
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        let conn = self.conn.lock().await;
+
+        let hashes = Self::query_arrays(
+            &conn,
+            "SELECT hash FROM posts WHERE channel = ?1 AND timestamp >= ?2
+             AND (?3 = 0 OR timestamp < ?3) ORDER BY timestamp, rowid",
+            params![opts.channel, opts.time_start as i64, opts.time_end as i64],
+        )
+        .into_iter()
+        .map(Ok)
+        .collect::<Vec<Result<Hash, Error>>>();
+
+        // Return a hash stream.
+        Box::new(stream::from_iter(hashes))
+    }
+
+    async fn remove_post(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM posts WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        let post_bytes = match post.to_bytes() {
+            Ok(post_bytes) => post_bytes,
+            Err(err) => {
+                error!("Failed to encode post for SQLite store: {}", err);
+                return;
+            }
+        };
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO posts (hash, channel, timestamp, public_key, post)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                &hash[..],
+                channel,
+                *timestamp as i64,
+                &post.get_public_key()[..],
+                post_bytes
+            ],
+        );
+    }
+
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT payload FROM post_payloads WHERE hash = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row(params![&hash[..]], |row| row.get(0))
+                    .optional()
+            });
+
+        log_err(res).flatten()
+    }
+
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload> {
+        let mut payloads = Vec::new();
+        for hash in hashes {
+            if let Some(payload) = self.get_post_payload(hash).await {
+                payloads.push(payload)
+            }
+        }
+
+        payloads
+    }
+
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        let conn = self.conn.lock().await;
+
+        Self::query_arrays(&conn, "SELECT hash FROM post_payloads", [])
+    }
+
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO post_payloads (hash, payload) VALUES (?1, ?2)",
+            params![&hash[..], payload],
+        );
+        self.remember_hash(&conn, hash);
+    }
+
+    async fn remove_post_payload(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM post_payloads WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR IGNORE INTO tombstones (hash) VALUES (?1)",
+            params![&hash[..]],
+        );
+        self.remember_hash(&conn, hash);
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT 1 FROM tombstones WHERE hash = ?1")
+            .and_then(|mut stmt| stmt.exists(params![&hash[..]]));
+
+        log_err(res).unwrap_or(false)
+    }
+
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        let conn = self.conn.lock().await;
+
+        Self::query_arrays(
+            &conn,
+            "SELECT hash FROM mentions WHERE channel = ?1 ORDER BY timestamp, hash",
+            params![channel],
+        )
+    }
+
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO mentions (hash, channel, timestamp) VALUES (?1, ?2, ?3)",
+            params![&hash[..], channel, timestamp as i64],
+        );
+    }
+
+    async fn remove_mention(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM mentions WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        let conn = self.conn.lock().await;
+
+        Self::query_arrays(
+            &conn,
+            "SELECT hash FROM replies WHERE parent = ?1 ORDER BY timestamp, hash",
+            params![&parent[..]],
+        )
+    }
+
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO replies (hash, parent, timestamp) VALUES (?1, ?2, ?3)",
+            params![&hash[..], &parent[..], timestamp as i64],
+        );
+    }
+
+    async fn remove_reply(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM replies WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        let conn = self.conn.lock().await;
+
+        Self::query_arrays(
+            &conn,
+            "SELECT hash FROM reactions WHERE target = ?1 ORDER BY timestamp, hash",
+            params![&target[..]],
+        )
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO reactions (hash, target, timestamp) VALUES (?1, ?2, ?3)",
+            params![&hash[..], &target[..], timestamp as i64],
+        );
+    }
+
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM reactions WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached(
+                "SELECT public_key, hash FROM channel_archives WHERE channel = ?1 ORDER BY hash",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![channel], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            });
+
+        log_err(res)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(public_key, hash)| Some((to_array(public_key)?, to_array(hash)?)))
+            .collect()
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO channel_archives (hash, channel, public_key) VALUES (?1, ?2, ?3)",
+            params![&hash[..], channel, &public_key[..]],
+        );
+    }
+
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM channel_archives WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT blob FROM blobs WHERE hash = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row(params![&hash[..]], |row| row.get(0))
+                    .optional()
+            });
+
+        log_err(res).flatten()
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO blobs (hash, blob) VALUES (?1, ?2)",
+            params![&hash[..], &blob],
+        );
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM blobs WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT COALESCE(SUM(LENGTH(blob)), 0) FROM blobs")
+            .and_then(|mut stmt| stmt.query_row([], |row| row.get::<_, i64>(0)));
+
+        log_err(res).unwrap_or_default() as u64
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached(
+                "SELECT timestamp, hash FROM author_posts WHERE public_key = ?1
+                 ORDER BY timestamp, hash",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![&public_key[..]], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            });
+
+        log_err(res)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(timestamp, hash)| {
+                Some(PageCursor {
+                    timestamp: timestamp as Timestamp,
+                    hash: to_array(hash)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO author_posts (hash, public_key, timestamp) VALUES (?1, ?2, ?3)",
+            params![&hash[..], &public_key[..], timestamp as i64],
+        );
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        _public_key: &PublicKey,
+        _timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM author_posts WHERE hash = ?1",
+            params![&hash[..]],
+        );
+    }
+
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT timestamp, hash FROM last_read WHERE channel = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row(params![channel], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .optional()
+            });
+        let (timestamp, hash) = log_err(res).flatten()?;
+
+        Some(PageCursor {
+            timestamp: timestamp as Timestamp,
+            hash: to_array(hash)?,
+        })
+    }
+
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO last_read (channel, timestamp, hash) VALUES (?1, ?2, ?3)",
+            params![channel, cursor.timestamp as i64, &cursor.hash[..]],
+        );
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        let conn = self.conn.lock().await;
+
+        let res = conn
+            .prepare_cached("SELECT req_id, request FROM outbound_requests")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            });
+
+        log_err(res)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(req_id, request)| Some((to_array(req_id)?, request)))
+            .collect()
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "INSERT OR REPLACE INTO outbound_requests (req_id, request) VALUES (?1, ?2)",
+            params![&req_id[..], request],
+        );
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        let conn = self.conn.lock().await;
+
+        Self::execute(
+            &conn,
+            "DELETE FROM outbound_requests WHERE req_id = ?1",
+            params![&req_id[..]],
+        );
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Begin a transaction, waiting for the batch of any other handle of the
+    /// store to be committed.
+    ///
+    /// Statements executed by other handles of the store while the batch is
+    /// open are committed with it.
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        if self.read_only {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        self.batch.begin().await;
+        if let Err(err) = self.conn.lock().await.execute_batch("BEGIN") {
+            self.batch.end().await;
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Commit the transaction of the batch, rolling it back if it fails to
+    /// commit so that the connection is not left within the transaction.
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        let res = {
+            let conn = self.conn.lock().await;
+            let res = conn.execute_batch("COMMIT");
+            if res.is_err() {
+                // The commit error is reported; the rollback fails only if
+                // the transaction was already rolled back.
+                let _ = conn.execute_batch("ROLLBACK");
+            }
+            res
+        };
+        self.batch.end().await;
+
+        Ok(res?)
+    }
+
+    /// Roll back the transaction of the batch.
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        let res = self.conn.lock().await.execute_batch("ROLLBACK");
+        self.batch.end().await;
+
+        Ok(res?)
+    }
+
+    /// Rebuild the database file, releasing the pages freed by deleted posts.
+    async fn compact(&mut self) -> Result<(), Error> {
+        self.purge_tombstones().await;
+
+        // The database cannot be rebuilt within a transaction.
+        let _batch = self.batch.lock().await;
+        self.conn.lock().await.execute_batch("VACUUM")?;
+
+        Ok(())
+    }
+
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        let pending_tombstones = self.get_pending_tombstones().await.len();
+        let conn = self.conn.lock().await;
+
+        // Pages on the freelist hold deleted data until the database is
+        // rebuilt.
+        let freelist_count: i64 =
+            conn.pragma_query_value(None, "freelist_count", |row| row.get(0))?;
+        let page_count: i64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        let page_size: i64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+
+        Ok(CompactionStats {
+            pending_tombstones,
+            reclaimable_bytes: Some((freelist_count * page_size) as u64),
+            disk_usage: Some((page_count * page_size) as u64),
+        })
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        let conn = self.conn.lock().await;
+
+        let count = |table: &str| -> Result<usize, Error> {
+            let sql = format!("SELECT COUNT(*) FROM {}", table);
+            let count: i64 = conn.query_row(&sql, [], |row| row.get(0))?;
+            Ok(count as usize)
+        };
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT channel, COUNT(*) FROM posts WHERE channel IS NOT NULL GROUP BY channel",
+        )?;
+        let posts_per_channel = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut index_sizes = std::collections::BTreeMap::new();
+        for table in [
+            "identities",
+            "channels",
+            "channel_members",
+            "ex_channel_members",
+            "channel_membership",
+            "channel_topics",
+            "delete_hashes",
+            "info_hashes",
+            "peer_names",
+            "peer_avatars",
+            "posts",
+            "channel_heads",
+            "post_links",
+            "author_posts",
+        ] {
+            index_sizes.insert(table.to_string(), count(table)?);
+        }
+
+        let page_count: i64 = conn.pragma_query_value(None, "page_count", |row| row.get(0))?;
+        let page_size: i64 = conn.pragma_query_value(None, "page_size", |row| row.get(0))?;
+
+        Ok(StoreMetrics {
+            total_posts: count("post_payloads")?,
+            posts_per_channel,
+            tombstones: Some(count("tombstones")?),
+            index_sizes,
+            disk_usage: Some((page_count * page_size) as u64),
+        })
+    }
+
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
+        // Only hashes which may be known are looked up in the database.
+        if !hashes
+            .iter()
+            .any(|hash| self.known_hashes.may_contain(hash))
+        {
+            return hashes.to_vec();
+        }
+
+        let conn = self.conn.lock().await;
+
+        let mut wanted = Vec::new();
+        for hash in hashes {
+            if !self.known_hashes.may_contain(hash) {
+                wanted.push(*hash);
+                continue;
+            }
+
+            let res = conn
+                .prepare_cached(
+                    "SELECT 1 FROM post_payloads WHERE hash = ?1
+                     UNION ALL SELECT 1 FROM tombstones WHERE hash = ?1",
+                )
+                .and_then(|mut stmt| stmt.exists(params![&hash[..]]));
+
+            // Return the "wanted" hashes, excluding those of deleted posts.
+            if !log_err(res).unwrap_or(false) {
+                wanted.push(*hash)
+            }
+        }
+
+        wanted
+    }
+}
@@ -2,24 +2,37 @@
 //! an in-memory implementation of the `Store` trait.
 
 use std::{
-    collections::{BTreeMap, BTreeSet, HashMap},
-    convert::TryInto,
+    collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet},
+    io::{Read, Write},
 };
 
 use async_std::{
     prelude::*,
     stream,
-    sync::{Arc, Mutex, RwLock},
-    task,
+    sync::{Arc, RwLock},
 };
 use cable::{
+    crypto,
+    error::CableErrorKind,
     post::{Post, PostBody},
-    Channel, ChannelOptions, Error, Hash, Nickname, Payload, Timestamp, Topic, UserInfo,
+    Avatar, Channel, ChannelOptions, Error, Hash, Nickname, Payload, ReqId, Timestamp, Topic,
+    UserInfo,
 };
 use desert::{FromBytes, ToBytes};
-use sodiumoxide::crypto;
 
-use crate::stream::{HashStream, LiveStream, PostStream};
+use crate::{
+    archive::{Archive, ExportOptions},
+    causal::causal_order,
+    decrypt_keypair,
+    dyn_store::StoreClone,
+    encrypt_keypair,
+    integrity::IntegrityReport,
+    intern::{ChannelInterner, SharedChannel},
+    metrics::{CompactionStats, StoreMetrics},
+    retention::RetentionPolicy,
+    stream::{self as post_stream, EventStream, HashStream, LiveStreams, PostStream, StoreEvent},
+    thread::{self, Thread},
+};
 
 /// A public key.
 pub type PublicKey = [u8; 32];
@@ -27,9 +40,98 @@ pub type PublicKey = [u8; 32];
 /// A public-private keypair.
 pub type Keypair = ([u8; 32], [u8; 64]);
 
-/// A `HashMap` of live streams with a key of channel name and a value
-/// of a `Vec` of streams (wrapped in an `Arc` and `RwLock`).
-pub type LiveStreamMap = HashMap<Channel, Arc<RwLock<Vec<LiveStream>>>>;
+/// Return an error reporting that the store does not hold the identity with
+/// the given public key.
+pub(crate) fn unknown_identity<T>(public_key: &PublicKey) -> Result<T, Error> {
+    CableErrorKind::NoneError {
+        context: format!("unknown identity {}", hex::encode(public_key)),
+    }
+    .raise()
+}
+
+/// The position of a post in the reverse-chronological order of a channel,
+/// used to continue paginated retrieval with `Store::get_posts_page()`.
+///
+/// Posts are ordered by timestamp, with posts sharing a timestamp ordered by
+/// hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PageCursor {
+    /// The timestamp of the post.
+    pub timestamp: Timestamp,
+    /// The hash of the post.
+    pub hash: Hash,
+}
+
+impl PageCursor {
+    /// Create a cursor positioned before all posts with the given timestamp
+    /// or a later timestamp.
+    pub fn before(timestamp: Timestamp) -> Self {
+        PageCursor {
+            timestamp,
+            hash: [0; 32],
+        }
+    }
+}
+
+/// A page of channel posts, ordered from newest to oldest.
+#[derive(Clone, Debug)]
+pub struct PostPage {
+    /// The posts of the page, newest first.
+    pub posts: Vec<Post>,
+    /// The cursor from which to retrieve the next (older) page, or `None` if
+    /// this is the final page.
+    pub next: Option<PageCursor>,
+}
+
+impl PostPage {
+    /// Create a page from the given posts and their positions, retaining the
+    /// `limit` newest posts. A limit of 0 retains all posts.
+    ///
+    /// A continuation cursor is only returned if posts were omitted from the
+    /// page, so stores may retrieve `limit + 1` posts to determine whether
+    /// another page exists.
+    pub(crate) fn from_posts(mut posts: Vec<(PageCursor, Post)>, limit: usize) -> Self {
+        posts.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        let next = if limit > 0 && posts.len() > limit {
+            posts.truncate(limit);
+            posts.last().map(|(cursor, _post)| *cursor)
+        } else {
+            None
+        };
+
+        PostPage {
+            posts: posts.into_iter().map(|(_cursor, post)| post).collect(),
+            next,
+        }
+    }
+}
+
+/// The parameters of a query of the posts of an author with
+/// `Store::get_posts_by_author()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuthorOptions {
+    /// The channel to which the posts were made, or `None` for posts of all
+    /// channels, including posts without a channel.
+    pub channel: Option<Channel>,
+    /// The position before which posts are retrieved, or `None` to start
+    /// from the newest post.
+    pub before: Option<PageCursor>,
+    /// The maximum number of posts to retrieve (0 for no limit).
+    pub limit: usize,
+}
+
+/// A `post/info` post of a user, as returned by
+/// `Store::get_user_info_history()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserInfoEntry {
+    /// The timestamp of the post.
+    pub timestamp: Timestamp,
+    /// The hash of the post.
+    pub hash: Hash,
+    /// The information published by the post, such as the name of the user.
+    pub info: Vec<UserInfo>,
+}
 
 /// A `HashMap` of peer names with a key of public key and a value of a
 /// `BTreeMap`. The `BTreeMap` has a key of timestamp and a value of a tuple
@@ -37,6 +139,12 @@ pub type LiveStreamMap = HashMap<Channel, Arc<RwLock<Vec<LiveStream>>>>;
 /// stored name.
 pub type NameHashMap = HashMap<PublicKey, BTreeMap<Timestamp, (Nickname, Hash)>>;
 
+/// A `HashMap` of peer avatars with a key of public key and a value of a
+/// `BTreeMap`. The `BTreeMap` has a key of timestamp and a value of a tuple
+/// of avatar and hash. The hash is of the `post/info` post which defined the
+/// stored avatar.
+pub type AvatarHashMap = HashMap<PublicKey, BTreeMap<Timestamp, (Avatar, Hash)>>;
+
 /// A `HashMap` of posts with a key of an option-enclosed channel name and a
 /// value of a `BTreeMap`. The `BTreeMap` has a key of timestamp and value of
 /// a `Vec` of tuple with post and post hash.
@@ -44,48 +152,240 @@ pub type NameHashMap = HashMap<PublicKey, BTreeMap<Timestamp, (Nickname, Hash)>>
 /// The key is an `Option` to allow for storage and retrieved of post types
 /// which do not have an associated channel; these posts are stored with a
 /// key of `None`.
-pub type PostMap = HashMap<Option<Channel>, BTreeMap<Timestamp, Vec<(Post, Hash)>>>;
+pub type PostMap = HashMap<Option<SharedChannel>, BTreeMap<Timestamp, Vec<(Post, Hash)>>>;
+
+/// A `HashMap` of post locations with a key of post hash and a value of the
+/// channel and timestamp under which the post is held in a `PostMap`.
+pub type PostKeyMap = HashMap<Hash, (Option<SharedChannel>, Timestamp)>;
+
+/// Return the entries of the given posts of a channel, indexed by timestamp,
+/// which fall within the given time range.
+///
+/// An end time of 0 denotes no upper bound. A range which ends before it
+/// starts is empty.
+fn time_range(
+    post_map: &BTreeMap<Timestamp, Vec<(Post, Hash)>>,
+    start: Timestamp,
+    end: Timestamp,
+) -> btree_map::Range<'_, Timestamp, Vec<(Post, Hash)>> {
+    match end {
+        0 => post_map.range(start..),
+        end => post_map.range(start..end.max(start)),
+    }
+}
+
+/// A `HashMap` of post links with a key of channel name and a value of a
+/// `HashMap`. The inner `HashMap` has a key of the hash of a linked post and
+/// a value of the hashes of the posts linking to it.
+pub type LinkHashMap = HashMap<SharedChannel, HashMap<Hash, HashSet<Hash>>>;
 
 /// A `HashMap` of channel topics with a key of channel name and a value of a
 /// `BTreeMap`. The `BTreeMap` has a key of timestamp and a value of a tuple
 /// of topic and hash. The hash is of the `post/topic` post which defined the
 /// stored topic.
-pub type TopicHashMap = HashMap<Channel, BTreeMap<Timestamp, (Topic, Hash)>>;
+pub type TopicHashMap = HashMap<SharedChannel, BTreeMap<Timestamp, (Topic, Hash)>>;
 
 #[async_trait::async_trait]
 /// Storage trait with methods for storing and retrieving cryptographic
 /// keypairs, hashes and posts.
-pub trait Store: Clone + Send + Sync + Unpin + 'static {
+pub trait Store: StoreClone + Send + Sync + Unpin + 'static {
     // TODO: Getters do not need a mutable reference to self.
     //
-    /// Retrieve the keypair associated with the store.
+    /// Retrieve the keypair of the active identity of the store.
     async fn get_keypair(&self) -> Option<Keypair>;
 
-    /// Define the keypair associated with the store.
+    /// Define the keypair associated with the store, adding it to the
+    /// identities of the store and making it the active identity.
     async fn set_keypair(&mut self, keypair: Keypair);
 
+    /// Retrieve the public keys of all identities held by the store.
+    async fn list_identities(&self) -> Vec<PublicKey>;
+
+    /// Add the given keypair to the identities of the store without
+    /// changing the active identity.
+    async fn insert_identity(&mut self, keypair: Keypair);
+
+    /// Make the identity with the given public key the active identity, so
+    /// that subsequent posts are authored by it.
+    ///
+    /// An error is returned if the store does not hold the identity.
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error>;
+
+    /// Generate a new keypair and add it to the identities of the store
+    /// without changing the active identity, returning the public key.
+    async fn create_identity(&mut self) -> PublicKey {
+        let keypair = crypto::generate_keypair();
+        self.insert_identity(keypair).await;
+
+        keypair.0
+    }
+
     /// Retrieve the keypair associated with the store, creating a new keypair
     /// if one does not yet exist.
     async fn get_or_create_keypair(&mut self) -> Keypair {
         if let Some(kp) = self.get_keypair().await {
             kp
         } else {
-            let (pk, sk) = crypto::sign::gen_keypair();
-            let kp = (
-                pk.as_ref().try_into().unwrap(),
-                sk.as_ref().try_into().unwrap(),
-            );
+            let kp = crypto::generate_keypair();
             self.set_keypair(kp).await;
             kp
         }
     }
 
+    /// Export the keypair associated with the store as an identity protected
+    /// by the given passphrase, creating a new keypair if one does not yet
+    /// exist.
+    ///
+    /// The identity may be imported into another store with
+    /// `import_identity()`.
+    async fn export_identity(&mut self, passphrase: &str) -> Result<Vec<u8>, Error> {
+        let keypair = self.get_or_create_keypair().await;
+
+        encrypt_keypair(&keypair, passphrase)
+    }
+
+    /// Add the given identity to the store and make it the active identity,
+    /// returning the public key of the imported identity.
+    async fn import_identity(
+        &mut self,
+        identity: &[u8],
+        passphrase: &str,
+    ) -> Result<PublicKey, Error> {
+        let keypair = decrypt_keypair(identity, passphrase)?;
+        self.set_keypair(keypair).await;
+
+        Ok(keypair.0)
+    }
+
+    /// Write an archive of the posts in the store to the given writer,
+    /// returning the number of archived posts.
+    ///
+    /// The archive contains every indexed post of the exported channels
+    /// (including the posts which define the channel state) and all posts
+    /// without a channel. The keypair is included as a passphrase-protected
+    /// identity if `opts.passphrase` is defined.
+    async fn export(
+        &mut self,
+        writer: &mut (dyn Write + Send),
+        opts: &ExportOptions,
+    ) -> Result<usize, Error> {
+        let identity = match &opts.passphrase {
+            Some(passphrase) => Some(self.export_identity(passphrase).await?),
+            None => None,
+        };
+
+        let channels = match &opts.channels {
+            Some(channels) => channels.clone(),
+            None => self.get_channels().await.unwrap_or_default(),
+        };
+
+        let mut hashes = HashSet::new();
+        let mut payloads = Vec::new();
+        for channel in channels {
+            let mut posts = self
+                .get_posts(&ChannelOptions::new(channel.clone(), 0, 0, 0))
+                .await;
+            while let Some(post) = posts.next().await {
+                let post = post?;
+                if hashes.insert(post.hash()?) {
+                    payloads.push(post.to_bytes()?);
+                }
+            }
+
+            // The channel state includes posts which are not returned by
+            // `get_posts()`, such as `post/join` and `post/delete` posts.
+            for hash in self.get_channel_state_hashes(&channel).await {
+                if hashes.insert(hash) {
+                    if let Some(payload) = self.get_post_payload(&hash).await {
+                        payloads.push(payload);
+                    }
+                }
+            }
+        }
+
+        let archive = Archive { identity, payloads };
+        archive.write(writer)?;
+
+        Ok(archive.payloads.len())
+    }
+
+    /// Import the archive read from the given reader into the store,
+    /// returning the number of imported posts.
+    ///
+    /// The keypair of the store is replaced by the archived identity if the
+    /// archive contains an identity and a passphrase is given. Posts with an
+    /// invalid signature are skipped.
+    async fn import(
+        &mut self,
+        reader: &mut (dyn Read + Send),
+        passphrase: Option<&str>,
+    ) -> Result<usize, Error> {
+        let archive = Archive::read(reader)?;
+
+        if let (Some(identity), Some(passphrase)) = (&archive.identity, passphrase) {
+            self.import_identity(identity, passphrase).await?;
+        }
+
+        let mut imported = 0;
+        for payload in archive.payloads {
+            if !Post::verify(&payload) {
+                continue;
+            }
+            let (_s, post) = Post::from_bytes(&payload)?;
+            self.insert_post(&post).await?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+
     /// Retrieve all channels from the store.
     async fn get_channels(&self) -> Option<Vec<Channel>>;
 
     /// Insert the given channel into the store.
     async fn insert_channel(&mut self, channel: &Channel);
 
+    /// Retrieve all channels archived by the local user, sorted by name.
+    async fn get_archived_channels(&self) -> Vec<Channel>;
+
+    /// Query if the given channel has been archived by the local user.
+    async fn is_channel_archived(&self, channel: &Channel) -> bool;
+
+    /// Set or clear the local archival flag of the given channel. The posts
+    /// of an archived channel are retained.
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool);
+
+    /// Remove the given channel from the channel list, along with its
+    /// archival flag and last-read position. The posts of the channel are
+    /// left in place; see `drop_channel()`.
+    async fn remove_channel(&mut self, channel: &Channel);
+
+    /// Delete all posts made to the given channel and remove the channel from
+    /// the store, returning the number of deleted posts.
+    ///
+    /// Posts which are not bound to a channel, such as `post/info` and
+    /// `post/delete` posts, are retained since they are shared by all
+    /// channels. No tombstones are recorded, so the channel may be synced
+    /// again later. Persistent stores partition posts by channel, so the cost
+    /// of this method scales with the number of posts in the channel.
+    async fn drop_channel(&mut self, channel: &Channel) -> Result<usize, Error> {
+        let mut hashes = Vec::new();
+        let mut stream = self
+            .get_post_hashes(&ChannelOptions::new(channel.clone(), 0, 0, 0))
+            .await;
+        while let Some(hash) = stream.next().await {
+            hashes.push(hash?);
+        }
+        drop(stream);
+
+        for hash in &hashes {
+            self.delete_post(hash).await;
+        }
+        self.remove_channel(channel).await;
+
+        Ok(hashes.len())
+    }
+
     /// Retrieve all members of the given channel.
     async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>>;
 
@@ -162,14 +462,77 @@ pub trait Store: Clone + Send + Sync + Unpin + 'static {
     /// Remove the info post data for the given post hash.
     async fn remove_info_hash(&mut self, hash: &Hash);
 
-    /// Retrieve the hash(es) of the most recently published post(s) in the
-    /// given channel.
+    /// Retrieve all stored `post/info` posts authored by the given public
+    /// key, oldest first.
+    ///
+    /// Every info post is retained rather than only the latest, so the
+    /// history shows the names a user was previously known by.
+    async fn get_user_info_history(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Vec<UserInfoEntry>, Error> {
+        let hashes = self.get_info_hashes(public_key).await.unwrap_or_default();
+
+        let mut history = Vec::new();
+        for payload in self.get_post_payloads(&hashes).await {
+            let (_s, post) = Post::from_bytes(&payload)?;
+            let hash = post.hash()?;
+            if let PostBody::Info { info } = post.body {
+                history.push(UserInfoEntry {
+                    timestamp: post.header.timestamp,
+                    hash,
+                    info,
+                });
+            }
+        }
+        history.sort_by_key(|entry| (entry.timestamp, entry.hash));
+
+        Ok(history)
+    }
+
+    /// Retrieve the hashes of the current heads of the given channel: the
+    /// posts of the channel which are not linked to by any other known post.
     ///
-    /// More than one hash will be returned if several posts were
-    /// made to the same channel at the same time. Usually though, only one
-    /// hash or no hashes will be returned.
+    /// More than one hash will be returned if posts were made to the channel
+    /// concurrently. A new post linking to all heads merges them into a
+    /// single head.
     async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>>;
 
+    /// Insert the given post hash into the heads of the given channel.
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash);
+
+    /// Remove the given post hash from the heads of the given channel.
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash);
+
+    /// Record that the post represented by the given hash, made to the given
+    /// channel, links to the post represented by `link`.
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash);
+
+    /// Remove the record that the post represented by the given hash links
+    /// to the post represented by `link`.
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash);
+
+    /// Query whether any known post made to the given channel links to the
+    /// post represented by the given hash.
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool;
+
+    /// Update the heads of the given channel with the given post, which
+    /// links to the given hashes.
+    ///
+    /// The linked posts are no longer heads. The post becomes a head unless a
+    /// known post already links to it, as happens when posts are received
+    /// out of order.
+    async fn update_channel_heads(&mut self, channel: &Channel, hash: &Hash, links: &[Hash]) {
+        for link in links {
+            self.insert_post_link(channel, hash, link).await;
+            self.remove_channel_head(channel, link).await;
+        }
+
+        if !self.is_post_linked(channel, hash).await {
+            self.insert_channel_head(channel, hash).await;
+        }
+    }
+
     /// Retrieve the latest `post/info` name and hash for the given public key.
     async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)>;
 
@@ -186,166 +549,1194 @@ pub trait Store: Clone + Send + Sync + Unpin + 'static {
     /// Remove the peer name data for the given post hash.
     async fn remove_peer_name(&mut self, hash: &Hash);
 
-    /// Retrieve all posts matching the parameters defined by the given
-    /// `ChannelOptions`.
-    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream;
-
-    /// Retrieve all posts matching the parameters defined by the given
-    /// `ChannelOptions` and continue to return new messages as they become
-    /// available (stream remains active).
-    async fn get_posts_live<'a>(&'a mut self, opts: &ChannelOptions) -> PostStream;
-
-    /// Retrieve the hashes of all posts matching the parameters defined by the
-    /// given `ChannelOptions`.
-    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream;
+    /// Retrieve the latest `post/info` avatar and hash for the given public
+    /// key.
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)>;
 
-    /// Insert the given post into the store and return the hash.
-    async fn insert_post(&mut self, post: &Post) -> Result<Hash, Error>;
+    /// Insert the given avatar, timestamp and hash into the store.
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    );
 
-    /// Remove the given post from the posts and post hashes stores.
-    async fn remove_post(&mut self, hash: &Hash);
+    /// Remove the peer avatar data for the given post hash.
+    async fn remove_peer_avatar(&mut self, hash: &Hash);
 
-    /// Delete the given post from all stores, leaving no trace.
+    /// Retrieve the hashes of all posts comprising the current state of the
+    /// given channel.
     ///
-    /// This method combines several removal methods to achieve complete
-    /// removal of the post.
-    async fn delete_post(&mut self, hash: &Hash);
-
-    /// Update the posts store by inserting the given post.
+    /// The channel state consists of the latest `post/join` or `post/leave`
+    /// post of each member and ex-member, the latest `post/topic` post and the
+    /// latest name-setting and avatar-setting `post/info` posts of each member
+    /// and ex-member. The hashes of all `post/delete` posts authored by channel
+    /// members are also included, allowing peers to learn of deleted state.
     ///
-    /// This method is more specific than `insert_post()`. It updates only
-    /// the `posts` store, whereas `insert_post()` is responsible for updating
-    /// multiple stores based on the post type.
-    async fn update_posts(
-        &mut self,
-        post: &Post,
-        channel: Option<Channel>,
-        timestamp: &Timestamp,
-        hash: Hash,
-    );
+    /// The hashes are read from the channel membership, topic, delete, peer
+    /// name and peer avatar indexes, all of which are updated by `insert_post()`, so the
+    /// cost of this method scales with the number of members rather than the
+    /// number of posts.
+    async fn get_channel_state_hashes(&self, channel: &Channel) -> Vec<Hash> {
+        let mut hashes = Vec::new();
+
+        // Return the latest join or leave post hash of each member and
+        // ex-member.
+        if let Some(membership_hashes) = self.get_channel_membership_hashes(channel).await {
+            hashes.extend(membership_hashes)
+        }
 
-    /// Retrieve the post payload for the post represented by the given hash.
-    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload>;
+        // Return the latest topic post hash for this channel.
+        if let Some((_topic, topic_hash)) = self.get_channel_topic_and_hash(channel).await {
+            hashes.push(topic_hash)
+        }
 
-    /// Retrieve the post payloads for all posts represented by the given hashes.
-    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload>;
+        // Return all delete post hashes and the most-recent name-setting and
+        // avatar-setting info post hashes for each member.
+        if let Some(channel_members) = self.get_channel_members(channel).await {
+            for public_key in channel_members {
+                if let Some(delete_hashes) = self.get_delete_hashes(&public_key).await {
+                    hashes.extend(delete_hashes)
+                }
 
-    /// Insert the given hash and post payload into the store.
-    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload);
+                if let Some((_name, name_hash)) = self.get_peer_name_and_hash(&public_key).await {
+                    hashes.push(name_hash)
+                }
 
-    /// Remove the given post from the post payloads store.
-    async fn remove_post_payload(&mut self, hash: &Hash);
+                // The name and avatar may be set by the same info post.
+                if let Some((_avatar, avatar_hash)) =
+                    self.get_peer_avatar_and_hash(&public_key).await
+                {
+                    if !hashes.contains(&avatar_hash) {
+                        hashes.push(avatar_hash)
+                    }
+                }
+            }
+        }
 
-    /// Send the given post to each live stream for which the channel option
-    /// criteria are satisfied.
-    async fn send_post_to_live_streams(&self, post: &Post, channel: &Channel);
+        // Return the most-recent name-setting and avatar-setting info post
+        // hashes for each ex-member.
+        if let Some(ex_channel_members) = self.get_ex_channel_members(channel).await {
+            for public_key in ex_channel_members {
+                if let Some((_name, name_hash)) = self.get_peer_name_and_hash(&public_key).await {
+                    hashes.push(name_hash)
+                }
 
-    /// Retrieve the hashes of all posts representing the subset of the given
-    /// hashes for which post data is not available locally (ie. the hashes of
-    /// all posts which are not already in the store).
-    async fn want(&self, hashes: &[Hash]) -> Vec<Hash>;
-}
+                if let Some((_avatar, avatar_hash)) =
+                    self.get_peer_avatar_and_hash(&public_key).await
+                {
+                    if !hashes.contains(&avatar_hash) {
+                        hashes.push(avatar_hash)
+                    }
+                }
+            }
+        }
 
-#[derive(Clone)]
-/// An in-memory store containing a keypair and post data.
-pub struct MemoryStore {
-    keypair: Keypair,
-    /// All channels in the store.
-    channels: Arc<RwLock<BTreeSet<Channel>>>,
-    /// The public keys of all members, indexed by channel.
-    ///
-    /// This map is updated according to received / published `post/join`
-    /// and `post/leave` posts.
-    channel_members: Arc<RwLock<HashMap<Channel, Vec<PublicKey>>>>,
-    /// The public keys of all ex-members, indexed by channel.
-    ///
-    /// This map is updated according to received / published `post/join`
-    /// and `post/leave` posts.
-    ex_channel_members: Arc<RwLock<HashMap<Channel, Vec<PublicKey>>>>,
-    /// The hash of the latest `post/join` or `post/leave` post for each known
-    /// peer, indexed by channel (the outer key) and public key (the first
-    /// element of the tuple).
-    channel_membership: Arc<RwLock<HashMap<Channel, HashMap<PublicKey, Hash>>>>,
-    /// The topic, timestamp and hash of the latest `post/topic` post for each
-    /// known channel, indexed by channel.
-    channel_topics: Arc<RwLock<TopicHashMap>>,
-    /// The hashes of all known `post/delete` posts.
-    delete_hashes: Arc<RwLock<HashMap<PublicKey, Vec<Hash>>>>,
-    /// The hashes of all known `post/info` posts.
-    info_hashes: Arc<RwLock<HashMap<PublicKey, Vec<Hash>>>>,
-    /// The nickname, timestamp and hash of the latest `post/info` post for
-    /// each known peer, indexed by public key.
-    peer_names: Arc<RwLock<NameHashMap>>,
-    /// All posts and hashes in the store divided according to channel (the
-    /// outer key) and indexed by timestamp (the inner key).
-    posts: Arc<RwLock<PostMap>>,
-    /// Binary payloads for all posts in the store, indexed by the post hash.
-    post_payloads: Arc<RwLock<HashMap<Hash, Payload>>>,
-    /// An empty `BTreeMap` of posts and hashes, indexed by timestamp.
-    empty_post_bt: BTreeMap<u64, Vec<(Post, Hash)>>,
-    /// All active live streams, indexed by channel.
-    live_streams: Arc<RwLock<LiveStreamMap>>,
-    /// The unique identifier of a live stream.
-    live_stream_id: Arc<Mutex<usize>>,
-}
+        hashes
+    }
 
-impl Default for MemoryStore {
-    fn default() -> Self {
-        // Generate a new public-private keypair.
-        let (pk, sk) = crypto::sign::gen_keypair();
+    /// Retrieve all posts matching the parameters defined by the given
+    /// `ChannelOptions`.
+    async fn get_posts(&self, opts: &ChannelOptions) -> PostStream;
 
-        Self {
-            keypair: (
-                pk.as_ref().try_into().unwrap(),
-                sk.as_ref().try_into().unwrap(),
-            ),
-            channels: Arc::new(RwLock::new(BTreeSet::new())),
-            channel_members: Arc::new(RwLock::new(HashMap::new())),
-            ex_channel_members: Arc::new(RwLock::new(HashMap::new())),
-            channel_membership: Arc::new(RwLock::new(HashMap::new())),
-            channel_topics: Arc::new(RwLock::new(HashMap::new())),
-            delete_hashes: Arc::new(RwLock::new(HashMap::new())),
-            info_hashes: Arc::new(RwLock::new(HashMap::new())),
-            peer_names: Arc::new(RwLock::new(HashMap::new())),
-            posts: Arc::new(RwLock::new(HashMap::new())),
-            post_payloads: Arc::new(RwLock::new(HashMap::new())),
-            empty_post_bt: BTreeMap::new(),
-            live_streams: Arc::new(RwLock::new(HashMap::new())),
-            live_stream_id: Arc::new(Mutex::new(0)),
+    /// Retrieve all posts matching the parameters defined by the given
+    /// `ChannelOptions` in causal order.
+    ///
+    /// Each post follows the posts it links to, so that posts are returned
+    /// in the order in which their authors saw them even when peer clocks are
+    /// skewed. Concurrent posts are ordered by timestamp. Errors encountered
+    /// while retrieving the posts are returned after the ordered posts.
+    async fn get_posts_causal(&self, opts: &ChannelOptions) -> PostStream {
+        let mut posts = Vec::new();
+        let mut errors = Vec::new();
+        {
+            let mut stream = self.get_posts(opts).await;
+            while let Some(post) = stream.next().await {
+                match post {
+                    Ok(post) => posts.push(post),
+                    Err(err) => errors.push(err),
+                }
+            }
         }
-    }
-}
 
-#[async_trait::async_trait]
-impl Store for MemoryStore {
-    async fn get_keypair(&self) -> Option<Keypair> {
-        Some(self.keypair)
+        let items: Vec<Result<Post, Error>> = match causal_order(posts) {
+            Ok(posts) => posts
+                .into_iter()
+                .map(Ok)
+                .chain(errors.into_iter().map(Err))
+                .collect(),
+            Err(err) => std::iter::once(Err(err))
+                .chain(errors.into_iter().map(Err))
+                .collect(),
+        };
+
+        Box::new(stream::from_iter(items))
     }
 
-    async fn set_keypair(&mut self, keypair: Keypair) {
-        self.keypair = keypair;
+    /// Retrieve a page of at most `limit` posts of the given channel, newest
+    /// first, positioned before the given cursor. The first page is retrieved
+    /// with a cursor of `None`, and each subsequent page with the `next`
+    /// cursor of the previous page. A limit of 0 retrieves all posts.
+    ///
+    /// Posts without a channel, such as `post/info` posts, are not included.
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        let time_end = before.map_or(0, |before| before.timestamp.saturating_add(1));
+        let mut stream = self
+            .get_posts(&ChannelOptions::new(channel.clone(), 0, time_end, 0))
+            .await;
+
+        let mut posts = Vec::new();
+        while let Some(post) = stream.next().await {
+            let post = post?;
+            if post.get_channel() != Some(channel) {
+                continue;
+            }
+            let cursor = PageCursor {
+                timestamp: post.get_timestamp(),
+                hash: post.hash()?,
+            };
+            if before.is_none_or(|before| cursor < before) {
+                posts.push((cursor, post));
+            }
+        }
+
+        Ok(PostPage::from_posts(posts, limit))
     }
 
-    async fn get_channels(&self) -> Option<Vec<Channel>> {
-        let channels = self.channels.read().await;
+    /// Retrieve the hashes of the posts of the given channel which mention the
+    /// local user, ordered by timestamp.
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash>;
 
-        if channels.is_empty() {
-            None
-        } else {
-            Some(channels.iter().cloned().collect())
+    /// Record that the post with the given hash, channel and timestamp
+    /// mentions the local user.
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash);
+
+    /// Remove the post with the given hash from the mention index.
+    async fn remove_mention(&mut self, hash: &Hash);
+
+    /// Retrieve the hashes of the replies to the post with the given hash,
+    /// ordered by timestamp.
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash>;
+
+    /// Record that the reply with the given hash and timestamp replies to the
+    /// post with the given parent hash.
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash);
+
+    /// Remove the reply with the given hash from the reply index.
+    async fn remove_reply(&mut self, hash: &Hash);
+
+    /// Retrieve the tree of replies to the post with the given root hash.
+    ///
+    /// The tree is assembled from the reply index, which is updated by
+    /// `insert_post()`. A deleted reply is omitted from the tree, along with
+    /// the replies to it.
+    async fn get_thread(&self, root_hash: &Hash) -> Thread {
+        let mut replies = HashMap::new();
+        let mut pending = vec![*root_hash];
+        while let Some(hash) = pending.pop() {
+            if replies.contains_key(&hash) {
+                continue;
+            }
+            let children = self.get_replies(&hash).await;
+            pending.extend(children.iter().copied());
+            replies.insert(hash, children);
         }
-    }
 
-    async fn insert_channel(&mut self, channel: &Channel) {
-        let mut channel_store = self.channels.write().await;
-        channel_store.insert(channel.to_owned());
+        Thread::assemble(*root_hash, &replies)
     }
 
-    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
+    /// Retrieve the hashes of the reaction posts to the post with the given
+    /// hash, ordered by timestamp.
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash>;
+
+    /// Record that the reaction post with the given hash and timestamp
+    /// reacts to the post with the given target hash.
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash);
+
+    /// Remove the reaction post with the given hash from the reaction index.
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash);
+
+    /// Retrieve the author and hash of each archive post of the given
+    /// channel, ordered by hash.
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)>;
+
+    /// Record that the archive post with the given hash, authored by the
+    /// given public key, archives the given channel.
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    );
+
+    /// Remove the archive post with the given hash from the archive index.
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash);
+
+    /// Retrieve the binary blob with the given hash.
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>>;
+
+    /// Insert the given binary blob, indexed by the given hash, without
+    /// checking the hash or the blob quota; see `store_blob()`.
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>);
+
+    /// Remove the binary blob with the given hash.
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash);
+
+    /// Retrieve the total size of the binary blobs held by the store, in
+    /// bytes.
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64;
+
+    /// Store the given binary blob and return its hash.
+    ///
+    /// An error is returned if the store is read-only or if storing the blob
+    /// would bring the total size of the blobs of the store above the given
+    /// quota, in bytes. A blob which is already stored is not counted again.
+    #[cfg(feature = "blobs")]
+    async fn store_blob(&mut self, blob: Vec<u8>, quota: Option<u64>) -> Result<Hash, Error> {
+        if self.is_read_only() {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        let hash = match crypto::hash(&blob) {
+            Some(hash) => hash,
+            None => {
+                return CableErrorKind::NoneError {
+                    context: "failed to hash blob".to_string(),
+                }
+                .raise()
+            }
+        };
+        if self.get_blob(&hash).await.is_some() {
+            return Ok(hash);
+        }
+
+        if let Some(quota) = quota {
+            let size = blob.len() as u64;
+            if self.get_blob_usage().await.saturating_add(size) > quota {
+                return CableErrorKind::BlobQuotaExceeded { size, quota }.raise();
+            }
+        }
+        self.insert_blob(&hash, blob).await;
+
+        Ok(hash)
+    }
+
+    /// Retrieve the position of each post of the given author, ordered by
+    /// timestamp and hash.
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor>;
+
+    /// Record that the post with the given hash and timestamp was authored
+    /// by the given public key.
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    );
+
+    /// Remove the post with the given author, timestamp and hash from the
+    /// author index.
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    );
+
+    /// Retrieve a page of the posts of the given author, newest first, as for
+    /// `get_posts_page()`.
+    ///
+    /// The posts of all types are included, such as the `post/info` posts
+    /// of the author, unless the options restrict them to a channel.
+    async fn get_posts_by_author(
+        &self,
+        public_key: &PublicKey,
+        opts: &AuthorOptions,
+    ) -> Result<PostPage, Error> {
+        let mut posts = Vec::new();
+        for cursor in self.get_author_index(public_key).await.into_iter().rev() {
+            if opts.before.is_some_and(|before| cursor >= before) {
+                continue;
+            }
+            let Some(payload) = self.get_post_payload(&cursor.hash).await else {
+                continue;
+            };
+            let (_s, post) = Post::from_bytes(&payload)?;
+            if opts.channel.is_some() && post.get_channel() != opts.channel.as_ref() {
+                continue;
+            }
+            posts.push((cursor, post));
+
+            // One post beyond the limit determines whether another page
+            // exists.
+            if opts.limit > 0 && posts.len() > opts.limit {
+                break;
+            }
+        }
+
+        Ok(PostPage::from_posts(posts, opts.limit))
+    }
+
+    /// Retrieve the position of the last post read by the local user in the
+    /// given channel, if any.
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor>;
+
+    /// Set the position of the last post read by the local user in the given
+    /// channel.
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor);
+
+    /// Count the `post/text` posts of the given channel which follow its
+    /// last-read position (or all of them, if the channel has none),
+    /// excluding the posts authored by the active identity.
+    async fn get_unread_count(&self, channel: &Channel) -> Result<usize, Error> {
+        let last_read = self.get_last_read(channel).await;
+        let public_key = self.get_keypair().await.map(|(public_key, _)| public_key);
+
+        let time_start = last_read.map_or(0, |last_read| last_read.timestamp);
+        let mut stream = self
+            .get_posts(&ChannelOptions::new(channel.clone(), time_start, 0, 0))
+            .await;
+
+        let mut unread = 0;
+        while let Some(post) = stream.next().await {
+            let post = post?;
+            if post.get_channel() != Some(channel) || Some(post.get_public_key()) == public_key {
+                continue;
+            }
+            let cursor = PageCursor {
+                timestamp: post.get_timestamp(),
+                hash: post.hash()?,
+            };
+            if last_read.is_none_or(|last_read| cursor > last_read) {
+                unread += 1;
+            }
+        }
+
+        Ok(unread)
+    }
+
+    /// Retrieve all posts matching the parameters defined by the given
+    /// `ChannelOptions` and continue to return new messages as they become
+    /// available (stream remains active).
+    async fn get_posts_live(&self, opts: &ChannelOptions) -> PostStream {
+        // Watch the channel before retrieving the stored posts, so that no
+        // post inserted in the meantime is missed.
+        let events = self.watch(&opts.channel).await;
+        let posts = self.get_posts(opts).await;
+
+        let opts = opts.clone();
+        let live_posts = events.filter_map(move |event| match event {
+            StoreEvent::PostInserted { post, .. } if post_stream::matches(&opts, &post) => {
+                Some(Ok(post))
+            }
+            _ => None,
+        });
+
+        Box::new(posts.chain(live_posts))
+    }
+
+    /// Watch the given channel, returning a stream of events describing the
+    /// posts affecting the channel which are inserted into or deleted from
+    /// the store from now on.
+    ///
+    /// Any number of watchers may be active at once. A watcher is removed
+    /// once its stream is dropped.
+    async fn watch(&self, channel: &Channel) -> EventStream<'static>;
+
+    /// Send the given event to all watchers of the given channel.
+    async fn send_event(&self, channel: &Channel, event: StoreEvent);
+
+    /// Retrieve the hashes of all posts matching the parameters defined by the
+    /// given `ChannelOptions`.
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream;
+
+    /// Query whether the store was opened read-only.
+    ///
+    /// A read-only store serves its existing posts but refuses to insert new
+    /// ones. The default implementation returns `false`.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Insert the given post into the store and return the hash.
+    ///
+    /// The post is indexed according to its type by calling the more
+    /// specific insertion methods of the store. An error is returned if the
+    /// store is read-only.
+    async fn insert_post(&mut self, post: &Post) -> Result<Hash, Error> {
+        if self.is_read_only() {
+            return CableErrorKind::StoreReadOnly {}.raise();
+        }
+
+        let timestamp = &post.get_timestamp();
+
+        let hash = post.hash()?;
+
+        // Do not store a post which has previously been deleted.
+        if self.is_tombstone(&hash).await {
+            return Ok(hash);
+        }
+
+        // Store the payload before indexing the post, so that a store whose
+        // indexes were left incomplete by an interrupted batch can rebuild
+        // them from the stored payloads.
+        if !matches!(post.body, PostBody::Unrecognized { .. }) {
+            self.insert_post_payload(&hash, post.to_bytes()?).await;
+        }
+
+        match &post.body {
+            PostBody::Text { channel, text } => {
+                // Insert the post into the `posts` store.
+                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
+                    .await;
+
+                // Index the post as a reply if it carries a reply marker
+                // naming one of its links.
+                if let Some((parent, _text)) = thread::parse_reply(text) {
+                    if post.header.links.contains(&parent) {
+                        self.insert_reply(&parent, *timestamp, &hash).await;
+                    }
+                }
+            }
+            PostBody::Join { channel } => {
+                let public_key = &post.get_public_key();
+
+                self.update_channel_membership_hashes(channel, public_key, &hash)
+                    .await;
+                self.insert_channel_member(channel, public_key).await;
+                self.remove_ex_channel_member(channel, public_key).await;
+            }
+            PostBody::Leave { channel } => {
+                let public_key = &post.get_public_key();
+
+                self.update_channel_membership_hashes(channel, public_key, &hash)
+                    .await;
+                self.remove_channel_member(channel, public_key).await;
+                self.insert_ex_channel_member(channel, public_key).await;
+            }
+            PostBody::Topic { channel, topic } => {
+                // Insert the post into the `posts` store.
+                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
+                    .await;
+                self.insert_channel_topic(channel, topic, timestamp, &hash)
+                    .await;
+            }
+            #[cfg(feature = "reactions")]
+            PostBody::Reaction {
+                channel, target, ..
+            } => {
+                // Insert the post into the `posts` store.
+                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
+                    .await;
+                self.insert_reaction(target, *timestamp, &hash).await;
+            }
+            #[cfg(feature = "archive-posts")]
+            PostBody::Archive { channel } => {
+                // Insert the post into the `posts` store.
+                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
+                    .await;
+                self.insert_channel_archive(channel, &post.get_public_key(), &hash)
+                    .await;
+            }
+            PostBody::Delete { hashes } => {
+                let public_key = &post.get_public_key();
+
+                for post_hash in hashes {
+                    if let Some(payload) = self.get_post_payload(post_hash).await {
+                        // TODO: Consider whether it is more efficient to
+                        // decode the payload or retrieve the post from the
+                        // `posts` store.
+                        let (_s, stored_post) = Post::from_bytes(&payload)?;
+                        // Only delete the post if the author matches the
+                        // author of the `post/delete` post.
+                        if post.get_public_key() == stored_post.get_public_key() {
+                            // Delete the post from all stores, retaining
+                            // a tombstone.
+                            self.delete_posts(&[*post_hash]).await;
+                            // The hash of the `post/delete` post is inserted,
+                            // not the hash of the post referenced by the
+                            // `post/delete` post.
+                            self.insert_delete_hash(public_key, &hash).await;
+                        }
+                    }
+                }
+            }
+            PostBody::Info { info } => {
+                // Insert the post into the `posts` store.
+                self.update_posts(post, None, timestamp, hash).await;
+
+                let public_key = &post.get_public_key();
+
+                // Insert the public key of the post author and the assigned
+                // name if the key of the info element is "name", or the
+                // assigned avatar if the key is "avatar".
+                for UserInfo { key, val } in info {
+                    if key == "name" {
+                        self.insert_peer_name(public_key, val, timestamp, &hash)
+                            .await;
+                    } else if key == "avatar" {
+                        if let Ok(avatar) = Avatar::parse(val) {
+                            self.insert_peer_avatar(public_key, &avatar, timestamp, &hash)
+                                .await;
+                        }
+                    }
+                }
+
+                self.insert_info_hash(public_key, &hash).await;
+            }
+            _ => {}
+        }
+
+        // Index the post by its author if its payload was stored.
+        if !matches!(post.body, PostBody::Unrecognized { .. }) {
+            self.insert_author_post(&post.get_public_key(), *timestamp, &hash)
+                .await;
+        }
+
+        let channel = post.get_channel();
+
+        // Update the store of known channels and the channel heads.
+        if let Some(channel) = channel {
+            self.insert_channel(channel).await;
+            self.update_channel_heads(channel, &hash, &post.header.links)
+                .await;
+        }
+
+        // Notify the watchers of the affected channels. Info and delete
+        // posts affect each channel of which the author is a member.
+        let event = StoreEvent::PostInserted {
+            hash,
+            post: post.clone(),
+        };
+        if let Some(channel) = channel {
+            self.send_event(channel, event).await;
+        } else if matches!(post.body, PostBody::Info { .. } | PostBody::Delete { .. }) {
+            let public_key = post.get_public_key();
+            for channel in self.get_channels().await.unwrap_or_default() {
+                if self.is_channel_member(&channel, &public_key).await {
+                    self.send_event(&channel, event.clone()).await;
+                }
+            }
+        }
+
+        Ok(hash)
+    }
+
+    /// Insert the given posts into the store in a single batch and return
+    /// their hashes, in order.
+    ///
+    /// Committing the whole batch at once is much faster than inserting each
+    /// post of a post response separately in persistent stores. If any post
+    /// fails to be inserted, the batch is rolled back.
+    async fn insert_posts(&mut self, posts: &[Post]) -> Result<Vec<Hash>, Error> {
+        self.begin_batch().await?;
+
+        let mut hashes = Vec::with_capacity(posts.len());
+        for post in posts {
+            match self.insert_post(post).await {
+                Ok(hash) => hashes.push(hash),
+                Err(err) => {
+                    // The failed insert is reported rather than any failure
+                    // to roll back.
+                    let _ = self.rollback_batch().await;
+                    return Err(err);
+                }
+            }
+        }
+
+        self.commit_batch().await?;
+
+        Ok(hashes)
+    }
+
+    /// Begin a batch of writes, which are applied atomically once committed
+    /// with `commit_batch()`, or discarded with `rollback_batch()`.
+    ///
+    /// The manager inserts each post within a batch, so that a crash while
+    /// the post is stored and its derived indexes (channel heads, channel
+    /// state and the author index) are updated does not leave the indexes
+    /// diverged from the stored posts. The SQLite store runs the batch in a
+    /// transaction; the sled store rebuilds its indexes from the stored posts
+    /// when it is reopened after an interrupted batch.
+    ///
+    /// Batches do not nest, and the batches of the handles of a store run one
+    /// at a time. The default implementation does nothing, which suits stores
+    /// which are not persisted.
+    async fn begin_batch(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Commit the batch of writes begun by `begin_batch()`.
+    async fn commit_batch(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Discard the batch of writes begun by `begin_batch()`, in place of
+    /// committing it once one of its writes has failed.
+    async fn rollback_batch(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Remove the given post from the posts and post hashes stores.
+    async fn remove_post(&mut self, hash: &Hash);
+
+    /// Delete the given post from all stores, leaving no trace.
+    ///
+    /// This method combines several removal methods to achieve complete
+    /// removal of the post.
+    async fn delete_post(&mut self, hash: &Hash) {
+        // Remove the post from the channel heads, restoring the posts it
+        // linked to as heads if no other post links to them. The links of a
+        // post which is not a head are retained, since the posts linking to
+        // it still succeed the posts it linked to.
+        let post = self
+            .get_post_payload(hash)
+            .await
+            .and_then(|payload| Post::from_bytes(&payload).ok())
+            .map(|(_s, post)| post);
+        if let Some(post) = post {
+            self.remove_author_post(&post.get_public_key(), post.get_timestamp(), hash)
+                .await;
+
+            let channel = post.get_channel();
+            if let Some(channel) = channel {
+                self.send_event(channel, StoreEvent::PostDeleted { hash: *hash })
+                    .await;
+            }
+            let is_head = match channel {
+                Some(channel) => self
+                    .get_latest_hashes(channel)
+                    .await
+                    .is_some_and(|heads| heads.contains(hash)),
+                None => false,
+            };
+            if let (Some(channel), true) = (channel, is_head) {
+                self.remove_channel_head(channel, hash).await;
+                for link in &post.header.links {
+                    self.remove_post_link(channel, hash, link).await;
+                    if !self.is_post_linked(channel, link).await
+                        && self.is_channel_post(channel, link).await
+                    {
+                        self.insert_channel_head(channel, link).await;
+                    }
+                }
+            }
+        }
+
+        // Remove post from all stores.
+        self.remove_channel_topic(hash).await;
+        self.remove_channel_membership_hash(hash).await;
+        self.remove_peer_name(hash).await;
+        self.remove_peer_avatar(hash).await;
+        self.remove_info_hash(hash).await;
+        self.remove_mention(hash).await;
+        self.remove_reply(hash).await;
+        #[cfg(feature = "reactions")]
+        self.remove_reaction(hash).await;
+        #[cfg(feature = "archive-posts")]
+        self.remove_channel_archive(hash).await;
+        self.remove_post(hash).await;
+        self.remove_post_payload(hash).await;
+    }
+
+    /// Query whether the store holds the post represented by the given hash
+    /// and the post was made to the given channel.
+    async fn is_channel_post(&self, channel: &Channel, hash: &Hash) -> bool {
+        self.get_post_payload(hash)
+            .await
+            .and_then(|payload| Post::from_bytes(&payload).ok())
+            .is_some_and(|(_s, post)| post.get_channel() == Some(channel))
+    }
+
+    /// Delete the given posts from all stores, retaining the hash of each
+    /// post as a tombstone.
+    ///
+    /// Tombstoned posts are no longer wanted from peers and are not stored
+    /// again if they are received. Call `compact()` afterwards to reclaim the
+    /// space freed by the deletions in persistent stores.
+    async fn delete_posts(&mut self, hashes: &[Hash]) {
+        for hash in hashes {
+            self.delete_post(hash).await;
+            self.insert_tombstone(hash).await;
+        }
+    }
+
+    /// Insert the given hash of a deleted post into the tombstone store.
+    async fn insert_tombstone(&mut self, hash: &Hash);
+
+    /// Query whether the post represented by the given hash has been deleted.
+    async fn is_tombstone(&self, hash: &Hash) -> bool;
+
+    /// Retrieve the encoded outbound requests of local origin which remain
+    /// active, along with their request IDs.
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)>;
+
+    /// Insert the given encoded outbound request of local origin, so that it
+    /// may be reissued after a restart.
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]);
+
+    /// Remove the outbound request with the given request ID.
+    async fn remove_outbound_request(&mut self, req_id: &ReqId);
+
+    /// Retrieve the hashes of the tombstoned posts whose payloads remain
+    /// stored, such as those of a deletion which was interrupted before it
+    /// completed.
+    async fn get_pending_tombstones(&self) -> Vec<Hash> {
+        let mut pending = Vec::new();
+        for hash in self.get_post_payload_hashes().await {
+            if self.is_tombstone(&hash).await {
+                pending.push(hash);
+            }
+        }
+
+        pending
+    }
+
+    /// Delete the posts of all pending tombstones, returning the number of
+    /// posts deleted.
+    async fn purge_tombstones(&mut self) -> usize {
+        let pending = self.get_pending_tombstones().await;
+        for hash in &pending {
+            self.delete_post(hash).await;
+        }
+
+        pending.len()
+    }
+
+    /// Purge the posts of pending tombstones and reclaim storage space freed
+    /// by deleted posts.
+    ///
+    /// Stores which free space immediately only purge the pending tombstones.
+    async fn compact(&mut self) -> Result<(), Error> {
+        self.purge_tombstones().await;
+
+        Ok(())
+    }
+
+    /// Report the storage space which `compact()` may reclaim.
+    ///
+    /// The default implementation counts the pending tombstones; persistent
+    /// stores override it to report their disk usage and, where the backend
+    /// reports it, the number of reclaimable bytes.
+    async fn compaction_stats(&self) -> Result<CompactionStats, Error> {
+        Ok(CompactionStats {
+            pending_tombstones: self.get_pending_tombstones().await.len(),
+            ..CompactionStats::default()
+        })
+    }
+
+    /// Report the size and contents of the store.
+    ///
+    /// The default implementation counts the channel posts of each channel;
+    /// stores override it to report their total post count, tombstones,
+    /// index sizes and disk usage.
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        let mut metrics = StoreMetrics::default();
+        for channel in self.get_channels().await.unwrap_or_default() {
+            let posts = self.get_posts_page(&channel, None, 0).await?.posts.len();
+            metrics.total_posts += posts;
+            metrics.posts_per_channel.insert(channel, posts);
+        }
+
+        Ok(metrics)
+    }
+
+    /// Prune the channel posts which fall outside the given retention policy
+    /// at the given time, returning the hashes of the pruned posts.
+    ///
+    /// Pruned posts are deleted and tombstoned. Posts which define the
+    /// current channel state are retained.
+    async fn prune(
+        &mut self,
+        policy: &RetentionPolicy,
+        now: Timestamp,
+    ) -> Result<Vec<Hash>, Error> {
+        let cutoff = policy.cutoff(now);
+
+        let mut pruned = Vec::new();
+        for channel in self.get_channels().await.unwrap_or_default() {
+            let state_hashes: HashSet<Hash> = self
+                .get_channel_state_hashes(&channel)
+                .await
+                .into_iter()
+                .collect();
+
+            // Posts are retrieved newest first, so the posts beyond the
+            // maximum count are the oldest.
+            let page = self.get_posts_page(&channel, None, 0).await?;
+            let mut retained = 0;
+            for post in page.posts {
+                let hash = post.hash()?;
+                if state_hashes.contains(&hash) {
+                    continue;
+                }
+
+                let expired = cutoff.is_some_and(|cutoff| post.get_timestamp() < cutoff);
+                let excess = policy
+                    .max_posts_per_channel
+                    .is_some_and(|max_posts| retained >= max_posts);
+                if expired || excess {
+                    pruned.push(hash);
+                } else {
+                    retained += 1;
+                }
+            }
+        }
+
+        self.delete_posts(&pruned).await;
+
+        Ok(pruned)
+    }
+
+    /// Check the integrity of the store, repairing the problems found if
+    /// `repair` is `true`.
+    ///
+    /// Every stored payload is re-hashed and its signature re-validated, and
+    /// the channel post and channel state indexes are checked against the
+    /// stored payloads. Repairs remove corrupt posts and stale index entries
+    /// without tombstoning them, so that the posts may be retrieved from
+    /// peers again, and restore the payloads of valid indexed posts.
+    async fn verify_integrity(&mut self, repair: bool) -> Result<IntegrityReport, Error> {
+        let mut report = IntegrityReport {
+            repaired: repair,
+            ..IntegrityReport::default()
+        };
+
+        for hash in self.get_post_payload_hashes().await {
+            report.checked_posts += 1;
+
+            let valid = self.get_post_payload(&hash).await.is_some_and(|payload| {
+                Post::verify(&payload)
+                    && Post::from_bytes(&payload).is_ok()
+                    && crypto::hash(&payload).is_some_and(|digest| digest == hash)
+            });
+
+            if !valid {
+                report.corrupt_posts.push(hash);
+            } else if self.is_tombstone(&hash).await {
+                report.deleted_posts.push(hash);
+            }
+        }
+
+        if repair {
+            for hash in report.corrupt_posts.iter().chain(&report.deleted_posts) {
+                self.delete_post(hash).await;
+            }
+        }
+
+        let corrupt: HashSet<Hash> = report.corrupt_posts.iter().copied().collect();
+        for channel in self.get_channels().await.unwrap_or_default() {
+            let mut indexed = Vec::new();
+            {
+                let mut posts = self
+                    .get_posts(&ChannelOptions::new(channel.clone(), 0, 0, 0))
+                    .await;
+                while let Some(post) = posts.next().await {
+                    match post.and_then(|post| Ok((post.hash()?, post))) {
+                        Ok(post) => indexed.push(post),
+                        Err(_) => report.unreadable_entries += 1,
+                    }
+                }
+            }
+
+            for (hash, post) in indexed {
+                if corrupt.contains(&hash) || self.get_post_payload(&hash).await.is_some() {
+                    continue;
+                }
+                report.missing_payloads.push(hash);
+
+                if repair {
+                    // Restore the payload from the indexed post if it is
+                    // intact; otherwise remove the index entries.
+                    let payload = post.to_bytes()?;
+                    if Post::verify(&payload) && !self.is_tombstone(&hash).await {
+                        self.insert_post_payload(&hash, payload).await;
+                    } else {
+                        self.delete_post(&hash).await;
+                    }
+                }
+            }
+
+            for hash in self.get_channel_state_hashes(&channel).await {
+                if corrupt.contains(&hash) || report.missing_payloads.contains(&hash) {
+                    continue;
+                }
+                if self.get_post_payload(&hash).await.is_none() {
+                    report.missing_payloads.push(hash);
+                    if repair {
+                        self.delete_post(&hash).await;
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Update the posts store by inserting the given post.
+    ///
+    /// This method is more specific than `insert_post()`. It updates only
+    /// the `posts` store, whereas `insert_post()` is responsible for updating
+    /// multiple stores based on the post type.
+    async fn update_posts(
+        &mut self,
+        post: &Post,
+        channel: Option<Channel>,
+        timestamp: &Timestamp,
+        hash: Hash,
+    );
+
+    /// Retrieve the post payload for the post represented by the given hash.
+    async fn get_post_payload(&self, hash: &Hash) -> Option<Payload>;
+
+    /// Retrieve the post payloads for all posts represented by the given hashes.
+    async fn get_post_payloads(&self, hashes: &[Hash]) -> Vec<Payload>;
+
+    /// Retrieve the hashes of all post payloads in the store.
+    async fn get_post_payload_hashes(&self) -> Vec<Hash>;
+
+    /// Insert the given hash and post payload into the store.
+    async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload);
+
+    /// Remove the given post from the post payloads store.
+    async fn remove_post_payload(&mut self, hash: &Hash);
+
+    /// Retrieve the hashes of all posts representing the subset of the given
+    /// hashes for which post data is not available locally (ie. the hashes of
+    /// all posts which are not already in the store and have not been
+    /// deleted).
+    async fn want(&self, hashes: &[Hash]) -> Vec<Hash>;
+}
+
+#[derive(Clone)]
+/// An in-memory store containing a keypair and post data.
+pub struct MemoryStore {
+    /// The keypair of the active identity.
+    keypair: Arc<RwLock<Keypair>>,
+    /// The keypairs of all identities, indexed by public key.
+    identities: Arc<RwLock<HashMap<PublicKey, Keypair>>>,
+    /// The interned names of all channels in the store, shared by the
+    /// indexes below.
+    channel_names: ChannelInterner,
+    /// All channels in the store.
+    channels: Arc<RwLock<BTreeSet<SharedChannel>>>,
+    /// All channels archived by the local user.
+    archived_channels: Arc<RwLock<BTreeSet<SharedChannel>>>,
+    /// The public keys of all members, indexed by channel.
+    ///
+    /// This map is updated according to received / published `post/join`
+    /// and `post/leave` posts.
+    channel_members: Arc<RwLock<HashMap<SharedChannel, Vec<PublicKey>>>>,
+    /// The public keys of all ex-members, indexed by channel.
+    ///
+    /// This map is updated according to received / published `post/join`
+    /// and `post/leave` posts.
+    ex_channel_members: Arc<RwLock<HashMap<SharedChannel, Vec<PublicKey>>>>,
+    /// The hash of the latest `post/join` or `post/leave` post for each known
+    /// peer, indexed by channel (the outer key) and public key (the first
+    /// element of the tuple).
+    channel_membership: Arc<RwLock<HashMap<SharedChannel, HashMap<PublicKey, Hash>>>>,
+    /// The topic, timestamp and hash of the latest `post/topic` post for each
+    /// known channel, indexed by channel.
+    channel_topics: Arc<RwLock<TopicHashMap>>,
+    /// The hashes of all known `post/delete` posts.
+    delete_hashes: Arc<RwLock<HashMap<PublicKey, Vec<Hash>>>>,
+    /// The hashes of all known `post/info` posts.
+    info_hashes: Arc<RwLock<HashMap<PublicKey, Vec<Hash>>>>,
+    /// The nickname, timestamp and hash of the latest `post/info` post for
+    /// each known peer, indexed by public key.
+    peer_names: Arc<RwLock<NameHashMap>>,
+    /// The avatar, timestamp and hash of each avatar-setting `post/info`
+    /// post, indexed by public key.
+    peer_avatars: Arc<RwLock<AvatarHashMap>>,
+    /// All posts and hashes in the store divided according to channel (the
+    /// outer key) and indexed by timestamp (the inner key).
+    posts: Arc<RwLock<PostMap>>,
+    /// The channel and timestamp under which each post is held in `posts`,
+    /// indexed by hash.
+    post_keys: Arc<RwLock<PostKeyMap>>,
+    /// The hashes of the current heads of each channel, indexed by channel.
+    channel_heads: Arc<RwLock<HashMap<SharedChannel, BTreeSet<Hash>>>>,
+    /// The hashes of the posts linking to each linked post, indexed by
+    /// channel (the outer key) and linked post hash (the inner key).
+    post_links: Arc<RwLock<LinkHashMap>>,
+    /// Binary payloads for all posts in the store, indexed by the post hash.
+    post_payloads: Arc<RwLock<HashMap<Hash, Payload>>>,
+    /// The hashes of all deleted posts.
+    tombstones: Arc<RwLock<HashSet<Hash>>>,
+    /// Encoded outbound requests of local origin, indexed by request ID.
+    outbound_requests: Arc<RwLock<HashMap<ReqId, Vec<u8>>>>,
+    /// The position of the last read post of each channel.
+    last_read: Arc<RwLock<HashMap<SharedChannel, PageCursor>>>,
+    /// The channel and timestamp of each post mentioning the local user,
+    /// indexed by hash.
+    mentions: Arc<RwLock<HashMap<Hash, (SharedChannel, Timestamp)>>>,
+    /// The hash of the post replied to and the timestamp of each reply,
+    /// indexed by hash.
+    replies: Arc<RwLock<HashMap<Hash, (Hash, Timestamp)>>>,
+    /// The hash of the post reacted to and the timestamp of each reaction
+    /// post, indexed by hash.
+    #[cfg(feature = "reactions")]
+    reactions: Arc<RwLock<HashMap<Hash, (Hash, Timestamp)>>>,
+    /// The channel and author of each archive post, indexed by hash.
+    #[cfg(feature = "archive-posts")]
+    channel_archives: Arc<RwLock<HashMap<Hash, (SharedChannel, PublicKey)>>>,
+    /// Binary blobs, indexed by hash.
+    #[cfg(feature = "blobs")]
+    blobs: Arc<RwLock<HashMap<Hash, Vec<u8>>>>,
+    /// The position of each post, indexed by author.
+    authors: Arc<RwLock<HashMap<PublicKey, BTreeSet<PageCursor>>>>,
+    /// An empty `BTreeMap` of posts and hashes, indexed by timestamp.
+    empty_post_bt: BTreeMap<u64, Vec<(Post, Hash)>>,
+    /// All active live streams, indexed by channel.
+    live_streams: LiveStreams,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        // Generate a new public-private keypair.
+        let keypair: Keypair = crypto::generate_keypair();
+
+        Self {
+            keypair: Arc::new(RwLock::new(keypair)),
+            identities: Arc::new(RwLock::new(HashMap::from([(keypair.0, keypair)]))),
+            channel_names: ChannelInterner::default(),
+            channels: Arc::new(RwLock::new(BTreeSet::new())),
+            archived_channels: Arc::new(RwLock::new(BTreeSet::new())),
+            channel_members: Arc::new(RwLock::new(HashMap::new())),
+            ex_channel_members: Arc::new(RwLock::new(HashMap::new())),
+            channel_membership: Arc::new(RwLock::new(HashMap::new())),
+            channel_topics: Arc::new(RwLock::new(HashMap::new())),
+            delete_hashes: Arc::new(RwLock::new(HashMap::new())),
+            info_hashes: Arc::new(RwLock::new(HashMap::new())),
+            peer_names: Arc::new(RwLock::new(HashMap::new())),
+            peer_avatars: Arc::new(RwLock::new(HashMap::new())),
+            posts: Arc::new(RwLock::new(HashMap::new())),
+            post_keys: Arc::new(RwLock::new(HashMap::new())),
+            channel_heads: Arc::new(RwLock::new(HashMap::new())),
+            post_links: Arc::new(RwLock::new(HashMap::new())),
+            post_payloads: Arc::new(RwLock::new(HashMap::new())),
+            tombstones: Arc::new(RwLock::new(HashSet::new())),
+            outbound_requests: Arc::new(RwLock::new(HashMap::new())),
+            last_read: Arc::new(RwLock::new(HashMap::new())),
+            mentions: Arc::new(RwLock::new(HashMap::new())),
+            replies: Arc::new(RwLock::new(HashMap::new())),
+            #[cfg(feature = "reactions")]
+            reactions: Arc::new(RwLock::new(HashMap::new())),
+            #[cfg(feature = "archive-posts")]
+            channel_archives: Arc::new(RwLock::new(HashMap::new())),
+            #[cfg(feature = "blobs")]
+            blobs: Arc::new(RwLock::new(HashMap::new())),
+            authors: Arc::new(RwLock::new(HashMap::new())),
+            empty_post_bt: BTreeMap::new(),
+            live_streams: LiveStreams::default(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for MemoryStore {
+    async fn get_keypair(&self) -> Option<Keypair> {
+        Some(*self.keypair.read().await)
+    }
+
+    async fn set_keypair(&mut self, keypair: Keypair) {
+        self.insert_identity(keypair).await;
+        *self.keypair.write().await = keypair;
+    }
+
+    async fn list_identities(&self) -> Vec<PublicKey> {
+        let mut public_keys: Vec<PublicKey> =
+            self.identities.read().await.keys().copied().collect();
+        public_keys.sort();
+
+        public_keys
+    }
+
+    async fn insert_identity(&mut self, keypair: Keypair) {
+        self.identities.write().await.insert(keypair.0, keypair);
+    }
+
+    async fn set_active_identity(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        match self.identities.read().await.get(public_key) {
+            Some(keypair) => {
+                *self.keypair.write().await = *keypair;
+                Ok(())
+            }
+            None => unknown_identity(public_key),
+        }
+    }
+
+    async fn get_channels(&self) -> Option<Vec<Channel>> {
+        let channels = self.channels.read().await;
+
+        if channels.is_empty() {
+            None
+        } else {
+            Some(channels.iter().map(|channel| channel.to_string()).collect())
+        }
+    }
+
+    async fn insert_channel(&mut self, channel: &Channel) {
+        let mut channel_store = self.channels.write().await;
+        channel_store.insert(self.channel_names.intern(channel));
+    }
+
+    async fn get_archived_channels(&self) -> Vec<Channel> {
+        self.archived_channels
+            .read()
+            .await
+            .iter()
+            .map(|channel| channel.to_string())
+            .collect()
+    }
+
+    async fn is_channel_archived(&self, channel: &Channel) -> bool {
+        self.archived_channels
+            .read()
+            .await
+            .contains(channel.as_str())
+    }
+
+    async fn set_channel_archived(&mut self, channel: &Channel, archived: bool) {
+        let mut archived_channels = self.archived_channels.write().await;
+        if archived {
+            archived_channels.insert(self.channel_names.intern(channel));
+        } else {
+            archived_channels.remove(channel.as_str());
+        }
+    }
+
+    async fn remove_channel(&mut self, channel: &Channel) {
+        self.channels.write().await.remove(channel.as_str());
+        self.archived_channels
+            .write()
+            .await
+            .remove(channel.as_str());
+        self.last_read.write().await.remove(channel.as_str());
+    }
+
+    async fn get_channel_members(&self, channel: &Channel) -> Option<Vec<PublicKey>> {
         self.channel_members
             .read()
             .await
-            .get(channel)
+            .get(channel.as_str())
             .map(|member| member.to_owned())
     }
 
@@ -353,14 +1744,14 @@ impl Store for MemoryStore {
         // Open the channel members store for writing.
         let mut channel_members = self.channel_members.write().await;
         // Retrieve the stored members matching the given channel.
-        if let Some(members) = channel_members.get_mut(channel) {
+        if let Some(members) = channel_members.get_mut(channel.as_str()) {
             // Add the public key to the vector of public keys indexed by the
             // given channel.
             members.push(public_key.to_owned())
         } else {
             // Insert the channel into the hash map, using the
             // given public key to create the value vec.
-            channel_members.insert(channel.to_owned(), vec![*public_key]);
+            channel_members.insert(self.channel_names.intern(channel), vec![*public_key]);
         }
     }
 
@@ -376,7 +1767,7 @@ impl Store for MemoryStore {
         // Open the channel members store for writing.
         let mut channel_members = self.channel_members.write().await;
         // Retrieve the stored members matching the given channel.
-        if let Some(members) = channel_members.get_mut(channel) {
+        if let Some(members) = channel_members.get_mut(channel.as_str()) {
             // Iterate over the members and retain only those for which the
             // member does not match the given public key.
             members.retain(|member| member != public_key);
@@ -387,7 +1778,7 @@ impl Store for MemoryStore {
         self.channel_membership
             .read()
             .await
-            .get(channel)
+            .get(channel.as_str())
             .map(|members| {
                 members
                     // Retrieve the hash for each entry in the hash map.
@@ -423,7 +1814,7 @@ impl Store for MemoryStore {
         let mut channel_membership = self.channel_membership.write().await;
         // Retrieve the stored public key / hash hash map matching the given
         // channel.
-        if let Some(membership_map) = channel_membership.get_mut(channel) {
+        if let Some(membership_map) = channel_membership.get_mut(channel.as_str()) {
             // Add the public key to the vector of public keys indexed by the
             // given channel.
             membership_map.insert(public_key.to_owned(), *hash);
@@ -435,7 +1826,7 @@ impl Store for MemoryStore {
             membership_map.insert(*public_key, *hash);
 
             // Insert the members hash map into the channel membership hash map.
-            channel_membership.insert(channel.to_owned(), membership_map);
+            channel_membership.insert(self.channel_names.intern(channel), membership_map);
         }
     }
 
@@ -443,7 +1834,7 @@ impl Store for MemoryStore {
         self.ex_channel_members
             .read()
             .await
-            .get(channel)
+            .get(channel.as_str())
             .map(|member| member.to_owned())
     }
 
@@ -451,14 +1842,14 @@ impl Store for MemoryStore {
         // Open the ex-channel members store for writing.
         let mut ex_channel_members = self.ex_channel_members.write().await;
         // Retrieve the stored ex-members matching the given channel.
-        if let Some(ex_members) = ex_channel_members.get_mut(channel) {
+        if let Some(ex_members) = ex_channel_members.get_mut(channel.as_str()) {
             // Add the public key to the vector of public keys indexed by the
             // given channel.
             ex_members.push(public_key.to_owned())
         } else {
             // Insert the channel into the hash map, using the
             // given public key to create the value vec.
-            ex_channel_members.insert(channel.to_owned(), vec![*public_key]);
+            ex_channel_members.insert(self.channel_names.intern(channel), vec![*public_key]);
         }
     }
 
@@ -466,7 +1857,7 @@ impl Store for MemoryStore {
         // Open the ex-channel members store for writing.
         let mut ex_channel_members = self.ex_channel_members.write().await;
         // Retrieve the stored ex-members matching the given channel.
-        if let Some(ex_members) = ex_channel_members.get_mut(channel) {
+        if let Some(ex_members) = ex_channel_members.get_mut(channel.as_str()) {
             // Iterate over the ex-members and retain only those for which the
             // member does not match the given public key.
             ex_members.retain(|ex_member| ex_member != public_key);
@@ -477,7 +1868,7 @@ impl Store for MemoryStore {
         self.channel_topics
             .read()
             .await
-            .get(channel)
+            .get(channel.as_str())
             .and_then(|topics| {
                 topics
                     // Get the key-value pair with the largest timestamp.
@@ -498,7 +1889,7 @@ impl Store for MemoryStore {
         let mut channel_topics = self.channel_topics.write().await;
         // Retrieve the stored tuple of topic, timestamp and hash matching the
         // given channel.
-        if let Some(topic_map) = channel_topics.get_mut(channel) {
+        if let Some(topic_map) = channel_topics.get_mut(channel.as_str()) {
             // Insert the given topic and hash into the map, using the
             // timestamp as the key.
             topic_map.insert(*timestamp, (topic.to_owned(), *hash));
@@ -512,7 +1903,7 @@ impl Store for MemoryStore {
             topic_map.insert(*timestamp, (topic.to_owned(), *hash));
             // Insert the `BTreeMap` into the channel topics `HashMap`,
             // using the channel name as the key.
-            channel_topics.insert(channel.to_owned(), topic_map);
+            channel_topics.insert(self.channel_names.intern(channel), topic_map);
         }
     }
 
@@ -585,24 +1976,61 @@ impl Store for MemoryStore {
     }
 
     async fn get_latest_hashes(&self, channel: &Channel) -> Option<Vec<Hash>> {
-        // Open the posts store for reading.
-        let posts_map = self.posts.read().await;
-
-        // Get the BTree associated with the given channel.
-        if let Some(posts_btree) = posts_map.get(&Some(channel.to_owned())) {
-            // Return the most recently added hash(es).
-            posts_btree.last_key_value().map(|(_, post_vec)| {
-                post_vec
-                    .iter()
-                    // Only return the hash from each tuple in the vector.
-                    .map(|(_post, hash)| hash.to_owned())
-                    .collect()
-            })
-        } else {
-            None
+        self.channel_heads
+            .read()
+            .await
+            .get(channel.as_str())
+            .filter(|heads| !heads.is_empty())
+            .map(|heads| heads.iter().copied().collect())
+    }
+
+    async fn insert_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        self.channel_heads
+            .write()
+            .await
+            .entry(self.channel_names.intern(channel))
+            .or_default()
+            .insert(*hash);
+    }
+
+    async fn remove_channel_head(&mut self, channel: &Channel, hash: &Hash) {
+        if let Some(heads) = self.channel_heads.write().await.get_mut(channel.as_str()) {
+            heads.remove(hash);
         }
     }
 
+    async fn insert_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        self.post_links
+            .write()
+            .await
+            .entry(self.channel_names.intern(channel))
+            .or_default()
+            .entry(*link)
+            .or_default()
+            .insert(*hash);
+    }
+
+    async fn remove_post_link(&mut self, channel: &Channel, hash: &Hash, link: &Hash) {
+        let mut post_links = self.post_links.write().await;
+
+        if let Some(links) = post_links.get_mut(channel.as_str()) {
+            if let Some(linking_hashes) = links.get_mut(link) {
+                linking_hashes.remove(hash);
+                if linking_hashes.is_empty() {
+                    links.remove(link);
+                }
+            }
+        }
+    }
+
+    async fn is_post_linked(&self, channel: &Channel, hash: &Hash) -> bool {
+        self.post_links
+            .read()
+            .await
+            .get(channel.as_str())
+            .is_some_and(|links| links.contains_key(hash))
+    }
+
     async fn get_peer_name_and_hash(&self, public_key: &PublicKey) -> Option<(Nickname, Hash)> {
         self.peer_names
             .read()
@@ -645,18 +2073,55 @@ impl Store for MemoryStore {
         }
     }
 
-    async fn remove_peer_name(&mut self, hash: &Hash) {
-        // Open the peer names store for writing.
-        let mut peer_names = self.peer_names.write().await;
-
-        // Iterate over all key-value pairs in the hash map.
-        //
-        // The `name_map` is a `BTreeMap`.
-        peer_names.iter_mut().for_each(|(_public_key, name_map)| {
-            // Remove any key-value pair for which the stored hash of the name
-            // post matches the given hash.
-            name_map.retain(|_timestamp, (_name, stored_hash)| stored_hash != hash)
-        });
+    async fn remove_peer_name(&mut self, hash: &Hash) {
+        // Open the peer names store for writing.
+        let mut peer_names = self.peer_names.write().await;
+
+        // Iterate over all key-value pairs in the hash map.
+        //
+        // The `name_map` is a `BTreeMap`.
+        peer_names.iter_mut().for_each(|(_public_key, name_map)| {
+            // Remove any key-value pair for which the stored hash of the name
+            // post matches the given hash.
+            name_map.retain(|_timestamp, (_name, stored_hash)| stored_hash != hash)
+        });
+    }
+
+    async fn get_peer_avatar_and_hash(&self, public_key: &PublicKey) -> Option<(Avatar, Hash)> {
+        self.peer_avatars
+            .read()
+            .await
+            .get(public_key)
+            .and_then(|avatars| {
+                avatars
+                    .last_key_value()
+                    .map(|(_, (avatar, hash))| (avatar.to_owned(), hash.to_owned()))
+            })
+    }
+
+    async fn insert_peer_avatar(
+        &mut self,
+        public_key: &PublicKey,
+        avatar: &Avatar,
+        timestamp: &Timestamp,
+        hash: &Hash,
+    ) {
+        self.peer_avatars
+            .write()
+            .await
+            .entry(*public_key)
+            .or_default()
+            .insert(*timestamp, (avatar.to_owned(), *hash));
+    }
+
+    async fn remove_peer_avatar(&mut self, hash: &Hash) {
+        self.peer_avatars
+            .write()
+            .await
+            .values_mut()
+            .for_each(|avatar_map| {
+                avatar_map.retain(|_timestamp, (_avatar, stored_hash)| stored_hash != hash)
+            });
     }
 
     async fn get_posts(&self, opts: &ChannelOptions) -> PostStream {
@@ -667,16 +2132,13 @@ impl Store for MemoryStore {
         let all_posts = self.posts.read().await;
 
         // Retrieve all posts matching the given channel options.
-        let mut posts = all_posts
-            .get(&Some(opts.channel.to_owned()))
-            // Return only the posts for which the key (timestamp: `x`)
-            // matches the given range (provided via `opts`).
-            .map(|x| match (start, end) {
-                (0, 0) => x.range(..),
-                (0, end) => x.range(..end),
-                (start, 0) => x.range(start..),
-                _ => x.range(start..end),
-            })
+        let mut posts = self
+            .channel_names
+            .get(&opts.channel)
+            .and_then(|channel| all_posts.get(&Some(channel)))
+            // Return only the posts for which the key (timestamp) matches
+            // the given range (provided via `opts`).
+            .map(|post_map| time_range(post_map, start, end))
             // Return an empty map if no posts are found matching the given
             // channel.
             .unwrap_or(empty)
@@ -706,209 +2168,302 @@ impl Store for MemoryStore {
         Box::new(stream::from_iter(posts.into_iter()))
     }
 
-    async fn get_posts_live(&mut self, opts: &ChannelOptions) -> PostStream {
-        let live_stream = {
-            let mut live_streams = self.live_streams.write().await;
-
-            // Select existing streams which match the given channel.
-            if let Some(streams) = live_streams.get_mut(&opts.channel) {
-                let live_stream = {
-                    let mut id = self.live_stream_id.lock().await;
-                    // Increment the live stream counter.
-                    *id += 1;
-                    // Return a new live stream.
-                    LiveStream::new(*id, opts.clone(), streams.clone())
-                };
-                let live = live_stream.clone();
-                task::block_on(async move {
-                    // Add the newly-created stream to the streams store for
-                    // the given channel.
-                    streams.write().await.push(live);
-                });
+    async fn get_posts_page(
+        &self,
+        channel: &Channel,
+        before: Option<PageCursor>,
+        limit: usize,
+    ) -> Result<PostPage, Error> {
+        let all_posts = self.posts.read().await;
 
-                live_stream
-            } else {
-                // No streams were found which match the given channel.
-
-                let streams = Arc::new(RwLock::new(vec![]));
-                // Generate a new live stream ID.
-                let live_stream_id = {
-                    let mut id = self.live_stream_id.lock().await;
-                    *id += 1;
-                    id
-                };
-                let streams_c = streams.clone();
-                // Create a new stream and add it to the streams `Vec`.
-                let live_stream = task::block_on(async move {
-                    let live_stream =
-                        LiveStream::new(*live_stream_id, opts.clone(), streams_c.clone());
-                    streams_c.write().await.push(live_stream.clone());
-                    live_stream
-                });
-                // Add the newly-created stream to the streams store
-                // for the given channel.
-                live_streams.insert(opts.channel.clone(), streams);
+        let mut posts = Vec::new();
+        let channel = self.channel_names.get(channel);
+        if let Some(channel_posts) = channel.and_then(|channel| all_posts.get(&Some(channel))) {
+            let range = match before {
+                Some(before) => channel_posts.range(..=before.timestamp),
+                None => channel_posts.range(..),
+            };
+
+            for (timestamp, timestamp_posts) in range.rev() {
+                for (post, hash) in timestamp_posts {
+                    let cursor = PageCursor {
+                        timestamp: *timestamp,
+                        hash: *hash,
+                    };
+                    if before.is_none_or(|before| cursor < before) {
+                        posts.push((cursor, post.clone()));
+                    }
+                }
 
-                live_stream
+                // Stop once the page is full and the existence of another
+                // page is known.
+                if limit > 0 && posts.len() > limit {
+                    break;
+                }
             }
-        };
+        }
+
+        Ok(PostPage::from_posts(posts, limit))
+    }
 
-        // Retrieve all stored posts matching the channel options,
-        // as well as all non-channel posts.
-        let post_stream = self.get_posts(opts).await;
+    async fn get_mentions(&self, channel: &Channel) -> Vec<Hash> {
+        let mut mentions: Vec<(Timestamp, Hash)> = self
+            .mentions
+            .read()
+            .await
+            .iter()
+            .filter(|(_hash, (mention_channel, _timestamp))| **mention_channel == **channel)
+            .map(|(hash, (_channel, timestamp))| (*timestamp, *hash))
+            .collect();
+        mentions.sort();
+
+        mentions
+            .into_iter()
+            .map(|(_timestamp, hash)| hash)
+            .collect()
+    }
 
-        // Merge the existing post stream with the live post stream.
-        Box::new(post_stream.merge(live_stream))
+    async fn insert_mention(&mut self, channel: &Channel, timestamp: Timestamp, hash: &Hash) {
+        self.mentions
+            .write()
+            .await
+            .insert(*hash, (self.channel_names.intern(channel), timestamp));
     }
 
-    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
-        let start = opts.time_start;
-        let end = opts.time_end;
-        let empty = self.empty_post_bt.range(..);
+    async fn remove_mention(&mut self, hash: &Hash) {
+        self.mentions.write().await.remove(hash);
+    }
 
-        let hashes = self
-            .posts
+    async fn get_replies(&self, parent: &Hash) -> Vec<Hash> {
+        let mut replies: Vec<(Timestamp, Hash)> = self
+            .replies
             .read()
             .await
-            .get(&Some(opts.channel.to_owned()))
-            // Return only the hashes for which the key (timestamp: `x`)
-            // matches the given range (provided via `opts`).
-            .map(|x| match (start, end) {
-                (0, 0) => x.range(..),
-                (0, end) => x.range(..end),
-                (start, 0) => x.range(start..),
-                _ => x.range(start..end),
-            })
-            .unwrap_or(empty)
-            // Iterate over the post data and extract the hash for each one,
-            // wrapping it in a `Result`.
-            .flat_map(|(_time, posts)| posts.iter().map(|(_post, hash)| Ok(*hash)))
-            .collect::<Vec<Result<Hash, Error>>>();
+            .iter()
+            .filter(|(_hash, (reply_parent, _timestamp))| reply_parent == parent)
+            .map(|(hash, (_parent, timestamp))| (*timestamp, *hash))
+            .collect();
+        replies.sort();
 
-        // Return a hash stream.
-        Box::new(stream::from_iter(hashes.into_iter()))
+        replies.into_iter().map(|(_timestamp, hash)| hash).collect()
     }
 
-    async fn insert_post(&mut self, post: &Post) -> Result<Hash, Error> {
-        let timestamp = &post.get_timestamp();
+    async fn insert_reply(&mut self, parent: &Hash, timestamp: Timestamp, hash: &Hash) {
+        self.replies
+            .write()
+            .await
+            .insert(*hash, (*parent, timestamp));
+    }
 
-        let hash = post.hash()?;
+    async fn remove_reply(&mut self, hash: &Hash) {
+        self.replies.write().await.remove(hash);
+    }
 
-        match &post.body {
-            PostBody::Text { channel, text: _ } => {
-                // Insert the post into the `posts` store.
-                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
-                    .await;
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
-                self.send_post_to_live_streams(post, channel).await;
-            }
-            PostBody::Join { channel } => {
-                let public_key = &post.get_public_key();
+    #[cfg(feature = "reactions")]
+    async fn get_reactions(&self, target: &Hash) -> Vec<Hash> {
+        let mut reactions: Vec<(Timestamp, Hash)> = self
+            .reactions
+            .read()
+            .await
+            .iter()
+            .filter(|(_hash, (reaction_target, _timestamp))| reaction_target == target)
+            .map(|(hash, (_target, timestamp))| (*timestamp, *hash))
+            .collect();
+        reactions.sort();
+
+        reactions
+            .into_iter()
+            .map(|(_timestamp, hash)| hash)
+            .collect()
+    }
 
-                self.update_channel_membership_hashes(channel, public_key, &hash)
-                    .await;
-                self.insert_channel_member(channel, public_key).await;
-                self.remove_ex_channel_member(channel, public_key).await;
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
-            }
-            PostBody::Leave { channel } => {
-                let public_key = &post.get_public_key();
+    #[cfg(feature = "reactions")]
+    async fn insert_reaction(&mut self, target: &Hash, timestamp: Timestamp, hash: &Hash) {
+        self.reactions
+            .write()
+            .await
+            .insert(*hash, (*target, timestamp));
+    }
 
-                self.update_channel_membership_hashes(channel, public_key, &hash)
-                    .await;
-                self.remove_channel_member(channel, public_key).await;
-                self.insert_ex_channel_member(channel, public_key).await;
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
-            }
-            PostBody::Topic { channel, topic } => {
-                // Insert the post into the `posts` store.
-                self.update_posts(post, Some(channel.to_owned()), timestamp, hash)
-                    .await;
-                self.insert_channel_topic(channel, topic, timestamp, &hash)
-                    .await;
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
-                self.send_post_to_live_streams(post, channel).await;
-            }
-            PostBody::Delete { hashes } => {
-                let public_key = &post.get_public_key();
+    #[cfg(feature = "reactions")]
+    async fn remove_reaction(&mut self, hash: &Hash) {
+        self.reactions.write().await.remove(hash);
+    }
 
-                for post_hash in hashes {
-                    if let Some(payload) = self.get_post_payload(post_hash).await {
-                        // TODO: Consider whether it is more efficient to
-                        // decode the payload or retrieve the post from the
-                        // `posts` store.
-                        let (_s, stored_post) = Post::from_bytes(&payload)?;
-                        // Only delete the post if the author matches the
-                        // author of the `post/delete` post.
-                        if post.get_public_key() == stored_post.get_public_key() {
-                            // Delete the post from all stores.
-                            self.delete_post(post_hash).await;
-                            // The hash of the `post/delete` post is inserted,
-                            // not the hash of the post referenced by the
-                            // `post/delete` post.
-                            self.insert_delete_hash(public_key, &hash).await;
-                        }
-                    }
-                }
+    #[cfg(feature = "archive-posts")]
+    async fn get_channel_archives(&self, channel: &Channel) -> Vec<(PublicKey, Hash)> {
+        let mut archives: Vec<(Hash, PublicKey)> = self
+            .channel_archives
+            .read()
+            .await
+            .iter()
+            .filter(|(_hash, (archived_channel, _public_key))| {
+                &**archived_channel == channel.as_str()
+            })
+            .map(|(hash, (_channel, public_key))| (*hash, *public_key))
+            .collect();
+        archives.sort();
 
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
-            }
-            PostBody::Info { info } => {
-                // Insert the post into the `posts` store.
-                self.update_posts(post, None, timestamp, hash).await;
+        archives
+            .into_iter()
+            .map(|(hash, public_key)| (public_key, hash))
+            .collect()
+    }
 
-                let public_key = &post.get_public_key();
+    #[cfg(feature = "archive-posts")]
+    async fn insert_channel_archive(
+        &mut self,
+        channel: &Channel,
+        public_key: &PublicKey,
+        hash: &Hash,
+    ) {
+        let channel = self.channel_names.intern(channel);
+        self.channel_archives
+            .write()
+            .await
+            .insert(*hash, (channel, *public_key));
+    }
 
-                // Insert the public key of the post author and the assigned
-                // name if the key of the info element is "name".
-                for UserInfo { key, val } in info {
-                    if key == "name" {
-                        self.insert_peer_name(public_key, val, timestamp, &hash)
-                            .await;
-                    }
-                }
+    #[cfg(feature = "archive-posts")]
+    async fn remove_channel_archive(&mut self, hash: &Hash) {
+        self.channel_archives.write().await.remove(hash);
+    }
 
-                self.insert_info_hash(public_key, &hash).await;
-                self.insert_post_payload(&hash, post.to_bytes()?).await;
+    #[cfg(feature = "blobs")]
+    async fn get_blob(&self, hash: &Hash) -> Option<Vec<u8>> {
+        self.blobs.read().await.get(hash).cloned()
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn insert_blob(&mut self, hash: &Hash, blob: Vec<u8>) {
+        self.blobs.write().await.insert(*hash, blob);
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn remove_blob(&mut self, hash: &Hash) {
+        self.blobs.write().await.remove(hash);
+    }
+
+    #[cfg(feature = "blobs")]
+    async fn get_blob_usage(&self) -> u64 {
+        self.blobs
+            .read()
+            .await
+            .values()
+            .map(|blob| blob.len() as u64)
+            .sum()
+    }
+
+    async fn get_author_index(&self, public_key: &PublicKey) -> Vec<PageCursor> {
+        self.authors
+            .read()
+            .await
+            .get(public_key)
+            .map(|cursors| cursors.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    async fn insert_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        self.authors
+            .write()
+            .await
+            .entry(*public_key)
+            .or_default()
+            .insert(PageCursor {
+                timestamp,
+                hash: *hash,
+            });
+    }
+
+    async fn remove_author_post(
+        &mut self,
+        public_key: &PublicKey,
+        timestamp: Timestamp,
+        hash: &Hash,
+    ) {
+        let mut authors = self.authors.write().await;
+        if let Some(cursors) = authors.get_mut(public_key) {
+            cursors.remove(&PageCursor {
+                timestamp,
+                hash: *hash,
+            });
+            if cursors.is_empty() {
+                authors.remove(public_key);
             }
-            _ => {}
         }
+    }
 
-        let channel = post.get_channel();
+    async fn get_last_read(&self, channel: &Channel) -> Option<PageCursor> {
+        self.last_read.read().await.get(channel.as_str()).copied()
+    }
 
-        // Update the store of known channels.
-        if let Some(channel) = channel {
-            self.insert_channel(channel).await;
-        }
+    async fn set_last_read(&mut self, channel: &Channel, cursor: PageCursor) {
+        self.last_read
+            .write()
+            .await
+            .insert(self.channel_names.intern(channel), cursor);
+    }
 
-        Ok(hash)
+    async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        self.live_streams.watch(channel).await
+    }
+
+    async fn send_event(&self, channel: &Channel, event: StoreEvent) {
+        self.live_streams.send(channel, event).await;
+    }
+
+    async fn get_post_hashes(&self, opts: &ChannelOptions) -> HashStream {
+        let start = opts.time_start;
+        let end = opts.time_end;
+        let empty = self.empty_post_bt.range(..);
+
+        let all_posts = self.posts.read().await;
+
+        let hashes = self
+            .channel_names
+            .get(&opts.channel)
+            .and_then(|channel| all_posts.get(&Some(channel)))
+            // Return only the hashes for which the key (timestamp) matches
+            // the given range (provided via `opts`).
+            .map(|post_map| time_range(post_map, start, end))
+            .unwrap_or(empty)
+            // Iterate over the post data and extract the hash for each one,
+            // wrapping it in a `Result`.
+            .flat_map(|(_time, posts)| posts.iter().map(|(_post, hash)| Ok(*hash)))
+            .collect::<Vec<Result<Hash, Error>>>();
+
+        // Return a hash stream.
+        Box::new(stream::from_iter(hashes.into_iter()))
     }
 
     async fn remove_post(&mut self, hash: &Hash) {
+        // Look up the channel and timestamp under which the post is held,
+        // so that only that entry of the post store is visited.
+        let Some((channel, timestamp)) = self.post_keys.write().await.remove(hash) else {
+            return;
+        };
+
         // Open the post store for writing.
         let mut posts = self.posts.write().await;
 
-        // Iterate over all key-value pairs in the hash map.
-        //
-        // The `post_map` is a `BTreeMap`.
-        posts.iter_mut().for_each(|(_channel, post_map)| {
-            // Iterate over the key-value pairs of the post map.
-            post_map.iter_mut().for_each(|(_timestamp, post_vec)| {
+        if let Some(post_map) = posts.get_mut(&channel) {
+            if let Some(post_vec) = post_map.get_mut(&timestamp) {
                 // Remove any tuple from the vector for which the stored
                 // hash matches the given hash.
-                post_vec.retain(|(_post, stored_hash)| stored_hash != hash)
-            })
-        });
-    }
+                post_vec.retain(|(_post, stored_hash)| stored_hash != hash);
 
-    async fn delete_post(&mut self, hash: &Hash) {
-        // Remove post from all stores.
-        self.remove_channel_topic(hash).await;
-        self.remove_channel_membership_hash(hash).await;
-        self.remove_peer_name(hash).await;
-        self.remove_info_hash(hash).await;
-        self.remove_post(hash).await;
-        self.remove_post_payload(hash).await;
+                // Remove the entry of the timestamp once it holds no posts,
+                // so that range queries do not visit it.
+                if post_vec.is_empty() {
+                    post_map.remove(&timestamp);
+                }
+            }
+        }
     }
 
     async fn update_posts(
@@ -918,6 +2473,13 @@ impl Store for MemoryStore {
         timestamp: &Timestamp,
         hash: Hash,
     ) {
+        let channel = channel.map(|channel| self.channel_names.intern(&channel));
+
+        self.post_keys
+            .write()
+            .await
+            .insert(hash, (channel.clone(), *timestamp));
+
         // Open the post store for writing.
         let mut posts = self.posts.write().await;
 
@@ -964,6 +2526,10 @@ impl Store for MemoryStore {
             .collect()
     }
 
+    async fn get_post_payload_hashes(&self) -> Vec<Hash> {
+        self.post_payloads.read().await.keys().copied().collect()
+    }
+
     async fn insert_post_payload(&mut self, hash: &Hash, payload: Payload) {
         self.post_payloads.write().await.insert(*hash, payload);
     }
@@ -977,23 +2543,153 @@ impl Store for MemoryStore {
         post_payloads.retain(|stored_hash, _payload| stored_hash != hash);
     }
 
-    async fn send_post_to_live_streams(&self, post: &Post, channel: &Channel) {
-        if let Some(senders) = self.live_streams.read().await.get(channel) {
-            for stream in senders.write().await.iter_mut() {
-                if stream.matches(post) {
-                    stream.send(post.clone()).await;
-                }
-            }
-        }
+    async fn insert_tombstone(&mut self, hash: &Hash) {
+        self.tombstones.write().await.insert(*hash);
+    }
+
+    async fn is_tombstone(&self, hash: &Hash) -> bool {
+        self.tombstones.read().await.contains(hash)
+    }
+
+    async fn get_outbound_requests(&self) -> Vec<(ReqId, Vec<u8>)> {
+        self.outbound_requests
+            .read()
+            .await
+            .iter()
+            .map(|(req_id, request)| (*req_id, request.clone()))
+            .collect()
+    }
+
+    async fn insert_outbound_request(&mut self, req_id: &ReqId, request: &[u8]) {
+        self.outbound_requests
+            .write()
+            .await
+            .insert(*req_id, request.to_vec());
+    }
+
+    async fn remove_outbound_request(&mut self, req_id: &ReqId) {
+        self.outbound_requests.write().await.remove(req_id);
+    }
+
+    async fn metrics(&self) -> Result<StoreMetrics, Error> {
+        let posts = self.posts.read().await;
+        let posts_per_channel = posts
+            .iter()
+            .filter_map(|(channel, posts)| {
+                let count = posts.values().map(|posts| posts.len()).sum();
+                channel.as_ref().map(|channel| (channel.to_string(), count))
+            })
+            .collect();
+
+        let index_sizes = [
+            ("channels", self.channels.read().await.len()),
+            (
+                "channel_members",
+                self.channel_members
+                    .read()
+                    .await
+                    .values()
+                    .map(Vec::len)
+                    .sum(),
+            ),
+            (
+                "ex_channel_members",
+                self.ex_channel_members
+                    .read()
+                    .await
+                    .values()
+                    .map(Vec::len)
+                    .sum(),
+            ),
+            (
+                "channel_membership",
+                self.channel_membership
+                    .read()
+                    .await
+                    .values()
+                    .map(HashMap::len)
+                    .sum(),
+            ),
+            (
+                "channel_topics",
+                self.channel_topics
+                    .read()
+                    .await
+                    .values()
+                    .map(BTreeMap::len)
+                    .sum(),
+            ),
+            (
+                "delete_hashes",
+                self.delete_hashes.read().await.values().map(Vec::len).sum(),
+            ),
+            (
+                "info_hashes",
+                self.info_hashes.read().await.values().map(Vec::len).sum(),
+            ),
+            (
+                "peer_names",
+                self.peer_names
+                    .read()
+                    .await
+                    .values()
+                    .map(BTreeMap::len)
+                    .sum(),
+            ),
+            (
+                "peer_avatars",
+                self.peer_avatars
+                    .read()
+                    .await
+                    .values()
+                    .map(BTreeMap::len)
+                    .sum(),
+            ),
+            (
+                "channel_heads",
+                self.channel_heads
+                    .read()
+                    .await
+                    .values()
+                    .map(BTreeSet::len)
+                    .sum(),
+            ),
+            (
+                "post_links",
+                self.post_links
+                    .read()
+                    .await
+                    .values()
+                    .flat_map(HashMap::values)
+                    .map(HashSet::len)
+                    .sum(),
+            ),
+            (
+                "author_posts",
+                self.authors.read().await.values().map(BTreeSet::len).sum(),
+            ),
+        ]
+        .into_iter()
+        .map(|(index, size)| (index.to_string(), size))
+        .collect();
+
+        Ok(StoreMetrics {
+            total_posts: self.post_payloads.read().await.len(),
+            posts_per_channel,
+            tombstones: Some(self.tombstones.read().await.len()),
+            index_sizes,
+            disk_usage: None,
+        })
     }
 
     async fn want(&self, hashes: &[Hash]) -> Vec<Hash> {
         let post_payloads = self.post_payloads.read().await;
+        let tombstones = self.tombstones.read().await;
 
-        // Return the "wanted" hashes.
+        // Return the "wanted" hashes, excluding those of deleted posts.
         hashes
             .iter()
-            .filter(|hash| !post_payloads.contains_key(&(*hash).clone()))
+            .filter(|hash| !post_payloads.contains_key(*hash) && !tombstones.contains(*hash))
             .cloned()
             .collect()
     }
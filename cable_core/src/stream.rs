@@ -1,125 +1,116 @@
-//! Live stream data type and associated methods, along with an implementation
-//! of the asynchronous `Stream` trait (`async_std`) for the `LiveStream` type.
+//! Stream data types, along with the registry of store watchers which
+//! receive events as posts are inserted into and deleted from a store.
+
+use std::collections::HashMap;
 
 use async_std::{
     channel,
-    pin::Pin,
-    prelude::*,
     stream::Stream,
-    sync::{Arc, Mutex, RwLock},
-    task,
-    task::{Context, Poll, Waker},
+    sync::{Arc, RwLock},
 };
-use cable::{ChannelOptions, Error, Hash, Post};
+use cable::{post::PostBody, Channel, ChannelOptions, Error, Hash, Post};
 
 /// An asynchronous stream of posts.
 pub type PostStream<'a> = Box<dyn Stream<Item = Result<Post, Error>> + Unpin + Send + 'a>;
+/// An asynchronous stream of posts, each paired with the name of the channel
+/// from whose stream it was returned.
+pub type ChannelPostStream<'a> =
+    Box<dyn Stream<Item = Result<(Channel, Post), Error>> + Unpin + Send + 'a>;
 /// An asynchronous stream of post hashes.
 pub type HashStream<'a> = Box<dyn Stream<Item = Result<Hash, Error>> + Unpin + Send + 'a>;
+/// An asynchronous stream of store events.
+pub type EventStream<'a> = Box<dyn Stream<Item = StoreEvent> + Unpin + Send + 'a>;
+
+/// A `HashMap` of watchers with a key of channel name and a value of a `Vec`
+/// of event senders.
+pub type WatcherMap = HashMap<Channel, Vec<channel::Sender<StoreEvent>>>;
 
-#[derive(Clone)]
-/// A live stream manager with a unique ID and channel parameters.
-pub struct LiveStream {
-    id: usize,
-    options: ChannelOptions,
-    sender: channel::Sender<Post>,
-    receiver: channel::Receiver<Post>,
-    live_streams: Arc<RwLock<Vec<Self>>>,
-    waker: Arc<Mutex<Option<Waker>>>,
+/// A change to the posts of a channel, as emitted by `Store::watch()`.
+#[derive(Clone, Debug)]
+// Reaction posts enlarge `Post`; events are short-lived, so the post is not
+// boxed.
+#[cfg_attr(feature = "reactions", allow(clippy::large_enum_variant))]
+pub enum StoreEvent {
+    /// A post affecting the channel was inserted into the store.
+    ///
+    /// `post/info` and `post/delete` posts, which do not have a channel, are
+    /// emitted for each channel of which their author is a member.
+    PostInserted {
+        /// The hash of the inserted post.
+        hash: Hash,
+        /// The inserted post.
+        post: Post,
+    },
+    /// A post made to the channel was deleted from the store.
+    PostDeleted {
+        /// The hash of the deleted post.
+        hash: Hash,
+    },
 }
 
-impl LiveStream {
-    /// Create a new `LiveStream` with the given channel options and streams.
-    pub fn new(id: usize, options: ChannelOptions, live_streams: Arc<RwLock<Vec<Self>>>) -> Self {
-        let limit = options.limit as usize;
+#[derive(Clone, Default)]
+/// All active watchers of a store, indexed by channel.
+///
+/// This allows any `Store` implementation to hand out event streams and to
+/// notify them of changes to the store.
+pub struct LiveStreams {
+    /// The event senders of all active watchers, indexed by channel.
+    watchers: Arc<RwLock<WatcherMap>>,
+}
 
-        let (sender, receiver) = channel::bounded(limit);
+impl LiveStreams {
+    /// Create and register a new watcher for the given channel, returning
+    /// the stream of events it receives.
+    pub async fn watch(&self, channel: &Channel) -> EventStream<'static> {
+        let (sender, receiver) = channel::unbounded();
 
-        Self {
-            id,
-            options,
-            sender,
-            receiver,
-            live_streams,
-            waker: Arc::new(Mutex::new(None)),
-        }
-    }
+        self.watchers
+            .write()
+            .await
+            .entry(channel.to_owned())
+            .or_default()
+            .push(sender);
 
-    /// Send a post to the live stream manager.
-    pub async fn send(&mut self, post: Post) {
-        if self.sender.try_send(post).is_err() {}
-        if let Some(waker) = self.waker.lock().await.as_ref() {
-            waker.wake_by_ref();
-        }
-    }
-
-    /// Check if the given post matches the channel parameters
-    /// defined for the live stream manager.
-    pub fn matches(&self, post: &Post) -> bool {
-        if Some(&self.options.channel) != post.get_channel() {
-            return false;
-        }
-        match (self.options.time_start, self.options.time_end) {
-            (0, 0) => true,
-            (0, end) => post.get_timestamp() <= end,
-            (start, 0) => start <= post.get_timestamp(),
-            (start, end) => {
-                let timestamp = post.get_timestamp();
-                start <= timestamp && timestamp <= end
-            }
-        }
+        Box::new(receiver)
     }
-}
 
-impl Stream for LiveStream {
-    type Item = Result<Post, Error>;
+    /// Send the given event to each watcher of the given channel, discarding
+    /// the watchers whose streams have been dropped.
+    pub async fn send(&self, channel: &Channel, event: StoreEvent) {
+        let mut watchers = self.watchers.write().await;
 
-    fn poll_next(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
-        let r = Pin::new(&mut self.receiver.recv()).poll(ctx);
-        match r {
-            Poll::Ready(Ok(x)) => {
-                let m_waker = self.waker.clone();
-                task::block_on(async move {
-                    *m_waker.lock().await = None;
-                });
-                Poll::Ready(Some(Ok(x)))
-            }
-            Poll::Ready(Err(x)) => {
-                let m_waker = self.waker.clone();
-                task::block_on(async move {
-                    *m_waker.lock().await = None;
-                });
-                Poll::Ready(Some(Err(x.into())))
-            }
-            Poll::Pending => {
-                let m_waker = self.waker.clone();
-                let waker = ctx.waker().clone();
-                task::block_on(async move {
-                    *m_waker.lock().await = Some(waker);
-                });
-                Poll::Pending
+        if let Some(senders) = watchers.get_mut(channel) {
+            senders.retain(|sender| sender.try_send(event.clone()).is_ok());
+            if senders.is_empty() {
+                watchers.remove(channel);
             }
         }
     }
 }
 
-impl Drop for LiveStream {
-    fn drop(&mut self) {
-        let live_streams = self.live_streams.clone();
-        let id = self.id;
-
-        task::block_on(async move {
-            // NOTE: `drain_filter()` would be more efficient to use here but
-            // it's not currrently available on the stable API (nightly-only
-            // experimental API only).
-            let mut i = 0;
-            while i < live_streams.read().await.len() {
-                if live_streams.read().await[i].id == id {
-                    let _stream = live_streams.write().await.remove(i);
-                } else {
-                    i += 1;
-                }
-            }
-        });
+/// Check if the given post is a channel post (`post/text` or `post/topic`,
+/// `post/reaction` with the `reactions` feature or `post/archive` with the
+/// `archive-posts` feature) matching the given channel options, ignoring the
+/// limit.
+pub fn matches(opts: &ChannelOptions, post: &Post) -> bool {
+    let is_channel_post = match post.body {
+        PostBody::Text { .. } | PostBody::Topic { .. } => true,
+        #[cfg(feature = "reactions")]
+        PostBody::Reaction { .. } => true,
+        #[cfg(feature = "archive-posts")]
+        PostBody::Archive { .. } => true,
+        _ => false,
+    };
+    if !is_channel_post || Some(&opts.channel) != post.get_channel() {
+        return false;
+    }
+    match (opts.time_start, opts.time_end) {
+        (0, 0) => true,
+        (0, end) => post.get_timestamp() <= end,
+        (start, 0) => start <= post.get_timestamp(),
+        (start, end) => {
+            let timestamp = post.get_timestamp();
+            start <= timestamp && timestamp <= end
+        }
     }
 }
@@ -0,0 +1,85 @@
+//! Channel subscriptions.
+//!
+//! A `Subscription` is returned by `CableManager::subscribe()`. It holds the
+//! live channel time range and channel state requests of a channel open until
+//! it is unsubscribed, and provides a stream of the posts of the channel.
+
+use cable::{Channel, ChannelOptions, Error, Timestamp};
+
+use crate::{manager::CableManager, store::Store, stream::PostStream};
+
+#[derive(Clone, Debug)]
+/// Parameters controlling a channel subscription.
+pub struct SubscribeOptions {
+    /// Whether a `post/join` post is published when subscribing, if the local
+    /// peer is not yet a member of the channel.
+    pub join: bool,
+    /// Whether a `post/leave` post is published when unsubscribing, if the
+    /// local peer is a member of the channel.
+    pub leave: bool,
+    /// The timestamp of the earliest posts requested from peers and returned
+    /// by `Subscription::posts()`. Set to 0 to include the full history.
+    pub time_start: Timestamp,
+    /// The maximum number of post hashes requested from each peer. Set to 0
+    /// to request all of them.
+    pub limit: u64,
+}
+
+impl Default for SubscribeOptions {
+    fn default() -> Self {
+        SubscribeOptions {
+            join: true,
+            leave: false,
+            time_start: 0,
+            limit: 0,
+        }
+    }
+}
+
+/// A subscription to a channel, keeping the live requests for the channel
+/// open until it is unsubscribed.
+///
+/// Dropping the subscription does not close the channel; call
+/// `unsubscribe()` to cancel its requests.
+#[derive(Clone)]
+pub struct Subscription<S: Store + Clone> {
+    manager: CableManager<S>,
+    channel_opts: ChannelOptions,
+    options: SubscribeOptions,
+}
+
+impl<S> Subscription<S>
+where
+    S: Store + Clone,
+{
+    pub(crate) fn new(
+        manager: CableManager<S>,
+        channel_opts: ChannelOptions,
+        options: SubscribeOptions,
+    ) -> Self {
+        Subscription {
+            manager,
+            channel_opts,
+            options,
+        }
+    }
+
+    /// The (normalized) name of the subscribed channel.
+    pub fn channel(&self) -> &Channel {
+        &self.channel_opts.channel
+    }
+
+    /// Retrieve the stored posts of the channel, continuing to return new
+    /// posts as they are received or published.
+    pub async fn posts(&self) -> PostStream<'_> {
+        self.manager.get_posts_live(&self.channel_opts).await
+    }
+
+    /// Cancel the requests for the channel, publishing a `post/leave` post if
+    /// the subscription was made with the `leave` option.
+    pub async fn unsubscribe(mut self) -> Result<(), Error> {
+        let channel = self.channel_opts.channel.clone();
+
+        self.manager.unsubscribe(&channel, self.options.leave).await
+    }
+}
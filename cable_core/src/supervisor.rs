@@ -0,0 +1,145 @@
+//! Connection supervision with automatic reconnection.
+//!
+//! The `Supervisor` remembers the addresses of known peers and maintains a
+//! connection to each of them. When a connection is closed or fails, it is
+//! re-established after a jittered exponential backoff.
+//!
+//! Outbound requests which are still active (such as live channel
+//! subscriptions created by `open_channel()`) are held by the cable manager
+//! and are re-issued to the peer each time a connection is established.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use async_std::{
+    net::TcpStream,
+    sync::{Arc, RwLock},
+    task,
+};
+use log::debug;
+
+use crate::{backoff::Backoff, manager::CableManager, store::Store};
+
+#[derive(Clone, Debug)]
+/// Parameters controlling the behaviour of a `Supervisor`.
+pub struct SupervisorOptions {
+    /// Delay before the first reconnection attempt.
+    pub initial_backoff: Duration,
+    /// Maximum delay between reconnection attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        SupervisorOptions {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Maintains connections to known peers, reconnecting when they are lost.
+#[derive(Clone)]
+pub struct Supervisor<S: Store + Clone> {
+    manager: CableManager<S>,
+    options: SupervisorOptions,
+    /// Addresses of all supervised peers, with the generation of the task
+    /// supervising each of them.
+    known_peers: Arc<RwLock<HashMap<SocketAddr, u64>>>,
+    /// The generation of the most recently spawned supervising task.
+    last_generation: Arc<AtomicU64>,
+}
+
+impl<S> Supervisor<S>
+where
+    S: Store + Clone,
+{
+    /// Create a new `Supervisor` for the given manager.
+    pub fn new(manager: CableManager<S>, options: SupervisorOptions) -> Self {
+        Supervisor {
+            manager,
+            options,
+            known_peers: Arc::new(RwLock::new(HashMap::new())),
+            last_generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Return the addresses of all supervised peers.
+    pub async fn known_peers(&self) -> Vec<SocketAddr> {
+        self.known_peers.read().await.keys().copied().collect()
+    }
+
+    /// Remember the given peer address and begin maintaining a connection
+    /// to it.
+    ///
+    /// Returns `false` if the address was already being supervised.
+    pub async fn add_peer(&self, addr: SocketAddr) -> bool {
+        let generation = {
+            let mut known_peers = self.known_peers.write().await;
+            if known_peers.contains_key(&addr) {
+                return false;
+            }
+            let generation = self.last_generation.fetch_add(1, Ordering::Relaxed) + 1;
+            known_peers.insert(addr, generation);
+            generation
+        };
+
+        let this = self.clone();
+        task::spawn(async move { this.supervise(addr, generation).await });
+
+        true
+    }
+
+    /// Forget the given peer address.
+    ///
+    /// Any established connection is left open, but it will not be
+    /// re-established once it closes, even if the address is added again in
+    /// the meantime.
+    pub async fn remove_peer(&self, addr: &SocketAddr) -> bool {
+        self.known_peers.write().await.remove(addr).is_some()
+    }
+
+    /// Query whether the given address is still supervised by the task of
+    /// the given generation.
+    async fn is_supervising(&self, addr: &SocketAddr, generation: u64) -> bool {
+        self.known_peers.read().await.get(addr) == Some(&generation)
+    }
+
+    /// Connect to the given address, handing each established stream to the
+    /// manager and reconnecting with backoff until the address is removed.
+    async fn supervise(&self, addr: SocketAddr, generation: u64) {
+        let mut backoff =
+            Backoff::new(self.options.initial_backoff, self.options.max_backoff).with_jitter();
+
+        while self.is_supervising(&addr, generation).await {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => {
+                    debug!("Connected to supervised peer {}", addr);
+                    backoff.reset();
+
+                    // Active local requests are sent to the peer as part of
+                    // the listener setup.
+                    match self.manager.listen(stream).await {
+                        Ok(()) => debug!("Connection to {} closed", addr),
+                        Err(err) => debug!("Connection to {} failed: {}", addr, err),
+                    }
+                }
+                Err(err) => {
+                    debug!("Failed to connect to supervised peer {}: {}", addr, err);
+                }
+            }
+
+            if !self.is_supervising(&addr, generation).await {
+                break;
+            }
+
+            let delay = backoff.next_delay();
+            debug!("Reconnecting to {} in {:?}", addr, delay);
+            task::sleep(delay).await;
+        }
+    }
+}
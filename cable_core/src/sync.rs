@@ -0,0 +1,60 @@
+//! Progress of the synchronisation of a channel with remote peers.
+
+use std::fmt;
+
+use cable::Timestamp;
+
+/// A snapshot of the synchronisation of a channel, as returned by
+/// `CableManager::sync_status()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// The number of active requests for the channel made by the local peer.
+    pub open_requests: usize,
+    /// The number of posts of the channel which have been requested from
+    /// peers and not yet received.
+    pub pending_posts: usize,
+    /// The number of distinct post hashes returned by peers in response to
+    /// the requests for the channel.
+    pub known_hashes: usize,
+    /// The number of the known hashes for which the post has been fetched
+    /// (or deleted).
+    pub fetched_hashes: usize,
+    /// The timestamp of the oldest stored post of the channel, if any.
+    pub oldest_timestamp: Option<Timestamp>,
+}
+
+impl SyncStatus {
+    /// The fraction of the known hashes which have been fetched, between 0
+    /// and 1. A channel for which no hashes are known is fully synced.
+    pub fn progress(&self) -> f64 {
+        if self.known_hashes == 0 {
+            1.0
+        } else {
+            self.fetched_hashes as f64 / self.known_hashes as f64
+        }
+    }
+
+    /// Query whether all known hashes have been fetched.
+    pub fn is_synced(&self) -> bool {
+        self.fetched_hashes == self.known_hashes
+    }
+}
+
+impl fmt::Display for SyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} posts fetched ({:.0}%), {} pending, {} open requests",
+            self.fetched_hashes,
+            self.known_hashes,
+            self.progress() * 100.0,
+            self.pending_posts,
+            self.open_requests
+        )?;
+        if let Some(oldest_timestamp) = self.oldest_timestamp {
+            write!(f, ", synced back to {}", oldest_timestamp)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+//! An in-process harness for testing several cable peers at once.
+//!
+//! A `Network` creates a `CableManager` for each peer and connects the peers
+//! over in-memory duplex streams according to a `Topology`, so that request
+//! forwarding, TTL handling and live subscriptions can be exercised without
+//! opening sockets. Connections may be severed and re-established while the
+//! network is running.
+//!
+//! ```rust,ignore
+//! use cable_core::testing::{eventually, Network, Topology};
+//!
+//! let mut network = Network::new(3, Topology::Line);
+//! network.peer(0).post_text("default", "hello").await?;
+//!
+//! assert!(eventually(Duration::from_secs(5), || async { ... }).await);
+//! ```
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use async_std::task::{self, JoinHandle};
+use futures::{AsyncRead, AsyncWrite};
+
+use crate::{manager::CableManager, store::MemoryStore, store::Store};
+
+/// The bytes written to one end of a duplex stream and not yet read from the
+/// other.
+#[derive(Debug, Default)]
+pub(crate) struct Pipe {
+    pub(crate) buf: VecDeque<u8>,
+    pub(crate) closed: bool,
+    /// The task waiting to read from the pipe.
+    pub(crate) reader: Option<Waker>,
+}
+
+impl Pipe {
+    /// Wake the task waiting to read from the pipe, if any.
+    pub(crate) fn wake(&mut self) {
+        if let Some(waker) = self.reader.take() {
+            waker.wake();
+        }
+    }
+}
+
+pub(crate) type SharedPipe = Arc<Mutex<Pipe>>;
+
+/// Lock the given pipe, recovering it if a panicking thread held the lock.
+pub(crate) fn lock(pipe: &SharedPipe) -> std::sync::MutexGuard<'_, Pipe> {
+    pipe.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// One end of an in-memory duplex stream, as created by `duplex()`.
+///
+/// Clones refer to the same end of the stream. Bytes written to one end are
+/// read from the other; once either end is closed, reads from both ends
+/// return end-of-file after any buffered bytes and writes fail.
+#[derive(Clone, Debug)]
+pub struct MemoryStream {
+    read: SharedPipe,
+    write: SharedPipe,
+}
+
+impl MemoryStream {
+    /// Create a stream reading from and writing to the given pipes.
+    pub(crate) fn new(read: SharedPipe, write: SharedPipe) -> Self {
+        MemoryStream { read, write }
+    }
+
+    /// Close both directions of the stream.
+    pub fn close(&self) {
+        for pipe in [&self.read, &self.write] {
+            let mut pipe = lock(pipe);
+            pipe.closed = true;
+            pipe.wake();
+        }
+    }
+}
+
+/// Create a connected pair of in-memory streams.
+pub fn duplex() -> (MemoryStream, MemoryStream) {
+    let a_to_b = SharedPipe::default();
+    let b_to_a = SharedPipe::default();
+
+    (
+        MemoryStream {
+            read: b_to_a.clone(),
+            write: a_to_b.clone(),
+        },
+        MemoryStream {
+            read: a_to_b,
+            write: b_to_a,
+        },
+    )
+}
+
+impl AsyncRead for MemoryStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = lock(&self.read);
+        if pipe.buf.is_empty() {
+            if pipe.closed {
+                return Poll::Ready(Ok(0));
+            }
+            pipe.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let len = buf.len().min(pipe.buf.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buf.drain(..len)) {
+            *dst = src;
+        }
+
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncWrite for MemoryStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = lock(&self.write);
+        if pipe.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        pipe.buf.extend(buf);
+        pipe.wake();
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.close();
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// The arrangement of the connections between the peers of a `Network`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Each peer is connected to the next: 0 - 1 - 2 - ... - n.
+    Line,
+    /// A line in which the last peer is also connected to the first.
+    Ring,
+    /// Peer 0 is connected to every other peer.
+    Star,
+    /// Every peer is connected to every other peer.
+    Complete,
+    /// The given pairs of peers are connected.
+    Custom(Vec<(usize, usize)>),
+}
+
+impl Topology {
+    /// Return the pairs of connected peers in a network of the given size.
+    pub fn edges(&self, peers: usize) -> Vec<(usize, usize)> {
+        match self {
+            Topology::Line => (1..peers).map(|i| (i - 1, i)).collect(),
+            Topology::Ring => {
+                let mut edges = Topology::Line.edges(peers);
+                if peers > 2 {
+                    edges.push((peers - 1, 0));
+                }
+                edges
+            }
+            Topology::Star => (1..peers).map(|i| (0, i)).collect(),
+            Topology::Complete => (0..peers)
+                .flat_map(|a| (a + 1..peers).map(move |b| (a, b)))
+                .collect(),
+            Topology::Custom(edges) => edges.clone(),
+        }
+    }
+}
+
+/// A connection between two peers of a `Network`.
+struct Link {
+    stream: MemoryStream,
+    listeners: [JoinHandle<()>; 2],
+}
+
+/// A set of cable peers connected over in-memory streams.
+pub struct Network<S: Store + Clone> {
+    peers: Vec<CableManager<S>>,
+    links: HashMap<(usize, usize), Link>,
+}
+
+impl Network<MemoryStore> {
+    /// Create a network of the given number of peers, each with an empty
+    /// `MemoryStore`, connected according to the given topology.
+    pub fn new(peers: usize, topology: Topology) -> Self {
+        let managers = (0..peers)
+            .map(|_| CableManager::new(MemoryStore::default()))
+            .collect();
+
+        Network::with_managers(managers, topology)
+    }
+}
+
+impl<S: Store + Clone> Network<S> {
+    /// Create a network of the given managers, connected according to the
+    /// given topology.
+    pub fn with_managers(managers: Vec<CableManager<S>>, topology: Topology) -> Self {
+        let mut network = Network {
+            links: HashMap::new(),
+            peers: managers,
+        };
+        for (a, b) in topology.edges(network.peers.len()) {
+            network.connect(a, b);
+        }
+
+        network
+    }
+
+    /// Return the manager of the given peer.
+    ///
+    /// Panics if there is no such peer.
+    pub fn peer(&self, index: usize) -> CableManager<S> {
+        self.peers[index].clone()
+    }
+
+    /// Return the managers of all peers, in order.
+    pub fn peers(&self) -> &[CableManager<S>] {
+        &self.peers
+    }
+
+    /// Return the number of peers in the network.
+    pub fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Return `true` if the network has no peers.
+    pub fn is_empty(&self) -> bool {
+        self.peers.is_empty()
+    }
+
+    /// Return `true` if the given peers are connected.
+    pub fn is_connected(&self, a: usize, b: usize) -> bool {
+        self.links.contains_key(&(a.min(b), a.max(b)))
+    }
+
+    /// Connect the given peers, unless they are already connected.
+    ///
+    /// Panics if either peer does not exist or if a peer is connected to
+    /// itself.
+    pub fn connect(&mut self, a: usize, b: usize) {
+        assert!(a != b, "a peer cannot be connected to itself");
+        let key = (a.min(b), a.max(b));
+        if self.links.contains_key(&key) {
+            return;
+        }
+
+        let (stream_a, stream_b) = duplex();
+        let listeners = [(a, stream_a.clone()), (b, stream_b)].map(|(peer, stream)| {
+            let manager = self.peers[peer].clone();
+            task::spawn(async move {
+                if let Err(err) = manager.listen(stream).await {
+                    log::debug!("Connection of test peer {} closed: {}", peer, err);
+                }
+            })
+        });
+
+        self.links.insert(
+            key,
+            Link {
+                stream: stream_a,
+                listeners,
+            },
+        );
+    }
+
+    /// Sever the connection between the given peers, waiting until both
+    /// peers have stopped listening. Returns `false` if the peers were not
+    /// connected.
+    pub async fn disconnect(&mut self, a: usize, b: usize) -> bool {
+        match self.links.remove(&(a.min(b), a.max(b))) {
+            Some(link) => {
+                link.stream.close();
+                for listener in link.listeners {
+                    listener.await;
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sever all connections of the network.
+    pub async fn shutdown(&mut self) {
+        let keys: Vec<_> = self.links.keys().cloned().collect();
+        for (a, b) in keys {
+            self.disconnect(a, b).await;
+        }
+    }
+}
+
+/// Evaluate the given check repeatedly until it returns `true` or the given
+/// timeout elapses, returning the final result.
+///
+/// Messages between peers are handled asynchronously, so assertions about the
+/// state of a network are usually made with this function.
+pub async fn eventually<F, Fut>(timeout: Duration, mut check: F) -> bool
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let interval = Duration::from_millis(10);
+    let mut elapsed = Duration::ZERO;
+    loop {
+        if check().await {
+            return true;
+        }
+        if elapsed >= timeout {
+            return false;
+        }
+        task::sleep(interval).await;
+        elapsed += interval;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn topology_edges() {
+        assert_eq!(Topology::Line.edges(3), vec![(0, 1), (1, 2)]);
+        assert_eq!(Topology::Ring.edges(3), vec![(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(Topology::Star.edges(3), vec![(0, 1), (0, 2)]);
+        assert_eq!(Topology::Complete.edges(3), vec![(0, 1), (0, 2), (1, 2)]);
+        assert!(Topology::Ring.edges(1).is_empty());
+    }
+
+    #[async_std::test]
+    async fn duplex_streams_carry_bytes_until_closed() -> io::Result<()> {
+        let (mut a, mut b) = duplex();
+
+        a.write_all(b"ping").await?;
+        let mut buf = [0; 4];
+        b.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"ping");
+
+        let reader = task::spawn(async move {
+            let mut buf = Vec::new();
+            a.read_to_end(&mut buf).await.map(|_| buf)
+        });
+        b.write_all(b"pong").await?;
+        AsyncWriteExt::close(&mut b).await?;
+        assert_eq!(reader.await?, b"pong");
+
+        assert!(b.write_all(b"late").await.is_err());
+
+        Ok(())
+    }
+}
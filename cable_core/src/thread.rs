@@ -0,0 +1,121 @@
+//! Threads of replies built on post links.
+//!
+//! A reply is a `post/text` post whose text begins with a reply marker of the
+//! form `reply:<hex hash>`, naming the post replied to, and whose links
+//! include the hash of that post. Both are required, so that a post quoting a
+//! marker is not mistaken for a reply. Replies are indexed by the store as
+//! they are inserted and assembled into a tree with `Store::get_thread()`.
+
+use std::collections::{HashMap, HashSet};
+
+use cable::Hash;
+
+/// The prefix of the reply marker at the start of the text of a reply.
+const MARKER_PREFIX: &str = "reply:";
+
+/// Return the text of a reply to the post with the given hash, prefixing the
+/// given text with a reply marker.
+pub fn reply_text(parent: &Hash, text: &str) -> String {
+    format!("{}{} {}", MARKER_PREFIX, hex::encode(parent), text)
+}
+
+/// Return the hash of the post replied to and the remaining text if the
+/// given text begins with a reply marker.
+pub fn parse_reply(text: &str) -> Option<(Hash, &str)> {
+    let rest = text.strip_prefix(MARKER_PREFIX)?;
+    let parent = <[u8; 32] as hex::FromHex>::from_hex(rest.get(..64)?).ok()?;
+    let rest = &rest[64..];
+
+    // The marker must be followed by whitespace or end the text.
+    if rest.starts_with(|c: char| !c.is_whitespace()) {
+        return None;
+    }
+
+    Some((parent, rest.trim_start()))
+}
+
+/// A post and the tree of replies to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thread {
+    /// The hash of the post.
+    pub hash: Hash,
+    /// The threads of the replies to the post, ordered by timestamp.
+    pub replies: Vec<Thread>,
+}
+
+impl Thread {
+    /// Assemble the thread rooted at the given hash from the replies to each
+    /// post, visiting each post at most once.
+    pub(crate) fn assemble(root: Hash, replies: &HashMap<Hash, Vec<Hash>>) -> Self {
+        let mut visited = HashSet::new();
+
+        Self::assemble_visited(root, replies, &mut visited)
+    }
+
+    fn assemble_visited(
+        hash: Hash,
+        replies: &HashMap<Hash, Vec<Hash>>,
+        visited: &mut HashSet<Hash>,
+    ) -> Self {
+        visited.insert(hash);
+        let replies = replies
+            .get(&hash)
+            .into_iter()
+            .flatten()
+            .filter_map(|reply| {
+                if visited.contains(reply) {
+                    return None;
+                }
+
+                Some(Self::assemble_visited(*reply, replies, visited))
+            })
+            .collect();
+
+        Thread { hash, replies }
+    }
+
+    /// Return the number of replies in the thread, at any depth.
+    pub fn len(&self) -> usize {
+        self.replies.iter().map(|reply| 1 + reply.len()).sum()
+    }
+
+    /// Query whether the post has no replies.
+    pub fn is_empty(&self) -> bool {
+        self.replies.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_reply_markers() {
+        let parent = [7; 32];
+        let text = reply_text(&parent, "what a moth!");
+
+        assert_eq!(parse_reply(&text), Some((parent, "what a moth!")));
+        assert_eq!(
+            parse_reply(&format!("reply:{}", hex::encode(parent))),
+            Some((parent, ""))
+        );
+        assert!(parse_reply(&format!("reply:{}x", hex::encode(parent))).is_none());
+        assert!(parse_reply("reply:cafe moths").is_none());
+        assert!(parse_reply(&format!("see {}", text)).is_none());
+    }
+
+    #[test]
+    fn assemble_thread() {
+        let replies = HashMap::from([
+            ([0; 32], vec![[1; 32], [2; 32]]),
+            ([1; 32], vec![[3; 32]]),
+            ([3; 32], vec![[0; 32]]),
+        ]);
+        let thread = Thread::assemble([0; 32], &replies);
+
+        assert_eq!(thread.len(), 3);
+        assert_eq!(thread.replies[0].replies[0].hash, [3; 32]);
+        assert!(thread.replies[0].replies[0].is_empty());
+        assert!(thread.replies[1].is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! Verification and decoding of the posts of post responses.
+//!
+//! Verifying signatures dominates the cost of handling the post responses of
+//! an initial sync. The posts of a response are divided between a bounded
+//! number of blocking tasks, which verify and decode them in parallel. The
+//! results are returned in the order of the response, so that the posts are
+//! stored in the order in which they were received.
+
+use cable::{limits::Limits, Error, Payload, Post};
+
+/// Verify and decode the given encoded post.
+///
+/// Returns `None` if the signature is invalid or if the post is followed by
+/// trailing bytes, and an error if the post exceeds the given limits.
+fn verify_post(post_bytes: &[u8], limits: &Limits) -> Result<Option<Post>, Error> {
+    if !Post::verify(post_bytes) {
+        return Ok(None);
+    }
+
+    let (s, post) = Post::from_bytes_with_limits(post_bytes, limits)?;
+
+    // Ensure the number of processed bytes matches the received amount.
+    if s != post_bytes.len() {
+        return Ok(None);
+    }
+
+    Ok(Some(post))
+}
+
+/// Verify and decode the given encoded posts, using at most the given number
+/// of workers.
+///
+/// The returned list holds an entry for each given post, in order; see
+/// `verify_post()`.
+pub(crate) async fn verify_posts(
+    posts: &[Payload],
+    workers: usize,
+    limits: Limits,
+) -> Result<Vec<Option<Post>>, Error> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if workers > 1 && posts.len() > 1 {
+        let chunk_size = posts.len().div_ceil(workers);
+        let handles: Vec<_> = posts
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                async_std::task::spawn_blocking(move || {
+                    chunk
+                        .iter()
+                        .map(|post_bytes| verify_post(post_bytes, &limits))
+                        .collect::<Result<Vec<_>, Error>>()
+                })
+            })
+            .collect();
+
+        let mut verified = Vec::with_capacity(posts.len());
+        for handle in handles {
+            verified.extend(handle.await?);
+        }
+
+        return Ok(verified);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    let _ = workers;
+
+    posts
+        .iter()
+        .map(|post_bytes| verify_post(post_bytes, &limits))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use cable::crypto;
+    use desert::ToBytes;
+
+    #[async_std::test]
+    async fn verify_posts_in_order() -> Result<(), Error> {
+        let (pk, sk) = crypto::generate_keypair();
+
+        let mut posts = Vec::new();
+        for i in 0..9 {
+            let mut post = Post::text(pk, vec![], i, "entomology".into(), format!("moth {}", i));
+            post.sign(&sk)?;
+            posts.push(post.to_bytes()?);
+        }
+        // Corrupt the text of the fifth post, invalidating its signature.
+        let last = posts[4].len() - 1;
+        posts[4][last] ^= 1;
+
+        for workers in [1, 2, 4, 16] {
+            let verified = verify_posts(&posts, workers, Limits::DEFAULT).await?;
+            assert_eq!(verified.len(), 9);
+            for (i, post) in verified.iter().enumerate() {
+                match post {
+                    Some(post) => assert_eq!(post.get_timestamp(), i as u64),
+                    None => assert_eq!(i, 4),
+                }
+            }
+            assert!(verified[4].is_none());
+        }
+
+        Ok(())
+    }
+}
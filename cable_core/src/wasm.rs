@@ -0,0 +1,32 @@
+//! Substitutes for functionality which is unavailable in the browser.
+//!
+//! Passphrase-based encryption relies on libsodium, which cannot be compiled
+//! for `wasm32-unknown-unknown`. Exporting and importing identities therefore
+//! returns an error on that target.
+
+use cable::{error::CableErrorKind, Error};
+
+use crate::store::Keypair;
+
+/// Return an error indicating that passphrase encryption is unsupported.
+fn unsupported<T>() -> Result<T, Error> {
+    CableErrorKind::NoneError {
+        context: "passphrase encryption is not supported on this target".to_string(),
+    }
+    .raise()
+}
+
+/// Export the given keypair as an identity protected by the given
+/// passphrase.
+///
+/// Always returns an error on this target.
+pub fn encrypt_keypair(_keypair: &Keypair, _passphrase: &str) -> Result<Vec<u8>, Error> {
+    unsupported()
+}
+
+/// Import a keypair from an identity exported with the given passphrase.
+///
+/// Always returns an error on this target.
+pub fn decrypt_keypair(_identity: &[u8], _passphrase: &str) -> Result<Keypair, Error> {
+    unsupported()
+}
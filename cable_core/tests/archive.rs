@@ -0,0 +1,93 @@
+//! Test the export and import of store archives.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts to two channels, a name and a deleted post.
+//!
+//! 2) Export an archive of one channel, including the keypair.
+//!
+//! 3) Import the archive into a new store with the passphrase.
+//!
+//! 4) Ensure the keypair, channel posts and channel state posts were imported.
+//!
+//! 5) Ensure the other channel and the deleted post were not imported.
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{CableManager, ExportOptions, MemoryStore, Store};
+
+#[async_std::test]
+async fn export_and_import_archive() -> Result<(), Error> {
+    let entomology = "entomology".to_string();
+    let botany = "botany".to_string();
+
+    let mut cable = CableManager::new(MemoryStore::default());
+    cable.post_join(&entomology).await?;
+    cable.post_join(&botany).await?;
+    cable.post_topic(&entomology, "insects").await?;
+    cable.post_text(&entomology, "moths").await?;
+    let deleted_hash = cable.post_text(&entomology, "oops").await?;
+    cable.post_text(&botany, "ferns").await?;
+    cable.post_info_name("glyph").await?;
+    let delete_hash = cable.post_delete(vec![deleted_hash]).await?;
+
+    let opts = ExportOptions {
+        channels: Some(vec![entomology.clone()]),
+        passphrase: Some("correct horse".to_string()),
+    };
+    let mut archive = Vec::new();
+    let exported = cable.store.export(&mut archive, &opts).await?;
+
+    let mut store = MemoryStore::default();
+    let imported = store
+        .import(&mut archive.as_slice(), Some("correct horse"))
+        .await?;
+    assert_eq!(imported, exported);
+
+    let keypair = cable.store.get_keypair().await;
+    assert_eq!(store.get_keypair().await, keypair);
+    let (public_key, _secret_key) = keypair.unwrap();
+
+    let mut texts = Vec::new();
+    let mut posts = store
+        .get_posts(&ChannelOptions::new(&entomology, 0, 0, 0))
+        .await;
+    while let Some(post) = posts.next().await {
+        if let PostBody::Text { text, .. } = post?.body {
+            texts.push(text);
+        }
+    }
+    assert_eq!(texts, vec!["moths".to_string()]);
+
+    assert_eq!(store.get_channels().await, Some(vec![entomology.clone()]));
+    assert!(store.is_channel_member(&entomology, &public_key).await);
+    assert_eq!(
+        store
+            .get_channel_topic_and_hash(&entomology)
+            .await
+            .map(|(topic, _hash)| topic),
+        Some("insects".to_string())
+    );
+    assert_eq!(
+        store
+            .get_peer_name_and_hash(&public_key)
+            .await
+            .map(|(name, _hash)| name),
+        Some("glyph".to_string())
+    );
+    assert!(store.get_post_payload(&delete_hash).await.is_some());
+    assert!(store.get_post_payload(&deleted_hash).await.is_none());
+
+    // Without a passphrase, the posts are imported but the keypair is not.
+    let mut store = MemoryStore::default();
+    store.import(&mut archive.as_slice(), None).await?;
+    assert_ne!(store.get_keypair().await, keypair);
+
+    // A truncated archive is rejected.
+    let mut store = MemoryStore::default();
+    let truncated = &archive[..archive.len() - 1];
+    assert!(store.import(&mut &truncated[..], None).await.is_err());
+
+    Ok(())
+}
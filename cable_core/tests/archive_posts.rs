@@ -0,0 +1,120 @@
+//! Test archiving channels with archive posts and hiding them from channel
+//! lists according to the archive policy.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts to two channels and an archive post for one of them,
+//! ensuring the archive post is indexed and hides the channel.
+//!
+//! 2) Connect a second manager and request the channel list, ensuring the
+//! archived channel is not listed in the response.
+//!
+//! 3) Ensure the archive policy decides whose archive posts hide a channel.
+//!
+//! 4) Unarchive the channel, ensuring it is listed again.
+
+#![cfg(feature = "archive-posts")]
+
+use std::time::Duration;
+
+use cable::Error;
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    ArchivePolicy, CableManager, ManagerOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn archive_posts<S: Store + Clone>(store: S, peer_store: S) -> Result<(), Error> {
+    let mut cable = CableManager::new(store.clone());
+    let public_key = cable.get_public_key().await?;
+    cable.post_text("entomology", "moth").await?;
+    cable.post_text("botany", "fern").await?;
+    let archive = cable.post_archive("entomology").await?;
+
+    let entomology = "entomology".to_string();
+    assert_eq!(
+        cable.store.get_channel_archives(&entomology).await,
+        vec![(public_key, archive)]
+    );
+    assert!(cable.is_channel_archived_by_post(&entomology).await);
+    assert_eq!(cable.list_channels().await, vec!["botany".to_string()]);
+    assert!(cable.has_post(&archive).await);
+
+    // Peers are not sent archived channels in channel list responses.
+    let network = Network::with_managers(
+        vec![cable.clone(), CableManager::new(peer_store)],
+        Topology::Line,
+    );
+    let peer = network.peer(1);
+    assert!(
+        eventually(TIMEOUT, || async {
+            !peer.list_connections().await.is_empty()
+        })
+        .await
+    );
+    peer.request_channel_list(0, 0).await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            peer.store.get_channels().await == Some(vec!["botany".to_string()])
+        })
+        .await
+    );
+
+    // Only the archive posts of operators hide a channel under a policy of
+    // operators.
+    let options = ManagerOptions {
+        archive_policy: ArchivePolicy::Operators(vec![[1; 32]]),
+        ..ManagerOptions::default()
+    };
+    let operated = CableManager::with_options(store.clone(), options);
+    assert!(!operated.is_channel_archived_by_post(&entomology).await);
+    let options = ManagerOptions {
+        archive_policy: ArchivePolicy::Operators(vec![public_key]),
+        ..ManagerOptions::default()
+    };
+    let operated = CableManager::with_options(store.clone(), options);
+    assert!(operated.is_channel_archived_by_post(&entomology).await);
+    let ignoring = CableManager::builder(store)
+        .archive_policy(ArchivePolicy::Ignore)
+        .build();
+    assert_eq!(ignoring.list_channels().await.len(), 2);
+
+    assert!(cable.post_unarchive("entomology").await?.is_some());
+    assert!(cable
+        .store
+        .get_channel_archives(&entomology)
+        .await
+        .is_empty());
+    assert!(!cable.is_channel_archived_by_post(&entomology).await);
+    assert_eq!(cable.list_channels().await.len(), 2);
+    assert!(cable.post_unarchive("entomology").await?.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn archive_posts_memory_store() -> Result<(), Error> {
+    archive_posts(MemoryStore::default(), MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn archive_posts_sled_store() -> Result<(), Error> {
+    archive_posts(
+        cable_core::SledStore::temporary()?,
+        cable_core::SledStore::temporary()?,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn archive_posts_sqlite_store() -> Result<(), Error> {
+    archive_posts(
+        cable_core::SqliteStore::open_in_memory()?,
+        cable_core::SqliteStore::open_in_memory()?,
+    )
+    .await
+}
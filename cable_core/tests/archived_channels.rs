@@ -0,0 +1,63 @@
+//! Test archiving and unarchiving channels.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts to two channels and open one of them.
+//!
+//! 2) Archive the opened channel and ensure it is excluded from the channel
+//!    list, its live requests are closed and its posts are retained.
+//!
+//! 3) Unarchive the channel and ensure it is listed again.
+
+use cable::{ChannelOptions, Error};
+
+use cable_core::{CableManager, MemoryStore, Store};
+
+// Archive and unarchive a channel of a manager over the given store.
+async fn archive<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let mut cable = CableManager::new(store);
+    let moth = cable.post_text("entomology", "moth").await?;
+    cable.post_text("botany", "fern").await?;
+    let _ = cable
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    assert!(!cable.store.get_outbound_requests().await.is_empty());
+
+    let channels = vec!["botany".to_string(), "entomology".to_string()];
+    assert_eq!(cable.list_channels().await, channels);
+
+    cable.archive_channel("entomology").await?;
+    assert_eq!(cable.list_channels().await, vec!["botany".to_string()]);
+    assert_eq!(
+        cable.store.get_archived_channels().await,
+        vec!["entomology".to_string()]
+    );
+    assert!(cable.store.is_channel_archived(&channels[1]).await);
+    assert!(cable.store.get_outbound_requests().await.is_empty());
+    assert!(cable.has_post(&moth).await);
+    assert_eq!(cable.store.get_channels().await, Some(channels.clone()));
+
+    cable.unarchive_channel("entomology").await;
+    assert_eq!(cable.list_channels().await, channels);
+    assert!(cable.store.get_archived_channels().await.is_empty());
+    assert!(!cable.store.is_channel_archived(&channels[1]).await);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn archive_memory_store() -> Result<(), Error> {
+    archive(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn archive_sled_store() -> Result<(), Error> {
+    archive(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn archive_sqlite_store() -> Result<(), Error> {
+    archive(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,114 @@
+//! Test setting avatars with info posts and syncing them with peers.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Join a channel and publish an inline avatar, ensuring it is indexed as
+//! the latest avatar and included in the channel state.
+//!
+//! 2) Publish a blob avatar, ensuring it replaces the inline avatar, and
+//! ensure an oversized inline avatar is rejected.
+//!
+//! 3) Connect a second manager and open the channel, ensuring the avatar is
+//! synced with the channel state.
+//!
+//! 4) Delete the latest avatar post, ensuring the previous avatar is
+//! restored.
+
+use std::{sync::Arc, time::Duration};
+
+use cable::{Avatar, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, MockClock, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn sync_avatars<S: Store + Clone>(store: S, peer_store: S) -> Result<(), Error> {
+    let clock = MockClock::new(1_000);
+    let mut cable = CableManager::builder(store)
+        .clock(Arc::new(clock.clone()))
+        .build();
+    let public_key = cable.get_public_key().await?;
+    let channel = "entomology".to_string();
+    cable.post_join(&channel).await?;
+
+    let inline = Avatar::Inline(vec![0x89, 0x50, 0x4e, 0x47]);
+    let inline_hash = cable.post_info_avatar(&inline).await?;
+    assert_eq!(
+        cable.get_peer_avatar(&public_key).await,
+        Some(inline.clone())
+    );
+    assert!(cable
+        .store
+        .get_channel_state_hashes(&channel)
+        .await
+        .contains(&inline_hash));
+
+    clock.advance(Duration::from_secs(1));
+    let blob = Avatar::Blob([7; 32]);
+    let blob_hash = cable.post_info_avatar(&blob).await?;
+    assert_eq!(
+        cable.store.get_peer_avatar_and_hash(&public_key).await,
+        Some((blob.clone(), blob_hash))
+    );
+    assert!(cable
+        .post_info_avatar(&Avatar::Inline(vec![0; 2048]))
+        .await
+        .is_err());
+
+    // Peers learn of the avatar from the channel state.
+    let network = Network::with_managers(
+        vec![cable.clone(), CableManager::new(peer_store)],
+        Topology::Line,
+    );
+    let mut peer = network.peer(1);
+    assert!(
+        eventually(TIMEOUT, || async {
+            !peer.list_connections().await.is_empty()
+        })
+        .await
+    );
+    let _live = peer
+        .open_channel(&ChannelOptions::new(&channel, 0, 0, 0))
+        .await?;
+    let synced = network.peer(1);
+    assert!(
+        eventually(TIMEOUT, || async {
+            synced.get_peer_avatar(&public_key).await == Some(blob.clone())
+        })
+        .await
+    );
+
+    // Deleting the latest avatar restores the previous one.
+    cable.post_delete(vec![blob_hash]).await?;
+    assert_eq!(cable.get_peer_avatar(&public_key).await, Some(inline));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn sync_avatars_memory_store() -> Result<(), Error> {
+    sync_avatars(MemoryStore::default(), MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn sync_avatars_sled_store() -> Result<(), Error> {
+    sync_avatars(
+        cable_core::SledStore::temporary()?,
+        cable_core::SledStore::temporary()?,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn sync_avatars_sqlite_store() -> Result<(), Error> {
+    sync_avatars(
+        cable_core::SqliteStore::open_in_memory()?,
+        cable_core::SqliteStore::open_in_memory()?,
+    )
+    .await
+}
@@ -0,0 +1,90 @@
+//! Test inserting the posts of a post response in a single batch.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create join and text posts by another author and tombstone one of the
+//! text posts.
+//!
+//! 2) Insert the posts in a single batch, ensuring their hashes are returned
+//! in order.
+//!
+//! 3) Ensure the posts are indexed like posts inserted one at a time, and
+//! that the tombstoned post is not stored.
+//!
+//! 4) Ensure the store may be compacted after a batch.
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{ChannelOptions, Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+async fn insert_batch<S: Store>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut posts = vec![Post::join(pk, vec![], 100, channel.clone())];
+    for i in 1..=3 {
+        posts.push(Post::text(
+            pk,
+            vec![],
+            100 + i,
+            channel.clone(),
+            format!("moth {}", i),
+        ));
+    }
+    for post in posts.iter_mut() {
+        post.sign(&sk)?;
+    }
+    let expected: Vec<_> = posts
+        .iter()
+        .map(|post| post.hash())
+        .collect::<Result<_, _>>()?;
+
+    // The second text post was deleted before it was received.
+    store.insert_tombstone(&expected[2]).await;
+
+    let hashes = store.insert_posts(&posts).await?;
+    assert_eq!(hashes, expected);
+
+    assert!(store.is_channel_member(&channel, &pk).await);
+    assert_eq!(store.get_channels().await.unwrap(), vec![channel.clone()]);
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let stored: Vec<_> = store
+        .get_post_hashes(&opts)
+        .await
+        .filter_map(|hash| hash.ok())
+        .collect()
+        .await;
+    assert!(stored.contains(&expected[1]));
+    assert!(!stored.contains(&expected[2]));
+    assert!(stored.contains(&expected[3]));
+    assert!(store.get_post_payload(&expected[2]).await.is_none());
+
+    assert!(store.insert_posts(&[]).await?.is_empty());
+    store.compact().await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn insert_batch_memory_store() -> Result<(), Error> {
+    insert_batch(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn insert_batch_sled_store() -> Result<(), Error> {
+    insert_batch(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn insert_batch_sqlite_store() -> Result<(), Error> {
+    insert_batch(cable_core::SqliteStore::open_in_memory()?).await
+}
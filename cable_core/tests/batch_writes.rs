@@ -0,0 +1,235 @@
+//! Test storing posts in batches of writes.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert two linked posts in a batch and ensure they are stored and
+//!    indexed once the batch is committed.
+//!
+//! 2) Begin a batch in a sled store and store the payload of a post without
+//!    indexing it, as if the store were interrupted mid-insert; ensure
+//!    reopening the store indexes the post.
+//!
+//! 3) Begin a batch in a SQLite store and insert a post without committing
+//!    the batch; ensure reopening the store discards the post, while a
+//!    committed batch is retained.
+//!
+//! 4) Insert a batch of posts whose last post fails to be inserted; ensure
+//!    the whole batch is rolled back and a later batch is committed.
+//!
+//! 5) Fail to commit a batch of a SQLite store while another connection
+//!    reads the database; ensure the batch is rolled back and a later batch
+//!    is committed.
+
+use std::convert::TryInto;
+
+use cable::{post::Post, Error, Hash};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+// Create a signed text post in the given channel, linking to the given hashes.
+fn text_post(channel: &str, text: &str, timestamp: u64, links: Vec<Hash>) -> Result<Post, Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let mut post = Post::text(
+        pk.as_ref().try_into()?,
+        links,
+        timestamp,
+        channel.to_string(),
+        text.to_string(),
+    );
+    post.sign(sk.as_ref().try_into()?)?;
+
+    Ok(post)
+}
+
+// Create a signed delete post of the given hashes.
+fn delete_post(hashes: Vec<Hash>) -> Result<Post, Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let mut post = Post::delete(pk.as_ref().try_into()?, vec![], 300, hashes);
+    post.sign(sk.as_ref().try_into()?)?;
+
+    Ok(post)
+}
+
+// Insert two linked posts into the given store in a single batch.
+async fn insert_batch<S: Store + Clone>(mut store: S) -> Result<(), Error> {
+    let channel = "entomology".to_string();
+    let moth = text_post(&channel, "moth", 100, vec![])?;
+    let beetle = text_post(&channel, "beetle", 200, vec![moth.hash()?])?;
+
+    store.begin_batch().await?;
+    store.insert_post(&moth).await?;
+    let beetle_hash = store.insert_post(&beetle).await?;
+    store.commit_batch().await?;
+
+    assert!(store.get_post_payload(&moth.hash()?).await.is_some());
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(vec![beetle_hash])
+    );
+
+    // Batches of different handles of the store run one after the other.
+    let wasp = text_post(&channel, "wasp", 300, vec![beetle_hash])?;
+    let mut other = store.clone();
+    store.begin_batch().await?;
+    let insert = async_std::task::spawn(async move { other.insert_posts(&[wasp]).await });
+    store.commit_batch().await?;
+    let wasp_hash = insert.await?[0];
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(vec![wasp_hash])
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn insert_memory_store_batch() -> Result<(), Error> {
+    insert_batch(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn insert_sled_store_batch() -> Result<(), Error> {
+    insert_batch(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn insert_sqlite_store_batch() -> Result<(), Error> {
+    insert_batch(cable_core::SqliteStore::open_in_memory()?).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn recover_interrupted_sled_batch() -> Result<(), Error> {
+    use cable_core::AuthorOptions;
+    use desert::ToBytes;
+
+    let db = sled::Config::new().temporary(true).open()?;
+    let channel = "entomology".to_string();
+    let post = text_post(&channel, "moth", 100, vec![])?;
+    let hash = post.hash()?;
+
+    let mut store = cable_core::SledStore::from_db(db.clone())?;
+    store.begin_batch().await?;
+    store.insert_post_payload(&hash, post.to_bytes()?).await;
+    assert_eq!(store.get_latest_hashes(&channel).await, None);
+    drop(store);
+
+    let store = cable_core::SledStore::from_db(db)?;
+    assert_eq!(store.get_latest_hashes(&channel).await, Some(vec![hash]));
+    assert_eq!(store.get_channels().await, Some(vec![channel]));
+    let page = store
+        .get_posts_by_author(&post.get_public_key(), &AuthorOptions::default())
+        .await?;
+    assert_eq!(page.posts.len(), 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn discard_uncommitted_sqlite_batch() -> Result<(), Error> {
+    use cable_core::SqliteStore;
+
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("cable.sqlite");
+    let channel = "entomology".to_string();
+    let moth = text_post(&channel, "moth", 100, vec![])?;
+    let beetle = text_post(&channel, "beetle", 200, vec![])?;
+
+    let mut store = SqliteStore::open(&db_path)?;
+    store.begin_batch().await?;
+    store.insert_post(&moth).await?;
+    store.commit_batch().await?;
+    store.begin_batch().await?;
+    store.insert_post(&beetle).await?;
+    drop(store);
+
+    let store = SqliteStore::open(&db_path)?;
+    assert!(store.get_post_payload(&moth.hash()?).await.is_some());
+    assert_eq!(store.get_post_payload(&beetle.hash()?).await, None);
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(vec![moth.hash()?])
+    );
+
+    Ok(())
+}
+
+// Insert a batch of posts whose last post fails to be inserted.
+async fn roll_back_batch<S: Store + Clone>(mut store: S) -> Result<(), Error> {
+    let channel = "entomology".to_string();
+
+    // Deleting a post whose stored payload does not decode fails.
+    let corrupt = [7; 32];
+    store.insert_post_payload(&corrupt, vec![0xff; 4]).await;
+    let moth = text_post(&channel, "moth", 100, vec![])?;
+    let delete = delete_post(vec![corrupt])?;
+
+    assert!(store
+        .insert_posts(&[moth.clone(), delete.clone()])
+        .await
+        .is_err());
+    assert_eq!(store.get_post_payload(&moth.hash()?).await, None);
+    assert_eq!(store.get_post_payload(&delete.hash()?).await, None);
+
+    // The rolled back batch no longer holds the store.
+    let beetle = text_post(&channel, "beetle", 200, vec![])?;
+    let hashes = store.insert_posts(&[beetle]).await?;
+    assert_eq!(store.get_latest_hashes(&channel).await, Some(hashes));
+
+    Ok(())
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn roll_back_sled_batch() -> Result<(), Error> {
+    roll_back_batch(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn roll_back_sqlite_batch() -> Result<(), Error> {
+    roll_back_batch(cable_core::SqliteStore::open_in_memory()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn roll_back_failed_sqlite_commit() -> Result<(), Error> {
+    use std::time::Duration;
+
+    use cable_core::SqliteStore;
+    use rusqlite::Connection;
+
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("cable.sqlite");
+    let channel = "entomology".to_string();
+    let moth = text_post(&channel, "moth", 100, vec![])?;
+    let beetle = text_post(&channel, "beetle", 200, vec![])?;
+
+    let conn = Connection::open(&db_path)?;
+    conn.busy_timeout(Duration::ZERO)?;
+    let mut store = SqliteStore::from_connection(conn)?;
+    store.begin_batch().await?;
+    store.insert_post(&moth).await?;
+
+    // The open read transaction of another connection prevents the commit.
+    let reader = Connection::open(&db_path)?;
+    reader.execute_batch("BEGIN")?;
+    reader.query_row("SELECT count(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })?;
+    assert!(store.commit_batch().await.is_err());
+    reader.execute_batch("COMMIT")?;
+
+    store.begin_batch().await?;
+    store.insert_post(&beetle).await?;
+    store.commit_batch().await?;
+
+    assert_eq!(store.get_post_payload(&moth.hash()?).await, None);
+    assert!(store.get_post_payload(&beetle.hash()?).await.is_some());
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Test attaching blobs and fetching them from peers in chunks.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Attach a blob larger than a single chunk on one manager and link it
+//! from a post.
+//!
+//! 2) Connect a second manager and fetch the blob by the hash linked from the
+//! post, ensuring it is stored once fetched.
+//!
+//! 3) Ensure a blob which no peer holds is not found, and that attaching a
+//! blob beyond the quota fails.
+
+#![cfg(feature = "blobs")]
+
+use std::time::Duration;
+
+use async_std::future;
+use cable::{post::PostBody, Error};
+
+use cable_core::{
+    blob_link, blob_links,
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn fetch_blobs<S: Store + Clone>(a: S, b: S) -> Result<(), Error> {
+    let mut a = CableManager::new(a);
+    let b = CableManager::builder(b).blob_quota(Some(200_000)).build();
+
+    let blob: Vec<u8> = (0..100_000).map(|i| (i % 251) as u8).collect();
+    let hash = a.attach_file(blob.clone()).await?;
+    assert_eq!(a.attach_file(blob.clone()).await?, hash);
+    assert_eq!(a.store.get_blob_usage().await, 100_000);
+
+    let text = format!("a luna moth! {}", blob_link(&hash));
+    let post_hash = a.post_text("entomology", text).await?;
+    let linked = match a.get_post(&post_hash).await?.map(|post| post.body) {
+        Some(PostBody::Text { text, .. }) => blob_links(&text),
+        _ => Vec::new(),
+    };
+    assert_eq!(linked, vec![hash]);
+
+    let mut network = Network::with_managers(vec![a, b.clone()], Topology::Line);
+    assert!(eventually(TIMEOUT, || async { !b.list_connections().await.is_empty() }).await);
+
+    let fetched = future::timeout(TIMEOUT, b.fetch_blob(&hash)).await??;
+    assert_eq!(fetched, blob);
+    assert_eq!(b.store.get_blob(&hash).await, Some(blob));
+
+    let missing = future::timeout(TIMEOUT, b.fetch_blob(&[0; 32])).await?;
+    assert!(missing.is_err());
+
+    // The fetched blob counts towards the quota of the second manager.
+    let mut b = network.peer(1);
+    assert!(b.attach_file(vec![1; 150_000]).await.is_err());
+    assert!(b.attach_file(vec![1; 50_000]).await.is_ok());
+
+    network.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn fetch_blobs_memory_store() -> Result<(), Error> {
+    fetch_blobs(MemoryStore::default(), MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn fetch_blobs_sled_store() -> Result<(), Error> {
+    fetch_blobs(
+        cable_core::SledStore::temporary()?,
+        cable_core::SledStore::temporary()?,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn fetch_blobs_sqlite_store() -> Result<(), Error> {
+    fetch_blobs(
+        cable_core::SqliteStore::open_in_memory()?,
+        cable_core::SqliteStore::open_in_memory()?,
+    )
+    .await
+}
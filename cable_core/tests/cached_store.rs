@@ -0,0 +1,63 @@
+//! Test the read-through cache layer over a store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts through a manager backed by a cached sled store.
+//!
+//! 2) Ensure payloads, wanted hashes and channels match the wrapped store.
+//!
+//! 3) Delete a post and ensure the cached payload is evicted.
+//!
+//! 4) Ensure cached payloads are served after the wrapped data is removed.
+
+#![cfg(feature = "sled")]
+
+use std::num::NonZeroUsize;
+
+use cable::Error;
+
+use cable_core::{CableManager, CachedStore, SledStore, Store};
+
+#[async_std::test]
+async fn cache_reads_through_to_store() -> Result<(), Error> {
+    let store = CachedStore::with_capacity(SledStore::temporary()?, NonZeroUsize::new(8).unwrap());
+    let mut cable = CableManager::new(store);
+    let channel = "entomology".to_string();
+
+    assert_eq!(cable.store.get_channels().await, None);
+
+    let text_hash = cable.post_text(&channel, "moths").await?;
+    let deleted_hash = cable.post_text(&channel, "oops").await?;
+    let unknown_hash = [7; 32];
+
+    assert_eq!(
+        cable.store.get_channels().await,
+        Some(vec![channel.clone()])
+    );
+    assert_eq!(
+        cable.store.get_post_payload(&text_hash).await,
+        cable.store.inner().get_post_payload(&text_hash).await
+    );
+    assert_eq!(
+        cable
+            .store
+            .want(&[text_hash, deleted_hash, unknown_hash])
+            .await,
+        vec![unknown_hash]
+    );
+
+    cable.post_delete(vec![deleted_hash]).await?;
+    assert!(cable.store.get_post_payload(&deleted_hash).await.is_none());
+    assert!(cable.store.want(&[deleted_hash]).await.is_empty());
+
+    // The payload is served from the cache, even once it is removed from the
+    // wrapped store directly.
+    let mut inner = cable.store.inner().clone();
+    inner.remove_post_payload(&text_hash).await;
+    assert!(cable.store.get_post_payload(&text_hash).await.is_some());
+
+    cable.store.clear().await;
+    assert!(cable.store.get_post_payload(&text_hash).await.is_none());
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+//! Test announcing capabilities and filtering unsupported message types.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two managers, only one of which announces its capabilities on
+//! connection, ensuring each learns the capabilities of the other.
+//!
+//! 2) Connect a raw peer to a cable manager and announce capabilities which
+//! exclude post responses, ensuring the manager announces its own
+//! capabilities in return.
+//!
+//! 3) Send a post request followed by a channel list request, ensuring only
+//! the channel list response is written to the raw peer.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    capabilities::Capabilities,
+    constants::{
+        CAPABILITY_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE, NO_CIRCUIT, POST_REQUEST,
+        PROTOCOL_VERSION,
+    },
+    Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, Network, Topology},
+    CableManager, ManagerOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+async fn exchange_capabilities<S: Store + Clone>(a: S, b: S) -> Result<(), Error> {
+    let a = CableManager::builder(a).announce_capabilities(true).build();
+    let b = CableManager::new(b);
+
+    let mut network = Network::with_managers(vec![a.clone(), b.clone()], Topology::Line);
+
+    // The manager which does not announce its capabilities on connection
+    // announces them in return.
+    for manager in [&a, &b] {
+        assert!(
+            eventually(TIMEOUT, || async {
+                manager
+                    .list_connections()
+                    .await
+                    .first()
+                    .and_then(|info| info.capabilities.clone())
+                    == Some(Capabilities::local())
+            })
+            .await
+        );
+    }
+
+    network.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn exchange_capabilities_memory_store() -> Result<(), Error> {
+    exchange_capabilities(MemoryStore::default(), MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn exchange_capabilities_sled_store() -> Result<(), Error> {
+    exchange_capabilities(
+        cable_core::SledStore::temporary()?,
+        cable_core::SledStore::temporary()?,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn exchange_capabilities_sqlite_store() -> Result<(), Error> {
+    exchange_capabilities(
+        cable_core::SqliteStore::open_in_memory()?,
+        cable_core::SqliteStore::open_in_memory()?,
+    )
+    .await
+}
+
+#[async_std::test]
+async fn skip_unsupported_messages() -> Result<(), Error> {
+    let options = ManagerOptions {
+        // Handle the messages in the order in which they are sent.
+        handler_concurrency: 1,
+        ..Default::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer.clone(), options);
+
+    // Announce capabilities excluding post responses.
+    let capabilities = Capabilities {
+        version: PROTOCOL_VERSION,
+        msg_types: vec![
+            POST_REQUEST,
+            CHANNEL_LIST_REQUEST,
+            CHANNEL_LIST_RESPONSE,
+            CAPABILITY_REQUEST,
+        ],
+        post_types: Vec::new(),
+    };
+    let announcement = capabilities.to_request([0, 0, 0, 1]);
+    let post_request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 2], 0, Vec::new());
+    let channel_list_request = Message::channel_list_request(NO_CIRCUIT, [0, 0, 0, 3], 0, 0, 0);
+    peer.write_all(&announcement.to_bytes()?).await?;
+    peer.write_all(&post_request.to_bytes()?).await?;
+    peer.write_all(&channel_list_request.to_bytes()?).await?;
+
+    let received = future::timeout(TIMEOUT, async {
+        let mut received = Vec::new();
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            received.push(msg.message_type());
+            if msg.message_type() == CHANNEL_LIST_RESPONSE {
+                return Ok(received);
+            }
+        }
+        Err::<_, Error>("stream closed".into())
+    })
+    .await??;
+    assert_eq!(received, vec![CAPABILITY_REQUEST, CHANNEL_LIST_RESPONSE]);
+
+    let info = cable.list_connections().await;
+    assert_eq!(info[0].capabilities, Some(capabilities));
+
+    Ok(())
+}
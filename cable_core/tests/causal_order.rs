@@ -0,0 +1,103 @@
+//! Test the causal ordering of channel posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert posts from two authors, one of whose clocks is behind.
+//!
+//! 2) Retrieve the posts in causal order.
+//!
+//! 3) Ensure each post follows the posts it links to.
+//!
+//! 4) Ensure concurrent posts are ordered by timestamp.
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error, Hash, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{causal_order, MemoryStore, Store};
+
+// Create a signed text post.
+fn text(links: Vec<Hash>, timestamp: u64, text: &str) -> Result<Post, Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let mut post = Post::text(
+        pk.as_ref().try_into()?,
+        links,
+        timestamp,
+        "entomology".into(),
+        text.into(),
+    );
+    post.sign(sk.as_ref().try_into()?)?;
+
+    Ok(post)
+}
+
+// Return the text of the given text posts.
+fn texts(posts: &[Post]) -> Vec<&str> {
+    posts
+        .iter()
+        .filter_map(|post| match &post.body {
+            PostBody::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn order_posts<S: Store>(mut store: S) -> Result<(), Error> {
+    // The clock of the author of "beetle" and "wasp" is behind, so their
+    // replies carry earlier timestamps than the posts they follow.
+    let moth = text(vec![], 1000, "moth")?;
+    let beetle = text(vec![moth.hash()?], 500, "beetle")?;
+    let ant = text(vec![moth.hash()?], 1200, "ant")?;
+    let wasp = text(vec![beetle.hash()?, ant.hash()?], 600, "wasp")?;
+    let bee = text(vec![], 700, "bee")?;
+
+    for post in [&wasp, &ant, &bee, &moth, &beetle] {
+        store.insert_post(post).await?;
+    }
+
+    let opts = ChannelOptions::new("entomology", 0, 0, 0);
+    let mut stream = store.get_posts_causal(&opts).await;
+    let mut posts = Vec::new();
+    while let Some(post) = stream.next().await {
+        posts.push(post?);
+    }
+    drop(stream);
+
+    // "bee" is concurrent with all other posts and ordered by timestamp.
+    assert_eq!(texts(&posts), vec!["bee", "moth", "beetle", "ant", "wasp"]);
+
+    // Ordering by timestamp alone places replies before their parents.
+    let mut by_timestamp = posts.clone();
+    by_timestamp.sort_by_key(|post| post.get_timestamp());
+    assert_eq!(
+        texts(&by_timestamp),
+        vec!["beetle", "wasp", "bee", "moth", "ant"]
+    );
+
+    // Sorting is independent of the initial order and removes duplicates.
+    let mut shuffled = by_timestamp.clone();
+    shuffled.reverse();
+    shuffled.push(moth.clone());
+    assert_eq!(texts(&causal_order(shuffled)?), texts(&posts));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn order_memory_store_posts() -> Result<(), Error> {
+    order_posts(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn order_sled_store_posts() -> Result<(), Error> {
+    order_posts(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn order_sqlite_store_posts() -> Result<(), Error> {
+    order_posts(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,77 @@
+//! Test working with a channel through its handle.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of two connected peers and post to a channel on the
+//!    second peer through a handle, ensuring the second peer joins the
+//!    channel first.
+//!
+//! 2) Set the topic of the channel through the handle and ensure it is
+//!    returned by the handle.
+//!
+//! 3) Stream the posts of the channel through a handle on the first peer,
+//!    ensuring the post of the second peer is received.
+//!
+//! 4) Close the handle, ensuring the requests for the channel are cancelled,
+//!    and leave the channel on the second peer.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn channel_handle() -> Result<(), Error> {
+    let network = Network::new(2, Topology::Line);
+    let first = network.peer(0);
+    let mut second = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.len() == 1 }).await);
+
+    let channel = second.channel("entomology");
+    assert_eq!(channel.name(), "entomology");
+    assert!(!channel.is_member().await?);
+    channel.post("moths").await?;
+    assert!(channel.is_member().await?);
+    assert_eq!(
+        channel.members().await,
+        vec![second.get_public_key().await?]
+    );
+    assert_eq!(channel.join().await?, None);
+
+    channel.set_topic("lepidoptera").await?;
+    assert_eq!(channel.topic().await, Some("lepidoptera".to_string()));
+
+    let handle = first.channel("entomology");
+    let post = {
+        let mut posts = handle.posts_live().await?;
+        future::timeout(TIMEOUT, async {
+            while let Some(post) = posts.next().await {
+                let post = post?;
+                if matches!(&post.body, PostBody::Text { .. }) {
+                    return Ok(post);
+                }
+            }
+
+            Err::<_, Error>("stream closed".into())
+        })
+        .await??
+    };
+    assert!(matches!(post.body, PostBody::Text { text, .. } if text == "moths"));
+    assert!(!first.store.get_outbound_requests().await.is_empty());
+
+    handle.close().await?;
+    assert!(first.store.get_outbound_requests().await.is_empty());
+
+    assert!(channel.leave().await?.is_some());
+    assert!(!channel.is_member().await?);
+    assert_eq!(channel.leave().await?, None);
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Test the tracking of channel heads according to the links of posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert a chain of posts, ensuring the latest post is the only head.
+//!
+//! 2) Insert a concurrent post from a second author, creating two heads.
+//!
+//! 3) Insert two posts of a third author out of order.
+//!
+//! 4) Insert a post linking to all heads, merging them into a single head.
+//!
+//! 5) Delete the merging post, ensuring the merged heads are restored.
+
+use std::convert::TryInto;
+
+use cable::{Error, Hash, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+struct Author {
+    public_key: [u8; 32],
+    secret_key: [u8; 64],
+}
+
+impl Author {
+    fn new() -> Result<Self, Error> {
+        let (pk, sk) = sign::gen_keypair();
+
+        Ok(Author {
+            public_key: pk.as_ref().try_into()?,
+            secret_key: sk.as_ref().try_into()?,
+        })
+    }
+
+    fn text(&self, links: Vec<Hash>, timestamp: u64, text: &str) -> Result<Post, Error> {
+        let mut post = Post::text(
+            self.public_key,
+            links,
+            timestamp,
+            "entomology".into(),
+            text.into(),
+        );
+        post.sign(&self.secret_key)?;
+
+        Ok(post)
+    }
+}
+
+// Return the given hashes in sorted order.
+fn sorted(mut hashes: Vec<Hash>) -> Vec<Hash> {
+    hashes.sort();
+    hashes
+}
+
+async fn track_heads<S: Store>(mut store: S) -> Result<(), Error> {
+    let channel = "entomology".to_string();
+    let (alice, bob, carol) = (Author::new()?, Author::new()?, Author::new()?);
+
+    assert_eq!(store.get_latest_hashes(&channel).await, None);
+
+    let a1 = store.insert_post(&alice.text(vec![], 100, "moth")?).await?;
+    let a2 = store
+        .insert_post(&alice.text(vec![a1], 200, "beetle")?)
+        .await?;
+    assert_eq!(store.get_latest_hashes(&channel).await, Some(vec![a2]));
+
+    // Bob has not yet seen Alice's second post. His clock is ahead, so the
+    // post is newer than all others.
+    let b1 = store.insert_post(&bob.text(vec![a1], 900, "wasp")?).await?;
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(sorted(vec![a2, b1]))
+    );
+
+    // Carol's second post arrives before her first, which follows Alice's
+    // second post.
+    let c1_post = carol.text(vec![a2], 300, "ant")?;
+    let c1 = c1_post.hash()?;
+    let c2 = store
+        .insert_post(&carol.text(vec![c1], 400, "bee")?)
+        .await?;
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(sorted(vec![a2, b1, c2]))
+    );
+    store.insert_post(&c1_post).await?;
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(sorted(vec![b1, c2]))
+    );
+
+    // A post linking to all heads merges them.
+    let heads = store.get_latest_hashes(&channel).await.unwrap();
+    let merge = store.insert_post(&alice.text(heads, 1000, "fly")?).await?;
+    assert_eq!(store.get_latest_hashes(&channel).await, Some(vec![merge]));
+
+    store.delete_posts(&[merge]).await;
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(sorted(vec![b1, c2]))
+    );
+
+    // Deleting a post which is not a head leaves the heads unchanged.
+    store.delete_posts(&[c1]).await;
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(sorted(vec![b1, c2]))
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn track_memory_store_heads() -> Result<(), Error> {
+    track_heads(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn track_sled_store_heads() -> Result<(), Error> {
+    track_heads(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn track_sqlite_store_heads() -> Result<(), Error> {
+    track_heads(cable_core::SqliteStore::open_in_memory()?).await
+}
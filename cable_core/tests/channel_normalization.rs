@@ -0,0 +1,55 @@
+//! Test the normalization of channel names given by the local user.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Join a channel using a decomposed form of its name and post to it
+//! using the precomposed form, ensuring both posts are in the same channel.
+//!
+//! 2) Repeat with normalization disabled, ensuring the posts are in
+//! different channels.
+
+use async_std::stream::StreamExt;
+use cable::{validation::ChannelNormalization, ChannelOptions, Error};
+
+use cable_core::{CableManager, ManagerOptions, MemoryStore, Store};
+
+// "café" with a precomposed `é` and with a combining acute accent.
+const COMPOSED: &str = "caf\u{e9}";
+const DECOMPOSED: &str = "cafe\u{301}";
+
+async fn post_count(cable: &CableManager<MemoryStore>, channel: &str) -> usize {
+    cable
+        .store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await
+        .count()
+        .await
+}
+
+#[async_std::test]
+async fn normalize_channel_names() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+    cable.post_join(DECOMPOSED).await?;
+    cable.post_text(COMPOSED, "croissants").await?;
+    cable.post_text(DECOMPOSED, "coffee").await?;
+
+    assert_eq!(post_count(&cable, COMPOSED).await, 2);
+    assert_eq!(post_count(&cable, DECOMPOSED).await, 0);
+    assert_eq!(
+        cable.store.get_channels().await,
+        Some(vec![COMPOSED.to_string()])
+    );
+
+    let options = ManagerOptions {
+        channel_normalization: ChannelNormalization::None,
+        ..ManagerOptions::default()
+    };
+    let mut cable = CableManager::with_options(MemoryStore::default(), options);
+    cable.post_text(COMPOSED, "croissants").await?;
+    cable.post_text(DECOMPOSED, "coffee").await?;
+
+    assert_eq!(post_count(&cable, COMPOSED).await, 1);
+    assert_eq!(post_count(&cable, DECOMPOSED).await, 1);
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+//! Test the channel state indexes of the store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert join, topic and name posts authored by two peers.
+//!
+//! 2) Insert a leave post and a later topic post authored by the second peer.
+//!
+//! 3) Ensure the channel state hashes are the latest post of each kind.
+
+use std::{collections::HashSet, convert::TryInto};
+
+use cable::{Error, Post, UserInfo};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+// Generate a new public-private keypair.
+fn keypair() -> ([u8; 32], [u8; 64]) {
+    let (pk, sk) = sign::gen_keypair();
+    (
+        pk.as_ref().try_into().unwrap(),
+        sk.as_ref().try_into().unwrap(),
+    )
+}
+
+// Sign the given post and insert it into the store, returning the hash.
+async fn insert(store: &mut MemoryStore, mut post: Post, sk: &[u8; 64]) -> Result<[u8; 32], Error> {
+    post.sign(sk)?;
+    store.insert_post(&post).await
+}
+
+#[async_std::test]
+async fn channel_state_hashes_from_indexes() -> Result<(), Error> {
+    let mut store = MemoryStore::default();
+    let channel = "entomology".to_string();
+
+    let (alice_pk, alice_sk) = keypair();
+    let (bob_pk, bob_sk) = keypair();
+
+    let alice_join = Post::join(alice_pk, vec![], 100, channel.clone());
+    let alice_join_hash = insert(&mut store, alice_join, &alice_sk).await?;
+
+    let alice_name = Post::info(alice_pk, vec![], 110, vec![UserInfo::name("alice")?]);
+    let alice_name_hash = insert(&mut store, alice_name, &alice_sk).await?;
+
+    let alice_topic = Post::topic(alice_pk, vec![], 120, channel.clone(), "moths".into());
+    insert(&mut store, alice_topic, &alice_sk).await?;
+
+    let bob_join = Post::join(bob_pk, vec![], 130, channel.clone());
+    insert(&mut store, bob_join, &bob_sk).await?;
+
+    let bob_topic = Post::topic(bob_pk, vec![], 140, channel.clone(), "beetles".into());
+    let bob_topic_hash = insert(&mut store, bob_topic, &bob_sk).await?;
+
+    let bob_leave = Post::leave(bob_pk, vec![], 150, channel.clone());
+    let bob_leave_hash = insert(&mut store, bob_leave, &bob_sk).await?;
+
+    let hashes: HashSet<[u8; 32]> = store
+        .get_channel_state_hashes(&channel)
+        .await
+        .into_iter()
+        .collect();
+
+    let expected: HashSet<[u8; 32]> = [
+        alice_join_hash,
+        alice_name_hash,
+        bob_topic_hash,
+        bob_leave_hash,
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(hashes, expected);
+
+    // An unknown channel has no state.
+    assert!(store
+        .get_channel_state_hashes(&"lepidoptera".to_string())
+        .await
+        .is_empty());
+
+    Ok(())
+}
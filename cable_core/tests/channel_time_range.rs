@@ -17,11 +17,13 @@
 //!
 //! 3) Send a channel time range request for the "myco" channel with a start
 //! time before the three posts were published and an end time of `now()`.
-//! Ensure that all three hashes are returned in the response.
+//! Ensure that all three hashes are returned in the response, followed by an
+//! empty hash response concluding the request.
 //!
 //! 4) Send a channel time range request for the "myco" channel with a start
 //! time before the three posts were published, an end time of `now()` and a
-//! limit of 2. Ensure that only two hashes are returned in the response.
+//! limit of 2. Ensure that only two hashes are returned in the response,
+//! followed by an empty hash response concluding the request.
 //!
 //! 5) Publish a post to the "books" channel.
 //!
@@ -37,7 +39,7 @@ use std::{thread, time::Duration};
 
 use async_std::{
     net::{TcpListener, TcpStream},
-    stream::StreamExt,
+    stream::{Stream, StreamExt},
     task,
 };
 use cable::{
@@ -46,7 +48,8 @@ use cable::{
     ChannelOptions, Error, Message,
 };
 use desert::{FromBytes, ToBytes};
-use futures::{AsyncReadExt, AsyncWriteExt};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeError, DecodeOptions};
 use log::info;
 
 use cable_core::{CableManager, MemoryStore};
@@ -63,6 +66,27 @@ fn init() {
     let _ = env_logger::builder().is_test(false).try_init();
 }
 
+// Read the next message from the given stream of encoded messages.
+async fn next_message(
+    responses: &mut (impl Stream<Item = Result<Vec<u8>, DecodeError>> + Unpin),
+) -> Result<Message, Error> {
+    match responses.next().await {
+        Some(buf) => Ok(Message::from_bytes(&buf?)?.1),
+        None => Err("stream closed".into()),
+    }
+}
+
+// Query whether the given message is an empty hash response, concluding a
+// request.
+fn is_empty_hash_response(msg: &Message) -> bool {
+    matches!(
+        &msg.body,
+        MessageBody::Response {
+            body: ResponseBody::Hash { hashes },
+        } if hashes.is_empty()
+    )
+}
+
 // Get the current system time in milliseconds since the UNIX epoch.
 fn now() -> Result<u64, Error> {
     let time = std::time::SystemTime::now()
@@ -108,6 +132,13 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     let mut stream = TcpStream::connect(addr).await?;
     info!("Connected to TCP server on {}", addr);
 
+    // Decode the messages written to the stream.
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut responses = decode_with_options(stream.clone(), options);
+
     // Create a timestamp for later use.
     let time_before_posts_were_published = now()?;
 
@@ -150,11 +181,9 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     thread::sleep(five_millis);
 
     // Read the response from the stream.
-    let mut res_bytes = [0u8; 1024];
-    let _n = stream.read(&mut res_bytes).await?;
+    let msg = next_message(&mut responses).await?;
 
     // Ensure that a hash response was returned by the listening peer.
-    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
     assert_eq!(msg.message_type(), HASH_RESPONSE);
 
     if let MessageBody::Response { body } = msg.body {
@@ -193,11 +222,9 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     thread::sleep(five_millis);
 
     // Read the response from the stream.
-    let mut res_bytes = [0u8; 1024];
-    let _n = stream.read(&mut res_bytes).await?;
+    let msg = next_message(&mut responses).await?;
 
     // Ensure that a hash response was returned by the listening peer.
-    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
     assert_eq!(msg.message_type(), HASH_RESPONSE);
 
     if let MessageBody::Response { body } = msg.body {
@@ -207,6 +234,10 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
         }
     }
 
+    // Ensure that the request is concluded by an empty hash response.
+    let msg = next_message(&mut responses).await?;
+    assert!(is_empty_hash_response(&msg));
+
     /* THIRD REQUEST */
 
     // Generate a novel request ID.
@@ -232,11 +263,9 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     thread::sleep(five_millis);
 
     // Read the response from the stream.
-    let mut res_bytes = [0u8; 1024];
-    let _n = stream.read(&mut res_bytes).await?;
+    let msg = next_message(&mut responses).await?;
 
     // Ensure that a hash response was returned by the listening peer.
-    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
     assert_eq!(msg.message_type(), HASH_RESPONSE);
 
     if let MessageBody::Response { body } = msg.body {
@@ -246,6 +275,10 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
         }
     }
 
+    // Ensure that the request is concluded by an empty hash response.
+    let msg = next_message(&mut responses).await?;
+    assert!(is_empty_hash_response(&msg));
+
     /* FOURTH REQUEST */
 
     // Publish a post to the "books" channel.
@@ -281,11 +314,9 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     thread::sleep(five_millis);
 
     // Read the response from the stream.
-    let mut res_bytes = [0u8; 1024];
-    let _n = stream.read(&mut res_bytes).await?;
+    let msg = next_message(&mut responses).await?;
 
     // Ensure that a hash response was returned by the listening peer.
-    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
     assert_eq!(msg.message_type(), HASH_RESPONSE);
 
     if let MessageBody::Response { body } = msg.body {
@@ -305,11 +336,9 @@ async fn channel_time_range_request_response() -> Result<(), Error> {
     thread::sleep(five_millis);
 
     // Read the response from the stream.
-    let mut res_bytes = [0u8; 1024];
-    let _n = stream.read(&mut res_bytes).await?;
+    let msg = next_message(&mut responses).await?;
 
     // Ensure that a hash response was returned by the listening peer.
-    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
     assert_eq!(msg.message_type(), HASH_RESPONSE);
 
     if let MessageBody::Response { body } = msg.body {
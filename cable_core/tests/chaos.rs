@@ -0,0 +1,109 @@
+//! Test syncing posts between managers injecting faults.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two managers which delay the handling of received messages and
+//! reorder their writes, publish posts on one and open the channel on the
+//! other, ensuring every post is synced.
+//!
+//! 2) Connect two managers which drop every outgoing message, ensuring no post
+//! is synced.
+
+#![cfg(feature = "chaos")]
+
+use std::time::Duration;
+
+use async_std::{stream::StreamExt, task};
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, ChaosOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Return the text of all posts in the given channel of the given peer.
+async fn texts(peer: &CableManager<MemoryStore>, channel: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut posts = peer
+        .store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+
+    texts
+}
+
+/// Return a network of two managers injecting the given faults.
+fn network(chaos: ChaosOptions) -> Network<MemoryStore> {
+    let managers = (0..2)
+        .map(|peer| {
+            CableManager::builder(MemoryStore::default())
+                .chaos(Some(ChaosOptions {
+                    seed: chaos.seed + peer,
+                    ..chaos.clone()
+                }))
+                .build()
+        })
+        .collect();
+
+    Network::with_managers(managers, Topology::Line)
+}
+
+#[async_std::test]
+async fn sync_with_delays_and_reordering() -> Result<(), Error> {
+    let mut network = network(ChaosOptions {
+        seed: 1,
+        max_handler_delay: Duration::from_millis(20),
+        reorder_rate: 0.5,
+        ..Default::default()
+    });
+
+    let mut first = network.peer(0);
+    for text in ["a luna moth!", "a polyphemus moth!", "an atlas moth!"] {
+        first.post_text("entomology", text).await?;
+    }
+
+    let (second, mut reader) = (network.peer(1), network.peer(1));
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            texts(&second, "entomology").await.len() == 3
+        })
+        .await
+    );
+
+    network.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_every_message() -> Result<(), Error> {
+    let mut network = network(ChaosOptions {
+        seed: 2,
+        drop_rate: 1.0,
+        ..Default::default()
+    });
+
+    let mut first = network.peer(0);
+    first.post_text("entomology", "a luna moth!").await?;
+
+    let (second, mut reader) = (network.peer(1), network.peer(1));
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    task::sleep(Duration::from_millis(500)).await;
+    assert!(texts(&second, "entomology").await.is_empty());
+
+    network.shutdown().await;
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Test skipping received posts timestamped implausibly far in the future.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager with a mock clock which accepts
+//! unsolicited posts.
+//!
+//! 2) Send a post timestamped within the tolerated clock skew and another
+//! timestamped beyond it, in a single response.
+//!
+//! 3) Ensure only the first post is stored.
+
+use std::{convert::TryInto, sync::Arc, time::Duration};
+
+use async_std::task;
+use cable::{constants::NO_CIRCUIT, Error, Message, Post};
+use desert::ToBytes;
+use futures::AsyncWriteExt;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{
+    testing::{duplex, eventually},
+    CableManager, ManagerOptions, MemoryStore, MockClock, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const NOW: u64 = 1_000_000;
+const SKEW: Duration = Duration::from_secs(60);
+
+#[async_std::test]
+async fn skip_future_posts() -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+
+    let options = ManagerOptions {
+        clock: Arc::new(MockClock::new(NOW)),
+        max_clock_skew: Some(SKEW),
+        accept_unsolicited_posts: true,
+        ..Default::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let mut payloads = Vec::new();
+    let mut hashes = Vec::new();
+    for timestamp in [NOW + 30_000, NOW + 3_600_000] {
+        let mut post = Post::text(
+            pk,
+            vec![],
+            timestamp,
+            "entomology".to_string(),
+            "moth".to_string(),
+        );
+        post.sign(&sk)?;
+        hashes.push(post.hash()?);
+        payloads.push(post.to_bytes()?);
+    }
+    let response = Message::post_response(NO_CIRCUIT, [0, 0, 0, 1], payloads);
+    peer.write_all(&response.to_bytes()?).await?;
+
+    // Both posts arrive in a single response, so the future post has been
+    // handled once the other is stored.
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable.store.get_post_payload(&hashes[0]).await.is_some()
+        })
+        .await
+    );
+    assert_eq!(cable.store.get_post_payload(&hashes[1]).await, None);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+//! Test the compaction statistics and manual compaction of each store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert text posts and tombstone one of them without deleting it, as if
+//!    its deletion had been interrupted, then delete the others.
+//!
+//! 2) Ensure the compaction statistics report the pending tombstone and, for
+//!    persistent stores, the disk usage.
+//!
+//! 3) Compact the store and ensure the pending tombstone is purged.
+
+use std::convert::TryInto;
+
+use cable::{Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{CachedStore, CompactionStats, MemoryStore, Store};
+
+// Delete posts from the given store and compact it, returning the
+// compaction statistics from before and after compaction.
+async fn compact<S: Store>(mut store: S) -> Result<(CompactionStats, CompactionStats), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+
+    let mut hashes = Vec::new();
+    for i in 0..100 {
+        let text = format!("{} {}", i, "moth ".repeat(100));
+        let mut post = Post::text(pk, vec![], 100 + i, "entomology".into(), text);
+        post.sign(&sk)?;
+        hashes.push(store.insert_post(&post).await?);
+    }
+
+    store.insert_tombstone(&hashes[0]).await;
+    store.delete_posts(&hashes[2..]).await;
+
+    let before = store.compaction_stats().await?;
+    assert_eq!(before.pending_tombstones, 1);
+    assert_eq!(store.get_pending_tombstones().await, vec![hashes[0]]);
+
+    store.compact().await?;
+
+    let after = store.compaction_stats().await?;
+    assert_eq!(after.pending_tombstones, 0);
+    assert!(store.get_post_payload(&hashes[0]).await.is_none());
+    assert!(store.get_post_payload(&hashes[1]).await.is_some());
+    assert!(store.is_tombstone(&hashes[0]).await);
+
+    Ok((before, after))
+}
+
+#[async_std::test]
+async fn compact_memory_store() -> Result<(), Error> {
+    let (before, _after) = compact(MemoryStore::default()).await?;
+    assert_eq!(before.disk_usage, None);
+
+    compact(CachedStore::new(MemoryStore::default())).await?;
+
+    Ok(())
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn compact_sled_store() -> Result<(), Error> {
+    let (before, _after) = compact(cable_core::SledStore::temporary()?).await?;
+    assert!(before.disk_usage.is_some());
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn compact_sqlite_store() -> Result<(), Error> {
+    let (before, after) = compact(cable_core::SqliteStore::open_in_memory()?).await?;
+    assert!(before.reclaimable_bytes.unwrap() > 0);
+    assert_eq!(after.reclaimable_bytes, Some(0));
+    assert!(after.disk_usage.unwrap() < before.disk_usage.unwrap());
+
+    Ok(())
+}
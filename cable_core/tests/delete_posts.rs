@@ -0,0 +1,57 @@
+//! Test physical deletion of posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert two text posts into a memory store.
+//!
+//! 2) Delete the first post, ensuring its payload is removed and its hash is retained as a tombstone.
+//!
+//! 3) Insert the deleted post again, ensuring it is not stored.
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{ChannelOptions, Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+#[async_std::test]
+async fn delete_posts_and_retain_tombstones() -> Result<(), Error> {
+    let mut store = MemoryStore::default();
+    let channel = "entomology".to_string();
+
+    let (pk, sk) = sign::gen_keypair();
+    let pk: [u8; 32] = pk.as_ref().try_into()?;
+    let sk: [u8; 64] = sk.as_ref().try_into()?;
+
+    let mut deleted_post = Post::text(pk, vec![], 100, channel.clone(), "oops".into());
+    deleted_post.sign(&sk)?;
+    let deleted_hash = store.insert_post(&deleted_post).await?;
+
+    let mut kept_post = Post::text(pk, vec![], 200, channel.clone(), "hello".into());
+    kept_post.sign(&sk)?;
+    let kept_hash = store.insert_post(&kept_post).await?;
+
+    store.delete_posts(&[deleted_hash]).await;
+    store.compact().await?;
+
+    assert!(store.get_post_payload(&deleted_hash).await.is_none());
+    assert!(store.get_post_payload(&kept_hash).await.is_some());
+    assert!(store.is_tombstone(&deleted_hash).await);
+    assert!(!store.is_tombstone(&kept_hash).await);
+
+    // The deleted post is no longer wanted from peers.
+    assert!(store.want(&[deleted_hash, kept_hash]).await.is_empty());
+
+    // Receiving the deleted post again does not restore it.
+    store.insert_post(&deleted_post).await?;
+    assert!(store.get_post_payload(&deleted_hash).await.is_none());
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let hashes: Vec<_> = store.get_post_hashes(&opts).await.collect().await;
+    assert_eq!(hashes.len(), 1);
+    assert_eq!(*hashes[0].as_ref().unwrap(), kept_hash);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+//! Test tracking the delivery of published posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a manager tracking deliveries, subscribe to its events and
+//!    publish a post; ensure the post is sent but not yet delivered.
+//!
+//! 2) Connect a peer and open the channel of the post on the peer.
+//!
+//! 3) Ensure the post is delivered to the peer once the peer requests it,
+//!    and a delivery event is emitted.
+//!
+//! 4) Publish and delete another post, ensuring its delivery is no longer
+//!    tracked.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{ChannelOptions, Error};
+
+use cable_core::{
+    testing::{Network, Topology},
+    CableEvent, CableManager, DeliveryStatus, MemoryStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn track_delivery() -> Result<(), Error> {
+    let mut author = CableManager::builder(MemoryStore::default())
+        .track_deliveries(true)
+        .build();
+    let mut events = author.events().await;
+
+    let moth = author.post_text("entomology", "moth").await?;
+    assert_eq!(
+        author.delivery_status(&moth).await,
+        Some(DeliveryStatus::Sent)
+    );
+
+    let reader = CableManager::new(MemoryStore::default());
+    let network = Network::with_managers(vec![author.clone(), reader], Topology::Line);
+    let mut reader = network.peer(1);
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let peer_id = future::timeout(TIMEOUT, author.delivered(&moth))
+        .await?
+        .expect("post is tracked");
+    assert_eq!(
+        author.delivery_status(&moth).await,
+        Some(DeliveryStatus::Delivered { peer_id })
+    );
+    assert_eq!(author.delivered(&moth).await, Some(peer_id));
+
+    let event = future::timeout(TIMEOUT, async {
+        while let Some(event) = events.next().await {
+            if let CableEvent::PostDelivered { hash, peer_id } = event {
+                return Some((hash, peer_id));
+            }
+        }
+
+        None
+    })
+    .await?;
+    assert_eq!(event, Some((moth, peer_id)));
+
+    let wasp = author.post_text("apiology", "wasp").await?;
+    author.post_delete(vec![wasp]).await?;
+    assert_eq!(author.delivery_status(&wasp).await, None);
+    assert_eq!(author.delivered(&wasp).await, None);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn untracked_deliveries() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    let moth = cable.post_text("entomology", "moth").await?;
+    assert_eq!(cable.delivery_status(&moth).await, None);
+    assert_eq!(cable.delivered(&moth).await, None);
+
+    Ok(())
+}
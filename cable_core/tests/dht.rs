@@ -0,0 +1,118 @@
+//! Test peer discovery on a local DHT.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Bind four DHT nodes on the loopback interface, each bootstrapping from
+//!    the first.
+//!
+//! 2) Announce a port under a discovery key on one node and look it up on
+//!    another, ensuring the port is found at the address of the announcing
+//!    node.
+//!
+//! 3) Look up another discovery key, ensuring nothing is found.
+//!
+//! 4) Deploy a TCP listener announced by a dialer on one node and run a
+//!    dialer on another, ensuring both peers connect.
+#![cfg(feature = "dht")]
+
+use std::{net::SocketAddr, time::Duration};
+
+use async_std::{net::TcpListener, stream::StreamExt, task};
+use cable::Error;
+
+use cable_core::{
+    discovery_key, testing::eventually, CableManager, DhtDiscovery, DhtOptions, Dialer,
+    DialerOptions, Discovery, MemoryStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bind the given number of nodes, each bootstrapping from the first.
+async fn bind_nodes(count: usize) -> Result<Vec<DhtDiscovery>, Error> {
+    let options = DhtOptions {
+        bind_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+        query_timeout: Duration::from_millis(500),
+        ..DhtOptions::default()
+    };
+    let first = DhtDiscovery::bind(options.clone()).await?;
+    let mut nodes = vec![first.clone()];
+    for _ in 1..count {
+        let options = DhtOptions {
+            bootstrap: vec![first.local_addr()?],
+            ..options.clone()
+        };
+        nodes.push(DhtDiscovery::bind(options).await?);
+    }
+
+    Ok(nodes)
+}
+
+#[async_std::test]
+async fn announce_and_look_up_peers() -> Result<(), Error> {
+    let nodes = bind_nodes(4).await?;
+    for node in &nodes[1..] {
+        assert!(node.node_count() > 0);
+    }
+
+    let key = discovery_key(b"cabal key")?;
+    let addr = SocketAddr::from(([127, 0, 0, 1], 8007));
+    nodes[3].announce(&key, addr).await?;
+
+    assert_eq!(nodes[1].lookup(&key).await?, vec![addr]);
+    assert_eq!(nodes[0].lookup(&key).await?, vec![addr]);
+
+    let other_key = discovery_key(b"other cabal key")?;
+    assert!(nodes[2].lookup(&other_key).await?.is_empty());
+
+    // Only IPv4 addresses may be announced.
+    assert!(nodes[3]
+        .announce(&key, "[::1]:8007".parse()?)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn discover_and_dial_peers() -> Result<(), Error> {
+    let nodes = bind_nodes(3).await?;
+    let key = discovery_key(b"cabal key")?;
+
+    // Create the first cable manager and deploy a TCP listener.
+    let listener_cable = CableManager::new(MemoryStore::default());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let cable = listener_cable.clone();
+    task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(Ok(stream)) = incoming.next().await {
+            let cable = cable.clone();
+            task::spawn(async move {
+                let _ = cable.listen(stream).await;
+            });
+        }
+    });
+
+    let options = DialerOptions {
+        lookup_interval: Duration::from_millis(100),
+        ..DialerOptions::default()
+    };
+
+    // Announce the listener, without dialing anyone.
+    let announcer = Dialer::new(listener_cable.clone(), options.clone());
+    let node = nodes[1].clone();
+    task::spawn(async move { announcer.run(&node, &key, Some(addr)).await });
+
+    // Look up and dial the listener from the second cable manager.
+    let dialer_cable = CableManager::new(MemoryStore::default());
+    let dialer = Dialer::new(dialer_cable.clone(), options);
+    let node = nodes[2].clone();
+    task::spawn(async move { dialer.run(&node, &key, None).await });
+
+    for cable in [&listener_cable, &dialer_cable] {
+        assert!(eventually(TIMEOUT, || async { cable.get_peer_ids().await.len() == 1 }).await);
+    }
+
+    Ok(())
+}
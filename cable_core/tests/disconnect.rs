@@ -0,0 +1,82 @@
+//! Test disconnecting a peer explicitly.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a manager and ensure the connection is listed.
+//!
+//! 2) Open a channel, queueing its requests for the raw peer, and disconnect
+//!    the raw peer immediately.
+//!
+//! 3) Ensure the manager stops listening to the raw peer without error and
+//!    no longer lists the connection.
+//!
+//! 4) Ensure the raw peer receives the queued requests before its stream is
+//!    closed.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    message::{MessageBody, RequestBody},
+    ChannelOptions, Error, Message,
+};
+use desert::FromBytes;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually},
+    CableManager, MemoryStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn disconnect_peer() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    let (stream, peer) = duplex();
+    let listener = cable.clone();
+    let listening = task::spawn(async move { listener.listen(stream).await });
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable.list_connections().await.len() == 1
+        })
+        .await
+    );
+    let peer_id = cable.list_connections().await[0].peer_id;
+
+    let opts = ChannelOptions::new("entomology", 0, 0, 0);
+    drop(cable.open_channel(&opts).await?);
+    assert!(future::timeout(TIMEOUT, cable.disconnect(peer_id)).await?);
+
+    future::timeout(TIMEOUT, listening).await??;
+    assert!(cable.peer_info(peer_id).await.is_none());
+    assert!(cable.list_connections().await.is_empty());
+    assert!(!cable.disconnect(peer_id).await);
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer, options);
+    let requests = future::timeout(TIMEOUT, async {
+        let mut requests = Vec::new();
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if let MessageBody::Request { body, .. } = msg.body {
+                requests.push(body);
+            }
+        }
+
+        Ok::<_, Error>(requests)
+    })
+    .await??;
+    assert!(requests
+        .iter()
+        .any(|body| matches!(body, RequestBody::ChannelTimeRange { .. })));
+    assert!(requests
+        .iter()
+        .any(|body| matches!(body, RequestBody::ChannelState { .. })));
+
+    Ok(())
+}
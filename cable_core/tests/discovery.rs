@@ -0,0 +1,79 @@
+//! Test peer discovery and automatic dialing.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Deploy a TCP listener and announce its address via in-memory discovery.
+//!
+//! 2) Look up and dial the announced address, ensuring both peers connect.
+//!
+//! 3) Dial the same address again, ensuring no duplicate connection is made.
+//!
+//! 4) Dial an address with no listener, ensuring it is not retried at once.
+
+use std::{thread, time::Duration};
+
+use async_std::{net::TcpListener, stream::StreamExt, task};
+use cable::Error;
+
+use cable_core::{
+    discovery_key, CableManager, Dialer, DialerOptions, Discovery, MemoryDiscovery, MemoryStore,
+};
+
+#[async_std::test]
+async fn discover_and_dial_peers() -> Result<(), Error> {
+    let discovery = MemoryDiscovery::default();
+    let key = discovery_key(b"cabal key")?;
+
+    // Create the first cable manager and deploy a TCP listener.
+    let listener_cable = CableManager::new(MemoryStore::default());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let cable = listener_cable.clone();
+    task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(Ok(stream)) = incoming.next().await {
+            let cable = cable.clone();
+            task::spawn(async move {
+                cable.listen(stream).await.unwrap();
+            });
+        }
+    });
+
+    // Announce the address of the first peer.
+    discovery.announce(&key, addr).await?;
+
+    // Create the second cable manager and a dialer.
+    let dialer_cable = CableManager::new(MemoryStore::default());
+    let dialer = Dialer::new(dialer_cable.clone(), DialerOptions::default());
+
+    // Look up and dial all announced peers.
+    let addrs = discovery.lookup(&key).await?;
+    assert_eq!(addrs, vec![addr]);
+    assert!(dialer.dial(addrs[0]).await);
+
+    // Sleep briefly to allow time for both listeners to register the peer.
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(dialer_cable.get_peer_ids().await.len(), 1);
+    assert_eq!(listener_cable.get_peer_ids().await.len(), 1);
+    assert!(dialer.is_active(&addr).await);
+
+    // Dialing the same address again must not create a second connection.
+    assert!(!dialer.dial(addr).await);
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(dialer_cable.get_peer_ids().await.len(), 1);
+
+    // Dial an address without a listener.
+    let unused_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let unused_addr = unused_listener.local_addr()?;
+    drop(unused_listener);
+
+    assert!(!dialer.dial(unused_addr).await);
+    assert!(!dialer.is_active(&unused_addr).await);
+
+    // The failed address is backing off and is not retried immediately.
+    assert!(!dialer.dial(unused_addr).await);
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Test dropping a channel along with all of its posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts to two channels, set a username and open one of the
+//!    channels.
+//!
+//! 2) Drop the opened channel and ensure its posts are deleted, its live
+//!    requests are closed and it is no longer listed.
+//!
+//! 3) Ensure the posts of the other channel and the info post are retained.
+//!
+//! 4) Publish to the dropped channel again, ensuring no tombstones were
+//!    recorded.
+
+use cable::{ChannelOptions, Error};
+
+use cable_core::{CableManager, MemoryStore, Store};
+
+// Drop a channel of a manager over the given store.
+async fn drop_channel<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let mut cable = CableManager::new(store);
+    let moth = cable.post_text("entomology", "moth").await?;
+    let beetle = cable.post_text("entomology", "beetle").await?;
+    let fern = cable.post_text("botany", "fern").await?;
+    let name = cable.post_info_name("mariana").await?;
+    let _ = cable
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    assert!(!cable.store.get_outbound_requests().await.is_empty());
+
+    // Both text posts of the channel are deleted.
+    assert_eq!(cable.drop_channel("entomology").await?, 2);
+    assert!(cable.store.get_outbound_requests().await.is_empty());
+    assert!(!cable.has_post(&moth).await);
+    assert!(!cable.has_post(&beetle).await);
+    assert_eq!(cable.list_channels().await, vec!["botany".to_string()]);
+    assert_eq!(
+        cable
+            .store
+            .get_latest_hashes(&"entomology".to_string())
+            .await,
+        None
+    );
+
+    assert!(cable.has_post(&fern).await);
+    assert!(cable.has_post(&name).await);
+
+    assert!(!cable.store.is_tombstone(&moth).await);
+    let wasp = cable.post_text("entomology", "wasp").await?;
+    assert!(cable.has_post(&wasp).await);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn drop_memory_store_channel() -> Result<(), Error> {
+    drop_channel(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn drop_sled_store_channel() -> Result<(), Error> {
+    drop_channel(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn drop_sqlite_store_channel() -> Result<(), Error> {
+    drop_channel(cable_core::SqliteStore::open_in_memory()?).await
+}
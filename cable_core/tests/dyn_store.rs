@@ -0,0 +1,69 @@
+//! Test choosing the store backend at runtime.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create two managers over boxed stores of different backends, as chosen
+//!    at runtime, and connect them.
+//!
+//! 2) Publish a post on the first manager and open its channel on the
+//!    second, ensuring the post is replicated to the boxed store of the
+//!    second manager.
+//!
+//! 3) Ensure clones of a boxed store share the data of the store.
+
+use std::time::Duration;
+
+use cable::{ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Open a store of the backend with the given name.
+fn open_store(backend: &str) -> Result<Box<dyn Store>, Error> {
+    let store: Box<dyn Store> = match backend {
+        #[cfg(feature = "sled")]
+        "sled" => Box::new(cable_core::SledStore::temporary()?),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Box::new(cable_core::SqliteStore::open_in_memory()?),
+        _ => Box::new(MemoryStore::default()),
+    };
+
+    Ok(store)
+}
+
+#[async_std::test]
+async fn replicate_between_boxed_stores() -> Result<(), Error> {
+    let mut author = CableManager::new(open_store("sled")?);
+    let moth = author.post_text("entomology", "moth").await?;
+
+    let reader = CableManager::new(open_store("sqlite")?);
+    let network = Network::with_managers(vec![author, reader], Topology::Line);
+    let mut reader_peer = network.peer(1);
+    let _live = reader_peer
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let reader = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { reader.has_post(&moth).await }).await);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn clone_boxed_store() -> Result<(), Error> {
+    let store = open_store("memory")?;
+    let mut clone = store.clone();
+
+    clone.insert_channel(&"entomology".to_string()).await;
+    assert_eq!(
+        store.get_channels().await,
+        Some(vec!["entomology".to_string()])
+    );
+    assert!(!store.is_read_only());
+
+    Ok(())
+}
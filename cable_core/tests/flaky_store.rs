@@ -0,0 +1,157 @@
+//! Test syncing posts to a store which fails or delays calls.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two managers, the second over a store failing every insert of
+//! the posts of a response, publish a post on the first and open the channel
+//! on the second.
+//!
+//! 2) Ensure the failure is reported as a `CableEvent::HandlerFailed` event,
+//! the post is not stored and the peers remain connected.
+//!
+//! 3) Connect two managers over stores delaying half of their calls and
+//! ensure posts are synced regardless.
+//!
+//! 4) Ensure reads returning a `Result` fail on a store failing every call,
+//! while other reads are served.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{error::CableError, post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableEvent, CableManager, FlakyLayer, FlakyStore, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Return the text of all posts in the given channel of the given store.
+async fn texts<S: Store + Clone>(store: &S, channel: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut posts = store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+
+    texts
+}
+
+async fn fail_inserts<S: Store + Clone>(a: S, b: S) -> Result<(), Error> {
+    let layer = FlakyLayer::new(1).fail_rate(1.0).methods(&["insert_posts"]);
+    let flaky = FlakyStore::with_layer(b, layer);
+    let mut first = CableManager::new(FlakyStore::with_layer(a, FlakyLayer::new(0)));
+    let second = CableManager::new(flaky.clone());
+    let mut events = second.events().await;
+
+    let mut network = Network::with_managers(vec![first.clone(), second.clone()], Topology::Line);
+
+    first.post_text("entomology", "a luna moth!").await?;
+    let mut reader = second.clone();
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let error = future::timeout(TIMEOUT, async {
+        while let Some(event) = events.next().await {
+            if let CableEvent::HandlerFailed { error, .. } = event {
+                return Some(error);
+            }
+        }
+        None
+    })
+    .await?
+    .unwrap();
+    assert_eq!(
+        error.downcast_ref::<CableError>().map(CableError::code),
+        Some(300)
+    );
+    assert!(flaky.layer().failures() > 0);
+    assert!(texts(&flaky, "entomology").await.is_empty());
+    assert_eq!(second.get_peer_ids().await.len(), 1);
+
+    network.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn fail_inserts_memory_store() -> Result<(), Error> {
+    fail_inserts(MemoryStore::default(), MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn fail_inserts_sled_store() -> Result<(), Error> {
+    fail_inserts(
+        cable_core::SledStore::temporary()?,
+        cable_core::SledStore::temporary()?,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn fail_inserts_sqlite_store() -> Result<(), Error> {
+    fail_inserts(
+        cable_core::SqliteStore::open_in_memory()?,
+        cable_core::SqliteStore::open_in_memory()?,
+    )
+    .await
+}
+
+#[async_std::test]
+async fn sync_with_delays() -> Result<(), Error> {
+    let stores: Vec<FlakyStore<MemoryStore>> = (0..2)
+        .map(|seed| {
+            let layer = FlakyLayer::new(seed).delay_rate(0.5, Duration::from_millis(5));
+            FlakyStore::with_layer(MemoryStore::default(), layer)
+        })
+        .collect();
+    let managers = stores.iter().cloned().map(CableManager::new).collect();
+    let mut network = Network::with_managers(managers, Topology::Line);
+
+    let mut first = network.peer(0);
+    for text in ["a luna moth!", "a polyphemus moth!"] {
+        first.post_text("entomology", text).await?;
+    }
+    let mut reader = network.peer(1);
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    assert!(
+        eventually(TIMEOUT, || async {
+            texts(&stores[1], "entomology").await.len() == 2
+        })
+        .await
+    );
+    assert!(stores[1].layer().delays() > 0);
+
+    network.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn fail_reads() -> Result<(), Error> {
+    let store = FlakyStore::new(MemoryStore::default(), 1.0, 3);
+
+    let err = store
+        .get_posts_page(&"entomology".to_string(), None, 10)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<CableError>().map(CableError::code),
+        Some(300)
+    );
+    assert!(store.get_post_payload(&[0; 32]).await.is_none());
+    assert_eq!(store.layer().failures(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! Test the forwarding of requests between connected peers.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of four peers connected in a line and wait until all
+//! connections are established.
+//!
+//! 2) Join a channel on the last peer.
+//!
+//! 3) Broadcast a channel list request with a TTL of 2 from the first peer.
+//!
+//! 4) Ensure the request is forwarded to the last peer, whose response adds
+//! the channel to the store of the third peer.
+//!
+//! 5) Forward a channel time range request and a post request from a raw
+//! peer through a manager to another, ensuring both are forgotten once the
+//! other manager concludes them and are not sent to a peer which connects
+//! afterwards.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    ChannelOptions, Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, Network, Topology},
+    Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Return the options for decoding the messages written to a raw peer.
+fn decode_options() -> DecodeOptions {
+    DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn forward_requests_to_connected_peers() -> Result<(), Error> {
+    let network = Network::new(4, Topology::Line);
+    let first = network.peer(0);
+    let mut last = network.peer(3);
+
+    for (peer, connections) in [(0, 1), (1, 2), (2, 2), (3, 1)] {
+        let peer = network.peer(peer);
+        assert!(
+            eventually(TIMEOUT, || async {
+                peer.get_peer_ids().await.len() == connections
+            })
+            .await
+        );
+    }
+
+    last.post_join("entomology").await?;
+
+    let (_, req_id) = first.new_req_id().await?;
+    let request = Message::channel_list_request(NO_CIRCUIT, req_id, 2, 0, 0);
+    first.broadcast(&request).await?;
+
+    // The third peer forwards the request with a TTL of 0 to the last peer,
+    // which responds to the third peer.
+    let third = network.peer(2);
+    assert!(
+        eventually(TIMEOUT, || async {
+            third.store.get_channels().await == Some(vec!["entomology".to_string()])
+        })
+        .await
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn forget_concluded_forwarded_requests() -> Result<(), Error> {
+    let network = Network::new(2, Topology::Line);
+    let (middle, mut last) = (network.peer(0), network.peer(1));
+    let hash = last.post_text("entomology", "a luna moth!").await?;
+
+    let (stream, mut first) = duplex();
+    let listener = middle.clone();
+    task::spawn(async move { listener.listen(stream).await });
+    let mut responses = decode_with_options(first.clone(), decode_options());
+    assert!(eventually(TIMEOUT, || async { middle.get_peer_ids().await.len() == 2 }).await);
+
+    // Neither request is kept alive: the time range ends in the future.
+    let time_end = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64 + 60_000;
+    let time_range_req_id = [0, 0, 0, 1];
+    let time_range_request = Message::channel_time_range_request(
+        NO_CIRCUIT,
+        time_range_req_id,
+        1,
+        ChannelOptions::new("entomology", 0, time_end, 0),
+    );
+    let post_req_id = [0, 0, 0, 2];
+    let post_request = Message::post_request(NO_CIRCUIT, post_req_id, 1, vec![hash]);
+    first.write_all(&time_range_request.to_bytes()?).await?;
+    first.write_all(&post_request.to_bytes()?).await?;
+
+    // The last peer concludes both requests, and the responses concluding
+    // them are relayed by the middle peer.
+    future::timeout(TIMEOUT, async {
+        let (mut time_range_concluded, mut post_relayed) = (false, false);
+        while let Some(buf) = responses.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            match msg.body {
+                MessageBody::Response {
+                    body: ResponseBody::Hash { hashes },
+                } if msg.header.req_id == time_range_req_id => {
+                    time_range_concluded |= hashes.is_empty();
+                }
+                MessageBody::Response {
+                    body: ResponseBody::Post { posts },
+                } if msg.header.req_id == post_req_id => {
+                    post_relayed |= !posts.is_empty();
+                }
+                _ => {}
+            }
+            if time_range_concluded && post_relayed {
+                return Ok(());
+            }
+        }
+        Err::<(), Error>("stream closed".into())
+    })
+    .await??;
+
+    // The middle peer forgets the concluded requests, so that a peer
+    // connecting afterwards is not sent them.
+    assert!(
+        eventually(TIMEOUT, || async {
+            middle
+                .list_connections()
+                .await
+                .iter()
+                .all(|info| info.outstanding_requests.is_empty())
+        })
+        .await
+    );
+
+    let (stream, peer) = duplex();
+    let listener = middle.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let mut messages = decode_with_options(peer, decode_options());
+    let _ = future::timeout(Duration::from_millis(500), async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            assert_ne!(msg.header.req_id, time_range_req_id);
+            assert_ne!(msg.header.req_id, post_req_id);
+        }
+        Ok::<_, Error>(())
+    })
+    .await;
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Test looking up posts by hash.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a text post and ensure it is retrieved by its hash.
+//!
+//! 2) Ensure an unknown hash is not found.
+//!
+//! 3) Delete the post and ensure it is no longer found.
+//!
+//! 4) Publish a constructed post and ensure the signed post is returned
+//!    alongside its hash.
+
+use cable::{
+    post::{Post, PostBody},
+    Error,
+};
+use desert::ToBytes;
+
+use cable_core::{CableManager, MemoryStore};
+
+#[async_std::test]
+async fn get_post_by_hash() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    let hash = cable.post_text("entomology", "moth").await?;
+    assert!(cable.has_post(&hash).await);
+    let post = cable.get_post(&hash).await?.expect("post is stored");
+    assert_eq!(post.hash()?, hash);
+    assert!(matches!(post.body, PostBody::Text { text, .. } if text == "moth"));
+
+    assert!(!cable.has_post(&[0; 32]).await);
+    assert!(cable.get_post(&[0; 32]).await?.is_none());
+
+    cable.post_delete(vec![hash]).await?;
+    assert!(!cable.has_post(&hash).await);
+    assert!(cable.get_post(&hash).await?.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn publish_signed_post() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    let public_key = cable.get_public_key().await?;
+    let post = Post::text(public_key, vec![], 100, "entomology".into(), "moth".into());
+    assert!(!post.is_signed());
+
+    let (hash, post) = cable.publish(post).await?;
+    assert!(post.is_signed());
+    assert!(Post::verify(&post.to_bytes()?));
+    assert_eq!(post.hash()?, hash);
+    let stored = cable.get_post(&hash).await?.expect("post is stored");
+    assert_eq!(stored.to_bytes()?, post.to_bytes()?);
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! Test forgetting handled request IDs once their time to live has elapsed.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager with a mock clock and send a post
+//! request, waiting for the response.
+//!
+//! 2) Send the same request again, followed by a request with another ID,
+//! ensuring only the second is answered.
+//!
+//! 3) Advance the clock past the time to live of handled request IDs and
+//! send the first request again, ensuring it is answered.
+
+use std::{sync::Arc, time::Duration};
+
+use async_std::{
+    future,
+    stream::{Stream, StreamExt},
+    task,
+};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    Error, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeError, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, ManagerOptions, MemoryStore, MockClock};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const TTL: Duration = Duration::from_secs(60);
+
+/// Return the request ID of the next post response read from the given
+/// stream of messages.
+async fn next_response(
+    messages: &mut (impl Stream<Item = Result<Vec<u8>, DecodeError>> + Unpin),
+) -> Result<ReqId, Error> {
+    future::timeout(TIMEOUT, async {
+        loop {
+            let buf = match messages.next().await {
+                Some(buf) => buf?,
+                None => return Err("stream closed".into()),
+            };
+            let (_, msg) = Message::from_bytes(&buf)?;
+            if let MessageBody::Response {
+                body: ResponseBody::Post { .. },
+            } = msg.body
+            {
+                return Ok(msg.header.req_id);
+            }
+        }
+    })
+    .await?
+}
+
+#[async_std::test]
+async fn reuse_request_id_after_ttl() -> Result<(), Error> {
+    let clock = MockClock::new(1_000);
+    let options = ManagerOptions {
+        clock: Arc::new(clock.clone()),
+        handled_request_ttl: TTL,
+        // Handle the requests in the order in which they are sent.
+        handler_concurrency: 1,
+        ..Default::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    task::spawn(async move { cable.listen(stream).await });
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer.clone(), options);
+
+    let first = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+    let second = Message::post_request(NO_CIRCUIT, [0, 0, 0, 2], 0, Vec::new());
+
+    peer.write_all(&first.to_bytes()?).await?;
+    assert_eq!(next_response(&mut messages).await?, [0, 0, 0, 1]);
+
+    // The repeated request is ignored.
+    peer.write_all(&first.to_bytes()?).await?;
+    peer.write_all(&second.to_bytes()?).await?;
+    assert_eq!(next_response(&mut messages).await?, [0, 0, 0, 2]);
+
+    clock.advance(TTL);
+    peer.write_all(&first.to_bytes()?).await?;
+    assert_eq!(next_response(&mut messages).await?, [0, 0, 0, 1]);
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Test reporting the failures of message handlers as manager events.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Subscribe to the events of a cable manager whose clock always fails.
+//!
+//! 2) Connect a raw peer and send a post request, ensuring a
+//! `CableEvent::HandlerFailed` event is emitted with the peer ID and the
+//! error of the clock.
+
+use std::{sync::Arc, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{constants::NO_CIRCUIT, Error, Message, Timestamp};
+use desert::ToBytes;
+use futures::AsyncWriteExt;
+
+use cable_core::{testing::duplex, CableEvent, CableManager, Clock, ManagerOptions, MemoryStore};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A clock which never returns the time.
+#[derive(Debug)]
+struct BrokenClock;
+
+impl Clock for BrokenClock {
+    fn now(&self) -> Result<Timestamp, Error> {
+        Err("clock is broken".into())
+    }
+}
+
+#[async_std::test]
+async fn emit_handler_failure() -> Result<(), Error> {
+    let options = ManagerOptions {
+        clock: Arc::new(BrokenClock),
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+    let mut events = cable.events().await;
+
+    let (stream, mut peer) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await });
+    }
+
+    let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+    peer.write_all(&request.to_bytes()?).await?;
+
+    let event = future::timeout(TIMEOUT, events.next()).await?.unwrap();
+    let CableEvent::HandlerFailed { peer_id, error } = event else {
+        panic!("expected a handler failure event");
+    };
+    assert_eq!(cable.get_peer_ids().await, vec![peer_id]);
+    assert_eq!(error.to_string(), "clock is broken");
+
+    Ok(())
+}
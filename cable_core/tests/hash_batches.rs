@@ -0,0 +1,98 @@
+//! Test answering a channel time range request matching a large number of
+//! posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert 2500 posts into the store of a cable manager and connect a raw
+//! peer.
+//!
+//! 2) Request the complete channel history, ensuring the hashes are returned
+//! in batches of at most 1024, followed by an empty hash response.
+//!
+//! 3) Request the history with a limit of 1500, ensuring exactly 1500 hashes
+//! are returned before the empty hash response.
+
+use std::{convert::TryInto, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    ChannelOptions, Error, Message, Post,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{testing::duplex, CableManager, MemoryStore, Store};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const POSTS: u64 = 2500;
+
+#[async_std::test]
+async fn answer_in_batches() -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+
+    let mut store = MemoryStore::default();
+    for timestamp in 1..=POSTS {
+        let mut post = Post::text(
+            pk,
+            vec![],
+            timestamp,
+            "entomology".to_string(),
+            format!("moth {}", timestamp),
+        );
+        post.sign(&sk)?;
+        store.insert_post(&post).await?;
+    }
+
+    let cable = CableManager::new(store);
+    let (stream, mut peer) = duplex();
+    task::spawn(async move { cable.listen(stream).await });
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer.clone(), options);
+
+    for (req_id, limit, expected) in [
+        ([0, 0, 0, 1], 0, vec![1024, 1024, 452]),
+        ([0, 0, 0, 2], 1500, vec![1024, 476]),
+    ] {
+        let opts = ChannelOptions::new("entomology", 0, POSTS + 1, limit);
+        let request = Message::channel_time_range_request(NO_CIRCUIT, req_id, 0, opts);
+        peer.write_all(&request.to_bytes()?).await?;
+
+        // Collect the number of hashes of each response until the request is
+        // concluded.
+        let batches = future::timeout(TIMEOUT, async {
+            let mut batches = Vec::new();
+            loop {
+                let buf = match messages.next().await {
+                    Some(buf) => buf?,
+                    None => return Err::<_, Error>("stream closed".into()),
+                };
+                let (_, msg) = Message::from_bytes(&buf)?;
+                assert_eq!(msg.header.req_id, req_id);
+                if let MessageBody::Response {
+                    body: ResponseBody::Hash { hashes },
+                } = msg.body
+                {
+                    if hashes.is_empty() {
+                        return Ok(batches);
+                    }
+                    batches.push(hashes.len());
+                }
+            }
+        })
+        .await??;
+
+        assert_eq!(batches, expected);
+    }
+
+    Ok(())
+}
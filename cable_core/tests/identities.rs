@@ -0,0 +1,73 @@
+//! Test storing and selecting among multiple identities of a store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Ensure a new store holds a single identity, which is active.
+//!
+//! 2) Create a second identity and ensure the active identity is unchanged.
+//!
+//! 3) Select the second identity and ensure its keypair is active.
+//!
+//! 4) Ensure selecting an unknown identity fails and leaves the store as is.
+//!
+//! 5) Set a keypair and ensure it is added to the identities and activated.
+
+use std::convert::TryInto;
+
+use cable::Error;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+async fn switch_identities<S: Store>(mut store: S) -> Result<(), Error> {
+    let (first, _) = store.get_keypair().await.expect("store has a keypair");
+    assert_eq!(store.list_identities().await, vec![first]);
+
+    let second = store.create_identity().await;
+    let mut expected = vec![first, second];
+    expected.sort();
+    assert_eq!(store.list_identities().await, expected);
+    assert_eq!(store.get_keypair().await.map(|(pk, _)| pk), Some(first));
+
+    store.set_active_identity(&second).await?;
+    assert_eq!(store.get_keypair().await.map(|(pk, _)| pk), Some(second));
+
+    assert!(store.set_active_identity(&[0; 32]).await.is_err());
+    assert_eq!(store.get_keypair().await.map(|(pk, _)| pk), Some(second));
+    assert_eq!(store.list_identities().await, expected);
+
+    let (pk, sk) = sign::gen_keypair();
+    let keypair = (pk.as_ref().try_into()?, sk.as_ref().try_into()?);
+    store.set_keypair(keypair).await;
+    assert_eq!(store.get_keypair().await, Some(keypair));
+    assert_eq!(store.list_identities().await.len(), 3);
+
+    store.set_active_identity(&first).await?;
+    assert_eq!(store.get_keypair().await.map(|(pk, _)| pk), Some(first));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn switch_memory_store_identities() -> Result<(), Error> {
+    switch_identities(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn switch_sled_store_identities() -> Result<(), Error> {
+    switch_identities(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn switch_encrypted_sled_store_identities() -> Result<(), Error> {
+    let db = sled::Config::new().temporary(true).open()?;
+    switch_identities(cable_core::SledStore::from_db_encrypted(db, "moth")?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn switch_sqlite_store_identities() -> Result<(), Error> {
+    switch_identities(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,53 @@
+//! Test moving an identity between stores.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Export the identity of the first store with a passphrase.
+//!
+//! 2) Ensure the identity cannot be imported with an incorrect passphrase.
+//!
+//! 3) Import the identity into the second store with the passphrase.
+//!
+//! 4) Ensure both stores share a keypair and sign posts with the same key.
+
+use cable::{Error, Post};
+use desert::FromBytes;
+
+use cable_core::{CableManager, MemoryStore, Store};
+
+#[async_std::test]
+async fn export_and_import_identity() -> Result<(), Error> {
+    let mut laptop = CableManager::new(MemoryStore::default());
+    let mut phone = CableManager::new(MemoryStore::default());
+
+    let laptop_pk = laptop.get_public_key().await?;
+    assert_ne!(phone.get_public_key().await?, laptop_pk);
+
+    let identity = laptop.store.export_identity("correct horse").await?;
+
+    assert!(phone
+        .store
+        .import_identity(&identity, "battery staple")
+        .await
+        .is_err());
+    assert_ne!(phone.get_public_key().await?, laptop_pk);
+
+    let imported_pk = phone
+        .store
+        .import_identity(&identity, "correct horse")
+        .await?;
+    assert_eq!(imported_pk, laptop_pk);
+    assert_eq!(
+        phone.store.get_keypair().await,
+        laptop.store.get_keypair().await
+    );
+
+    let channel = "entomology".to_string();
+    let hash = phone.post_text(&channel, "hello from the phone").await?;
+    let payload = phone.store.get_post_payload(&hash).await.unwrap();
+    let (_, post) = Post::from_bytes(&payload)?;
+    assert_eq!(post.header.public_key, laptop_pk);
+    assert!(Post::verify(&payload));
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+//! Test rejecting messages which make implausible claims.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager tolerating a single protocol
+//! violation and send a hash response which repeats a hash, ensuring its
+//! rejection is reported as a specification violation.
+//!
+//! 2) Send a second such response, ensuring the peer is disconnected.
+//!
+//! 3) Request channel lists skipping past the limit and past the known
+//! channels, ensuring empty lists are returned and the peer stays connected.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    error::{CableError, ErrorCategory},
+    message::{MessageBody, ResponseBody},
+    Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableEvent, CableManager, ManagerOptions, MemoryStore};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn disconnect_after_violations() -> Result<(), Error> {
+    let options = ManagerOptions {
+        violation_limit: 1,
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+    let mut events = cable.events().await;
+
+    let (stream, mut peer) = duplex();
+    let listener = {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await })
+    };
+
+    let response = Message::hash_response(NO_CIRCUIT, [0, 0, 0, 1], vec![[1; 32], [1; 32]]);
+    peer.write_all(&response.to_bytes()?).await?;
+
+    let event = future::timeout(TIMEOUT, events.next()).await?.unwrap();
+    let CableEvent::HandlerFailed { error, .. } = event else {
+        panic!("expected a handler failure event");
+    };
+    let err = error.downcast_ref::<CableError>().unwrap();
+    assert_eq!(err.category(), ErrorCategory::Protocol);
+    assert_eq!(cable.get_peer_ids().await.len(), 1);
+
+    let response = Message::hash_response(NO_CIRCUIT, [0, 0, 0, 2], vec![[2; 32], [2; 32]]);
+    peer.write_all(&response.to_bytes()?).await?;
+
+    assert!(future::timeout(TIMEOUT, listener).await?.is_err());
+    assert!(cable.get_peer_ids().await.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn skip_past_channel_list() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+    cable.post_text("entomology", "a luna moth!").await?;
+    cable.post_text("myrmecology", "an army ant!").await?;
+
+    let (stream, mut peer) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await });
+    }
+
+    // Skip past the limit, then past the two known channels.
+    for (req_id, skip, limit) in [([0, 0, 0, 1], 2, 1), ([0, 0, 0, 2], 5, 0)] {
+        let request = Message::channel_list_request(NO_CIRCUIT, req_id, 0, skip, limit);
+        peer.write_all(&request.to_bytes()?).await?;
+    }
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer, options);
+    let mut responses = 0;
+    while responses < 2 {
+        let buf = future::timeout(TIMEOUT, messages.next()).await?.unwrap()?;
+        if let MessageBody::Response {
+            body: ResponseBody::ChannelList { channels },
+        } = Message::from_bytes(&buf)?.1.body
+        {
+            assert!(channels.is_empty());
+            responses += 1;
+        }
+    }
+    assert_eq!(cable.get_peer_ids().await.len(), 1);
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Test handling the messages received from a peer through bounded queues.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager with a single handler and a queue
+//! of one message, and write a burst of post requests at once.
+//!
+//! 2) Ensure every request is answered, in the order in which the requests
+//! were written.
+//!
+//! 3) Repeat with several handlers, ensuring every request is answered.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    Error, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, ManagerOptions, MemoryStore};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const REQUESTS: u32 = 50;
+
+/// Write a burst of post requests to a manager with the given handler
+/// concurrency and return the request IDs of the responses, in the order in
+/// which they were received.
+async fn answer_burst(handler_concurrency: usize) -> Result<Vec<ReqId>, Error> {
+    let options = ManagerOptions {
+        handler_concurrency,
+        inbound_queue_size: 1,
+        ..Default::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    task::spawn(async move { cable.listen(stream).await });
+
+    let mut burst = Vec::new();
+    for i in 0..REQUESTS {
+        let request = Message::post_request(NO_CIRCUIT, i.to_be_bytes(), 0, Vec::new());
+        burst.extend(request.to_bytes()?);
+    }
+    peer.write_all(&burst).await?;
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer, options);
+
+    future::timeout(TIMEOUT, async {
+        let mut answered = Vec::new();
+        while answered.len() < REQUESTS as usize {
+            let buf = match messages.next().await {
+                Some(buf) => buf?,
+                None => return Err("stream closed".into()),
+            };
+            let (_, msg) = Message::from_bytes(&buf)?;
+            if let MessageBody::Response {
+                body: ResponseBody::Post { .. },
+            } = msg.body
+            {
+                answered.push(msg.header.req_id);
+            }
+        }
+
+        Ok(answered)
+    })
+    .await?
+}
+
+#[async_std::test]
+async fn handle_burst_in_order() -> Result<(), Error> {
+    let expected: Vec<ReqId> = (0..REQUESTS).map(|i| i.to_be_bytes()).collect();
+
+    assert_eq!(answer_burst(1).await?, expected);
+
+    let mut answered = answer_burst(4).await?;
+    answered.sort();
+    assert_eq!(answered, expected);
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Test the integrity check and repair of stores.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert text posts and a join post, ensuring the store passes the check.
+//!
+//! 2) Corrupt one payload, remove another and store a deleted post.
+//!
+//! 3) Ensure the check reports each problem without repairing it.
+//!
+//! 4) Repair the store and ensure the intact post is restored.
+//!
+//! 5) Ensure the repaired store passes the check.
+
+use std::convert::TryInto;
+
+use cable::{Error, Post};
+use desert::ToBytes;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+async fn verify_and_repair<S: Store>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut hashes = Vec::new();
+    for (timestamp, text) in [(100, "moth"), (200, "beetle"), (300, "wasp")] {
+        let mut post = Post::text(pk, vec![], timestamp, channel.clone(), text.into());
+        post.sign(&sk)?;
+        hashes.push(store.insert_post(&post).await?);
+    }
+    let mut join = Post::join(pk, vec![], 50, channel.clone());
+    join.sign(&sk)?;
+    store.insert_post(&join).await?;
+
+    let report = store.verify_integrity(false).await?;
+    assert!(report.is_ok());
+    assert_eq!(report.checked_posts, 4);
+
+    let (corrupt, missing, deleted) = (hashes[0], hashes[1], hashes[2]);
+
+    // Flip a byte of the post text, invalidating the hash and signature.
+    let mut payload = store.get_post_payload(&corrupt).await.unwrap();
+    let last = payload.len() - 1;
+    payload[last] ^= 0xff;
+    store.insert_post_payload(&corrupt, payload).await;
+
+    store.remove_post_payload(&missing).await;
+    store.insert_tombstone(&deleted).await;
+
+    let report = store.verify_integrity(false).await?;
+    assert!(!report.is_ok() && !report.repaired);
+    assert_eq!(report.checked_posts, 3);
+    assert_eq!(report.corrupt_posts, vec![corrupt]);
+    assert_eq!(report.missing_payloads, vec![missing]);
+    assert_eq!(report.deleted_posts, vec![deleted]);
+
+    // Reporting leaves the store unchanged.
+    assert_eq!(store.verify_integrity(false).await?, report);
+
+    let report = store.verify_integrity(true).await?;
+    assert!(report.repaired);
+    assert_eq!(report.missing_payloads, vec![missing]);
+
+    let restored = store.get_post_payload(&missing).await.unwrap();
+    assert!(Post::verify(&restored));
+    assert!(store.get_post_payload(&corrupt).await.is_none());
+    assert!(store.get_post_payload(&deleted).await.is_none());
+    assert_eq!(store.want(&[corrupt]).await, vec![corrupt]);
+
+    let page = store.get_posts_page(&channel, None, 0).await?;
+    let remaining = page
+        .posts
+        .iter()
+        .map(|post| post.to_bytes())
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(remaining, vec![restored]);
+
+    let report = store.verify_integrity(false).await?;
+    assert!(report.is_ok());
+    assert_eq!(report.checked_posts, 2);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn verify_memory_store() -> Result<(), Error> {
+    verify_and_repair(MemoryStore::default()).await
+}
+
+#[async_std::test]
+async fn verify_cached_store() -> Result<(), Error> {
+    verify_and_repair(cable_core::CachedStore::new(MemoryStore::default())).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn verify_sled_store() -> Result<(), Error> {
+    verify_and_repair(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn verify_sqlite_store() -> Result<(), Error> {
+    verify_and_repair(cable_core::SqliteStore::open_in_memory()?).await
+}
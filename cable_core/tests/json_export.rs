@@ -0,0 +1,46 @@
+//! Test the export of channel history as JSON lines.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a join post, a topic and text posts to one channel and a text
+//! post to another.
+//!
+//! 2) Export the history of the first channel as JSON lines.
+//!
+//! 3) Ensure each line converts back to a post of the channel with the
+//! expected hash, and that the other channel was not exported.
+
+#![cfg(feature = "serde")]
+
+use cable::{Error, Post};
+
+use cable_core::{CableManager, MemoryStore};
+
+#[async_std::test]
+async fn export_channel_as_json_lines() -> Result<(), Error> {
+    let entomology = "entomology".to_string();
+
+    let mut cable = CableManager::new(MemoryStore::default());
+    let join_hash = cable.post_join(&entomology).await?;
+    let topic_hash = cable.post_topic(&entomology, "insects").await?;
+    let text_hash = cable.post_text(&entomology, "moths").await?;
+    cable.post_text("botany", "ferns").await?;
+
+    let mut json = Vec::new();
+    let exported = cable.export_channel_json(&entomology, &mut json).await?;
+    assert_eq!(exported, 3);
+
+    let json = String::from_utf8(json)?;
+    let mut hashes = Vec::new();
+    for line in json.lines() {
+        let post = Post::from_json(line)?;
+        assert_eq!(post.get_channel(), Some(&entomology));
+        hashes.push(post.hash()?);
+    }
+    assert_eq!(hashes.len(), 3);
+    for hash in [join_hash, topic_hash, text_hash] {
+        assert!(hashes.contains(&hash));
+    }
+
+    Ok(())
+}
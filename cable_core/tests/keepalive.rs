@@ -0,0 +1,116 @@
+//! Test keepalive pings and idle-connection timeouts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two cable managers with short keepalive and idle timeouts.
+//!
+//! 2) Wait beyond the idle timeout, ensuring pings keep both peers connected.
+//!
+//! 3) Connect a cable manager to a raw TCP peer and ensure a ping is received.
+//!
+//! 4) Leave the raw peer silent, ensuring the connection is torn down.
+
+use std::{error::Error as _, io::ErrorKind, thread, time::Duration};
+
+use async_std::{
+    future,
+    net::{TcpListener, TcpStream},
+    stream::StreamExt,
+    task,
+};
+use cable::{
+    constants::POST_REQUEST,
+    error::{CableError, ErrorCategory},
+    message::{MessageBody, RequestBody},
+    Error, Message,
+};
+use desert::FromBytes;
+use futures::AsyncReadExt;
+
+use cable_core::{CableManager, ManagerOptions, MemoryStore};
+
+fn options() -> ManagerOptions {
+    ManagerOptions {
+        keepalive_interval: Some(Duration::from_millis(50)),
+        idle_timeout: Some(Duration::from_millis(200)),
+        ..ManagerOptions::default()
+    }
+}
+
+#[async_std::test]
+async fn keepalive_and_idle_timeout() -> Result<(), Error> {
+    // Create the first cable manager and deploy a TCP listener.
+    let listener_cable = CableManager::with_options(MemoryStore::default(), options());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let cable = listener_cable.clone();
+    task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(Ok(stream)) = incoming.next().await {
+            let cable = cable.clone();
+            task::spawn(async move {
+                let _ = cable.listen(stream).await;
+            });
+        }
+    });
+
+    // Create the second cable manager and connect to the first.
+    let dialer_cable = CableManager::with_options(MemoryStore::default(), options());
+    let stream = TcpStream::connect(addr).await?;
+    let cable = dialer_cable.clone();
+    task::spawn(async move {
+        let _ = cable.listen(stream).await;
+    });
+
+    // Wait for more than twice the idle timeout. Neither peer sends any
+    // messages other than keepalive pings and their responses.
+    thread::sleep(Duration::from_millis(500));
+
+    assert_eq!(dialer_cable.get_peer_ids().await.len(), 1);
+    assert_eq!(listener_cable.get_peer_ids().await.len(), 1);
+
+    // Deploy a raw TCP listener which never responds to pings.
+    let silent_listener = TcpListener::bind("127.0.0.1:0").await?;
+    let silent_addr = silent_listener.local_addr()?;
+
+    let cable = CableManager::with_options(MemoryStore::default(), options());
+    let stream = TcpStream::connect(silent_addr).await?;
+    let (mut silent_stream, _) = silent_listener.accept().await?;
+
+    let listen_cable = cable.clone();
+    let listen_handle = task::spawn(async move { listen_cable.listen(stream).await });
+
+    // Ensure a ping (an empty post request) is received.
+    let mut res_bytes = [0u8; 1024];
+    let _n = future::timeout(Duration::from_secs(5), silent_stream.read(&mut res_bytes)).await??;
+    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
+    assert_eq!(msg.message_type(), POST_REQUEST);
+    if let MessageBody::Request {
+        ttl,
+        body: RequestBody::Post { hashes },
+    } = msg.body
+    {
+        assert_eq!(ttl, 0);
+        assert!(hashes.is_empty());
+    } else {
+        panic!("expected a post request");
+    }
+
+    // The silent peer is torn down once the idle timeout elapses.
+    let res = future::timeout(Duration::from_secs(5), listen_handle).await?;
+    let err = res.expect_err("listen should time out");
+    let cable_err = err
+        .downcast_ref::<CableError>()
+        .expect("timeout should be a cable error");
+    assert_eq!(cable_err.category(), ErrorCategory::Transport);
+    let io_err = cable_err
+        .source()
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .expect("timeout should be caused by an I/O error");
+    assert_eq!(io_err.kind(), ErrorKind::TimedOut);
+
+    assert!(cable.get_peer_ids().await.is_empty());
+
+    Ok(())
+}
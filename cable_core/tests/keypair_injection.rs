@@ -0,0 +1,66 @@
+//! Test supplying the keypair of the local peer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a manager with a keypair supplied by the host application and
+//!    publish a post.
+//!
+//! 2) Ensure the post is authored and signed with the supplied keypair, and
+//!    that the keypair of the store is left untouched.
+//!
+//! 3) Connect the manager to a second peer which posts a text mentioning
+//!    the supplied public key, and ensure the post is recorded as a mention
+//!    once the channel is opened.
+
+use std::time::Duration;
+
+use cable::{crypto, ChannelOptions, Error, Post};
+use desert::ToBytes;
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn supplied_keypair() -> Result<(), Error> {
+    let keypair = crypto::generate_keypair();
+    let store = MemoryStore::default();
+    let stored = store.get_keypair().await;
+    assert_ne!(stored, Some(keypair));
+    let mut cable = CableManager::builder(store.clone())
+        .keypair(keypair)
+        .build();
+
+    assert_eq!(cable.get_public_key().await?, keypair.0);
+    let moth = cable.post_text("entomology", "moth").await?;
+    let post = cable.get_post(&moth).await?.unwrap();
+    assert_eq!(post.get_public_key(), keypair.0);
+    assert!(Post::verify(&post.to_bytes()?));
+    assert_eq!(store.get_keypair().await, stored);
+
+    let network = Network::with_managers(
+        vec![cable, CableManager::new(MemoryStore::default())],
+        Topology::Line,
+    );
+    let (mut first, mut second) = (network.peer(0), network.peer(1));
+    let text = format!("look {}, a wasp", hex::encode(keypair.0));
+    let wasp = second.post_text("entomology", &text).await?;
+    let _live = first
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let channel = "entomology".to_string();
+    let first = network.peer(0);
+    assert!(
+        eventually(TIMEOUT, || async {
+            first.store.get_mentions(&channel).await == vec![wasp]
+        })
+        .await
+    );
+    assert_eq!(store.get_keypair().await, stored);
+
+    Ok(())
+}
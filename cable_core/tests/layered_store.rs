@@ -0,0 +1,80 @@
+//! Test wrapping stores with layers.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Wrap a store with caching, metrics and logging layers and publish a
+//!    post through a manager over the wrapped store.
+//!
+//! 2) Ensure the post is stored in the inner store and the calls made by
+//!    the manager are counted.
+//!
+//! 3) Wrap the same store as read-only and ensure reads are served while
+//!    writes are refused or skipped, and a manager over it is read-only.
+
+use std::num::NonZeroUsize;
+
+use cable::{error::CableError, Error};
+
+use cable_core::{
+    CableManager, CachedStore, LoggingStore, MemoryStore, MetricsStore, ReadOnlyStore, Store,
+};
+
+// Wrap the given store with layers and ensure the layers are applied.
+async fn layered<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let cached = CachedStore::with_capacity(store.clone(), NonZeroUsize::new(16).unwrap());
+    let metrics = MetricsStore::new(cached);
+    let mut cable = CableManager::new(LoggingStore::new(metrics.clone()));
+
+    let moth = cable.post_text("entomology", "moth").await?;
+    assert!(store.get_post_payload(&moth).await.is_some());
+    assert_eq!(metrics.layer().call_count("insert_post"), 1);
+    assert!(metrics.layer().calls()["get_or_create_keypair"] > 0);
+
+    let mut read_only = ReadOnlyStore::new(store.clone());
+    assert!(read_only.is_read_only());
+    assert!(read_only.get_post_payload(&moth).await.is_some());
+    let keypair = read_only.get_or_create_keypair().await;
+    assert_eq!(read_only.get_keypair().await, Some(keypair));
+
+    let post = cable.get_post(&moth).await?.unwrap();
+    read_only.delete_post(&moth).await;
+    let err = read_only.insert_post(&post).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<CableError>().map(CableError::code),
+        Some(301)
+    );
+    read_only.insert_channel(&"apiology".to_string()).await;
+    assert_eq!(
+        store.get_channels().await,
+        Some(vec!["entomology".to_string()])
+    );
+    assert!(store.get_post_payload(&moth).await.is_some());
+
+    let mut mirror = CableManager::new(read_only.clone());
+    assert!(mirror.is_read_only());
+    assert!(mirror.post_text("entomology", "wasp").await.is_err());
+    assert!(read_only
+        .into_inner()
+        .get_post_payload(&moth)
+        .await
+        .is_some());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn layered_memory_store() -> Result<(), Error> {
+    layered(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn layered_sled_store() -> Result<(), Error> {
+    layered(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn layered_sqlite_store() -> Result<(), Error> {
+    layered(cable_core::SqliteStore::open_in_memory()?).await
+}
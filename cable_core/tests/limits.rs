@@ -0,0 +1,91 @@
+//! Test applying custom limits to local and received posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a cable manager with a lowered maximum text length, ensuring a
+//! text post exceeding it cannot be published.
+//!
+//! 2) Connect a raw peer and send a post within the limit and a post
+//! exceeding it, in separate responses.
+//!
+//! 3) Ensure only the post within the limit is stored.
+
+use std::{convert::TryInto, time::Duration};
+
+use async_std::task;
+use cable::{
+    constants::NO_CIRCUIT,
+    error::{CableError, CableErrorKind},
+    limits::Limits,
+    Error, Message, Post,
+};
+use desert::ToBytes;
+use futures::AsyncWriteExt;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{
+    testing::{duplex, eventually},
+    CableManager, ManagerOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn apply_custom_limits() -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+
+    let options = ManagerOptions {
+        limits: Limits {
+            max_text_len: 8,
+            ..Limits::DEFAULT
+        },
+        accept_unsolicited_posts: true,
+        // Handle the responses in the order in which they are sent.
+        handler_concurrency: 1,
+        ..Default::default()
+    };
+    let mut cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let err = cable
+        .post_text("entomology", "a moth of many colours")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<CableError>().map(|err| err.kind()),
+        Some(CableErrorKind::TextLengthIncorrect { max: 8, .. })
+    ));
+
+    let (stream, mut peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let mut hashes = Vec::new();
+    for (i, text) in ["a moth of many colours", "moth"].into_iter().enumerate() {
+        let mut post = Post::text(
+            pk,
+            vec![],
+            i as u64 + 1,
+            "entomology".to_string(),
+            text.to_string(),
+        );
+        post.sign(&sk)?;
+        hashes.push(post.hash()?);
+
+        let response = Message::post_response(NO_CIRCUIT, [0, 0, 0, 1], vec![post.to_bytes()?]);
+        peer.write_all(&response.to_bytes()?).await?;
+    }
+
+    // The responses are handled in order, so the post exceeding the limit has
+    // been handled once the other is stored.
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable.store.get_post_payload(&hashes[1]).await.is_some()
+        })
+        .await
+    );
+    assert_eq!(cable.store.get_post_payload(&hashes[0]).await, None);
+
+    Ok(())
+}
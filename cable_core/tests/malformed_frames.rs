@@ -0,0 +1,85 @@
+//! Test skipping malformed frames received from a peer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager and send a malformed frame
+//! followed by a post request, ensuring the request is answered.
+//!
+//! 2) Connect a raw peer to a cable manager which skips at most one
+//! malformed frame and send two, ensuring the connection is closed with an
+//! error.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, ManagerOptions, MemoryStore};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A frame with a valid length prefix holding a truncated post request:
+/// the message type is followed by an incomplete circuit ID.
+const MALFORMED: [u8; 3] = [2, 2, 0];
+
+#[async_std::test]
+async fn skip_malformed_frame() -> Result<(), Error> {
+    let cable = CableManager::new(MemoryStore::default());
+    let (stream, mut peer) = duplex();
+    let listener = task::spawn(async move { cable.listen(stream).await });
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer.clone(), options);
+
+    peer.write_all(&MALFORMED).await?;
+    let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+    peer.write_all(&request.to_bytes()?).await?;
+
+    let buf = future::timeout(TIMEOUT, messages.next())
+        .await?
+        .ok_or("stream closed")??;
+    let (_, msg) = Message::from_bytes(&buf)?;
+    assert_eq!(msg.header.req_id, [0, 0, 0, 1]);
+    assert!(matches!(
+        msg.body,
+        MessageBody::Response {
+            body: ResponseBody::Post { .. }
+        }
+    ));
+
+    // The connection remains open.
+    assert!(future::timeout(Duration::from_millis(100), listener)
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn close_beyond_malformed_frame_limit() -> Result<(), Error> {
+    let options = ManagerOptions {
+        malformed_frame_limit: 1,
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+    let (stream, mut peer) = duplex();
+    let listener = task::spawn(async move { cable.listen(stream).await });
+
+    peer.write_all(&MALFORMED).await?;
+    peer.write_all(&MALFORMED).await?;
+
+    let res = future::timeout(TIMEOUT, listener).await?;
+    assert!(res.is_err());
+
+    Ok(())
+}
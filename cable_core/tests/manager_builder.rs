@@ -0,0 +1,73 @@
+//! Test configuring a manager with its builder.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Build a manager with a request TTL, a mock clock and events disabled.
+//!
+//! 2) Ensure published posts are timestamped by the mock clock and the event
+//!    stream of the manager ends immediately.
+//!
+//! 3) Connect a raw peer and open a channel, ensuring the channel time range
+//!    request carries the configured TTL.
+
+use std::{sync::Arc, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    message::{MessageBody, RequestBody},
+    ChannelOptions, Error, Message,
+};
+use desert::FromBytes;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, MemoryStore, MockClock};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const NOW: u64 = 1_000_000;
+
+#[async_std::test]
+async fn build_manager() -> Result<(), Error> {
+    let mut cable = CableManager::builder(MemoryStore::default())
+        .ttl(4)
+        .max_live_requests(64)
+        .clock(Arc::new(MockClock::new(NOW)))
+        .events(false)
+        .build();
+
+    let hash = cable.post_text("entomology", "moth").await?;
+    let post = cable.get_post(&hash).await?.expect("post is stored");
+    assert_eq!(post.get_timestamp(), NOW);
+    assert!(cable.events().await.next().await.is_none());
+
+    let (stream, peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let _live = cable
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer, options);
+    let ttl = future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if let MessageBody::Request {
+                ttl,
+                body: RequestBody::ChannelTimeRange { .. },
+            } = msg.body
+            {
+                return Ok(ttl);
+            }
+        }
+
+        Err::<_, Error>("stream closed".into())
+    })
+    .await??;
+    assert_eq!(ttl, 4);
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Test detecting mentions of the local user in received posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of two connected peers, set a name on the first peer
+//! and subscribe to its events.
+//!
+//! 2) Post a text mentioning the name and a text without a mention on the
+//! second peer, then open the channel on the first peer.
+//!
+//! 3) Ensure a mention event is emitted for the mentioning post only and that
+//! it is recorded in the mention index of the store.
+//!
+//! 4) Delete the post, ensuring it is removed from the mention index.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableEvent, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn detect_mentions() -> Result<(), Error> {
+    let network = Network::new(2, Topology::Line);
+    let mut first = network.peer(0);
+    let mut second = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.len() == 1 }).await);
+
+    first.post_info_name("glyph").await?;
+    let mut events = first.events().await;
+
+    let hash = second
+        .post_text("entomology", "look @glyph, a moth")
+        .await?;
+    let other_hash = second.post_text("entomology", "a beetle").await?;
+
+    let _ = first
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let event = future::timeout(TIMEOUT, events.next()).await?.unwrap();
+    let CableEvent::Mention {
+        channel,
+        hash: mention_hash,
+        post,
+    } = event
+    else {
+        panic!("expected a mention event");
+    };
+    assert_eq!(channel, "entomology");
+    assert_eq!(mention_hash, hash);
+    assert!(matches!(post.body, PostBody::Text { text, .. } if text.contains("@glyph")));
+
+    let channel = "entomology".to_string();
+    assert!(
+        eventually(TIMEOUT, || async {
+            first.store.get_post_payload(&other_hash).await.is_some()
+        })
+        .await
+    );
+    assert_eq!(first.store.get_mentions(&channel).await, vec![hash]);
+    assert!(future::timeout(Duration::from_millis(100), events.next())
+        .await
+        .is_err());
+
+    first.store.delete_post(&hash).await;
+    assert!(first.store.get_mentions(&channel).await.is_empty());
+
+    Ok(())
+}
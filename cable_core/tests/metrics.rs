@@ -0,0 +1,70 @@
+//! Test the metrics reported by each store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish posts to two channels, a name and a deleted post.
+//!
+//! 2) Ensure the reported post counts and tombstone count are correct.
+//!
+//! 3) Ensure index sizes and, for persistent stores, disk usage are reported.
+
+use cable::Error;
+
+use cable_core::{CableManager, CachedStore, MemoryStore, Store, StoreMetrics};
+
+// Publish posts through a manager backed by the given store and return the
+// reported metrics.
+async fn publish_and_measure<S: Store + Clone>(store: S) -> Result<StoreMetrics, Error> {
+    let mut cable = CableManager::new(store);
+    let entomology = "entomology".to_string();
+    let botany = "botany".to_string();
+
+    cable.post_join(&entomology).await?;
+    cable.post_text(&entomology, "moths").await?;
+    cable.post_topic(&entomology, "insects").await?;
+    let deleted_hash = cable.post_text(&entomology, "oops").await?;
+    cable.post_text(&botany, "ferns").await?;
+    cable.post_info_name("glyph").await?;
+    cable.post_delete(vec![deleted_hash]).await?;
+
+    let metrics = cable.store.metrics().await?;
+
+    assert_eq!(metrics.posts_per_channel.get(&entomology), Some(&2));
+    assert_eq!(metrics.posts_per_channel.get(&botany), Some(&1));
+    // The join, two text, topic, info and delete posts.
+    assert_eq!(metrics.total_posts, 6);
+    assert_eq!(metrics.tombstones, Some(1));
+    assert_eq!(metrics.index_sizes.get("channels"), Some(&2));
+    assert_eq!(metrics.index_sizes.get("delete_hashes"), Some(&1));
+
+    Ok(metrics)
+}
+
+#[async_std::test]
+async fn memory_store_metrics() -> Result<(), Error> {
+    let metrics = publish_and_measure(MemoryStore::default()).await?;
+    assert_eq!(metrics.disk_usage, None);
+
+    let metrics = publish_and_measure(CachedStore::new(MemoryStore::default())).await?;
+    assert!(metrics.index_sizes.contains_key("cached_payloads"));
+
+    Ok(())
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn sled_store_metrics() -> Result<(), Error> {
+    let metrics = publish_and_measure(cable_core::SledStore::temporary()?).await?;
+    assert!(metrics.disk_usage.is_some());
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn sqlite_store_metrics() -> Result<(), Error> {
+    let metrics = publish_and_measure(cable_core::SqliteStore::open_in_memory()?).await?;
+    assert!(metrics.disk_usage.unwrap() > 0);
+
+    Ok(())
+}
@@ -0,0 +1,102 @@
+//! Test rejecting requested posts which fall outside of the channel for which
+//! their hashes were advertised.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a post in each of two channels on one manager, then open one of
+//! the channels on another manager and connect a raw peer to it.
+//!
+//! 2) Answer the channel time range request of the second manager with the
+//! hashes of both posts, then answer the ensuing post request with both
+//! posts.
+//!
+//! 3) Ensure only the post in the opened channel is stored.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, RequestBody},
+    ChannelOptions, Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn reject_misplaced_posts() -> Result<(), Error> {
+    let mut author = CableManager::new(MemoryStore::default());
+    let moth = author.post_text("entomology", "moth").await?;
+    let fern = author.post_text("botany", "fern").await?;
+    let mut payloads = Vec::new();
+    for hash in [moth, fern] {
+        payloads.push(author.store.get_post_payload(&hash).await.unwrap());
+    }
+
+    let mut cable = CableManager::new(MemoryStore::default());
+    let _ = cable
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let (stream, mut peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(peer.clone(), options);
+
+    // Advertise both hashes in response to the channel time range request and
+    // answer the post request with both posts.
+    future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            let (response, answered) = match msg.body {
+                MessageBody::Request {
+                    body: RequestBody::ChannelTimeRange { .. },
+                    ..
+                } => (
+                    Message::hash_response(NO_CIRCUIT, msg.header.req_id, vec![moth, fern]),
+                    false,
+                ),
+                MessageBody::Request {
+                    body: RequestBody::Post { .. },
+                    ..
+                } => (
+                    Message::post_response(NO_CIRCUIT, msg.header.req_id, payloads.clone()),
+                    true,
+                ),
+                _ => continue,
+            };
+            peer.write_all(&response.to_bytes()?).await?;
+            if answered {
+                return Ok(());
+            }
+        }
+
+        Err::<_, Error>("stream closed".into())
+    })
+    .await??;
+
+    // Both posts arrive in a single response, so the misplaced post has been
+    // handled once the other is stored.
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable.store.get_post_payload(&moth).await.is_some()
+        })
+        .await
+    );
+    assert_eq!(cable.store.get_post_payload(&fern).await, None);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+//! Test the exchange of posts between peers of an in-process network.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of three peers connected in a line.
+//!
+//! 2) Publish a post on the first peer and open the channel on the second.
+//!
+//! 3) Ensure the post is replicated to the second peer.
+//!
+//! 4) Publish a second post on the first peer and ensure it is delivered to
+//! the second peer by the live channel time range request.
+//!
+//! 5) Disconnect the first and second peers and ensure both peers remove the
+//! connection.
+
+use std::time::Duration;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Return the text of all posts in the given channel of the given peer.
+async fn texts(peer: &CableManager<MemoryStore>, channel: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut posts = peer
+        .store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+
+    texts
+}
+
+#[async_std::test]
+async fn replicate_posts_between_adjacent_peers() -> Result<(), Error> {
+    let mut network = Network::new(3, Topology::Line);
+    let channel = "default";
+
+    let mut first = network.peer(0);
+    let second = network.peer(1);
+
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.len() == 1 }).await);
+    assert!(eventually(TIMEOUT, || async { second.get_peer_ids().await.len() == 2 }).await);
+
+    first.post_text(channel, "hello").await?;
+
+    let mut reader = network.peer(1);
+    let _live = reader
+        .open_channel(&ChannelOptions::new(channel, 0, 0, 0))
+        .await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            texts(&second, channel).await == vec!["hello".to_string()]
+        })
+        .await
+    );
+
+    first.post_text(channel, "again").await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            texts(&second, channel).await.len() == 2
+        })
+        .await
+    );
+
+    assert!(network.disconnect(0, 1).await);
+    assert!(!network.is_connected(0, 1));
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.is_empty() }).await);
+    assert!(eventually(TIMEOUT, || async { second.get_peer_ids().await.len() == 1 }).await);
+
+    network.shutdown().await;
+
+    Ok(())
+}
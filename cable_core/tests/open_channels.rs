@@ -0,0 +1,60 @@
+//! Test opening several channels as a single merged stream of posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of two connected peers and post to two channels on the
+//! second peer.
+//!
+//! 2) Open both channels on the first peer and ensure the posts of both are
+//! returned by the merged stream, each tagged with its channel.
+//!
+//! 3) Post to one of the channels on the second peer and ensure the new post
+//! is returned by the stream.
+
+use std::{collections::HashSet, time::Duration};
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::testing::{eventually, Network, Topology};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn open_channels_as_merged_stream() -> Result<(), Error> {
+    let network = Network::new(2, Topology::Line);
+    let mut first = network.peer(0);
+    let mut second = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.len() == 1 }).await);
+
+    second.post_text("entomology", "moths").await?;
+    second.post_text("botany", "ferns").await?;
+
+    let opts = [
+        ChannelOptions::new("entomology", 0, 0, 0),
+        ChannelOptions::new("botany", 0, 0, 0),
+    ];
+    let mut posts = first.open_channels(&opts).await?;
+
+    let mut received = HashSet::new();
+    while received.len() < 2 {
+        let (channel, post) = future::timeout(TIMEOUT, posts.next()).await?.unwrap()?;
+        if let PostBody::Text { text, .. } = post.body {
+            received.insert((channel, text));
+        }
+    }
+    assert_eq!(
+        received,
+        HashSet::from([
+            ("entomology".to_string(), "moths".to_string()),
+            ("botany".to_string(), "ferns".to_string()),
+        ])
+    );
+
+    second.post_text("botany", "mosses").await?;
+    let (channel, post) = future::timeout(TIMEOUT, posts.next()).await?.unwrap()?;
+    assert_eq!(channel, "botany");
+    assert!(matches!(post.body, PostBody::Text { text, .. } if text == "mosses"));
+
+    Ok(())
+}
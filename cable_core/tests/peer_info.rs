@@ -0,0 +1,79 @@
+//! Test inspecting the state of the connections to peers.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of two connected peers, the first with a mock clock,
+//!    and open a channel on the first peer.
+//!
+//! 2) Ensure the second peer holds live requests for the channel against
+//!    the first peer, as reported by the first peer.
+//!
+//! 3) Ensure the first peer reports its channel requests as outstanding
+//!    requests sent to the second peer, the traffic exchanged and the age of
+//!    the connection.
+//!
+//! 4) Ensure no information is returned for an unknown peer.
+
+use std::{sync::Arc, time::Duration};
+
+use cable::{ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, ManagerOptions, MemoryStore, MockClock,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const NOW: u64 = 1_000_000;
+
+#[async_std::test]
+async fn inspect_peers() -> Result<(), Error> {
+    let clock = MockClock::new(NOW);
+    let options = ManagerOptions {
+        clock: Arc::new(clock.clone()),
+        ..Default::default()
+    };
+    let first = CableManager::with_options(MemoryStore::default(), options);
+    let second = CableManager::new(MemoryStore::default());
+    let network = Network::with_managers(vec![first, second], Topology::Line);
+    let (mut first, second) = (network.peer(0), network.peer(1));
+    for peer in [&first, &second] {
+        assert!(eventually(TIMEOUT, || async { peer.get_peer_ids().await.len() == 1 }).await);
+    }
+
+    let _live = first
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    let first = network.peer(0);
+    let second_id = first.get_peer_ids().await[0];
+    let first_id = second.get_peer_ids().await[0];
+    assert!(
+        eventually(TIMEOUT, || async {
+            let info = second.peer_info(first_id).await.unwrap();
+            info.live_requests.len() == 2 && info.messages_received >= 2
+        })
+        .await
+    );
+    let info = second.peer_info(first_id).await.unwrap();
+    assert!(info
+        .live_requests
+        .iter()
+        .all(|(_req_id, channel)| channel == "entomology"));
+    assert!(info.bytes_received > 0);
+
+    clock.advance(Duration::from_secs(5));
+    let info = first.peer_info(second_id).await.unwrap();
+    assert_eq!(info.peer_id, second_id);
+    assert_eq!(info.connected_at, Some(NOW));
+    assert_eq!(info.connection_age, Some(Duration::from_secs(5)));
+    assert_eq!(info.outstanding_requests.len(), 2);
+    assert!(info.messages_sent >= 2);
+    assert!(info.bytes_sent > 0);
+    assert_eq!(info.requested_posts, 0);
+    assert!(info.live_requests.is_empty());
+
+    assert!(first.peer_info(second_id + 100).await.is_none());
+
+    Ok(())
+}
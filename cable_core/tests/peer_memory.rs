@@ -0,0 +1,118 @@
+//! Test enforcing the memory budget of each peer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager with a small memory budget per
+//! peer and send 50 requests with a TTL of 1, to be forwarded.
+//!
+//! 2) Connect a second raw peer and ensure it is sent only the most recent
+//! of the forwarded requests, the oldest having been shed.
+//!
+//! 3) Connect a raw peer to a cable manager with a memory budget too small
+//! for a single response and send a request, ensuring the peer is
+//! disconnected.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, RequestBody},
+    Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, ManagerOptions, MemoryStore};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const REQUESTS: u32 = 50;
+
+fn decode_options() -> DecodeOptions {
+    DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn shed_oldest_forwarded_requests() -> Result<(), Error> {
+    let options = ManagerOptions {
+        peer_memory_budget: Some(1_000),
+        // Handle the requests in the order in which they are sent.
+        handler_concurrency: 1,
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await });
+    }
+
+    let mut responses = decode_with_options(peer.clone(), decode_options());
+
+    for i in 1..=REQUESTS {
+        let request = Message::channel_list_request(NO_CIRCUIT, i.to_be_bytes(), 1, 0, 0);
+        peer.write_all(&request.to_bytes()?).await?;
+    }
+
+    // Wait for every request to be answered.
+    for _ in 1..=REQUESTS {
+        future::timeout(TIMEOUT, responses.next())
+            .await?
+            .ok_or("stream closed")??;
+    }
+
+    let (stream, other_peer) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await });
+    }
+
+    // Collect the IDs of the forwarded requests sent to the second peer.
+    let mut messages = decode_with_options(other_peer, decode_options());
+    let mut forwarded = Vec::new();
+    while let Ok(Some(buf)) = future::timeout(Duration::from_millis(200), messages.next()).await {
+        let (_, msg) = Message::from_bytes(&buf?)?;
+        if let MessageBody::Request {
+            body: RequestBody::ChannelList { .. },
+            ..
+        } = msg.body
+        {
+            forwarded.push(u32::from_be_bytes(msg.header.req_id));
+        }
+    }
+
+    assert!(!forwarded.is_empty());
+    assert!(forwarded.len() < REQUESTS as usize);
+    assert!(forwarded.contains(&REQUESTS));
+    assert!(!forwarded.contains(&1));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn disconnect_peer_exceeding_budget() -> Result<(), Error> {
+    let options = ManagerOptions {
+        peer_memory_budget: Some(8),
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    let listener = {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await })
+    };
+
+    let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+    peer.write_all(&request.to_bytes()?).await?;
+
+    assert!(future::timeout(TIMEOUT, listener).await?.is_err());
+    assert!(cable.get_peer_ids().await.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Test persisting the outbound requests of local origin and reissuing them
+//! after a restart.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open a channel and request the channel list, ensuring the three requests
+//! are persisted in the store.
+//!
+//! 2) Connect a raw peer and conclude the channel list request with an empty
+//! response, ensuring it is no longer persisted.
+//!
+//! 3) Create a new manager over the same store, connect a raw peer and ensure
+//! the live channel requests are reissued under fresh request IDs.
+//!
+//! 4) Close the channel and ensure no requests remain persisted.
+//!
+//! 5) Ensure requests persisted in a sled store are retained when it is
+//! reopened.
+
+use std::{collections::HashSet, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::{
+        CHANNEL_LIST_REQUEST, CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST, NO_CIRCUIT,
+    },
+    ChannelOptions, Error, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, MemoryStream},
+    CableManager, MemoryStore, SledStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read the given number of messages from the stream.
+async fn read_messages(stream: MemoryStream, n: usize) -> Result<Vec<Message>, Error> {
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(stream, options);
+
+    future::timeout(TIMEOUT, async {
+        let mut received = Vec::new();
+        while received.len() < n {
+            match messages.next().await {
+                Some(buf) => received.push(Message::from_bytes(&buf?)?.1),
+                None => return Err("stream closed".into()),
+            }
+        }
+
+        Ok(received)
+    })
+    .await?
+}
+
+/// Connect a raw peer to the given manager.
+fn connect(cable: &CableManager<MemoryStore>) -> MemoryStream {
+    let (stream, peer) = duplex();
+    let cable = cable.clone();
+    task::spawn(async move { cable.listen(stream).await });
+
+    peer
+}
+
+/// Return the request IDs of the requests persisted in the given store.
+async fn persisted(store: &impl Store) -> HashSet<ReqId> {
+    store
+        .get_outbound_requests()
+        .await
+        .into_iter()
+        .map(|(req_id, _request)| req_id)
+        .collect()
+}
+
+#[async_std::test]
+async fn reissue_persisted_requests() -> Result<(), Error> {
+    let store = MemoryStore::default();
+
+    let mut cable = CableManager::new(store.clone());
+    let opts = ChannelOptions::new("entomology", 0, 0, 10);
+    let _ = cable.open_channel(&opts).await?;
+    cable.request_channel_list(0, 0).await?;
+    assert_eq!(persisted(&store).await.len(), 3);
+
+    let mut peer = connect(&cable);
+    let requests = read_messages(peer.clone(), 3).await?;
+    let list_request = requests
+        .iter()
+        .find(|msg| msg.message_type() == CHANNEL_LIST_REQUEST)
+        .unwrap();
+    let response =
+        Message::channel_list_response(NO_CIRCUIT, list_request.header.req_id, Vec::new());
+    peer.write_all(&response.to_bytes()?).await?;
+
+    let live_req_ids: HashSet<ReqId> = requests
+        .iter()
+        .filter(|msg| msg.message_type() != CHANNEL_LIST_REQUEST)
+        .map(|msg| msg.header.req_id)
+        .collect();
+    assert!(
+        eventually(TIMEOUT, || async {
+            persisted(&store).await == live_req_ids
+        })
+        .await
+    );
+    peer.close();
+
+    // Restart with the same store.
+    let cable = CableManager::new(store.clone());
+    let peer = connect(&cable);
+    let requests = read_messages(peer.clone(), 2).await?;
+
+    let mut request_types: Vec<u64> = requests.iter().map(|msg| msg.message_type()).collect();
+    request_types.sort();
+    assert_eq!(
+        request_types,
+        vec![CHANNEL_TIME_RANGE_REQUEST, CHANNEL_STATE_REQUEST]
+    );
+
+    let reissued_req_ids: HashSet<ReqId> = requests.iter().map(|msg| msg.header.req_id).collect();
+    assert!(reissued_req_ids.is_disjoint(&live_req_ids));
+    assert_eq!(persisted(&store).await, reissued_req_ids);
+
+    // Nothing is restored twice.
+    assert_eq!(cable.restore_requests().await?, 0);
+
+    cable.close_channel("entomology").await?;
+    assert!(persisted(&store).await.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn persist_requests_in_sled_store() -> Result<(), Error> {
+    let db = sled::Config::new().temporary(true).open()?;
+
+    let request = Message::channel_list_request(NO_CIRCUIT, [0, 0, 0, 1], 1, 0, 0);
+    let mut store = SledStore::from_db(db.clone())?;
+    store
+        .insert_outbound_request(&[0, 0, 0, 1], &request.to_bytes()?)
+        .await;
+    drop(store);
+
+    // Reopen the store from the same database.
+    let mut store = SledStore::from_db(db)?;
+    assert_eq!(
+        store.get_outbound_requests().await,
+        vec![([0, 0, 0, 1], request.to_bytes()?)]
+    );
+    store.remove_outbound_request(&[0, 0, 0, 1]).await;
+    assert!(store.get_outbound_requests().await.is_empty());
+
+    Ok(())
+}
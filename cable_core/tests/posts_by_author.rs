@@ -0,0 +1,107 @@
+//! Test retrieving the posts of an author.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert text posts by two authors to two channels, along with a
+//!    `post/join` post and a `post/info` post by the first author.
+//!
+//! 2) Retrieve the posts of the first author in pages of two posts, ensuring
+//!    the pages are newest first and exclude the posts of the second author.
+//!
+//! 3) Ensure the posts can be restricted to a single channel.
+//!
+//! 4) Delete a post and ensure it is removed from the posts of its author.
+
+use std::convert::TryInto;
+
+use cable::{Error, Hash, Post, UserInfo};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{AuthorOptions, MemoryStore, Store};
+
+// Return the hashes of the given posts.
+fn hashes(posts: &[Post]) -> Result<Vec<Hash>, Error> {
+    posts.iter().map(Post::hash).collect()
+}
+
+// Retrieve the posts of an author in the given store.
+async fn query_author<S: Store>(mut store: S) -> Result<(), Error> {
+    let mut keypairs = Vec::new();
+    for _ in 0..2 {
+        let (pk, sk) = sign::gen_keypair();
+        let pk: [u8; 32] = pk.as_ref().try_into()?;
+        let sk: [u8; 64] = sk.as_ref().try_into()?;
+        keypairs.push((pk, sk));
+    }
+    let (pk, sk) = keypairs[0];
+    let (other_pk, other_sk) = keypairs[1];
+
+    let mut posts = [
+        Post::text(pk, vec![], 100, "entomology".into(), "moth".into()),
+        Post::join(pk, vec![], 200, "botany".into()),
+        Post::text(pk, vec![], 300, "botany".into(), "fern".into()),
+        Post::info(pk, vec![], 400, vec![UserInfo::new("name", "ayla")]),
+        Post::text(pk, vec![], 500, "entomology".into(), "beetle".into()),
+    ];
+    let mut expected = Vec::new();
+    for post in posts.iter_mut() {
+        post.sign(&sk)?;
+        expected.push(store.insert_post(post).await?);
+    }
+    expected.reverse();
+
+    let mut other = Post::text(other_pk, vec![], 250, "entomology".into(), "wasp".into());
+    other.sign(&other_sk)?;
+    let other_hash = store.insert_post(&other).await?;
+
+    let mut pages = Vec::new();
+    let mut opts = AuthorOptions {
+        limit: 2,
+        ..Default::default()
+    };
+    loop {
+        let page = store.get_posts_by_author(&pk, &opts).await?;
+        assert!(page.posts.len() <= 2);
+        pages.extend(hashes(&page.posts)?);
+        match page.next {
+            Some(next) => opts.before = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(pages, expected);
+
+    let page = store
+        .get_posts_by_author(&other_pk, &AuthorOptions::default())
+        .await?;
+    assert_eq!(hashes(&page.posts)?, vec![other_hash]);
+
+    let opts = AuthorOptions {
+        channel: Some("entomology".into()),
+        ..Default::default()
+    };
+    let page = store.get_posts_by_author(&pk, &opts).await?;
+    assert_eq!(hashes(&page.posts)?, vec![expected[0], expected[4]]);
+
+    store.delete_post(&expected[0]).await;
+    let page = store.get_posts_by_author(&pk, &opts).await?;
+    assert_eq!(hashes(&page.posts)?, vec![expected[4]]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn query_author_memory_store() -> Result<(), Error> {
+    query_author(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn query_author_sled_store() -> Result<(), Error> {
+    query_author(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn query_author_sqlite_store() -> Result<(), Error> {
+    query_author(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,102 @@
+//! Test reverse-chronological, paginated post retrieval.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert text posts to two channels, several sharing a timestamp.
+//!
+//! 2) Retrieve the posts of one channel in pages of two posts.
+//!
+//! 3) Ensure the pages are newest first, complete and without duplicates.
+//!
+//! 4) Ensure a cursor created from a timestamp excludes later posts.
+
+use std::convert::TryInto;
+
+use cable::{post::PostBody, Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, PageCursor, Store};
+
+// Return the text of the given text posts.
+fn texts(posts: &[Post]) -> Vec<String> {
+    posts
+        .iter()
+        .filter_map(|post| match &post.body {
+            PostBody::Text { text, .. } => Some(text.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+// Page through the posts of a channel in the given store, ensuring the
+// ordering and completeness of the pages.
+async fn paginate<S: Store>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut expected = Vec::new();
+    for (i, timestamp) in [100, 200, 200, 200, 300].iter().enumerate() {
+        let text = format!("moth {}", i);
+        let mut post = Post::text(pk, vec![], *timestamp, channel.clone(), text.clone());
+        post.sign(&sk)?;
+        let hash = store.insert_post(&post).await?;
+
+        let mut other = Post::text(pk, vec![], *timestamp, "botany".into(), text.clone());
+        other.sign(&sk)?;
+        store.insert_post(&other).await?;
+
+        expected.push((*timestamp, hash, text));
+    }
+    expected.sort();
+    expected.reverse();
+    let expected: Vec<String> = expected.into_iter().map(|(_, _, text)| text).collect();
+
+    let mut pages = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = store.get_posts_page(&channel, cursor, 2).await?;
+        assert!(page.posts.len() <= 2);
+        pages.extend(texts(&page.posts));
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(pages, expected);
+
+    // A limit of 0 returns all posts in a single page.
+    let page = store.get_posts_page(&channel, None, 0).await?;
+    assert_eq!(texts(&page.posts), expected);
+    assert!(page.next.is_none());
+
+    let page = store
+        .get_posts_page(&channel, Some(PageCursor::before(300)), 0)
+        .await?;
+    assert_eq!(texts(&page.posts), expected[1..].to_vec());
+
+    let page = store
+        .get_posts_page(&"lepidoptera".to_string(), None, 2)
+        .await?;
+    assert!(page.posts.is_empty() && page.next.is_none());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn paginate_memory_store() -> Result<(), Error> {
+    paginate(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn paginate_sled_store() -> Result<(), Error> {
+    paginate(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn paginate_sqlite_store() -> Result<(), Error> {
+    paginate(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,94 @@
+//! Test exchanging encrypted posts in a private channel.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a line of three connected peers and mark the private channel
+//! shared by the first and last peers on both of them.
+//!
+//! 2) Post a text to the private channel on the first peer and ensure it is
+//! encrypted in the store.
+//!
+//! 3) Open the channel on the last peer and ensure the post is decrypted.
+//!
+//! 4) Ensure the middle peer, which relays the post, cannot read it.
+
+#![cfg(feature = "private-channels")]
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, ChannelOptions, Error, Post};
+
+use cable_core::{
+    private_channel,
+    testing::{eventually, Network, Topology},
+    Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn text(post: &Post) -> Option<&str> {
+    match &post.body {
+        PostBody::Text { text, .. } => Some(text),
+        _ => None,
+    }
+}
+
+#[async_std::test]
+async fn exchange_private_posts() -> Result<(), Error> {
+    let network = Network::new(3, Topology::Line);
+    let mut first = network.peer(0);
+    let mut middle = network.peer(1);
+    let mut last = network.peer(2);
+    assert!(eventually(TIMEOUT, || async { middle.get_peer_ids().await.len() == 2 }).await);
+
+    let first_key = first.get_public_key().await?;
+    let last_key = last.get_public_key().await?;
+    let channel = first.add_private_channel(&last_key).await?;
+    assert_eq!(last.add_private_channel(&first_key).await?, channel);
+    assert_eq!(channel, private_channel(&first_key, &last_key));
+
+    let hash = first.post_text(&channel, "the moths are back").await?;
+    let stored = first.store.get_post_payload(&hash).await.unwrap();
+    let stored = String::from_utf8_lossy(&stored);
+    assert!(!stored.contains("moths"));
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let mut posts = last.open_channel(&opts).await?;
+    let post = future::timeout(TIMEOUT, async {
+        loop {
+            match posts.next().await {
+                Some(Ok(post)) if text(&post).is_some() => return Ok(post),
+                Some(Ok(_)) => continue,
+                Some(Err(err)) => return Err(err),
+                None => return Err("stream closed".into()),
+            }
+        }
+    })
+    .await??;
+    assert_eq!(text(&post), Some("the moths are back"));
+    drop(posts);
+
+    // The middle peer stores the post but cannot read it, and its stream of
+    // the channel contains the encrypted text.
+    assert!(
+        eventually(TIMEOUT, || async {
+            middle.store.get_post_payload(&hash).await.is_some()
+        })
+        .await
+    );
+    let mut posts = middle.open_channel(&opts).await?;
+    let post = future::timeout(TIMEOUT, async {
+        loop {
+            if let Some(Ok(post)) = posts.next().await {
+                if text(&post).is_some() {
+                    return post;
+                }
+            }
+        }
+    })
+    .await?;
+    assert!(!text(&post).unwrap().contains("moths"));
+
+    Ok(())
+}
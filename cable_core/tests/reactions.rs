@@ -0,0 +1,85 @@
+//! Test publishing reaction posts and indexing them by the post they react
+//! to.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a text post and insert reactions to it by the local peer and by
+//! another author, with distinct timestamps.
+//!
+//! 2) Ensure the reactions are indexed in order of timestamp and are returned
+//! with the posts of the channel.
+//!
+//! 3) Delete a reaction, ensuring it is removed from the index.
+
+#![cfg(feature = "reactions")]
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{CableManager, MemoryStore, Store};
+
+async fn index_reactions<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut cable = CableManager::new(store);
+    let target = cable.post_text("entomology", "a luna moth!").await?;
+    let own_reaction = cable.post_reaction("entomology", &target, "🦋").await?;
+
+    // A reaction by another author, made before that of the local peer.
+    let mut post = Post::reaction(pk, vec![], 100, channel.clone(), target, "👀".into());
+    post.sign(&sk)?;
+    let other_reaction = cable.store.insert_post(&post).await?;
+
+    assert_eq!(
+        cable.store.get_reactions(&target).await,
+        vec![other_reaction, own_reaction]
+    );
+    assert!(cable.store.get_reactions(&own_reaction).await.is_empty());
+
+    // Reactions are channel posts.
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let reactions: Vec<String> = cable
+        .store
+        .get_posts(&opts)
+        .await
+        .filter_map(|post| match post.ok()?.body {
+            PostBody::Reaction { reaction, .. } => Some(reaction),
+            _ => None,
+        })
+        .collect()
+        .await;
+    assert_eq!(reactions, vec!["👀".to_string(), "🦋".to_string()]);
+
+    assert!(cable
+        .post_reaction("entomology", &target, "")
+        .await
+        .is_err());
+
+    cable.store.delete_post(&other_reaction).await;
+    assert_eq!(cable.store.get_reactions(&target).await, vec![own_reaction]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn index_reactions_memory_store() -> Result<(), Error> {
+    index_reactions(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn index_reactions_sled_store() -> Result<(), Error> {
+    index_reactions(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn index_reactions_sqlite_store() -> Result<(), Error> {
+    index_reactions(cable_core::SqliteStore::open_in_memory()?).await
+}
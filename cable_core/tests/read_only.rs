@@ -0,0 +1,143 @@
+//! Test serving the history of a store without writing to it.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a post to a store, then create a read-only manager over the
+//!    store and ensure it refuses to publish.
+//!
+//! 2) Connect the read-only manager to a peer with a post of its own and open
+//!    the channel on both peers.
+//!
+//! 3) Ensure the peer receives the post of the read-only manager, while the
+//!    read-only manager stores neither the post of the peer nor its request.
+//!
+//! 4) Write a post to a persistent store, then reopen the store read-only
+//!    and ensure it serves the post but refuses inserts.
+
+use std::time::Duration;
+
+use async_std::task;
+use cable::{error::CableError, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, ManagerOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+// Ensure the given result is the error raised for writes to a read-only
+// store.
+fn assert_read_only<T: std::fmt::Debug>(result: Result<T, Error>) {
+    let err = result.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<CableError>().map(CableError::code),
+        Some(301)
+    );
+}
+
+#[async_std::test]
+async fn serve_history_read_only() -> Result<(), Error> {
+    let store = MemoryStore::default();
+    let moth = CableManager::new(store.clone())
+        .post_text("entomology", "moth")
+        .await?;
+
+    let options = ManagerOptions {
+        read_only: true,
+        ..Default::default()
+    };
+    let mut mirror = CableManager::with_options(store, options);
+    assert!(mirror.is_read_only());
+    assert_read_only(mirror.post_text("entomology", "wasp").await);
+
+    let mut reader = CableManager::new(MemoryStore::default());
+    assert!(!reader.is_read_only());
+    let wasp = reader.post_text("entomology", "wasp").await?;
+
+    let network = Network::with_managers(vec![mirror.clone(), reader], Topology::Line);
+    let (mut mirror_peer, mut reader_peer) = (network.peer(0), network.peer(1));
+    let opts = ChannelOptions::new("entomology", 0, 0, 0);
+    let _mirror_live = mirror_peer.open_channel(&opts).await?;
+    let _reader_live = reader_peer.open_channel(&opts).await?;
+
+    let reader = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { reader.has_post(&moth).await }).await);
+    assert!(mirror.store.get_outbound_requests().await.is_empty());
+
+    // Give the post of the reader time to arrive, had it been stored.
+    task::sleep(Duration::from_millis(200)).await;
+    assert!(!mirror.has_post(&wasp).await);
+
+    Ok(())
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn open_sled_store_read_only() -> Result<(), Error> {
+    use cable_core::SledStore;
+
+    let dir = tempfile::tempdir()?;
+    let moth = CableManager::new(SledStore::open(dir.path())?)
+        .post_text("entomology", "moth")
+        .await?;
+
+    // Wait for the file lock of the dropped store to be released by its
+    // background flusher.
+    let mut store = None;
+    for _ in 0..100 {
+        match SledStore::open_read_only(dir.path()) {
+            Err(err) if err.to_string().contains("could not acquire lock") => {
+                task::sleep(Duration::from_millis(20)).await
+            }
+            result => {
+                store = Some(result?);
+                break;
+            }
+        }
+    }
+    let mut store = store.expect("store is unlocked");
+
+    assert!(store.is_read_only());
+    assert!(store.get_post_payload(&moth).await.is_some());
+    let post = CableManager::new(store.clone())
+        .get_post(&moth)
+        .await?
+        .unwrap();
+    assert_read_only(store.insert_post(&post).await);
+
+    let mut cable = CableManager::new(store);
+    assert!(cable.is_read_only());
+    assert_read_only(cable.post_text("entomology", "wasp").await);
+
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn open_sqlite_store_read_only() -> Result<(), Error> {
+    use cable_core::SqliteStore;
+
+    let dir = tempfile::tempdir()?;
+    let db_path = dir.path().join("cable.sqlite");
+    assert!(SqliteStore::open_read_only(&db_path).is_err());
+
+    let moth = CableManager::new(SqliteStore::open(&db_path)?)
+        .post_text("entomology", "moth")
+        .await?;
+
+    let mut store = SqliteStore::open_read_only(&db_path)?;
+    assert!(store.is_read_only());
+    assert!(store.get_post_payload(&moth).await.is_some());
+    let post = CableManager::new(store.clone())
+        .get_post(&moth)
+        .await?
+        .unwrap();
+    assert_read_only(store.insert_post(&post).await);
+
+    let mut cable = CableManager::new(store);
+    assert!(cable.is_read_only());
+    assert_read_only(cable.post_text("entomology", "wasp").await);
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+//! Test automatic reconnection to a supervised peer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open a channel, creating live local requests while no peers are known.
+//!
+//! 2) Supervise the address of a raw TCP listener and accept the connection.
+//!
+//! 3) Ensure the live channel requests are sent, then close the connection.
+//!
+//! 4) Accept the reconnection and ensure the live requests are re-issued.
+//!
+//! 5) Remove and re-add a peer while connected to it; ensure only the
+//!    connection of the re-added peer is re-established once both close.
+
+use std::time::Duration;
+
+use async_std::{future, net::TcpListener};
+use cable::{
+    constants::{CHANNEL_STATE_REQUEST, CHANNEL_TIME_RANGE_REQUEST},
+    ChannelOptions, Error, Message,
+};
+use desert::FromBytes;
+use futures::AsyncReadExt;
+
+use cable_core::{CableManager, MemoryStore, Supervisor, SupervisorOptions};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn options() -> SupervisorOptions {
+    SupervisorOptions {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(100),
+    }
+}
+
+// Read a single message from the stream and return its type.
+async fn read_message_type(stream: &mut async_std::net::TcpStream) -> Result<u64, Error> {
+    let mut res_bytes = [0u8; 1024];
+    let _n = future::timeout(Duration::from_secs(5), stream.read(&mut res_bytes)).await??;
+    let (_bytes_len, msg) = Message::from_bytes(&res_bytes)?;
+
+    Ok(msg.message_type())
+}
+
+#[async_std::test]
+async fn reconnect_and_reissue_requests() -> Result<(), Error> {
+    let mut cable = CableManager::new(MemoryStore::default());
+
+    // Open a channel before any peers are connected.
+    let opts = ChannelOptions::new("reconnect", 0, 0, 10);
+    let mut client = cable.clone();
+    let _post_stream = client.open_channel(&opts).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let supervisor = Supervisor::new(cable.clone(), options());
+    assert!(supervisor.add_peer(addr).await);
+    assert!(!supervisor.add_peer(addr).await);
+
+    let live_request_types = [CHANNEL_TIME_RANGE_REQUEST, CHANNEL_STATE_REQUEST];
+
+    // Accept the first connection and ensure a live request is received.
+    let (mut stream, _) = future::timeout(Duration::from_secs(5), listener.accept()).await??;
+    assert!(live_request_types.contains(&read_message_type(&mut stream).await?));
+
+    // Close the connection from the listening side.
+    stream.shutdown(std::net::Shutdown::Both)?;
+    drop(stream);
+
+    // Accept the reconnection and ensure the live request is re-issued.
+    let (mut stream, _) = future::timeout(Duration::from_secs(5), listener.accept()).await??;
+    assert!(live_request_types.contains(&read_message_type(&mut stream).await?));
+
+    // Stop supervising the peer.
+    assert!(supervisor.remove_peer(&addr).await);
+    assert!(supervisor.known_peers().await.is_empty());
+
+    // The manager is still usable after the reconnection.
+    cable.post_text("reconnect", "still here").await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn readd_peer_while_connected() -> Result<(), Error> {
+    let cable = CableManager::new(MemoryStore::default());
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let supervisor = Supervisor::new(cable, options());
+    assert!(supervisor.add_peer(addr).await);
+    let (first, _) = future::timeout(TIMEOUT, listener.accept()).await??;
+
+    // Remove and re-add the peer while the first connection is open.
+    assert!(supervisor.remove_peer(&addr).await);
+    assert!(supervisor.add_peer(addr).await);
+    let (second, _) = future::timeout(TIMEOUT, listener.accept()).await??;
+
+    // The first connection is not re-established once it closes.
+    first.shutdown(std::net::Shutdown::Both)?;
+    drop(first);
+    assert!(
+        future::timeout(Duration::from_millis(500), listener.accept())
+            .await
+            .is_err()
+    );
+
+    // The connection of the re-added peer is.
+    second.shutdown(std::net::Shutdown::Both)?;
+    drop(second);
+    future::timeout(TIMEOUT, listener.accept()).await??;
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Test the relaying of responses to forwarded requests.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of three peers connected in a line and wait until all
+//! connections are established.
+//!
+//! 2) Publish a post on the last peer.
+//!
+//! 3) Request the channel list from the first peer and ensure the channel of
+//! the last peer is returned through the second peer.
+//!
+//! 4) Open the channel on the first peer and ensure the post of the last peer
+//! is fetched through the second peer.
+
+use std::time::Duration;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Return the text of all posts in the given channel of the given peer.
+async fn texts(peer: &CableManager<MemoryStore>, channel: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut posts = peer
+        .store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+
+    texts
+}
+
+#[async_std::test]
+async fn relay_responses_to_origin() -> Result<(), Error> {
+    let network = Network::new(3, Topology::Line);
+    let channel = "entomology";
+
+    for (peer, connections) in [(0, 1), (1, 2), (2, 1)] {
+        let peer = network.peer(peer);
+        assert!(
+            eventually(TIMEOUT, || async {
+                peer.get_peer_ids().await.len() == connections
+            })
+            .await
+        );
+    }
+
+    let mut last = network.peer(2);
+    last.post_join(channel).await?;
+    last.post_text(channel, "moths").await?;
+
+    let first = network.peer(0);
+    first.request_channel_list(0, 0).await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            first.store.get_channels().await == Some(vec![channel.to_string()])
+        })
+        .await
+    );
+
+    let mut reader = network.peer(0);
+    let _live = reader
+        .open_channel(&ChannelOptions::new(channel, 0, 0, 0))
+        .await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            texts(&first, channel).await == vec!["moths".to_string()]
+        })
+        .await
+    );
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Test the pruning of posts according to a retention policy.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert join and topic posts, followed by five text posts.
+//!
+//! 2) Prune the store with a maximum of three posts per channel.
+//!
+//! 3) Ensure the oldest text posts are pruned and tombstoned.
+//!
+//! 4) Ensure the channel state posts are retained.
+//!
+//! 5) Prune the store with a maximum age and ensure older posts are pruned.
+//!
+//! 6) Publish posts from a manager with a mock clock and ensure the manager
+//! prunes them according to its policy and the time of the clock.
+
+use std::{convert::TryInto, sync::Arc, time::Duration};
+
+use cable::{Error, Post};
+use desert::FromBytes;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{CableManager, ManagerOptions, MemoryStore, MockClock, RetentionPolicy, Store};
+
+#[async_std::test]
+async fn prune_posts_outside_retention_policy() -> Result<(), Error> {
+    let mut store = MemoryStore::default();
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut join = Post::join(pk, vec![], 1_000, channel.clone());
+    join.sign(&sk)?;
+    let join_hash = store.insert_post(&join).await?;
+
+    let mut topic = Post::topic(pk, vec![], 1_500, channel.clone(), "moths".into());
+    topic.sign(&sk)?;
+    let topic_hash = store.insert_post(&topic).await?;
+
+    let mut text_hashes = Vec::new();
+    for i in 0..5 {
+        let timestamp = 2_000 + i * 1_000;
+        let mut text = Post::text(pk, vec![], timestamp, channel.clone(), format!("{}", i));
+        text.sign(&sk)?;
+        text_hashes.push(store.insert_post(&text).await?);
+    }
+
+    let policy = RetentionPolicy {
+        max_posts_per_channel: Some(3),
+        ..RetentionPolicy::default()
+    };
+    let pruned = store.prune(&policy, 10_000).await?;
+    assert_eq!(pruned, vec![text_hashes[1], text_hashes[0]]);
+
+    for hash in &pruned {
+        assert!(store.get_post_payload(hash).await.is_none());
+        assert!(store.is_tombstone(hash).await);
+    }
+    assert!(store.want(&pruned).await.is_empty());
+    assert!(store.get_post_payload(&join_hash).await.is_some());
+    assert!(store.get_post_payload(&topic_hash).await.is_some());
+
+    // Posts more than 2.5 seconds older than the time of pruning are pruned.
+    let policy = RetentionPolicy {
+        max_age: Some(Duration::from_millis(2_500)),
+        ..RetentionPolicy::default()
+    };
+    let pruned = store.prune(&policy, 7_000).await?;
+    assert_eq!(pruned, vec![text_hashes[2]]);
+    assert!(store.get_post_payload(&topic_hash).await.is_some());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn prune_with_manager_policy() -> Result<(), Error> {
+    let clock = MockClock::new(1_000);
+    let options = ManagerOptions {
+        retention: Some(RetentionPolicy {
+            max_posts_per_channel: Some(2),
+            max_age: Some(Duration::from_secs(60)),
+            ..RetentionPolicy::default()
+        }),
+        clock: Arc::new(clock.clone()),
+        ..ManagerOptions::default()
+    };
+    let mut cable = CableManager::with_options(MemoryStore::default(), options);
+    let channel = "entomology".to_string();
+
+    let first_hash = cable.post_text(&channel, "first").await?;
+    clock.advance(Duration::from_millis(5));
+    let second_hash = cable.post_text(&channel, "second").await?;
+    clock.advance(Duration::from_secs(30));
+    let third_hash = cable.post_text(&channel, "third").await?;
+
+    let third = cable.store.get_post_payload(&third_hash).await.unwrap();
+    let (_, third) = Post::from_bytes(&third)?;
+    assert_eq!(third.get_timestamp(), 31_005);
+
+    // The oldest post exceeds the maximum number of posts.
+    assert_eq!(cable.prune().await?, vec![first_hash]);
+
+    // The second post exceeds the maximum age one minute after publication.
+    clock.set(61_005);
+    assert!(cable.prune().await?.is_empty());
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(cable.prune().await?, vec![second_hash]);
+    assert!(cable.store.get_post_payload(&third_hash).await.is_some());
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Test requesting a post again from another peer when the peer from which it
+//! was requested disconnects.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a post on one manager and connect two raw peers to another.
+//!
+//! 2) Advertise the hash of the post from the first raw peer, ensuring the
+//! post is requested from it.
+//!
+//! 3) Advertise the hash from the second raw peer, ensuring the post is not
+//! requested again while the first request is outstanding.
+//!
+//! 4) Disconnect the first raw peer and ensure the post is requested from the
+//! second.
+//!
+//! 5) Respond with the post from the second raw peer and ensure it is stored.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, RequestBody},
+    Error, Hash, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, MemoryStream},
+    CableManager, ManagerOptions, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read messages from the given stream until one satisfies the given
+/// predicate, returning it.
+async fn read_until(
+    stream: MemoryStream,
+    mut found: impl FnMut(&Message) -> bool,
+) -> Result<Message, Error> {
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(stream, options);
+
+    future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if found(&msg) {
+                return Ok(msg);
+            }
+        }
+
+        Err("stream closed".into())
+    })
+    .await?
+}
+
+/// Return `true` if the given message is a post request for the given hash.
+fn requests_post(msg: &Message, hash: &Hash) -> bool {
+    matches!(
+        &msg.body,
+        MessageBody::Request {
+            body: RequestBody::Post { hashes },
+            ..
+        } if hashes.contains(hash)
+    )
+}
+
+/// Connect a raw peer to the given manager.
+fn connect(cable: &CableManager<MemoryStore>) -> MemoryStream {
+    let (stream, peer) = duplex();
+    let cable = cable.clone();
+    task::spawn(async move { cable.listen(stream).await });
+
+    peer
+}
+
+/// Send a message from a raw peer.
+async fn write(peer: &mut MemoryStream, msg: &Message) -> Result<(), Error> {
+    peer.write_all(&msg.to_bytes()?).await?;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn request_post_from_alternate_peer() -> Result<(), Error> {
+    let mut author = CableManager::new(MemoryStore::default());
+    let hash = author.post_text("entomology", "moths").await?;
+    let payload = author.store.get_post_payload(&hash).await.unwrap();
+
+    // The raw peers advertise the hash without having been asked.
+    let options = ManagerOptions {
+        accept_unknown_responses: true,
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+    let mut first = connect(&cable);
+    let mut second = connect(&cable);
+
+    write(
+        &mut first,
+        &Message::hash_response(NO_CIRCUIT, [0, 0, 0, 1], vec![hash]),
+    )
+    .await?;
+    read_until(first.clone(), |msg| requests_post(msg, &hash)).await?;
+
+    // Follow the advertisement with a channel list request, so that the
+    // advertisement has been handled once the response is received.
+    write(
+        &mut second,
+        &Message::hash_response(NO_CIRCUIT, [0, 0, 0, 2], vec![hash]),
+    )
+    .await?;
+    let sync_req_id: ReqId = [0, 0, 0, 3];
+    write(
+        &mut second,
+        &Message::channel_list_request(NO_CIRCUIT, sync_req_id, 0, 0, 0),
+    )
+    .await?;
+    let msg = read_until(second.clone(), |msg| {
+        requests_post(msg, &hash) || msg.header.req_id == sync_req_id
+    })
+    .await?;
+    assert!(!requests_post(&msg, &hash));
+
+    first.close();
+
+    let request = read_until(second.clone(), |msg| requests_post(msg, &hash)).await?;
+    let response = Message::post_response(NO_CIRCUIT, request.header.req_id, vec![payload]);
+    write(&mut second, &response).await?;
+
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable.store.get_post_payload(&hash).await.is_some()
+        })
+        .await
+    );
+
+    Ok(())
+}
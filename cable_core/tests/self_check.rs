@@ -0,0 +1,84 @@
+//! Test the checking of outgoing messages against the cable specification.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two peers which fail on any violation of the specification.
+//!
+//! 2) Publish posts on the first peer, open the channel on the second and
+//! request the channel list, ensuring the posts and channels are exchanged.
+//!
+//! 3) Send a request with an excessive TTL from the first peer and ensure its
+//! connection is closed with an error describing the violation.
+
+use std::time::Duration;
+
+use async_std::{stream::StreamExt, task};
+use cable::{constants::NO_CIRCUIT, ChannelOptions, Error, Message};
+
+use cable_core::{
+    testing::{duplex, eventually},
+    CableManager, ManagerOptions, MemoryStore, SelfCheck, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn manager() -> CableManager<MemoryStore> {
+    let options = ManagerOptions {
+        self_check: SelfCheck::Fail,
+        ..ManagerOptions::default()
+    };
+
+    CableManager::with_options(MemoryStore::default(), options)
+}
+
+#[async_std::test]
+async fn compliant_exchange_passes_self_check() -> Result<(), Error> {
+    let mut first = manager();
+    let second = manager();
+    let channel = "default";
+
+    let (stream_a, stream_b) = duplex();
+    let first_listener = {
+        let first = first.clone();
+        task::spawn(async move { first.listen(stream_a).await })
+    };
+    let second_listener = {
+        let second = second.clone();
+        task::spawn(async move { second.listen(stream_b).await })
+    };
+
+    first.post_join(channel).await?;
+    first.post_text(channel, "hello").await?;
+
+    let mut reader = second.clone();
+    let _live = reader
+        .open_channel(&ChannelOptions::new(channel, 0, 0, 10))
+        .await?;
+    second.request_channel_list(0, 10).await?;
+
+    assert!(
+        eventually(TIMEOUT, || async {
+            let posts = second
+                .store
+                .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+                .await
+                .count()
+                .await;
+            posts == 1 && second.store.get_channels().await.is_some()
+        })
+        .await
+    );
+
+    // A request with a TTL above 16 violates the specification.
+    let peer_id = first.get_peer_ids().await[0];
+    let request = Message::channel_list_request(NO_CIRCUIT, [1, 2, 3, 4], 17, 0, 0);
+    first.send(peer_id, &request).await?;
+
+    let err = first_listener.await.unwrap_err();
+    assert!(err.to_string().contains("violates the specification"));
+
+    // The second peer sees the connection close without error.
+    assert!(second_listener.await.is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Test signing the posts of the local peer with an external signer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a manager with a signer which holds its keypair outside the
+//!    manager and counts the signatures it makes.
+//!
+//! 2) Publish a post and ensure it is authored by the public key of the
+//!    signer, signed by the signer and verifiable.
+//!
+//! 3) Ensure the secret key of the local peer is unavailable, and that a
+//!    post authored by another public key is not signed.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use async_trait::async_trait;
+use cable::{
+    crypto::{self, PublicKey, Signature},
+    error::CableError,
+    signer::{KeypairSigner, Signer},
+    Error, Post,
+};
+use desert::ToBytes;
+
+use cable_core::{CableManager, MemoryStore};
+
+// A signer standing in for a hardware security module.
+#[derive(Debug)]
+struct CountingSigner {
+    inner: KeypairSigner,
+    signatures: AtomicUsize,
+}
+
+#[async_trait]
+impl Signer for CountingSigner {
+    fn public_key(&self) -> PublicKey {
+        self.inner.public_key()
+    }
+
+    async fn sign(&self, buf: &[u8]) -> Result<Signature, Error> {
+        self.signatures.fetch_add(1, Ordering::SeqCst);
+        self.inner.sign(buf).await
+    }
+}
+
+#[async_std::test]
+async fn external_signer() -> Result<(), Error> {
+    let keypair = crypto::generate_keypair();
+    let signer = Arc::new(CountingSigner {
+        inner: KeypairSigner::from(keypair),
+        signatures: AtomicUsize::new(0),
+    });
+    let mut cable = CableManager::builder(MemoryStore::default())
+        .signer(signer.clone())
+        .build();
+
+    assert_eq!(cable.get_public_key().await?, keypair.0);
+    let moth = cable.post_text("entomology", "moth").await?;
+    let post = cable.get_post(&moth).await?.unwrap();
+    assert_eq!(post.get_public_key(), keypair.0);
+    assert!(Post::verify(&post.to_bytes()?));
+    assert_eq!(signer.signatures.load(Ordering::SeqCst), 1);
+
+    assert!(cable.get_secret_key().await.is_err());
+
+    let (other, _other_secret_key) = crypto::generate_keypair();
+    let post = Post::text(other, vec![], 0, "entomology".into(), "wasp".into());
+    let err = cable.publish(post).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<CableError>().map(CableError::code),
+        Some(113)
+    );
+    assert_eq!(signer.signatures.load(Ordering::SeqCst), 1);
+
+    Ok(())
+}
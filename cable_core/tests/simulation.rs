@@ -0,0 +1,158 @@
+//! Test syncing posts over a simulated network with latency, loss and
+//! partitions.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a post on one peer and open the channel on another over a link
+//! with latency, ensuring the post is synced no sooner than two round trips.
+//!
+//! 2) Cut a peer off from a line of three peers and open the channel on it,
+//! ensuring the post is only synced once the partition is healed.
+//!
+//! 3) Sync over lossy links twice with the same seed, ensuring both runs lose
+//! the same frames, and ensure nothing is synced over a link losing every
+//! frame.
+
+use std::time::Duration;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{
+    simulation::{LinkConditions, Simulation, SimulationStats},
+    testing::Topology,
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Return the text of all posts in the given channel of the given peer.
+async fn texts(peer: &CableManager<MemoryStore>, channel: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut posts = peer
+        .store
+        .get_posts(&ChannelOptions::new(channel, 0, 0, 0))
+        .await;
+    while let Some(Ok(post)) = posts.next().await {
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+
+    texts
+}
+
+/// Wait until every peer of the simulation is connected to its neighbours.
+async fn connected(sim: &mut Simulation<MemoryStore>, peer_counts: &[usize]) -> bool {
+    let peers = sim.peers().to_vec();
+    sim.run_until(TIMEOUT, || async {
+        for (peer, count) in peers.iter().zip(peer_counts) {
+            if peer.get_peer_ids().await.len() != *count {
+                return false;
+            }
+        }
+        true
+    })
+    .await
+}
+
+#[async_std::test]
+async fn sync_with_latency() -> Result<(), Error> {
+    let mut sim = Simulation::new(2, Topology::Line, 1).with_conditions(LinkConditions {
+        latency: Duration::from_millis(100),
+        ..Default::default()
+    });
+    assert!(connected(&mut sim, &[1, 1]).await);
+
+    let (mut first, second) = (sim.peer(0), sim.peer(1));
+    first.post_text("entomology", "a luna moth!").await?;
+    let mut reader = sim.peer(1);
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+
+    // The hashes of the channel and then the post itself are requested.
+    assert!(
+        sim.run_until(TIMEOUT, || async {
+            texts(&second, "entomology").await.len() == 1
+        })
+        .await
+    );
+    assert!(sim.elapsed() >= Duration::from_millis(400));
+    assert_eq!(sim.stats().lost, 0);
+
+    sim.shutdown().await;
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn sync_after_partition_heals() -> Result<(), Error> {
+    let mut sim = Simulation::new(3, Topology::Line, 2).with_conditions(LinkConditions {
+        latency: Duration::from_millis(20),
+        jitter: Duration::from_millis(10),
+        ..Default::default()
+    });
+    assert!(connected(&mut sim, &[1, 2, 1]).await);
+
+    let (mut first, third) = (sim.peer(0), sim.peer(2));
+    first.post_text("entomology", "a luna moth!").await?;
+
+    sim.partition(&[2]);
+    let mut reader = sim.peer(2);
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    sim.run_for(Duration::from_secs(1)).await;
+    assert!(texts(&third, "entomology").await.is_empty());
+
+    // The requests held back by the partition are forwarded to the first
+    // peer once it heals.
+    sim.heal();
+    assert!(
+        sim.run_until(TIMEOUT, || async {
+            texts(&third, "entomology").await.len() == 1
+        })
+        .await
+    );
+
+    sim.shutdown().await;
+
+    Ok(())
+}
+
+/// Sync a post over lossy links, returning the stats of the simulation.
+async fn sync_with_loss(seed: u64, loss: f64) -> Result<(SimulationStats, usize), Error> {
+    let mut sim = Simulation::new(2, Topology::Line, seed).with_conditions(LinkConditions {
+        latency: Duration::from_millis(50),
+        jitter: Duration::from_millis(50),
+        loss,
+    });
+    assert!(connected(&mut sim, &[1, 1]).await);
+
+    let (mut first, second) = (sim.peer(0), sim.peer(1));
+    first.post_text("entomology", "a luna moth!").await?;
+    let mut reader = sim.peer(1);
+    let _live = reader
+        .open_channel(&ChannelOptions::new("entomology", 0, 0, 0))
+        .await?;
+    sim.run_for(Duration::from_secs(1)).await;
+
+    let synced = texts(&second, "entomology").await.len();
+    sim.shutdown().await;
+
+    Ok((sim.stats(), synced))
+}
+
+#[async_std::test]
+async fn reproduce_lossy_runs() -> Result<(), Error> {
+    let (stats, synced) = sync_with_loss(7, 0.5).await?;
+    assert_eq!(sync_with_loss(7, 0.5).await?, (stats, synced));
+
+    let (stats, synced) = sync_with_loss(7, 1.0).await?;
+    assert_eq!(stats.delivered, 0);
+    assert!(stats.lost > 0);
+    assert_eq!(synced, 0);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! Test at-rest encryption of the sled store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open an encrypted sled store and publish a text post and a name.
+//!
+//! 2) Ensure the database files contain neither the text nor the secret key.
+//!
+//! 3) Ensure the store cannot be opened without the correct passphrase.
+//!
+//! 4) Reopen the store with the passphrase and ensure the data is intact.
+
+#![cfg(feature = "sled")]
+
+use std::{fs, path::Path, time::Duration};
+
+use async_std::{stream::StreamExt, task};
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{CableManager, SledStore, Store};
+
+// Open the sled store at the given path, waiting for the file lock of a
+// previously dropped store to be released by its background flusher.
+async fn open_store(path: &Path, passphrase: Option<&str>) -> Result<SledStore, Error> {
+    for _ in 0..100 {
+        let result = match passphrase {
+            Some(passphrase) => SledStore::open_encrypted(path, passphrase),
+            None => SledStore::open(path),
+        };
+        match result {
+            Err(err) if err.to_string().contains("could not acquire lock") => {
+                task::sleep(Duration::from_millis(20)).await
+            }
+            result => return result,
+        }
+    }
+
+    SledStore::open(path)
+}
+
+// Return true if any file below the given directory contains the needle.
+fn dir_contains(dir: &std::path::Path, needle: &[u8]) -> Result<bool, Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let found = if path.is_dir() {
+            dir_contains(&path, needle)?
+        } else {
+            fs::read(&path)?
+                .windows(needle.len())
+                .any(|window| window == needle)
+        };
+        if found {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+#[async_std::test]
+async fn encrypt_store_at_rest() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let channel = "entomology".to_string();
+    let passphrase = "correct horse battery staple";
+
+    let keypair = {
+        let mut cable = CableManager::new(open_store(dir.path(), Some(passphrase)).await?);
+        cable.post_join(&channel).await?;
+        cable.post_text(&channel, "a secret about moths").await?;
+        cable.post_info_name("glyph").await?;
+
+        cable.store.flush().await?;
+        cable.store.get_keypair().await.unwrap()
+    };
+    let (public_key, secret_key) = keypair;
+
+    assert!(!dir_contains(dir.path(), b"a secret about moths")?);
+    assert!(!dir_contains(dir.path(), b"entomology")?);
+    assert!(!dir_contains(dir.path(), &secret_key[..32])?);
+
+    assert!(open_store(dir.path(), None).await.is_err());
+    assert!(open_store(dir.path(), Some("incorrect horse"))
+        .await
+        .is_err());
+
+    let store = open_store(dir.path(), Some(passphrase)).await?;
+    assert_eq!(store.get_keypair().await, Some(keypair));
+    assert_eq!(store.get_channels().await, Some(vec![channel.clone()]));
+    assert!(store.is_channel_member(&channel, &public_key).await);
+    assert_eq!(
+        store
+            .get_peer_name_and_hash(&public_key)
+            .await
+            .map(|(name, _hash)| name),
+        Some("glyph".to_string())
+    );
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let mut texts = Vec::new();
+    let mut stream = store.get_posts(&opts).await;
+    while let Some(post) = stream.next().await {
+        if let PostBody::Text { text, .. } = post?.body {
+            texts.push(text);
+        }
+    }
+    assert_eq!(texts, vec!["a secret about moths".to_string()]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reject_passphrase_for_plaintext_store() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+
+    open_store(dir.path(), None).await?.flush().await?;
+    assert!(open_store(dir.path(), Some("passphrase")).await.is_err());
+
+    Ok(())
+}
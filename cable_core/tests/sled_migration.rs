@@ -0,0 +1,112 @@
+//! Test the schema migration of the sled store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Write a keypair, a channel, a member and a post in the version 0 layout,
+//!    holding the post in the single posts tree shared by all channels.
+//!
+//! 2) Open the database as a store, applying the migrations.
+//!
+//! 3) Ensure the store is at the latest schema version and the data is intact.
+//!
+//! 4) Ensure the keypair is listed among the identities of the store and the
+//!    post is indexed as the channel head and by its author and has been
+//!    moved to the tree of its channel.
+//!
+//! 5) Ensure a store with an unknown future schema version is rejected.
+
+#![cfg(feature = "sled")]
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{ChannelOptions, Error, Post};
+use desert::ToBytes;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{AuthorOptions, SledStore, Store};
+
+#[async_std::test]
+async fn migrate_unversioned_store() -> Result<(), Error> {
+    let db = sled::Config::new().temporary(true).open()?;
+    let channel = "entomology".to_string();
+
+    let (pk, sk) = sign::gen_keypair();
+    let public_key: [u8; 32] = pk.as_ref().try_into()?;
+    let mut keypair = pk.as_ref().to_vec();
+    keypair.extend_from_slice(sk.as_ref());
+
+    // Version 0 keyed channels by name and stored members as keys only.
+    let mut member_key = (channel.len() as u32).to_be_bytes().to_vec();
+    member_key.extend_from_slice(channel.as_bytes());
+    member_key.extend_from_slice(&public_key);
+    db.open_tree("meta")?.insert("keypair", keypair)?;
+    db.open_tree("channels")?.insert(channel.as_bytes(), &[])?;
+    db.open_tree("channel_members")?.insert(member_key, &[])?;
+
+    let mut post = Post::text(public_key, vec![], 100, channel.clone(), "moth".into());
+    post.sign(sk.as_ref().try_into()?)?;
+    let hash = post.hash()?;
+    db.open_tree("post_payloads")?
+        .insert(hash, post.to_bytes()?)?;
+
+    // Posts were keyed by channel, timestamp and hash in a single tree.
+    let mut post_key = vec![1];
+    post_key.extend_from_slice(&(channel.len() as u32).to_be_bytes());
+    post_key.extend_from_slice(channel.as_bytes());
+    post_key.extend_from_slice(&100u64.to_be_bytes());
+    post_key.extend_from_slice(&hash);
+    db.open_tree("posts")?.insert(post_key, post.to_bytes()?)?;
+
+    let store = SledStore::from_db(db.clone())?;
+    assert_eq!(store.schema_version()?, 5);
+    assert_eq!(store.get_channels().await, Some(vec![channel.clone()]));
+    assert_eq!(
+        store.get_channel_members(&channel).await,
+        Some(vec![public_key])
+    );
+    assert!(store.is_channel_member(&channel, &public_key).await);
+    assert_eq!(store.list_identities().await, vec![public_key]);
+    assert_eq!(store.get_latest_hashes(&channel).await, Some(vec![hash]));
+    assert_eq!(
+        store
+            .get_posts_by_author(&public_key, &AuthorOptions::default())
+            .await?
+            .posts
+            .iter()
+            .map(Post::hash)
+            .collect::<Result<Vec<_>, _>>()?,
+        vec![hash]
+    );
+    let posts: Vec<Post> = store
+        .get_posts(&ChannelOptions::new(channel.clone(), 0, 0, 0))
+        .await
+        .collect::<Result<_, _>>()
+        .await?;
+    assert_eq!(
+        posts
+            .iter()
+            .map(Post::hash)
+            .collect::<Result<Vec<_>, _>>()?,
+        vec![hash]
+    );
+    assert!(!db.tree_names().iter().any(|name| &name[..] == b"posts"));
+
+    // Reopening a migrated store leaves it unchanged.
+    let store = SledStore::from_db(db.clone())?;
+    assert_eq!(store.get_channels().await, Some(vec![channel.clone()]));
+
+    db.open_tree("meta")?
+        .insert("schema_version", &99u32.to_be_bytes())?;
+    assert!(SledStore::from_db(db).is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn create_store_at_latest_version() -> Result<(), Error> {
+    let store = SledStore::temporary()?;
+    assert_eq!(store.schema_version()?, 5);
+
+    Ok(())
+}
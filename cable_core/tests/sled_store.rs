@@ -0,0 +1,120 @@
+//! Test persistence of the sled store across restarts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open a sled store in a temporary directory and publish several posts.
+//!
+//! 2) Delete one of the text posts.
+//!
+//! 3) Close and reopen the store, ensuring the keypair is unchanged.
+//!
+//! 4) Ensure the posts, channel indexes and peer name survived the restart.
+
+#![cfg(feature = "sled")]
+
+use std::{path::Path, time::Duration};
+
+use async_std::{stream::StreamExt, task};
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{CableManager, SledStore, Store};
+
+// Open the sled store at the given path, waiting for the file lock of a
+// previously dropped store to be released by its background flusher.
+async fn open_store(path: &Path) -> Result<SledStore, Error> {
+    for _ in 0..100 {
+        match SledStore::open(path) {
+            Err(err) if err.to_string().contains("could not acquire lock") => {
+                task::sleep(Duration::from_millis(20)).await
+            }
+            result => return result,
+        }
+    }
+
+    SledStore::open(path)
+}
+
+#[async_std::test]
+async fn persist_posts_across_restart() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let channel = "entomology".to_string();
+
+    let (keypair, first_hash, deleted_hash, topic_hash) = {
+        let mut cable = CableManager::new(open_store(dir.path()).await?);
+        let keypair = cable.store.get_keypair().await;
+
+        // Sleep briefly between posts so that each has a distinct timestamp.
+        cable.post_join(&channel).await?;
+        let first_hash = cable.post_text(&channel, "first").await?;
+        task::sleep(Duration::from_millis(5)).await;
+        let deleted_hash = cable.post_text(&channel, "oops").await?;
+        task::sleep(Duration::from_millis(5)).await;
+        let topic_hash = cable.post_topic(&channel, "insects").await?;
+        cable.post_info_name("glyph").await?;
+
+        cable.post_delete(vec![deleted_hash]).await?;
+
+        cable.store.flush().await?;
+
+        (keypair, first_hash, deleted_hash, topic_hash)
+    };
+
+    // Reopen the store from disk.
+    let mut store = open_store(dir.path()).await?;
+    assert_eq!(store.get_keypair().await, keypair);
+    let (public_key, _secret_key) = keypair.unwrap();
+
+    // Only the remaining channel posts and the info post are returned.
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let mut posts = Vec::new();
+    let mut stream = store.get_posts(&opts).await;
+    while let Some(post) = stream.next().await {
+        posts.push(post?);
+    }
+    drop(stream);
+
+    assert_eq!(posts.len(), 3);
+    assert!(matches!(&posts[0].body, PostBody::Text { text, .. } if text == "first"));
+    assert!(matches!(&posts[1].body, PostBody::Topic { topic, .. } if topic == "insects"));
+    assert!(matches!(&posts[2].body, PostBody::Info { .. }));
+
+    let mut hashes = Vec::new();
+    let mut stream = store.get_post_hashes(&opts).await;
+    while let Some(hash) = stream.next().await {
+        hashes.push(hash?);
+    }
+    drop(stream);
+    assert_eq!(hashes, vec![first_hash, topic_hash]);
+
+    assert!(store.get_post_payload(&deleted_hash).await.is_none());
+    // The deleted post is tombstoned and is no longer wanted.
+    assert!(store.is_tombstone(&deleted_hash).await);
+    assert!(store.want(&[first_hash, deleted_hash]).await.is_empty());
+
+    assert_eq!(store.get_channels().await, Some(vec![channel.clone()]));
+    assert_eq!(
+        store.get_latest_hashes(&channel).await,
+        Some(vec![topic_hash])
+    );
+    assert!(store.is_channel_member(&channel, &public_key).await);
+    assert_eq!(
+        store.get_channel_topic_and_hash(&channel).await,
+        Some(("insects".to_string(), topic_hash))
+    );
+    assert_eq!(
+        store
+            .get_peer_name_and_hash(&public_key)
+            .await
+            .map(|(name, _hash)| name),
+        Some("glyph".to_string())
+    );
+    assert_eq!(
+        store.get_delete_hashes(&public_key).await.map(|h| h.len()),
+        Some(1)
+    );
+
+    store.compact().await?;
+    assert!(store.get_post_payload(&first_hash).await.is_some());
+
+    Ok(())
+}
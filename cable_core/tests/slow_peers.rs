@@ -0,0 +1,137 @@
+//! Test the handling of peers which stop reading.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a peer which never reads to a cable manager with a short write
+//! timeout and send a post request, ensuring the connection is closed with a
+//! timeout error once the response cannot be written.
+//!
+//! 2) Connect a peer which never reads to a cable manager which drops at
+//! most five messages for a slow peer, and broadcast enough messages to
+//! fill its queue. Ensure the broadcasts complete and the peer is
+//! disconnected.
+
+use std::{
+    error::Error as _,
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_std::{future, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    error::{CableError, ErrorCategory},
+    Error, Message,
+};
+use desert::ToBytes;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use cable_core::{
+    testing::{duplex, MemoryStream},
+    CableManager, ManagerOptions, MemoryStore, SlowPeerPolicy,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A stream which reads from the given in-memory stream but never completes
+/// a write, as if the remote peer had stopped reading.
+#[derive(Clone)]
+struct StalledStream(MemoryStream);
+
+impl AsyncRead for StalledStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for StalledStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[async_std::test]
+async fn close_on_write_timeout() -> Result<(), Error> {
+    let options = ManagerOptions {
+        write_timeout: Some(Duration::from_millis(100)),
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, mut peer) = duplex();
+    let listener = {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(StalledStream(stream)).await })
+    };
+
+    let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+    peer.write_all(&request.to_bytes()?).await?;
+
+    let err = future::timeout(TIMEOUT, listener).await?.unwrap_err();
+    let cable_err = err.downcast_ref::<CableError>().unwrap();
+    assert_eq!(cable_err.category(), ErrorCategory::Transport);
+    let io_err = cable_err
+        .source()
+        .and_then(|source| source.downcast_ref::<io::Error>())
+        .unwrap();
+    assert_eq!(io_err.kind(), ErrorKind::TimedOut);
+
+    assert!(cable.get_peer_ids().await.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn disconnect_slow_peer() -> Result<(), Error> {
+    let options = ManagerOptions {
+        write_timeout: None,
+        slow_peer_policy: SlowPeerPolicy::Drop { max_dropped: 5 },
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream, _peer) = duplex();
+    let listener = {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(StalledStream(stream)).await })
+    };
+
+    // Wait for the peer to be connected.
+    while cable.get_peer_ids().await.is_empty() {
+        task::sleep(Duration::from_millis(10)).await;
+    }
+
+    // One message is held by the stalled write and 100 fill the queue, after
+    // which 6 messages are dropped.
+    future::timeout(TIMEOUT, async {
+        for i in 0..107u32 {
+            let request = Message::post_request(NO_CIRCUIT, i.to_be_bytes(), 0, Vec::new());
+            cable.broadcast(&request).await?;
+        }
+        Ok::<_, Error>(())
+    })
+    .await??;
+
+    assert!(future::timeout(TIMEOUT, listener).await?.is_err());
+    assert!(cable.get_peer_ids().await.is_empty());
+
+    Ok(())
+}
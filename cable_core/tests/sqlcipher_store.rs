@@ -0,0 +1,60 @@
+//! Test at-rest encryption of the SQLite store with SQLCipher.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open an encrypted SQLite store and publish a text post.
+//!
+//! 2) Ensure the database file contains neither the text nor the secret key.
+//!
+//! 3) Ensure the store cannot be opened without the correct passphrase.
+//!
+//! 4) Reopen the store with the passphrase and ensure the data is intact.
+
+#![cfg(feature = "sqlcipher")]
+
+use std::fs;
+
+use async_std::stream::StreamExt;
+use cable::{post::PostBody, ChannelOptions, Error};
+
+use cable_core::{CableManager, SqliteStore, Store};
+
+#[async_std::test]
+async fn encrypt_store_at_rest() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cable.db");
+    let channel = "entomology".to_string();
+    let passphrase = "correct horse battery staple";
+
+    let keypair = {
+        let mut cable = CableManager::new(SqliteStore::open_encrypted(&path, passphrase)?);
+        cable.post_join(&channel).await?;
+        cable.post_text(&channel, "a secret about moths").await?;
+
+        cable.store.get_keypair().await.unwrap()
+    };
+    let (_public_key, secret_key) = keypair;
+
+    let contents = fs::read(&path)?;
+    let contains = |needle: &[u8]| contents.windows(needle.len()).any(|w| w == needle);
+    assert!(!contains(b"a secret about moths"));
+    assert!(!contains(&secret_key[..32]));
+
+    assert!(SqliteStore::open(&path).is_err());
+    assert!(SqliteStore::open_encrypted(&path, "incorrect horse").is_err());
+
+    let store = SqliteStore::open_encrypted(&path, passphrase)?;
+    assert_eq!(store.get_keypair().await, Some(keypair));
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let mut texts = Vec::new();
+    let mut stream = store.get_posts(&opts).await;
+    while let Some(post) = stream.next().await {
+        if let PostBody::Text { text, .. } = post?.body {
+            texts.push(text);
+        }
+    }
+    assert_eq!(texts, vec!["a secret about moths".to_string()]);
+
+    Ok(())
+}
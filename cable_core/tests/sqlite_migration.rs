@@ -0,0 +1,37 @@
+//! Test the schema versioning of the SQLite store.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Open a new store and ensure it is at the latest schema version.
+//!
+//! 2) Reopen the store and ensure the version and keypair are unchanged.
+//!
+//! 3) Ensure a store with an unknown future schema version is rejected.
+
+#![cfg(feature = "sqlite")]
+
+use cable::Error;
+use rusqlite::Connection;
+
+use cable_core::{SqliteStore, Store};
+
+#[async_std::test]
+async fn version_sqlite_schema() -> Result<(), Error> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("cable.sqlite");
+
+    let store = SqliteStore::open(&path)?;
+    assert_eq!(store.schema_version().await?, 13);
+    let keypair = store.get_keypair().await;
+    drop(store);
+
+    let store = SqliteStore::open(&path)?;
+    assert_eq!(store.schema_version().await?, 13);
+    assert_eq!(store.get_keypair().await, keypair);
+    drop(store);
+
+    Connection::open(&path)?.pragma_update(None, "user_version", 99)?;
+    assert!(SqliteStore::open(&path).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+//! Test subscribing to and unsubscribing from a channel.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a network of two connected peers and post to a channel on the
+//! second peer.
+//!
+//! 2) Subscribe to the channel on the first peer, ensuring a join post is
+//! published and the post of the second peer is received on the stream of
+//! the subscription.
+//!
+//! 3) Unsubscribe, ensuring a leave post is published and the requests for
+//! the channel are cancelled.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, Error};
+
+use cable_core::{
+    testing::{eventually, Network, Topology},
+    Store, SubscribeOptions,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn subscribe_and_unsubscribe() -> Result<(), Error> {
+    let network = Network::new(2, Topology::Line);
+    let mut first = network.peer(0);
+    let mut second = network.peer(1);
+    assert!(eventually(TIMEOUT, || async { first.get_peer_ids().await.len() == 1 }).await);
+
+    second.post_text("entomology", "moths").await?;
+
+    let options = SubscribeOptions {
+        leave: true,
+        ..SubscribeOptions::default()
+    };
+    let subscription = first.subscribe_with_options("entomology", options).await?;
+    assert_eq!(subscription.channel(), "entomology");
+
+    let public_key = first.get_public_key().await?;
+    assert!(
+        first
+            .store
+            .is_channel_member(&"entomology".to_string(), &public_key)
+            .await
+    );
+
+    let post = {
+        let mut posts = subscription.posts().await;
+        future::timeout(TIMEOUT, posts.next()).await?.unwrap()?
+    };
+    assert!(matches!(post.body, PostBody::Text { text, .. } if text == "moths"));
+
+    subscription.unsubscribe().await?;
+    assert!(
+        !first
+            .store
+            .is_channel_member(&"entomology".to_string(), &public_key)
+            .await
+    );
+    assert!(first.store.get_outbound_requests().await.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Test reporting the progress of the synchronisation of a channel.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish two posts on one manager, then open the channel on another
+//! manager connected to a raw peer.
+//!
+//! 2) Advertise the hashes of both posts from the raw peer in response to the
+//! channel time range request, ensuring both are reported as pending.
+//!
+//! 3) Respond with one of the posts, ensuring the sync is reported as half
+//! complete and synced back to the timestamp of the post.
+//!
+//! 4) Close the channel, ensuring the requests and hashes are forgotten.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::{CHANNEL_TIME_RANGE_REQUEST, NO_CIRCUIT, POST_REQUEST},
+    ChannelOptions, Error, Message, Post,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, MemoryStream},
+    CableManager, MemoryStore, Store,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read messages from the given stream until one of the given type is
+/// received, returning it.
+async fn read_until(stream: MemoryStream, msg_type: u64) -> Result<Message, Error> {
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(stream, options);
+
+    future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if msg.message_type() == msg_type {
+                return Ok(msg);
+            }
+        }
+
+        Err("stream closed".into())
+    })
+    .await?
+}
+
+#[async_std::test]
+async fn report_sync_status() -> Result<(), Error> {
+    let mut author = CableManager::new(MemoryStore::default());
+    let first_hash = author.post_text("entomology", "moths").await?;
+    let second_hash = author.post_text("entomology", "beetles").await?;
+    let payload = author.store.get_post_payload(&first_hash).await.unwrap();
+    let (_, post) = Post::from_bytes(&payload)?;
+
+    let mut cable = CableManager::new(MemoryStore::default());
+    let (stream, mut peer) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream).await });
+    }
+
+    let opts = ChannelOptions::new("entomology", 0, 0, 0);
+    let _ = cable.open_channel(&opts).await?;
+
+    let status = cable.sync_status("entomology").await?;
+    assert_eq!(status.open_requests, 2);
+    assert_eq!(status.known_hashes, 0);
+    assert!(status.is_synced());
+
+    let request = read_until(peer.clone(), CHANNEL_TIME_RANGE_REQUEST).await?;
+    let response = Message::hash_response(
+        NO_CIRCUIT,
+        request.header.req_id,
+        vec![first_hash, second_hash],
+    );
+    peer.write_all(&response.to_bytes()?).await?;
+
+    let request = read_until(peer.clone(), POST_REQUEST).await?;
+    let status = cable.sync_status("entomology").await?;
+    assert_eq!(status.known_hashes, 2);
+    assert_eq!(status.pending_posts, 2);
+    assert_eq!(status.progress(), 0.0);
+
+    let response = Message::post_response(NO_CIRCUIT, request.header.req_id, vec![payload]);
+    peer.write_all(&response.to_bytes()?).await?;
+    assert!(
+        eventually(TIMEOUT, || async {
+            cable
+                .sync_status("entomology")
+                .await
+                .unwrap()
+                .fetched_hashes
+                == 1
+        })
+        .await
+    );
+
+    let status = cable.sync_status("entomology").await?;
+    assert_eq!(status.pending_posts, 1);
+    assert_eq!(status.progress(), 0.5);
+    assert_eq!(status.oldest_timestamp, Some(post.get_timestamp()));
+
+    cable.close_channel("entomology").await?;
+    let status = cable.sync_status("entomology").await?;
+    assert_eq!(status.open_requests, 0);
+    assert_eq!(status.known_hashes, 0);
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Test the teardown of the state held for a peer once it disconnects.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect two raw peers to a manager.
+//!
+//! 2) Send a live channel time range request with a TTL of 1 from the first
+//! raw peer and ensure it is forwarded to the second.
+//!
+//! 3) Disconnect the first raw peer and ensure the forwarded request is
+//! cancelled on the second.
+//!
+//! 4) Connect a third raw peer and ensure the request of the disconnected peer
+//! is not forwarded to it.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, RequestBody},
+    ChannelOptions, Error, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, eventually, MemoryStream},
+    CableManager, MemoryStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read messages from the given stream until one satisfies the given
+/// predicate, returning it.
+async fn read_until(
+    stream: MemoryStream,
+    mut found: impl FnMut(&Message) -> bool,
+) -> Result<Message, Error> {
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(stream, options);
+
+    future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if found(&msg) {
+                return Ok(msg);
+            }
+        }
+
+        Err("stream closed".into())
+    })
+    .await?
+}
+
+/// Connect a raw peer to the given manager.
+fn connect(cable: &CableManager<MemoryStore>) -> MemoryStream {
+    let (stream, peer) = duplex();
+    let cable = cable.clone();
+    task::spawn(async move { cable.listen(stream).await });
+
+    peer
+}
+
+/// Return `true` if the given message is a cancel request for the given
+/// request ID.
+fn cancels(msg: &Message, req_id: &ReqId) -> bool {
+    matches!(
+        &msg.body,
+        MessageBody::Request {
+            body: RequestBody::Cancel { cancel_id },
+            ..
+        } if cancel_id == req_id
+    )
+}
+
+#[async_std::test]
+async fn tear_down_disconnected_peer() -> Result<(), Error> {
+    let cable = CableManager::new(MemoryStore::default());
+    let mut first = connect(&cable);
+    let second = connect(&cable);
+    assert!(eventually(TIMEOUT, || async { cable.get_peer_ids().await.len() == 2 }).await);
+
+    let req_id: ReqId = [0, 0, 0, 1];
+    let opts = ChannelOptions::new("entomology", 0, 0, 0);
+    let request = Message::channel_time_range_request(NO_CIRCUIT, req_id, 1, opts);
+    first.write_all(&request.to_bytes()?).await?;
+    read_until(second.clone(), |msg| msg.header.req_id == req_id).await?;
+
+    first.close();
+    read_until(second.clone(), |msg| cancels(msg, &req_id)).await?;
+
+    // The request is no longer sent to newly connected peers. A channel list
+    // request is answered only after the outbound requests have been sent.
+    let mut third = connect(&cable);
+    let sync_req_id: ReqId = [0, 0, 0, 2];
+    let request = Message::channel_list_request(NO_CIRCUIT, sync_req_id, 0, 0, 0);
+    third.write_all(&request.to_bytes()?).await?;
+    let msg = read_until(third.clone(), |msg| {
+        msg.header.req_id == req_id || msg.header.req_id == sync_req_id
+    })
+    .await?;
+    assert_eq!(msg.header.req_id, sync_req_id);
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Test publishing replies and assembling them into threads.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a root text post, replies to it and a reply to a reply, with
+//! distinct timestamps.
+//!
+//! 2) Ensure the thread of the root post holds the replies in order of
+//! timestamp, and that a text post whose reply marker names a post it does
+//! not link to is not indexed as a reply.
+//!
+//! 3) Delete a reply, ensuring it is omitted from the thread along with the
+//! replies to it.
+
+use std::{convert::TryInto, sync::Arc, time::Duration};
+
+use cable::{post::PostBody, Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{parse_reply, reply_text, CableManager, MemoryStore, MockClock, Store};
+
+async fn assemble_threads<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let clock = MockClock::new(1_000);
+    let mut cable = CableManager::builder(store)
+        .clock(Arc::new(clock.clone()))
+        .build();
+    let root = cable.post_text(&channel, "a luna moth!").await?;
+    clock.advance(Duration::from_secs(1));
+    let first = cable.post_reply(&channel, &root, "what a wingspan").await?;
+    clock.advance(Duration::from_secs(1));
+    let nested = cable.post_reply(&channel, &first, "about 11 cm").await?;
+    clock.advance(Duration::from_secs(1));
+    let second = cable.post_reply(&channel, &root, "where was it?").await?;
+
+    // The text of a reply begins with a marker naming its parent.
+    let post = cable.get_post(&nested).await?.unwrap();
+    assert!(post.header.links.contains(&first));
+    assert!(matches!(
+        post.body,
+        PostBody::Text { text, .. }
+            if parse_reply(&text) == Some((first, "about 11 cm"))
+    ));
+
+    let thread = cable.get_thread(&root).await;
+    assert_eq!(thread.hash, root);
+    assert_eq!(thread.len(), 3);
+    let replies: Vec<_> = thread.replies.iter().map(|reply| reply.hash).collect();
+    assert_eq!(replies, vec![first, second]);
+    assert_eq!(thread.replies[0].replies[0].hash, nested);
+    assert!(cable.get_thread(&second).await.is_empty());
+
+    // A marker naming a post which is not linked does not make a reply.
+    let text = reply_text(&root, "not really a reply");
+    let mut post = Post::text(pk, vec![], 100, channel.clone(), text);
+    post.sign(&sk)?;
+    cable.store.insert_post(&post).await?;
+    assert_eq!(cable.store.get_replies(&root).await, vec![first, second]);
+
+    cable.post_delete(vec![first]).await?;
+    let thread = cable.get_thread(&root).await;
+    assert_eq!(thread.len(), 1);
+    assert_eq!(thread.replies[0].hash, second);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn assemble_threads_memory_store() -> Result<(), Error> {
+    assemble_threads(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn assemble_threads_sled_store() -> Result<(), Error> {
+    assemble_threads(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn assemble_threads_sqlite_store() -> Result<(), Error> {
+    assemble_threads(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,91 @@
+//! Test the time range queries of the stores.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert text posts with several timestamps to two channels.
+//!
+//! 2) Retrieve the hashes of one channel for bounded, open-ended and empty
+//!    time ranges, including a range which ends before it starts.
+//!
+//! 3) Delete posts and ensure they are no longer retrieved.
+
+use std::convert::TryInto;
+
+use async_std::stream::StreamExt;
+use cable::{ChannelOptions, Error, Hash, Post, Timestamp};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+// Retrieve the hashes of the "entomology" channel within the given range.
+async fn hashes<S: Store>(store: &S, start: Timestamp, end: Timestamp) -> Vec<Hash> {
+    let opts = ChannelOptions::new("entomology", start, end, 0);
+    store
+        .get_post_hashes(&opts)
+        .await
+        .filter_map(Result::ok)
+        .collect()
+        .await
+}
+
+// Query the posts of a channel by time range in the given store.
+async fn query_time_ranges<S: Store>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+
+    let mut expected = Vec::new();
+    for timestamp in [100, 200, 200, 300, 400] {
+        let text = format!("moth {}", expected.len());
+        let mut post = Post::text(pk, vec![], timestamp, "entomology".into(), text.clone());
+        post.sign(&sk)?;
+        expected.push(store.insert_post(&post).await?);
+
+        let mut other = Post::text(pk, vec![], timestamp, "botany".into(), text);
+        other.sign(&sk)?;
+        store.insert_post(&other).await?;
+    }
+
+    // Posts sharing a timestamp are not ordered by the query.
+    let sorted = |mut hashes: Vec<Hash>| {
+        hashes.sort();
+        hashes
+    };
+
+    assert_eq!(sorted(hashes(&store, 0, 0).await), sorted(expected.clone()));
+    assert_eq!(
+        sorted(hashes(&store, 200, 400).await),
+        sorted(expected[1..4].to_vec())
+    );
+    assert_eq!(hashes(&store, 300, 0).await, expected[3..].to_vec());
+    assert_eq!(hashes(&store, 0, 200).await, expected[..1].to_vec());
+    assert!(hashes(&store, 200, 200).await.is_empty());
+    assert!(hashes(&store, 400, 100).await.is_empty());
+    assert!(hashes(&store, 500, 0).await.is_empty());
+
+    store.delete_post(&expected[1]).await;
+    store.delete_post(&expected[3]).await;
+    assert_eq!(
+        sorted(hashes(&store, 200, 0).await),
+        sorted(vec![expected[2], expected[4]])
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn query_time_ranges_memory_store() -> Result<(), Error> {
+    query_time_ranges(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn query_time_ranges_sled_store() -> Result<(), Error> {
+    query_time_ranges(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn query_time_ranges_sqlite_store() -> Result<(), Error> {
+    query_time_ranges(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,107 @@
+//! Test retrieving the topic of a channel and the events emitted when it
+//! changes.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create a cable manager with a mock clock which accepts unsolicited
+//!    posts, and subscribe to its events.
+//!
+//! 2) Publish a topic and ensure a topic change event is emitted and the
+//!    topic is returned by `get_topic()`.
+//!
+//! 3) Publish a topic post older than the current topic and ensure no event
+//!    is emitted and the topic is unchanged.
+//!
+//! 4) Send a newer topic post from a raw peer and ensure a topic change
+//!    event is emitted for it.
+
+use std::{convert::TryInto, sync::Arc, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{constants::NO_CIRCUIT, Error, Message, Post};
+use desert::ToBytes;
+use futures::AsyncWriteExt;
+use sodiumoxide::crypto::sign;
+
+use cable_core::{
+    testing::duplex, CableEvent, CableManager, ManagerOptions, MemoryStore, MockClock,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const NOW: u64 = 1_000_000;
+
+#[async_std::test]
+async fn notify_topic_changes() -> Result<(), Error> {
+    let options = ManagerOptions {
+        clock: Arc::new(MockClock::new(NOW)),
+        accept_unsolicited_posts: true,
+        ..Default::default()
+    };
+    let mut cable = CableManager::with_options(MemoryStore::default(), options);
+    let mut events = cable.events().await;
+    assert_eq!(cable.get_topic("entomology").await, None);
+
+    let hash = cable.post_topic("entomology", "moths").await?;
+    let event = future::timeout(TIMEOUT, events.next()).await?.unwrap();
+    let CableEvent::TopicChanged {
+        channel,
+        topic,
+        hash: topic_hash,
+    } = event
+    else {
+        panic!("expected a topic change event");
+    };
+    assert_eq!(channel, "entomology");
+    assert_eq!(topic, "moths");
+    assert_eq!(topic_hash, hash);
+    assert_eq!(
+        cable.get_topic("entomology").await,
+        Some("moths".to_string())
+    );
+
+    let public_key = cable.get_public_key().await?;
+    let older = Post::topic(
+        public_key,
+        vec![],
+        NOW - 1,
+        "entomology".to_string(),
+        "beetles".to_string(),
+    );
+    cable.post(older).await?;
+    assert!(future::timeout(Duration::from_millis(100), events.next())
+        .await
+        .is_err());
+    assert_eq!(
+        cable.get_topic("entomology").await,
+        Some("moths".to_string())
+    );
+
+    let (stream, mut peer) = duplex();
+    let listener = cable.clone();
+    task::spawn(async move { listener.listen(stream).await });
+
+    let (pk, sk) = sign::gen_keypair();
+    let mut newer = Post::topic(
+        pk.as_ref().try_into()?,
+        vec![],
+        NOW + 1,
+        "entomology".to_string(),
+        "wasps".to_string(),
+    );
+    newer.sign(sk.as_ref().try_into()?)?;
+    let response = Message::post_response(NO_CIRCUIT, [0, 0, 0, 1], vec![newer.to_bytes()?]);
+    peer.write_all(&response.to_bytes()?).await?;
+
+    let event = future::timeout(TIMEOUT, events.next()).await?.unwrap();
+    let CableEvent::TopicChanged { topic, hash, .. } = event else {
+        panic!("expected a topic change event");
+    };
+    assert_eq!(topic, "wasps");
+    assert_eq!(hash, newer.hash()?);
+    assert_eq!(
+        cable.get_topic("entomology").await,
+        Some("wasps".to_string())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Test the handling of request TTLs at their boundaries.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager and send channel list requests
+//! with TTLs of 0, 1, 2 and 17, waiting for the responses.
+//!
+//! 2) Connect a second raw peer and ensure the requests received with a TTL
+//! above 0 are forwarded to it with the TTL decremented, clamping a TTL
+//! above 16 to 16 before decrementing.
+//!
+//! 3) Send a request with a TTL of 17 to a manager rejecting excessive TTLs,
+//! ensuring the connection is closed with an error.
+
+use std::{collections::HashMap, time::Duration};
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, RequestBody, ResponseBody},
+    Error, Message, ReqId,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{
+    testing::{duplex, MemoryStream},
+    CableManager, ManagerOptions, MemoryStore,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read messages from the given stream, passing each to the given closure
+/// until it returns `true`.
+async fn read_until(
+    stream: MemoryStream,
+    mut done: impl FnMut(Message) -> bool,
+) -> Result<(), Error> {
+    let options = DecodeOptions {
+        include_len: true,
+        ..Default::default()
+    };
+    let mut messages = decode_with_options(stream, options);
+
+    future::timeout(TIMEOUT, async {
+        while let Some(buf) = messages.next().await {
+            let (_, msg) = Message::from_bytes(&buf?)?;
+            if done(msg) {
+                return Ok(());
+            }
+        }
+
+        Err("stream closed".into())
+    })
+    .await?
+}
+
+#[async_std::test]
+async fn forward_requests_with_decremented_ttl() -> Result<(), Error> {
+    let cable = CableManager::new(MemoryStore::default());
+
+    let (stream_a, mut first) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream_a).await });
+    }
+
+    for (i, ttl) in [0, 1, 2, 17].into_iter().enumerate() {
+        let request = Message::channel_list_request(NO_CIRCUIT, [0, 0, 0, i as u8], ttl, 0, 0);
+        first.write_all(&request.to_bytes()?).await?;
+    }
+
+    // Wait until every request has been handled.
+    let mut responses = 0;
+    read_until(first.clone(), |msg| {
+        if let MessageBody::Response {
+            body: ResponseBody::ChannelList { .. },
+        } = msg.body
+        {
+            responses += 1;
+        }
+        responses == 4
+    })
+    .await?;
+
+    let (stream_b, second) = duplex();
+    {
+        let cable = cable.clone();
+        task::spawn(async move { cable.listen(stream_b).await });
+    }
+
+    let mut forwarded: HashMap<ReqId, u8> = HashMap::new();
+    read_until(second, |msg| {
+        if let MessageBody::Request {
+            ttl,
+            body: RequestBody::ChannelList { .. },
+        } = msg.body
+        {
+            forwarded.insert(msg.header.req_id, ttl);
+        }
+        forwarded.len() == 3
+    })
+    .await?;
+
+    let expected = HashMap::from([([0, 0, 0, 1], 0), ([0, 0, 0, 2], 1), ([0, 0, 0, 3], 15)]);
+    assert_eq!(forwarded, expected);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn reject_excessive_ttl() -> Result<(), Error> {
+    let options = ManagerOptions {
+        reject_excessive_ttl: true,
+        ..ManagerOptions::default()
+    };
+    let cable = CableManager::with_options(MemoryStore::default(), options);
+
+    let (stream_a, mut peer) = duplex();
+    let listener = task::spawn(async move { cable.listen(stream_a).await });
+
+    let request = Message::channel_list_request(NO_CIRCUIT, [0, 0, 0, 1], 17, 0, 0);
+    peer.write_all(&request.to_bytes()?).await?;
+
+    let err = future::timeout(TIMEOUT, listener).await?.unwrap_err();
+    assert!(err.to_string().contains("ttl"));
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Test dropping responses to requests which were never made.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Connect a raw peer to a cable manager and send a channel list response
+//! with a request ID the manager never issued, followed by a post request.
+//!
+//! 2) Wait for the post response, ensuring the channels of the unknown
+//! response were not stored.
+//!
+//! 3) Repeat with `accept_unknown_responses` set, ensuring the channels are
+//! stored.
+
+use std::time::Duration;
+
+use async_std::{future, stream::StreamExt, task};
+use cable::{
+    constants::NO_CIRCUIT,
+    message::{MessageBody, ResponseBody},
+    Error, Message,
+};
+use desert::{FromBytes, ToBytes};
+use futures::AsyncWriteExt;
+use length_prefixed_stream::{decode_with_options, DecodeOptions};
+
+use cable_core::{testing::duplex, CableManager, ManagerOptions, MemoryStore, Store};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[async_std::test]
+async fn drop_unknown_responses() -> Result<(), Error> {
+    for accept_unknown_responses in [false, true] {
+        let options = ManagerOptions {
+            accept_unknown_responses,
+            // Handle the messages in the order in which they are sent.
+            handler_concurrency: 1,
+            ..Default::default()
+        };
+        let cable = CableManager::with_options(MemoryStore::default(), options);
+
+        let (stream, mut peer) = duplex();
+        let listener = cable.clone();
+        task::spawn(async move { listener.listen(stream).await });
+
+        let options = DecodeOptions {
+            include_len: true,
+            ..Default::default()
+        };
+        let mut messages = decode_with_options(peer.clone(), options);
+
+        let channels = vec!["entomology".to_string()];
+        let response = Message::channel_list_response(NO_CIRCUIT, [9, 9, 9, 9], channels.clone());
+        let request = Message::post_request(NO_CIRCUIT, [0, 0, 0, 1], 0, Vec::new());
+        peer.write_all(&response.to_bytes()?).await?;
+        peer.write_all(&request.to_bytes()?).await?;
+
+        // The post request is answered once the response has been handled.
+        future::timeout(TIMEOUT, async {
+            loop {
+                let buf = match messages.next().await {
+                    Some(buf) => buf?,
+                    None => return Err::<_, Error>("stream closed".into()),
+                };
+                let (_, msg) = Message::from_bytes(&buf)?;
+                if let MessageBody::Response {
+                    body: ResponseBody::Post { .. },
+                } = msg.body
+                {
+                    return Ok(());
+                }
+            }
+        })
+        .await??;
+
+        let stored = cable.store.get_channels().await;
+        if accept_unknown_responses {
+            assert_eq!(stored, Some(channels));
+        } else {
+            assert_eq!(stored, None);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Test tracking the last-read post of a channel and counting unread posts.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert three text posts by another author into a channel, along with a
+//! post by the local peer.
+//!
+//! 2) Ensure all posts by the other author are unread.
+//!
+//! 3) Mark the second post as read, ensuring one post remains unread and that
+//! marking the first post as read does not move the position back.
+//!
+//! 4) Mark the channel as read, ensuring no posts remain unread.
+
+use std::convert::TryInto;
+
+use cable::{Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{CableManager, MemoryStore, PageCursor, Store};
+
+async fn track_unread<S: Store + Clone>(store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut cable = CableManager::new(store);
+    let mut hashes = Vec::new();
+    for (timestamp, text) in [(100, "moths"), (200, "beetles"), (300, "wasps")] {
+        let mut post = Post::text(pk, vec![], timestamp, channel.clone(), text.into());
+        post.sign(&sk)?;
+        hashes.push(cable.store.insert_post(&post).await?);
+    }
+    cable.post_text("entomology", "ants").await?;
+
+    assert_eq!(cable.store.get_last_read(&channel).await, None);
+    assert_eq!(cable.unread_count("entomology").await?, 3);
+
+    cable.mark_read("entomology", &hashes[1]).await?;
+    assert_eq!(
+        cable.store.get_last_read(&channel).await,
+        Some(PageCursor {
+            timestamp: 200,
+            hash: hashes[1]
+        })
+    );
+    assert_eq!(cable.unread_count("entomology").await?, 1);
+
+    cable.mark_read("entomology", &hashes[0]).await?;
+    assert_eq!(cable.unread_count("entomology").await?, 1);
+
+    cable.mark_channel_read("entomology").await?;
+    assert_eq!(cable.unread_count("entomology").await?, 0);
+
+    assert!(cable.mark_read("entomology", &[0; 32]).await.is_err());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn track_unread_memory_store() -> Result<(), Error> {
+    track_unread(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn track_unread_sled_store() -> Result<(), Error> {
+    track_unread(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn track_unread_sqlite_store() -> Result<(), Error> {
+    track_unread(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,62 @@
+//! Test the handling of posts received without having been requested.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Publish a text post on one peer and retrieve its payload.
+//!
+//! 2) Handle an unsolicited post response containing the payload on a peer
+//! with the default options, ensuring the post is not stored.
+//!
+//! 3) Handle the same response twice on a peer accepting unsolicited posts,
+//! ensuring the post is stored once.
+//!
+//! 4) Handle a response containing a post with an invalid signature,
+//! ensuring it is not stored.
+
+use async_std::stream::StreamExt;
+use cable::{constants::NO_CIRCUIT, ChannelOptions, Error, Message};
+
+use cable_core::{CableManager, ManagerOptions, MemoryStore, Store};
+
+const CHANNEL: &str = "entomology";
+
+async fn post_count(cable: &CableManager<MemoryStore>) -> usize {
+    cable
+        .store
+        .get_posts(&ChannelOptions::new(CHANNEL, 0, 0, 0))
+        .await
+        .count()
+        .await
+}
+
+#[async_std::test]
+async fn accept_unsolicited_posts() -> Result<(), Error> {
+    let mut author = CableManager::new(MemoryStore::default());
+    let hash = author.post_text(CHANNEL, "moths").await?;
+    let payload = author.store.get_post_payload(&hash).await.unwrap();
+
+    let response = Message::post_response(NO_CIRCUIT, [0, 0, 0, 1], vec![payload.clone()]);
+
+    let mut strict = CableManager::new(MemoryStore::default());
+    strict.handle(0, &response).await?;
+    assert_eq!(post_count(&strict).await, 0);
+
+    let options = ManagerOptions {
+        accept_unsolicited_posts: true,
+        ..ManagerOptions::default()
+    };
+    let mut gossip = CableManager::with_options(MemoryStore::default(), options);
+    gossip.handle(0, &response).await?;
+    gossip.handle(0, &response).await?;
+    assert_eq!(post_count(&gossip).await, 1);
+
+    // Corrupt the text of the post, invalidating the signature.
+    let mut forged = payload;
+    let last = forged.len() - 1;
+    forged[last] ^= 1;
+    let response = Message::post_response(NO_CIRCUIT, [0, 0, 0, 2], vec![forged]);
+    gossip.handle(0, &response).await?;
+    assert_eq!(post_count(&gossip).await, 1);
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Test retrieving the history of the `post/info` posts of a user.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert three `post/info` posts by one author, out of chronological
+//!    order, and one by another author.
+//!
+//! 2) Ensure the history of the first author holds all three posts, oldest
+//!    first, while the latest name remains the current name.
+//!
+//! 3) Delete a post and ensure it is removed from the history.
+
+use std::convert::TryInto;
+
+use cable::{Error, Post, UserInfo};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+// Query the info history of an author in the given store.
+async fn query_history<S: Store>(mut store: S) -> Result<(), Error> {
+    let mut keypairs = Vec::new();
+    for _ in 0..2 {
+        let (pk, sk) = sign::gen_keypair();
+        let pk: [u8; 32] = pk.as_ref().try_into()?;
+        let sk: [u8; 64] = sk.as_ref().try_into()?;
+        keypairs.push((pk, sk));
+    }
+    let (pk, sk) = keypairs[0];
+    let (other_pk, other_sk) = keypairs[1];
+
+    let mut hashes = Vec::new();
+    for (timestamp, name) in [(200, "moth"), (100, "caterpillar"), (300, "butterfly")] {
+        let mut post = Post::info(pk, vec![], timestamp, vec![UserInfo::new("name", name)]);
+        post.sign(&sk)?;
+        hashes.push(store.insert_post(&post).await?);
+    }
+
+    let mut other = Post::info(other_pk, vec![], 150, vec![UserInfo::new("name", "wasp")]);
+    other.sign(&other_sk)?;
+    store.insert_post(&other).await?;
+
+    let history = store.get_user_info_history(&pk).await?;
+    let names: Vec<&str> = history
+        .iter()
+        .map(|entry| entry.info[0].val.as_str())
+        .collect();
+    assert_eq!(names, vec!["caterpillar", "moth", "butterfly"]);
+    let timestamps: Vec<u64> = history.iter().map(|entry| entry.timestamp).collect();
+    assert_eq!(timestamps, vec![100, 200, 300]);
+    assert_eq!(history[0].hash, hashes[1]);
+    assert_eq!(
+        store.get_peer_name_and_hash(&pk).await,
+        Some(("butterfly".to_string(), hashes[2]))
+    );
+
+    store.delete_post(&hashes[0]).await;
+    let history = store.get_user_info_history(&pk).await?;
+    assert_eq!(history.len(), 2);
+    assert!(history.iter().all(|entry| entry.hash != hashes[0]));
+
+    assert!(store.get_user_info_history(&[0; 32]).await?.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn query_history_memory_store() -> Result<(), Error> {
+    query_history(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn query_history_sled_store() -> Result<(), Error> {
+    query_history(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn query_history_sqlite_store() -> Result<(), Error> {
+    query_history(cable_core::SqliteStore::open_in_memory()?).await
+}
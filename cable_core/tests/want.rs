@@ -0,0 +1,73 @@
+//! Test the wanted hashes reported by each store as it grows.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Insert more text posts than the initial capacity of the known-hash
+//!    filter of the persistent stores.
+//!
+//! 2) Tombstone the hash of a post which was never stored.
+//!
+//! 3) Ensure that none of the stored or tombstoned hashes are wanted.
+//!
+//! 4) Ensure that hashes of unknown posts are wanted, in the given order.
+
+use std::convert::TryInto;
+
+use cable::{Error, Post};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{MemoryStore, Store};
+
+async fn want_only_unknown_hashes<S: Store>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut known = Vec::new();
+    for i in 0..1200 {
+        let mut text = Post::text(pk, vec![], 1_000 + i, channel.clone(), format!("{}", i));
+        text.sign(&sk)?;
+        known.push(store.insert_post(&text).await?);
+    }
+
+    let mut deleted = Post::text(pk, vec![], 500, channel.clone(), "deleted".into());
+    deleted.sign(&sk)?;
+    let deleted_hash = deleted.hash()?;
+    store.insert_tombstone(&deleted_hash).await;
+    known.push(deleted_hash);
+
+    assert!(store.want(&known).await.is_empty());
+
+    let mut unknown = Vec::new();
+    for i in 0..50 {
+        let mut text = Post::text(pk, vec![], 100_000 + i, channel.clone(), "unknown".into());
+        text.sign(&sk)?;
+        unknown.push(text.hash()?);
+    }
+
+    assert_eq!(store.want(&unknown).await, unknown);
+
+    let mut mixed = unknown.clone();
+    mixed.extend_from_slice(&known[..50]);
+    assert_eq!(store.want(&mixed).await, unknown);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn memory_store_wants_only_unknown_hashes() -> Result<(), Error> {
+    want_only_unknown_hashes(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn sled_store_wants_only_unknown_hashes() -> Result<(), Error> {
+    want_only_unknown_hashes(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn sqlite_store_wants_only_unknown_hashes() -> Result<(), Error> {
+    want_only_unknown_hashes(cable_core::SqliteStore::open_in_memory()?).await
+}
@@ -0,0 +1,129 @@
+//! Test watching a store for changes to the posts of a channel.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Watch a channel with two watchers.
+//!
+//! 2) Insert posts to the channel and to another channel.
+//!
+//! 3) Ensure both watchers receive events for the watched channel only.
+//!
+//! 4) Ensure info posts of channel members are emitted for the channel.
+//!
+//! 5) Delete a post and ensure a deletion event is received.
+//!
+//! 6) Ensure a live post stream returns stored posts followed by new posts.
+
+use std::{convert::TryInto, time::Duration};
+
+use async_std::{future, stream::StreamExt};
+use cable::{post::PostBody, ChannelOptions, Error, Post, UserInfo};
+use sodiumoxide::crypto::sign;
+
+use cable_core::{EventStream, MemoryStore, Store, StoreEvent};
+
+// Receive the next event, failing if none is received within a second.
+async fn next_event(events: &mut EventStream<'static>) -> Result<StoreEvent, Error> {
+    let event = future::timeout(Duration::from_secs(1), events.next()).await?;
+
+    Ok(event.expect("event stream ended"))
+}
+
+// Ensure no event is pending.
+async fn assert_no_event(events: &mut EventStream<'static>) {
+    let res = future::timeout(Duration::from_millis(50), events.next()).await;
+    assert!(res.is_err());
+}
+
+async fn watch_channel<S: Store + Clone>(mut store: S) -> Result<(), Error> {
+    let (pk, sk) = sign::gen_keypair();
+    let pk = pk.as_ref().try_into()?;
+    let sk = sk.as_ref().try_into()?;
+    let channel = "entomology".to_string();
+
+    let mut first = store.watch(&channel).await;
+    let mut second = store.watch(&channel).await;
+
+    let mut join = Post::join(pk, vec![], 100, channel.clone());
+    join.sign(&sk)?;
+    let join_hash = store.insert_post(&join).await?;
+
+    let mut other = Post::text(pk, vec![], 150, "botany".into(), "fern".into());
+    other.sign(&sk)?;
+    store.insert_post(&other).await?;
+
+    let mut text = Post::text(pk, vec![], 200, channel.clone(), "moth".into());
+    text.sign(&sk)?;
+    let text_hash = store.insert_post(&text).await?;
+
+    for events in [&mut first, &mut second] {
+        for expected in [join_hash, text_hash] {
+            match next_event(events).await? {
+                StoreEvent::PostInserted { hash, .. } => assert_eq!(hash, expected),
+                event => panic!("unexpected event: {:?}", event),
+            }
+        }
+        assert_no_event(events).await;
+    }
+
+    // Dropping a watcher leaves the others active.
+    drop(second);
+
+    let mut info = Post::info(pk, vec![], 300, vec![UserInfo::name("glyph")?]);
+    info.sign(&sk)?;
+    let info_hash = store.insert_post(&info).await?;
+    match next_event(&mut first).await? {
+        StoreEvent::PostInserted { hash, post } => {
+            assert_eq!(hash, info_hash);
+            assert!(matches!(post.body, PostBody::Info { .. }));
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    store.delete_posts(&[text_hash]).await;
+    match next_event(&mut first).await? {
+        StoreEvent::PostDeleted { hash } => assert_eq!(hash, text_hash),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    let mut stored = Post::text(pk, vec![], 400, channel.clone(), "beetle".into());
+    stored.sign(&sk)?;
+    store.insert_post(&stored).await?;
+
+    let opts = ChannelOptions::new(&channel, 0, 0, 0);
+    let reader = store.clone();
+    let mut live = reader.get_posts_live(&opts).await;
+
+    let mut texts = Vec::new();
+    let mut new = Post::text(pk, vec![], 500, channel.clone(), "wasp".into());
+    new.sign(&sk)?;
+    store.insert_post(&new).await?;
+    while texts.len() < 2 {
+        let post = future::timeout(Duration::from_secs(1), live.next())
+            .await?
+            .expect("post stream ended")?;
+        if let PostBody::Text { text, .. } = post.body {
+            texts.push(text);
+        }
+    }
+    assert_eq!(texts, vec!["beetle", "wasp"]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn watch_memory_store() -> Result<(), Error> {
+    watch_channel(MemoryStore::default()).await
+}
+
+#[cfg(feature = "sled")]
+#[async_std::test]
+async fn watch_sled_store() -> Result<(), Error> {
+    watch_channel(cable_core::SledStore::temporary()?).await
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn watch_sqlite_store() -> Result<(), Error> {
+    watch_channel(cable_core::SqliteStore::open_in_memory()?).await
+}
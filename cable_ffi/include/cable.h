@@ -0,0 +1,68 @@
+/*
+ * C interface to cable.rs.
+ *
+ * Link against the `cable_ffi` library built by `cargo build -p cable-ffi`
+ * (libcable_ffi.so, libcable_ffi.dylib or libcable_ffi.a).
+ *
+ * Functions returning int32_t return CABLE_OK on success or a negative status
+ * code on failure. Strings are NUL-terminated UTF-8.
+ */
+
+#ifndef CABLE_H
+#define CABLE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define CABLE_OK 0
+#define CABLE_ERR_NULL -1
+#define CABLE_ERR_UTF8 -2
+#define CABLE_ERR_IO -3
+#define CABLE_ERR_FAILED -4
+
+/* A cable peer. */
+typedef struct Cable Cable;
+
+/* A post delivered to a subscription callback. Pointers are only valid for
+ * the duration of the callback. */
+typedef struct CablePost {
+    uint64_t post_type;
+    const uint8_t *public_key; /* 32 bytes */
+    const uint8_t *hash;       /* 32 bytes */
+    uint64_t timestamp;        /* milliseconds since the UNIX epoch */
+    const char *channel;       /* NULL if the post has no channel */
+    const char *text;          /* text or topic; NULL for other post types */
+} CablePost;
+
+typedef void (*CablePostCallback)(void *user_data, const CablePost *post);
+
+Cable *cable_new(void);
+void cable_free(Cable *cable);
+
+int32_t cable_public_key(const Cable *cable, uint8_t *out_public_key);
+
+/* Takes ownership of a connected TCP socket. */
+int32_t cable_connect_fd(const Cable *cable, int fd);
+
+/* `out_hash` may be NULL; otherwise it receives the 32 byte post hash. */
+int32_t cable_post_text(const Cable *cable, const char *channel, const char *text,
+                        uint8_t *out_hash);
+int32_t cable_post_topic(const Cable *cable, const char *channel, const char *topic,
+                         uint8_t *out_hash);
+int32_t cable_join(const Cable *cable, const char *channel, uint8_t *out_hash);
+int32_t cable_leave(const Cable *cable, const char *channel, uint8_t *out_hash);
+int32_t cable_set_name(const Cable *cable, const char *name, uint8_t *out_hash);
+
+/* Callbacks are invoked from a background thread. */
+int32_t cable_subscribe(const Cable *cable, const char *channel, CablePostCallback callback,
+                        void *user_data);
+int32_t cable_unsubscribe(const Cable *cable, const char *channel);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CABLE_H */
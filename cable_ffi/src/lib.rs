@@ -0,0 +1,441 @@
+//! A C ABI for embedding a cable peer in applications written in other
+//! languages.
+//!
+//! The functions declared in `include/cable.h` create a peer backed by an
+//! in-memory store, connect it to remote peers over sockets opened by the
+//! caller, publish posts and deliver the posts of subscribed channels to a
+//! callback.
+//!
+//! Every function returning `int32_t` returns `CABLE_OK` (zero) on success or
+//! a negative status code on failure. Strings are NUL-terminated UTF-8.
+
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    ptr,
+    sync::Mutex,
+};
+
+use async_std::{net::TcpStream, prelude::*, task};
+use cable::{post::PostBody, Channel, ChannelOptions, Error, Hash, Post};
+use cable_core::{CableManager, MemoryStore};
+
+/// The operation succeeded.
+pub const CABLE_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const CABLE_ERR_NULL: i32 = -1;
+/// A string argument was not valid UTF-8.
+pub const CABLE_ERR_UTF8: i32 = -2;
+/// The socket could not be used.
+pub const CABLE_ERR_IO: i32 = -3;
+/// The operation was rejected by the peer, for example because a post
+/// failed validation.
+pub const CABLE_ERR_FAILED: i32 = -4;
+
+/// A post delivered to a subscription callback.
+///
+/// All pointers are only valid for the duration of the callback.
+#[repr(C)]
+pub struct CablePost {
+    /// The numeric post type (see the cable specification).
+    pub post_type: u64,
+    /// The 32 byte public key of the author.
+    pub public_key: *const u8,
+    /// The 32 byte hash of the post.
+    pub hash: *const u8,
+    /// The time at which the post was published, in milliseconds since the
+    /// UNIX epoch.
+    pub timestamp: u64,
+    /// The channel of the post, or null if the post has no channel.
+    pub channel: *const c_char,
+    /// The text of a text post or the topic of a topic post; null for other
+    /// post types.
+    pub text: *const c_char,
+}
+
+/// A function receiving the posts of a subscribed channel, along with the
+/// user data given to `cable_subscribe()`.
+pub type CablePostCallback = extern "C" fn(user_data: *mut c_void, post: *const CablePost);
+
+/// A subscription callback and its user data.
+struct Callback {
+    f: CablePostCallback,
+    user_data: *mut c_void,
+}
+
+// The caller of `cable_subscribe()` guarantees that the user data may be used
+// from the thread on which callbacks are invoked.
+unsafe impl Send for Callback {}
+
+/// A cable peer.
+pub struct Cable {
+    manager: CableManager<MemoryStore>,
+    /// The tasks delivering posts to the callback of each subscribed channel.
+    subscriptions: Mutex<HashMap<Channel, task::JoinHandle<()>>>,
+}
+
+/// Convert the given C string to a `String`.
+unsafe fn to_string(s: *const c_char) -> Result<String, i32> {
+    if s.is_null() {
+        return Err(CABLE_ERR_NULL);
+    }
+
+    CStr::from_ptr(s)
+        .to_str()
+        .map(str::to_owned)
+        .map_err(|_| CABLE_ERR_UTF8)
+}
+
+/// Copy the hash returned by a publishing operation to `out_hash`, if it is
+/// not null, and return a status code.
+unsafe fn publish_result(result: Result<Hash, Error>, out_hash: *mut u8) -> i32 {
+    match result {
+        Ok(hash) => {
+            if !out_hash.is_null() {
+                ptr::copy_nonoverlapping(hash.as_ptr(), out_hash, hash.len());
+            }
+            CABLE_OK
+        }
+        Err(_) => CABLE_ERR_FAILED,
+    }
+}
+
+/// Deliver the given post to the given callback.
+fn deliver(callback: &Callback, post: &Post) {
+    let hash = match post.hash() {
+        Ok(hash) => hash,
+        Err(_) => return,
+    };
+    let channel = post
+        .get_channel()
+        .and_then(|channel| CString::new(channel.as_str()).ok());
+    let text = match &post.body {
+        PostBody::Text { text, .. } => CString::new(text.as_str()).ok(),
+        PostBody::Topic { topic, .. } => CString::new(topic.as_str()).ok(),
+        _ => None,
+    };
+
+    let post = CablePost {
+        post_type: post.post_type(),
+        public_key: post.header.public_key.as_ptr(),
+        hash: hash.as_ptr(),
+        timestamp: post.get_timestamp(),
+        channel: channel
+            .as_ref()
+            .map_or(ptr::null(), |channel| channel.as_ptr()),
+        text: text.as_ref().map_or(ptr::null(), |text| text.as_ptr()),
+    };
+    (callback.f)(callback.user_data, &post);
+}
+
+/// Create a new peer with an in-memory store and a new keypair.
+///
+/// The peer must be freed with `cable_free()`.
+#[no_mangle]
+pub extern "C" fn cable_new() -> *mut Cable {
+    let cable = Cable {
+        manager: CableManager::new(MemoryStore::default()),
+        subscriptions: Mutex::new(HashMap::new()),
+    };
+
+    Box::into_raw(Box::new(cable))
+}
+
+/// Free the given peer, ending all subscriptions. Connections are closed once
+/// the remote peer disconnects.
+///
+/// # Safety
+///
+/// `cable` must be null or a peer returned by `cable_new()` which has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn cable_free(cable: *mut Cable) {
+    if cable.is_null() {
+        return;
+    }
+
+    let cable = Box::from_raw(cable);
+    let subscriptions = cable
+        .subscriptions
+        .into_inner()
+        .unwrap_or_else(|err| err.into_inner());
+    task::block_on(async {
+        for (_channel, subscription) in subscriptions {
+            subscription.cancel().await;
+        }
+    });
+}
+
+/// Copy the 32 byte public key of the peer to `out_public_key`.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer and `out_public_key` must point to 32
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_public_key(cable: *const Cable, out_public_key: *mut u8) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) if !out_public_key.is_null() => cable,
+        _ => return CABLE_ERR_NULL,
+    };
+
+    let mut manager = cable.manager.clone();
+    match task::block_on(manager.get_public_key()) {
+        Ok(public_key) => {
+            ptr::copy_nonoverlapping(public_key.as_ptr(), out_public_key, public_key.len());
+            CABLE_OK
+        }
+        Err(_) => CABLE_ERR_FAILED,
+    }
+}
+
+/// Exchange messages with a remote peer over the given connected TCP socket.
+///
+/// The peer takes ownership of the socket and closes it when the connection
+/// ends; the caller must not use or close the descriptor afterwards.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer and `fd` an open, connected TCP socket.
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn cable_connect_fd(cable: *const Cable, fd: c_int) -> i32 {
+    use std::os::unix::io::FromRawFd;
+
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    if fd < 0 {
+        return CABLE_ERR_IO;
+    }
+
+    let stream = std::net::TcpStream::from_raw_fd(fd);
+    if stream.set_nonblocking(true).is_err() {
+        return CABLE_ERR_IO;
+    }
+    let stream = TcpStream::from(stream);
+
+    let manager = cable.manager.clone();
+    task::spawn(async move {
+        let _ = manager.listen(stream).await;
+    });
+
+    CABLE_OK
+}
+
+/// Publish a text post to the given channel, copying the 32 byte hash of the
+/// post to `out_hash` unless it is null.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer, `channel` and `text` valid C strings and
+/// `out_hash` null or a pointer to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_post_text(
+    cable: *const Cable,
+    channel: *const c_char,
+    text: *const c_char,
+    out_hash: *mut u8,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let (channel, text) = match (to_string(channel), to_string(text)) {
+        (Ok(channel), Ok(text)) => (channel, text),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    let mut manager = cable.manager.clone();
+    publish_result(task::block_on(manager.post_text(channel, text)), out_hash)
+}
+
+/// Publish a topic post setting the topic of the given channel, copying the
+/// 32 byte hash of the post to `out_hash` unless it is null.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer, `channel` and `topic` valid C strings and
+/// `out_hash` null or a pointer to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_post_topic(
+    cable: *const Cable,
+    channel: *const c_char,
+    topic: *const c_char,
+    out_hash: *mut u8,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let (channel, topic) = match (to_string(channel), to_string(topic)) {
+        (Ok(channel), Ok(topic)) => (channel, topic),
+        (Err(err), _) | (_, Err(err)) => return err,
+    };
+
+    let mut manager = cable.manager.clone();
+    publish_result(task::block_on(manager.post_topic(channel, topic)), out_hash)
+}
+
+/// Publish a join post for the given channel, copying the 32 byte hash of
+/// the post to `out_hash` unless it is null.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer, `channel` a valid C string and `out_hash`
+/// null or a pointer to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_join(
+    cable: *const Cable,
+    channel: *const c_char,
+    out_hash: *mut u8,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let channel = match to_string(channel) {
+        Ok(channel) => channel,
+        Err(err) => return err,
+    };
+
+    let mut manager = cable.manager.clone();
+    publish_result(task::block_on(manager.post_join(channel)), out_hash)
+}
+
+/// Publish a leave post for the given channel, copying the 32 byte hash of
+/// the post to `out_hash` unless it is null.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer, `channel` a valid C string and `out_hash`
+/// null or a pointer to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_leave(
+    cable: *const Cable,
+    channel: *const c_char,
+    out_hash: *mut u8,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let channel = match to_string(channel) {
+        Ok(channel) => channel,
+        Err(err) => return err,
+    };
+
+    let mut manager = cable.manager.clone();
+    publish_result(task::block_on(manager.post_leave(channel)), out_hash)
+}
+
+/// Publish an info post setting the name of the local peer, copying the 32
+/// byte hash of the post to `out_hash` unless it is null.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer, `name` a valid C string and `out_hash` null
+/// or a pointer to 32 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn cable_set_name(
+    cable: *const Cable,
+    name: *const c_char,
+    out_hash: *mut u8,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let name = match to_string(name) {
+        Ok(name) => name,
+        Err(err) => return err,
+    };
+
+    let mut manager = cable.manager.clone();
+    publish_result(task::block_on(manager.post_info_name(&name)), out_hash)
+}
+
+/// Subscribe to the given channel, requesting its posts from connected peers.
+///
+/// The callback is invoked with each stored post of the channel, oldest
+/// first, and then with each new post as it is published or received, until
+/// `cable_unsubscribe()` or `cable_free()` is called. Callbacks are invoked
+/// from a background thread.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer and `channel` a valid C string. `user_data`
+/// must remain valid, and be safe to use from another thread, until the
+/// subscription ends.
+#[no_mangle]
+pub unsafe extern "C" fn cable_subscribe(
+    cable: *const Cable,
+    channel: *const c_char,
+    callback: CablePostCallback,
+    user_data: *mut c_void,
+) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let channel = match to_string(channel) {
+        Ok(channel) => channel,
+        Err(err) => return err,
+    };
+
+    let callback = Callback {
+        f: callback,
+        user_data,
+    };
+    let mut manager = cable.manager.clone();
+    let opts = ChannelOptions::new(channel.clone(), 0, 0, 0);
+    let subscription = task::spawn(async move {
+        if let Ok(mut posts) = manager.open_channel(&opts).await {
+            while let Some(Ok(post)) = posts.next().await {
+                deliver(&callback, &post);
+            }
+        }
+    });
+
+    let mut subscriptions = cable
+        .subscriptions
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    if let Some(previous) = subscriptions.insert(channel, subscription) {
+        task::block_on(previous.cancel());
+    }
+
+    CABLE_OK
+}
+
+/// End the subscription to the given channel, if any, and cancel the channel
+/// requests sent to connected peers.
+///
+/// # Safety
+///
+/// `cable` must be a valid peer and `channel` a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn cable_unsubscribe(cable: *const Cable, channel: *const c_char) -> i32 {
+    let cable = match cable.as_ref() {
+        Some(cable) => cable,
+        None => return CABLE_ERR_NULL,
+    };
+    let channel = match to_string(channel) {
+        Ok(channel) => channel,
+        Err(err) => return err,
+    };
+
+    let subscription = cable
+        .subscriptions
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .remove(&channel);
+    task::block_on(async {
+        if let Some(subscription) = subscription {
+            subscription.cancel().await;
+        }
+        cable.manager.close_channel(&channel).await
+    })
+    .map_or(CABLE_ERR_FAILED, |_| CABLE_OK)
+}
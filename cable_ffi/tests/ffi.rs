@@ -0,0 +1,102 @@
+//! Test the C interface by connecting two peers over a TCP socket and
+//! exchanging posts through the exported functions.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Create two peers and connect them over a loopback TCP connection, handing
+//! each end of the connection to a peer as a file descriptor.
+//!
+//! 2) Subscribe to a channel on the second peer.
+//!
+//! 3) Publish a text post on the first peer and ensure the callback of the
+//! second peer receives it.
+//!
+//! 4) Ensure invalid arguments are rejected with status codes.
+
+#![cfg(unix)]
+
+use std::{
+    ffi::{c_void, CStr, CString},
+    net::{TcpListener, TcpStream},
+    os::unix::io::IntoRawFd,
+    ptr,
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
+
+use cable_ffi::*;
+
+/// Record the text of each received post in the `Mutex<Vec<String>>` given as
+/// user data.
+extern "C" fn record(user_data: *mut c_void, post: *const CablePost) {
+    let texts = unsafe { &*(user_data as *const Mutex<Vec<String>>) };
+    let post = unsafe { &*post };
+    if !post.text.is_null() {
+        let text = unsafe { CStr::from_ptr(post.text) };
+        texts
+            .lock()
+            .unwrap()
+            .push(text.to_str().unwrap().to_owned());
+    }
+}
+
+#[test]
+fn exchange_posts_over_ffi() {
+    let channel = CString::new("default").unwrap();
+    let text = CString::new("hello from C").unwrap();
+    let texts: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    unsafe {
+        let first = cable_new();
+        let second = cable_new();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _addr) = listener.accept().unwrap();
+        assert_eq!(cable_connect_fd(first, client.into_raw_fd()), CABLE_OK);
+        assert_eq!(cable_connect_fd(second, server.into_raw_fd()), CABLE_OK);
+
+        let user_data = &texts as *const Mutex<Vec<String>> as *mut c_void;
+        assert_eq!(
+            cable_subscribe(second, channel.as_ptr(), record, user_data),
+            CABLE_OK
+        );
+
+        let mut hash = [0; 32];
+        assert_eq!(
+            cable_post_text(first, channel.as_ptr(), text.as_ptr(), hash.as_mut_ptr()),
+            CABLE_OK
+        );
+        assert_ne!(hash, [0; 32]);
+
+        let mut received = false;
+        for _ in 0..500 {
+            if texts.lock().unwrap().contains(&"hello from C".to_string()) {
+                received = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(received);
+
+        assert_eq!(cable_unsubscribe(second, channel.as_ptr()), CABLE_OK);
+
+        let invalid = [0xff, 0];
+        assert_eq!(
+            cable_join(first, invalid.as_ptr() as *const _, ptr::null_mut()),
+            CABLE_ERR_UTF8
+        );
+        assert_eq!(
+            cable_post_text(first, ptr::null(), text.as_ptr(), ptr::null_mut()),
+            CABLE_ERR_NULL
+        );
+        assert_eq!(
+            cable_public_key(ptr::null(), hash.as_mut_ptr()),
+            CABLE_ERR_NULL
+        );
+
+        cable_free(first);
+        cable_free(second);
+    }
+}
@@ -0,0 +1,641 @@
+//! Node.js bindings for cable, built with napi-rs.
+//!
+//! The bindings expose the wire codec (`decodePost()`, `encodePost()`,
+//! `decodeMessage()` and `encodeMessage()`) and a `CableManager` class backed
+//! by a `SledStore`, so that JavaScript cabal clients can delegate the protocol
+//! to cable.rs while keeping their own user interface.
+//!
+//! Binary fields (keys, signatures, hashes, request IDs) are exchanged as
+//! Node.js `Buffer`s and timestamps as milliseconds since the UNIX epoch.
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    sync::{Arc, Mutex},
+};
+
+use async_std::{
+    net::{TcpListener, TcpStream},
+    prelude::*,
+    task,
+};
+use cable::{
+    constants::{
+        CANCEL_REQUEST, CHANNEL_LIST_REQUEST, CHANNEL_LIST_RESPONSE, CHANNEL_STATE_REQUEST,
+        CHANNEL_TIME_RANGE_REQUEST, DELETE_POST, HASH_RESPONSE, INFO_POST, JOIN_POST, LEAVE_POST,
+        POST_REQUEST, POST_RESPONSE, TEXT_POST, TOPIC_POST,
+    },
+    message::{MessageBody, MessageHeader, RequestBody, ResponseBody},
+    post::{PostBody, PostHeader},
+    ChannelOptions, Hash, Message, Post, UserInfo,
+};
+use cable_core::{CableManager as Manager, SledStore, Store};
+use desert::{FromBytes, ToBytes};
+use napi::{
+    bindgen_prelude::Buffer,
+    threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+    Error, JsFunction, Result, Status,
+};
+use napi_derive::napi;
+
+/// Convert an error of the cable crates into a JavaScript error.
+fn to_js_error<E: std::fmt::Display>(err: E) -> Error {
+    Error::new(Status::GenericFailure, err.to_string())
+}
+
+/// Return an error for an invalid argument.
+fn invalid_arg(reason: &str) -> Error {
+    Error::new(Status::InvalidArg, reason.to_string())
+}
+
+/// Convert a buffer into a fixed-length byte array.
+fn to_array<const N: usize>(buf: &Buffer, name: &str) -> Result<[u8; N]> {
+    buf.as_ref()
+        .try_into()
+        .map_err(|_| invalid_arg(&format!("{} must be {} bytes", name, N)))
+}
+
+/// Convert a list of buffers into a list of hashes.
+fn to_hashes(bufs: &[Buffer]) -> Result<Vec<Hash>> {
+    bufs.iter().map(|buf| to_array(buf, "hash")).collect()
+}
+
+/// Convert a list of hashes into a list of buffers.
+fn from_hashes(hashes: &[Hash]) -> Vec<Buffer> {
+    hashes.iter().map(|hash| hash.to_vec().into()).collect()
+}
+
+/// Convert a JavaScript number into an unsigned integer.
+fn to_u64(n: i64, name: &str) -> Result<u64> {
+    n.try_into()
+        .map_err(|_| invalid_arg(&format!("{} must not be negative", name)))
+}
+
+/// A key-value pair of an info post.
+#[napi(object)]
+pub struct JsUserInfo {
+    pub key: String,
+    pub val: String,
+}
+
+/// A post, as exchanged with JavaScript.
+///
+/// The fields of the body which are present depend on the post type: `channel`
+/// and `text` for text posts, `hashes` for delete posts, `info` for info
+/// posts, `channel` and `topic` for topic posts and `channel` for join and
+/// leave posts.
+#[napi(object)]
+pub struct JsPost {
+    /// The hash of the encoded post. Ignored when encoding.
+    pub hash: Option<Buffer>,
+    pub public_key: Buffer,
+    /// The signature of the post. When encoding, omit the signature and pass
+    /// a secret key to sign the post instead.
+    pub signature: Option<Buffer>,
+    pub links: Vec<Buffer>,
+    pub post_type: u32,
+    pub timestamp: i64,
+    pub channel: Option<String>,
+    pub text: Option<String>,
+    pub topic: Option<String>,
+    pub hashes: Option<Vec<Buffer>>,
+    pub info: Option<Vec<JsUserInfo>>,
+}
+
+impl JsPost {
+    fn new(hash: Option<Hash>, post: Post) -> Self {
+        let header = post.header;
+        let mut js_post = JsPost {
+            hash: hash.map(|hash| hash.to_vec().into()),
+            public_key: header.public_key.to_vec().into(),
+            signature: Some(header.signature.to_vec().into()),
+            links: from_hashes(&header.links),
+            post_type: header.post_type as u32,
+            timestamp: header.timestamp as i64,
+            channel: None,
+            text: None,
+            topic: None,
+            hashes: None,
+            info: None,
+        };
+
+        match post.body {
+            PostBody::Text { channel, text } => {
+                js_post.channel = Some(channel);
+                js_post.text = Some(text);
+            }
+            PostBody::Delete { hashes } => js_post.hashes = Some(from_hashes(&hashes)),
+            PostBody::Info { info } => {
+                js_post.info = Some(
+                    info.into_iter()
+                        .map(|UserInfo { key, val }| JsUserInfo { key, val })
+                        .collect(),
+                )
+            }
+            PostBody::Topic { channel, topic } => {
+                js_post.channel = Some(channel);
+                js_post.topic = Some(topic);
+            }
+            PostBody::Join { channel } | PostBody::Leave { channel } => {
+                js_post.channel = Some(channel)
+            }
+            // Unrecognized and experimental post types have no body fields
+            // in JavaScript.
+            _ => (),
+        }
+
+        js_post
+    }
+
+    fn into_post(self) -> Result<Post> {
+        let post_type = self.post_type as u64;
+        let channel = || {
+            self.channel
+                .clone()
+                .ok_or_else(|| invalid_arg("channel is required"))
+        };
+        let body = match post_type {
+            TEXT_POST => PostBody::Text {
+                channel: channel()?,
+                text: self.text.clone().unwrap_or_default(),
+            },
+            DELETE_POST => PostBody::Delete {
+                hashes: to_hashes(self.hashes.as_deref().unwrap_or_default())?,
+            },
+            INFO_POST => PostBody::Info {
+                info: self
+                    .info
+                    .iter()
+                    .flatten()
+                    .map(|info| UserInfo::new(info.key.clone(), info.val.clone()))
+                    .collect(),
+            },
+            TOPIC_POST => PostBody::Topic {
+                channel: channel()?,
+                topic: self.topic.clone().unwrap_or_default(),
+            },
+            JOIN_POST => PostBody::Join {
+                channel: channel()?,
+            },
+            LEAVE_POST => PostBody::Leave {
+                channel: channel()?,
+            },
+            _ => return Err(invalid_arg("unrecognized post type")),
+        };
+        let signature = match &self.signature {
+            Some(signature) => to_array(signature, "signature")?,
+            None => [0; 64],
+        };
+        let header = PostHeader::new(
+            to_array(&self.public_key, "public key")?,
+            signature,
+            to_hashes(&self.links)?,
+            post_type,
+            to_u64(self.timestamp, "timestamp")?,
+        );
+
+        Ok(Post::new(header, body))
+    }
+}
+
+/// A message, as exchanged with JavaScript.
+///
+/// The fields of the body which are present depend on the message type, and
+/// are named as in the cable specification. `ttl` is present for all
+/// requests.
+#[napi(object)]
+pub struct JsMessage {
+    pub msg_type: u32,
+    pub circuit_id: Buffer,
+    pub req_id: Buffer,
+    pub ttl: Option<u32>,
+    pub hashes: Option<Vec<Buffer>>,
+    pub cancel_id: Option<Buffer>,
+    pub channel: Option<String>,
+    pub time_start: Option<i64>,
+    pub time_end: Option<i64>,
+    pub limit: Option<i64>,
+    pub future: Option<i64>,
+    pub skip: Option<i64>,
+    /// The encoded posts of a post response.
+    pub posts: Option<Vec<Buffer>>,
+    pub channels: Option<Vec<String>>,
+}
+
+impl JsMessage {
+    fn new(msg: Message) -> Self {
+        let mut js_msg = JsMessage {
+            msg_type: msg.message_type() as u32,
+            circuit_id: msg.header.circuit_id.to_vec().into(),
+            req_id: msg.header.req_id.to_vec().into(),
+            ttl: None,
+            hashes: None,
+            cancel_id: None,
+            channel: None,
+            time_start: None,
+            time_end: None,
+            limit: None,
+            future: None,
+            skip: None,
+            posts: None,
+            channels: None,
+        };
+
+        match msg.body {
+            MessageBody::Request { ttl, body } => {
+                js_msg.ttl = Some(ttl as u32);
+                match body {
+                    RequestBody::Post { hashes } => js_msg.hashes = Some(from_hashes(&hashes)),
+                    RequestBody::Cancel { cancel_id } => {
+                        js_msg.cancel_id = Some(cancel_id.to_vec().into())
+                    }
+                    RequestBody::ChannelTimeRange {
+                        channel,
+                        time_start,
+                        time_end,
+                        limit,
+                    } => {
+                        js_msg.channel = Some(channel);
+                        js_msg.time_start = Some(time_start as i64);
+                        js_msg.time_end = Some(time_end as i64);
+                        js_msg.limit = Some(limit as i64);
+                    }
+                    RequestBody::ChannelState { channel, future } => {
+                        js_msg.channel = Some(channel);
+                        js_msg.future = Some(future as i64);
+                    }
+                    RequestBody::ChannelList { skip, limit } => {
+                        js_msg.skip = Some(skip as i64);
+                        js_msg.limit = Some(limit as i64);
+                    }
+                    // Experimental requests are not exposed to JavaScript.
+                    #[allow(unreachable_patterns)]
+                    _ => (),
+                }
+            }
+            MessageBody::Response { body } => match body {
+                ResponseBody::Hash { hashes } => js_msg.hashes = Some(from_hashes(&hashes)),
+                ResponseBody::Post { posts } => {
+                    js_msg.posts = Some(posts.into_iter().map(Buffer::from).collect())
+                }
+                ResponseBody::ChannelList { channels } => js_msg.channels = Some(channels),
+                #[allow(unreachable_patterns)]
+                _ => (),
+            },
+            MessageBody::Unrecognized { .. } => (),
+        }
+
+        js_msg
+    }
+
+    fn into_message(self) -> Result<Message> {
+        let msg_type = self.msg_type as u64;
+        let header = MessageHeader::new(
+            msg_type,
+            to_array(&self.circuit_id, "circuit id")?,
+            to_array(&self.req_id, "request id")?,
+        );
+        let ttl = || -> Result<u8> {
+            self.ttl
+                .unwrap_or_default()
+                .try_into()
+                .map_err(|_| invalid_arg("ttl must be between 0 and 255"))
+        };
+        let number = |n: Option<i64>, name: &str| to_u64(n.unwrap_or_default(), name);
+        let channel = || {
+            self.channel
+                .clone()
+                .ok_or_else(|| invalid_arg("channel is required"))
+        };
+        let hashes = || to_hashes(self.hashes.as_deref().unwrap_or_default());
+
+        let body = match msg_type {
+            POST_REQUEST => RequestBody::Post { hashes: hashes()? },
+            CANCEL_REQUEST => RequestBody::Cancel {
+                cancel_id: match &self.cancel_id {
+                    Some(cancel_id) => to_array(cancel_id, "cancel id")?,
+                    None => return Err(invalid_arg("cancel id is required")),
+                },
+            },
+            CHANNEL_TIME_RANGE_REQUEST => RequestBody::ChannelTimeRange {
+                channel: channel()?,
+                time_start: number(self.time_start, "time start")?,
+                time_end: number(self.time_end, "time end")?,
+                limit: number(self.limit, "limit")?,
+            },
+            CHANNEL_STATE_REQUEST => RequestBody::ChannelState {
+                channel: channel()?,
+                future: number(self.future, "future")?,
+            },
+            CHANNEL_LIST_REQUEST => RequestBody::ChannelList {
+                skip: number(self.skip, "skip")?,
+                limit: number(self.limit, "limit")?,
+            },
+            HASH_RESPONSE | POST_RESPONSE | CHANNEL_LIST_RESPONSE => {
+                let body = match msg_type {
+                    HASH_RESPONSE => ResponseBody::Hash { hashes: hashes()? },
+                    POST_RESPONSE => ResponseBody::Post {
+                        posts: self
+                            .posts
+                            .iter()
+                            .flatten()
+                            .map(|post| post.to_vec())
+                            .collect(),
+                    },
+                    _ => ResponseBody::ChannelList {
+                        channels: self.channels.clone().unwrap_or_default(),
+                    },
+                };
+                return Ok(Message::new(header, MessageBody::Response { body }));
+            }
+            _ => return Err(invalid_arg("unrecognized message type")),
+        };
+
+        Ok(Message::new(
+            header,
+            MessageBody::Request { ttl: ttl()?, body },
+        ))
+    }
+}
+
+/// An Ed25519 keypair in the libsodium format.
+#[napi(object)]
+pub struct JsKeypair {
+    pub public_key: Buffer,
+    pub secret_key: Buffer,
+}
+
+/// Generate a new random keypair with which posts may be signed.
+#[napi]
+pub fn generate_keypair() -> JsKeypair {
+    let (public_key, secret_key) = cable::crypto::generate_keypair();
+
+    JsKeypair {
+        public_key: public_key.to_vec().into(),
+        secret_key: secret_key.to_vec().into(),
+    }
+}
+
+/// Decode a post from its binary representation.
+#[napi]
+pub fn decode_post(buf: Buffer) -> Result<JsPost> {
+    let (_, post) = Post::from_bytes(&buf).map_err(to_js_error)?;
+    let hash = cable::crypto::hash(&buf);
+
+    Ok(JsPost::new(hash, post))
+}
+
+/// Encode a post into its binary representation, signing it with the given
+/// secret key if one is provided.
+#[napi]
+pub fn encode_post(post: JsPost, secret_key: Option<Buffer>) -> Result<Buffer> {
+    let mut post = post.into_post()?;
+    if let Some(secret_key) = secret_key {
+        post.sign(&to_array(&secret_key, "secret key")?)
+            .map_err(to_js_error)?;
+    }
+
+    Ok(post.to_bytes().map_err(to_js_error)?.into())
+}
+
+/// Return the hash of an encoded post.
+#[napi]
+pub fn hash_post(buf: Buffer) -> Result<Buffer> {
+    cable::crypto::hash(&buf)
+        .map(|hash| hash.to_vec().into())
+        .ok_or_else(|| Error::from_reason("failed to hash post"))
+}
+
+/// Return `true` if the signature of an encoded post is valid.
+#[napi]
+pub fn verify_post(buf: Buffer) -> bool {
+    Post::verify(&buf)
+}
+
+/// Decode a message from its binary representation (without the length
+/// prefix).
+#[napi]
+pub fn decode_message(buf: Buffer) -> Result<JsMessage> {
+    let (_, msg) = Message::from_bytes(&buf).map_err(to_js_error)?;
+
+    Ok(JsMessage::new(msg))
+}
+
+/// Encode a message into its binary representation (without the length
+/// prefix).
+#[napi]
+pub fn encode_message(msg: JsMessage) -> Result<Buffer> {
+    let msg = msg.into_message()?;
+
+    Ok(msg.to_bytes().map_err(to_js_error)?.into())
+}
+
+/// A cable peer with a persistent store.
+#[napi]
+pub struct CableManager {
+    manager: Manager<SledStore>,
+    /// The tasks delivering the posts of each open channel.
+    channels: Arc<Mutex<HashMap<String, task::JoinHandle<()>>>>,
+}
+
+#[napi]
+impl CableManager {
+    /// Open a manager with the store at the given path, creating the store if
+    /// it does not yet exist. A temporary store, removed when the manager is
+    /// garbage collected, is used if no path is given.
+    #[napi(constructor)]
+    pub fn new(path: Option<String>) -> Result<Self> {
+        let store = match path {
+            Some(path) => SledStore::open(path),
+            None => SledStore::temporary(),
+        }
+        .map_err(to_js_error)?;
+
+        Ok(CableManager {
+            manager: Manager::new(store),
+            channels: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Return the public key of the active identity.
+    #[napi]
+    pub async fn public_key(&self) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let public_key = manager.get_public_key().await.map_err(to_js_error)?;
+
+        Ok(public_key.to_vec().into())
+    }
+
+    /// Connect to the peer at the given address (`host:port`), exchanging
+    /// messages with it in the background until the connection ends.
+    #[napi]
+    pub async fn connect(&self, address: String) -> Result<()> {
+        let stream = TcpStream::connect(address).await?;
+
+        let manager = self.manager.clone();
+        task::spawn(async move {
+            let _ = manager.listen(stream).await;
+        });
+
+        Ok(())
+    }
+
+    /// Accept connections on the given address (`host:port`) in the
+    /// background, returning the local port once listening.
+    #[napi]
+    pub async fn listen(&self, address: String) -> Result<u32> {
+        let listener = TcpListener::bind(address).await?;
+        let port = listener.local_addr()?.port();
+
+        let manager = self.manager.clone();
+        task::spawn(async move {
+            let mut incoming = listener.incoming();
+            while let Some(Ok(stream)) = incoming.next().await {
+                let manager = manager.clone();
+                task::spawn(async move {
+                    let _ = manager.listen(stream).await;
+                });
+            }
+        });
+
+        Ok(port as u32)
+    }
+
+    /// Request the posts of the given channel from connected peers, along with
+    /// new posts as they are published. The given callback is invoked with
+    /// each stored or received post until the channel is closed.
+    #[napi(ts_args_type = "channel: string, callback: (post: JsPost) => void")]
+    pub fn open_channel(&self, channel: String, callback: JsFunction) -> Result<()> {
+        let callback: ThreadsafeFunction<JsPost, ErrorStrategy::Fatal> =
+            callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+
+        let mut manager = self.manager.clone();
+        let opts = ChannelOptions::new(channel.clone(), 0, 0, 0);
+        let delivery = task::spawn(async move {
+            let mut posts = match manager.open_channel(&opts).await {
+                Ok(posts) => posts,
+                Err(_) => return,
+            };
+            while let Some(Ok(post)) = posts.next().await {
+                let hash = post.hash().ok();
+                callback.call(
+                    JsPost::new(hash, post),
+                    ThreadsafeFunctionCallMode::NonBlocking,
+                );
+            }
+        });
+
+        let previous = self
+            .channels
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(channel, delivery);
+        if let Some(previous) = previous {
+            task::spawn(previous.cancel());
+        }
+
+        Ok(())
+    }
+
+    /// Cancel the requests for the posts of the given channel and stop
+    /// invoking its callback.
+    #[napi]
+    pub async fn close_channel(&self, channel: String) -> Result<()> {
+        let delivery = self
+            .channels
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&channel);
+        if let Some(delivery) = delivery {
+            delivery.cancel().await;
+        }
+
+        self.manager
+            .close_channel(&channel)
+            .await
+            .map_err(to_js_error)
+    }
+
+    /// Return the stored posts of the given channel, oldest first.
+    #[napi]
+    pub async fn get_posts(&self, channel: String) -> Result<Vec<JsPost>> {
+        let opts = ChannelOptions::new(channel, 0, 0, 0);
+
+        let mut stored = Vec::new();
+        let mut posts = self.manager.store.get_posts(&opts).await;
+        while let Some(post) = posts.next().await {
+            let post = post.map_err(to_js_error)?;
+            let hash = post.hash().ok();
+            stored.push(JsPost::new(hash, post));
+        }
+
+        Ok(stored)
+    }
+
+    /// Publish a text post to the given channel, returning the hash of the
+    /// post.
+    #[napi]
+    pub async fn post_text(&self, channel: String, text: String) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let hash = manager
+            .post_text(channel, text)
+            .await
+            .map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+
+    /// Publish a topic post for the given channel, returning the hash of the
+    /// post.
+    #[napi]
+    pub async fn post_topic(&self, channel: String, topic: String) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let hash = manager
+            .post_topic(channel, topic)
+            .await
+            .map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+
+    /// Publish a join post for the given channel, returning the hash of the
+    /// post.
+    #[napi]
+    pub async fn post_join(&self, channel: String) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let hash = manager.post_join(channel).await.map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+
+    /// Publish a leave post for the given channel, returning the hash of the
+    /// post.
+    #[napi]
+    pub async fn post_leave(&self, channel: String) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let hash = manager.post_leave(channel).await.map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+
+    /// Publish an info post setting the name of the active identity,
+    /// returning the hash of the post.
+    #[napi]
+    pub async fn set_name(&self, name: String) -> Result<Buffer> {
+        let mut manager = self.manager.clone();
+        let hash = manager.post_info_name(&name).await.map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+
+    /// Publish a post encoded with `encodePost()`, returning its hash. The
+    /// post is signed with the active identity if it is unsigned.
+    #[napi]
+    pub async fn post(&self, buf: Buffer) -> Result<Buffer> {
+        let (_, post) = Post::from_bytes(&buf).map_err(to_js_error)?;
+        let mut manager = self.manager.clone();
+        let hash = manager.post(post).await.map_err(to_js_error)?;
+
+        Ok(hash.to_vec().into())
+    }
+}
@@ -0,0 +1,89 @@
+// Tests of the Node.js bindings.
+//
+// Build the addon with `npm run build` before running `npm test`.
+//
+// 1. Encode, sign and decode a text post
+// 2. Reject a post with an invalid public key
+// 3. Encode and decode a channel time range request
+// 4. Exchange posts between two managers over TCP
+
+const assert = require('node:assert')
+const test = require('node:test')
+
+const cable = require('..')
+
+const { publicKey, secretKey } = cable.generateKeypair()
+
+test('encode, sign and decode a text post', () => {
+  const buf = cable.encodePost({
+    publicKey,
+    links: [],
+    postType: 0,
+    timestamp: 80,
+    channel: 'default',
+    text: 'h e y'
+  }, secretKey)
+
+  const post = cable.decodePost(buf)
+  assert.strictEqual(post.postType, 0)
+  assert.strictEqual(post.timestamp, 80)
+  assert.strictEqual(post.channel, 'default')
+  assert.strictEqual(post.text, 'h e y')
+  assert.deepStrictEqual(post.publicKey, publicKey)
+  assert.deepStrictEqual(post.hash, cable.hashPost(buf))
+  assert.strictEqual(cable.verifyPost(buf), true)
+  assert.deepStrictEqual(cable.encodePost(post), buf)
+})
+
+test('reject a post with an invalid public key', () => {
+  assert.throws(() => cable.encodePost({
+    publicKey: Buffer.alloc(8),
+    links: [],
+    postType: 4,
+    timestamp: 80,
+    channel: 'default'
+  }), /public key must be 32 bytes/)
+})
+
+test('encode and decode a channel time range request', () => {
+  const buf = cable.encodeMessage({
+    msgType: 4,
+    circuitId: Buffer.alloc(4),
+    reqId: Buffer.from([4, 186, 175, 251]),
+    ttl: 1,
+    channel: 'default',
+    timeStart: 0,
+    timeEnd: 100,
+    limit: 20
+  })
+
+  const msg = cable.decodeMessage(buf)
+  assert.strictEqual(msg.msgType, 4)
+  assert.strictEqual(msg.ttl, 1)
+  assert.strictEqual(msg.channel, 'default')
+  assert.strictEqual(msg.timeEnd, 100)
+  assert.strictEqual(msg.limit, 20)
+})
+
+test('exchange posts between two managers over TCP', async () => {
+  const alice = new cable.CableManager()
+  const bob = new cable.CableManager()
+
+  const port = await alice.listen('127.0.0.1:0')
+  await bob.connect(`127.0.0.1:${port}`)
+
+  const received = new Promise((resolve) => {
+    bob.openChannel('default', (post) => {
+      if (post.text === 'hello') resolve(post)
+    })
+  })
+  await alice.postText('default', 'hello')
+
+  const post = await received
+  assert.deepStrictEqual(post.publicKey, await alice.publicKey())
+  assert.strictEqual(cable.verifyPost(cable.encodePost(post)), true)
+
+  await bob.closeChannel('default')
+  const posts = await bob.getPosts('default')
+  assert.strictEqual(posts.length, 1)
+})
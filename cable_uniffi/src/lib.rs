@@ -0,0 +1,279 @@
+//! UniFFI bindings exposing a simplified cable client to Kotlin and Swift.
+//!
+//! A `CableClient` wraps a `CableManager` backed by a `SledStore`, offering
+//! blocking methods to manage identities, connect to peers, open channels and
+//! publish posts. Changes to a channel are delivered to a foreign
+//! `CableListener` implementation from a background thread.
+//!
+//! Generate the bindings from a compiled library with the bundled
+//! `uniffi-bindgen` binary (see the README).
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use async_std::{net::TcpStream, prelude::*, task};
+use cable::{post::PostBody, ChannelOptions, Hash, Post};
+use cable_core::{CableManager, SledStore, Store, StoreEvent};
+
+uniffi::setup_scaffolding!();
+
+/// An error returned by a `CableClient`.
+#[derive(Debug, uniffi::Error)]
+pub enum CableError {
+    /// A connection to a peer could not be established.
+    Connection { message: String },
+    /// An argument was invalid, such as a malformed public key.
+    InvalidArgument { message: String },
+    /// The operation failed.
+    Failed { message: String },
+}
+
+impl fmt::Display for CableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CableError::Connection { message } => write!(f, "connection failed: {}", message),
+            CableError::InvalidArgument { message } => write!(f, "invalid argument: {}", message),
+            CableError::Failed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CableError {}
+
+impl From<cable::Error> for CableError {
+    fn from(err: cable::Error) -> Self {
+        CableError::Failed {
+            message: err.to_string(),
+        }
+    }
+}
+
+/// A post, as delivered to foreign code.
+#[derive(Clone, Debug, PartialEq, uniffi::Record)]
+pub struct CablePost {
+    /// The 32 byte hash of the post.
+    pub hash: Vec<u8>,
+    /// The 32 byte public key of the author.
+    pub public_key: Vec<u8>,
+    /// The numeric post type (see the cable specification).
+    pub post_type: u64,
+    /// The time at which the post was published, in milliseconds since the
+    /// UNIX epoch.
+    pub timestamp: u64,
+    /// The channel of the post, if any.
+    pub channel: Option<String>,
+    /// The text of a text post or the topic of a topic post.
+    pub text: Option<String>,
+}
+
+impl CablePost {
+    fn new(hash: Hash, post: Post) -> Self {
+        let text = match &post.body {
+            PostBody::Text { text, .. } => Some(text.to_owned()),
+            PostBody::Topic { topic, .. } => Some(topic.to_owned()),
+            _ => None,
+        };
+
+        CablePost {
+            hash: hash.to_vec(),
+            public_key: post.header.public_key.to_vec(),
+            post_type: post.post_type(),
+            timestamp: post.get_timestamp(),
+            channel: post.get_channel().cloned(),
+            text,
+        }
+    }
+}
+
+/// Receives the changes to a channel subscribed to with
+/// `CableClient::subscribe()`.
+///
+/// Methods are invoked from a background thread.
+#[uniffi::export(with_foreign)]
+pub trait CableListener: Send + Sync {
+    /// A post affecting the channel was stored.
+    fn on_post(&self, channel: String, post: CablePost);
+
+    /// A post made to the channel was deleted.
+    fn on_post_deleted(&self, channel: String, hash: Vec<u8>);
+}
+
+/// A cable peer with a persistent store.
+#[derive(uniffi::Object)]
+pub struct CableClient {
+    manager: CableManager<SledStore>,
+    /// The tasks delivering the changes to each subscribed channel.
+    subscriptions: Mutex<HashMap<String, task::JoinHandle<()>>>,
+}
+
+impl CableClient {
+    fn manager(&self) -> CableManager<SledStore> {
+        self.manager.clone()
+    }
+}
+
+#[uniffi::export]
+impl CableClient {
+    /// Open a client with the store at the given path, creating the store if
+    /// it does not yet exist. A temporary store, removed when the client is
+    /// dropped, is used if no path is given.
+    #[uniffi::constructor]
+    pub fn new(path: Option<String>) -> Result<Arc<Self>, CableError> {
+        let store = match path {
+            Some(path) => SledStore::open(path)?,
+            None => SledStore::temporary()?,
+        };
+
+        Ok(Arc::new(CableClient {
+            manager: CableManager::new(store),
+            subscriptions: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Return the public key of the active identity.
+    pub fn public_key(&self) -> Vec<u8> {
+        let mut manager = self.manager();
+        let (public_key, _secret_key) = task::block_on(manager.store.get_or_create_keypair());
+
+        public_key.to_vec()
+    }
+
+    /// Create a new identity, returning its public key. The active identity
+    /// is unchanged.
+    pub fn create_identity(&self) -> Vec<u8> {
+        let mut manager = self.manager();
+
+        task::block_on(manager.store.create_identity()).to_vec()
+    }
+
+    /// Make the identity with the given public key the active identity, with
+    /// which new posts are signed.
+    pub fn use_identity(&self, public_key: Vec<u8>) -> Result<(), CableError> {
+        let public_key: [u8; 32] =
+            public_key
+                .try_into()
+                .map_err(|_| CableError::InvalidArgument {
+                    message: "public key must be 32 bytes".to_string(),
+                })?;
+        let mut manager = self.manager();
+
+        Ok(task::block_on(
+            manager.store.set_active_identity(&public_key),
+        )?)
+    }
+
+    /// Connect to the peer at the given address (`host:port`), exchanging
+    /// messages with it in the background until the connection ends.
+    pub fn connect(&self, address: String) -> Result<(), CableError> {
+        let stream =
+            task::block_on(TcpStream::connect(address)).map_err(|err| CableError::Connection {
+                message: err.to_string(),
+            })?;
+
+        let manager = self.manager();
+        task::spawn(async move {
+            let _ = manager.listen(stream).await;
+        });
+
+        Ok(())
+    }
+
+    /// Request the posts of the given channel from connected peers, along with
+    /// new posts as they are published. Received posts are stored and
+    /// delivered to any subscribed listener.
+    pub fn open_channel(&self, channel: String) -> Result<(), CableError> {
+        let mut manager = self.manager();
+        let opts = ChannelOptions::new(channel, 0, 0, 0);
+
+        task::block_on(async {
+            // Received posts are delivered to subscribed listeners as they are
+            // stored, so the returned stream of posts is not needed.
+            let _posts = manager.open_channel(&opts).await?;
+            Ok(())
+        })
+    }
+
+    /// Cancel the requests for the posts of the given channel.
+    pub fn close_channel(&self, channel: String) -> Result<(), CableError> {
+        Ok(task::block_on(self.manager.close_channel(&channel))?)
+    }
+
+    /// Return the stored posts of the given channel, oldest first.
+    pub fn get_posts(&self, channel: String) -> Vec<CablePost> {
+        let opts = ChannelOptions::new(channel, 0, 0, 0);
+
+        task::block_on(async {
+            let mut stored = Vec::new();
+            let mut posts = self.manager.store.get_posts(&opts).await;
+            while let Some(Ok(post)) = posts.next().await {
+                if let Ok(hash) = post.hash() {
+                    stored.push(CablePost::new(hash, post));
+                }
+            }
+            stored
+        })
+    }
+
+    /// Publish a text post to the given channel, returning the hash of the
+    /// post.
+    pub fn post_text(&self, channel: String, text: String) -> Result<Vec<u8>, CableError> {
+        let mut manager = self.manager();
+
+        Ok(task::block_on(manager.post_text(channel, text))?.to_vec())
+    }
+
+    /// Publish a join post for the given channel, returning the hash of the
+    /// post.
+    pub fn join_channel(&self, channel: String) -> Result<Vec<u8>, CableError> {
+        let mut manager = self.manager();
+
+        Ok(task::block_on(manager.post_join(channel))?.to_vec())
+    }
+
+    /// Deliver the changes to the given channel to the given listener until
+    /// `unsubscribe()` is called, replacing any previous listener of the
+    /// channel.
+    pub fn subscribe(&self, channel: String, listener: Arc<dyn CableListener>) {
+        let store = self.manager.store.clone();
+        let subscribed_channel = channel.clone();
+        let subscription = task::spawn(async move {
+            let channel = subscribed_channel;
+            let mut events = store.watch(&channel).await;
+            while let Some(event) = events.next().await {
+                match event {
+                    StoreEvent::PostInserted { hash, post } => {
+                        listener.on_post(channel.clone(), CablePost::new(hash, post))
+                    }
+                    StoreEvent::PostDeleted { hash } => {
+                        listener.on_post_deleted(channel.clone(), hash.to_vec())
+                    }
+                }
+            }
+        });
+
+        let previous = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(channel, subscription);
+        if let Some(previous) = previous {
+            task::block_on(previous.cancel());
+        }
+    }
+
+    /// Stop delivering the changes to the given channel.
+    pub fn unsubscribe(&self, channel: String) {
+        let subscription = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&channel);
+        if let Some(subscription) = subscription {
+            task::block_on(subscription.cancel());
+        }
+    }
+}
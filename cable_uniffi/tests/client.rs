@@ -0,0 +1,97 @@
+//! Test the client exposed to foreign code by connecting it to a peer.
+//!
+//! An outline of the actions taken in this test:
+//!
+//! 1) Deploy a TCP listener for a peer which has published a post.
+//!
+//! 2) Create a client, subscribe to the channel of the post and connect the
+//! client to the peer.
+//!
+//! 3) Open the channel and ensure the post of the peer is delivered to the
+//! listener and stored.
+//!
+//! 4) Publish a post with a new identity and ensure it is delivered to the
+//! listener with the public key of that identity.
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use async_std::{net::TcpListener, prelude::*, task};
+
+use cable_core::{CableManager, MemoryStore};
+use cable_uniffi::{CableClient, CableError, CableListener, CablePost};
+
+#[derive(Default)]
+struct Recorder {
+    posts: Mutex<Vec<CablePost>>,
+}
+
+impl CableListener for Recorder {
+    fn on_post(&self, _channel: String, post: CablePost) {
+        self.posts.lock().unwrap().push(post);
+    }
+
+    fn on_post_deleted(&self, _channel: String, _hash: Vec<u8>) {}
+}
+
+/// Wait until the recorder holds a post with the given text.
+fn wait_for(recorder: &Recorder, text: &str) -> Option<CablePost> {
+    for _ in 0..500 {
+        let posts = recorder.posts.lock().unwrap();
+        if let Some(post) = posts.iter().find(|post| post.text.as_deref() == Some(text)) {
+            return Some(post.clone());
+        }
+        drop(posts);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    None
+}
+
+#[test]
+fn client_receives_and_publishes_posts() -> Result<(), CableError> {
+    let mut peer = CableManager::new(MemoryStore::default());
+    task::block_on(peer.post_text("default", "hello from the peer"))?;
+
+    let listener = task::block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let addr = listener.local_addr().unwrap();
+    task::spawn(async move {
+        let mut incoming = listener.incoming();
+        while let Some(Ok(stream)) = incoming.next().await {
+            let peer = peer.clone();
+            task::spawn(async move { peer.listen(stream).await });
+        }
+    });
+
+    let client = CableClient::new(None)?;
+    let recorder = Arc::new(Recorder::default());
+    client.subscribe("default".to_string(), recorder.clone());
+    client.connect(addr.to_string())?;
+
+    // Wait for the connection to be established before opening the channel.
+    thread::sleep(Duration::from_millis(50));
+    client.open_channel("default".to_string())?;
+    assert!(wait_for(&recorder, "hello from the peer").is_some());
+    assert!(client
+        .get_posts("default".to_string())
+        .iter()
+        .any(|post| post.text.as_deref() == Some("hello from the peer")));
+
+    let public_key = client.create_identity();
+    assert_ne!(public_key, client.public_key());
+    client.use_identity(public_key.clone())?;
+    assert!(client.use_identity(vec![0; 3]).is_err());
+
+    let hash = client.post_text("default".to_string(), "hello from the client".to_string())?;
+    let post = wait_for(&recorder, "hello from the client").unwrap();
+    assert_eq!(post.hash, hash);
+    assert_eq!(post.public_key, public_key);
+
+    client.unsubscribe("default".to_string());
+    client.close_channel("default".to_string())?;
+
+    Ok(())
+}
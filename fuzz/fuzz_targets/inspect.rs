@@ -0,0 +1,15 @@
+//! Inspect arbitrary bytes as both a message and a post.
+
+#![no_main]
+
+use cable::inspect::{inspect_message, inspect_post};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let inspection = inspect_message(data);
+    assert!(inspection.len <= data.len());
+    let _ = inspection.to_string();
+
+    let inspection = inspect_post(data);
+    assert!(inspection.len <= data.len());
+});
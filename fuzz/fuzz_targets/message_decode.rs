@@ -0,0 +1,15 @@
+//! Decode arbitrary bytes as a message, re-encoding any message which is
+//! decoded successfully.
+
+#![no_main]
+
+use cable::Message;
+use desert::{FromBytes, ToBytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_len, msg)) = Message::from_bytes(data) {
+        let _ = msg.to_bytes();
+        let _ = msg.to_string();
+    }
+});
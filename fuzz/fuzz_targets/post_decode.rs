@@ -0,0 +1,18 @@
+//! Verify and decode arbitrary bytes as a post, re-encoding and hashing any
+//! post which is decoded successfully.
+
+#![no_main]
+
+use cable::Post;
+use desert::{FromBytes, ToBytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Post::verify(data);
+
+    if let Ok((_len, post)) = Post::from_bytes(data) {
+        let _ = post.to_bytes();
+        let _ = post.hash();
+        let _ = post.to_string();
+    }
+});
@@ -0,0 +1,36 @@
+//! Check arbitrary bytes as both a message and a post with the conformance
+//! module, ensuring that the re-encoding of anything which decodes is
+//! canonical: it must round-trip exactly.
+
+#![no_main]
+
+use cable::{
+    conformance::{check_post_roundtrip, check_roundtrip},
+    Message, Post,
+};
+use desert::{FromBytes, ToBytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(err) = check_roundtrip(data) {
+        let _ = err.to_string();
+    }
+    if let Ok((_len, msg)) = Message::from_bytes(data) {
+        if let Ok(buf) = msg.to_bytes() {
+            if let Err(err) = check_roundtrip(&buf) {
+                panic!("re-encoded message does not round-trip: {}", err);
+            }
+        }
+    }
+
+    if let Err(err) = check_post_roundtrip(data) {
+        let _ = err.to_string();
+    }
+    if let Ok((_len, post)) = Post::from_bytes(data) {
+        if let Ok(buf) = post.to_bytes() {
+            if let Err(err) = check_post_roundtrip(&buf) {
+                panic!("re-encoded post does not round-trip: {}", err);
+            }
+        }
+    }
+});